@@ -0,0 +1,16 @@
+create table public.adjusted_quotes
+(
+    security_code          varchar(24)              default ''::character varying                   not null,
+    date                   date                                                                     not null,
+    closing_price          numeric(18, 4)           default 0                                       not null,
+    adjustment_factor      numeric(24, 12)          default 1                                       not null,
+    adjusted_closing_price numeric(18, 4)           default 0                                       not null,
+    created_time           timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time           timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    primary key (security_code, date)
+);
+
+comment on table public.adjusted_quotes is '依除權息還原的收盤價，最近一個交易日的調整係數為 1';
+comment on column public.adjusted_quotes.closing_price is '原始收盤價';
+comment on column public.adjusted_quotes.adjustment_factor is '調整係數，之後每次除權息的係數連乘';
+comment on column public.adjusted_quotes.adjusted_closing_price is '還原收盤價 = 原始收盤價 × 調整係數';
@@ -0,0 +1,23 @@
+create table public.announcement
+(
+    serial            bigserial
+        primary key,
+    security_code     varchar(24)              default ''::character varying                   not null,
+    name              varchar(255)             default ''::character varying                   not null,
+    announced_time    timestamp with time zone                                                 not null,
+    subject           text                     default ''::text                                not null,
+    clause            varchar(255)             default ''::character varying                   not null,
+    occurred_date     date,
+    description       text                     default ''::text                                not null,
+    created_time      timestamp with time zone default ('now'::text)::timestamp with time zone not null
+);
+
+comment on table public.announcement is '上市公司重大訊息';
+comment on column public.announcement.announced_time is '發言時間';
+comment on column public.announcement.subject is '主旨';
+comment on column public.announcement.clause is '符合條款';
+comment on column public.announcement.occurred_date is '事實發生日';
+comment on column public.announcement.description is '說明';
+
+create unique index "announcement-security_code-announced_time-subject-uidx"
+    on public.announcement (security_code, announced_time, md5(subject));
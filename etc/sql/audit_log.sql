@@ -0,0 +1,24 @@
+create table public.audit_log
+(
+    serial        bigserial
+        primary key,
+    table_name    varchar(64)              default ''::character varying                   not null,
+    record_key    varchar(128)             default ''::character varying                   not null,
+    source        varchar(128)             default ''::character varying                   not null,
+    rows_affected bigint                   default 0                                       not null,
+    run_id        varchar(64)              default ''::character varying                   not null,
+    created_time  timestamp with time zone default ('now'::text)::timestamp with time zone not null
+);
+
+comment on table public.audit_log is '回補模組寫入資料的記錄，用來追查錯誤數據的來源';
+comment on column public.audit_log.table_name is '寫入的資料表';
+comment on column public.audit_log.record_key is '資料的鍵值 ex. 股票代號-年月';
+comment on column public.audit_log.source is '寫入資料的模組 ex. stock_crawler::backfill::revenue';
+comment on column public.audit_log.rows_affected is '影響的筆數';
+comment on column public.audit_log.run_id is '執行批次的識別碼，同一次執行的記錄相同';
+
+create index "audit_log-table_name-record_key-idx"
+    on public.audit_log (table_name, record_key, created_time desc);
+
+create index "audit_log-run_id-idx"
+    on public.audit_log (run_id);
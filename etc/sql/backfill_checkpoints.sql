@@ -0,0 +1,12 @@
+create table public.backfill_checkpoints
+(
+    name         varchar(255)             default ''::character varying                   not null
+        primary key,
+    checkpoint   varchar(255)             default ''::character varying                   not null,
+    created_time timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time timestamp with time zone default ('now'::text)::timestamp with time zone not null
+);
+
+comment on table public.backfill_checkpoints is '歷史數據回補的進度，中斷後重新執行時從此處接續';
+comment on column public.backfill_checkpoints.name is '回補的名稱 ex. revenue_history';
+comment on column public.backfill_checkpoints.checkpoint is '最後完成的位置 ex. 月營收為 YYYYMM';
@@ -0,0 +1,14 @@
+create table if not exists public.bot_roles
+(
+    user_id      bigint                                                                   not null
+        primary key,
+    role         varchar(16)              default 'viewer'::character varying             not null,
+    note         varchar(64)              default ''::character varying                   not null,
+    created_time timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time timestamp with time zone default ('now'::text)::timestamp with time zone not null
+);
+
+comment on table public.bot_roles is 'Telegram 使用者可以使用的指令角色，以 /roles 指令管理';
+comment on column public.bot_roles.user_id is 'Telegram 使用者編號';
+comment on column public.bot_roles.role is 'viewer 只能查詢、trader 另外可以模擬交易與修改偏好、admin 另外可以使用管理指令';
+comment on column public.bot_roles.note is '備註 ex. 使用者的稱呼';
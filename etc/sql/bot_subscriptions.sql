@@ -0,0 +1,16 @@
+create table if not exists public.bot_subscriptions
+(
+    chat_id       bigint                                                                   not null,
+    security_code varchar(24)              default ''::character varying                   not null,
+    event         varchar(16)              default ''::character varying                   not null,
+    created_time  timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    primary key (chat_id, security_code, event)
+);
+
+comment on table public.bot_subscriptions is '聊天室以 /subscribe 訂閱的個股通知，有訂閱的聊天室只會收到訂閱的股票與事件';
+comment on column public.bot_subscriptions.chat_id is 'Telegram 聊天室編號，私人聊天室與使用者編號相同';
+comment on column public.bot_subscriptions.security_code is '股票代號';
+comment on column public.bot_subscriptions.event is '事件 revenue:月營收 dividend:除權息與股利發放 announcement:重大訊息';
+
+create index if not exists "bot_subscriptions-event-security_code-idx"
+    on public.bot_subscriptions (event, security_code);
@@ -0,0 +1,20 @@
+create table if not exists public.bot_user_settings
+(
+    chat_id      bigint                                                                   not null
+        primary key,
+    language     varchar(16)              default ''::character varying                   not null,
+    member_id    bigint                   default 0                                       not null,
+    quiet_start  time,
+    quiet_end    time,
+    digest       boolean                  default true                                    not null,
+    created_time timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time timestamp with time zone default ('now'::text)::timestamp with time zone not null
+);
+
+comment on table public.bot_user_settings is '各聊天室以 /settings 指令設定的偏好，優先於設定檔 bot.telegram 的設定';
+comment on column public.bot_user_settings.chat_id is 'Telegram 聊天室編號，私人聊天室與使用者編號相同';
+comment on column public.bot_user_settings.language is '接收通知的語系 ex. zh-TW、en，空字串時使用設定檔的語系';
+comment on column public.bot_user_settings.member_id is '/allocation、/xirr 預設查詢的成員，0 表示全部成員';
+comment on column public.bot_user_settings.quiet_start is '勿擾時段的開始時間，與 quiet_end 都為 null 時使用設定檔的勿擾時段';
+comment on column public.bot_user_settings.quiet_end is '勿擾時段的結束時間，與開始時間相同表示關閉勿擾時段';
+comment on column public.bot_user_settings.digest is '是否接收彙整後的警示摘要';
@@ -0,0 +1,32 @@
+create table public.buyback
+(
+    serial          bigserial
+        primary key,
+    security_code   varchar(24)              default ''::character varying                   not null,
+    name            varchar(255)             default ''::character varying                   not null,
+    resolution_date date                                                                     not null,
+    purpose         varchar(255)             default ''::character varying                   not null,
+    target_shares   bigint                   default 0                                       not null,
+    price_low       numeric(18, 4)           default 0                                       not null,
+    price_high      numeric(18, 4)           default 0                                       not null,
+    start_date      date,
+    end_date        date,
+    executed_shares bigint                   default 0                                       not null,
+    completed       boolean                  default false                                   not null,
+    created_time    timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time    timestamp with time zone default ('now'::text)::timestamp with time zone not null
+);
+
+comment on table public.buyback is '上市公司庫藏股買回計畫';
+comment on column public.buyback.resolution_date is '董事會決議日期';
+comment on column public.buyback.purpose is '買回目的';
+comment on column public.buyback.target_shares is '預定買回股數(股)';
+comment on column public.buyback.price_low is '買回價格區間-最低(元)';
+comment on column public.buyback.price_high is '買回價格區間-最高(元)';
+comment on column public.buyback.start_date is '預定買回期間-起';
+comment on column public.buyback.end_date is '預定買回期間-迄';
+comment on column public.buyback.executed_shares is '已買回股數(股)';
+comment on column public.buyback.completed is '是否執行完畢';
+
+create unique index "buyback-security_code-resolution_date-uidx"
+    on public.buyback (security_code, resolution_date);
@@ -0,0 +1,19 @@
+create table if not exists public.cash_ledger
+(
+    serial       bigserial
+        primary key,
+    member_id    bigint                   default 0                                       not null,
+    date         date                     default CURRENT_DATE                            not null,
+    amount       numeric(18, 4)           default 0                                       not null,
+    memo         varchar(128)             default ''::character varying                   not null,
+    created_time timestamp with time zone default ('now'::text)::timestamp with time zone not null
+);
+
+comment on table public.cash_ledger is '成員投入與提出的資金';
+comment on column public.cash_ledger.member_id is '會員編號 Member.Id';
+comment on column public.cash_ledger.date is '資金進出的日期';
+comment on column public.cash_ledger.amount is '金額，投入為正數、提出為負數';
+comment on column public.cash_ledger.memo is '備註';
+
+create index if not exists "cash_ledger-member_id-date-idx"
+    on public.cash_ledger (member_id, date);
@@ -0,0 +1,19 @@
+create table if not exists public.corporate_events
+(
+    security_code varchar(24)              default ''::character varying                   not null,
+    kind          varchar(32)              default ''::character varying                   not null,
+    event_date    date                     default CURRENT_DATE                            not null,
+    description   text                     default ''::text                                not null,
+    created_time  timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time  timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    primary key (security_code, kind, event_date)
+);
+
+comment on table public.corporate_events is '公司的股東會與法人說明會等事件';
+comment on column public.corporate_events.security_code is '股票代號';
+comment on column public.corporate_events.kind is '事件類別 shareholder_meeting:股東會 earnings_call:法人說明會';
+comment on column public.corporate_events.event_date is '事件的日期';
+comment on column public.corporate_events.description is '事件的說明 ex. 常會或臨時會、法說會的時間地點';
+
+create index if not exists "corporate_events-event_date-idx"
+    on public.corporate_events (event_date);
@@ -0,0 +1,57 @@
+-- 將 "DailyQuotes" 改為以 "Date" 為分區鍵的 RANGE 分區表，每個年度一個分區，命名為 "DailyQuotes_YYYY"
+-- 分區表的主鍵與唯一索引必須包含分區鍵，主鍵由 ("Serial") 改為 ("Serial", "Date")
+-- 之後年度的分區由排程 database::partition::execute 預先建立(設定檔 partition.years_ahead)，
+-- 超過保留年限(partition.retention_years)的分區會 DETACH 後移到 archive schema，不再參與查詢
+-- 執行前請先備份，資料量大時搬移需要一段時間，期間請停止服務
+begin;
+
+create schema if not exists archive;
+
+alter table public."DailyQuotes" rename to "DailyQuotes_unpartitioned";
+alter index public."DailyQuotes_Date_idx" rename to "DailyQuotes_unpartitioned_Date_idx";
+alter index public."DailyQuotes_SecurityCode_Date_uidx" rename to "DailyQuotes_unpartitioned_SecurityCode_Date_uidx";
+-- 序號沿用原本的 sequence，刪除舊表時不能一併刪除
+alter sequence public."DailyQuotes_Serial_seq" owned by none;
+
+create table public."DailyQuotes"
+(
+    like public."DailyQuotes_unpartitioned" including defaults including comments,
+    primary key ("Serial", "Date")
+) partition by range ("Date");
+
+alter sequence public."DailyQuotes_Serial_seq" owned by public."DailyQuotes"."Serial";
+
+create index "DailyQuotes_Date_idx"
+    on public."DailyQuotes" ("Date" desc) include ("Serial", "SecurityCode");
+
+create unique index "DailyQuotes_SecurityCode_Date_uidx"
+    on public."DailyQuotes" ("SecurityCode" asc, "Date" desc) include (year, "HighestPrice", "LowestPrice", "ClosingPrice", "price-to-book_ratio", "PriceEarningRatio");
+
+-- 建立既有資料的年度到明年的分區
+do
+$$
+    declare
+        y integer;
+    begin
+        for y in
+            select generate_series(
+                           coalesce(min(extract(year from "Date"))::integer, extract(year from current_date)::integer),
+                           extract(year from current_date)::integer + 1)
+            from public."DailyQuotes_unpartitioned"
+            loop
+                execute format(
+                        'create table if not exists public.%I partition of public."DailyQuotes" for values from (%L) to (%L)',
+                        'DailyQuotes_' || y, make_date(y, 1, 1), make_date(y + 1, 1, 1));
+            end loop;
+    end
+$$;
+
+insert into public."DailyQuotes"
+select *
+from public."DailyQuotes_unpartitioned";
+
+commit;
+
+-- 確認筆數一致後再刪除舊表
+-- select (select count(*) from public."DailyQuotes"), (select count(*) from public."DailyQuotes_unpartitioned");
+-- drop table public."DailyQuotes_unpartitioned";
@@ -0,0 +1,26 @@
+create table public.daily_valuation
+(
+    serial               bigserial
+        primary key,
+    date                 date                     default CURRENT_DATE                            not null,
+    security_code        varchar(24)              default ''::character varying                   not null,
+    stock_exchange_id    integer                  default 0                                       not null,
+    price_earning_ratio  numeric(18, 4)           default 0                                       not null,
+    price_to_book_ratio  numeric(18, 4)           default 0                                       not null,
+    dividend_yield       numeric(18, 4)           default 0                                       not null,
+    created_time         timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time         timestamp with time zone default ('now'::text)::timestamp with time zone not null
+);
+
+comment on table public.daily_valuation is '交易所公布的個股日本益比、殖利率及股價淨值比';
+comment on column public.daily_valuation.date is '資料屬於那一天';
+comment on column public.daily_valuation.stock_exchange_id is '交易所 1:twse 2:tpex';
+comment on column public.daily_valuation.price_earning_ratio is '本益比，虧損時為 0';
+comment on column public.daily_valuation.price_to_book_ratio is '股價淨值比';
+comment on column public.daily_valuation.dividend_yield is '殖利率(%)';
+
+create unique index "daily_valuation-date-security_code-uidx"
+    on public.daily_valuation (date, security_code);
+
+create index "daily_valuation-security_code-date-idx"
+    on public.daily_valuation (security_code asc, date desc);
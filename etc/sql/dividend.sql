@@ -25,7 +25,7 @@ create table public.dividend
 );
 
 comment on column public.dividend.year_of_dividend is '股利所屬年度';
-comment on column public.dividend.quarter is '季度 A:全年度 Q1~Q4:第一季~第四季 H1~H2︰上半季~下半季';
+comment on column public.dividend.quarter is '季度 A:全年度 Q1~Q4:第一季~第四季 H1~H2︰上半季~下半季 S:特別股利';
 comment on column public.dividend.cash_dividend is '現金股利';
 comment on column public.dividend.stock_dividend is '股票股利';
 comment on column public.dividend.sum is '合計';
@@ -50,3 +50,7 @@ create index "dividend-year-dividend_date-idx"
 create index "dividend-year-payable_date-idx"
     on public.dividend (year, payable_date1, payable_date2);
 
+
+alter table public.dividend add batch_id varchar(64) default ''::character varying not null;
+
+comment on column public.dividend.batch_id is '寫入此筆數據的 ingestion_batches 批次';
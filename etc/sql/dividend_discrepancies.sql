@@ -0,0 +1,23 @@
+create table public.dividend_discrepancies
+(
+    serial        bigserial
+        primary key,
+    security_code varchar(16)              default ''::character varying                   not null,
+    year          integer                  default 0                                       not null,
+    kind          varchar(16)              default ''::character varying                   not null,
+    expected      numeric(18, 4)           default 0                                       not null,
+    actual        numeric(18, 4)           default 0                                       not null,
+    detail        text                     default ''::text                                not null,
+    created_time  timestamp with time zone default ('now'::text)::timestamp with time zone not null
+);
+
+comment on table public.dividend_discrepancies is '股利數據與證交所除權除息結果比對不一致的記錄，每次比對後整批重建';
+comment on column public.dividend_discrepancies.security_code is '股票代號';
+comment on column public.dividend_discrepancies.year is '發放年度';
+comment on column public.dividend_discrepancies.kind is 'missing:缺少該年度的股利 mismatch:現金股利金額不一致';
+comment on column public.dividend_discrepancies.expected is '證交所除息的息值合計';
+comment on column public.dividend_discrepancies.actual is 'dividend 表的現金股利';
+comment on column public.dividend_discrepancies.detail is '說明';
+
+create unique index "dividend_discrepancies-security_code-year-uidx"
+    on public.dividend_discrepancies (security_code, year);
@@ -54,3 +54,21 @@ comment on column public.estimate.per_expensive is '歷年80%百分位數本益
 create unique index "estimate-security_code-date-uidx"
     on public.estimate (security_code, date);
 
+
+alter table public.estimate add yield_percentile numeric(18, 4) default 0 not null;
+alter table public.estimate add yield_cheap numeric(18, 4) default 0 not null;
+alter table public.estimate add yield_fair numeric(18, 4) default 0 not null;
+alter table public.estimate add yield_expensive numeric(18, 4) default 0 not null;
+
+comment on column public.estimate.yield_percentile is '當日殖利率在近 5 年殖利率分佈中的百分位(%)，沒有數據時為 0';
+comment on column public.estimate.yield_cheap is '殖利率回到近 5 年 80% 百分位數時的股價';
+comment on column public.estimate.yield_fair is '殖利率回到近 5 年中位數時的股價';
+comment on column public.estimate.yield_expensive is '殖利率回到近 5 年 20% 百分位數時的股價';
+
+alter table public.estimate add pbr_band_cheap numeric(18, 4) default 0 not null;
+alter table public.estimate add pbr_band_fair numeric(18, 4) default 0 not null;
+alter table public.estimate add pbr_band_expensive numeric(18, 4) default 0 not null;
+
+comment on column public.estimate.pbr_band_cheap is '歷年收盤價 / 當時每股淨值(net_asset_value_histories)的10%百分位數 * 最新每股淨值，沒有淨值歷史時為 0';
+comment on column public.estimate.pbr_band_fair is '歷年收盤價 / 當時每股淨值(net_asset_value_histories)的50%百分位數 * 最新每股淨值，沒有淨值歷史時為 0';
+comment on column public.estimate.pbr_band_expensive is '歷年收盤價 / 當時每股淨值(net_asset_value_histories)的80%百分位數 * 最新每股淨值，沒有淨值歷史時為 0';
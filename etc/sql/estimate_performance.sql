@@ -0,0 +1,31 @@
+create table public.estimate_performance
+(
+    security_code  varchar(24)              default ''::character varying                   not null,
+    date           date                                                                     not null,
+    model          varchar(16)              default ''::character varying                   not null,
+    months         integer                  default 0                                       not null,
+    closing_price  numeric(18, 4)           default 0                                       not null,
+    cheap          numeric(18, 4)           default 0                                       not null,
+    fair           numeric(18, 4)           default 0                                       not null,
+    expensive      numeric(18, 4)           default 0                                       not null,
+    signal         varchar(16)              default ''::character varying                   not null,
+    forward_date   date                                                                     not null,
+    forward_return numeric(18, 4)           default 0                                       not null,
+    hit            boolean                  default false                                   not null,
+    created_time   timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time   timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    primary key (security_code, date, model, months)
+);
+
+comment on table public.estimate_performance is '估價模型在估價日之後 3、6、12 個月的實際報酬，只記錄收盤價低於便宜價或高於昂貴價的訊號';
+comment on column public.estimate_performance.date is '估價日';
+comment on column public.estimate_performance.model is '估價模型 overall:綜合 price:歷年股價 dividend:股利 eps:EPS pbr:股價淨值比 per:本益比 yield:殖利率百分位 pbr_band:歷史股價淨值比區間';
+comment on column public.estimate_performance.months is '驗證的期間(月)';
+comment on column public.estimate_performance.closing_price is '估價日的收盤價';
+comment on column public.estimate_performance.signal is 'cheap:收盤價小於等於便宜價 expensive:收盤價大於等於昂貴價';
+comment on column public.estimate_performance.forward_date is '驗證日，估價日加上驗證期間前最近的交易日';
+comment on column public.estimate_performance.forward_return is '估價日至驗證日的還原股價報酬率(%)';
+comment on column public.estimate_performance.hit is '便宜價訊號之後上漲或昂貴價訊號之後下跌';
+
+create index "estimate_performance-forward_date-idx"
+    on public.estimate_performance (forward_date);
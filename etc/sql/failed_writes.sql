@@ -0,0 +1,22 @@
+create table if not exists public.failed_writes
+(
+    serial       bigserial
+        primary key,
+    table_name   varchar(64)              default ''::character varying                   not null,
+    record_key   varchar(128)             default ''::character varying                   not null,
+    payload      jsonb                    default '{}'::jsonb                             not null,
+    error        text                     default ''::text                                not null,
+    attempts     integer                  default 0                                       not null,
+    created_time timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time timestamp with time zone default ('now'::text)::timestamp with time zone not null
+);
+
+comment on table public.failed_writes is '重試後仍寫入失敗的數據，由排程定時重新寫入，成功後刪除';
+comment on column public.failed_writes.table_name is '寫入的資料表';
+comment on column public.failed_writes.record_key is '資料的鍵值 ex. 股票代號-年月';
+comment on column public.failed_writes.payload is '序列化後的完整數據';
+comment on column public.failed_writes.error is '最後一次寫入失敗的原因';
+comment on column public.failed_writes.attempts is '排程重新寫入失敗的次數，達上限後不再自動重試';
+
+create unique index if not exists "failed_writes-table_name-record_key-uidx"
+    on public.failed_writes (table_name, record_key);
@@ -38,3 +38,13 @@ create unique index "financial_statement-security_code-year-quarter-uidx"
 create index "financial_statement-year-quarter-idx"
     on public.financial_statement (year, quarter) include (security_code, earnings_per_share);
 
+
+alter table financial_statement add statement_type integer default 1 not null;
+comment on column public.financial_statement.statement_type is '財報類別 1:合併 2:個別，同一期已有合併財報時不會被個別財報覆蓋';
+
+alter table financial_statement add operating_cash_flow numeric(18, 4) default 0 not null;
+alter table financial_statement add investing_cash_flow numeric(18, 4) default 0 not null;
+alter table financial_statement add financing_cash_flow numeric(18, 4) default 0 not null;
+comment on column public.financial_statement.operating_cash_flow is '營業活動之淨現金流入(流出)，當年度累計至該季的仟元';
+comment on column public.financial_statement.investing_cash_flow is '投資活動之淨現金流入(流出)，當年度累計至該季的仟元';
+comment on column public.financial_statement.financing_cash_flow is '籌資活動之淨現金流入(流出)，當年度累計至該季的仟元';
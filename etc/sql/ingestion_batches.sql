@@ -0,0 +1,18 @@
+create table if not exists public.ingestion_batches
+(
+    source         varchar(64)              default ''::character varying                   not null,
+    period         varchar(64)              default ''::character varying                   not null,
+    batch_id       varchar(64)              default ''::character varying                   not null,
+    checksum       varchar(64)              default ''::character varying                   not null,
+    row_count      integer                  default 0                                       not null,
+    created_time   timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time   timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    primary key (source, period)
+);
+
+comment on table public.ingestion_batches is '各抓取來源與期間最後一次完整寫入的批次，相同內容重新執行時略過寫入';
+comment on column public.ingestion_batches.source is '抓取來源 ex. revenue、goodinfo_dividend';
+comment on column public.ingestion_batches.period is '批次涵蓋的期間 ex. 月營收為 yyyyMM，股利為 股票代號-年度';
+comment on column public.ingestion_batches.batch_id is '批次識別碼，同時寫入該批次的每一筆數據';
+comment on column public.ingestion_batches.checksum is '抓取內容的 SHA-256';
+comment on column public.ingestion_batches.row_count is '批次寫入的筆數';
@@ -0,0 +1,30 @@
+create table public.insider_shareholding
+(
+    serial          bigserial
+        primary key,
+    security_code   varchar(24)              default ''::character varying                   not null,
+    year            integer                  default 0                                       not null,
+    month           integer                  default 0                                       not null,
+    shares_held     bigint                   default 0                                       not null,
+    shares_pledged  bigint                   default 0                                       not null,
+    holding_ratio   numeric(18, 4)           default 0                                       not null,
+    pledge_ratio    numeric(18, 4)           default 0                                       not null,
+    created_time    timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time    timestamp with time zone default ('now'::text)::timestamp with time zone not null
+);
+
+comment on table public.insider_shareholding is '董事、監察人每月持股彙總';
+comment on column public.insider_shareholding.shares_held is '董監事合計持股(股)';
+comment on column public.insider_shareholding.shares_pledged is '董監事合計設質股數(股)';
+comment on column public.insider_shareholding.holding_ratio is '董監事持股佔已發行股數比率(%)';
+comment on column public.insider_shareholding.pledge_ratio is '設質股數佔董監事持股比率(%)';
+
+create unique index "insider_shareholding-security_code-year-month-uidx"
+    on public.insider_shareholding (security_code, year, month);
+
+alter table insider_shareholding add previous_shares_held bigint default 0 not null;
+alter table insider_shareholding add decrease_percent numeric(18, 4) default 0 not null;
+alter table insider_shareholding add large_decrease boolean default false not null;
+comment on column public.insider_shareholding.previous_shares_held is '標記持股大幅減少時上個月的董監事合計持股(股)';
+comment on column public.insider_shareholding.decrease_percent is '董監事持股較上個月減少的百分比(%)';
+comment on column public.insider_shareholding.large_decrease is '庫存股票的董監事持股較上個月大幅減少，列入庫存月報';
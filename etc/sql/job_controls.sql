@@ -0,0 +1,12 @@
+create table if not exists public.job_controls
+(
+    job_name     varchar(255)             default ''::character varying                   not null
+        primary key,
+    disabled     boolean                  default false                                   not null,
+    created_time timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time timestamp with time zone default ('now'::text)::timestamp with time zone not null
+);
+
+comment on table public.job_controls is '停用的排程任務，重啟服務後仍維持停用';
+comment on column public.job_controls.job_name is '任務名稱(任務函式的路徑)，* 代表暫停整個排程';
+comment on column public.job_controls.disabled is '是否停用';
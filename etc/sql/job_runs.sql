@@ -0,0 +1,12 @@
+create table public.job_runs
+(
+    job_name          varchar(255)             default ''::character varying                   not null
+        primary key,
+    last_success_time timestamp with time zone                                                 not null,
+    created_time      timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time      timestamp with time zone default ('now'::text)::timestamp with time zone not null
+);
+
+comment on table public.job_runs is '排程任務最後一次成功執行的時間';
+comment on column public.job_runs.job_name is '任務名稱(任務函式的路徑)';
+comment on column public.job_runs.last_success_time is '最後一次成功執行的時間';
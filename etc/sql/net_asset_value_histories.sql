@@ -0,0 +1,13 @@
+create table if not exists public.net_asset_value_histories
+(
+    security_code             varchar(24)              default ''::character varying                   not null,
+    date                      date                     default CURRENT_DATE                            not null,
+    net_asset_value_per_share numeric(18, 4)           default 0                                       not null,
+    created_time              timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time              timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    primary key (security_code, date)
+);
+
+comment on table public.net_asset_value_histories is '個股每股淨值的歷史記錄，季報的數值以季底日期記錄，興櫃與雅虎回補的數值以更新當天記錄';
+comment on column public.net_asset_value_histories.date is '每股淨值開始生效的日期';
+comment on column public.net_asset_value_histories.net_asset_value_per_share is '每股淨值';
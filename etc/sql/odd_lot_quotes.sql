@@ -0,0 +1,28 @@
+create table if not exists public.odd_lot_quotes
+(
+    date              date                     default CURRENT_DATE                            not null,
+    security_code     varchar(24)              default ''::character varying                   not null,
+    stock_exchange_id integer                  default 0                                       not null,
+    trading_volume    bigint                   default 0                                       not null,
+    transaction       bigint                   default 0                                       not null,
+    trade_value       numeric(24, 4)           default 0                                       not null,
+    closing_price     numeric(18, 4)           default 0                                       not null,
+    last_bid_price    numeric(18, 4)           default 0                                       not null,
+    last_ask_price    numeric(18, 4)           default 0                                       not null,
+    created_time      timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time      timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    primary key (date, security_code)
+);
+
+comment on table public.odd_lot_quotes is '盤後零股交易的成交行情';
+comment on column public.odd_lot_quotes.date is '資料屬於那一天';
+comment on column public.odd_lot_quotes.stock_exchange_id is '交易所 1:twse 2:tpex';
+comment on column public.odd_lot_quotes.trading_volume is '成交股數';
+comment on column public.odd_lot_quotes.transaction is '成交筆數';
+comment on column public.odd_lot_quotes.trade_value is '成交金額';
+comment on column public.odd_lot_quotes.closing_price is '成交價，沒有成交時為 0';
+comment on column public.odd_lot_quotes.last_bid_price is '最後揭示買價';
+comment on column public.odd_lot_quotes.last_ask_price is '最後揭示賣價';
+
+create index if not exists "odd_lot_quotes-security_code-date-idx"
+    on public.odd_lot_quotes (security_code asc, date desc);
@@ -0,0 +1,23 @@
+create table if not exists public.order_book_snapshots
+(
+    security_code varchar(24)              default ''::character varying                   not null,
+    quote_time    timestamp with time zone                                                  not null,
+    last_price    numeric(18, 4)           default 0                                       not null,
+    volume        bigint                   default 0                                       not null,
+    bid_prices    numeric(18, 4)[]         default '{}'::numeric[]                         not null,
+    bid_volumes   bigint[]                 default '{}'::bigint[]                          not null,
+    ask_prices    numeric(18, 4)[]         default '{}'::numeric[]                         not null,
+    ask_volumes   bigint[]                 default '{}'::bigint[]                          not null,
+    created_time  timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    primary key (security_code, quote_time)
+);
+
+comment on table public.order_book_snapshots is '庫存股票收盤前最佳五檔委買委賣的快照';
+comment on column public.order_book_snapshots.security_code is '股票代號';
+comment on column public.order_book_snapshots.quote_time is '交易所揭示資料的時間';
+comment on column public.order_book_snapshots.last_price is '最近成交價，尚未成交或集合競價期間為 0';
+comment on column public.order_book_snapshots.volume is '累積成交張數';
+comment on column public.order_book_snapshots.bid_prices is '委買價由高到低';
+comment on column public.order_book_snapshots.bid_volumes is '委買張數，與 bid_prices 對應';
+comment on column public.order_book_snapshots.ask_prices is '委賣價由低到高';
+comment on column public.order_book_snapshots.ask_volumes is '委賣張數，與 ask_prices 對應';
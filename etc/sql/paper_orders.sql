@@ -0,0 +1,33 @@
+create table if not exists public.paper_orders
+(
+    serial        bigserial
+        primary key,
+    account_id    bigint                   default 0                                       not null,
+    security_code varchar(24)              default ''::character varying                   not null,
+    side          varchar(8)               default 'buy'::character varying                not null,
+    quantity      bigint                   default 0                                       not null,
+    price_type    varchar(8)               default 'open'::character varying               not null,
+    status        varchar(16)              default 'pending'::character varying            not null,
+    order_date    date                     default CURRENT_DATE                            not null,
+    fill_date     date,
+    fill_price    numeric(18, 4),
+    created_time  timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time  timestamp with time zone default ('now'::text)::timestamp with time zone not null
+);
+
+comment on table public.paper_orders is '模擬交易的委託';
+comment on column public.paper_orders.account_id is '下單的 Telegram 使用者編號';
+comment on column public.paper_orders.security_code is '股票代號';
+comment on column public.paper_orders.side is 'buy 買進、sell 賣出';
+comment on column public.paper_orders.quantity is '股數';
+comment on column public.paper_orders.price_type is '以下單後第一個交易日的 open 開盤價或 close 收盤價成交';
+comment on column public.paper_orders.status is 'pending 等待成交、filled 已成交、cancelled 已取消';
+comment on column public.paper_orders.order_date is '下單日期';
+comment on column public.paper_orders.fill_date is '成交日期';
+comment on column public.paper_orders.fill_price is '成交價';
+
+create index if not exists "paper_orders-account_id-status-idx"
+    on public.paper_orders (account_id, status);
+
+create index if not exists "paper_orders-status-order_date-idx"
+    on public.paper_orders (status, order_date);
@@ -0,0 +1,12 @@
+create table if not exists public.portfolio_returns
+(
+    date         date                     default CURRENT_DATE                            not null
+        primary key,
+    daily_return numeric(18, 8)           default 0                                       not null,
+    created_time timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time timestamp with time zone default ('now'::text)::timestamp with time zone not null
+);
+
+comment on table public.portfolio_returns is '整體庫存每日的時間加權報酬';
+comment on column public.portfolio_returns.date is '資料屬於那一天';
+comment on column public.portfolio_returns.daily_return is '當日持股相對前一個交易日收盤的報酬率，不受當日買進的金額影響 ex. 0.0123 代表 1.23%';
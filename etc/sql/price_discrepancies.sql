@@ -0,0 +1,24 @@
+create table public.price_discrepancies
+(
+    serial        bigserial
+        primary key,
+    date          date                                                                     not null,
+    security_code varchar(16)              default ''::character varying                   not null,
+    closing_price numeric(18, 4)           default 0                                       not null,
+    yahoo_price   numeric(18, 4)           default 0                                       not null,
+    diff_percent  numeric(18, 4)           default 0                                       not null,
+    is_corrected  boolean                  default false                                   not null,
+    created_time  timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time  timestamp with time zone default ('now'::text)::timestamp with time zone not null
+);
+
+comment on table public.price_discrepancies is '收盤價與雅虎報價不一致的記錄，人工修正 DailyQuotes 後將 is_corrected 設為 true';
+comment on column public.price_discrepancies.date is '收盤日';
+comment on column public.price_discrepancies.security_code is '股票代號';
+comment on column public.price_discrepancies.closing_price is 'DailyQuotes 內來自證交所、櫃買中心的收盤價';
+comment on column public.price_discrepancies.yahoo_price is '雅虎的收盤價';
+comment on column public.price_discrepancies.diff_percent is '差異百分比';
+comment on column public.price_discrepancies.is_corrected is '是否已人工修正';
+
+create unique index "price_discrepancies-date-security_code-uidx"
+    on public.price_discrepancies (date, security_code);
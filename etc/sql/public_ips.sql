@@ -0,0 +1,13 @@
+create table if not exists public.public_ips
+(
+    serial       bigserial
+        primary key,
+    ip           varchar(64)              default ''::character varying                   not null,
+    previous_ip  varchar(64)              default ''::character varying                   not null,
+    created_time timestamp with time zone default ('now'::text)::timestamp with time zone not null
+);
+
+comment on table public.public_ips is '偵測到的公網 IP 變動，每次變動新增一筆';
+comment on column public.public_ips.ip is '變動後的公網 IP';
+comment on column public.public_ips.previous_ip is '變動前的公網 IP，第一次記錄時為空字串';
+comment on column public.public_ips.created_time is '偵測到變動的時間';
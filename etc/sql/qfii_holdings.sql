@@ -0,0 +1,20 @@
+create table if not exists public.qfii_holdings
+(
+    date               date                     default CURRENT_DATE                            not null,
+    security_code      varchar(24)              default ''::character varying                   not null,
+    issued_share       bigint                   default 0                                       not null,
+    shares_held        bigint                   default 0                                       not null,
+    holding_percentage numeric(18, 4)           default 0                                       not null,
+    created_time       timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time       timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    primary key (date, security_code)
+);
+
+comment on table public.qfii_holdings is '外資及陸資每日持股的歷史紀錄';
+comment on column public.qfii_holdings.date is '資料屬於那一天';
+comment on column public.qfii_holdings.issued_share is '發行股數';
+comment on column public.qfii_holdings.shares_held is '全體外資及陸資持有股數';
+comment on column public.qfii_holdings.holding_percentage is '全體外資及陸資持股比率';
+
+create index if not exists "qfii_holdings-security_code-date-idx"
+    on public.qfii_holdings (security_code asc, date desc);
@@ -0,0 +1,20 @@
+create table public.quality_report
+(
+    serial       bigserial
+        primary key,
+    date         date                                                                     not null,
+    check_name   varchar(64)              default ''::character varying                   not null,
+    passed       boolean                  default true                                    not null,
+    detail       text                     default ''::text                                not null,
+    created_time timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time timestamp with time zone default ('now'::text)::timestamp with time zone not null
+);
+
+comment on table public.quality_report is '每日數據品質檢查結果';
+comment on column public.quality_report.date is '檢查的收盤日';
+comment on column public.quality_report.check_name is '檢查項目';
+comment on column public.quality_report.passed is '是否通過';
+comment on column public.quality_report.detail is '檢查結果說明';
+
+create unique index "quality_report-date-check_name-uidx"
+    on public.quality_report (date, check_name);
@@ -33,3 +33,10 @@ comment on column public."Revenue".highest_price is '當月最高價';
 create unique index "Revenue_SecurityCode_Date-uidx"
     on public."Revenue" ("SecurityCode", "Date");
 
+
+alter table public."Revenue" add batch_id varchar(64) default ''::character varying not null;
+
+comment on column public."Revenue".batch_id is '寫入此筆數據的 ingestion_batches 批次';
+
+create index "Revenue-batch_id-idx"
+    on public."Revenue" (batch_id);
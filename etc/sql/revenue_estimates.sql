@@ -0,0 +1,24 @@
+create table if not exists public.revenue_estimates
+(
+    security_code  varchar(24)              default ''::character varying                   not null,
+    month          bigint                   default 0                                       not null,
+    estimated      numeric(18, 4)           default 0                                       not null,
+    basis          varchar(32)              default ''::character varying                   not null,
+    peer_count     integer                  default 0                                       not null,
+    peer_growth    numeric(18, 4)           default 0                                       not null,
+    is_provisional boolean                  default true                                    not null,
+    actual         numeric(18, 4)           default 0                                       not null,
+    created_time   timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time   timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    primary key (security_code, month)
+);
+
+comment on table public.revenue_estimates is '月營收公布前估算的庫存股票月營收';
+comment on column public.revenue_estimates.security_code is '股票代號';
+comment on column public.revenue_estimates.month is '營收月份 yyyyMM';
+comment on column public.revenue_estimates.estimated is '估算的當月營收';
+comment on column public.revenue_estimates.basis is '估算依據 peers:同產業已公布公司的年增率 seasonality:去年同期的月增率 peers+seasonality:兩者平均';
+comment on column public.revenue_estimates.peer_count is '同產業已公布營收的公司數';
+comment on column public.revenue_estimates.peer_growth is '同產業已公布公司的年增率中位數(%)';
+comment on column public.revenue_estimates.is_provisional is '是否為暫估值，公司公布營收後改為 false';
+comment on column public.revenue_estimates.actual is '公司公布的當月營收，暫估時為 0';
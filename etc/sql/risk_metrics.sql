@@ -0,0 +1,20 @@
+create table if not exists public.risk_metrics
+(
+    date          date                     default CURRENT_DATE                            not null,
+    security_code varchar(24)              default ''::character varying                   not null,
+    beta          numeric(18, 4)           default 0                                       not null,
+    volatility    numeric(18, 4)           default 0                                       not null,
+    sharpe_ratio  numeric(18, 4)           default 0                                       not null,
+    sample_days   integer                  default 0                                       not null,
+    created_time  timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time  timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    primary key (date, security_code)
+);
+
+comment on table public.risk_metrics is '庫存股票與整體庫存近一年的風險指標';
+comment on column public.risk_metrics.date is '資料屬於那一天';
+comment on column public.risk_metrics.security_code is '股票代號，整體庫存為 portfolio';
+comment on column public.risk_metrics.beta is '相對加權指數(TAIEX)的 beta';
+comment on column public.risk_metrics.volatility is '年化波動度(%)';
+comment on column public.risk_metrics.sharpe_ratio is '年化夏普比率';
+comment on column public.risk_metrics.sample_days is '計算時使用的日報酬筆數';
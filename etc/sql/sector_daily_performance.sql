@@ -0,0 +1,25 @@
+create table if not exists public.sector_daily_performance
+(
+    date                 date                     default CURRENT_DATE                            not null,
+    stock_industry_id    integer                  default 0                                       not null,
+    stock_count          integer                  default 0                                       not null,
+    advancing            integer                  default 0                                       not null,
+    declining            integer                  default 0                                       not null,
+    average_change_range numeric(18, 4)           default 0                                       not null,
+    trade_value          numeric(24, 4)           default 0                                       not null,
+    created_time         timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time         timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    primary key (date, stock_industry_id)
+);
+
+comment on table public.sector_daily_performance is '各產業每日的平均漲跌幅';
+comment on column public.sector_daily_performance.date is '資料屬於那一天';
+comment on column public.sector_daily_performance.stock_industry_id is '股票的產業分類編號 stock_industry';
+comment on column public.sector_daily_performance.stock_count is '當日有收盤價的股票數';
+comment on column public.sector_daily_performance.advancing is '上漲的股票數';
+comment on column public.sector_daily_performance.declining is '下跌的股票數';
+comment on column public.sector_daily_performance.average_change_range is '產業內股票漲跌幅(%)的平均';
+comment on column public.sector_daily_performance.trade_value is '產業內股票的成交金額合計';
+
+create index if not exists "sector_daily_performance-stock_industry_id-date-idx"
+    on public.sector_daily_performance (stock_industry_id, date);
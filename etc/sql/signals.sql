@@ -0,0 +1,19 @@
+create table if not exists public.signals
+(
+    date          date                     default CURRENT_DATE                            not null,
+    security_code varchar(24)              default ''::character varying                   not null,
+    rule          varchar(64)              default ''::character varying                   not null,
+    value         numeric(18, 4)           default 0                                       not null,
+    created_time  timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time  timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    primary key (date, security_code, rule)
+);
+
+comment on table public.signals is '策略規則每日觸發的訊號';
+comment on column public.signals.date is '資料屬於那一天';
+comment on column public.signals.security_code is '股票代號';
+comment on column public.signals.rule is 'signals.toml 內的規則名稱';
+comment on column public.signals.value is '觸發時的指標值 ex. 快線均價、RSI、殖利率';
+
+create index if not exists "signals-security_code-date-idx"
+    on public.signals (security_code, date);
@@ -0,0 +1,54 @@
+-- 本機模式(sqlite feature)使用的資料表，欄位為 Postgres 對應資料表的子集，金額與比率以 NUMERIC 保存
+
+-- 每日收盤報價，對應 "DailyQuotes"
+create table if not exists daily_quotes
+(
+    security_code  text    not null,
+    date           text    not null,
+    opening_price  numeric not null default 0,
+    highest_price  numeric not null default 0,
+    lowest_price   numeric not null default 0,
+    closing_price  numeric not null default 0,
+    change         numeric not null default 0,
+    change_range   numeric not null default 0,
+    trading_volume numeric not null default 0,
+    trade_value    numeric not null default 0,
+    "transaction"  numeric not null default 0,
+    primary key (security_code, date)
+);
+
+-- 月營收，對應 revenue，date 為營收的年月 ex. 202401
+create table if not exists revenues
+(
+    security_code                      text    not null,
+    date                               integer not null,
+    monthly                            numeric not null default 0,
+    last_month                         numeric not null default 0,
+    last_year_this_month               numeric not null default 0,
+    monthly_accumulated                numeric not null default 0,
+    last_year_monthly_accumulated      numeric not null default 0,
+    compared_with_last_month           numeric not null default 0,
+    compared_with_last_year_same_month numeric not null default 0,
+    accumulated_compared_with_last_year numeric not null default 0,
+    primary key (security_code, date)
+);
+
+-- 股利，對應 dividend
+create table if not exists dividends
+(
+    security_code                  text    not null,
+    year                           integer not null,
+    year_of_dividend               integer not null,
+    quarter                        text    not null default '',
+    earnings_cash_dividend         numeric not null default 0,
+    capital_reserve_cash_dividend  numeric not null default 0,
+    cash_dividend                  numeric not null default 0,
+    earnings_stock_dividend        numeric not null default 0,
+    capital_reserve_stock_dividend numeric not null default 0,
+    stock_dividend                 numeric not null default 0,
+    sum                            numeric not null default 0,
+    payout_ratio_cash              numeric not null default 0,
+    payout_ratio_stock             numeric not null default 0,
+    payout_ratio                   numeric not null default 0,
+    primary key (security_code, year_of_dividend, quarter)
+);
@@ -0,0 +1,38 @@
+create table if not exists public.stock_lot_sales
+(
+    serial         bigserial
+        primary key,
+    member_id      bigint                   default 0                                       not null,
+    security_code  varchar(24)              default ''::character varying                   not null,
+    lot_serial     bigint                   default 0                                       not null,
+    quantity       bigint                   default 0                                       not null,
+    buy_date       date                     default CURRENT_DATE                            not null,
+    sell_date      date                     default CURRENT_DATE                            not null,
+    sell_price     numeric(18, 4)           default 0                                       not null,
+    cost           numeric(18, 4)           default 0                                       not null,
+    proceeds       numeric(18, 4)           default 0                                       not null,
+    fee            numeric(18, 4)           default 0                                       not null,
+    realized       numeric(18, 4)           default 0                                       not null,
+    holding_days   integer                  default 0                                       not null,
+    created_time   timestamp with time zone default ('now'::text)::timestamp with time zone not null
+);
+
+comment on table public.stock_lot_sales is '賣出時與買進批次(stock_ownership_details)配對的明細';
+comment on column public.stock_lot_sales.member_id is '會員編號 Member.Id';
+comment on column public.stock_lot_sales.security_code is '股票代碼';
+comment on column public.stock_lot_sales.lot_serial is '配對的買進批次 stock_ownership_details.serial';
+comment on column public.stock_lot_sales.quantity is '自該批次賣出的股數';
+comment on column public.stock_lot_sales.buy_date is '買進批次的交易日期';
+comment on column public.stock_lot_sales.sell_date is '賣出日期';
+comment on column public.stock_lot_sales.sell_price is '每股賣出價';
+comment on column public.stock_lot_sales.cost is '賣出股數分攤的買入成本';
+comment on column public.stock_lot_sales.proceeds is '賣出金額(未扣費用)';
+comment on column public.stock_lot_sales.fee is '分攤的賣出手續費與交易稅';
+comment on column public.stock_lot_sales.realized is '已實現損益 = 賣出金額 - 成本 - 費用';
+comment on column public.stock_lot_sales.holding_days is '持有天數';
+
+create index if not exists "stock_lot_sales-member_id-sell_date-idx"
+    on public.stock_lot_sales (member_id, sell_date);
+
+create index if not exists "stock_lot_sales-lot_serial-idx"
+    on public.stock_lot_sales (lot_serial);
@@ -34,4 +34,9 @@ create index "stock_ownership_details-security_code_idx"
     on public.stock_ownership_details (security_code);
 
 alter table stock_ownership_details  add current_cost_per_share numeric(18, 4) default 0 not null;
-comment on column public.stock_ownership_details.current_cost_per_share is '目前每股成本';
\ No newline at end of file
+comment on column public.stock_ownership_details.current_cost_per_share is '目前每股成本';
+
+alter table stock_ownership_details add stop_loss_price numeric(18, 4) default 0 not null;
+alter table stock_ownership_details add take_profit_price numeric(18, 4) default 0 not null;
+comment on column public.stock_ownership_details.stop_loss_price is '停損價，0 代表未設定，收盤價跌破時通知';
+comment on column public.stock_ownership_details.take_profit_price is '停利價，0 代表未設定，收盤價突破時通知';
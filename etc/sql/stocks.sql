@@ -16,7 +16,8 @@ create table public.stocks
     weight                        numeric(18, 4)           default 0                                       not null,
     issued_share                  bigint                   default 0                                       not null,
     qfii_shares_held              bigint                   default 0                                       not null,
-    qfii_share_holding_percentage numeric(18, 4)           default 0                                       not null
+    qfii_share_holding_percentage numeric(18, 4)           default 0                                       not null,
+    market_cap                    numeric(24, 4)           default 0                                       not null
 );
 
 comment on column public.stocks.last_one_eps is '近一季EPS';
@@ -29,6 +30,7 @@ comment on column public.stocks.weight is '權值佔比';
 comment on column public.stocks.issued_share is '發行股數';
 comment on column public.stocks.qfii_shares_held is '全體外資及陸資持有股數';
 comment on column public.stocks.qfii_share_holding_percentage is '全體外資及陸資持股比率';
+comment on column public.stocks.market_cap is '市值(發行股數 * 最近收盤價)';
 
 create index "stocks-stock_exchange_market_id-stock_industry_id-idx"
     on public.stocks (stock_exchange_market_id, stock_industry_id);
@@ -36,4 +38,8 @@ create index "stocks-stock_exchange_market_id-stock_industry_id-idx"
 create index "stocks-stock_industry_id-idx"
     on public.stocks (stock_industry_id);
 
+alter table stocks add security_type integer default 0 not null;
+comment on column public.stocks.security_type is '證券類別 0:未分類 1:普通股 2:特別股 3:臺灣存託憑證 4:ETF 5:權證';
 
+create index "stocks-security_type-idx"
+    on public.stocks (security_type);
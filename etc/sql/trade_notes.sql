@@ -0,0 +1,26 @@
+create table if not exists public.trade_notes
+(
+    serial        bigserial
+        primary key,
+    target        varchar(8)               default 'lot'::character varying                not null,
+    target_serial bigint                   default 0                                       not null,
+    member_id     bigint                   default 0                                       not null,
+    security_code varchar(24)              default ''::character varying                   not null,
+    tag           varchar(32)              default ''::character varying                   not null,
+    note          varchar(512)             default ''::character varying                   not null,
+    created_time  timestamp with time zone default ('now'::text)::timestamp with time zone not null
+);
+
+comment on table public.trade_notes is '庫存批次或賣出明細的交易筆記與標籤';
+comment on column public.trade_notes.target is 'lot(庫存批次 stock_ownership_details) 或 sale(賣出明細 stock_lot_sales)';
+comment on column public.trade_notes.target_serial is '標記對象的序號';
+comment on column public.trade_notes.member_id is '會員編號 Member.Id，取自標記的對象';
+comment on column public.trade_notes.security_code is '股票代碼，取自標記的對象';
+comment on column public.trade_notes.tag is '標籤 ex. earnings_play、dividend_capture，空字串代表沒有標籤';
+comment on column public.trade_notes.note is '筆記內容';
+
+create index if not exists "trade_notes-target-target_serial-idx"
+    on public.trade_notes (target, target_serial);
+
+create index if not exists "trade_notes-tag-idx"
+    on public.trade_notes (tag);
@@ -0,0 +1,31 @@
+create table public.week52_stats
+(
+    security_code      varchar(24)              default ''::character varying                   not null
+        primary key,
+    date               date                     default CURRENT_DATE                            not null,
+    closing_price      numeric(18, 4)           default 0                                       not null,
+    high_price         numeric(18, 4)           default 0                                       not null,
+    high_date          date                     default '1970-01-01'::date                      not null,
+    low_price          numeric(18, 4)           default 0                                       not null,
+    low_date           date                     default '1970-01-01'::date                      not null,
+    distance_from_high numeric(18, 4)           default 0                                       not null,
+    distance_from_low  numeric(18, 4)           default 0                                       not null,
+    peak_price         numeric(18, 4)           default 0                                       not null,
+    drawdown           numeric(18, 4)           default 0                                       not null,
+    created_time       timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time       timestamp with time zone default ('now'::text)::timestamp with time zone not null
+);
+
+comment on table public.week52_stats is '個股最近一個交易日的 52 週高低點與回檔幅度';
+comment on column public.week52_stats.date is '資料屬於那一天';
+comment on column public.week52_stats.high_price is '52 週最高價';
+comment on column public.week52_stats.high_date is '52 週最高價的日期';
+comment on column public.week52_stats.low_price is '52 週最低價';
+comment on column public.week52_stats.low_date is '52 週最低價的日期';
+comment on column public.week52_stats.distance_from_high is '收盤價距 52 週最高價(%)，小於等於 0';
+comment on column public.week52_stats.distance_from_low is '收盤價距 52 週最低價(%)，大於等於 0';
+comment on column public.week52_stats.peak_price is '上市櫃以來的最高收盤價';
+comment on column public.week52_stats.drawdown is '收盤價自最高收盤價的回檔幅度(%)，小於等於 0';
+
+create index "week52_stats-distance_from_high-idx"
+    on public.week52_stats (distance_from_high desc);
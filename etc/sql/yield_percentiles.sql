@@ -0,0 +1,26 @@
+create table if not exists public.yield_percentiles
+(
+    date           date                     default CURRENT_DATE                            not null,
+    security_code  varchar(24)              default ''::character varying                   not null,
+    dividend_yield numeric(18, 4)           default 0                                       not null,
+    percentile     numeric(18, 4)           default 0                                       not null,
+    yield_p20      numeric(18, 4)           default 0                                       not null,
+    yield_p50      numeric(18, 4)           default 0                                       not null,
+    yield_p80      numeric(18, 4)           default 0                                       not null,
+    sample_days    integer                  default 0                                       not null,
+    created_time   timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    updated_time   timestamp with time zone default ('now'::text)::timestamp with time zone not null,
+    primary key (date, security_code)
+);
+
+comment on table public.yield_percentiles is '個股當日殖利率在自己近 5 年殖利率分佈中的百分位';
+comment on column public.yield_percentiles.date is '資料屬於那一天';
+comment on column public.yield_percentiles.dividend_yield is '當日殖利率(%)';
+comment on column public.yield_percentiles.percentile is '近 5 年殖利率小於等於當日殖利率的比例(%)，越高代表殖利率相對歷史越高';
+comment on column public.yield_percentiles.yield_p20 is '近 5 年殖利率的 20% 百分位數';
+comment on column public.yield_percentiles.yield_p50 is '近 5 年殖利率的中位數';
+comment on column public.yield_percentiles.yield_p80 is '近 5 年殖利率的 80% 百分位數';
+comment on column public.yield_percentiles.sample_days is '計算時使用的殖利率筆數';
+
+create index if not exists "yield_percentiles-security_code-date-idx"
+    on public.yield_percentiles (security_code asc, date desc);
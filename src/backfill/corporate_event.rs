@@ -0,0 +1,223 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{Datelike, Local, Months, NaiveDate};
+
+use crate::{
+    backfill::{pipeline::Pipeline, registry::Crawler},
+    cache::SHARE,
+    crawler::twse::{self, earnings_call::EarningsCall, shareholder_meeting::ShareholderMeeting},
+    database::{
+        repository::{CorporateEventRepository, PgRepository},
+        table::corporate_event::{self, CorporateEvent},
+    },
+    declare::{StockExchangeMarket, StockSymbol},
+    error, logging,
+    util::datetime::{self, Weekend},
+};
+
+/// 19:00 更新股東會與法人說明會的日期
+pub struct CorporateEventCrawler;
+
+#[async_trait]
+impl Crawler for CorporateEventCrawler {
+    fn name(&self) -> &'static str {
+        "corporate_event"
+    }
+
+    fn schedule(&self) -> &'static str {
+        "0 0 11 * * *"
+    }
+
+    async fn execute(&self) -> Result<()> {
+        let now = Local::now();
+        if now.is_weekend() {
+            return Ok(());
+        }
+
+        let count = execute(now.date_naive()).await?;
+        logging::info_file_async(format!("更新股東會與法說會日期結束:{}", count));
+
+        Ok(())
+    }
+}
+
+/// 取得已公告的股東會與本月、下個月上市櫃公司的法說會寫入 corporate_events，回傳寫入的筆數，
+/// 來源當天的請求數已達上限時寫完已取得的事件後回傳 Error::BudgetExhausted 讓排程延後執行
+pub async fn execute(today: NaiveDate) -> Result<usize> {
+    let pipeline = writer(Arc::new(PgRepository::new(module_path!())));
+    let crawled = crawl(&pipeline, today).await;
+    let count = pipeline.finish().await?;
+    crawled?;
+
+    Ok(count)
+}
+
+/// 依序抓取股東會與法說會，每個來源取得的事件立即送往寫入任務
+async fn crawl(pipeline: &Pipeline<CorporateEvent>, today: NaiveDate) -> Result<()> {
+    match twse::shareholder_meeting::visit().await {
+        Ok(list) => send(pipeline, list.iter().filter_map(from_shareholder_meeting)).await?,
+        Err(why) if error::is_budget_exhausted(&why) => return Err(why),
+        Err(why) => {
+            logging::error_file_async(format!(
+                "Failed to visit shareholder meetings because {:?}",
+                why
+            ));
+        }
+    }
+
+    let next_month = today.checked_add_months(Months::new(1)).unwrap_or(today);
+    for month in [today, next_month] {
+        for market in [
+            StockExchangeMarket::Listed,
+            StockExchangeMarket::OverTheCounter,
+        ] {
+            match twse::earnings_call::visit(market, month.year(), month.month()).await {
+                Ok(list) => send(pipeline, list.iter().filter_map(from_earnings_call)).await?,
+                Err(why) if error::is_budget_exhausted(&why) => return Err(why),
+                Err(why) => {
+                    logging::error_file_async(format!(
+                        "Failed to visit {} earnings calls of {}/{} because {:?}",
+                        market,
+                        month.year(),
+                        month.month(),
+                        why
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// 只將資料庫中有的股票的事件送往寫入任務
+async fn send(
+    pipeline: &Pipeline<CorporateEvent>,
+    events: impl Iterator<Item = CorporateEvent>,
+) -> Result<()> {
+    pipeline
+        .send_all(events.filter(|event| SHARE.stock_contains_key(&event.security_code)))
+        .await
+}
+
+/// 啟動將事件批次寫入 repository 的任務
+pub fn writer(repository: Arc<dyn CorporateEventRepository>) -> Pipeline<CorporateEvent> {
+    Pipeline::spawn(move |events| save(repository.clone(), events))
+}
+
+/// 將一批事件以一個 INSERT 寫入 repository，寫入失敗的只記錄日誌，回傳寫入成功的筆數
+async fn save(
+    repository: Arc<dyn CorporateEventRepository>,
+    events: Vec<CorporateEvent>,
+) -> Result<usize> {
+    match repository.upsert_batch(&events).await {
+        Ok(count) => Ok(count),
+        Err(why) => {
+            logging::error_file_async(format!("{:?}", why));
+            Ok(0)
+        }
+    }
+}
+
+fn from_shareholder_meeting(meeting: &ShareholderMeeting) -> Option<CorporateEvent> {
+    let date = datetime::parse_taiwan_date(&meeting.meeting_date)?;
+    let description = if meeting.kind.is_empty() {
+        "股東會".to_string()
+    } else {
+        format!("股東{}", meeting.kind.trim())
+    };
+
+    Some(CorporateEvent::new(
+        StockSymbol::parse(&meeting.stock_symbol).ok()?,
+        corporate_event::SHAREHOLDER_MEETING,
+        date,
+        description,
+    ))
+}
+
+fn from_earnings_call(call: &EarningsCall) -> Option<CorporateEvent> {
+    let description = [call.time.as_str(), call.location.as_str()]
+        .into_iter()
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Some(CorporateEvent::new(
+        StockSymbol::parse(&call.stock_symbol).ok()?,
+        corporate_event::EARNINGS_CALL,
+        call.date,
+        description,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::repository::MemoryRepository;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_from_shareholder_meeting() {
+        let meeting = ShareholderMeeting {
+            stock_symbol: "2330 ".to_string(),
+            name: "台積電".to_string(),
+            kind: "常會".to_string(),
+            meeting_date: "1130604".to_string(),
+        };
+
+        let event = from_shareholder_meeting(&meeting).unwrap();
+
+        assert_eq!(event.security_code, "2330");
+        assert_eq!(event.kind, corporate_event::SHAREHOLDER_MEETING);
+        assert_eq!(
+            event.event_date,
+            NaiveDate::from_ymd_opt(2024, 6, 4).unwrap()
+        );
+        assert_eq!(event.description, "股東常會");
+        assert!(from_shareholder_meeting(&ShareholderMeeting::default()).is_none());
+    }
+
+    #[test]
+    fn test_from_earnings_call() {
+        let call = EarningsCall {
+            stock_symbol: "2330".to_string(),
+            name: "台積電".to_string(),
+            date: NaiveDate::from_ymd_opt(2024, 4, 18).unwrap(),
+            time: "14:00".to_string(),
+            location: String::new(),
+        };
+
+        let event = from_earnings_call(&call).unwrap();
+
+        assert_eq!(event.kind_name(), "法說會");
+        assert_eq!(event.description, "14:00");
+    }
+
+    #[tokio::test]
+    async fn test_writer() {
+        let repository = Arc::new(MemoryRepository::default());
+        let date = NaiveDate::from_ymd_opt(2024, 6, 4).unwrap();
+        let meeting = |description: &str| {
+            CorporateEvent::new(
+                "2330".parse().unwrap(),
+                corporate_event::SHAREHOLDER_MEETING,
+                date,
+                description.to_string(),
+            )
+        };
+
+        let pipeline = writer(repository.clone());
+        pipeline
+            .send_all([meeting("股東會"), meeting("股東常會")])
+            .await
+            .unwrap();
+
+        assert_eq!(pipeline.finish().await.unwrap(), 2);
+        let events = repository.corporate_events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events.values().next().unwrap().description, "股東常會");
+    }
+}
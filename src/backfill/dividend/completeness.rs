@@ -0,0 +1,195 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Write,
+    time::Duration,
+};
+
+use anyhow::Result;
+use chrono::{Datelike, Local, NaiveDate};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::{
+    bot,
+    crawler::twse::{self, ex_right_dividend::ExRightDividend},
+    database::table::{
+        dividend::Dividend,
+        dividend_discrepancy::{self, DividendDiscrepancy},
+        stock_ownership_details::StockOwnershipDetail,
+    },
+    declare::StockSymbol,
+    logging,
+};
+
+/// 比對最近幾個年度的股利
+const AUDIT_YEARS: i32 = 10;
+/// 現金股利允許的誤差
+const TOLERANCE: Decimal = dec!(0.01);
+
+/// 以證交所除權除息計算結果表比對庫存股票最近 10 年的股利，缺少年度或現金股利不一致時
+/// 寫入 dividend_discrepancies 並以 Telegram 通知，上櫃股票不在證交所的資料內所以不會被比對
+pub async fn execute() -> Result<()> {
+    let symbols: HashSet<StockSymbol> = StockOwnershipDetail::fetch(None)
+        .await?
+        .into_iter()
+        .map(|detail| detail.security_code)
+        .collect();
+    if symbols.is_empty() {
+        return Ok(());
+    }
+
+    let current_year = Local::now().year();
+    let from_year = current_year - AUDIT_YEARS + 1;
+    let mut events = Vec::with_capacity(1024);
+    for year in from_year..=current_year {
+        let (Some(start), Some(end)) = (
+            NaiveDate::from_ymd_opt(year, 1, 1),
+            NaiveDate::from_ymd_opt(year, 12, 31),
+        ) else {
+            continue;
+        };
+        events.extend(
+            twse::ex_right_dividend::visit(start, end)
+                .await?
+                .into_iter()
+                .filter(|event| symbols.contains(&event.stock_symbol)),
+        );
+        tokio::time::sleep(Duration::from_secs(3)).await;
+    }
+
+    let codes: Vec<StockSymbol> = symbols.into_iter().collect();
+    let dividends: HashMap<(StockSymbol, i32), Decimal> =
+        Dividend::fetch_annual_cash_dividends(&codes, from_year)
+            .await?
+            .into_iter()
+            .map(|(security_code, year, cash)| ((security_code, year), cash))
+            .collect();
+    let discrepancies = compare(&events, &dividends);
+
+    DividendDiscrepancy::replace_all(&discrepancies).await?;
+
+    if discrepancies.is_empty() {
+        logging::info_file_async(format!(
+            "庫存 {} 檔股票近 {} 年的股利與證交所一致",
+            codes.len(),
+            AUDIT_YEARS
+        ));
+        return Ok(());
+    }
+
+    let msg = summary(codes.len(), &discrepancies);
+    logging::warn_file_async(msg.clone());
+    bot::telegram::send(&msg).await;
+
+    Ok(())
+}
+
+/// 依股票與除權息年度彙總證交所的除權息結果後與 dividend 表的全年度現金股利比對
+/// 有權值(配股)的年度無法換算為現金股利，只檢查是否缺少該年度
+fn compare(
+    events: &[ExRightDividend],
+    dividends: &HashMap<(StockSymbol, i32), Decimal>,
+) -> Vec<DividendDiscrepancy> {
+    // (股票代號, 年度) => (息值合計, 除權息次數, 是否都只有除息)
+    let mut groups: BTreeMap<(StockSymbol, i32), (Decimal, usize, bool)> = BTreeMap::new();
+    for event in events {
+        let group = groups
+            .entry((event.stock_symbol.clone(), event.date.year()))
+            .or_insert((Decimal::ZERO, 0, true));
+        group.0 += event.value;
+        group.1 += 1;
+        group.2 &= event.is_cash_only();
+    }
+
+    groups
+        .into_iter()
+        .filter_map(|((security_code, year), (expected, times, cash_only))| {
+            match dividends.get(&(security_code.clone(), year)) {
+                None => Some(DividendDiscrepancy {
+                    detail: format!(
+                        "證交所 {} 年有 {} 次除權息，dividend 表沒有該年度的股利",
+                        year, times
+                    ),
+                    security_code,
+                    year,
+                    kind: dividend_discrepancy::KIND_MISSING.to_string(),
+                    expected,
+                    actual: Decimal::ZERO,
+                }),
+                Some(actual) if cash_only && (expected - actual).abs() > TOLERANCE => {
+                    Some(DividendDiscrepancy {
+                        detail: format!(
+                            "證交所 {} 年息值合計 {}，dividend 表現金股利 {}",
+                            year,
+                            expected.normalize(),
+                            actual.normalize()
+                        ),
+                        security_code,
+                        year,
+                        kind: dividend_discrepancy::KIND_MISMATCH.to_string(),
+                        expected,
+                        actual: *actual,
+                    })
+                }
+                Some(_) => None,
+            }
+        })
+        .collect()
+}
+
+fn summary(held: usize, discrepancies: &[DividendDiscrepancy]) -> String {
+    let mut msg = format!(
+        "庫存 {} 檔股票近 {} 年的股利與證交所不一致 {} 筆",
+        held,
+        AUDIT_YEARS,
+        discrepancies.len()
+    );
+    for item in discrepancies {
+        let _ = write!(&mut msg, "\r\n{} {}", item.security_code, item.detail);
+    }
+
+    msg
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn event(symbol: &str, date: (i32, u32, u32), value: Decimal, kind: &str) -> ExRightDividend {
+        ExRightDividend {
+            date: NaiveDate::from_ymd_opt(date.0, date.1, date.2).unwrap(),
+            stock_symbol: symbol.parse().unwrap(),
+            value,
+            kind: kind.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_compare() {
+        let events = vec![
+            event("2330", (2023, 3, 16), dec!(2.75), "息"),
+            event("2330", (2023, 6, 15), dec!(3), "息"),
+            event("2330", (2024, 3, 14), dec!(3.5), "息"),
+            event("2884", (2023, 7, 20), dec!(1.2), "權息"),
+            event("2884", (2022, 7, 21), dec!(1), "息"),
+        ];
+        let dividends = HashMap::from([
+            (("2330".parse().unwrap(), 2023), dec!(5.75)),
+            (("2330".parse().unwrap(), 2024), dec!(3)),
+            (("2884".parse().unwrap(), 2023), dec!(0.6)),
+        ]);
+
+        let result = compare(&events, &dividends);
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].security_code, "2330");
+        assert_eq!(result[0].year, 2024);
+        assert_eq!(result[0].kind, dividend_discrepancy::KIND_MISMATCH);
+        assert_eq!(result[0].expected, dec!(3.5));
+        assert_eq!(result[0].actual, dec!(3));
+        assert_eq!(result[1].security_code, "2884");
+        assert_eq!(result[1].year, 2022);
+        assert_eq!(result[1].kind, dividend_discrepancy::KIND_MISSING);
+    }
+}
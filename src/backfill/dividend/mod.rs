@@ -2,22 +2,26 @@ use std::{collections::HashSet, time::Duration};
 
 use anyhow::{anyhow, Result};
 use chrono::{Datelike, Local};
-use tokio_retry::{
-    strategy::{jitter, ExponentialBackoff},
-    Retry,
-};
+use tokio_retry::{strategy::jitter, RetryIf};
 
 use crate::{
+    backfill::priority::{CrawlFrequency, CrawlQueue},
     crawler::{goodinfo, yahoo},
-    database::table::{self, dividend},
-    logging, nosql,
-    util::map::Keyable,
+    database::table::{self, audit_log::Audit, dividend, ingestion_batch::IngestionBatch},
+    error, logging, nosql,
+    util::{http::policy::RequestPolicy, map::Keyable},
 };
 
+/// 以證交所除權除息結果比對庫存股票的股利
+pub mod completeness;
 pub mod payout_ratio;
 
+/// Goodinfo 股利在 ingestion_batches 的來源名稱
+const INGESTION_SOURCE: &str = "goodinfo_dividend";
+
 /// 更新股利發送數據
-/// 資料庫內尚未有年度配息數據的股票取出後向第三方查詢後更新回資料庫
+/// 資料庫內尚未有年度配息數據的股票取出後向第三方查詢後更新回資料庫，
+/// 來源當天的請求數已達上限時回傳 Error::BudgetExhausted 讓排程延後執行
 pub async fn execute() -> Result<()> {
     //尚未有股利或多次配息
     let now = Local::now();
@@ -25,6 +29,7 @@ pub async fn execute() -> Result<()> {
     let no_or_multiple_dividend = processing_no_or_multiple(year);
     let yahoo = processing_unannounced_ex_dividend_date(year);
     let (res_no_or_multiple, res_yahoo) = tokio::join!(no_or_multiple_dividend, yahoo);
+    let mut exhausted = None;
 
     match res_no_or_multiple {
         Ok(_) => {
@@ -32,6 +37,7 @@ pub async fn execute() -> Result<()> {
                 "processing_without_or_multiple executed successfully.".to_string(),
             );
         }
+        Err(why) if error::is_budget_exhausted(&why) => exhausted = Some(why),
         Err(why) => {
             logging::error_file_async(format!(
                 "Failed to process_without_or_multiple because {:?}",
@@ -46,12 +52,16 @@ pub async fn execute() -> Result<()> {
                 "processing_with_unannounced_ex_dividend_dates executed successfully.".to_string(),
             );
         }
+        Err(why) if error::is_budget_exhausted(&why) => exhausted = exhausted.or(Some(why)),
         Err(why) => {
             logging::error_file_async(format!("Failed to process_yahoo because {:?}", why));
         }
     }
 
-    Ok(())
+    match exhausted {
+        Some(why) => Err(why),
+        None => Ok(()),
+    }
 }
 
 /// Asynchronously processes the stocks that have no dividends or have issued multiple dividends.
@@ -65,7 +75,8 @@ pub async fn execute() -> Result<()> {
 /// If the upsert operation fails, it logs the error.
 ///
 /// Between each stock symbol processing, the function sleeps for 6 seconds to prevent too many requests to the
-/// `goodinfo::dividend::visit` function.
+/// `goodinfo::dividend::visit` function. The skip key of a stock is only set after its dividends were fetched,
+/// so stocks that were not visited are retried on the next run.
 ///
 /// Returns `Ok(())` if the function finishes processing all stock symbols. If any error occurs during the process,
 /// it returns `Err(e)`, where `e` is the error.
@@ -74,7 +85,7 @@ pub async fn execute() -> Result<()> {
 ///
 /// This function will return an error if:
 /// - It fails to fetch the list of stock symbols.
-/// - It fails to visit the dividend information of a stock symbol.
+/// - The daily crawl budget of goodinfo is exhausted, the remaining stocks are left to the deferred run.
 /// - It fails to upsert a dividend entity.
 async fn processing_no_or_multiple(year: i32) -> Result<()> {
     //年度內尚未有股利配息資料
@@ -92,7 +103,8 @@ async fn processing_no_or_multiple(year: i32) -> Result<()> {
     }
 
     logging::info_file_async(format!("本次殖利率的採集需收集 {} 家", stock_symbols.len()));
-    for stock_symbol in stock_symbols {
+    let frequency = CrawlFrequency::for_source("goodinfo");
+    for (tier, stock_symbol) in CrawlQueue::load(stock_symbols).await? {
         let cache_key = format!("goodinfo:dividend:{}", stock_symbol);
         let is_jump = nosql::redis::CLIENT.get_bool(&cache_key).await?;
 
@@ -100,14 +112,20 @@ async fn processing_no_or_multiple(year: i32) -> Result<()> {
             continue;
         }
 
-        nosql::redis::CLIENT
-            .set(cache_key, true, 60 * 60 * 24 * 3)
-            .await?;
-
-        if let Err(why) =
-            process_stock_dividends(year, &stock_symbol, &multiple_dividend_cache).await
-        {
-            logging::error_file_async(format!("{:?} ", why));
+        match process_stock_dividends(year, &stock_symbol, &multiple_dividend_cache).await {
+            Ok(_) => {
+                nosql::redis::CLIENT
+                    .set(
+                        cache_key,
+                        true,
+                        frequency.interval_secs(tier, 60 * 60 * 24 * 3),
+                    )
+                    .await?;
+            }
+            Err(why) if error::is_budget_exhausted(&why) => return Err(why),
+            Err(why) => {
+                logging::error_file_async(format!("{:?} ", why));
+            }
         }
 
         tokio::time::sleep(Duration::from_secs(120)).await;
@@ -133,24 +151,32 @@ async fn process_stock_dividends(
                 .map(|details| details.iter().cloned())
         })
         .flatten()
+        .filter(|d| {
+            //檢查是否為多次配息，並且已經收錄該筆股利
+            (d.year_of_dividend == year || d.year_of_dividend == last_year)
+                && !multiple_dividend_cache.contains(&d.key())
+        })
         .collect::<Vec<_>>();
 
+    // 與上一次寫入的內容相同時不需要重新寫入
+    let period = format!("{}-{}", stock_symbol, year);
+    let Some(mut batch) =
+        IngestionBatch::begin(INGESTION_SOURCE, &period, &dividend_details_from_goodinfo).await?
+    else {
+        logging::debug_file_async(format!("{} 的股利與上一次寫入的內容相同，略過寫入", period));
+        return Ok(());
+    };
+
+    let total = dividend_details_from_goodinfo.len();
+    let mut failed = 0;
     for dividend_from_goodinfo in dividend_details_from_goodinfo {
-        if dividend_from_goodinfo.year_of_dividend != year
-            && dividend_from_goodinfo.year_of_dividend != last_year
+        let mut entity = table::dividend::Dividend::from(dividend_from_goodinfo);
+        entity.batch_id = batch.batch_id.clone();
+        match entity
+            .upsert()
+            .await
+            .audit("dividend", entity.key(), module_path!())
         {
-            continue;
-        }
-
-        //檢查是否為多次配息，並且已經收錄該筆股利
-        let key = dividend_from_goodinfo.key();
-
-        if multiple_dividend_cache.contains(&key) {
-            continue;
-        }
-
-        let entity = table::dividend::Dividend::from(dividend_from_goodinfo);
-        match entity.upsert().await {
             Ok(_) => {
                 logging::debug_file_async(format!(
                     "dividend upsert executed successfully. \r\n{:#?}",
@@ -165,11 +191,17 @@ async fn process_stock_dividends(
                 }
             }
             Err(why) => {
+                failed += 1;
                 logging::error_file_async(format!("{:?} ", why));
             }
         }
     }
 
+    // 有寫入失敗時不記錄批次，下次執行會整批重新寫入
+    if failed == 0 {
+        batch.complete(total).await?;
+    }
+
     Ok(())
 }
 
@@ -189,6 +221,10 @@ async fn processing_unannounced_ex_dividend_date(year: i32) -> Result<()> {
 
     for dividend in dividends {
         if let Err(why) = processing_unannounced_ex_dividend_date_from_yahoo(dividend, year).await {
+            if error::is_budget_exhausted(&why) {
+                return Err(why);
+            }
+
             logging::error_file_async(format!(
                 "Failed to fetch_dividend_from_yahoo because {:?}",
                 why
@@ -218,19 +254,20 @@ async fn processing_unannounced_ex_dividend_date_from_yahoo(
     mut entity: dividend::Dividend,
     year: i32,
 ) -> Result<()> {
-    let strategy = ExponentialBackoff::from_millis(100)
-        .map(jitter) // add jitter to delays
-        .take(5); // limit to 5 retries
-    let retry_future = Retry::spawn(strategy, || yahoo::dividend::visit(&entity.security_code));
-    let yahoo = match retry_future.await {
-        Ok(yahoo_dividend) => yahoo_dividend,
-        Err(why) => {
-            return Err(anyhow!("{}", why));
-        }
-    };
+    let policy = RequestPolicy::for_source("yahoo");
+    let strategy = (1..=policy.retries).map(|attempt| jitter(policy.backoff(attempt)));
+    // 請求數已達上限時重試也不會成功，直接回傳讓呼叫端中斷
+    let retry_future = RetryIf::start(
+        strategy,
+        || yahoo::dividend::visit(&entity.security_code),
+        |why: &anyhow::Error| !error::is_budget_exhausted(why),
+    );
+    let yahoo = retry_future.await?;
 
     // 取得今年度的股利數據
     if let Some(yahoo_dividend_details) = yahoo.dividend.get(&year) {
+        upsert_special_dividends(yahoo_dividend_details).await;
+
         let yahoo_dividend_detail = yahoo_dividend_details.iter().find(|detail| {
             detail.year_of_dividend == entity.year_of_dividend
                 && detail.quarter == entity.quarter
@@ -246,7 +283,12 @@ async fn processing_unannounced_ex_dividend_date_from_yahoo(
             entity.payable_date1 = yahoo_dividend_detail.payable_date1.to_string();
             entity.payable_date2 = yahoo_dividend_detail.payable_date2.to_string();
 
-            if let Err(why) = entity.update_dividend_date().await {
+            if let Err(why) =
+                entity
+                    .update_dividend_date()
+                    .await
+                    .audit("dividend", entity.key(), module_path!())
+            {
                 return Err(anyhow!("{}", why));
             }
 
@@ -260,6 +302,32 @@ async fn processing_unannounced_ex_dividend_date_from_yahoo(
     Ok(())
 }
 
+/// goodinfo 沒有特別股利，以雅虎的數據寫入季度為 S 的股利
+/// 季配、半年配的股票需重算股利年度的合計，全年度配發的股票則保留原本的全年度數據
+async fn upsert_special_dividends(details: &[yahoo::dividend::YahooDividendDetail]) {
+    let is_periodic = details
+        .iter()
+        .any(|detail| !detail.quarter.is_empty() && !detail.is_special());
+
+    for detail in details.iter().filter(|detail| detail.is_special()) {
+        let entity = dividend::Dividend::from(detail.clone());
+        if let Err(why) = entity
+            .upsert()
+            .await
+            .audit("dividend", entity.key(), module_path!())
+        {
+            logging::error_file_async(format!("{:?} ", why));
+            continue;
+        }
+
+        if is_periodic {
+            if let Err(why) = entity.upsert_annual_total_dividend().await {
+                logging::error_file_async(format!("{:?} ", why));
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::cache::SHARE;
@@ -3,13 +3,18 @@ use std::{collections::HashSet, time::Duration};
 use anyhow::Result;
 
 use crate::{
+    backfill::priority::{CrawlFrequency, CrawlQueue},
     crawler::goodinfo,
-    database::{table, table::stock},
+    database::{
+        table,
+        table::{audit_log::Audit, stock},
+    },
     logging, nosql,
     util::map::{vec_to_hashmap, Keyable},
 };
 
-/// 將股息中盈餘分配率為零的數據向第三方取得數據後更新更新
+/// 將股息中盈餘分配率為零的數據向第三方取得數據後更新更新，
+/// 來源當天的請求數已達上限時回傳 Error::BudgetExhausted 讓排程延後執行
 pub async fn execute() -> Result<()> {
     let without_payout_ratio =
         table::dividend::extension::payout_ratio_info::fetch_without_payout_ratio().await?;
@@ -21,7 +26,8 @@ pub async fn execute() -> Result<()> {
 
     let mut dividend_without_payout_ratio = vec_to_hashmap(without_payout_ratio);
 
-    for security_code in unique_security_code {
+    let frequency = CrawlFrequency::for_source("goodinfo");
+    for (tier, security_code) in CrawlQueue::load(unique_security_code).await? {
         if stock::is_preference_shares(&security_code) {
             continue;
         }
@@ -32,11 +38,17 @@ pub async fn execute() -> Result<()> {
             continue;
         }
 
+        let dividends_from_goodinfo = goodinfo::dividend::visit(&security_code).await?;
+
+        // 抓取成功後才略過，未抓到的股票在下次執行時會再抓一次
         nosql::redis::CLIENT
-            .set(cache_key, true, 60 * 60 * 24 * 7)
+            .set(
+                cache_key,
+                true,
+                frequency.interval_secs(tier, 60 * 60 * 24 * 7),
+            )
             .await?;
 
-        let dividends_from_goodinfo = goodinfo::dividend::visit(&security_code).await?;
         for gds in dividends_from_goodinfo.values() {
             for gd in gds {
                 let key = gd.key();
@@ -45,7 +57,11 @@ pub async fn execute() -> Result<()> {
                     pri.payout_ratio_stock = gd.payout_ratio_stock;
                     pri.payout_ratio_cash = gd.payout_ratio_cash;
 
-                    if let Err(why) = pri.update().await {
+                    if let Err(why) =
+                        pri.update()
+                            .await
+                            .audit("dividend", key.clone(), module_path!())
+                    {
                         logging::error_file_async(format!("{} {:?}", key, why));
                     }
                 }
@@ -1,13 +1,17 @@
+use std::sync::Arc;
+
 use anyhow::Result;
 use chrono::Local;
-use futures::future;
 
 use crate::{
-    backfill::financial_statement::update_roe_and_roa_for_zero_values,
-    crawler::wespai,
-    database::table::{financial_statement, stock},
+    backfill::{financial_statement::update_roe_and_roa_for_zero_values, pipeline::Pipeline},
+    crawler::wespai::{self, profit::Profit},
+    database::{
+        repository::{FinancialStatementRepository, PgRepository},
+        table::{financial_statement::FinancialStatement, stock},
+    },
     logging, nosql,
-    util::{self, datetime::Weekend},
+    util::{self, datetime::Weekend, map::Keyable},
 };
 
 /// 更新台股年報
@@ -28,32 +32,7 @@ pub async fn execute() -> Result<()> {
         return Ok(());
     }
 
-    let annual = financial_statement::fetch_annual(profits[0].year).await?;
-    let exist_fs = util::map::vec_to_hashmap(annual);
-    let upsert_futures: Vec<_> = profits
-        .into_iter()
-        .filter(|profit| !stock::is_preference_shares(&profit.security_code))
-        .filter(|profit| {
-            let key = format!(
-                "{}-{}-{}",
-                profit.security_code, profit.year, profit.quarter
-            );
-            !exist_fs.contains_key(&key)
-        })
-        .map(|profit| {
-            let fs = financial_statement::FinancialStatement::from(profit);
-            fs.upsert()
-        })
-        .collect();
-    let results = future::join_all(upsert_futures).await;
-    for result in results {
-        if let Err(why) = result {
-            logging::error_file_async(format!(
-                "Failed to FinancialStatement.upsert because {:?}",
-                why
-            ));
-        }
-    }
+    save(Arc::new(PgRepository::new(module_path!())), profits).await?;
 
     update_roe_and_roa_for_zero_values(None).await?;
 
@@ -64,9 +43,57 @@ pub async fn execute() -> Result<()> {
     Ok(())
 }
 
+/// 將 repository 內還沒有的年報送往寫入任務，特別股不寫入，回傳要寫入的筆數
+pub async fn save(
+    repository: Arc<dyn FinancialStatementRepository>,
+    profits: Vec<Profit>,
+) -> Result<usize> {
+    let Some(year) = profits.first().map(|profit| profit.year) else {
+        return Ok(0);
+    };
+
+    let exist_fs = util::map::vec_to_hashmap(repository.fetch_annual(year).await?);
+    let pipeline = writer(repository);
+    let sent = pipeline
+        .send_all(
+            profits
+                .into_iter()
+                .filter(|profit| !stock::is_preference_shares(&profit.security_code))
+                .map(FinancialStatement::from)
+                .filter(|fs| !exist_fs.contains_key(&fs.key())),
+        )
+        .await;
+    let count = pipeline.finish().await?;
+    sent?;
+
+    Ok(count)
+}
+
+/// 啟動將年報批次寫入 repository 的任務
+pub fn writer(repository: Arc<dyn FinancialStatementRepository>) -> Pipeline<FinancialStatement> {
+    Pipeline::spawn(move |statements| upsert(repository.clone(), statements))
+}
+
+/// 將一批年報以一個 INSERT 寫入 repository，寫入失敗的只記錄日誌，回傳寫入成功的筆數
+async fn upsert(
+    repository: Arc<dyn FinancialStatementRepository>,
+    statements: Vec<FinancialStatement>,
+) -> Result<usize> {
+    match repository.upsert_batch(&statements).await {
+        Ok(count) => Ok(count),
+        Err(why) => {
+            logging::error_file_async(format!(
+                "Failed to FinancialStatement.upsert_batch because {:?}",
+                why
+            ));
+            Ok(0)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{cache::SHARE, logging};
+    use crate::{cache::SHARE, database::repository::MemoryRepository, logging};
 
     use super::*;
 
@@ -86,4 +113,26 @@ mod tests {
 
         logging::debug_file_async("結束 execute".to_string());
     }
+
+    #[tokio::test]
+    async fn test_save() {
+        let repository = Arc::new(MemoryRepository::default());
+        let existing = FinancialStatement::from(Profit::new(2023, "2330".parse().unwrap()));
+        FinancialStatementRepository::upsert_batch(repository.as_ref(), &[existing])
+            .await
+            .unwrap();
+
+        let profits = ["2330", "2317", "2881A"]
+            .into_iter()
+            .map(|code| Profit::new(2023, code.parse().unwrap()))
+            .collect();
+
+        assert_eq!(save(repository.clone(), profits).await.unwrap(), 1);
+        assert_eq!(save(repository.clone(), Vec::new()).await.unwrap(), 0);
+        let statements = repository.financial_statements.lock().unwrap();
+        assert_eq!(
+            statements.keys().collect::<Vec<_>>(),
+            vec!["2317-2023-", "2330-2023-"]
+        );
+    }
 }
@@ -0,0 +1,103 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use chrono::{Datelike, Local};
+
+use crate::{
+    cache::SHARE,
+    crawler::twse,
+    database::table::{
+        audit_log::Audit,
+        financial_statement::{self, FinancialStatement},
+    },
+    declare::{Quarter, StockExchangeMarket, StockSymbol},
+    error, logging,
+    util::map::Keyable,
+};
+
+/// 從公開資訊觀測站的現金流量表彙總取回上一季的營業、投資與籌資現金流量，寫入 financial_statement 表
+pub async fn execute() -> Result<()> {
+    let now = Local::now();
+    let quarter = Quarter::from_month(now.month())
+        .unwrap_or(Quarter::Q1)
+        .previous();
+    let year = if quarter == Quarter::Q4 {
+        now.year() - 1
+    } else {
+        now.year()
+    };
+    let recorded: HashSet<StockSymbol> =
+        financial_statement::fetch_symbols_with_cash_flow(year, quarter)
+            .await?
+            .into_iter()
+            .collect();
+    let mut success_count = 0;
+
+    for market in [
+        StockExchangeMarket::Listed,
+        StockExchangeMarket::OverTheCounter,
+    ] {
+        let cash_flows = match twse::cash_flow::visit(market, year, quarter).await {
+            Ok(cash_flows) => cash_flows,
+            Err(why) if error::is_budget_exhausted(&why) => return Err(why),
+            Err(why) => {
+                logging::error_file_async(format!(
+                    "Failed to twse::cash_flow::visit({}) because {:?}",
+                    market, why
+                ));
+                continue;
+            }
+        };
+
+        for cash_flow in cash_flows {
+            if recorded.contains(&cash_flow.stock_symbol)
+                || !SHARE.stock_contains_key(&cash_flow.stock_symbol)
+            {
+                continue;
+            }
+
+            let fs = FinancialStatement::from(cash_flow);
+            match fs
+                .upsert_cash_flow()
+                .await
+                .audit("financial_statement", fs.key(), module_path!())
+            {
+                Ok(_) => success_count += 1,
+                Err(why) => logging::error_file_async(format!("{:?}", why)),
+            }
+        }
+    }
+
+    if success_count > 0 {
+        logging::info_file_async(format!(
+            "{}年{}現金流量新增 {} 筆",
+            year, quarter, success_count
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logging;
+
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_execute() {
+        dotenv::dotenv().ok();
+        SHARE.load().await;
+        logging::debug_file_async("開始 execute".to_string());
+
+        match execute().await {
+            Ok(_) => {}
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to execute because {:?}", why));
+            }
+        }
+
+        logging::debug_file_async("結束 execute".to_string());
+    }
+}
@@ -8,20 +8,26 @@ use crate::{
         self,
         eps::{EpsQuarter, EpsYear},
     },
-    database::table::financial_statement::{self, FinancialStatement},
-    declare::Quarter,
-    logging,
+    database::table::{
+        audit_log::Audit,
+        financial_statement::{self, FinancialStatement},
+    },
+    declare::{Quarter, StockSymbol},
+    error, logging,
     util::map::Keyable,
 };
 
 /// 更新台股年度財報
 pub mod annual;
+/// 更新台股季度的現金流量
+pub mod cash_flow;
 /// 更新台股季度財報
 pub mod quarter;
 
+/// 從 nstock 取回 ROE、ROA 為零的財報數據，來源當天的請求數已達上限時中斷並回傳 Error::BudgetExhausted
 async fn update_roe_and_roa_for_zero_values(quarter: Option<Quarter>) -> Result<()> {
     let fss = financial_statement::fetch_roe_or_roa_equal_to_zero(None, quarter).await?;
-    let mut stock_symbols: HashSet<String> = HashSet::new();
+    let mut stock_symbols: HashSet<StockSymbol> = HashSet::new();
     let mut ffs_map: HashMap<String, FinancialStatement> = HashMap::with_capacity(fss.len());
 
     for fs in fss {
@@ -38,6 +44,7 @@ async fn update_roe_and_roa_for_zero_values(quarter: Option<Quarter>) -> Result<
                     update_values_for_quarters(eps.quarters, &mut ffs_map).await;
                 }
             }
+            Err(why) if error::is_budget_exhausted(&why) => return Err(why),
             Err(why) => {
                 logging::error_file_async(format!("{:?}", why));
             }
@@ -75,7 +82,11 @@ async fn update_roe_and_roa(fs: &mut FinancialStatement, roe: Decimal, roa: Deci
     fs.return_on_equity = roe;
     fs.return_on_assets = roa;
 
-    if let Err(why) = fs.update_roe_roa().await {
+    if let Err(why) =
+        fs.update_roe_roa()
+            .await
+            .audit("financial_statement", fs.key(), module_path!())
+    {
         logging::error_file_async(format!("{:?}", why));
     }
 }
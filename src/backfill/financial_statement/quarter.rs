@@ -2,11 +2,20 @@ use anyhow::Result;
 use chrono::{Datelike, Local, TimeDelta};
 
 use crate::{
-    backfill::financial_statement::update_roe_and_roa_for_zero_values, calculation, crawler::yahoo,
-    database::table, declare::Quarter, logging, nosql, util::map::Keyable,
+    backfill::financial_statement::update_roe_and_roa_for_zero_values,
+    calculation,
+    crawler::yahoo,
+    database::{
+        table,
+        table::{audit_log::Audit, failed_write::Requeue},
+    },
+    declare::Quarter,
+    error, logging, nosql,
+    util::map::Keyable,
 };
 
-/// 將季度財報 ROE為零的數據，到雅虎財經下載後回寫到 financial_statement 表
+/// 將季度財報 ROE為零的數據，到雅虎財經下載後回寫到 financial_statement 表，
+/// 來源當天的請求數已達上限時，已寫入的數據照常重新計算後回傳 Error::BudgetExhausted 讓排程延後執行
 pub async fn execute() -> Result<()> {
     let now = Local::now();
     let previous_quarter = now - TimeDelta::try_days(130).unwrap();
@@ -19,6 +28,7 @@ pub async fn execute() -> Result<()> {
     )
     .await?;
     let mut success_count = 0;
+    let mut exhausted = None;
 
     for fs in fss {
         let cache_key = fs.key_with_prefix();
@@ -30,6 +40,10 @@ pub async fn execute() -> Result<()> {
 
         let profile = match yahoo::profile::visit(&fs.security_code).await {
             Ok(profile) => profile,
+            Err(why) if error::is_budget_exhausted(&why) => {
+                exhausted = Some(why);
+                break;
+            }
             Err(why) => {
                 logging::error_file_async(format!(
                     "Failed to yahoo::profile::visit because {:?}",
@@ -49,7 +63,13 @@ pub async fn execute() -> Result<()> {
 
         let fs = table::financial_statement::FinancialStatement::from(profile);
 
-        if let Err(why) = fs.clone().upsert().await {
+        if let Err(why) = fs
+            .clone()
+            .upsert()
+            .await
+            .requeue("financial_statement", fs.key(), &fs)
+            .audit("financial_statement", fs.key(), module_path!())
+        {
             logging::error_file_async(format!("{:?}", why));
             continue;
         }
@@ -66,12 +86,20 @@ pub async fn execute() -> Result<()> {
         success_count += 1;
     }
 
-    if let Err(why) = update_roe_and_roa_for_zero_values(Some(previous_quarter)).await {
-        logging::error_file_async(format!("{:#?}", why));
+    match update_roe_and_roa_for_zero_values(Some(previous_quarter)).await {
+        Ok(_) => {}
+        Err(why) if error::is_budget_exhausted(&why) => exhausted = exhausted.or(Some(why)),
+        Err(why) => logging::error_file_async(format!("{:#?}", why)),
     }
 
     if success_count > 0 {
         table::stock::Stock::update_eps_and_roe().await?;
+        if let Err(why) =
+            table::net_asset_value_history::NetAssetValueHistory::upsert_from_financial_statement()
+                .await
+        {
+            logging::error_file_async(format!("{:?}", why));
+        }
         let estimate_date_config =
             table::config::Config::new("estimate-date".to_string(), "".to_string());
         let date = estimate_date_config.get_val_naive_date().await?;
@@ -80,7 +108,10 @@ pub async fn execute() -> Result<()> {
         logging::info_file_async("季度財報更新重新計算便宜、合理、昂貴價的估算結束".to_string());
     }
 
-    Ok(())
+    match exhausted {
+        Some(why) => Err(why),
+        None => Ok(()),
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,390 @@
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::{
+    backfill::{pipeline::Pipeline, registry::Crawler},
+    cache::SHARE,
+    crawler::twse,
+    database::{
+        repository::{InsiderShareholdingRepository, PgRepository, StockOwnershipDetailRepository},
+        table::insider_shareholding::InsiderShareholding,
+    },
+    declare::StockSymbol,
+    logging, util,
+};
+
+/// 董監事持股較上月減少超過此百分比時列入庫存月報
+const LARGE_DECREASE_PERCENT: Decimal = dec!(5);
+
+/// 董監事持股大幅減少的股票
+#[derive(Debug, PartialEq)]
+pub struct Decrease {
+    pub security_code: StockSymbol,
+    pub previous_shares: i64,
+    pub current_shares: i64,
+    /// 減少的百分比
+    pub percent: Decimal,
+}
+
+/// 05:00 更新董監事持股，新月份的數據標記庫存股票董監事持股大幅減少
+pub struct InsiderShareholdingCrawler;
+
+#[async_trait]
+impl Crawler for InsiderShareholdingCrawler {
+    fn name(&self) -> &'static str {
+        "insider_shareholding"
+    }
+
+    fn schedule(&self) -> &'static str {
+        "0 0 21 * * *"
+    }
+
+    async fn execute(&self) -> Result<()> {
+        execute().await
+    }
+}
+
+/// 更新董監事持股與設質比率，新月份的數據寫入後標記庫存股票中董監事持股大幅減少的公司，
+/// 標記的公司列入 event::taiwan_stock::portfolio_summary 的月報
+pub async fn execute() -> Result<()> {
+    let rows = twse::insider_shareholding::visit().await?;
+    let mut issued_shares = HashMap::new();
+    for row in &rows {
+        if let Some(stock) = SHARE.get_stock(row.stock_symbol.trim()).await {
+            issued_shares.insert(stock.stock_symbol, stock.issued_share);
+        }
+    }
+
+    let current = aggregate(rows, &issued_shares);
+
+    save(Arc::new(PgRepository::new(module_path!())), current).await
+}
+
+/// 將彙總後的董監事持股送往寫入任務，新月份的數據寫入後標記庫存股票中董監事持股大幅減少的公司
+pub async fn save<R>(repository: Arc<R>, current: Vec<InsiderShareholding>) -> Result<()>
+where
+    R: InsiderShareholdingRepository + StockOwnershipDetailRepository + 'static,
+{
+    let Some(first) = current.first() else {
+        return Ok(());
+    };
+    let (year, month) = (first.year, first.month);
+    let is_new_month = !repository.exists(year, month).await?;
+
+    let pipeline = writer(repository.clone());
+    let sent = pipeline.send_all(current.iter().cloned()).await;
+    pipeline.finish().await?;
+    sent?;
+
+    if !is_new_month {
+        return Ok(());
+    }
+
+    let (previous_year, previous_month) = if month == 1 {
+        (year - 1, 12)
+    } else {
+        (year, month - 1)
+    };
+    let previous = repository
+        .fetch_by_month(previous_year, previous_month)
+        .await?;
+    let held = repository.fetch_held_symbols().await?;
+    let decreases = detect_decreases(&current, &previous, LARGE_DECREASE_PERCENT)
+        .into_iter()
+        .filter(|d| held.contains(&d.security_code));
+
+    for d in decreases {
+        let Some(item) = current.iter().find(|c| c.security_code == d.security_code) else {
+            continue;
+        };
+
+        if let Err(why) = repository
+            .flag_large_decrease(item, d.previous_shares, d.percent)
+            .await
+        {
+            logging::error_file_async(format!("{:?}", why));
+        }
+    }
+
+    Ok(())
+}
+
+/// 啟動將董監事持股批次寫入 repository 的任務
+pub fn writer(repository: Arc<dyn InsiderShareholdingRepository>) -> Pipeline<InsiderShareholding> {
+    Pipeline::spawn(move |shareholdings| upsert(repository.clone(), shareholdings))
+}
+
+/// 將一批董監事持股以一個 INSERT 寫入 repository，寫入失敗的只記錄日誌，回傳寫入成功的筆數
+async fn upsert(
+    repository: Arc<dyn InsiderShareholdingRepository>,
+    shareholdings: Vec<InsiderShareholding>,
+) -> Result<usize> {
+    match repository.upsert_batch(&shareholdings).await {
+        Ok(count) => Ok(count),
+        Err(why) => {
+            logging::error_file_async(format!("{:?}", why));
+            Ok(0)
+        }
+    }
+}
+
+/// 將每位董監事的明細彙總成每家公司一筆，計算持股比率與設質比率
+fn aggregate(
+    rows: Vec<twse::insider_shareholding::InsiderShareholding>,
+    issued_shares: &HashMap<StockSymbol, i64>,
+) -> Vec<InsiderShareholding> {
+    let mut companies: HashMap<StockSymbol, InsiderShareholding> = HashMap::new();
+    let hundred = dec!(100);
+
+    for row in rows {
+        let Some((year, month)) = parse_year_month(&row.year_month) else {
+            continue;
+        };
+        let Ok(security_code) = StockSymbol::parse(&row.stock_symbol) else {
+            continue;
+        };
+        let held = util::text::parse_i64(&row.shares_held, None).unwrap_or(0);
+        let pledged = util::text::parse_i64(&row.shares_pledged, None).unwrap_or(0);
+        let company =
+            companies
+                .entry(security_code.clone())
+                .or_insert_with(|| InsiderShareholding {
+                    security_code,
+                    year,
+                    month,
+                    ..Default::default()
+                });
+
+        company.shares_held += held;
+        company.shares_pledged += pledged;
+    }
+
+    let mut result: Vec<InsiderShareholding> = companies
+        .into_values()
+        .map(|mut company| {
+            if company.shares_held > 0 {
+                company.pledge_ratio = Decimal::from(company.shares_pledged)
+                    / Decimal::from(company.shares_held)
+                    * hundred;
+            }
+
+            if let Some(issued) = issued_shares
+                .get(&company.security_code)
+                .filter(|i| **i > 0)
+            {
+                company.holding_ratio =
+                    Decimal::from(company.shares_held) / Decimal::from(*issued) * hundred;
+            }
+
+            company.pledge_ratio = company.pledge_ratio.round_dp(4);
+            company.holding_ratio = company.holding_ratio.round_dp(4);
+            company
+        })
+        .collect();
+    result.sort_by(|a, b| a.security_code.cmp(&b.security_code));
+
+    result
+}
+
+/// 民國年月 ex. 11301 => (2024, 1)
+fn parse_year_month(year_month: &str) -> Option<(i32, i32)> {
+    let year_month = year_month.trim();
+    if year_month.len() < 4 {
+        return None;
+    }
+
+    let (year, month) = year_month.split_at(year_month.len() - 2);
+    let year = util::datetime::roc_year_to_gregorian_year(year.parse().ok()?);
+    let month = month.parse::<i32>().ok().filter(|m| (1..=12).contains(m))?;
+
+    Some((year, month))
+}
+
+/// 找出董監事持股較前一期減少超過 threshold(%) 的股票
+fn detect_decreases(
+    current: &[InsiderShareholding],
+    previous: &[InsiderShareholding],
+    threshold: Decimal,
+) -> Vec<Decrease> {
+    let previous: HashMap<&str, i64> = previous
+        .iter()
+        .map(|p| (p.security_code.as_str(), p.shares_held))
+        .collect();
+
+    current
+        .iter()
+        .filter_map(|c| {
+            let previous_shares = *previous.get(c.security_code.as_str())?;
+            if previous_shares <= 0 || c.shares_held >= previous_shares {
+                return None;
+            }
+
+            let percent = Decimal::from(previous_shares - c.shares_held)
+                / Decimal::from(previous_shares)
+                * dec!(100);
+            (percent >= threshold).then(|| Decrease {
+                security_code: c.security_code.clone(),
+                previous_shares,
+                current_shares: c.shares_held,
+                percent,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::{database::repository::MemoryRepository, logging};
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn row(
+        symbol: &str,
+        held: &str,
+        pledged: &str,
+    ) -> twse::insider_shareholding::InsiderShareholding {
+        twse::insider_shareholding::InsiderShareholding {
+            year_month: "11301".to_string(),
+            stock_symbol: symbol.to_string(),
+            shares_held: held.to_string(),
+            shares_pledged: pledged.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn holding(symbol: &str, shares_held: i64) -> InsiderShareholding {
+        InsiderShareholding {
+            security_code: symbol.parse().unwrap(),
+            shares_held,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let rows = vec![
+            row("1101", "1,000,000", "500,000"),
+            row("1101", "3,000,000", "0"),
+            row("2330", "0", "0"),
+        ];
+        let issued = HashMap::from([("1101".parse().unwrap(), 40_000_000)]);
+
+        let list = aggregate(rows, &issued);
+
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].security_code, "1101");
+        assert_eq!((list[0].year, list[0].month), (2024, 1));
+        assert_eq!(list[0].shares_held, 4_000_000);
+        assert_eq!(list[0].shares_pledged, 500_000);
+        assert_eq!(list[0].pledge_ratio, dec!(12.5));
+        assert_eq!(list[0].holding_ratio, dec!(10));
+        assert_eq!(list[1].pledge_ratio, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_parse_year_month() {
+        assert_eq!(parse_year_month("11301"), Some((2024, 1)));
+        assert_eq!(parse_year_month("9912"), Some((2010, 12)));
+        assert_eq!(parse_year_month("11313"), None);
+        assert_eq!(parse_year_month("1"), None);
+    }
+
+    #[test]
+    fn test_detect_decreases() {
+        let previous = vec![
+            holding("1101", 1000),
+            holding("2330", 1000),
+            holding("2317", 1000),
+        ];
+        let current = vec![
+            holding("1101", 900),
+            holding("2330", 980),
+            holding("2317", 1100),
+            holding("2454", 10),
+        ];
+
+        let decreases = detect_decreases(&current, &previous, LARGE_DECREASE_PERCENT);
+
+        assert_eq!(
+            decreases,
+            vec![Decrease {
+                security_code: "1101".parse().unwrap(),
+                previous_shares: 1000,
+                current_shares: 900,
+                percent: dec!(10),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_save() {
+        let repository = Arc::new(MemoryRepository::default());
+        repository
+            .held_symbols
+            .lock()
+            .unwrap()
+            .extend(["1101".parse().unwrap(), "2330".parse().unwrap()]);
+        for previous in [
+            holding("1101", 1000),
+            holding("2330", 1000),
+            holding("2317", 1000),
+        ] {
+            let previous = InsiderShareholding {
+                month: 12,
+                year: 2023,
+                ..previous
+            };
+            InsiderShareholdingRepository::upsert_batch(repository.as_ref(), &[previous])
+                .await
+                .unwrap();
+        }
+
+        // 2317 不在庫存中，2330 減少的幅度未超過 5%
+        let current: Vec<InsiderShareholding> = [
+            holding("1101", 900),
+            holding("2330", 980),
+            holding("2317", 500),
+        ]
+        .into_iter()
+        .map(|current| InsiderShareholding {
+            year: 2024,
+            month: 1,
+            ..current
+        })
+        .collect();
+        save(repository.clone(), current.clone()).await.unwrap();
+
+        assert_eq!(
+            *repository.large_decreases.lock().unwrap(),
+            BTreeMap::from([(("1101".parse().unwrap(), 2024, 1), (1000, dec!(10)))])
+        );
+        assert_eq!(repository.insider_shareholdings.lock().unwrap().len(), 6);
+
+        // 已有該月的數據時只更新，不再標記
+        repository.large_decreases.lock().unwrap().clear();
+        save(repository.clone(), current).await.unwrap();
+        assert!(repository.large_decreases.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_execute() {
+        dotenv::dotenv().ok();
+        SHARE.load().await;
+        logging::debug_file_async("開始 execute".to_string());
+
+        match execute().await {
+            Ok(_) => logging::debug_file_async("execute executed successfully.".to_string()),
+            Err(why) => logging::debug_file_async(format!("Failed to execute because {:?}", why)),
+        }
+
+        logging::debug_file_async("結束 execute".to_string());
+    }
+}
@@ -1,14 +1,40 @@
 use std::fmt::Write;
 
 use anyhow::{anyhow, Result};
+use async_trait::async_trait;
 use chrono::Local;
 use rust_decimal::prelude::ToPrimitive;
 
 use crate::{
-    bot, cache::SHARE, crawler::twse, database::table, declare::StockExchangeMarket, logging, rpc,
-    rpc::stock, util::datetime::Weekend,
+    backfill::registry::Crawler,
+    bot::{self, telegram::fmt},
+    cache::SHARE,
+    crawler::twse,
+    database::{table, table::audit_log::Audit},
+    declare::{SecurityType, StockExchangeMarket},
+    logging, rpc,
+    rpc::stock,
+    util::datetime::Weekend,
 };
 
+/// 05:00 更新台股國際證券識別碼
+pub struct IsinCrawler;
+
+#[async_trait]
+impl Crawler for IsinCrawler {
+    fn name(&self) -> &'static str {
+        "isin"
+    }
+
+    fn schedule(&self) -> &'static str {
+        "0 0 21 * * *"
+    }
+
+    async fn execute(&self) -> Result<()> {
+        execute().await
+    }
+}
+
 /// 更新資料庫新上市股票的或更新其交易所的市場編號、股票的產業分類、名稱等欄位
 pub async fn execute() -> Result<()> {
     if Local::now().is_weekend() {
@@ -33,48 +59,65 @@ async fn process_market(mode: StockExchangeMarket) -> Result<()> {
     let result = twse::international_securities_identification_number::visit(mode).await?;
     let mut to_bot_msg = String::with_capacity(1024);
     for item in result {
-        let new_stock = match SHARE.get_stock(&item.stock_symbol).await {
+        let (new_stock, reclassified) = match SHARE.get_stock(&item.stock_symbol).await {
             Some(stock_db)
             if stock_db.stock_industry_id != item.industry_id
                 || stock_db.stock_exchange_market_id
                 != item.exchange_market.stock_exchange_market_id
                 || stock_db.name != item.name =>
                 {
-                    true
+                    (true, false)
                 }
-            None => true,
-            _ => false,
+            Some(stock_db) => (false, stock_db.security_type != item.security_type.serial()),
+            None => (true, false),
         };
 
-        if new_stock {
-            if let Err(why) = update_stock_info(&item, &mut to_bot_msg).await {
-                logging::error_file_async(format!(
-                    "Failed to update stock info for {} because {:?}",
-                    item.stock_symbol, why
-                ));
-            }
+        // 權證數量龐大且到期即下市，只寫入資料庫不發送通知；證券類別異動也只需更新欄位
+        let result = if new_stock && item.security_type != SecurityType::Warrant {
+            update_stock_info(&item, &mut to_bot_msg).await
+        } else if new_stock || reclassified {
+            save_stock(&item).await.map(|_| ())
+        } else {
+            continue;
+        };
+
+        if let Err(why) = result {
+            logging::error_file_async(format!(
+                "Failed to update stock info for {} because {:?}",
+                item.stock_symbol, why
+            ));
         }
     }
 
-    bot::telegram::send(&to_bot_msg).await;
+    bot::telegram::send_deferrable(&to_bot_msg).await;
 
     Ok(())
 }
 
-async fn update_stock_info(
+/// 寫入資料庫並更新快取
+async fn save_stock(
     stock: &twse::international_securities_identification_number::InternationalSecuritiesIdentificationNumber,
-    msg: &mut String,
-) -> Result<()> {
+) -> Result<table::stock::Stock> {
     let stock = table::stock::Stock::from(stock.clone());
     stock
         .upsert()
         .await
+        .audit("stocks", stock.stock_symbol.to_string(), module_path!())
         .map_err(|why| anyhow!("Failed to stock.upsert() because {:?}", why))?;
 
     if let Ok(mut stocks) = SHARE.stocks.write() {
         stocks.insert(stock.stock_symbol.to_string(), stock.clone());
     }
 
+    Ok(stock)
+}
+
+async fn update_stock_info(
+    stock: &twse::international_securities_identification_number::InternationalSecuritiesIdentificationNumber,
+    msg: &mut String,
+) -> Result<()> {
+    let stock = save_stock(stock).await?;
+
     let market = StockExchangeMarket::from(stock.stock_exchange_market_id);
     let market_name = match market {
         None => " - ",
@@ -84,7 +127,7 @@ async fn update_stock_info(
     let log_msg = format!(
         "新增股票︰ {stock_symbol} {stock_name} {market_name} {industry_name}",
         stock_symbol = stock.stock_symbol,
-        stock_name = stock.name,
+        stock_name = fmt::escape_markdown(&stock.name),
         market_name = market_name,
         industry_name = industry_name
     );
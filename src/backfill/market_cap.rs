@@ -0,0 +1,65 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Local;
+
+use crate::{
+    backfill::registry::Crawler, database::table::stock::extension::market_cap::SymbolAndMarketCap,
+    logging, util::datetime::Weekend,
+};
+
+/// 23:00 依外資持股統計更新後的發行股數計算個股市值
+pub struct MarketCapCrawler;
+
+#[async_trait]
+impl Crawler for MarketCapCrawler {
+    fn name(&self) -> &'static str {
+        "market_cap"
+    }
+
+    fn schedule(&self) -> &'static str {
+        "0 0 15 * * *"
+    }
+
+    async fn execute(&self) -> Result<()> {
+        execute().await
+    }
+}
+
+/// 以外資持股統計更新後的發行股數與最近收盤價，重新計算個股市值
+pub async fn execute() -> Result<()> {
+    if Local::now().is_weekend() {
+        return Ok(());
+    }
+
+    let result = SymbolAndMarketCap::refresh().await?;
+    logging::info_file_async(format!("更新個股市值:{}", result.rows_affected()));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cache::SHARE, logging};
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_execute() {
+        dotenv::dotenv().ok();
+        SHARE.load().await;
+        logging::debug_file_async("開始 execute".to_string());
+
+        match execute().await {
+            Ok(_) => {
+                logging::debug_file_async("成功執行 execute".to_string());
+            }
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to execute because {:?}", why));
+            }
+        }
+
+        logging::debug_file_async("結束 execute".to_string());
+    }
+}
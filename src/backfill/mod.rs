@@ -1,20 +1,77 @@
-/// 調用 twse API 更新終止上市公司
-pub mod delisted_company;
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+
+use crate::{calculation, declare::StockSymbol};
+
+/// 調用 twse、公開資訊觀測站取得股東會與法人說明會的日期
+pub mod corporate_event;
 /// 更新股利發送數據
 pub mod dividend;
 /// 回補財報
 pub mod financial_statement;
+/// 調用 twse API 取得董事、監察人持股與設質比率
+pub mod insider_shareholding;
 /// 調用 twse API 取得數據後更新股票相關欄位
 pub mod isin;
+/// 依發行股數與收盤價計算個股市值
+pub mod market_cap;
 /// 回補每股淨值為零的股票更新其數據
 pub mod net_asset_value_per_share;
+/// 調用 twse、tpex API 取得盤後零股交易的成交行情
+pub mod odd_lot_quote;
+/// 爬蟲將數據送入 channel 後由另一個任務批次寫入資料庫
+pub mod pipeline;
+/// 依庫存 > 追踪 > 其餘的順序排定個股的採集
+pub mod priority;
 /// 外資及陸資投資持股統計
 pub mod qualified_foreign_institutional_investor;
 /// 調用 twse、tpex API 取得並更新台股收盤報價
 pub mod quote;
 /// 調用 twse API 取得並更新每月營收
 pub mod revenue;
+/// 自動加入排程與命令列的數據來源
+pub mod registry;
 /// 查詢 taifex 提供個股權值比重
 pub mod stock_weight;
+/// 調用 twse API 更新終止上市公司
+pub mod suspend_listing;
 /// 調用 twse API 取得並更新台股加權指數
 pub mod taiwan_stock_index;
+/// 調用 twse、tpex API 取得個股日本益比、殖利率及股價淨值比
+pub mod valuation;
+
+/// 命令列回補的用法
+const USAGE: &str = "usage: stock_crawler backfill revenue <from_year> <to_year>
+       stock_crawler backfill quote <symbol> <from YYYY-MM-DD> <to YYYY-MM-DD>
+       stock_crawler backfill adjusted_price [symbol]";
+
+/// 依命令列參數執行歷史數據回補 ex. `stock_crawler backfill revenue 2013 2023`
+pub async fn command(args: &[String]) -> Result<()> {
+    match args.first().map(String::as_str) {
+        Some("revenue") => {
+            let (from_year, to_year) = match (args.get(1), args.get(2)) {
+                (Some(from), Some(to)) => (from.parse::<i32>()?, to.parse::<i32>()?),
+                _ => return Err(anyhow!(USAGE)),
+            };
+            revenue::execute_history(from_year, to_year).await
+        }
+        Some("quote") => {
+            let (symbol, from, to) = match (args.get(1), args.get(2), args.get(3)) {
+                (Some(symbol), Some(from), Some(to)) => (
+                    symbol,
+                    NaiveDate::parse_from_str(from, "%Y-%m-%d")?,
+                    NaiveDate::parse_from_str(to, "%Y-%m-%d")?,
+                ),
+                _ => return Err(anyhow!(USAGE)),
+            };
+            quote::execute_history(symbol, from, to).await.map(|_| ())
+        }
+        Some("adjusted_price") => match args.get(1) {
+            Some(symbol) => calculation::adjusted_price::rebuild(&StockSymbol::parse(symbol)?)
+                .await
+                .map(|_| ()),
+            None => calculation::adjusted_price::rebuild_all().await,
+        },
+        _ => Err(anyhow!(USAGE)),
+    }
+}
@@ -1,9 +1,16 @@
 use anyhow::Result;
+use chrono::Local;
 use sqlx::postgres::PgQueryResult;
 
 use crate::{
     cache::SHARE,
-    database::{table, table::stock::extension},
+    database::{
+        table,
+        table::{
+            audit_log::Audit, net_asset_value_history::NetAssetValueHistory, stock::extension,
+        },
+    },
+    logging,
 };
 
 /// 更新興櫃股票的每股淨值
@@ -11,17 +18,29 @@ pub mod emerging;
 /// 將每股淨值為零的股票嚐試從yahoo取得數據後更新
 pub mod zero_value;
 
-/// 更新興櫃股票的每股淨值，資料庫更新後會更新 SHARE.stocks
+/// 更新興櫃股票的每股淨值，資料庫更新後會更新 SHARE.stocks 並記錄於 net_asset_value_histories
 pub async fn update(stock: &table::stock::Stock) -> Result<PgQueryResult> {
     let item = extension::net_asset_value_per_share::SymbolAndNetAssetValuePerShare::from(stock);
-    let result = item.update().await?;
+    let result =
+        item.update()
+            .await
+            .audit("stocks", item.stock_symbol.to_string(), module_path!())?;
 
     if result.rows_affected() > 0 {
         if let Ok(mut stocks_cache) = SHARE.stocks.write() {
-            if let Some(stock_cache) = stocks_cache.get_mut(&stock.stock_symbol) {
+            if let Some(stock_cache) = stocks_cache.get_mut(stock.stock_symbol.as_str()) {
                 stock_cache.net_asset_value_per_share = stock.net_asset_value_per_share;
             }
         }
+
+        let history = NetAssetValueHistory::new(
+            stock.stock_symbol.clone(),
+            Local::now().date_naive(),
+            stock.net_asset_value_per_share,
+        );
+        if let Err(why) = history.upsert().await {
+            logging::error_file_async(format!("{:?}", why));
+        }
     }
 
     Ok(result)
@@ -1,10 +1,12 @@
 use anyhow::Result;
 
 use crate::{
-    backfill::net_asset_value_per_share::update, crawler::yahoo::profile, database::table, logging,
+    backfill::net_asset_value_per_share::update, crawler::yahoo::profile, database::table, error,
+    logging,
 };
 
-/// 將未下市每股淨值為零的股票試著到 yahoo 抓取數據後更新回 stocks表
+/// 將未下市每股淨值為零的股票試著到 yahoo 抓取數據後更新回 stocks表，
+/// 來源當天的請求數已達上限時回傳 Error::BudgetExhausted 讓排程延後執行
 pub async fn execute() -> Result<()> {
     let stocks = table::stock::fetch_net_asset_value_per_share_is_zero().await?;
     for mut stock in stocks {
@@ -14,6 +16,7 @@ pub async fn execute() -> Result<()> {
 
         let yahoo_profile = match profile::visit(&stock.stock_symbol).await {
             Ok(stock_profile) => stock_profile,
+            Err(why) if error::is_budget_exhausted(&why) => return Err(why),
             Err(why) => {
                 logging::error_file_async(format!("Failed to profile::visit because {:?}", why));
                 continue;
@@ -0,0 +1,151 @@
+use std::{future::Future, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{Local, NaiveDate};
+
+use crate::{
+    backfill::{pipeline::Pipeline, registry::Crawler},
+    crawler::{tpex, twse},
+    database::{
+        repository::{OddLotQuoteRepository, PgRepository},
+        table::odd_lot_quote::OddLotQuote,
+    },
+    logging,
+    util::datetime::Weekend,
+};
+
+/// 16:00 盤後零股交易(13:40~14:30)結束後抓取當日的成交行情
+pub struct OddLotQuoteCrawler;
+
+#[async_trait]
+impl Crawler for OddLotQuoteCrawler {
+    fn name(&self) -> &'static str {
+        "odd_lot_quote"
+    }
+
+    fn schedule(&self) -> &'static str {
+        "0 0 8 * * *"
+    }
+
+    async fn execute(&self) -> Result<()> {
+        let now = Local::now();
+        if now.is_weekend() {
+            return Ok(());
+        }
+
+        let count = execute(now.date_naive()).await?;
+        logging::info_file_async(format!("抓取盤後零股成交行情結束:{}", count));
+
+        Ok(())
+    }
+}
+
+/// 調用 twse、tpex API 取得指定日期盤後零股交易的成交行情並寫入資料庫，回傳寫入的筆數
+pub async fn execute(date: NaiveDate) -> Result<usize> {
+    let pipeline = writer(Arc::new(PgRepository::new(module_path!())));
+    let (twse, tpex) = tokio::join!(
+        crawl(&pipeline, "twse", twse::odd_lot::visit(date)),
+        crawl(&pipeline, "tpex", tpex::odd_lot::visit(date))
+    );
+    twse?;
+    tpex?;
+
+    pipeline.finish().await
+}
+
+/// 爬蟲取得的成交行情送往寫入任務，爬蟲失敗時只記錄日誌
+async fn crawl(
+    pipeline: &Pipeline<OddLotQuote>,
+    exchange: &str,
+    visit: impl Future<Output = Result<Vec<OddLotQuote>>>,
+) -> Result<()> {
+    match visit.await {
+        Ok(quotes) => pipeline.send_all(quotes).await,
+        Err(why) => {
+            logging::error_file_async(format!(
+                "Failed to visit {} odd lot quotes because {:?}",
+                exchange, why
+            ));
+            Ok(())
+        }
+    }
+}
+
+/// 啟動將成交行情批次寫入 repository 的任務
+pub fn writer(repository: Arc<dyn OddLotQuoteRepository>) -> Pipeline<OddLotQuote> {
+    Pipeline::spawn(move |quotes| save(repository.clone(), quotes))
+}
+
+/// 將一批成交行情以一個 INSERT 寫入 repository，寫入失敗的只記錄日誌，回傳寫入成功的筆數
+async fn save(
+    repository: Arc<dyn OddLotQuoteRepository>,
+    quotes: Vec<OddLotQuote>,
+) -> Result<usize> {
+    match repository.upsert_batch(&quotes).await {
+        Ok(count) => Ok(count),
+        Err(why) => {
+            logging::error_file_async(format!("{:?}", why));
+            Ok(0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use crate::{cache::SHARE, database::repository::MemoryRepository, logging};
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[tokio::test]
+    async fn test_save() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let quote = |security_code: &str, closing_price| OddLotQuote {
+            date,
+            security_code: security_code.parse().unwrap(),
+            closing_price,
+            ..Default::default()
+        };
+        let repository = Arc::new(MemoryRepository::default());
+        let pipeline = writer(repository.clone());
+
+        pipeline
+            .send_all(vec![
+                quote("2330", dec!(590)),
+                quote("2317", dec!(104)),
+                quote("2330", dec!(590)),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(pipeline.finish().await.unwrap(), 3);
+        let stored = repository.odd_lot_quotes.lock().unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(
+            stored[&(date, "2317".parse().unwrap())].closing_price,
+            dec!(104)
+        );
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_execute() {
+        dotenv::dotenv().ok();
+        SHARE.load().await;
+        logging::debug_file_async("開始 execute".to_string());
+
+        match execute(Local::now().date_naive()).await {
+            Ok(count) => {
+                logging::debug_file_async(format!("成功執行 execute:{}", count));
+            }
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to execute because {:?}", why));
+            }
+        }
+
+        logging::debug_file_async("結束 execute".to_string());
+    }
+}
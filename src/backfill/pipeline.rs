@@ -0,0 +1,129 @@
+use std::future::Future;
+
+use anyhow::{anyhow, Context, Result};
+use tokio::{sync::mpsc, task::JoinHandle};
+
+use crate::limits;
+
+/// 爬蟲與寫入分開執行的管線
+///
+/// 爬蟲將解析後的數據送入有上限的 channel 後繼續抓取，另一個寫入任務每次取出最多 batch_size 筆寫入資料庫，
+/// 資料庫變慢時只會讓 channel 排滿、爬蟲等待，不會讓進行中的 HTTP 請求逾時；
+/// 爬蟲的並行數由 limits.max_crawls 調整，寫入的批次由 limits.write_queue_capacity、limits.write_batch_size 調整
+///
+/// 目前用於零股行情、本益比、歷史報價、股東會與法說會、年報與董監事持股的回補；
+/// 逐檔抓取後要依寫入的結果設定 Redis 略過 key 的回補(股利、季報、現金流量、每股淨值)、
+/// 寫入前要先將全部權值歸零的 stock_weight，以及寫入後立即更新 SHARE 快取並通知的回補，維持抓取後直接寫入
+pub struct Pipeline<T> {
+    sender: mpsc::Sender<T>,
+    writer: JoinHandle<Result<usize>>,
+}
+
+impl<T: Send + 'static> Pipeline<T> {
+    /// 啟動寫入任務，write 寫入一批數據後回傳寫入的筆數，回傳錯誤時寫入任務結束，之後送入的數據會失敗
+    pub fn spawn<W, Fut>(write: W) -> Self
+    where
+        W: FnMut(Vec<T>) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<usize>> + Send,
+    {
+        Self::with_limits(
+            limits::write_queue_capacity(),
+            limits::write_batch_size(),
+            write,
+        )
+    }
+
+    fn with_limits<W, Fut>(capacity: usize, batch_size: usize, mut write: W) -> Self
+    where
+        W: FnMut(Vec<T>) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<usize>> + Send,
+    {
+        let batch_size = batch_size.max(1);
+        let (sender, mut receiver) = mpsc::channel(capacity.max(1));
+        let writer = tokio::spawn(async move {
+            let mut written = 0;
+            let mut batch = Vec::with_capacity(batch_size);
+
+            while receiver.recv_many(&mut batch, batch_size).await > 0 {
+                written += write(std::mem::take(&mut batch)).await?;
+            }
+
+            Ok(written)
+        });
+
+        Pipeline { sender, writer }
+    }
+
+    /// 送入一筆數據，channel 已滿時等待寫入任務取出
+    pub async fn send(&self, item: T) -> Result<()> {
+        self.sender
+            .send(item)
+            .await
+            .map_err(|_| anyhow!("Writer of the pipeline has stopped"))
+    }
+
+    /// 依序送入多筆數據
+    pub async fn send_all(&self, items: impl IntoIterator<Item = T>) -> Result<()> {
+        for item in items {
+            self.send(item).await?;
+        }
+
+        Ok(())
+    }
+
+    /// 不再送入數據，等待寫入任務寫完 channel 內剩餘的數據，回傳寫入的筆數
+    pub async fn finish(self) -> Result<usize> {
+        drop(self.sender);
+
+        self.writer
+            .await
+            .context("Failed to join writer of the pipeline")?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[tokio::test]
+    async fn test_pipeline() {
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let recorded = batches.clone();
+        let pipeline = Pipeline::with_limits(4, 3, move |batch: Vec<i32>| {
+            let recorded = recorded.clone();
+            async move {
+                let len = batch.len();
+                recorded.lock().unwrap().push(batch);
+                Ok(len)
+            }
+        });
+
+        pipeline.send_all(1..=7).await.unwrap();
+
+        assert_eq!(pipeline.finish().await.unwrap(), 7);
+        let batches = batches.lock().unwrap();
+        assert!(batches.iter().all(|batch| batch.len() <= 3));
+        assert_eq!(batches.concat(), (1..=7).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_writer_failed() {
+        let pipeline = Pipeline::with_limits(1, 1, |batch: Vec<i32>| async move {
+            match batch[0] {
+                2 => Err(anyhow!("database is down")),
+                _ => Ok(batch.len()),
+            }
+        });
+
+        assert!(pipeline.send_all(1..=10).await.is_err());
+        assert!(pipeline
+            .finish()
+            .await
+            .unwrap_err()
+            .to_string()
+            .contains("database is down"));
+    }
+}
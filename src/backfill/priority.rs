@@ -0,0 +1,161 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashSet},
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::SETTINGS,
+    database::table::{stock_ownership_details::StockOwnershipDetail, trace::Trace},
+};
+
+/// 股票的採集優先順序，排越前面越先採集
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Tier {
+    /// 庫存中(未賣出)的股票
+    Held,
+    /// trace 表內追踪中的股票
+    Watched,
+    /// 其餘的股票
+    Rest,
+}
+
+/// 各優先順序下同一檔股票兩次採集的間隔時數，設定檔 crawl_priority.<來源> 未設定的欄位使用預設值
+/// ex. `"crawl_priority": { "goodinfo": { "held_hours": 24, "watched_hours": 72 } }`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(default)]
+pub struct CrawlFrequency {
+    /// 0 代表使用採集任務本身的間隔
+    pub held_hours: usize,
+    pub watched_hours: usize,
+    pub rest_hours: usize,
+}
+
+impl CrawlFrequency {
+    /// 取得設定檔內指定來源的採集間隔 ex. `CrawlFrequency::for_source("goodinfo")`
+    pub fn for_source(source: &str) -> Self {
+        SETTINGS
+            .load()
+            .crawl_priority
+            .get(source)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// 指定優先順序的採集間隔秒數，未設定時回傳 default_secs
+    pub fn interval_secs(&self, tier: Tier, default_secs: usize) -> usize {
+        let hours = match tier {
+            Tier::Held => self.held_hours,
+            Tier::Watched => self.watched_hours,
+            Tier::Rest => self.rest_hours,
+        };
+
+        if hours == 0 {
+            default_secs
+        } else {
+            hours * 60 * 60
+        }
+    }
+}
+
+/// 依庫存 > 追踪 > 其餘的順序取出股票代號，同一優先順序內依代號排序
+#[derive(Debug, Default)]
+pub struct CrawlQueue {
+    heap: BinaryHeap<Reverse<(Tier, String)>>,
+}
+
+impl CrawlQueue {
+    /// 從資料庫取得庫存與追踪中的股票後建立佇列
+    pub async fn load<I>(symbols: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let held: HashSet<String> = StockOwnershipDetail::fetch(None)
+            .await?
+            .into_iter()
+            .map(|detail| detail.security_code.to_string())
+            .collect();
+        let watched: HashSet<String> = Trace::fetch()
+            .await?
+            .into_iter()
+            .map(|trace| trace.stock_symbol.to_string())
+            .collect();
+
+        Ok(Self::new(symbols, &held, &watched))
+    }
+
+    pub fn new<I>(symbols: I, held: &HashSet<String>, watched: &HashSet<String>) -> Self
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let heap = symbols
+            .into_iter()
+            .map(|symbol| {
+                let tier = if held.contains(&symbol) {
+                    Tier::Held
+                } else if watched.contains(&symbol) {
+                    Tier::Watched
+                } else {
+                    Tier::Rest
+                };
+                Reverse((tier, symbol))
+            })
+            .collect();
+
+        CrawlQueue { heap }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+}
+
+impl Iterator for CrawlQueue {
+    type Item = (Tier, String);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.heap.pop().map(|Reverse(item)| item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_queue_order() {
+        let held = HashSet::from(["2884".to_string()]);
+        let watched = HashSet::from(["2330".to_string(), "2884".to_string()]);
+        let symbols = ["1101", "2330", "0050", "2884"].map(String::from);
+
+        let queue = CrawlQueue::new(symbols, &held, &watched);
+
+        assert_eq!(queue.len(), 4);
+        assert_eq!(
+            queue.collect::<Vec<_>>(),
+            vec![
+                (Tier::Held, "2884".to_string()),
+                (Tier::Watched, "2330".to_string()),
+                (Tier::Rest, "0050".to_string()),
+                (Tier::Rest, "1101".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_interval_secs() {
+        let frequency: CrawlFrequency =
+            serde_json::from_str(r#"{ "held_hours": 24, "watched_hours": 72 }"#).unwrap();
+
+        assert_eq!(frequency.interval_secs(Tier::Held, 100), 24 * 60 * 60);
+        assert_eq!(frequency.interval_secs(Tier::Watched, 100), 72 * 60 * 60);
+        assert_eq!(frequency.interval_secs(Tier::Rest, 100), 100);
+    }
+}
@@ -1,12 +1,47 @@
+use std::fmt::Write;
+
 use anyhow::Result;
-use chrono::{DateTime, FixedOffset, Local};
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, Local, NaiveDate};
+use rust_decimal::{prelude::FromPrimitive, Decimal};
 
 use crate::{
-    cache::SHARE, crawler::twse,
-    database::table::stock::extension::qualified_foreign_institutional_investor::QualifiedForeignInstitutionalInvestor,
-    logging, util::datetime::Weekend,
+    backfill::registry::Crawler,
+    bot::{alert, telegram::fmt},
+    cache::SHARE,
+    config::SETTINGS,
+    crawler::twse,
+    database::table::{
+        audit_log::Audit,
+        qfii_holding::{QfiiChange, QfiiHolding},
+        stock::extension::qualified_foreign_institutional_investor::QualifiedForeignInstitutionalInvestor,
+    },
+    logging,
+    util::datetime::Weekend,
 };
 
+/// 與幾天前的外資持股比率比較
+const DROP_LOOKBACK_DAYS: i32 = 7;
+
+/// 22:00 外資持股狀態
+pub struct QualifiedForeignInstitutionalInvestorCrawler;
+
+#[async_trait]
+impl Crawler for QualifiedForeignInstitutionalInvestorCrawler {
+    fn name(&self) -> &'static str {
+        "qualified_foreign_institutional_investor"
+    }
+
+    fn schedule(&self) -> &'static str {
+        "0 0 14 * * *"
+    }
+
+    async fn execute(&self) -> Result<()> {
+        execute().await
+    }
+}
+
+/// 更新外資持股狀態並記錄當日的歷史，庫存股票的外資持股比率一週內大幅減少時發送警示
 pub async fn execute() -> Result<()> {
     let now = Local::now();
 
@@ -14,21 +49,74 @@ pub async fn execute() -> Result<()> {
         return Ok(());
     }
 
-    tokio::try_join!(listed(now.fixed_offset()), otc())?;
+    let date = now.date_naive();
+    tokio::try_join!(listed(now.fixed_offset()), otc(date))?;
 
-    Ok(())
+    notify_drops(date).await
 }
 
 async fn listed(date_time: DateTime<FixedOffset>) -> Result<()> {
     let listed = twse::qualified_foreign_institutional_investor::listed::visit(date_time).await?;
+    save_history(date_time.date_naive(), &listed).await;
     update(listed).await
 }
 
-async fn otc() -> Result<()> {
+async fn otc(date: NaiveDate) -> Result<()> {
     let toc = twse::qualified_foreign_institutional_investor::over_the_counter::visit().await?;
+    save_history(date, &toc).await;
     update(toc).await
 }
 
+/// 將當日所有股票的外資持股寫入 qfii_holdings，供查詢持股比率的趨勢
+async fn save_history(date: NaiveDate, qfiis: &[QualifiedForeignInstitutionalInvestor]) {
+    for qfii in qfiis {
+        if SHARE.get_stock(&qfii.stock_symbol).await.is_none() {
+            continue;
+        }
+
+        if let Err(why) = QfiiHolding::new(date, qfii).upsert().await {
+            logging::error_file_async(format!("{:?}", why));
+        }
+    }
+}
+
+/// 通知外資持股比率較一週前減少超過設定百分點的庫存股票
+async fn notify_drops(date: NaiveDate) -> Result<()> {
+    let threshold = Decimal::from_f64(SETTINGS.load().alert.qfii_drop_points)
+        .filter(|points| *points > Decimal::ZERO)
+        .unwrap_or(Decimal::TWO);
+    let changes = QfiiHolding::fetch_held_changes(date, DROP_LOOKBACK_DAYS).await?;
+    let drops = detect_drops(&changes, threshold);
+    if drops.is_empty() {
+        return Ok(());
+    }
+
+    let mut msg = format!("{} 庫存股票外資持股比率一週內大幅減少\n", date);
+    for change in drops {
+        let _ = writeln!(
+            msg,
+            "{} {} {}% → {}% ({})",
+            change.security_code,
+            fmt::escape_markdown(&change.name),
+            change.previous_percentage.normalize(),
+            change.current_percentage.normalize(),
+            change.points().normalize()
+        );
+    }
+
+    alert::send(alert::QFII_DROP, &msg).await;
+
+    Ok(())
+}
+
+/// 找出持股比率減少達 threshold 個百分點的股票
+fn detect_drops(changes: &[QfiiChange], threshold: Decimal) -> Vec<&QfiiChange> {
+    changes
+        .iter()
+        .filter(|change| -change.points() >= threshold)
+        .collect()
+}
+
 /// 更新股票的外資持股狀況，資料庫更新後會更新 SHARE.stocks
 async fn update(qfiis: Vec<QualifiedForeignInstitutionalInvestor>) -> Result<()> {
     for qfii in qfiis {
@@ -49,11 +137,15 @@ async fn update(qfiis: Vec<QualifiedForeignInstitutionalInvestor>) -> Result<()>
         }
 
         // 更新qfii
-        match qfii.update().await {
+        match qfii
+            .update()
+            .await
+            .audit("stocks", qfii.stock_symbol.to_string(), module_path!())
+        {
             Ok(_) => {
                 // 嘗試更新stocks_cache
                 if let Ok(mut stocks_cache) = SHARE.stocks.write() {
-                    if let Some(stock_cache) = stocks_cache.get_mut(&qfii.stock_symbol) {
+                    if let Some(stock_cache) = stocks_cache.get_mut(qfii.stock_symbol.as_str()) {
                         stock_cache.qfii_shares_held = qfii.qfii_shares_held;
                         stock_cache.issued_share = qfii.issued_share;
                         stock_cache.qfii_share_holding_percentage =
@@ -72,10 +164,38 @@ async fn update(qfiis: Vec<QualifiedForeignInstitutionalInvestor>) -> Result<()>
 
 #[cfg(test)]
 mod tests {
+    use rust_decimal_macros::dec;
+
     use crate::{cache::SHARE, logging};
 
     use super::*;
 
+    fn change(security_code: &str, previous: Decimal, current: Decimal) -> QfiiChange {
+        QfiiChange {
+            security_code: security_code.parse().unwrap(),
+            previous_percentage: previous,
+            current_percentage: current,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_detect_drops() {
+        let changes = vec![
+            change("2330", dec!(73.5), dec!(71.2)),
+            change("2317", dec!(40), dec!(38.5)),
+            change("2454", dec!(60), dec!(63)),
+            change("1101", dec!(20), dec!(18)),
+        ];
+
+        let drops = detect_drops(&changes, dec!(2));
+
+        assert_eq!(drops.len(), 2);
+        assert_eq!(drops[0].security_code, "2330");
+        assert_eq!(drops[0].points(), dec!(-2.3));
+        assert_eq!(drops[1].security_code, "1101");
+    }
+
     #[tokio::test]
     #[ignore]
     async fn test_execute() {
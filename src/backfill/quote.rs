@@ -1,40 +1,61 @@
 use std::{
+    collections::HashSet,
     future::{Future},
     time::Duration
 };
 
-use anyhow::Result;
-use chrono::{NaiveDate};
+use anyhow::{anyhow, Result};
+use chrono::{Datelike, Months, NaiveDate};
 use futures::{stream, StreamExt};
 
 use crate::{
+    backfill::pipeline::Pipeline,
     cache::{SHARE, TTL, TtlCacheInner},
+    calculation::daily_quotes::process_daily_quote_moving_average,
     crawler::{tpex, twse},
-    database::table::{self, daily_quote::DailyQuote},
-    logging, util,
+    database::table::{self, daily_quote, daily_quote::DailyQuote},
+    declare::StockExchangeMarket,
+    logging,
+    publisher::{self, Quote},
+    util,
     util::map::Keyable,
 };
 
-/// 調用  twse、tpex API 取得台股收盤報價
+/// 歷史報價每個月份之間等待的時間，避免被 twse 封鎖
+const HISTORY_REQUEST_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 調用  twse、tpex API 取得台股收盤報價，興櫃股票以日均價作為收盤價
 pub async fn execute(date: NaiveDate) -> Result<usize> {
     //上市報價
     let twse = twse::quote::visit(date);
     //上櫃報價
     let tpex = tpex::quote::visit(date);
+    //興櫃報價
+    let emerging = tpex::emerging_quote::visit(date);
     let mut quotes_twse: Vec<DailyQuote> = Vec::with_capacity(1024);
     let mut quotes_tpex: Vec<DailyQuote> = Vec::with_capacity(1024);
+    let mut quotes_emerging: Vec<DailyQuote> = Vec::with_capacity(512);
     let get_twse =  get_quotes_from_source(twse, "上市", &mut quotes_twse);
     let get_tpex = get_quotes_from_source(tpex, "上櫃", &mut quotes_tpex);
-    let (result_twse, result_tpex) = tokio::join!(get_twse, get_tpex);
+    let get_emerging = get_quotes_from_source(emerging, "興櫃", &mut quotes_emerging);
+    let (result_twse, result_tpex, result_emerging) =
+        tokio::join!(get_twse, get_tpex, get_emerging);
 
     result_twse?;
     result_tpex?;
+    result_emerging?;
+
+    // 興櫃只收錄資料庫中有的股票，並略過已經寫入過的報價
+    quotes_emerging.retain(|dq| {
+        SHARE.stock_contains_key(&dq.security_code) && !TTL.daily_quote_contains_key(&dq.key())
+    });
 
-    let quotes_len = quotes_twse.len() +quotes_tpex.len();
+    let quotes_len = quotes_twse.len() + quotes_tpex.len() + quotes_emerging.len();
     let mut quotes = Vec::with_capacity(quotes_len);
 
     quotes.append(&mut quotes_twse);
     quotes.append(&mut quotes_tpex);
+    quotes.append(&mut quotes_emerging);
 
     if quotes_len > 0 {
         process_quotes(quotes).await;
@@ -50,6 +71,98 @@ pub async fn execute(date: NaiveDate) -> Result<usize> {
     Ok(quotes_len)
 }
 
+/// 調用 twse STOCK_DAY API 逐月回補上市股票在 from 到 to(含)之間缺少的收盤報價，回傳新增的筆數
+pub async fn execute_history(symbol: &str, from: NaiveDate, to: NaiveDate) -> Result<usize> {
+    if from > to {
+        return Err(anyhow!("from({}) must not be after to({})", from, to));
+    }
+
+    let stock = SHARE
+        .get_stock(symbol)
+        .await
+        .ok_or_else(|| anyhow!("Stock({}) not found", symbol))?;
+    if stock.stock_exchange_market_id != StockExchangeMarket::Listed as i32 {
+        return Err(anyhow!(
+            "Stock({}) is not listed on twse, STOCK_DAY only provides listed stocks",
+            symbol
+        ));
+    }
+
+    let existing: HashSet<NaiveDate> = daily_quote::fetch_trading_days(symbol, from, to)
+        .await?
+        .into_iter()
+        .map(|(_, date, _)| date)
+        .collect();
+    let mut inserted = HashSet::new();
+    // 每個月份之間要等待，寫入交給另一個任務批次以 COPY 寫入，不佔用等待的時間
+    let pipeline = Pipeline::spawn(|quotes: Vec<DailyQuote>| async move {
+        daily_quote::copy_in(&quotes).await?;
+        Ok(quotes.len())
+    });
+
+    for (i, month) in history_months(from, to).into_iter().enumerate() {
+        if i > 0 {
+            tokio::time::sleep(HISTORY_REQUEST_INTERVAL).await;
+        }
+
+        let quotes: Vec<DailyQuote> = twse::stock_day::visit(symbol, month)
+            .await?
+            .into_iter()
+            .filter(|dq| dq.date >= from && dq.date <= to && !existing.contains(&dq.date))
+            .collect();
+        inserted.extend(quotes.iter().map(|dq| dq.date));
+        pipeline.send_all(quotes).await?;
+
+        logging::info_file_async(format!(
+            "{} {} 歷史收盤數據抓取完成，累計 {} 筆",
+            symbol,
+            month.format("%Y-%m"),
+            inserted.len()
+        ));
+    }
+
+    pipeline.finish().await?;
+
+    // 均線需依日期由舊到新計算，新增的交易日才會用到前面回補的收盤價
+    for (serial, date, closing_price) in daily_quote::fetch_trading_days(symbol, from, to).await? {
+        if !inserted.contains(&date) {
+            continue;
+        }
+
+        let mut dq = DailyQuote::new(stock.stock_symbol.clone());
+        dq.serial = serial;
+        dq.date = date;
+        dq.closing_price = closing_price;
+        process_daily_quote_moving_average(dq).await?;
+    }
+
+    logging::info_file_async(format!(
+        "{} 歷史收盤數據回補 {} ~ {} 完成，共新增 {} 筆",
+        symbol,
+        from,
+        to,
+        inserted.len()
+    ));
+
+    Ok(inserted.len())
+}
+
+/// 列出 from 到 to 之間每個月份的第一天
+fn history_months(from: NaiveDate, to: NaiveDate) -> Vec<NaiveDate> {
+    let mut months = Vec::new();
+    let mut month = from.with_day(1).unwrap_or(from);
+
+    while month <= to {
+        months.push(month);
+        match month.checked_add_months(Months::new(1)) {
+            Some(next) => month = next,
+            None => break,
+        }
+    }
+
+    months
+}
+
 pub async fn get_quotes_from_source(
     source: impl Future<Output = Result<Vec<DailyQuote>>>,
     source_name: &str,
@@ -63,7 +176,10 @@ pub async fn get_quotes_from_source(
 }
 
 pub async fn process_quotes(quotes: Vec<DailyQuote>) {
-    let result_count = DailyQuote::copy_in_raw(&quotes).await.unwrap_or_default();
+    let result_count = daily_quote::copy_in(&quotes).await.unwrap_or_default();
+    if result_count > 0 {
+        publisher::publish(publisher::QUOTE, quotes.iter().map(Quote::from));
+    }
     stream::iter(quotes)
         .for_each_concurrent(util::concurrent_limit_32(), |dq| async move {
             process_daily_quote(dq).await;
@@ -103,6 +219,26 @@ mod tests {
 
 //use std::time;
 
+    #[test]
+    fn test_history_months() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        assert_eq!(
+            history_months(date(2023, 11, 15), date(2024, 2, 1)),
+            vec![
+                date(2023, 11, 1),
+                date(2023, 12, 1),
+                date(2024, 1, 1),
+                date(2024, 2, 1)
+            ]
+        );
+        assert_eq!(
+            history_months(date(2024, 1, 2), date(2024, 1, 31)),
+            vec![date(2024, 1, 1)]
+        );
+        assert!(history_months(date(2024, 2, 1), date(2024, 1, 31)).is_empty());
+    }
+
     #[tokio::test]
     #[ignore]
     async fn test_execute() {
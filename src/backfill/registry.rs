@@ -0,0 +1,101 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+
+use crate::backfill::{
+    corporate_event, insider_shareholding, isin, market_cap, odd_lot_quote,
+    qualified_foreign_institutional_investor, revenue, stock_weight, suspend_listing,
+};
+
+/// 自成一體的數據來源，註冊後自動加入排程並可由 `stock_crawler crawl <name>` 手動執行
+#[async_trait]
+pub trait Crawler: Send + Sync {
+    /// 命令列與管理指令使用的名稱 ex. revenue
+    fn name(&self) -> &'static str;
+    /// 排程的 cron 表示式(UTC 時間)
+    fn schedule(&self) -> &'static str;
+    async fn execute(&self) -> Result<()>;
+}
+
+/// 已註冊的爬蟲，新增數據來源時在模組內實作 Crawler 後加入這裡
+static REGISTRY: Lazy<Vec<Arc<dyn Crawler>>> = Lazy::new(|| {
+    vec![
+        Arc::new(revenue::RevenueCrawler),
+        Arc::new(isin::IsinCrawler),
+        Arc::new(suspend_listing::SuspendListingCrawler),
+        Arc::new(insider_shareholding::InsiderShareholdingCrawler),
+        Arc::new(stock_weight::StockWeightCrawler),
+        Arc::new(
+            qualified_foreign_institutional_investor::QualifiedForeignInstitutionalInvestorCrawler,
+        ),
+        Arc::new(market_cap::MarketCapCrawler),
+        Arc::new(odd_lot_quote::OddLotQuoteCrawler),
+        Arc::new(corporate_event::CorporateEventCrawler),
+    ]
+});
+
+const USAGE: &str = "usage: stock_crawler crawl <name>";
+
+/// 取得所有已註冊的爬蟲
+pub fn crawlers() -> &'static [Arc<dyn Crawler>] {
+    &REGISTRY
+}
+
+/// 依名稱取得爬蟲
+pub fn find(name: &str) -> Option<Arc<dyn Crawler>> {
+    REGISTRY
+        .iter()
+        .find(|crawler| crawler.name() == name)
+        .cloned()
+}
+
+/// 排程使用的任務名稱，與改為 Crawler 前以函式路徑命名的任務相同，job_runs 的執行紀錄得以延續
+pub fn job_name(crawler: &dyn Crawler) -> String {
+    format!("backfill::{}::execute", crawler.name())
+}
+
+/// 依命令列參數立即執行指定的爬蟲 ex. `stock_crawler crawl revenue`
+pub async fn command(args: &[String]) -> Result<()> {
+    let Some(name) = args.first() else {
+        let names: Vec<&str> = REGISTRY.iter().map(|crawler| crawler.name()).collect();
+        return Err(anyhow!("{}\ncrawlers: {}", USAGE, names.join(", ")));
+    };
+
+    match find(name) {
+        Some(crawler) => crawler.execute().await,
+        None => Err(anyhow!("Unknown crawler {}\n{}", name, USAGE)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use croner::Cron;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        let names: HashSet<&str> = crawlers().iter().map(|crawler| crawler.name()).collect();
+        assert_eq!(names.len(), crawlers().len());
+
+        for crawler in crawlers() {
+            assert!(
+                Cron::new(crawler.schedule())
+                    .with_seconds_optional()
+                    .parse()
+                    .is_ok(),
+                "{} has an invalid schedule",
+                crawler.name()
+            );
+        }
+
+        let revenue = find("revenue").unwrap();
+        assert_eq!(job_name(revenue.as_ref()), "backfill::revenue::execute");
+        assert!(find("unknown").is_none());
+    }
+}
@@ -1,14 +1,57 @@
-use anyhow::Result;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
 use chrono::{Datelike, FixedOffset, Local, NaiveDate, TimeDelta, TimeZone};
 use futures::{stream, StreamExt};
 
 use crate::{
+    backfill::registry::Crawler,
+    bot::{self, subscription::Event, telegram::fmt},
     cache::SHARE,
+    calculation::{self, revenue_seasonality::Seasonality},
     crawler::twse,
-    database::{table, table::revenue},
-    logging, util,
+    database::{
+        table,
+        table::{
+            audit_log::Audit, backfill_checkpoint::BackfillCheckpoint,
+            bot_subscription::BotSubscription, failed_write::Requeue,
+            ingestion_batch::IngestionBatch, revenue, stock_ownership_details,
+        },
+    },
+    declare::StockSymbol,
+    logging, publisher, util,
 };
 
+/// 月營收在 ingestion_batches 的來源名稱
+const INGESTION_SOURCE: &str = "revenue";
+/// 歷史月營收回補在 backfill_checkpoints 的名稱
+const HISTORY_CHECKPOINT: &str = "revenue_history";
+/// 歷史月營收每個月份之間等待的時間，避免被 MOPS 封鎖
+const HISTORY_REQUEST_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 05:00 取得台股的營收
+pub struct RevenueCrawler;
+
+#[async_trait]
+impl Crawler for RevenueCrawler {
+    fn name(&self) -> &'static str {
+        "revenue"
+    }
+
+    fn schedule(&self) -> &'static str {
+        "0 0 21 * * *"
+    }
+
+    async fn execute(&self) -> Result<()> {
+        execute().await
+    }
+}
+
 /// 調用  twse API 取得台股月營收
 pub async fn execute() -> Result<()> {
     let now = Local::now();
@@ -22,18 +65,241 @@ pub async fn execute() -> Result<()> {
     let year = last_month_timezone.year();
     let month = last_month_timezone.month();
     let revenues = twse::revenue::visit(last_month_timezone).await?;
+    let subscribed = BotSubscription::fetch_symbols(Event::Revenue.as_str())
+        .await
+        .unwrap_or_else(|why| {
+            logging::error_file_async(format!("{:?}", why));
+            HashSet::new()
+        });
+    let held = stock_ownership_details::fetch_held_or_traced_symbols()
+        .await
+        .unwrap_or_else(|why| {
+            logging::error_file_async(format!("{:?}", why));
+            HashSet::new()
+        });
+    let notified_revenues: Vec<revenue::Revenue> = revenues
+        .iter()
+        .filter(|r| subscribed.contains(&r.security_code) || held.contains(&r.security_code))
+        .cloned()
+        .collect();
+
+    if ingest(revenues, year, month).await?.is_some() {
+        revenue::rebuild_revenue_last_date().await?;
+        notify(year, month, &notified_revenues, &held).await;
+    }
+
+    // 尚未公布營收的庫存股票以已公布的同業與去年季節性估算，已公布的估算改為正式值
+    match calculation::revenue_estimate::calculate(i64::from(year) * 100 + i64::from(month)).await {
+        Ok(count) => logging::info_file_async(format!("月營收估算結束:{}", count)),
+        Err(why) => logging::error_file_async(format!(
+            "Failed to revenue_estimate::calculate because {:?}",
+            why
+        )),
+    }
+
+    Ok(())
+}
+
+/// 發送新公布的月營收並附上與近 5 年同月季節常態的比較，以 /subscribe 訂閱了月營收的聊天室收到訂閱的股票，
+/// 沒有訂閱的聊天室只收到庫存與追踪中偏離季節常態超過 2 個標準差的股票
+async fn notify(year: i32, month: u32, revenues: &[revenue::Revenue], held: &HashSet<StockSymbol>) {
+    if revenues.is_empty() {
+        return;
+    }
+
+    let security_codes: Vec<StockSymbol> =
+        revenues.iter().map(|r| r.security_code.clone()).collect();
+    let seasonalities = calculation::revenue_seasonality::fetch(
+        &security_codes,
+        i64::from(year) * 100 + i64::from(month),
+    )
+    .await
+    .unwrap_or_else(|why| {
+        logging::error_file_async(format!("{:?}", why));
+        HashMap::new()
+    });
+
+    let mut items: Vec<(StockSymbol, String)> = Vec::with_capacity(revenues.len());
+    let mut surprises: HashSet<StockSymbol> = HashSet::new();
+    for r in revenues {
+        let name = SHARE
+            .get_stock(&r.security_code)
+            .await
+            .map(|stock| stock.name)
+            .unwrap_or_default();
+        let seasonality = seasonalities.get(&r.security_code);
+        if held.contains(&r.security_code) && seasonality.is_some_and(Seasonality::is_anomaly) {
+            surprises.insert(r.security_code.clone());
+        }
+        items.push((
+            r.security_code.clone(),
+            format_revenue(r, &name, seasonality),
+        ));
+    }
+
+    bot::telegram::send_subscribed(
+        Event::Revenue,
+        &items,
+        |(symbol, _)| symbol,
+        Some(&surprises),
+        |_, lines| {
+            let lines: Vec<&str> = lines.iter().map(|(_, line)| line.as_str()).collect();
+            format!("{}年{}月營收\n{}", year, month, lines.join("\n"))
+        },
+    )
+    .await;
+}
+
+/// 單一股票月營收的通知內容，營收以仟元為單位，有季節性時附上與同月常態的比較
+fn format_revenue(
+    revenue: &revenue::Revenue,
+    name: &str,
+    seasonality: Option<&Seasonality>,
+) -> String {
+    let mut line = format!(
+        "{} {} 營收 {} 仟元 月增 {}% 年增 {}%",
+        revenue.security_code,
+        fmt::escape_markdown(name),
+        fmt::number(revenue.monthly, 0),
+        revenue.compared_with_last_month.round_dp(2),
+        revenue.compared_with_last_year_same_month.round_dp(2)
+    );
+    if let Some(seasonality) = seasonality {
+        let marker = if seasonality.is_anomaly() {
+            "⚠️"
+        } else {
+            ""
+        };
+        line.push_str(&format!(" {}{}", marker, seasonality.describe()));
+    }
+
+    line
+}
+
+/// 回補 from_year 到 to_year(含)每個月份的月營收，每完成一個月份記錄進度，中斷後重新執行會從下一個月份接續
+pub async fn execute_history(from_year: i32, to_year: i32) -> Result<()> {
+    let checkpoint = BackfillCheckpoint::fetch(HISTORY_CHECKPOINT)
+        .await?
+        .and_then(|c| c.parse::<i64>().ok());
+    let last_month =
+        Local::now().date_naive().with_day(1).unwrap() - TimeDelta::try_days(1).unwrap();
+    let months = history_months(
+        from_year,
+        to_year,
+        checkpoint,
+        (last_month.year(), last_month.month()),
+    );
+    let timezone = FixedOffset::east_opt(8 * 60 * 60).unwrap();
 
+    logging::info_file_async(format!(
+        "開始回補 {}~{} 年的月營收，共 {} 個月份(已完成至 {:?})",
+        from_year,
+        to_year,
+        months.len(),
+        checkpoint
+    ));
+
+    for (i, (year, month)) in months.iter().enumerate() {
+        if i > 0 {
+            tokio::time::sleep(HISTORY_REQUEST_INTERVAL).await;
+        }
+
+        let date_time = timezone
+            .with_ymd_and_hms(*year, *month, 1, 0, 0, 0)
+            .single()
+            .ok_or_else(|| anyhow!("Invalid year month {}-{}", year, month))?;
+        let revenues = twse::revenue::visit(date_time).await?;
+        if revenues.is_empty() {
+            // 下載失敗時不記錄進度，下次執行從此月份重新開始
+            return Err(anyhow!(
+                "No revenue downloaded for {}-{:02}, stop the history backfill",
+                year,
+                month
+            ));
+        }
+
+        let count = ingest(revenues, *year, *month).await?.unwrap_or_default();
+
+        let checkpoint = (*year as i64 * 100 + *month as i64).to_string();
+        BackfillCheckpoint::save(HISTORY_CHECKPOINT, &checkpoint).await?;
+        logging::info_file_async(format!("{} 的月營收已回補 {} 筆", checkpoint, count));
+    }
+
+    revenue::rebuild_revenue_last_date().await?;
+
+    Ok(())
+}
+
+/// 以 ingestion_batches 批次寫入 year 年 month 月的月營收，回傳寫入成功的筆數
+///
+/// 抓到的內容與上一次完成的批次相同時(ex. 手動重新觸發任務)略過寫入並回傳 None；
+/// 全部寫入成功才記錄批次並刪除上一個批次有、這次已不存在的數據，有失敗時下次執行會整批重新寫入
+async fn ingest(revenues: Vec<revenue::Revenue>, year: i32, month: u32) -> Result<Option<usize>> {
+    let period = format!("{}{:02}", year, month);
+    let Some(mut batch) = IngestionBatch::begin(INGESTION_SOURCE, &period, &revenues).await? else {
+        logging::info_file_async(format!(
+            "{} 的月營收與上一次寫入的內容相同，略過寫入",
+            period
+        ));
+        return Ok(None);
+    };
+
+    let total = revenues.len();
+    let failed = AtomicUsize::new(0);
     stream::iter(revenues)
-        .for_each_concurrent(util::concurrent_limit_16(), |r| async move {
-            if let Err(why) = process_revenue(r, year, month as i32).await {
-                logging::error_file_async(format!("Failed to process_revenue because {:?}", why));
+        .for_each_concurrent(util::concurrent_limit_16(), |mut r| {
+            r.batch_id = batch.batch_id.clone();
+            let failed = &failed;
+            async move {
+                if let Err(why) = process_revenue(r, year, month as i32).await {
+                    failed.fetch_add(1, Ordering::Relaxed);
+                    logging::error_file_async(format!(
+                        "Failed to process_revenue because {:?}",
+                        why
+                    ));
+                }
             }
         })
         .await;
 
-    revenue::rebuild_revenue_last_date().await?;
+    let failed = failed.into_inner();
+    if failed > 0 {
+        logging::error_file_async(format!(
+            "{} 的月營收有 {} 筆寫入失敗，下次執行會重新寫入",
+            period, failed
+        ));
+        return Ok(Some(total - failed));
+    }
 
-    Ok(())
+    batch.complete(total).await?;
+    let removed = revenue::Revenue::delete_batch(&batch.previous_batch_id).await?;
+    if removed.rows_affected() > 0 {
+        logging::info_file_async(format!(
+            "刪除 {} 上一個批次已不存在的月營收 {} 筆",
+            period,
+            removed.rows_affected()
+        ));
+    }
+
+    Ok(Some(total))
+}
+
+/// 列出 from_year 到 to_year(含)之間需要回補的年月，略過 checkpoint(YYYYMM)以前已完成的月份與 last_month 之後尚未公布的月份
+fn history_months(
+    from_year: i32,
+    to_year: i32,
+    checkpoint: Option<i64>,
+    last_month: (i32, u32),
+) -> Vec<(i32, u32)> {
+    let last = last_month.0 as i64 * 100 + last_month.1 as i64;
+
+    (from_year..=to_year)
+        .flat_map(|year| (1..=12).map(move |month| (year, month)))
+        .filter(|(year, month)| {
+            let ym = *year as i64 * 100 + *month as i64;
+            ym <= last && checkpoint.is_none_or(|c| ym > c)
+        })
+        .collect()
 }
 
 pub(crate) async fn process_revenue(
@@ -50,9 +316,15 @@ pub(crate) async fn process_revenue(
         revenue.highest_price = dq.highest_price;
     }
 
-    revenue.upsert().await?;
+    let key = format!("{}-{}", revenue.security_code, revenue.date);
+    revenue
+        .upsert()
+        .await
+        .requeue("revenue", key.clone(), &revenue)
+        .audit("revenue", key, module_path!())?;
 
     SHARE.set_last_revenues(revenue.clone());
+    publisher::publish(publisher::REVENUE, [&revenue]);
 
     let name = match SHARE.get_stock(&revenue.security_code).await {
         None => String::from("-"),
@@ -77,10 +349,51 @@ pub(crate) async fn process_revenue(
 #[cfg(test)]
 mod tests {
     use std::time::Duration;
+    use rust_decimal::Decimal;
     use crate::logging;
 
     use super::*;
 
+    #[test]
+    fn test_format_revenue() {
+        let mut r = revenue::Revenue::new();
+        r.security_code = "2330".parse().unwrap();
+        r.monthly = Decimal::from(236_021_112);
+        r.compared_with_last_month = Decimal::new(-1234, 2);
+        r.compared_with_last_year_same_month = Decimal::new(3961, 2);
+
+        assert_eq!(
+            format_revenue(&r, "台積電", None),
+            "2330 台積電 營收 236,021,112 仟元 月增 -12.34% 年增 39.61%"
+        );
+
+        let seasonality = Seasonality {
+            index: 1.5,
+            mean: 1.0,
+            std_dev: 0.2,
+            z_score: 2.5,
+            samples: 5,
+        };
+        assert_eq!(
+            format_revenue(&r, "台積電", Some(&seasonality)),
+            "2330 台積電 營收 236,021,112 仟元 月增 -12.34% 年增 39.61% ⚠️高於近 5 年同月常態 2.50σ"
+        );
+    }
+
+    #[test]
+    fn test_history_months() {
+        let months = history_months(2023, 2024, None, (2024, 2));
+        assert_eq!(months.len(), 14);
+        assert_eq!(months.first(), Some(&(2023, 1)));
+        assert_eq!(months.last(), Some(&(2024, 2)));
+
+        let months = history_months(2023, 2024, Some(202311), (2024, 2));
+        assert_eq!(months, vec![(2023, 12), (2024, 1), (2024, 2)]);
+
+        assert!(history_months(2023, 2023, Some(202312), (2024, 2)).is_empty());
+        assert!(history_months(2024, 2023, None, (2024, 2)).is_empty());
+    }
+
     #[tokio::test]
     async fn test_execute() {
         dotenv::dotenv().ok();
@@ -1,16 +1,39 @@
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
+use async_trait::async_trait;
 use futures::{stream, StreamExt};
 use tokio::sync::Mutex;
 
 use crate::{
+    backfill::registry::Crawler,
     crawler::taifex,
-    database::table::stock::{self, extension::weight::SymbolAndWeight},
+    database::table::{
+        audit_log::Audit,
+        stock::{self, extension::weight::SymbolAndWeight},
+    },
     declare::StockExchange,
     logging, util,
 };
 
+/// 09:00 更新股票權值佔比
+pub struct StockWeightCrawler;
+
+#[async_trait]
+impl Crawler for StockWeightCrawler {
+    fn name(&self) -> &'static str {
+        "stock_weight"
+    }
+
+    fn schedule(&self) -> &'static str {
+        "0 0 1 * * *"
+    }
+
+    async fn execute(&self) -> Result<()> {
+        execute().await
+    }
+}
+
 /// 查詢 taifex 個股權值比重
 pub async fn execute() -> Result<()> {
     let stock_weights = Arc::new(Mutex::new(Vec::with_capacity(2000)));
@@ -30,7 +53,11 @@ pub async fn execute() -> Result<()> {
         SymbolAndWeight::zeroed_out().await.context("Failed to zero out SymbolAndWeight")?;
         stream::iter(weights.clone())
             .for_each_concurrent(util::concurrent_limit_16(), |sw| async move {
-                if let Err(why) = sw.update().await {
+                if let Err(why) = sw
+                    .update()
+                    .await
+                    .audit("stocks", sw.stock_symbol.to_string(), module_path!())
+                {
                     logging::error_file_async(format!(
                         "Failed to update stock weight: {:#?}",
                         why
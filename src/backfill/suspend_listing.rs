@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Local;
+
+use crate::{
+    backfill::registry::Crawler,
+    cache::SHARE,
+    crawler::twse::suspend_listing::{self, SuspendListing, SuspendListingSource},
+    database::table::{audit_log::Audit, stock},
+    declare::StockSymbol,
+    logging,
+    util::datetime::Weekend,
+};
+
+/// 民國 110 年以前下市的公司不再處理
+const MIN_DELISTING_YEAR: i32 = 110;
+
+/// 已被標記為終止上市的股票
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpdatedStock {
+    pub stock_symbol: StockSymbol,
+    pub name: String,
+    /// 民國年格式的終止上市日期 ex. 1130102
+    pub delisting_date: String,
+}
+
+/// 05:00 更新下市的股票
+pub struct SuspendListingCrawler;
+
+#[async_trait]
+impl Crawler for SuspendListingCrawler {
+    fn name(&self) -> &'static str {
+        "suspend_listing"
+    }
+
+    fn schedule(&self) -> &'static str {
+        "0 0 21 * * *"
+    }
+
+    async fn execute(&self) -> Result<()> {
+        execute().await
+    }
+}
+
+/// 更新資料庫中終止上市的公司
+pub async fn execute() -> Result<()> {
+    if Local::now().is_weekend() {
+        return Ok(());
+    }
+
+    let updated = apply(&suspend_listing::Twse).await?;
+    if !updated.is_empty() {
+        logging::info_file_async(format!("終止上市的股票已更新:{:?}", updated));
+    }
+
+    Ok(())
+}
+
+/// 從資料來源取得終止上市名單，將尚未標記下市的股票寫入資料庫與快取，並回傳本次更新的股票
+pub async fn apply(source: &dyn SuspendListingSource) -> Result<Vec<UpdatedStock>> {
+    let delisted = source.fetch().await?;
+    let mut stocks = HashMap::with_capacity(delisted.len());
+
+    for company in &delisted {
+        if let Some(stock) = SHARE.get_stock(&company.stock_symbol).await {
+            stocks.insert(stock.stock_symbol.to_string(), stock);
+        }
+    }
+
+    let candidates = select(delisted, &stocks);
+    let mut updated = Vec::with_capacity(candidates.len());
+
+    for candidate in candidates {
+        let item = stock::extension::suspend_listing::SymbolAndSuspendListing::new(
+            candidate.stock_symbol.clone(),
+            true,
+        );
+
+        if let Err(why) =
+            item.update()
+                .await
+                .audit("stocks", item.stock_symbol.to_string(), module_path!())
+        {
+            logging::error_file_async(format!(
+                "Failed to update_suspend_listing because {:?}",
+                why
+            ));
+            continue;
+        }
+
+        if let Ok(mut stocks_cache) = SHARE.stocks.write() {
+            if let Some(stock) = stocks_cache.get_mut(item.stock_symbol.as_str()) {
+                stock.suspend_listing = true;
+            }
+        }
+
+        updated.push(candidate);
+    }
+
+    Ok(updated)
+}
+
+/// 挑出存在於股票清單、尚未標記下市且於民國 110 年(含)以後終止上市的公司
+fn select(
+    delisted: Vec<SuspendListing>,
+    stocks: &HashMap<String, stock::Stock>,
+) -> Vec<UpdatedStock> {
+    delisted
+        .into_iter()
+        .filter(|company| {
+            stocks
+                .get(company.stock_symbol.as_str())
+                .is_some_and(|stock| !stock.suspend_listing)
+        })
+        .filter(|company| match company.delisting_date.get(..3) {
+            Some(year) => match year.parse::<i32>() {
+                Ok(year) => year >= MIN_DELISTING_YEAR,
+                Err(why) => {
+                    logging::error_file_async(format!("轉換資料日期發生錯誤. because {:?}", why));
+                    false
+                }
+            },
+            None => false,
+        })
+        .map(|company| UpdatedStock {
+            stock_symbol: company.stock_symbol,
+            name: company.name,
+            delisting_date: company.delisting_date,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use crate::cache::SHARE;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    struct Fixed(Vec<SuspendListing>);
+
+    #[async_trait]
+    impl SuspendListingSource for Fixed {
+        async fn fetch(&self) -> Result<Vec<SuspendListing>> {
+            Ok(self.0.clone())
+        }
+    }
+
+    fn listing(stock_symbol: &str, delisting_date: &str) -> SuspendListing {
+        SuspendListing {
+            delisting_date: delisting_date.to_string(),
+            name: format!("{}名稱", stock_symbol),
+            stock_symbol: stock_symbol.parse().unwrap(),
+        }
+    }
+
+    fn stock(stock_symbol: &str, suspend_listing: bool) -> (String, stock::Stock) {
+        let mut s = stock::Stock::new();
+        s.stock_symbol = stock_symbol.parse().unwrap();
+        s.suspend_listing = suspend_listing;
+        (stock_symbol.to_string(), s)
+    }
+
+    #[tokio::test]
+    async fn test_select() {
+        let source = Fixed(vec![
+            listing("1101", "1130102"),
+            listing("1102", "1090102"),
+            listing("1103", "1130102"),
+            listing("1104", "1130102"),
+            listing("1105", "11"),
+        ]);
+        let stocks = HashMap::from([
+            stock("1101", false),
+            stock("1102", false),
+            stock("1103", true),
+            stock("1105", false),
+        ]);
+
+        let selected = select(source.fetch().await.unwrap(), &stocks);
+
+        assert_eq!(
+            selected,
+            vec![UpdatedStock {
+                stock_symbol: "1101".parse().unwrap(),
+                name: "1101名稱".to_string(),
+                delisting_date: "1130102".to_string(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute() {
+        dotenv::dotenv().ok();
+        SHARE.load().await;
+        logging::debug_file_async("開始 execute".to_string());
+
+        match execute().await {
+            Ok(_) => {
+                logging::debug_file_async("execute executed successfully.".to_string());
+            }
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to execute because {:?}", why));
+            }
+        }
+
+        logging::debug_file_async("結束 execute".to_string());
+    }
+}
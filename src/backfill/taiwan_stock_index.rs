@@ -2,7 +2,16 @@ use anyhow::Result;
 use chrono::Local;
 
 use crate::util::map::Keyable;
-use crate::{bot, cache::SHARE, crawler::twse, database::table, logging};
+use crate::{
+    bot::{self, telegram::fmt},
+    cache::SHARE,
+    crawler::twse,
+    database::{
+        repository::{IndexRepository, PgRepository},
+        table::index::Index,
+    },
+    logging,
+};
 
 /// 調用  twse API 取得台股加權指數
 pub async fn execute() -> Result<()> {
@@ -12,53 +21,60 @@ pub async fn execute() -> Result<()> {
         return Ok(());
     }
 
-    if let Some(data) = tai_ex.data {
-        for item in data {
-            if item.len() != 6 {
-                logging::error_file_async(format!("資料欄位不等於6 item:{:?}", item));
-                continue;
+    let mut indexes = Vec::new();
+    for item in tai_ex.data.unwrap_or_default() {
+        if item.len() != 6 {
+            logging::error_file_async(format!("資料欄位不等於6 item:{:?}", item));
+            continue;
+        }
+
+        match Index::from_strings(&item) {
+            Ok(index) => indexes.push(index),
+            Err(why) => {
+                logging::error_file_async(format!(
+                    "Failed to index::Index::from_strings({:?}) because {:?}",
+                    item, why
+                ));
             }
+        }
+    }
 
-            let index = match table::index::Index::from_strings(&item) {
-                Ok(i) => i,
-                Err(why) => {
-                    logging::error_file_async(format!(
-                        "Failed to index::Index::from_strings({:?}) because {:?}",
-                        item, why
-                    ));
-                    continue;
-                }
-            };
+    save(&PgRepository::new(module_path!()), indexes).await;
 
-            //logging::debug_file_async(format!("index:{:?}", index));
-            let key = index.key();
-            if SHARE.get_stock_index(&key).is_some() {
-                continue;
-            }
+    Ok(())
+}
+
+/// 將快取內沒有的指數寫入 repository，寫入成功後通知並放入快取，寫入失敗的只記錄日誌
+pub async fn save(repository: &dyn IndexRepository, indexes: Vec<Index>) {
+    for index in indexes {
+        //logging::debug_file_async(format!("index:{:?}", index));
+        let key = index.key();
+        if SHARE.get_stock_index(&key).is_some() {
+            continue;
+        }
 
-            match index.upsert().await {
-                Ok(_) => {
-                    logging::info_file_async(format!("index add {:?}", index));
-                    let msg = format!(
-                        "{} 大盤指數︰{} 漲跌︰{}",
-                        index.date, index.index, index.change
-                    );
+        match repository.upsert(&index).await {
+            Ok(_) => {
+                logging::info_file_async(format!("index add {:?}", index));
+                let msg = format!(
+                    "{} 大盤指數︰{} 漲跌︰{}",
+                    index.date,
+                    fmt::number(index.index, 2),
+                    fmt::number(index.change, 2)
+                );
 
-                    bot::telegram::send(&msg).await;
+                bot::telegram::send(&msg).await;
 
-                    SHARE.set_stock_index(key, index).await;
-                }
-                Err(why) => {
-                    logging::error_file_async(format!(
-                        "Failed to index.upsert({:#?}) because {:?}",
-                        index, why
-                    ));
-                }
+                SHARE.set_stock_index(key, index).await;
+            }
+            Err(why) => {
+                logging::error_file_async(format!(
+                    "Failed to index.upsert({:#?}) because {:?}",
+                    index, why
+                ));
             }
         }
     }
-
-    Ok(())
 }
 
 #[cfg(test)]
@@ -0,0 +1,117 @@
+use std::{future::Future, sync::Arc};
+
+use anyhow::Result;
+use chrono::NaiveDate;
+
+use crate::{
+    backfill::pipeline::Pipeline,
+    crawler::{tpex, twse},
+    database::{
+        repository::{DailyValuationRepository, PgRepository},
+        table::daily_valuation::DailyValuation,
+    },
+    logging,
+};
+
+/// 調用 twse、tpex API 取得指定日期個股的本益比、殖利率及股價淨值比並寫入資料庫，回傳寫入的筆數
+pub async fn execute(date: NaiveDate) -> Result<usize> {
+    let pipeline = writer(Arc::new(PgRepository::new(module_path!())));
+    let (twse, tpex) = tokio::join!(
+        crawl(&pipeline, "twse", twse::valuation::visit(date)),
+        crawl(&pipeline, "tpex", tpex::valuation::visit(date))
+    );
+    twse?;
+    tpex?;
+
+    pipeline.finish().await
+}
+
+/// 爬蟲取得的本益比、殖利率及股價淨值比送往寫入任務，爬蟲失敗時只記錄日誌
+async fn crawl(
+    pipeline: &Pipeline<DailyValuation>,
+    exchange: &str,
+    visit: impl Future<Output = Result<Vec<DailyValuation>>>,
+) -> Result<()> {
+    match visit.await {
+        Ok(valuations) => pipeline.send_all(valuations).await,
+        Err(why) => {
+            logging::error_file_async(format!(
+                "Failed to visit {} valuation because {:?}",
+                exchange, why
+            ));
+            Ok(())
+        }
+    }
+}
+
+/// 啟動將本益比、殖利率及股價淨值比批次寫入 repository 的任務
+pub fn writer(repository: Arc<dyn DailyValuationRepository>) -> Pipeline<DailyValuation> {
+    Pipeline::spawn(move |valuations| save(repository.clone(), valuations))
+}
+
+/// 將一批本益比、殖利率及股價淨值比以一個 INSERT 寫入 repository，寫入失敗的只記錄日誌，回傳寫入成功的筆數
+async fn save(
+    repository: Arc<dyn DailyValuationRepository>,
+    valuations: Vec<DailyValuation>,
+) -> Result<usize> {
+    match repository.upsert_batch(&valuations).await {
+        Ok(count) => Ok(count),
+        Err(why) => {
+            logging::error_file_async(format!("{:?}", why));
+            Ok(0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+
+    use crate::{
+        cache::SHARE, database::repository::MemoryRepository, declare::StockExchange, logging,
+    };
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[tokio::test]
+    async fn test_save() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let fields = ["證券代號", "本益比", "股價淨值比", "殖利率(%)"].map(String::from);
+        let data = vec![
+            vec!["1101", "27.38", "1.24", "3.14"],
+            vec!["2330", "16.27", "4.61", "1.85"],
+        ]
+        .into_iter()
+        .map(|row| row.into_iter().map(String::from).collect())
+        .collect::<Vec<Vec<String>>>();
+        let valuations = DailyValuation::from_table(date, StockExchange::TWSE, &fields, &data);
+        let repository = Arc::new(MemoryRepository::default());
+        let pipeline = writer(repository.clone());
+        pipeline.send_all(valuations.clone()).await.unwrap();
+
+        assert_eq!(pipeline.finish().await.unwrap(), 2);
+
+        let stored = repository.daily_valuations.lock().unwrap();
+        assert_eq!(stored.values().cloned().collect::<Vec<_>>(), valuations);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_execute() {
+        dotenv::dotenv().ok();
+        SHARE.load().await;
+        logging::debug_file_async("開始 execute".to_string());
+
+        match execute(Local::now().date_naive()).await {
+            Ok(count) => {
+                logging::debug_file_async(format!("成功執行 execute:{}", count));
+            }
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to execute because {:?}", why));
+            }
+        }
+
+        logging::debug_file_async("結束 execute".to_string());
+    }
+}
@@ -0,0 +1,275 @@
+use anyhow::Result;
+
+use crate::{
+    bot::{
+        command::Command,
+        role::{self, Role},
+        telegram::fmt::{self, Align, Table},
+    },
+    cache::{TtlCacheInner, SHARE, TTL},
+    config::SETTINGS,
+    database::{
+        retry_queue,
+        table::{bot_role::BotRole, failed_write::FailedWrite},
+    },
+    logging, scheduler,
+};
+
+/// 只有 admin 角色可以使用的指令
+const COMMANDS: [&str; 5] = ["jobs", "cache", "config", "failed", "roles"];
+
+const USAGE: &str = "管理指令:
+/jobs status 任務排程與最後成功執行的時間
+/jobs run revenue 立即執行名稱含 revenue 的任務
+/jobs pause 暫停整個排程 ex. 資料庫維護期間
+/jobs resume 恢復整個排程
+/jobs disable dividend 停用名稱含 dividend 的任務
+/jobs enable dividend 啟用停用的任務
+/cache clear quotes 重新載入最後交易日的報價快取，也可用 stocks、all
+/config reload 重新載入設定檔
+/failed 寫入失敗等待重試的筆數與最近 10 筆
+/failed retry 立即重試寫入失敗的數據
+/failed purge 12 刪除序號 12 的記錄，也可用資料表名稱或 all
+/roles 列出授予的角色
+/roles grant 123456 viewer 阿姨 授予使用者 viewer、trader 或 admin 角色，最後可加上備註
+/roles revoke 123456 移除使用者的角色";
+
+/// /failed 列出的最近記錄筆數
+const RECENT_FAILED_WRITES: i64 = 10;
+
+/// 是否為管理指令
+pub fn is_admin_command(name: &str) -> bool {
+    COMMANDS.contains(&name)
+}
+
+/// 執行管理指令，回傳要回覆的訊息
+pub async fn dispatch(command: &Command) -> Result<String> {
+    let args: Vec<&str> = command.args.iter().map(String::as_str).collect();
+    logging::info_file_async(format!("Admin command /{} {:?}", command.name, args));
+
+    match (command.name.as_str(), args.as_slice()) {
+        ("jobs", ["status"]) => jobs_status().await,
+        ("jobs", ["run", keyword]) => Ok(reply_jobs("已開始執行", scheduler::run_now(keyword))),
+        ("jobs", ["pause"]) => {
+            scheduler::pause().await?;
+            Ok("已暫停整個排程，執行中的任務不受影響，重啟後仍維持暫停".to_string())
+        }
+        ("jobs", ["resume"]) => {
+            scheduler::resume().await?;
+            Ok("已恢復整個排程，暫停期間錯過的任務不會補跑".to_string())
+        }
+        ("jobs", ["disable", keyword]) => {
+            Ok(reply_jobs("已停用", scheduler::disable(keyword).await))
+        }
+        ("jobs", ["enable", keyword]) => Ok(reply_jobs("已啟用", scheduler::enable(keyword).await)),
+        ("cache", ["clear", target]) => cache_clear(target).await,
+        ("config", ["reload"]) => Ok(match SETTINGS.reload() {
+            Ok(_) => "已重新載入設定檔，啟動時建立的連線與排程不受影響".to_string(),
+            Err(why) => format!("重新載入設定檔失敗，維持目前的設定\n{}", why),
+        }),
+        ("failed", []) => failed_writes().await,
+        ("failed", ["retry"]) => {
+            let (succeeded, failed) = retry_queue::retry_pending().await?;
+            Ok(format!(
+                "重試結束，成功 {} 筆，失敗 {} 筆",
+                succeeded, failed
+            ))
+        }
+        ("failed", ["purge", target]) => failed_writes_purge(target).await,
+        ("roles", []) => roles().await,
+        ("roles", ["grant", user_id, name, note @ ..]) => {
+            match (user_id.parse::<i64>(), Role::parse(name)) {
+                (Ok(user_id), Some(role)) => grant(user_id, role, note.join(" ")).await,
+                _ => Ok(USAGE.to_string()),
+            }
+        }
+        ("roles", ["revoke", user_id]) => match user_id.parse::<i64>() {
+            Ok(user_id) => revoke(user_id).await,
+            Err(_) => Ok(USAGE.to_string()),
+        },
+        _ => Ok(USAGE.to_string()),
+    }
+}
+
+async fn jobs_status() -> Result<String> {
+    let list = scheduler::status().await?;
+    let mut table =
+        Table::new(&["任務", "狀態", "最後成功"]).align(&[Align::Left, Align::Left, Align::Left]);
+    for job in &list {
+        table.row(&[
+            job.name.clone(),
+            if job.disabled {
+                "停用"
+            } else {
+                job.cron_expr
+            }
+            .to_string(),
+            job.last_success
+                .map(|time| time.format("%m-%d %H:%M").to_string())
+                .unwrap_or_else(|| "-".to_string()),
+        ]);
+    }
+
+    let leader = if scheduler::leader::is_enabled() {
+        let holder = scheduler::leader::holder()
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| "無".to_string());
+        format!(
+            "本機 {} 為 {}，leader: {}\n",
+            scheduler::leader::token(),
+            if scheduler::leader::is_leader() {
+                "leader"
+            } else {
+                "follower"
+            },
+            holder
+        )
+    } else {
+        String::new()
+    };
+
+    Ok(format!(
+        "{}{}共 {} 個任務，執行中 {} 個(排程時間為 UTC)\n{}",
+        fmt::escape_markdown(&leader),
+        if scheduler::is_paused() {
+            "排程已暫停，"
+        } else {
+            ""
+        },
+        list.len(),
+        scheduler::running_jobs(),
+        table.render()
+    ))
+}
+
+fn reply_jobs(action: &str, result: Result<Vec<String>>) -> String {
+    match result {
+        Ok(names) => format!(
+            "{} {} 個任務\n{}",
+            action,
+            names.len(),
+            fmt::escape_markdown(&names.join("\n"))
+        ),
+        Err(why) => fmt::escape_markdown(&why.to_string()),
+    }
+}
+
+async fn failed_writes() -> Result<String> {
+    let counts = FailedWrite::count_by_table().await?;
+    if counts.is_empty() {
+        return Ok("沒有寫入失敗的數據".to_string());
+    }
+
+    let mut summary = Table::new(&["資料表", "筆數"]).align(&[Align::Left, Align::Right]);
+    for (table_name, count) in &counts {
+        summary.row(&[table_name.clone(), count.to_string()]);
+    }
+
+    let mut recent = Table::new(&["序號", "資料表", "鍵值", "重試"]).align(&[
+        Align::Right,
+        Align::Left,
+        Align::Left,
+        Align::Right,
+    ]);
+    for entry in FailedWrite::fetch_recent(RECENT_FAILED_WRITES).await? {
+        recent.row(&[
+            entry.serial.to_string(),
+            entry.table_name,
+            entry.record_key,
+            format!("{}/{}", entry.attempts, retry_queue::MAX_ATTEMPTS),
+        ]);
+    }
+
+    Ok(format!(
+        "寫入失敗等待重試的數據，重試 {} 次仍失敗後不再自動重試\n{}\n最近 {} 筆\n{}",
+        retry_queue::MAX_ATTEMPTS,
+        summary.render(),
+        RECENT_FAILED_WRITES,
+        recent.render()
+    ))
+}
+
+async fn failed_writes_purge(target: &str) -> Result<String> {
+    let result = match target {
+        "all" => FailedWrite::purge(None).await?,
+        target => match target.parse::<i64>() {
+            Ok(serial) => FailedWrite::delete(serial).await?,
+            Err(_) => FailedWrite::purge(Some(target)).await?,
+        },
+    };
+
+    Ok(format!("已刪除 {} 筆記錄", result.rows_affected()))
+}
+
+async fn roles() -> Result<String> {
+    let roles = BotRole::fetch_all().await?;
+    let mut table =
+        Table::new(&["使用者", "角色", "備註"]).align(&[Align::Left, Align::Left, Align::Left]);
+    for role in &roles {
+        table.row(&[
+            role.user_id.to_string(),
+            role.role.clone(),
+            role.note.clone(),
+        ]);
+    }
+
+    Ok(format!(
+        "設定檔 bot.telegram.admins 內的使用者一律為 admin，bot.telegram.allowed 內的聊天室未授予角色時為 trader\n{}",
+        table.render()
+    ))
+}
+
+async fn grant(user_id: i64, role: Role, note: String) -> Result<String> {
+    BotRole::new(user_id, role.as_str(), note).upsert().await?;
+    role::cache(user_id, Some(role));
+
+    Ok(format!("已授予 {} {} 角色", user_id, role))
+}
+
+async fn revoke(user_id: i64) -> Result<String> {
+    let result = BotRole::delete(user_id).await?;
+    role::cache(user_id, None);
+
+    Ok(format!(
+        "已移除 {} 的角色({} 筆)",
+        user_id,
+        result.rows_affected()
+    ))
+}
+
+async fn cache_clear(target: &str) -> Result<String> {
+    match target {
+        "quotes" => {
+            TTL.clear();
+            let count = SHARE.reload_last_trading_day_quotes().await?;
+            Ok(format!("已重新載入 {} 筆最後交易日的報價", count))
+        }
+        "stocks" => {
+            let count = SHARE.reload_stocks().await?;
+            Ok(format!("已重新載入 {} 檔股票", count))
+        }
+        "all" => {
+            SHARE.load().await;
+            TTL.clear();
+            Ok("已重新載入所有快取".to_string())
+        }
+        _ => Ok(USAGE.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_is_admin_command() {
+        assert!(is_admin_command("jobs"));
+        assert!(is_admin_command("config"));
+        assert!(is_admin_command("failed"));
+        assert!(is_admin_command("roles"));
+        assert!(!is_admin_command("top10"));
+    }
+}
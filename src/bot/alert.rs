@@ -0,0 +1,238 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+
+use crate::{
+    bot::{subscription::Event, telegram},
+    config::SETTINGS,
+    declare::StockSymbol,
+    publisher::{self, Alert},
+};
+
+/// 追踪股票超出高低標的價格警示
+pub const PRICE_ALERT: &str = "price_alert";
+/// 庫存或追踪股票的重大訊息
+pub const ANNOUNCEMENT: &str = "announcement";
+/// 庫存或追踪股票新出現的策略訊號
+pub const SIGNAL: &str = "signal";
+/// 庫存股票的外資持股比率一週內大幅減少
+pub const QFII_DROP: &str = "qfii_drop";
+/// 庫存股票跌破停損價或突破停利價
+pub const PROTECTION: &str = "protection";
+/// 動態 DNS 更新失敗
+pub const DDNS_FAILURE: &str = "ddns_failure";
+
+static AGGREGATOR: Lazy<Mutex<Aggregator>> = Lazy::new(|| Mutex::new(Aggregator::default()));
+
+/// 依規則發送警示，相同內容在去重時間內只發送一次，設定為摘要的規則先暫存再由 flush_digests 彙整送出
+///
+/// 警示都屬於非緊急的通知，會遵守勿擾時段
+pub async fn send(rule: &str, msg: &str) {
+    publisher::publish(publisher::ALERT, [Alert { rule, message: msg }]);
+
+    let now = Instant::now();
+    let dedup = Duration::from_secs(SETTINGS.load().alert.dedup_minutes * 60);
+    let digest = SETTINGS
+        .load()
+        .alert
+        .digest_hours
+        .get(rule)
+        .copied()
+        .unwrap_or(0)
+        > 0;
+
+    let deliver = match AGGREGATOR.lock() {
+        Ok(mut aggregator) => aggregator.offer(rule, msg, now, dedup, digest),
+        Err(_) => true,
+    };
+
+    if deliver {
+        telegram::send_deferrable(msg).await;
+    }
+}
+
+/// 個股的警示依各聊天室以 /subscribe 訂閱的股票與事件發送，items 為(股票代號, 內容)，沒有訂閱的聊天室接收 default 內的股票
+///
+/// 每個聊天室收到的內容不同，所以不經過去重與摘要，呼叫端需確保同一則內容只發送一次
+pub async fn send_subscribed(
+    rule: &str,
+    event: Event,
+    title: &str,
+    items: &[(StockSymbol, String)],
+    default: &HashSet<StockSymbol>,
+) {
+    if items.is_empty() {
+        return;
+    }
+
+    let render = |lines: &[&(StockSymbol, String)]| {
+        let lines: Vec<&str> = lines.iter().map(|(_, line)| line.as_str()).collect();
+        format!("{}\n{}", title, lines.join("\n"))
+    };
+    let msg = render(&items.iter().collect::<Vec<_>>());
+    publisher::publish(
+        publisher::ALERT,
+        [Alert {
+            rule,
+            message: &msg,
+        }],
+    );
+
+    telegram::send_subscribed(
+        event,
+        items,
+        |(symbol, _)| symbol,
+        Some(default),
+        |_, lines| render(lines),
+    )
+    .await;
+}
+
+/// 送出已到達摘要間隔的規則暫存的警示
+pub async fn flush_digests() -> Result<()> {
+    let now = Instant::now();
+    let digests = match AGGREGATOR.lock() {
+        Ok(mut aggregator) => aggregator.due(now, |rule| {
+            SETTINGS
+                .load()
+                .alert
+                .digest_hours
+                .get(rule)
+                .map(|hours| Duration::from_secs(hours * 60 * 60))
+        }),
+        Err(_) => return Ok(()),
+    };
+
+    for digest in digests {
+        telegram::send_digest(&digest).await;
+    }
+
+    Ok(())
+}
+
+/// 不論摘要間隔送出全部暫存的警示，關閉服務前呼叫避免訊息遺失
+pub async fn flush_all_digests() {
+    let digests = match AGGREGATOR.lock() {
+        Ok(mut aggregator) => aggregator.due(Instant::now(), |_| Some(Duration::ZERO)),
+        Err(_) => return,
+    };
+
+    for digest in digests {
+        telegram::send_digest(&digest).await;
+    }
+}
+
+/// 記錄最近發送過的警示與各規則暫存的摘要
+#[derive(Debug, Default)]
+struct Aggregator {
+    /// (規則, 內容) 最後一次收到的時間
+    seen: HashMap<(String, String), Instant>,
+    /// 規則暫存的警示與開始暫存的時間
+    pending: HashMap<String, (Instant, Vec<String>)>,
+}
+
+impl Aggregator {
+    /// 收到一則警示，回傳是否要立即發送
+    fn offer(
+        &mut self,
+        rule: &str,
+        msg: &str,
+        now: Instant,
+        dedup: Duration,
+        digest: bool,
+    ) -> bool {
+        self.seen
+            .retain(|_, seen| now.saturating_duration_since(*seen) < dedup);
+        let key = (rule.to_string(), msg.to_string());
+        if !dedup.is_zero() && self.seen.contains_key(&key) {
+            return false;
+        }
+        self.seen.insert(key, now);
+
+        if !digest {
+            return true;
+        }
+
+        self.pending
+            .entry(rule.to_string())
+            .or_insert_with(|| (now, Vec::new()))
+            .1
+            .push(msg.to_string());
+        false
+    }
+
+    /// 取出已到達摘要間隔的規則並組成訊息，interval 回傳 None 的規則(設定已移除)立即送出
+    fn due(&mut self, now: Instant, interval: impl Fn(&str) -> Option<Duration>) -> Vec<String> {
+        let ready: Vec<String> = self
+            .pending
+            .iter()
+            .filter(|(rule, (since, _))| {
+                interval(rule)
+                    .is_none_or(|interval| now.saturating_duration_since(*since) >= interval)
+            })
+            .map(|(rule, _)| rule.clone())
+            .collect();
+
+        let mut digests: Vec<String> = ready
+            .into_iter()
+            .filter_map(|rule| {
+                let (_, messages) = self.pending.remove(&rule)?;
+                Some(format!(
+                    "{} 摘要({} 則)\n\n{}",
+                    rule,
+                    messages.len(),
+                    messages.join("\n\n")
+                ))
+            })
+            .collect();
+        digests.sort();
+        digests
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_offer_dedup() {
+        let mut aggregator = Aggregator::default();
+        let now = Instant::now();
+        let window = Duration::from_secs(60);
+
+        assert!(aggregator.offer(PRICE_ALERT, "2330 突破", now, window, false));
+        assert!(!aggregator.offer(PRICE_ALERT, "2330 突破", now, window, false));
+        assert!(aggregator.offer(PRICE_ALERT, "2317 跌破", now, window, false));
+        assert!(aggregator.offer(ANNOUNCEMENT, "2330 突破", now, window, false));
+        assert!(aggregator.offer(PRICE_ALERT, "2330 突破", now + window, window, false));
+        assert!(aggregator.offer(ANNOUNCEMENT, "x", now, Duration::ZERO, false));
+        assert!(aggregator.offer(ANNOUNCEMENT, "x", now, Duration::ZERO, false));
+    }
+
+    #[test]
+    fn test_digest() {
+        let mut aggregator = Aggregator::default();
+        let now = Instant::now();
+        let window = Duration::from_secs(60);
+        let hour = Duration::from_secs(60 * 60);
+
+        assert!(!aggregator.offer(PRICE_ALERT, "a", now, window, true));
+        assert!(!aggregator.offer(PRICE_ALERT, "a", now, window, true));
+        assert!(!aggregator.offer(PRICE_ALERT, "b", now, window, true));
+
+        assert!(aggregator.due(now, |_| Some(hour)).is_empty());
+
+        let digests = aggregator.due(now + hour, |_| Some(hour));
+        assert_eq!(
+            digests,
+            vec!["price_alert 摘要(2 則)\n\na\n\nb".to_string()]
+        );
+        assert!(aggregator.due(now + hour * 2, |_| Some(hour)).is_empty());
+    }
+}
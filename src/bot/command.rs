@@ -0,0 +1,1171 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{Datelike, Local, Months, NaiveDate, TimeDelta};
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use rust_decimal_macros::dec;
+
+use crate::{
+    bot::{
+        admin, intent, lot, note, paper, protection,
+        role::{self, Role},
+        settings, subscription,
+        telegram::{
+            self,
+            fmt::{self, Align, Table},
+        },
+    },
+    cache::SHARE,
+    calculation::{
+        self,
+        allocation::{self, Weight},
+        xirr::{self, MemberXirr},
+    },
+    charts::{self, Candle, MovingAverage},
+    crawler,
+    database::{
+        page::{Page, Paged},
+        table::{
+            daily_quote,
+            dividend::{extension::dividend_schedule, Dividend},
+            financial_statement::{self, FinancialStatement},
+            qfii_holding::QfiiHolding,
+            revenue,
+            stock::{
+                self,
+                extension::{
+                    market_cap::SymbolAndMarketCap,
+                    metrics::{self, StockMetrics},
+                },
+            },
+            week52_stat::Week52Stat,
+        },
+    },
+    declare::{Industry, StockExchangeMarket, StockSymbol},
+    event, logging, scheduler,
+    screener::{self, compare},
+};
+
+/// 取得訊息失敗後等待多久再重新輪詢
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+/// /chart 未指定期間時預設的期間
+const DEFAULT_CHART_PERIOD: &str = "6m";
+/// /chart revenue 繪製的月份數
+const REVENUE_CHART_MONTHS: i64 = 24;
+/// /near_high 未指定幅度時預設距 52 週最高價幾個百分點以內
+const DEFAULT_NEAR_HIGH_PERCENT: Decimal = dec!(3);
+/// /near_high 最多列出的股票數量
+const NEAR_HIGH_LIMIT: i64 = 20;
+/// /screen 最多列出的股票數量
+const SCREEN_LIMIT: usize = 30;
+/// /quote 找到多檔相同符合程度的股票時最多列出的數量
+const QUOTE_CANDIDATES: usize = 10;
+/// /dividend 列出近幾年的股利
+const DIVIDEND_YEARS: i32 = 3;
+/// /foreign 列出最近幾個交易日的外資持股
+const FOREIGN_DAYS: i64 = 10;
+/// /history 每頁列出的筆數
+const HISTORY_PAGE_SIZE: i64 = 10;
+/// /fundamentals 列出最近幾季的財報
+const FUNDAMENTAL_QUARTERS: i64 = 4;
+/// 財報的金額單位為仟元，換算為億元
+const THOUSANDS_PER_HUNDRED_MILLION: Decimal = dec!(100000);
+
+/// 聊天室收到的指令 ex. `/top10 marketcap` 的 name 為 top10、args 為 [marketcap]
+#[derive(Debug, PartialEq)]
+pub struct Command {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+impl Command {
+    /// 解析以 / 開頭的訊息，群組內的 `/top10@bot_name` 會去掉 bot 名稱
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.split_whitespace();
+        let name = parts.next()?.strip_prefix('/')?;
+        let name = name.split('@').next().unwrap_or(name).to_lowercase();
+        if name.is_empty() {
+            return None;
+        }
+
+        Some(Command {
+            name,
+            args: parts.map(str::to_string).collect(),
+        })
+    }
+}
+
+/// 指令的回覆內容
+#[derive(Debug, PartialEq)]
+pub enum Reply {
+    Text(String),
+    /// PNG 圖片與說明文字
+    Photo {
+        image: Vec<u8>,
+        caption: String,
+    },
+}
+
+/// 輪詢聊天室的訊息，只回應設定檔 bot.telegram.allowed 內的聊天室與 bot_roles 內的使用者
+/// 各指令依 required_role 限定發送者的角色
+pub async fn listen() {
+    let mut offset = 0;
+
+    loop {
+        // 多個執行個體同時以 getUpdates 輪詢會互相衝突 (409) 並重複回覆，只由 leader 接收指令
+        if !scheduler::leader::is_leader() {
+            tokio::time::sleep(RETRY_DELAY).await;
+            continue;
+        }
+
+        let updates = match telegram::get_updates(offset).await {
+            Ok(updates) => updates,
+            Err(why) => {
+                logging::error_file_async(format!("Failed to get_updates because {:?}", why));
+                tokio::time::sleep(RETRY_DELAY).await;
+                continue;
+            }
+        };
+
+        for update in updates {
+            offset = update.update_id + 1;
+
+            let Some(message) = update.message else {
+                continue;
+            };
+
+            let user_id = message.from.as_ref().map(|user| user.id);
+            let Some(role) = role::resolve(message.chat.id, user_id) else {
+                continue;
+            };
+
+            // 沒有斜線的訊息只在看得出是查詢股票時才回應
+            let Some(command) = message
+                .text
+                .as_deref()
+                .and_then(|text| Command::parse(text).or_else(|| intent::parse(text)))
+            else {
+                continue;
+            };
+
+            let required = required_role(&command);
+            if role < required {
+                telegram::reply(
+                    message.chat.id,
+                    &format!("沒有權限使用 /{}，需要 {} 角色", command.name, required),
+                )
+                .await;
+                continue;
+            }
+
+            let reply = match dispatch(&command, message.chat.id, user_id).await {
+                Ok(reply) => reply,
+                Err(why) => {
+                    logging::error_file_async(format!(
+                        "Failed to dispatch command({:?}) because {:?}",
+                        command, why
+                    ));
+                    Reply::Text(format!("執行 /{} 失敗", command.name))
+                }
+            };
+
+            match reply {
+                Reply::Text(text) => telegram::reply(message.chat.id, &text).await,
+                Reply::Photo { image, caption } => {
+                    telegram::send_photo(message.chat.id, image, &caption).await
+                }
+            }
+        }
+    }
+}
+
+/// 指令需要的角色，管理指令需要 admin，會修改數據的指令需要 trader，其餘的查詢指令 viewer 即可；
+/// 沒有參數的 /subscribe 只列出目前的訂閱，viewer 即可使用
+pub fn required_role(command: &Command) -> Role {
+    match command.name.as_str() {
+        name if admin::is_admin_command(name) => Role::Admin,
+        "note" | "paper" | "sell" | "settings" | "stop" => Role::Trader,
+        "subscribe" | "unsubscribe" if !command.args.is_empty() => Role::Trader,
+        _ => Role::Viewer,
+    }
+}
+
+/// 依指令名稱交給對應的處理函式，回傳要回覆的訊息，chat_id 為聊天室編號，user_id 為發送者的 Telegram 使用者編號
+pub async fn dispatch(command: &Command, chat_id: i64, user_id: Option<i64>) -> Result<Reply> {
+    match command.name.as_str() {
+        "top10" => top10(&command.args).await.map(Reply::Text),
+        "chart" => chart(&command.args).await,
+        "stats" => stats(&command.args).await.map(Reply::Text),
+        "near_high" => near_high(&command.args).await.map(Reply::Text),
+        "industry" => industry(&command.args).await.map(Reply::Text),
+        "screen" => screen(&command.args).await.map(Reply::Text),
+        "quote" => quote(&command.args).await.map(Reply::Text),
+        "dividend" => dividend(&command.args).await.map(Reply::Text),
+        "fundamentals" => fundamentals(&command.args).await.map(Reply::Text),
+        "compare" => compare(&command.args).await.map(Reply::Text),
+        "foreign" => foreign(&command.args).await.map(Reply::Text),
+        "history" => history(&command.args).await.map(Reply::Text),
+        "allocation" => allocation(member(&command.args, chat_id))
+            .await
+            .map(Reply::Text),
+        "rebalance" => rebalance(member(&command.args, chat_id))
+            .await
+            .map(Reply::Text),
+        "xirr" => member_xirr(member(&command.args, chat_id))
+            .await
+            .map(Reply::Text),
+        "paper" => paper::dispatch(&command.args, user_id)
+            .await
+            .map(Reply::Text),
+        "sell" => lot::sell(&command.args, chat_id).await.map(Reply::Text),
+        "realized" => lot::realized(&command.args, chat_id)
+            .await
+            .map(Reply::Text),
+        "note" => note::dispatch(&command.args, chat_id)
+            .await
+            .map(Reply::Text),
+        "settings" => settings::dispatch(&command.args, chat_id)
+            .await
+            .map(Reply::Text),
+        "stop" => protection::dispatch(&command.args, chat_id)
+            .await
+            .map(Reply::Text),
+        "subscribe" => subscription::subscribe(&command.args, chat_id)
+            .await
+            .map(Reply::Text),
+        "unsubscribe" => subscription::unsubscribe(&command.args, chat_id)
+            .await
+            .map(Reply::Text),
+        name if admin::is_admin_command(name) => admin::dispatch(command).await.map(Reply::Text),
+        _ => Ok(Reply::Text(help())),
+    }
+}
+
+fn help() -> String {
+    [
+        "可用的指令:",
+        "/top10 marketcap 市值前十大的股票",
+        "/chart 2330 6m 股價K線圖，期間可用 30d、12w、6m、1y",
+        "/chart 2330 revenue 近兩年月營收",
+        "/stats 2330 52週高低點與回檔幅度",
+        "/near_high 3 收盤價距52週最高價3%以內的股票",
+        "/industry 半導體 列出產業內未下市的上市櫃股票",
+        "/screen yield > 5 && pe < 12 依條件選股",
+        "/quote 台積 以代號或名稱查詢股價",
+        "/dividend 2330 近三年的股利與除權息日",
+        "/fundamentals 2330 近四季的 EPS、現金流量與自由現金流量殖利率",
+        "/compare 2330 2303 2454 並列比較殖利率、本益比、淨值比、營收年增率、EPS 與距季線幅度",
+        "/foreign 2330 近十個交易日的外資持股比率",
+        "/history revenue 2330 2 分頁查詢歷史的收盤價(quote)、月營收(revenue)或股利(dividend)",
+        "/allocation 各成員持股依股票、產業、市值分類的比重，加上 all 忽略預設成員",
+        "/rebalance 依設定檔的目標配置建議各成員買賣的股數，加上 all 忽略預設成員",
+        "/xirr 各成員依資金進出計算的年化報酬率，加上 all 忽略預設成員",
+        "/paper buy 2330 1000 模擬交易，下一個交易日以開盤價成交，/paper 查詢部位與損益",
+        "/sell 2330 1000 950 賣出庫存，依先進先出或 #序號 指定配對的買進批次並記錄已實現損益",
+        "/realized 2024 年度各股票的已實現損益與平均持有天數，加上標籤只統計有該標籤的交易",
+        "/note lot 12 dividend_capture 除權息前買進 為買進批次或賣出明細加上標籤與筆記，/note 列出最近的筆記",
+        "/settings 語系、預設成員、勿擾時段與警示摘要的偏好設定",
+        "/stop 2330 850 1200 設定庫存的停損價與停利價，收盤後觸及時通知，/stop 列出已設定的庫存",
+        "/subscribe 2330 revenue,dividend,announcement 只接收訂閱的股票的月營收、股利與重大訊息通知",
+        "",
+        "也可以直接輸入 2330 營收、台積電 股利、鴻海 股價、2330 K線、2330 52週",
+    ]
+    .join("\n")
+}
+
+async fn top10(args: &[String]) -> Result<String> {
+    match args.first().map(String::as_str) {
+        Some("marketcap") => {
+            let list = SymbolAndMarketCap::fetch_top(10).await?;
+            let mut table = Table::new(&["#", "代號", "名稱", "市值(億)", "收盤價"]).align(&[
+                Align::Right,
+                Align::Left,
+                Align::Left,
+                Align::Right,
+                Align::Right,
+            ]);
+            for (i, stock) in list.iter().enumerate() {
+                // 市值以億元為單位
+                table.row(&[
+                    (i + 1).to_string(),
+                    stock.stock_symbol.to_string(),
+                    stock.name.clone(),
+                    fmt::number(stock.market_cap / dec!(100000000), 0),
+                    stock.closing_price.normalize().to_string(),
+                ]);
+            }
+
+            Ok(format!("市值前十大\n{}", table.render()))
+        }
+        _ => Ok("用法: /top10 marketcap".to_string()),
+    }
+}
+
+async fn chart(args: &[String]) -> Result<Reply> {
+    const USAGE: &str = "用法: /chart 2330 6m 或 /chart 2330 revenue";
+
+    let Some(symbol) = args.first() else {
+        return Ok(Reply::Text(USAGE.to_string()));
+    };
+    let title = match SHARE.get_stock(symbol).await {
+        Some(stock) => format!("{} {}", symbol, stock.name),
+        None => symbol.to_string(),
+    };
+    let period = args.get(1).map_or(DEFAULT_CHART_PERIOD, String::as_str);
+
+    if period == "revenue" {
+        let monthly = revenue::fetch_recent_monthly(symbol, REVENUE_CHART_MONTHS).await?;
+        if monthly.is_empty() {
+            return Ok(Reply::Text(format!("查無 {} 的月營收", symbol)));
+        }
+
+        // 月營收以千元為單位，圖表改以百萬元顯示
+        let bars: Vec<(String, f64)> = monthly
+            .iter()
+            .map(|(date, monthly)| {
+                (
+                    format!("{}/{:02}", date / 100, date % 100),
+                    monthly.to_f64().unwrap_or_default() / 1000.0,
+                )
+            })
+            .collect();
+        let caption = format!("{} 月營收(百萬元)", title);
+
+        return Ok(Reply::Photo {
+            image: charts::revenue_chart(&caption, &bars)?,
+            caption,
+        });
+    }
+
+    let Some(since) = parse_since(period, Local::now().date_naive()) else {
+        return Ok(Reply::Text(USAGE.to_string()));
+    };
+    let prices = daily_quote::fetch_daily_prices(symbol, since).await?;
+    if prices.is_empty() {
+        return Ok(Reply::Text(format!("查無 {} 的股價資料", symbol)));
+    }
+
+    let candles: Vec<Candle> = prices
+        .iter()
+        .map(|p| Candle {
+            date: p.date,
+            open: p.opening_price.to_f64().unwrap_or_default(),
+            high: p.highest_price.to_f64().unwrap_or_default(),
+            low: p.lowest_price.to_f64().unwrap_or_default(),
+            close: p.closing_price.to_f64().unwrap_or_default(),
+        })
+        .collect();
+    let averages = [
+        MovingAverage {
+            label: "MA20".to_string(),
+            values: prices
+                .iter()
+                .map(|p| p.moving_average_20.to_f64().unwrap_or_default())
+                .collect(),
+        },
+        MovingAverage {
+            label: "MA60".to_string(),
+            values: prices
+                .iter()
+                .map(|p| p.moving_average_60.to_f64().unwrap_or_default())
+                .collect(),
+        },
+    ];
+    let caption = format!("{} 近 {}", title, period);
+
+    Ok(Reply::Photo {
+        image: charts::price_chart(&caption, &candles, &averages)?,
+        caption,
+    })
+}
+
+async fn stats(args: &[String]) -> Result<String> {
+    let Some(symbol) = args.first() else {
+        return Ok("用法: /stats 2330".to_string());
+    };
+    let Some(stat) = Week52Stat::fetch(symbol).await? else {
+        return Ok(format!("查無 {} 的統計資料", symbol));
+    };
+
+    Ok(format_stats(&stat))
+}
+
+fn format_stats(stat: &Week52Stat) -> String {
+    [
+        format!(
+            "{} {} {}",
+            stat.security_code,
+            fmt::escape_markdown(&stat.name),
+            stat.date
+        ),
+        format!("收盤價: {}", stat.closing_price.normalize()),
+        format!(
+            "52週最高: {} ({}) 距高點 {}",
+            stat.high_price.normalize(),
+            stat.high_date,
+            fmt::percent(stat.distance_from_high)
+        ),
+        format!(
+            "52週最低: {} ({}) 距低點 {}",
+            stat.low_price.normalize(),
+            stat.low_date,
+            fmt::percent(stat.distance_from_low)
+        ),
+        format!(
+            "歷史最高收盤: {} 回檔 {}",
+            stat.peak_price.normalize(),
+            fmt::percent(stat.drawdown)
+        ),
+    ]
+    .join("\n")
+}
+
+async fn near_high(args: &[String]) -> Result<String> {
+    let within = match args.first() {
+        Some(arg) => match arg.trim_end_matches('%').parse::<Decimal>() {
+            Ok(within) if within >= Decimal::ZERO => within,
+            _ => return Ok("用法: /near_high 3".to_string()),
+        },
+        None => DEFAULT_NEAR_HIGH_PERCENT,
+    };
+
+    let list = Week52Stat::fetch_near_high(within, NEAR_HIGH_LIMIT).await?;
+    if list.is_empty() {
+        return Ok(format!(
+            "沒有距52週最高價 {}% 以內的股票",
+            within.normalize()
+        ));
+    }
+
+    let mut table = Table::new(&["代號", "名稱", "收盤價", "距高點"]).align(&[
+        Align::Left,
+        Align::Left,
+        Align::Right,
+        Align::Right,
+    ]);
+    for stat in &list {
+        table.row(&[
+            stat.security_code.to_string(),
+            stat.name.clone(),
+            stat.closing_price.normalize().to_string(),
+            format!("{}%", fmt::number(stat.distance_from_high, 2)),
+        ]);
+    }
+
+    Ok(format!(
+        "距52週最高價 {}% 以內\n{}",
+        within.normalize(),
+        table.render()
+    ))
+}
+
+/// 依產業名稱找出產業分類，名稱完全相同優先，其次為包含輸入文字的第一個產業 ex. 半導體 -> 半導體業
+fn parse_industry(text: &str) -> Option<Industry> {
+    Industry::iterator()
+        .find(|industry| industry.name() == text)
+        .or_else(|| Industry::iterator().find(|industry| industry.name().contains(text)))
+}
+
+async fn industry(args: &[String]) -> Result<String> {
+    let Some(industry) = args.first().and_then(|text| parse_industry(text)) else {
+        return Ok("用法: /industry 半導體".to_string());
+    };
+
+    let list = stock::fetch_by_industry(industry.serial()).await?;
+    if list.is_empty() {
+        return Ok(format!("{} 沒有上市櫃的股票", industry.name()));
+    }
+
+    let mut table = Table::new(&["代號", "名稱", "市場"]);
+    for stock in &list {
+        table.row(&[
+            stock.stock_symbol.to_string(),
+            stock.name.clone(),
+            StockExchangeMarket::from(stock.stock_exchange_market_id)
+                .map(|market| market.name())
+                .unwrap_or_default(),
+        ]);
+    }
+
+    Ok(format!("{} 共 {} 檔\n{}", industry.name(), list.len(), table.render()))
+}
+
+async fn screen(args: &[String]) -> Result<String> {
+    let expr = match screener::parser::parse(&args.join(" ")) {
+        Ok(expr) => expr,
+        Err(why) => return Ok(format!(
+            "條件錯誤: {}\n用法: /screen yield > 5 && pe < 12 && revenue_yoy > 0\n可用的指標: {}",
+            why,
+            screener::field_names()
+        )),
+    };
+
+    let list = screener::screen(&expr).await?;
+    if list.is_empty() {
+        return Ok("沒有符合條件的股票".to_string());
+    }
+
+    let mut table =
+        Table::new(&["代號", "名稱", "收盤價"]).align(&[Align::Left, Align::Left, Align::Right]);
+    for stock in list.iter().take(SCREEN_LIMIT) {
+        table.row(&[
+            stock.stock_symbol.to_string(),
+            stock.name.clone(),
+            stock.closing_price.normalize().to_string(),
+        ]);
+    }
+
+    let mut text = format!("符合條件共 {} 檔\n{}", list.len(), table.render());
+    if list.len() > SCREEN_LIMIT {
+        text.push_str(&format!("\n僅列出前 {} 檔", SCREEN_LIMIT));
+    }
+
+    Ok(text)
+}
+
+async fn quote(args: &[String]) -> Result<String> {
+    let query = args.join(" ");
+    if query.trim().is_empty() {
+        return Ok("用法: /quote 2330 或 /quote 台積".to_string());
+    }
+
+    let matches = stock::search(&query, QUOTE_CANDIDATES);
+    let Some(first) = matches.first() else {
+        return Ok(format!("查無符合 {} 的股票", fmt::escape_markdown(&query)));
+    };
+
+    // 符合程度相同的有多檔時列出候選，讓使用者改用代號查詢
+    if matches
+        .get(1)
+        .is_some_and(|second| second.score == first.score)
+    {
+        let candidates: Vec<String> = matches
+            .iter()
+            .filter(|m| m.score == first.score)
+            .map(|m| format!("{} {}", m.stock_symbol, fmt::escape_markdown(&m.name)))
+            .collect();
+        return Ok(format!(
+            "符合 {} 的股票有\n{}\n請以代號查詢",
+            fmt::escape_markdown(&query),
+            candidates.join("\n")
+        ));
+    }
+
+    let title = format!(
+        "{} {}",
+        first.stock_symbol,
+        fmt::escape_markdown(&first.name)
+    );
+    let symbol = StockSymbol::parse(&first.stock_symbol)?;
+    if let Ok(quotes) = crawler::fetch_stock_quotes_from_remote_site(&symbol).await {
+        return Ok(format!(
+            "{}\n股價 {} 漲跌 {} ({}%)",
+            title, quotes.price, quotes.change, quotes.change_range
+        ));
+    }
+
+    match SHARE.get_stock_last_price(&first.stock_symbol).await {
+        Some(last) => Ok(format!(
+            "{}\n{} 收盤價 {}",
+            title,
+            last.date,
+            last.closing_price.normalize()
+        )),
+        None => Ok(format!("{}\n查無報價", title)),
+    }
+}
+
+async fn dividend(args: &[String]) -> Result<String> {
+    let Some(symbol) = args.first() else {
+        return Ok("用法: /dividend 2330".to_string());
+    };
+    let since = Local::now().year() - DIVIDEND_YEARS + 1;
+    let schedules = dividend_schedule::fetch(std::slice::from_ref(symbol), since).await?;
+    let Some(first) = schedules.first() else {
+        return Ok(format!("查無 {} 近 {} 年的股利", symbol, DIVIDEND_YEARS));
+    };
+
+    let mut table = Table::new(&["年度", "現金", "股票", "除息日", "發放日"]).align(&[
+        Align::Left,
+        Align::Right,
+        Align::Right,
+        Align::Left,
+        Align::Left,
+    ]);
+    for schedule in &schedules {
+        table.row(&[
+            format!("{}{}", schedule.year_of_dividend, schedule.quarter),
+            schedule.cash_dividend.normalize().to_string(),
+            schedule.stock_dividend.normalize().to_string(),
+            schedule.ex_dividend_date1.clone(),
+            schedule.payable_date1.clone(),
+        ]);
+    }
+
+    Ok(format!(
+        "{} {} 股利\n{}",
+        first.stock_symbol,
+        fmt::escape_markdown(&first.name),
+        table.render()
+    ))
+}
+
+async fn fundamentals(args: &[String]) -> Result<String> {
+    let Some(symbol) = args.first() else {
+        return Ok("用法: /fundamentals 2330".to_string());
+    };
+    let Some(metrics) = metrics::fetch_one(symbol).await? else {
+        return Ok(format!("查無 {} 的數據", symbol));
+    };
+    let quarters = financial_statement::fetch_recent_quarters(symbol, FUNDAMENTAL_QUARTERS).await?;
+
+    Ok(format_fundamentals(&metrics, &quarters))
+}
+
+fn format_fundamentals(metrics: &StockMetrics, quarters: &[FinancialStatement]) -> String {
+    let hundred_million = |value: Decimal| fmt::number(value / THOUSANDS_PER_HUNDRED_MILLION, 2);
+    let mut lines = vec![
+        format!(
+            "{} {} 基本面",
+            metrics.stock_symbol,
+            fmt::escape_markdown(&metrics.name)
+        ),
+        format!(
+            "近四季 EPS: {} ROE: {}%",
+            metrics.last_four_eps.normalize(),
+            fmt::number(metrics.return_on_equity, 2)
+        ),
+        format!(
+            "近四季自由現金流量: {}",
+            metrics
+                .free_cash_flow
+                .map(|fcf| format!("{} 億", hundred_million(fcf)))
+                .unwrap_or_else(|| "-".to_string())
+        ),
+        format!(
+            "自由現金流量殖利率: {}",
+            metrics
+                .fcf_yield
+                .map(|fcf_yield| format!("{}%", fmt::number(fcf_yield, 2)))
+                .unwrap_or_else(|| "-".to_string())
+        ),
+    ];
+
+    if !quarters.is_empty() {
+        let mut table = Table::new(&["季度", "EPS", "營業", "投資", "籌資", "自由"]).align(&[
+            Align::Left,
+            Align::Right,
+            Align::Right,
+            Align::Right,
+            Align::Right,
+            Align::Right,
+        ]);
+        for fs in quarters {
+            table.row(&[
+                format!("{}{}", fs.year, fs.quarter),
+                fs.earnings_per_share.normalize().to_string(),
+                hundred_million(fs.operating_cash_flow),
+                hundred_million(fs.investing_cash_flow),
+                hundred_million(fs.financing_cash_flow),
+                hundred_million(fs.free_cash_flow()),
+            ]);
+        }
+        lines.push("現金流量(億，當年度累計)".to_string());
+        lines.push(table.render());
+    }
+
+    lines.join("\n")
+}
+
+async fn compare(args: &[String]) -> Result<String> {
+    if args.len() < 2 || args.len() > compare::MAX_SYMBOLS {
+        return Ok(format!(
+            "用法: /compare 2330 2303 2454，可比較 2~{} 檔股票",
+            compare::MAX_SYMBOLS
+        ));
+    }
+
+    let (list, missing) = compare::fetch(args).await?;
+    let mut text = format_compare(&list);
+    if !missing.is_empty() {
+        text.push_str(&format!("\n查無 {} 的數據", missing.join("、")));
+    }
+
+    Ok(text)
+}
+
+/// 股票為欄、指標為列的並列比較表
+fn format_compare(list: &[StockMetrics]) -> String {
+    if list.is_empty() {
+        return "查無可比較的股票".to_string();
+    }
+
+    let mut headers = vec!["".to_string()];
+    headers.extend(list.iter().map(|m| m.stock_symbol.to_string()));
+    let mut aligns = vec![Align::Left];
+    aligns.extend(list.iter().map(|_| Align::Right));
+    let mut table = Table::new(&headers).align(&aligns);
+
+    let mut names = vec!["名稱".to_string()];
+    names.extend(list.iter().map(|m| m.name.clone()));
+    table.row(&names);
+
+    let values: Vec<[String; 6]> = list.iter().map(compare::values).collect();
+    for (i, label) in compare::LABELS.iter().enumerate() {
+        let mut row = vec![label.to_string()];
+        row.extend(values.iter().map(|v| v[i].clone()));
+        table.row(&row);
+    }
+
+    table.render()
+}
+
+async fn foreign(args: &[String]) -> Result<String> {
+    let Some(symbol) = args.first() else {
+        return Ok("用法: /foreign 2330".to_string());
+    };
+    let trend = QfiiHolding::fetch_trend(symbol, FOREIGN_DAYS).await?;
+    if trend.is_empty() {
+        return Ok(format!("查無 {} 的外資持股紀錄", symbol));
+    }
+
+    let mut table = Table::new(&["日期", "持股比率", "增減", "持股(張)"]).align(&[
+        Align::Left,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+    ]);
+    let mut previous: Option<Decimal> = None;
+    for holding in &trend {
+        let change = previous
+            .map(|p| (holding.holding_percentage - p).normalize().to_string())
+            .unwrap_or_else(|| "-".to_string());
+        table.row(&[
+            holding.date.format("%m/%d").to_string(),
+            format!("{}%", holding.holding_percentage.normalize()),
+            change,
+            fmt::thousands(holding.shares_held / 1000),
+        ]);
+        previous = Some(holding.holding_percentage);
+    }
+
+    let name = SHARE
+        .get_stock(symbol)
+        .await
+        .map(|stock| stock.name)
+        .unwrap_or_default();
+
+    Ok(format!(
+        "{} {} 外資持股\n{}",
+        symbol,
+        fmt::escape_markdown(&name),
+        table.render()
+    ))
+}
+
+async fn history(args: &[String]) -> Result<String> {
+    let (Some(kind), Some(symbol)) = (args.first(), args.get(1)) else {
+        return Ok("用法: /history quote|revenue|dividend 2330 [頁數]".to_string());
+    };
+    let number = args
+        .get(2)
+        .and_then(|number| number.parse::<i64>().ok())
+        .unwrap_or(1);
+    let page = Page::nth(number, HISTORY_PAGE_SIZE, "");
+
+    let (title, table, position) = match kind.as_str() {
+        "quote" => {
+            let paged = daily_quote::fetch_page(symbol, &page).await?;
+            let mut table = Table::new(&["日期", "收盤", "漲跌", "成交(張)"]).align(&[
+                Align::Left,
+                Align::Right,
+                Align::Right,
+                Align::Right,
+            ]);
+            for dq in &paged.items {
+                table.row(&[
+                    dq.date.format("%Y-%m-%d").to_string(),
+                    dq.closing_price.normalize().to_string(),
+                    dq.change.normalize().to_string(),
+                    fmt::number(dq.trading_volume / dec!(1000), 0),
+                ]);
+            }
+            ("收盤價", table, paging(&paged))
+        }
+        "revenue" => {
+            let paged = revenue::fetch_page(symbol, &page).await?;
+            let mut table = Table::new(&["月份", "營收(千元)", "年增率"]).align(&[
+                Align::Left,
+                Align::Right,
+                Align::Right,
+            ]);
+            for r in &paged.items {
+                table.row(&[
+                    r.date.to_string(),
+                    fmt::number(r.monthly, 0),
+                    format!("{}%", r.compared_with_last_year_same_month.round_dp(2)),
+                ]);
+            }
+            ("月營收", table, paging(&paged))
+        }
+        "dividend" => {
+            let paged = Dividend::fetch_page(symbol, &page).await?;
+            let mut table = Table::new(&["年度", "現金", "股票", "除息日"]).align(&[
+                Align::Left,
+                Align::Right,
+                Align::Right,
+                Align::Left,
+            ]);
+            for d in &paged.items {
+                table.row(&[
+                    format!("{}{}", d.year_of_dividend, d.quarter),
+                    d.cash_dividend.normalize().to_string(),
+                    d.stock_dividend.normalize().to_string(),
+                    d.ex_dividend_date1.clone(),
+                ]);
+            }
+            ("股利", table, paging(&paged))
+        }
+        _ => return Ok("用法: /history quote|revenue|dividend 2330 [頁數]".to_string()),
+    };
+
+    if table.is_empty() {
+        return Ok(format!("查無 {} 第 {} 頁的{}", symbol, number, title));
+    }
+
+    let name = SHARE
+        .get_stock(symbol)
+        .await
+        .map(|stock| stock.name)
+        .unwrap_or_default();
+
+    Ok(format!(
+        "{} {} {} {}\n{}",
+        symbol,
+        fmt::escape_markdown(&name),
+        title,
+        position,
+        table.render()
+    ))
+}
+
+/// 頁數說明 ex. 第 2/5 頁，還有下一頁時提示指令
+fn paging<T>(paged: &Paged<T>) -> String {
+    match paged.has_more() {
+        true => format!(
+            "第 {}/{} 頁，下一頁加上 {}",
+            paged.number(),
+            paged.pages(),
+            paged.number() + 1
+        ),
+        false => format!("第 {}/{} 頁", paged.number(), paged.pages()),
+    }
+}
+
+/// 查詢的成員，參數為 all 時查詢全部成員，否則使用聊天室以 /settings 設定的預設成員
+fn member(args: &[String], chat_id: i64) -> Option<i64> {
+    match args.first().map(String::as_str) {
+        Some("all") => None,
+        _ => settings::member(chat_id),
+    }
+}
+
+async fn allocation(member_id: Option<i64>) -> Result<String> {
+    let mut allocations = allocation::calculate().await?;
+    if let Some(member_id) = member_id {
+        allocations.retain(|allocation| allocation.member_id == member_id);
+    }
+    if allocations.is_empty() {
+        return Ok("目前沒有庫存".to_string());
+    }
+
+    let sections: Vec<String> = allocations
+        .iter()
+        .map(|member| {
+            let mut section = format!(
+                "成員 {} 市值 {}\n{}\n{}\n{}",
+                member.member_id,
+                fmt::number(member.total, 0),
+                weights_table("股票", &member.by_stock),
+                weights_table("產業", &member.by_sector),
+                weights_table("市值", &member.by_cap)
+            );
+            for warning in &member.warnings {
+                section.push_str(&format!("\n⚠️ {}", fmt::escape_markdown(warning)));
+            }
+            section
+        })
+        .collect();
+
+    Ok(sections.join("\n\n"))
+}
+
+fn weights_table(caption: &str, weights: &[Weight]) -> String {
+    let mut table =
+        Table::new(&[caption, "市值", "比重"]).align(&[Align::Left, Align::Right, Align::Right]);
+    for weight in weights {
+        table.row(&[
+            weight.label.clone(),
+            fmt::number(weight.market_value, 0),
+            format!("{}%", fmt::number(weight.percent, 2)),
+        ]);
+    }
+
+    table.render()
+}
+
+async fn rebalance(member_id: Option<i64>) -> Result<String> {
+    let mut plans = calculation::rebalance::calculate().await?;
+    if let Some(member_id) = member_id {
+        plans.retain(|plan| plan.member_id == member_id);
+    }
+    if plans.is_empty() {
+        return Ok("尚未設定目標配置(rebalance.targets)或目前沒有庫存".to_string());
+    }
+
+    Ok(event::taiwan_stock::rebalance::compose(&plans))
+}
+
+async fn member_xirr(member_id: Option<i64>) -> Result<String> {
+    let mut results = xirr::calculate(Local::now().date_naive()).await?;
+    if let Some(member_id) = member_id {
+        results.retain(|result| result.member_id == member_id);
+    }
+    if results.is_empty() {
+        return Ok("尚未記錄任何資金進出".to_string());
+    }
+
+    Ok(format!("年化報酬率(XIRR)\n{}", xirr_table(&results)))
+}
+
+/// 成員的淨投入、市值與 XIRR，月報也使用同一個表格
+pub fn xirr_table(results: &[MemberXirr]) -> String {
+    let mut table = Table::new(&["成員", "淨投入", "市值", "XIRR"]).align(&[
+        Align::Left,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+    ]);
+    for result in results {
+        table.row(&[
+            result.member_id.to_string(),
+            fmt::number(result.net_contribution, 0),
+            fmt::number(result.market_value, 0),
+            result
+                .rate
+                .map(|rate| format!("{}%", fmt::number(rate, 2)))
+                .unwrap_or_else(|| "-".to_string()),
+        ]);
+    }
+
+    table.render()
+}
+
+/// 將 30d、12w、6m、1y 這類期間換算為起始日期
+fn parse_since(period: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let unit = period.chars().last()?;
+    let amount: u32 = period[..period.len() - unit.len_utf8()].parse().ok()?;
+    if amount == 0 {
+        return None;
+    }
+
+    match unit.to_ascii_lowercase() {
+        'd' => today.checked_sub_signed(TimeDelta::try_days(amount.into())?),
+        'w' => today.checked_sub_signed(TimeDelta::try_weeks(amount.into())?),
+        'm' => today.checked_sub_months(Months::new(amount)),
+        'y' => today.checked_sub_months(Months::new(amount.checked_mul(12)?)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Command::parse("/top10 marketcap"),
+            Some(Command {
+                name: "top10".to_string(),
+                args: vec!["marketcap".to_string()],
+            })
+        );
+        assert_eq!(
+            Command::parse("/Top10@stock_bot  marketcap ").map(|c| c.name),
+            Some("top10".to_string())
+        );
+        assert_eq!(Command::parse("top10 marketcap"), None);
+        assert_eq!(Command::parse("/"), None);
+        assert_eq!(Command::parse(""), None);
+    }
+
+    #[test]
+    fn test_required_role() {
+        let required_role = |text| required_role(&Command::parse(text).unwrap());
+        assert_eq!(required_role("/quote"), Role::Viewer);
+        assert_eq!(required_role("/paper"), Role::Trader);
+        assert_eq!(required_role("/stop"), Role::Trader);
+        assert_eq!(required_role("/sell"), Role::Trader);
+        assert_eq!(required_role("/note"), Role::Trader);
+        assert_eq!(required_role("/subscribe 2330 revenue"), Role::Trader);
+        assert_eq!(required_role("/unsubscribe 2330"), Role::Trader);
+        assert_eq!(required_role("/subscribe"), Role::Viewer);
+        assert_eq!(required_role("/realized"), Role::Viewer);
+        assert_eq!(required_role("/jobs"), Role::Admin);
+        assert_eq!(required_role("/roles"), Role::Admin);
+    }
+
+    #[test]
+    fn test_format_stats() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let stat = Week52Stat {
+            security_code: "2330".parse().unwrap(),
+            name: "台積電".to_string(),
+            date: date(2024, 8, 30),
+            closing_price: dec!(944),
+            high_price: dec!(1080),
+            high_date: date(2024, 7, 11),
+            low_price: dec!(540),
+            low_date: date(2023, 10, 26),
+            distance_from_high: dec!(-12.5926),
+            distance_from_low: dec!(74.8148),
+            peak_price: dec!(1075),
+            drawdown: dec!(-12.1860),
+        };
+
+        let text = format_stats(&stat);
+
+        assert!(text.starts_with("2330 台積電 2024-08-30\n收盤價: 944\n"));
+        assert!(text.contains("52週最高: 1080 (2024-07-11) 距高點 🔻-12.59%\n"));
+        assert!(text.contains("52週最低: 540 (2023-10-26) 距低點 🔺74.81%\n"));
+        assert!(text.ends_with("歷史最高收盤: 1075 回檔 🔻-12.19%"));
+    }
+
+    #[test]
+    fn test_format_fundamentals() {
+        let metrics = StockMetrics {
+            stock_symbol: "2330".parse().unwrap(),
+            name: "台積電".to_string(),
+            last_four_eps: dec!(39.2),
+            return_on_equity: dec!(28.45),
+            free_cash_flow: Some(dec!(870276000)),
+            fcf_yield: Some(dec!(3.2156)),
+            ..Default::default()
+        };
+        let mut fs = FinancialStatement::new("2330".parse().unwrap());
+        fs.year = 2024;
+        fs.quarter = "Q2".to_string();
+        fs.earnings_per_share = dec!(9.56);
+        fs.operating_cash_flow = dec!(870085000);
+        fs.investing_cash_flow = dec!(-409587000);
+        fs.financing_cash_flow = dec!(-125734000);
+
+        let text = format_fundamentals(&metrics, &[fs]);
+
+        assert!(text.starts_with("2330 台積電 基本面\n近四季 EPS: 39.2 ROE: 28.45%\n"));
+        assert!(text.contains("近四季自由現金流量: 8,702.76 億\n自由現金流量殖利率: 3.22%\n"));
+        assert!(text.contains("2024Q2"));
+        assert!(text.contains("4,604.98"));
+        assert_eq!(
+            format_fundamentals(&StockMetrics::default(), &[])
+                .lines()
+                .last(),
+            Some("自由現金流量殖利率: -")
+        );
+    }
+
+    #[test]
+    fn test_format_compare() {
+        let tsmc = StockMetrics {
+            stock_symbol: "2330".parse().unwrap(),
+            name: "台積電".to_string(),
+            closing_price: dec!(1100),
+            moving_average_60: dec!(1000),
+            last_four_eps: dec!(45.25),
+            dividend_yield: Some(dec!(1.64)),
+            ..Default::default()
+        };
+        let umc = StockMetrics {
+            stock_symbol: "2303".parse().unwrap(),
+            name: "聯電".to_string(),
+            ..Default::default()
+        };
+
+        let text = format_compare(&[tsmc, umc]);
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(
+            lines[1].split_whitespace().collect::<Vec<_>>(),
+            ["2330", "2303"]
+        );
+        assert_eq!(
+            lines[3].split_whitespace().collect::<Vec<_>>(),
+            ["殖利率(%)", "1.64", "-"]
+        );
+        assert_eq!(
+            lines[8].split_whitespace().collect::<Vec<_>>(),
+            ["距季線(%)", "10.00", "-"]
+        );
+        assert_eq!(format_compare(&[]), "查無可比較的股票");
+    }
+
+    #[test]
+    fn test_parse_industry() {
+        assert_eq!(parse_industry("半導體業"), Some(Industry::Semiconductor));
+        assert_eq!(parse_industry("半導體"), Some(Industry::Semiconductor));
+        assert_eq!(parse_industry("其他"), Some(Industry::Other));
+        assert_eq!(parse_industry("不存在"), None);
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let today = NaiveDate::from_ymd_opt(2024, 8, 31).unwrap();
+
+        assert_eq!(
+            parse_since("30d", today),
+            NaiveDate::from_ymd_opt(2024, 8, 1)
+        );
+        assert_eq!(
+            parse_since("2w", today),
+            NaiveDate::from_ymd_opt(2024, 8, 17)
+        );
+        assert_eq!(
+            parse_since("6m", today),
+            NaiveDate::from_ymd_opt(2024, 2, 29)
+        );
+        assert_eq!(
+            parse_since("1Y", today),
+            NaiveDate::from_ymd_opt(2023, 8, 31)
+        );
+        assert_eq!(parse_since("0m", today), None);
+        assert_eq!(parse_since("m", today), None);
+        assert_eq!(parse_since("6x", today), None);
+        assert_eq!(parse_since("", today), None);
+    }
+
+    #[test]
+    fn test_xirr_table() {
+        let table = xirr_table(&[
+            MemberXirr {
+                member_id: 1,
+                net_contribution: dec!(1000000),
+                market_value: dec!(1250000),
+                rate: Some(dec!(8.5)),
+            },
+            MemberXirr {
+                member_id: 2,
+                net_contribution: dec!(0),
+                market_value: dec!(0),
+                rate: None,
+            },
+        ]);
+
+        assert!(table.contains("1,250,000"));
+        assert!(table.contains("8.50%"));
+        assert!(table
+            .lines()
+            .any(|line| line.contains('2') && line.trim_end().ends_with('-')));
+    }
+}
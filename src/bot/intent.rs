@@ -0,0 +1,118 @@
+use crate::{bot::command::Command, database::table::stock};
+
+/// 查詢時常夾帶但不屬於股票名稱的字詞
+const FILLERS: [&str; 6] = ["現在", "目前", "今天", "呢", "?", "？"];
+
+/// 不需要斜線的快速查詢 ex. `2330 營收`、`台積電 股利`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Intent {
+    Quote,
+    Revenue,
+    Dividend,
+    Chart,
+    Stats,
+}
+
+impl Intent {
+    /// 訊息內出現任一關鍵字即視為該查詢，依序比對
+    const KEYWORDS: [(Intent, &'static [&'static str]); 5] = [
+        (Intent::Revenue, &["營收"]),
+        (Intent::Dividend, &["股利", "股息", "配息", "除息"]),
+        (Intent::Chart, &["K線", "k線", "線圖", "走勢"]),
+        (Intent::Stats, &["52週", "高低點"]),
+        (Intent::Quote, &["股價", "報價", "多少"]),
+    ];
+
+    /// 轉為對應的指令，symbol 為搜尋後的股票代號
+    fn to_command(self, symbol: String) -> Command {
+        let (name, args) = match self {
+            Intent::Quote => ("quote", vec![symbol]),
+            Intent::Revenue => ("chart", vec![symbol, "revenue".to_string()]),
+            Intent::Dividend => ("dividend", vec![symbol]),
+            Intent::Chart => ("chart", vec![symbol]),
+            Intent::Stats => ("stats", vec![symbol]),
+        };
+
+        Command {
+            name: name.to_string(),
+            args,
+        }
+    }
+}
+
+/// 解析沒有斜線的訊息，找不到關鍵字或股票時回傳 None，不回應一般的聊天內容
+pub fn parse(text: &str) -> Option<Command> {
+    let (intent, query) = classify(text)?;
+    let symbol = stock::search(&query, 1).into_iter().next()?.stock_symbol;
+
+    Some(intent.to_command(symbol.to_string()))
+}
+
+/// 取出查詢的種類與去掉關鍵字後的股票代號或名稱
+///
+/// 沒有關鍵字時只有整則訊息像股票代號(數字開頭的 4~6 碼)才當成查詢股價
+pub fn classify(text: &str) -> Option<(Intent, String)> {
+    let text = text.trim();
+    if text.is_empty() || text.starts_with('/') {
+        return None;
+    }
+
+    for (intent, keywords) in Intent::KEYWORDS {
+        if let Some(keyword) = keywords.iter().find(|k| text.contains(*k)) {
+            let query = FILLERS
+                .iter()
+                .fold(text.replacen(keyword, " ", 1), |query, filler| {
+                    query.replace(filler, " ")
+                });
+            let query = query.split_whitespace().collect::<Vec<_>>().join(" ");
+            return (!query.is_empty()).then_some((intent, query));
+        }
+    }
+
+    let looks_like_symbol = (4..=6).contains(&text.len())
+        && text.starts_with(|c: char| c.is_ascii_digit())
+        && text.chars().all(|c| c.is_ascii_alphanumeric());
+    looks_like_symbol.then(|| (Intent::Quote, text.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            classify("2330 營收"),
+            Some((Intent::Revenue, "2330".to_string()))
+        );
+        assert_eq!(
+            classify(" 台積電股利 "),
+            Some((Intent::Dividend, "台積電".to_string()))
+        );
+        assert_eq!(
+            classify("2330 K線"),
+            Some((Intent::Chart, "2330".to_string()))
+        );
+        assert_eq!(
+            classify("鴻海現在多少?"),
+            Some((Intent::Quote, "鴻海".to_string()))
+        );
+        assert_eq!(
+            classify("00878"),
+            Some((Intent::Quote, "00878".to_string()))
+        );
+        assert_eq!(classify("營收"), None);
+        assert_eq!(classify("晚餐吃什麼"), None);
+        assert_eq!(classify("2024"), Some((Intent::Quote, "2024".to_string())));
+        assert_eq!(classify("/quote 2330"), None);
+    }
+
+    #[test]
+    fn test_to_command() {
+        let command = Intent::Revenue.to_command("2330".to_string());
+
+        assert_eq!(command.name, "chart");
+        assert_eq!(command.args, vec!["2330", "revenue"]);
+    }
+}
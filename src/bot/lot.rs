@@ -0,0 +1,290 @@
+use std::{collections::BTreeSet, str::FromStr};
+
+use anyhow::Result;
+use chrono::{Datelike, Local, NaiveDate};
+use rust_decimal::Decimal;
+
+use crate::{
+    bot::{
+        settings,
+        telegram::fmt::{self, Align, Table},
+    },
+    calculation::lot::{self, Method, Summary, TagRealized},
+    database::table::{stock_lot_sale::StockLotSale, stock_ownership_details},
+};
+
+const USAGE: &str = "賣出與已實現損益:
+/sell 2330 1000 950 以每股 950 賣出 1000 股，依先進先出配對買進批次
+/sell 2330 1000 950 #12 #15 只依序配對指定的批次(庫存序號)
+/realized 2024 年度各股票的已實現損益與持有天數，省略年度為今年，加上 all 忽略預設成員
+/realized 2024 dividend_capture 只統計有這個標籤(/note)的賣出明細與買進批次
+設定了預設成員時只賣出該成員的庫存，多位成員持有同一檔股票時需先以 /settings member 指定";
+
+/// 執行 /sell 指令
+pub async fn sell(args: &[String], chat_id: i64) -> Result<String> {
+    let Some((symbol, quantity, price, method)) = parse_sell(args) else {
+        return Ok(USAGE.to_string());
+    };
+
+    let lots = stock_ownership_details::fetch_open_lots(&symbol, settings::member(chat_id)).await?;
+    if lots.is_empty() {
+        return Ok(format!("找不到 {} 的庫存", symbol));
+    }
+    let members: BTreeSet<i64> = lots.iter().map(|lot| lot.member_id).collect();
+    if members.len() > 1 {
+        return Ok(format!(
+            "{} 有多位成員({})的庫存，請先以 /settings member 指定成員",
+            symbol,
+            members
+                .iter()
+                .map(i64::to_string)
+                .collect::<Vec<_>>()
+                .join("、")
+        ));
+    }
+
+    let today = Local::now().date_naive();
+    let Some(sales) = lot::sell(&lots, quantity, price, today, &method).await? else {
+        let held: i64 = lots.iter().map(|lot| lot.share_quantity).sum();
+        return Ok(match method {
+            Method::Fifo => format!("{} 可賣出的股數只有 {}", symbol, fmt::thousands(held)),
+            Method::Specific(_) => "指定的批次不存在或股數不足".to_string(),
+        });
+    };
+
+    Ok(format_sales(&sales))
+}
+
+/// 解析 `2330 1000 950` 或 `2330 1000 950 #12 #15`
+fn parse_sell(args: &[String]) -> Option<(String, i64, Decimal, Method)> {
+    let [symbol, quantity, price, serials @ ..] = args else {
+        return None;
+    };
+    let quantity = quantity.parse::<i64>().ok().filter(|q| *q > 0)?;
+    let price = Decimal::from_str(price)
+        .ok()
+        .filter(|price| *price > Decimal::ZERO)?;
+    let method = if serials.is_empty() {
+        Method::Fifo
+    } else {
+        Method::Specific(
+            serials
+                .iter()
+                .map(|serial| serial.strip_prefix('#').unwrap_or(serial).parse().ok())
+                .collect::<Option<Vec<i64>>>()?,
+        )
+    };
+
+    Some((symbol.to_uppercase(), quantity, price, method))
+}
+
+/// 賣出後各批次的配對明細
+fn format_sales(sales: &[StockLotSale]) -> String {
+    let mut table = Table::new(&["批次", "買進日", "股數", "成本", "損益", "天數"]).align(&[
+        Align::Right,
+        Align::Left,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+    ]);
+    for sale in sales {
+        table.row(&[
+            format!("#{}", sale.lot_serial),
+            sale.buy_date.format("%Y-%m-%d").to_string(),
+            fmt::thousands(sale.quantity),
+            fmt::number(sale.cost, 0),
+            fmt::number(sale.realized, 0),
+            sale.holding_days.to_string(),
+        ]);
+    }
+
+    let quantity: i64 = sales.iter().map(|sale| sale.quantity).sum();
+    let fee: Decimal = sales.iter().map(|sale| sale.fee).sum();
+    let realized: Decimal = sales.iter().map(|sale| sale.realized).sum();
+    let symbol = sales
+        .first()
+        .map(|sale| sale.security_code.as_str())
+        .unwrap_or_default();
+
+    let serials: Vec<String> = sales
+        .iter()
+        .map(|sale| format!("#{}", sale.serial))
+        .collect();
+
+    format!(
+        "已賣出 {} {} 股，已實現損益 {}(費用 {})\n{}\n賣出明細 {}，可用 /note sale 序號 標籤 筆記 記錄這筆交易",
+        symbol,
+        fmt::thousands(quantity),
+        fmt::number(realized, 0),
+        fmt::number(fee, 0),
+        table.render(),
+        serials.join("、")
+    )
+}
+
+/// 執行 /realized 指令
+pub async fn realized(args: &[String], chat_id: i64) -> Result<String> {
+    let mut member_id = settings::member(chat_id);
+    let mut year = Local::now().year();
+    let mut tag = None;
+    for arg in args {
+        if arg == "all" {
+            member_id = None;
+        } else if let Ok(value) = arg.parse::<i32>() {
+            year = value;
+        } else {
+            tag = Some(arg.to_lowercase());
+        }
+    }
+    let (Some(start), Some(end)) = (
+        NaiveDate::from_ymd_opt(year, 1, 1),
+        NaiveDate::from_ymd_opt(year, 12, 31),
+    ) else {
+        return Ok(USAGE.to_string());
+    };
+
+    let (mut sales, notes) = lot::fetch_with_notes(member_id, start, end).await?;
+    if let Some(tag) = &tag {
+        sales.retain(|sale| lot::tags(sale, &notes).contains(tag.as_str()));
+    }
+    if sales.is_empty() {
+        return Ok(match tag {
+            Some(tag) => format!(
+                "{} 年沒有標籤 {} 的賣出記錄",
+                year,
+                fmt::escape_markdown(&tag)
+            ),
+            None => format!("{} 年沒有賣出記錄", year),
+        });
+    }
+
+    let mut text = format!(
+        "{} 年已實現損益{}\n{}",
+        year,
+        tag.map(|tag| format!("(標籤 {})", fmt::escape_markdown(&tag)))
+            .unwrap_or_default(),
+        format_summaries(&lot::summarize(&sales))
+    );
+    let by_tag = lot::realized_by_tag(&sales, &notes);
+    if by_tag.iter().any(|t| t.tag != lot::UNTAGGED) {
+        text.push_str(&format!("\n\n依標籤\n{}", tag_table(&by_tag)));
+    }
+
+    Ok(text)
+}
+
+/// 各標籤的已實現損益，月報也使用同一個表格
+pub fn tag_table(list: &[TagRealized]) -> String {
+    let mut table = Table::new(&["標籤", "筆數", "損益", "報酬"]).align(&[
+        Align::Left,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+    ]);
+    for t in list {
+        table.row(&[
+            t.tag.clone(),
+            t.sales.to_string(),
+            fmt::number(t.realized, 0),
+            format!("{}%", fmt::number(t.return_rate(), 2)),
+        ]);
+    }
+
+    table.render()
+}
+
+/// 各股票的已實現損益、報酬率與依股數加權的平均持有天數
+fn format_summaries(summaries: &[Summary]) -> String {
+    let mut table = Table::new(&["代號", "股數", "損益", "報酬", "平均天數"]).align(&[
+        Align::Left,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+    ]);
+    for summary in summaries {
+        table.row(&[
+            summary.security_code.clone(),
+            fmt::thousands(summary.quantity),
+            fmt::number(summary.realized, 0),
+            format!("{}%", fmt::number(summary.return_rate(), 2)),
+            summary.average_holding_days.normalize().to_string(),
+        ]);
+    }
+
+    let realized: Decimal = summaries.iter().map(|summary| summary.realized).sum();
+    let fee: Decimal = summaries.iter().map(|summary| summary.fee).sum();
+
+    format!(
+        "{}\n合計 {}，費用 {}",
+        table.render(),
+        fmt::number(realized, 0),
+        fmt::number(fee, 0)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_parse_sell() {
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            parse_sell(&args(&["2330", "1000", "950"])),
+            Some(("2330".to_string(), 1000, dec!(950), Method::Fifo))
+        );
+        assert_eq!(
+            parse_sell(&args(&["00878", "500", "21.5", "#12", "15"])),
+            Some((
+                "00878".to_string(),
+                500,
+                dec!(21.5),
+                Method::Specific(vec![12, 15])
+            ))
+        );
+        assert_eq!(parse_sell(&args(&["2330", "0", "950"])), None);
+        assert_eq!(parse_sell(&args(&["2330", "1000", "-1"])), None);
+        assert_eq!(parse_sell(&args(&["2330", "1000", "950", "#a"])), None);
+        assert_eq!(parse_sell(&args(&["2330", "1000"])), None);
+    }
+
+    #[test]
+    fn test_format_summaries() {
+        let summaries = vec![Summary {
+            security_code: "2330".to_string(),
+            quantity: 2000,
+            cost: dec!(1000000),
+            fee: dec!(3000),
+            realized: dec!(75000),
+            average_holding_days: dec!(67.8),
+            ..Default::default()
+        }];
+
+        let text = format_summaries(&summaries);
+
+        assert!(text.contains("2,000"));
+        assert!(text.contains("7.50%"));
+        assert!(text.contains("67.8"));
+        assert!(text.ends_with("合計 75,000，費用 3,000"));
+    }
+
+    #[test]
+    fn test_tag_table() {
+        let table = tag_table(&[TagRealized {
+            tag: "dividend_capture".to_string(),
+            sales: 2,
+            cost: dec!(200000),
+            realized: dec!(5000),
+        }]);
+
+        assert!(table.contains("dividend_capture"));
+        assert!(table.contains("5,000"));
+        assert!(table.contains("2.50%"));
+    }
+}
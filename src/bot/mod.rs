@@ -1 +1,41 @@
+use async_trait::async_trait;
+
+/// 限管理員使用的聊天室指令
+pub mod admin;
+/// 警示通知的去重與摘要
+pub mod alert;
+/// 聊天室指令
+pub mod command;
+/// 不需要斜線的快速查詢
+pub mod intent;
+/// 賣出配對買進批次與已實現損益的指令
+pub mod lot;
+/// 交易筆記與標籤的指令
+pub mod note;
+/// 模擬交易指令
+pub mod paper;
+/// 庫存的停損與停利設定
+pub mod protection;
+/// 指令的使用權限
+pub mod role;
+/// 各聊天室的偏好設定
+pub mod settings;
+/// 各聊天室訂閱的個股通知
+pub mod subscription;
 pub mod telegram;
+
+/// 訊息的發送管道，報表等功能透過它送出訊息而不直接綁定 Telegram
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn notify(&self, message: &str);
+}
+
+/// 發送到設定檔 bot.telegram.allowed 內的聊天室
+pub struct TelegramNotifier;
+
+#[async_trait]
+impl Notifier for TelegramNotifier {
+    async fn notify(&self, message: &str) {
+        telegram::send(message).await;
+    }
+}
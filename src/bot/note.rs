@@ -0,0 +1,170 @@
+use anyhow::Result;
+
+use crate::{
+    bot::{settings, telegram::fmt},
+    database::table::trade_note::{self, TradeNote},
+};
+
+const USAGE: &str = "交易筆記:
+/note 最近的筆記
+/note lot 12 dividend_capture 除權息前買進 為庫存序號 12 的買進批次加上標籤與筆記，標籤為 - 時只記錄筆記
+/note sale 31 earnings_play 法說會後獲利了結 為 /sell 回覆的賣出明細加上標籤與筆記
+/note tag dividend_capture 有這個標籤的筆記
+/note delete 5 刪除筆記
+設定了預設成員時只能標記與查詢該成員的交易，/realized 2024 dividend_capture 可依標籤統計已實現損益";
+/// 一次列出的筆記數量
+const LIST_LIMIT: i64 = 20;
+/// 標籤的最大長度，與 trade_notes.tag 欄位一致
+const MAX_TAG_LENGTH: usize = 32;
+/// 筆記的最大長度，與 trade_notes.note 欄位一致
+const MAX_NOTE_LENGTH: usize = 512;
+
+/// 執行 /note 指令
+pub async fn dispatch(args: &[String], chat_id: i64) -> Result<String> {
+    let member_id = settings::member(chat_id);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match args.as_slice() {
+        [] => list(member_id, None).await,
+        ["tag", tag] => list(member_id, Some(&tag.to_lowercase())).await,
+        ["delete", serial] => match serial.trim_start_matches('#').parse() {
+            Ok(serial) => match TradeNote::delete(serial, member_id).await? {
+                true => Ok(format!("已刪除筆記 #{}", serial)),
+                false => Ok(format!("找不到筆記 #{}", serial)),
+            },
+            Err(_) => Ok(USAGE.to_string()),
+        },
+        ["lot" | "sale", ..] => match parse(&args) {
+            Some(note) => add(note, member_id).await,
+            None => Ok(USAGE.to_string()),
+        },
+        _ => Ok(USAGE.to_string()),
+    }
+}
+
+/// 解析 `lot 12 dividend_capture 除權息前買進`，標籤為 - 時沒有標籤，標籤與筆記不能都是空的
+fn parse(args: &[&str]) -> Option<TradeNote> {
+    let [target @ ("lot" | "sale"), serial, tag, note @ ..] = args else {
+        return None;
+    };
+    let target = match *target {
+        "lot" => trade_note::LOT,
+        _ => trade_note::SALE,
+    };
+    let serial = serial.trim_start_matches('#').parse().ok()?;
+    let tag = match *tag {
+        "-" => String::new(),
+        tag => tag.to_lowercase(),
+    };
+    let note = note.join(" ");
+    if (tag.is_empty() && note.is_empty())
+        || tag.chars().count() > MAX_TAG_LENGTH
+        || note.chars().count() > MAX_NOTE_LENGTH
+    {
+        return None;
+    }
+
+    Some(TradeNote::new(target, serial, tag, note))
+}
+
+async fn add(note: TradeNote, member_id: Option<i64>) -> Result<String> {
+    match note.insert(member_id).await? {
+        Some(saved) => Ok(format!("已新增筆記\n{}", format_note(&saved))),
+        None => Ok(format!(
+            "找不到{} #{}",
+            if note.target == trade_note::LOT {
+                "庫存批次"
+            } else {
+                "賣出明細"
+            },
+            note.target_serial
+        )),
+    }
+}
+
+async fn list(member_id: Option<i64>, tag: Option<&str>) -> Result<String> {
+    let notes = TradeNote::fetch_recent(member_id, tag, LIST_LIMIT).await?;
+    if notes.is_empty() {
+        return Ok(format!("沒有符合的筆記\n\n{}", USAGE));
+    }
+
+    Ok(notes.iter().map(format_note).collect::<Vec<_>>().join("\n"))
+}
+
+/// 筆記一行 ex. #5 2024-07-01 2330 批次 #12 [dividend\_capture] 除權息前買進
+pub fn format_note(note: &TradeNote) -> String {
+    let mut line = format!(
+        "#{} {} {} {} #{}",
+        note.serial,
+        note.created_date,
+        note.security_code,
+        if note.target == trade_note::LOT {
+            "批次"
+        } else {
+            "賣出"
+        },
+        note.target_serial
+    );
+    if !note.tag.is_empty() {
+        line.push_str(&format!(" [{}]", fmt::escape_markdown(&note.tag)));
+    }
+    if !note.note.is_empty() {
+        line.push(' ');
+        line.push_str(&fmt::escape_markdown(&note.note));
+    }
+
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse(&["lot", "12", "Dividend_Capture", "除權息前", "買進"]),
+            Some(TradeNote::new(
+                trade_note::LOT,
+                12,
+                "dividend_capture".to_string(),
+                "除權息前 買進".to_string()
+            ))
+        );
+        assert_eq!(
+            parse(&["sale", "#31", "-", "獲利了結"]),
+            Some(TradeNote::new(
+                trade_note::SALE,
+                31,
+                String::new(),
+                "獲利了結".to_string()
+            ))
+        );
+        assert_eq!(parse(&["lot", "12", "-"]), None);
+        assert_eq!(parse(&["lot", "abc", "tag"]), None);
+        assert_eq!(parse(&["lot", "12"]), None);
+        assert_eq!(parse(&["lot", "12", &"x".repeat(33)]), None);
+    }
+
+    #[test]
+    fn test_format_note() {
+        let note = TradeNote {
+            serial: 5,
+            target: trade_note::LOT.to_string(),
+            target_serial: 12,
+            member_id: 1,
+            security_code: "2330".parse().unwrap(),
+            tag: "dividend_capture".to_string(),
+            note: "除權息前買進".to_string(),
+            created_date: NaiveDate::from_ymd_opt(2024, 7, 1).unwrap(),
+        };
+
+        assert_eq!(
+            format_note(&note),
+            "#5 2024-07-01 2330 批次 #12 [dividend\\_capture] 除權息前買進"
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use anyhow::Result;
+use chrono::Local;
+use rust_decimal::Decimal;
+
+use crate::{
+    bot::telegram::fmt::{self, Align, Table},
+    cache::SHARE,
+    calculation::paper_trading::{self, Position},
+    database::table::{
+        paper_order::{self, PaperOrder},
+        stock,
+    },
+};
+
+const USAGE: &str = "模擬交易:
+/paper 目前的部位與損益
+/paper buy 2330 1000 下一個交易日以開盤價買進 1000 股，最後加上 close 改以收盤價成交
+/paper sell 2330 1000 close 下一個交易日以收盤價賣出 1000 股
+/paper orders 等待成交的委託
+/paper cancel 12 取消等待成交的委託";
+
+/// 執行模擬交易指令，帳戶以發送者的 Telegram 使用者編號區分
+pub async fn dispatch(args: &[String], account_id: Option<i64>) -> Result<String> {
+    let Some(account_id) = account_id else {
+        return Ok("無法辨識發送者，不能使用模擬交易".to_string());
+    };
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match args.as_slice() {
+        [] | ["positions"] => positions(account_id).await,
+        ["orders"] => orders(account_id).await,
+        ["cancel", serial] => match serial.parse() {
+            Ok(serial) => cancel(account_id, serial).await,
+            Err(_) => Ok(USAGE.to_string()),
+        },
+        [side @ ("buy" | "sell"), symbol, quantity, rest @ ..] => {
+            let price_type = match rest {
+                [] | ["open"] => paper_order::OPEN,
+                ["close"] => paper_order::CLOSE,
+                _ => return Ok(USAGE.to_string()),
+            };
+            match quantity.parse::<i64>() {
+                Ok(quantity) if quantity > 0 => {
+                    place(account_id, side, symbol, quantity, price_type).await
+                }
+                _ => Ok(USAGE.to_string()),
+            }
+        }
+        _ => Ok(USAGE.to_string()),
+    }
+}
+
+async fn place(
+    account_id: i64,
+    side: &str,
+    symbol: &str,
+    quantity: i64,
+    price_type: &str,
+) -> Result<String> {
+    let Some(found) = stock::search(symbol, 1).into_iter().next() else {
+        return Ok(format!("查無符合 {} 的股票", fmt::escape_markdown(symbol)));
+    };
+
+    if side == paper_order::SELL {
+        let orders = PaperOrder::fetch_by_account(account_id).await?;
+        let sellable = paper_trading::sellable(&orders, &found.stock_symbol);
+        if quantity > sellable {
+            return Ok(format!(
+                "{} 可賣出的股數只有 {}",
+                found.stock_symbol,
+                fmt::number(Decimal::from(sellable), 0)
+            ));
+        }
+    }
+
+    let order = PaperOrder::new(
+        account_id,
+        found.stock_symbol.clone(),
+        side,
+        quantity,
+        price_type,
+        Local::now().date_naive(),
+    );
+    let serial = order.insert().await?;
+
+    Ok(format!(
+        "委託 #{} 已登記，下一個交易日以{}{} {} {} 股",
+        serial,
+        if price_type == paper_order::CLOSE {
+            "收盤價"
+        } else {
+            "開盤價"
+        },
+        if side == paper_order::BUY {
+            "買進"
+        } else {
+            "賣出"
+        },
+        found.stock_symbol,
+        fmt::number(Decimal::from(quantity), 0)
+    ))
+}
+
+async fn positions(account_id: i64) -> Result<String> {
+    let orders = PaperOrder::fetch_by_account(account_id).await?;
+    let positions = paper_trading::positions(&orders);
+    if positions.is_empty() {
+        return Ok(format!("尚未有成交的模擬交易\n\n{}", USAGE));
+    }
+
+    let mut prices = Vec::with_capacity(positions.len());
+    for position in &positions {
+        let price = SHARE
+            .get_stock_last_price(&position.security_code)
+            .await
+            .map(|last| last.closing_price);
+        prices.push(price);
+    }
+
+    Ok(render_positions(&positions, &prices))
+}
+
+/// 部位表格與合計的損益，prices 為各部位的最後收盤價，查無報價時以平均成本計算
+fn render_positions(positions: &[Position], prices: &[Option<Decimal>]) -> String {
+    let mut table = Table::new(&["股票", "股數", "均價", "未實現", "已實現"]).align(&[
+        Align::Left,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+    ]);
+    let mut unrealized = Decimal::ZERO;
+    let mut realized = Decimal::ZERO;
+    for (position, price) in positions.iter().zip(prices) {
+        let price = price.unwrap_or_else(|| position.average_cost());
+        let pnl = position.unrealized(price);
+        unrealized += pnl;
+        realized += position.realized;
+        table.row(&[
+            position.security_code.clone(),
+            fmt::number(Decimal::from(position.quantity), 0),
+            fmt::number(position.average_cost(), 2),
+            fmt::number(pnl, 0),
+            fmt::number(position.realized, 0),
+        ]);
+    }
+
+    format!(
+        "模擬交易部位\n{}\n未實現損益 {}、已實現損益 {}",
+        table.render(),
+        fmt::number(unrealized, 0),
+        fmt::number(realized, 0)
+    )
+}
+
+async fn orders(account_id: i64) -> Result<String> {
+    let orders = PaperOrder::fetch_by_account(account_id).await?;
+    let pending: Vec<&PaperOrder> = orders
+        .iter()
+        .filter(|order| order.status == paper_order::PENDING)
+        .collect();
+    if pending.is_empty() {
+        return Ok("沒有等待成交的委託".to_string());
+    }
+
+    let mut table = Table::new(&["#", "股票", "買賣", "股數", "價格", "下單日"]).align(&[
+        Align::Right,
+        Align::Left,
+        Align::Left,
+        Align::Right,
+        Align::Left,
+        Align::Left,
+    ]);
+    for order in pending {
+        table.row(&[
+            order.serial.to_string(),
+            order.security_code.to_string(),
+            order.side.clone(),
+            fmt::number(Decimal::from(order.quantity), 0),
+            order.price_type.clone(),
+            order.order_date.to_string(),
+        ]);
+    }
+
+    Ok(format!("等待成交的委託\n{}", table.render()))
+}
+
+async fn cancel(account_id: i64, serial: i64) -> Result<String> {
+    Ok(if PaperOrder::cancel(account_id, serial).await? {
+        format!("委託 #{} 已取消", serial)
+    } else {
+        format!("查無可取消的委託 #{}", serial)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_render_positions() {
+        let positions = vec![
+            Position {
+                security_code: "2330".to_string(),
+                quantity: 1000,
+                cost: dec!(500000),
+                realized: dec!(2000),
+            },
+            Position {
+                security_code: "2317".to_string(),
+                quantity: 0,
+                cost: dec!(0),
+                realized: dec!(-500),
+            },
+        ];
+
+        let text = render_positions(&positions, &[Some(dec!(520)), None]);
+
+        assert!(text.contains("未實現損益 20,000、已實現損益 1,500"));
+    }
+}
@@ -0,0 +1,178 @@
+use std::str::FromStr;
+
+use anyhow::Result;
+use chrono::Local;
+use rust_decimal::Decimal;
+
+use crate::{
+    bot::{
+        settings,
+        telegram::fmt::{Align, Table},
+    },
+    cache::SHARE,
+    database::table::stock_ownership_details::{self, StockOwnershipDetail},
+    event::taiwan_stock::protection::{self, Breach},
+};
+
+const USAGE: &str = "停損與停利:
+/stop 列出設定了停損或停利價的庫存
+/stop 2330 850 1200 設定 2330 全部庫存的停損價 850、停利價 1200(設定了預設成員時只更新該成員)
+/stop #123 850 以庫存序號只設定一筆，停利價省略或為 - 時不變更，0 代表取消
+收盤後檢查，當日收盤價跌破停損價或突破停利價時通知";
+
+/// 要設定的庫存
+#[derive(Debug, Clone, PartialEq)]
+enum Target {
+    /// 庫存序號
+    Serial(i64),
+    /// 股票代號
+    Symbol(String),
+}
+
+/// 執行 /stop 指令
+pub async fn dispatch(args: &[String], chat_id: i64) -> Result<String> {
+    if args.is_empty() {
+        return list(settings::member(chat_id)).await;
+    }
+
+    let Some((target, stop_loss, take_profit)) = parse(args) else {
+        return Ok(USAGE.to_string());
+    };
+    let (serial, symbol, member_id) = match &target {
+        Target::Serial(serial) => (Some(*serial), String::new(), None),
+        Target::Symbol(symbol) => (None, symbol.clone(), settings::member(chat_id)),
+    };
+
+    let count =
+        StockOwnershipDetail::update_protection(serial, &symbol, member_id, stop_loss, take_profit)
+            .await?;
+    if count == 0 {
+        return Ok("找不到符合的庫存".to_string());
+    }
+
+    let mut reply = format!("已更新 {} 筆庫存的停損與停利價", count);
+    if let Target::Symbol(symbol) = &target {
+        if let Some(price) = SHARE.get_stock_last_price(symbol).await {
+            let breached = protection::breach(
+                stop_loss.unwrap_or_default(),
+                take_profit.unwrap_or_default(),
+                price.closing_price,
+            );
+            match breached {
+                Some(Breach::StopLoss) => reply.push_str(&format!(
+                    "\n⚠️ 最新收盤價 {} 已低於停損價",
+                    price.closing_price.normalize()
+                )),
+                Some(Breach::TakeProfit) => reply.push_str(&format!(
+                    "\n⚠️ 最新收盤價 {} 已高於停利價",
+                    price.closing_price.normalize()
+                )),
+                None => {}
+            }
+        }
+    }
+
+    Ok(reply)
+}
+
+/// 解析 `2330 850 1200` 或 `#123 850 -`，價格為 - 時不變更，省略停利價時不變更
+fn parse(args: &[String]) -> Option<(Target, Option<Decimal>, Option<Decimal>)> {
+    let (target, stop_loss, take_profit) = match args {
+        [target, stop_loss] => (target, stop_loss.as_str(), "-"),
+        [target, stop_loss, take_profit] => (target, stop_loss.as_str(), take_profit.as_str()),
+        _ => return None,
+    };
+
+    let target = match target.strip_prefix('#') {
+        Some(serial) => Target::Serial(serial.parse().ok()?),
+        None => Target::Symbol(target.to_uppercase()),
+    };
+    let price = |text: &str| -> Option<Option<Decimal>> {
+        if text == "-" {
+            return Some(None);
+        }
+        Decimal::from_str(text)
+            .ok()
+            .filter(|price| *price >= Decimal::ZERO)
+            .map(Some)
+    };
+
+    let (stop_loss, take_profit) = (price(stop_loss)?, price(take_profit)?);
+    if stop_loss.is_none() && take_profit.is_none() {
+        return None;
+    }
+
+    Some((target, stop_loss, take_profit))
+}
+
+async fn list(member_id: Option<i64>) -> Result<String> {
+    let mut positions = stock_ownership_details::fetch_protected(Local::now().date_naive()).await?;
+    if let Some(member_id) = member_id {
+        positions.retain(|p| p.member_id == member_id);
+    }
+    if positions.is_empty() {
+        return Ok(format!("沒有設定停損或停利價的庫存\n{}", USAGE));
+    }
+
+    let mut table = Table::new(&["#", "代號", "成員", "成本", "停損", "停利"]).align(&[
+        Align::Right,
+        Align::Left,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+    ]);
+    let level = |price: Decimal| {
+        if price > Decimal::ZERO {
+            price.normalize().to_string()
+        } else {
+            "-".to_string()
+        }
+    };
+    for p in &positions {
+        table.row(&[
+            p.serial.to_string(),
+            p.security_code.to_string(),
+            p.member_id.to_string(),
+            p.share_price_average.round_dp(2).normalize().to_string(),
+            level(p.stop_loss_price),
+            level(p.take_profit_price),
+        ]);
+    }
+
+    Ok(table.render())
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            parse(&args(&["2330", "850", "1200"])),
+            Some((
+                Target::Symbol("2330".to_string()),
+                Some(dec!(850)),
+                Some(dec!(1200))
+            ))
+        );
+        assert_eq!(
+            parse(&args(&["#123", "-", "0"])),
+            Some((Target::Serial(123), None, Some(Decimal::ZERO)))
+        );
+        assert_eq!(
+            parse(&args(&["00878", "20.5"])),
+            Some((Target::Symbol("00878".to_string()), Some(dec!(20.5)), None))
+        );
+        assert_eq!(parse(&args(&["2330", "-", "-"])), None);
+        assert_eq!(parse(&args(&["#abc", "850"])), None);
+        assert_eq!(parse(&args(&["2330", "-1"])), None);
+        assert_eq!(parse(&args(&["2330"])), None);
+    }
+}
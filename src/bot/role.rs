@@ -0,0 +1,105 @@
+use std::{collections::HashMap, fmt, sync::RwLock};
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+
+use crate::{config::SETTINGS, database::table::bot_role::BotRole};
+
+/// 指令的使用權限，後面的角色包含前面角色的全部權限
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    /// 只能查詢
+    Viewer,
+    /// 另外可以模擬交易與修改聊天室的偏好
+    Trader,
+    /// 另外可以使用管理指令
+    Admin,
+}
+
+impl Role {
+    pub fn parse(text: &str) -> Option<Role> {
+        match text {
+            "viewer" => Some(Role::Viewer),
+            "trader" => Some(Role::Trader),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Trader => "trader",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 以 /roles 指令授予的角色，啟動時由 load 從資料庫載入
+static GRANTED: Lazy<RwLock<HashMap<i64, Role>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// 從資料庫載入全部使用者的角色
+pub async fn load() -> Result<()> {
+    let roles = BotRole::fetch_all().await?;
+    if let Ok(mut granted) = GRANTED.write() {
+        *granted = roles
+            .iter()
+            .filter_map(|role| Some((role.user_id, Role::parse(&role.role)?)))
+            .collect();
+    }
+
+    Ok(())
+}
+
+/// 更新記憶體內使用者的角色，role 為 None 時移除
+pub fn cache(user_id: i64, role: Option<Role>) {
+    if let Ok(mut granted) = GRANTED.write() {
+        match role {
+            Some(role) => granted.insert(user_id, role),
+            None => granted.remove(&user_id),
+        };
+    }
+}
+
+/// 發送者在聊天室可以使用的角色，None 表示不回應
+pub fn resolve(chat_id: i64, user_id: Option<i64>) -> Option<Role> {
+    let granted = user_id.and_then(|id| GRANTED.read().ok()?.get(&id).copied());
+    let is_admin = user_id.is_some_and(|id| SETTINGS.load().bot.telegram.admins.contains(&id));
+    let allowed = SETTINGS.load().bot.telegram.allowed.contains_key(&chat_id);
+
+    decide(granted, is_admin, allowed)
+}
+
+/// 設定檔 bot.telegram.admins 內的使用者一律為 admin，避免誤改角色後無法管理；
+/// 其次為 bot_roles 授予的角色，不限聊天室；都沒有時設定檔 bot.telegram.allowed 內的聊天室維持原本可以使用管理以外指令的 trader
+fn decide(granted: Option<Role>, is_admin: bool, allowed: bool) -> Option<Role> {
+    if is_admin {
+        return Some(Role::Admin);
+    }
+
+    granted.or(allowed.then_some(Role::Trader))
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_decide() {
+        assert_eq!(decide(None, false, false), None);
+        assert_eq!(decide(None, false, true), Some(Role::Trader));
+        assert_eq!(decide(Some(Role::Viewer), false, true), Some(Role::Viewer));
+        assert_eq!(decide(Some(Role::Viewer), false, false), Some(Role::Viewer));
+        assert_eq!(decide(Some(Role::Viewer), true, false), Some(Role::Admin));
+        assert!(Role::Viewer < Role::Trader && Role::Trader < Role::Admin);
+        assert_eq!(Role::parse("trader"), Some(Role::Trader));
+        assert_eq!(Role::parse("root"), None);
+    }
+}
@@ -0,0 +1,174 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use anyhow::Result;
+use chrono::NaiveTime;
+use once_cell::sync::Lazy;
+
+use crate::{config::QuietHours, database::table::bot_user_setting::BotUserSetting, i18n};
+
+const USAGE: &str = "偏好設定:
+/settings 目前的設定
+/settings language en 通知的語系，default 改回設定檔的語系
+/settings member 1 /allocation、/xirr 預設查詢的成員，all 查詢全部成員
+/settings quiet 23:00-08:00 勿擾時段，off 關閉，default 改回設定檔的時段
+/settings digest off 不接收彙整後的警示摘要，on 恢復接收";
+
+/// 各聊天室的偏好，啟動時由 load 從資料庫載入，通知與查詢都從這裡取得以免每次都查詢資料庫
+static USER_SETTINGS: Lazy<RwLock<HashMap<i64, BotUserSetting>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// 從資料庫載入全部聊天室的偏好
+pub async fn load() -> Result<()> {
+    let settings = BotUserSetting::fetch_all().await?;
+    if let Ok(mut cached) = USER_SETTINGS.write() {
+        *cached = settings
+            .into_iter()
+            .map(|setting| (setting.chat_id, setting))
+            .collect();
+    }
+
+    Ok(())
+}
+
+fn get(chat_id: i64) -> Option<BotUserSetting> {
+    USER_SETTINGS
+        .read()
+        .ok()
+        .and_then(|settings| settings.get(&chat_id).cloned())
+}
+
+/// 聊天室以 /settings 設定的語系，未設定時為 None
+pub fn language(chat_id: i64) -> Option<String> {
+    get(chat_id)
+        .map(|setting| setting.language)
+        .filter(|language| !language.is_empty())
+}
+
+/// 聊天室以 /settings 設定的勿擾時段，未設定時為 None
+pub fn quiet_hours(chat_id: i64) -> Option<QuietHours> {
+    let setting = get(chat_id)?;
+    Some(QuietHours {
+        start: setting.quiet_start?,
+        end: setting.quiet_end?,
+    })
+}
+
+/// 聊天室是否接收彙整後的警示摘要，未設定時接收
+pub fn digest(chat_id: i64) -> bool {
+    get(chat_id).is_none_or(|setting| setting.digest)
+}
+
+/// 聊天室預設查詢的成員，未設定時為 None 表示全部成員
+pub fn member(chat_id: i64) -> Option<i64> {
+    get(chat_id)
+        .map(|setting| setting.member_id)
+        .filter(|member_id| *member_id > 0)
+}
+
+/// 執行偏好設定指令，偏好以聊天室區分，私人聊天室即為發送者自己的偏好
+pub async fn dispatch(args: &[String], chat_id: i64) -> Result<String> {
+    let current = get(chat_id).unwrap_or_else(|| BotUserSetting::new(chat_id));
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    if args.is_empty() {
+        return Ok(describe(&current));
+    }
+
+    let Some(setting) = apply(current, &args) else {
+        return Ok(USAGE.to_string());
+    };
+
+    setting.upsert().await?;
+    let text = describe(&setting);
+    if let Ok(mut settings) = USER_SETTINGS.write() {
+        settings.insert(chat_id, setting);
+    }
+
+    Ok(format!("已更新偏好設定\n{}", text))
+}
+
+/// 依指令修改偏好，指令或值不正確時回傳 None
+fn apply(mut setting: BotUserSetting, args: &[&str]) -> Option<BotUserSetting> {
+    match args {
+        ["language", "default"] => setting.language = String::new(),
+        ["language", language] if i18n::is_supported(language) => {
+            setting.language = language.to_string()
+        }
+        ["member", "all"] => setting.member_id = 0,
+        ["member", member_id] => setting.member_id = member_id.parse().ok().filter(|id| *id > 0)?,
+        ["quiet", "default"] => (setting.quiet_start, setting.quiet_end) = (None, None),
+        // 開始與結束相同的時段不包含任何時間，用來覆蓋設定檔的勿擾時段
+        ["quiet", "off"] => {
+            (setting.quiet_start, setting.quiet_end) = (Some(NaiveTime::MIN), Some(NaiveTime::MIN))
+        }
+        ["quiet", range] => {
+            let (start, end) = range.split_once('-')?;
+            setting.quiet_start = Some(NaiveTime::parse_from_str(start, "%H:%M").ok()?);
+            setting.quiet_end = Some(NaiveTime::parse_from_str(end, "%H:%M").ok()?);
+        }
+        ["digest", "on"] => setting.digest = true,
+        ["digest", "off"] => setting.digest = false,
+        _ => return None,
+    }
+
+    Some(setting)
+}
+
+fn describe(setting: &BotUserSetting) -> String {
+    let language = match setting.language.as_str() {
+        "" => format!("{}(設定檔)", i18n::language(setting.chat_id)),
+        language => language.to_string(),
+    };
+    let member = match setting.member_id {
+        0 => "全部".to_string(),
+        member_id => member_id.to_string(),
+    };
+    let quiet = match (setting.quiet_start, setting.quiet_end) {
+        (Some(start), Some(end)) if start == end => "關閉".to_string(),
+        (Some(start), Some(end)) => format!("{}-{}", start.format("%H:%M"), end.format("%H:%M")),
+        _ => "設定檔".to_string(),
+    };
+    let digest = if setting.digest {
+        "接收"
+    } else {
+        "不接收"
+    };
+
+    format!(
+        "語系: {}\n預設成員: {}\n勿擾時段: {}\n警示摘要: {}",
+        language, member, quiet, digest
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let setting = BotUserSetting::new(42);
+
+        let updated = apply(setting.clone(), &["quiet", "23:00-08:00"]).unwrap();
+        assert_eq!(updated.quiet_start, NaiveTime::from_hms_opt(23, 0, 0));
+        assert_eq!(updated.quiet_end, NaiveTime::from_hms_opt(8, 0, 0));
+
+        let updated = apply(updated, &["quiet", "off"]).unwrap();
+        assert_eq!(updated.quiet_start, updated.quiet_end);
+        assert!(apply(updated, &["quiet", "23:00"]).is_none());
+
+        assert_eq!(
+            apply(setting.clone(), &["member", "2"]).unwrap().member_id,
+            2
+        );
+        assert!(apply(setting.clone(), &["member", "-1"]).is_none());
+        assert!(!apply(setting.clone(), &["digest", "off"]).unwrap().digest);
+        assert_eq!(
+            apply(setting.clone(), &["language", "en"])
+                .unwrap()
+                .language,
+            "en"
+        );
+        assert!(apply(setting.clone(), &["language", "xx"]).is_none());
+        assert!(apply(setting, &["unknown"]).is_none());
+    }
+}
@@ -0,0 +1,263 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    sync::RwLock,
+};
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+
+use crate::{
+    cache::SHARE, database::table::bot_subscription::BotSubscription, declare::StockSymbol,
+};
+
+const USAGE: &str = "個股通知訂閱:
+/subscribe 目前訂閱的股票與事件
+/subscribe 2330 revenue,dividend,announcement 只接收指定股票的月營收、除權息與股利發放、重大訊息，省略事件時訂閱全部
+/unsubscribe 2330 dividend 取消訂閱，省略事件時取消該股票全部的訂閱
+有任何訂閱的聊天室只會收到訂閱的股票與事件，沒有訂閱時維持接收庫存與追踪股票的通知";
+
+/// 可以訂閱的個股事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Event {
+    /// 月營收
+    Revenue,
+    /// 除權息與股利發放
+    Dividend,
+    /// 重大訊息
+    Announcement,
+}
+
+impl Event {
+    pub const ALL: [Event; 3] = [Event::Revenue, Event::Dividend, Event::Announcement];
+
+    pub fn parse(text: &str) -> Option<Event> {
+        match text {
+            "revenue" => Some(Event::Revenue),
+            "dividend" => Some(Event::Dividend),
+            "announcement" => Some(Event::Announcement),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Event::Revenue => "revenue",
+            Event::Dividend => "dividend",
+            Event::Announcement => "announcement",
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 聊天室訂閱的(股票代號, 事件)
+type Subscribed = HashSet<(StockSymbol, Event)>;
+
+/// 各聊天室的訂閱，啟動時由 load 從資料庫載入，發送通知時從這裡過濾以免每次都查詢資料庫
+static SUBSCRIPTIONS: Lazy<RwLock<HashMap<i64, Subscribed>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// 從資料庫載入全部聊天室的訂閱
+pub async fn load() -> Result<()> {
+    let subscriptions = BotSubscription::fetch_all().await?;
+    if let Ok(mut cached) = SUBSCRIPTIONS.write() {
+        cached.clear();
+        for subscription in subscriptions {
+            if let Some(event) = Event::parse(&subscription.event) {
+                cached
+                    .entry(subscription.chat_id)
+                    .or_default()
+                    .insert((subscription.security_code, event));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// 有任何訂閱的聊天室
+pub fn chat_ids() -> Vec<i64> {
+    SUBSCRIPTIONS
+        .read()
+        .map(|cached| cached.keys().copied().collect())
+        .unwrap_or_default()
+}
+
+/// 聊天室是否要接收指定股票的事件，有任何訂閱時只接收訂閱的股票與事件，
+/// 沒有訂閱時依 default 判斷，default 為 None 表示接收全部
+pub fn wanted(
+    chat_id: i64,
+    event: Event,
+    symbol: &StockSymbol,
+    default: Option<&HashSet<StockSymbol>>,
+) -> bool {
+    let subscribed = SUBSCRIPTIONS
+        .read()
+        .ok()
+        .and_then(|cached| cached.get(&chat_id).cloned());
+
+    decide(subscribed.as_ref(), event, symbol, default)
+}
+
+fn decide(
+    subscribed: Option<&Subscribed>,
+    event: Event,
+    symbol: &StockSymbol,
+    default: Option<&HashSet<StockSymbol>>,
+) -> bool {
+    match subscribed {
+        Some(subscribed) if !subscribed.is_empty() => subscribed.contains(&(symbol.clone(), event)),
+        _ => default.is_none_or(|symbols| symbols.contains(symbol)),
+    }
+}
+
+/// 執行 /subscribe 指令，沒有參數時列出聊天室目前的訂閱
+pub async fn subscribe(args: &[String], chat_id: i64) -> Result<String> {
+    let Some((symbol, events)) = parse(args) else {
+        return Ok(if args.is_empty() {
+            describe(chat_id)
+        } else {
+            USAGE.to_string()
+        });
+    };
+    if !SHARE.stock_contains_key(&symbol) {
+        return Ok(format!("找不到股票 {}", symbol));
+    }
+
+    for event in &events {
+        BotSubscription::new(chat_id, symbol.clone(), event.as_str())
+            .insert()
+            .await?;
+    }
+    if let Ok(mut cached) = SUBSCRIPTIONS.write() {
+        let subscribed = cached.entry(chat_id).or_default();
+        for event in events {
+            subscribed.insert((symbol.clone(), event));
+        }
+    }
+
+    Ok(format!("已訂閱\n{}", describe(chat_id)))
+}
+
+/// 執行 /unsubscribe 指令
+pub async fn unsubscribe(args: &[String], chat_id: i64) -> Result<String> {
+    let Some((symbol, events)) = parse(args) else {
+        return Ok(USAGE.to_string());
+    };
+
+    let names: Vec<String> = events.iter().map(|event| event.to_string()).collect();
+    BotSubscription::delete(chat_id, &symbol, &names).await?;
+    if let Ok(mut cached) = SUBSCRIPTIONS.write() {
+        if let Some(subscribed) = cached.get_mut(&chat_id) {
+            subscribed.retain(|(code, event)| code != &symbol || !events.contains(event));
+            if subscribed.is_empty() {
+                cached.remove(&chat_id);
+            }
+        }
+    }
+
+    Ok(format!("已取消訂閱\n{}", describe(chat_id)))
+}
+
+/// 解析股票代號與以逗號分隔的事件，省略事件時為全部的事件，有無法辨識的事件時回傳 None
+fn parse(args: &[String]) -> Option<(StockSymbol, Vec<Event>)> {
+    let (symbol, events) = match args {
+        [symbol] => (symbol, Event::ALL.to_vec()),
+        [symbol, events] => (
+            symbol,
+            events
+                .split(',')
+                .map(|event| Event::parse(event.trim()))
+                .collect::<Option<Vec<Event>>>()?,
+        ),
+        _ => return None,
+    };
+
+    Some((StockSymbol::parse(symbol).ok()?, events))
+}
+
+fn describe(chat_id: i64) -> String {
+    let subscribed = SUBSCRIPTIONS
+        .read()
+        .ok()
+        .and_then(|cached| cached.get(&chat_id).cloned())
+        .unwrap_or_default();
+    if subscribed.is_empty() {
+        return "沒有訂閱，接收庫存與追踪股票的通知".to_string();
+    }
+
+    let mut grouped: BTreeMap<StockSymbol, Vec<Event>> = BTreeMap::new();
+    for (symbol, event) in subscribed {
+        grouped.entry(symbol).or_default().push(event);
+    }
+
+    grouped
+        .into_iter()
+        .map(|(symbol, mut events)| {
+            events.sort();
+            let events: Vec<&str> = events.iter().map(Event::as_str).collect();
+            format!("{} {}", symbol, events.join(","))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            parse(&args(&["2330", "revenue,announcement"])),
+            Some((
+                "2330".parse().unwrap(),
+                vec![Event::Revenue, Event::Announcement]
+            ))
+        );
+        assert_eq!(
+            parse(&args(&["00878"])).map(|(_, events)| events),
+            Some(Event::ALL.to_vec())
+        );
+        assert_eq!(parse(&args(&["2330", "revenue,price"])), None);
+        assert_eq!(parse(&args(&["TSMC"])), None);
+        assert_eq!(parse(&args(&[])), None);
+    }
+
+    #[test]
+    fn test_decide() {
+        let symbol = |code: &str| code.parse::<StockSymbol>().unwrap();
+        let subscribed: Subscribed = HashSet::from([(symbol("2330"), Event::Revenue)]);
+        let held = HashSet::from([symbol("2317")]);
+
+        assert!(decide(
+            Some(&subscribed),
+            Event::Revenue,
+            &symbol("2330"),
+            Some(&held)
+        ));
+        assert!(!decide(
+            Some(&subscribed),
+            Event::Dividend,
+            &symbol("2330"),
+            None
+        ));
+        assert!(!decide(
+            Some(&subscribed),
+            Event::Revenue,
+            &symbol("2317"),
+            Some(&held)
+        ));
+        assert!(decide(None, Event::Dividend, &symbol("2317"), Some(&held)));
+        assert!(!decide(None, Event::Dividend, &symbol("2330"), Some(&held)));
+        assert!(decide(None, Event::Announcement, &symbol("2330"), None));
+    }
+}
@@ -1,16 +1,51 @@
-use std::sync::{Arc, OnceLock};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
+};
 
 use anyhow::{anyhow, Result};
+use chrono::{Local, NaiveTime};
 use futures::future::join_all;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 
-use crate::{config::SETTINGS, logging, util::http};
+use reqwest::{header, Method};
+
+use crate::{
+    bot::{
+        settings,
+        subscription::{self, Event},
+    },
+    config::{Sandbox, SETTINGS},
+    declare::StockSymbol,
+    i18n,
+    limits::{self, Stage},
+    logging::{self, run_id},
+    util::http,
+};
+
+/// 訊息的格式化工具
+pub mod fmt;
+
+/// 沙箱模式下加在訊息開頭的標記
+const SANDBOX_TAG: &str = "[SANDBOX]";
+
+/// getUpdates 長輪詢等待的秒數
+const POLL_TIMEOUT_SECONDS: u64 = 25;
+/// 上傳圖片的逾時秒數
+const SEND_PHOTO_TIMEOUT_SECONDS: u64 = 60;
+/// multipart/form-data 各欄位間的分隔字串
+const MULTIPART_BOUNDARY: &str = "----StockCrawlerBoundary7MA4YWxkTrZu0gW";
 
 static TELEGRAM: Lazy<Arc<OnceLock<Telegram>>> = Lazy::new(|| Arc::new(OnceLock::new()));
+/// 勿擾時段內延後發送的訊息(聊天室, 訊息)，只保存在記憶體，關閉服務前會全部送出
+static DEFERRED: Lazy<Mutex<Vec<(i64, String)>>> = Lazy::new(|| Mutex::new(Vec::new()));
 
 struct Telegram {
     send_message_url: String,
+    send_photo_url: String,
+    get_updates_url: String,
 }
 
 impl Telegram {
@@ -18,26 +53,115 @@ impl Telegram {
         Self {
             send_message_url: format!(
                 "https://api.telegram.org/bot{}/sendMessage",
-                SETTINGS.bot.telegram.token
+                SETTINGS.load().bot.telegram.token
+            ),
+            send_photo_url: format!(
+                "https://api.telegram.org/bot{}/sendPhoto",
+                SETTINGS.load().bot.telegram.token
             ),
+            get_updates_url: format!(
+                "https://api.telegram.org/bot{}/getUpdates",
+                SETTINGS.load().bot.telegram.token
+            ),
+        }
+    }
+
+    /// 以 multipart/form-data 上傳 PNG 圖片到指定的聊天室
+    async fn send_photo(&self, chat_id: i64, png: Vec<u8>, caption: &str) -> Result<()> {
+        if suppressed(chat_id, caption) {
+            return Ok(());
+        }
+
+        // 圖片說明不使用 Markdown，標記不需要跳脫
+        let (chat_id, caption) =
+            sandboxed(&SETTINGS.load().bot.sandbox, chat_id, caption, SANDBOX_TAG);
+        let caption = caption.as_str();
+        let body = multipart_body(
+            MULTIPART_BOUNDARY,
+            &[("chat_id", &chat_id.to_string()), ("caption", caption)],
+            ("photo", "chart.png", "image/png", png),
+        );
+        let mut headers = header::HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            header::HeaderValue::from_str(&format!(
+                "multipart/form-data; boundary={}",
+                MULTIPART_BOUNDARY
+            ))?,
+        );
+
+        let res = limits::run(Stage::TelegramMessage, async {
+            http::request_bytes(
+                Method::POST,
+                &self.send_photo_url,
+                Some(headers),
+                Some(body),
+                Duration::from_secs(SEND_PHOTO_TIMEOUT_SECONDS),
+            )
+            .await?
+            .json::<SendMessageResponse>()
+            .await
+            .map_err(|why| anyhow!("Failed to parse sendPhoto because: {:?}", why))
+        })
+        .await?;
+
+        if !res.ok {
+            return Err(anyhow!(
+                "Failed to sendPhoto because: {}",
+                res.description.unwrap_or_default()
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// 以長輪詢取得 offset 之後的訊息
+    async fn get_updates(&self, offset: i64) -> Result<Vec<Update>> {
+        let payload = GetUpdatesRequest {
+            offset,
+            timeout: POLL_TIMEOUT_SECONDS,
+            allowed_updates: vec!["message"],
+        };
+        let mut headers = header::HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            header::HeaderValue::from_static("application/json"),
+        );
+
+        let res = http::request_bytes(
+            Method::POST,
+            &self.get_updates_url,
+            Some(headers),
+            Some(serde_json::to_vec(&payload)?),
+            Duration::from_secs(POLL_TIMEOUT_SECONDS + 10),
+        )
+        .await?
+        .json::<GetUpdatesResponse>()
+        .await
+        .map_err(|why| anyhow!("Failed to parse getUpdates because: {:?}", why))?;
+
+        if !res.ok {
+            return Err(anyhow!(
+                "Failed to getUpdates because: {}",
+                res.description.unwrap_or_default()
+            ));
         }
+
+        Ok(res.result)
     }
 
     pub async fn send(&self, message: &str) -> Result<SendMessageResponse> {
         //let escape_text = self.escape_text("ModeMarkdown", message);
-        let futures: Vec<_> = SETTINGS
-            .bot
-            .telegram
-            .allowed
-            .keys()
-            .map(|id| self.send_message(SendMessageRequest::new(*id, message)))
+        let futures: Vec<_> = recipients()
+            .into_iter()
+            .map(|id| self.send_message(SendMessageRequest::new(id, message)))
             .collect();
 
         /* join_all(futures)
-            .await
-            .into_iter()
-            .find(|res| res.is_err())
-            .unwrap_or_else(|res| Ok(()))*/
+        .await
+        .into_iter()
+        .find(|res| res.is_err())
+        .unwrap_or_else(|res| Ok(()))*/
         let results = join_all(futures).await;
 
         for result in results {
@@ -51,18 +175,43 @@ impl Telegram {
     }
 
     async fn send_message(&self, payload: SendMessageRequest<'_>) -> Result<SendMessageResponse> {
-        let res = http::post_use_json::<SendMessageRequest, SendMessageResponse>(
-            &self.send_message_url,
-            None,
-            Some(&payload),
+        if suppressed(payload.chat_id, payload.text) {
+            return Ok(SendMessageResponse {
+                ok: true,
+                result: None,
+                error_code: None,
+                description: None,
+            });
+        }
+
+        let (chat_id, text) = sandboxed(
+            &SETTINGS.load().bot.sandbox,
+            payload.chat_id,
+            payload.text,
+            &fmt::escape_markdown(SANDBOX_TAG),
+        );
+        let payload = SendMessageRequest {
+            chat_id,
+            text: &text,
+            ..payload
+        };
+
+        // 同時傳送中的訊息數受設定檔 limits.max_telegram_messages 限制
+        let res = limits::run(
+            Stage::TelegramMessage,
+            http::post_use_json::<SendMessageRequest, SendMessageResponse>(
+                &self.send_message_url,
+                None,
+                Some(&payload),
+            ),
         )
-            .await
-            .map_err(|err| anyhow!("Failed to send_message because: {:?}", err))?;
+        .await
+        .map_err(|err| anyhow!("Failed to send_message because: {:?}", err))?;
         //logging::debug_file_async(format!("{}", res.description.unwrap()));
         Ok(res)
     }
 
-   /* fn escape_text(&self,parse_mode: &str, text: &str) -> String {
+    /* fn escape_text(&self,parse_mode: &str, text: &str) -> String {
         let replacements: HashMap<&str, &str> = match parse_mode {
             "ModeHTML" => vec![("<", "&lt;"), (">", "&gt;"), ("&", "&amp;")].into_iter().collect(),
             "ModeMarkdown" => vec![("_", "\\_"), ("*", "\\*"), ("`", "\\`"), ("[", "\\[")].into_iter().collect(),
@@ -89,34 +238,121 @@ impl Default for Telegram {
     }
 }
 
+/// 設定檔 profile.disable_notifications 開啟時不發送，改寫入日誌
+fn suppressed(chat_id: i64, text: &str) -> bool {
+    if !SETTINGS.load().profile.disable_notifications {
+        return false;
+    }
+
+    logging::info_file_async(format!(
+        "[{}] 已停用通知，未發送給 {} 的訊息:\r\n{}",
+        SETTINGS.load().profile.name,
+        chat_id,
+        text
+    ));
+
+    true
+}
+
+/// 接收通知的聊天室，沙箱模式下只有測試用的聊天室，避免同一則訊息重複送到測試聊天室
+fn recipients() -> Vec<i64> {
+    let sandbox = &SETTINGS.load().bot.sandbox;
+    if sandbox.enabled {
+        return vec![sandbox.chat_id];
+    }
+
+    SETTINGS
+        .load()
+        .bot
+        .telegram
+        .allowed
+        .keys()
+        .copied()
+        .collect()
+}
+
+/// 沙箱模式下將訊息改送到測試用的聊天室，並在開頭加上 tag
+fn sandboxed(sandbox: &Sandbox, chat_id: i64, text: &str, tag: &str) -> (i64, String) {
+    if !sandbox.enabled {
+        return (chat_id, text.to_string());
+    }
+
+    (sandbox.chat_id, format!("{} {}", tag, text))
+}
+
 fn get_client() -> Result<&'static Telegram> {
     Ok(TELEGRAM.get_or_init(Telegram::new))
 }
 
-#[derive(Serialize, Deserialize,Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct SendMessageResponse {
     pub ok: bool,
     pub result: Option<Message>,
     pub error_code: Option<i32>,
-    pub description: Option<String>
+    pub description: Option<String>,
 }
 
-#[derive(Serialize, Deserialize,Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Message {
     message_id: i64,
 }
 
+#[derive(Serialize)]
+struct GetUpdatesRequest {
+    offset: i64,
+    timeout: u64,
+    allowed_updates: Vec<&'static str>,
+}
+
+#[derive(Deserialize, Debug)]
+struct GetUpdatesResponse {
+    ok: bool,
+    #[serde(default)]
+    result: Vec<Update>,
+    description: Option<String>,
+}
+
+/// 聊天室收到的更新
+#[derive(Deserialize, Debug)]
+pub struct Update {
+    pub update_id: i64,
+    pub message: Option<IncomingMessage>,
+}
+
+/// 聊天室收到的訊息
+#[derive(Deserialize, Debug)]
+pub struct IncomingMessage {
+    pub chat: Chat,
+    /// 發送者，頻道的訊息沒有發送者
+    pub from: Option<User>,
+    pub text: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Chat {
+    pub id: i64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct User {
+    pub id: i64,
+}
+
 #[derive(Serialize)]
 pub struct SendMessageRequest<'a> {
     pub chat_id: i64,
     pub text: &'a str,
     #[serde(rename = "parse_mode")]
-    pub parse_mode: &'a str
+    pub parse_mode: &'a str,
 }
 
 impl<'a> SendMessageRequest<'a> {
     pub fn new(chat_id: i64, text: &'a str) -> SendMessageRequest<'a> {
-        SendMessageRequest { chat_id, text, parse_mode: "Markdown"}
+        SendMessageRequest {
+            chat_id,
+            text,
+            parse_mode: "Markdown",
+        }
     }
 }
 
@@ -132,14 +368,23 @@ impl<'a> SendMessageRequest<'a> {
 /// # Errors
 ///
 /// This function logs errors if it fails to get the Telegram client or send the message.
+/// When called inside a scheduled job, the run id is appended to the message.
 pub async fn send(msg: &str) {
     // Try to get a Telegram client
     match get_client() {
         Ok(client) => {
+            let msg = match run_id::current() {
+                Some(run_id) => format!("{}\r\nrun_id: {}", msg, run_id),
+                None => msg.to_string(),
+            };
+
             // Try to send the message using the client
-            if let Err(error) = client.send(msg).await {
+            if let Err(error) = client.send(&msg).await {
                 // Log an error if sending the message fails
-                logging::error_file_async(format!("Failed to send message to telegram because {:?}", error));
+                logging::error_file_async(format!(
+                    "Failed to send message to telegram because {:?}",
+                    error
+                ));
             }
         }
         Err(error) => {
@@ -149,6 +394,231 @@ pub async fn send(msg: &str) {
     }
 }
 
+/// 發送訊息並回傳結果，部署後的自我檢查用來確認 token 與聊天室的設定
+pub async fn try_send(msg: &str) -> Result<()> {
+    let res = get_client()?.send(msg).await?;
+    if !res.ok {
+        return Err(anyhow!(
+            "Failed to send message because {}",
+            res.description.unwrap_or_default()
+        ));
+    }
+
+    Ok(())
+}
+
+/// 發送非緊急的通知，聊天室在勿擾時段內時先放入佇列，由 flush_deferred 在時段結束後送出
+pub async fn send_deferrable(msg: &str) {
+    deliver(msg, |_| true).await;
+}
+
+/// 發送彙整後的警示摘要，略過以 /settings digest off 不接收摘要的聊天室，同樣遵守勿擾時段
+pub async fn send_digest(msg: &str) {
+    deliver(msg, settings::digest).await;
+}
+
+async fn deliver(msg: &str, wanted: impl Fn(i64) -> bool) {
+    let msg = match run_id::current() {
+        Some(run_id) => format!("{}\r\nrun_id: {}", msg, run_id),
+        None => msg.to_string(),
+    };
+    let now = Local::now().time();
+
+    for chat_id in recipients().into_iter().filter(|chat_id| wanted(*chat_id)) {
+        deliver_to(chat_id, &msg, now).await;
+    }
+}
+
+/// 聊天室在勿擾時段內時先放入佇列，否則立即送出
+async fn deliver_to(chat_id: i64, msg: &str, now: NaiveTime) {
+    if is_quiet(chat_id, now) {
+        if let Ok(mut deferred) = DEFERRED.lock() {
+            deferred.push((chat_id, msg.to_string()));
+            return;
+        }
+    }
+
+    reply(chat_id, msg).await;
+}
+
+/// 依各聊天室以 /subscribe 訂閱的股票與事件過濾後發送個股的通知，同樣遵守勿擾時段
+///
+/// 沒有訂閱的聊天室接收 default 內的股票(None 為全部)，build 以聊天室的語系與要接收的項目產生訊息，
+/// 過濾後沒有項目的聊天室不發送；有訂閱但不在設定檔 bot.telegram.allowed 內的聊天室也會收到
+pub async fn send_subscribed<T>(
+    event: Event,
+    items: &[T],
+    symbol: impl Fn(&T) -> &StockSymbol,
+    default: Option<&HashSet<StockSymbol>>,
+    build: impl Fn(&str, &[&T]) -> String,
+) {
+    let now = Local::now().time();
+    let mut chat_ids = recipients();
+    if !SETTINGS.load().bot.sandbox.enabled {
+        for chat_id in subscription::chat_ids() {
+            if !chat_ids.contains(&chat_id) {
+                chat_ids.push(chat_id);
+            }
+        }
+    }
+
+    for chat_id in chat_ids {
+        let wanted: Vec<&T> = items
+            .iter()
+            .filter(|item| subscription::wanted(chat_id, event, symbol(item), default))
+            .collect();
+        if wanted.is_empty() {
+            continue;
+        }
+
+        let msg = build(&i18n::language(chat_id), &wanted);
+        let msg = match run_id::current() {
+            Some(run_id) => format!("{}\r\nrun_id: {}", msg, run_id),
+            None => msg,
+        };
+        deliver_to(chat_id, &msg, now).await;
+    }
+}
+
+/// 依各聊天室的語系產生訊息後發送，同一語系的訊息只產生一次
+pub async fn send_localized(build: impl Fn(&str) -> String) {
+    let mut messages: HashMap<String, String> = HashMap::new();
+
+    for chat_id in recipients() {
+        let language = i18n::language(chat_id);
+        let msg = messages.entry(language).or_insert_with_key(|language| {
+            let msg = build(language);
+            match run_id::current() {
+                Some(run_id) => format!("{}\r\nrun_id: {}", msg, run_id),
+                None => msg,
+            }
+        });
+
+        reply(chat_id, msg).await;
+    }
+}
+
+/// 聊天室以 /settings 設定的勿擾時段優先，沒有時使用設定檔 bot.telegram.quiet_hours
+fn is_quiet(chat_id: i64, time: NaiveTime) -> bool {
+    settings::quiet_hours(chat_id)
+        .or_else(|| {
+            SETTINGS
+                .load()
+                .bot
+                .telegram
+                .quiet_hours
+                .get(&chat_id)
+                .copied()
+        })
+        .is_some_and(|quiet| quiet.contains(time))
+}
+
+/// 送出已離開勿擾時段的聊天室在佇列中的訊息
+pub async fn flush_deferred() -> Result<()> {
+    let now = Local::now().time();
+    deliver_deferred(|chat_id| !is_quiet(chat_id, now)).await;
+
+    Ok(())
+}
+
+/// 不論勿擾時段送出佇列中全部的訊息，關閉服務前呼叫避免訊息遺失
+pub async fn flush_all_deferred() {
+    deliver_deferred(|_| true).await;
+}
+
+async fn deliver_deferred(ready: impl Fn(i64) -> bool) {
+    let messages = match DEFERRED.lock() {
+        Ok(mut deferred) => take_ready(&mut deferred, ready),
+        Err(_) => return,
+    };
+
+    for (chat_id, msg) in messages {
+        reply(chat_id, &msg).await;
+    }
+}
+
+/// 從佇列取出可以發送的訊息，其餘的留在佇列中，保持原本的順序
+fn take_ready(
+    deferred: &mut Vec<(i64, String)>,
+    ready: impl Fn(i64) -> bool,
+) -> Vec<(i64, String)> {
+    let (messages, remaining) = deferred.drain(..).partition(|(chat_id, _)| ready(*chat_id));
+    *deferred = remaining;
+    messages
+}
+
+/// 回覆訊息給指定的聊天室
+pub async fn reply(chat_id: i64, msg: &str) {
+    match get_client() {
+        Ok(client) => {
+            if let Err(why) = client
+                .send_message(SendMessageRequest::new(chat_id, msg))
+                .await
+            {
+                logging::error_file_async(format!(
+                    "Failed to reply message to telegram({}) because {:?}",
+                    chat_id, why
+                ));
+            }
+        }
+        Err(why) => {
+            logging::error_file_async(format!("Failed to get telegram client because {:?}", why));
+        }
+    }
+}
+
+/// 傳送圖片給指定的聊天室
+pub async fn send_photo(chat_id: i64, png: Vec<u8>, caption: &str) {
+    match get_client() {
+        Ok(client) => {
+            if let Err(why) = client.send_photo(chat_id, png, caption).await {
+                logging::error_file_async(format!(
+                    "Failed to send photo to telegram({}) because {:?}",
+                    chat_id, why
+                ));
+            }
+        }
+        Err(why) => {
+            logging::error_file_async(format!("Failed to get telegram client because {:?}", why));
+        }
+    }
+}
+
+/// 組出 multipart/form-data 的內容，fields 為文字欄位，file 為(欄位名稱, 檔名, Content-Type, 內容)
+fn multipart_body(
+    boundary: &str,
+    fields: &[(&str, &str)],
+    file: (&str, &str, &str, Vec<u8>),
+) -> Vec<u8> {
+    let mut body = Vec::with_capacity(file.3.len() + 512);
+    for (name, value) in fields {
+        body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                boundary, name, value
+            )
+            .as_bytes(),
+        );
+    }
+
+    let (name, filename, content_type, content) = file;
+    body.extend_from_slice(
+        format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+            boundary, name, filename, content_type
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(&content);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+
+    body
+}
+
+/// 取得 offset 之後聊天室收到的訊息
+pub async fn get_updates(offset: i64) -> Result<Vec<Update>> {
+    get_client()?.get_updates(offset).await
+}
 
 #[cfg(test)]
 mod tests {
@@ -161,6 +631,56 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_multipart_body() {
+        let body = multipart_body(
+            "XYZ",
+            &[("chat_id", "42")],
+            ("photo", "chart.png", "image/png", vec![1, 2]),
+        );
+
+        let mut expected =
+            b"--XYZ\r\nContent-Disposition: form-data; name=\"chat_id\"\r\n\r\n42\r\n\
+--XYZ\r\nContent-Disposition: form-data; name=\"photo\"; filename=\"chart.png\"\r\n\
+Content-Type: image/png\r\n\r\n"
+                .to_vec();
+        expected.extend_from_slice(&[1, 2]);
+        expected.extend_from_slice(b"\r\n--XYZ--\r\n");
+        assert_eq!(body, expected);
+    }
+
+    #[test]
+    fn test_sandboxed() {
+        let mut sandbox = Sandbox {
+            enabled: false,
+            chat_id: 42,
+        };
+        assert_eq!(
+            sandboxed(&sandbox, 123, "hi", SANDBOX_TAG),
+            (123, "hi".to_string())
+        );
+
+        sandbox.enabled = true;
+        assert_eq!(
+            sandboxed(&sandbox, 123, "hi", SANDBOX_TAG),
+            (42, "[SANDBOX] hi".to_string())
+        );
+    }
+
+    #[test]
+    fn test_take_ready() {
+        let mut deferred = vec![
+            (1, "a".to_string()),
+            (2, "b".to_string()),
+            (1, "c".to_string()),
+        ];
+
+        let ready = take_ready(&mut deferred, |chat_id| chat_id == 1);
+
+        assert_eq!(ready, vec![(1, "a".to_string()), (1, "c".to_string())]);
+        assert_eq!(deferred, vec![(2, "b".to_string())]);
+    }
+
     #[tokio::test]
     #[ignore]
     async fn test_send_message() {
@@ -0,0 +1,262 @@
+use std::fmt::Write;
+
+use rust_decimal::Decimal;
+
+/// 跳脫 Markdown(舊版) 的特殊字元
+///
+/// 訊息預設以 Markdown 模式送出，股票名稱、重大訊息主旨等外部文字未跳脫時，`_`、`*` 會讓整則訊息發送失敗
+pub fn escape_markdown(text: &str) -> String {
+    escape_chars(text, &['_', '*', '`', '['])
+}
+
+/// 跳脫 MarkdownV2 的特殊字元
+pub fn escape_markdown_v2(text: &str) -> String {
+    escape_chars(
+        text,
+        &[
+            '_', '*', '[', ']', '(', ')', '~', '`', '>', '#', '+', '-', '=', '|', '{', '}', '.',
+            '!', '\\',
+        ],
+    )
+}
+
+/// 跳脫 HTML 模式的特殊字元
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn escape_chars(text: &str, specials: &[char]) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if specials.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+/// 整數加上千分位 ex. 1234567 => 1,234,567
+pub fn thousands(value: i64) -> String {
+    let digits = value.unsigned_abs().to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3 + 1);
+    if value < 0 {
+        grouped.push('-');
+    }
+
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+
+    grouped
+}
+
+/// 數值四捨五入到 dp 位小數並加上千分位 ex. 1234567.891 => 1,234,567.89
+pub fn number(value: Decimal, dp: u32) -> String {
+    let rounded = value.round_dp(dp);
+    let integer = rounded.trunc();
+    let mut text = thousands(integer.try_into().unwrap_or_default());
+    if rounded.is_sign_negative() && integer.is_zero() && !rounded.is_zero() {
+        text.insert(0, '-');
+    }
+
+    if dp > 0 {
+        let fraction = (rounded.fract().abs() * Decimal::from(10_i64.pow(dp))).round();
+        let _ = write!(text, ".{:0width$}", fraction, width = dp as usize);
+    }
+
+    text
+}
+
+/// 漲跌百分比加上表示方向的 emoji，台股習慣紅漲綠跌 ex. 🔺1.23%、🔻-1.23%、➖0.00%
+pub fn percent(value: Decimal) -> String {
+    let sign = if value > Decimal::ZERO {
+        "🔺"
+    } else if value < Decimal::ZERO {
+        "🔻"
+    } else {
+        "➖"
+    };
+
+    format!("{}{}%", sign, number(value, 2))
+}
+
+/// 文字在等寬字型下佔用的欄寬，中日韓文字與全形符號佔兩格
+pub fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c as u32 {
+            0x1100..=0x115F
+            | 0x2E80..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6 => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+/// 欄位對齊方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// 以等寬字型呈現的對齊表格，輸出時包在 ``` 內
+#[derive(Debug, Default)]
+pub struct Table {
+    headers: Vec<String>,
+    aligns: Vec<Align>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// 建立表格，數值欄位可用 [`Table::align`] 改為靠右
+    pub fn new<S: ToString>(headers: &[S]) -> Self {
+        Table {
+            headers: headers.iter().map(ToString::to_string).collect(),
+            aligns: vec![Align::Left; headers.len()],
+            rows: Vec::new(),
+        }
+    }
+
+    /// 設定各欄位的對齊方式
+    pub fn align(mut self, aligns: &[Align]) -> Self {
+        for (i, align) in aligns.iter().enumerate().take(self.aligns.len()) {
+            self.aligns[i] = *align;
+        }
+
+        self
+    }
+
+    /// 新增一列，欄位數不足時補空白
+    pub fn row<S: ToString>(&mut self, cells: &[S]) -> &mut Self {
+        let mut row: Vec<String> = cells
+            .iter()
+            .map(|cell| cell.to_string().replace('`', "'"))
+            .collect();
+        row.resize(self.headers.len().max(row.len()), String::new());
+        self.rows.push(row);
+
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// 輸出成 Markdown 的程式碼區塊
+    pub fn render(&self) -> String {
+        let columns = self
+            .rows
+            .iter()
+            .map(Vec::len)
+            .chain(std::iter::once(self.headers.len()))
+            .max()
+            .unwrap_or(0);
+        let mut widths = vec![0; columns];
+        for row in std::iter::once(&self.headers).chain(self.rows.iter()) {
+            for (i, cell) in row.iter().enumerate() {
+                widths[i] = widths[i].max(display_width(cell));
+            }
+        }
+
+        let mut out = String::from("```\n");
+        if !self.headers.is_empty() {
+            self.write_row(&mut out, &self.headers, &widths);
+        }
+        for row in &self.rows {
+            self.write_row(&mut out, row, &widths);
+        }
+        out.push_str("```");
+
+        out
+    }
+
+    fn write_row(&self, out: &mut String, row: &[String], widths: &[usize]) {
+        let mut line = String::new();
+        for (i, cell) in row.iter().enumerate() {
+            let padding = " ".repeat(widths[i].saturating_sub(display_width(cell)));
+            if i > 0 {
+                line.push(' ');
+            }
+
+            match self.aligns.get(i).copied().unwrap_or(Align::Left) {
+                Align::Left => {
+                    line.push_str(cell);
+                    line.push_str(&padding);
+                }
+                Align::Right => {
+                    line.push_str(&padding);
+                    line.push_str(cell);
+                }
+            }
+        }
+
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape_markdown("F-KY_新*"), "F-KY\\_新\\*");
+        assert_eq!(escape_markdown_v2("1.5 (a)"), "1\\.5 \\(a\\)");
+        assert_eq!(escape_html("<b>&</b>"), "&lt;b&gt;&amp;&lt;/b&gt;");
+    }
+
+    #[test]
+    fn test_number() {
+        assert_eq!(thousands(0), "0");
+        assert_eq!(thousands(999), "999");
+        assert_eq!(thousands(1234567), "1,234,567");
+        assert_eq!(thousands(-1234), "-1,234");
+        assert_eq!(number(dec!(1234567.891), 2), "1,234,567.89");
+        assert_eq!(number(dec!(-0.5), 2), "-0.50");
+        assert_eq!(number(dec!(12.3), 0), "12");
+        assert_eq!(percent(dec!(1.234)), "🔺1.23%");
+        assert_eq!(percent(dec!(-1.5)), "🔻-1.50%");
+        assert_eq!(percent(Decimal::ZERO), "➖0.00%");
+    }
+
+    #[test]
+    fn test_table() {
+        let mut table = Table::new(&["代號", "名稱", "殖利率"]).align(&[
+            Align::Left,
+            Align::Left,
+            Align::Right,
+        ]);
+        table.row(&["2330", "台積電", "1.50"]);
+        table.row(&["1101", "台泥", "10.25"]);
+
+        assert_eq!(display_width("台積電"), 6);
+        assert_eq!(
+            table.render(),
+            "```\n代號 名稱   殖利率\n2330 台積電   1.50\n1101 台泥    10.25\n```"
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use std::{collections::HashMap, sync::RwLock, time::Duration};
 
+use anyhow::{anyhow, Result};
+use futures::TryStreamExt;
 use once_cell::sync::Lazy;
 use rust_decimal::Decimal;
 
@@ -232,16 +234,19 @@ impl Share {
             }
         }
 
-        if let (Ok(result), Ok(mut last_revenue)) = (
-            revenue::fetch_last_two_month().await,
-            self.last_revenues.write(),
-        ) {
-            result.iter().for_each(|e| {
-                last_revenue
+        let revenues = revenue::stream_last_two_month()
+            .try_fold(HashMap::new(), |mut revenues, e| async move {
+                revenues
                     .entry(e.date)
                     .or_insert_with(HashMap::new)
-                    .insert(e.security_code.to_string(), e.clone());
-            });
+                    .insert(e.security_code.to_string(), e);
+                Ok(revenues)
+            })
+            .await;
+        if let (Ok(result), Ok(mut last_revenue)) = (revenues, self.last_revenues.write()) {
+            for (date, revenues) in result {
+                last_revenue.entry(date).or_default().extend(revenues);
+            }
         } else {
             logging::error_file_async("Failed to update last_revenues".to_string());
         }
@@ -427,7 +432,8 @@ impl Share {
     /// 更新快取內股票最後的報價
     pub async fn set_stock_last_price(&self, daily_quote: &daily_quote::DailyQuote) {
         if let Ok(mut last_trading_day_quotes) = self.last_trading_day_quotes.write() {
-            if let Some(quote) = last_trading_day_quotes.get_mut(&daily_quote.security_code) {
+            if let Some(quote) = last_trading_day_quotes.get_mut(daily_quote.security_code.as_str())
+            {
                 quote.date = daily_quote.date;
                 quote.closing_price = daily_quote.closing_price;
             }
@@ -444,6 +450,36 @@ impl Share {
             Err(_) => None,
         }
     }
+
+    /// 清除後從資料庫重新載入股票最後的報價，回傳載入的筆數
+    pub async fn reload_last_trading_day_quotes(&self) -> Result<usize> {
+        let quotes = last_daily_quotes::LastDailyQuotes::fetch().await?;
+        let mut cache = self
+            .last_trading_day_quotes
+            .write()
+            .map_err(|why| anyhow!("Failed to last_trading_day_quotes.write because {:?}", why))?;
+        cache.clear();
+        for e in quotes {
+            cache.insert(e.security_code.to_string(), e);
+        }
+
+        Ok(cache.len())
+    }
+
+    /// 清除後從資料庫重新載入股票代碼，回傳載入的筆數
+    pub async fn reload_stocks(&self) -> Result<usize> {
+        let stocks = stock::Stock::fetch().await?;
+        let mut cache = self
+            .stocks
+            .write()
+            .map_err(|why| anyhow!("Failed to stocks.write because {:?}", why))?;
+        cache.clear();
+        for e in stocks {
+            cache.insert(e.stock_symbol.to_string(), e);
+        }
+
+        Ok(cache.len())
+    }
 }
 
 impl Default for Share {
@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use chrono::{Local, NaiveDate};
+use futures::{stream, StreamExt};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::{
+    database::table::{
+        adjusted_quote::AdjustedQuote,
+        daily_quote,
+        dividend::extension::{dividend_schedule, stock_dividend_info},
+        stock::Stock,
+    },
+    declare::StockSymbol,
+    logging, util,
+};
+
+/// 股票股利以每股面額 10 元計算配股比例
+const PAR_VALUE: Decimal = dec!(10);
+
+/// 某個除權息日的現金股利與股票股利，同一天有多筆股利時會合併
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Adjustment {
+    cash_dividend: Decimal,
+    stock_dividend: Decimal,
+}
+
+/// 收盤後寫入當日的還原收盤價，當日除權息的股票重新計算全部的歷史還原價
+pub async fn calculate(date: NaiveDate) -> Result<()> {
+    AdjustedQuote::upsert_by_date(date).await?;
+
+    let stocks = stock_dividend_info::fetch_stocks_with_dividends_on_date(date).await?;
+    for stock in stocks {
+        rebuild(&stock.stock_symbol).await?;
+    }
+
+    Ok(())
+}
+
+/// 重新計算所有未下市股票的還原收盤價
+pub async fn rebuild_all() -> Result<()> {
+    let stocks = Stock::fetch().await?;
+    stream::iter(stocks.into_iter().filter(|stock| !stock.suspend_listing))
+        .for_each_concurrent(util::concurrent_limit_16(), |stock| async move {
+            if let Err(why) = rebuild(&stock.stock_symbol).await {
+                logging::error_file_async(format!(
+                    "Failed to adjusted_price::rebuild({}) because {:?}",
+                    stock.stock_symbol, why
+                ));
+            }
+        })
+        .await;
+
+    logging::info_file_async("重新計算全部股票的還原收盤價結束".to_string());
+
+    Ok(())
+}
+
+/// 依股票全部的收盤價與除權息記錄重新計算還原收盤價
+pub async fn rebuild(security_code: &StockSymbol) -> Result<u64> {
+    let closes: Vec<(NaiveDate, Decimal)> = daily_quote::fetch_trading_days(
+        security_code,
+        NaiveDate::default(),
+        Local::now().date_naive(),
+    )
+    .await?
+    .into_iter()
+    .map(|(_, date, closing_price)| (date, closing_price))
+    .collect();
+
+    let mut adjustments: BTreeMap<NaiveDate, Adjustment> = BTreeMap::new();
+    for schedule in dividend_schedule::fetch(&[security_code.to_string()], 0).await? {
+        // 年度合計的記錄沒有除權息日，不會重複計算
+        if let Ok(date) = NaiveDate::parse_from_str(&schedule.ex_dividend_date1, "%Y-%m-%d") {
+            adjustments.entry(date).or_default().cash_dividend += schedule.cash_dividend;
+        }
+        if let Ok(date) = NaiveDate::parse_from_str(&schedule.ex_dividend_date2, "%Y-%m-%d") {
+            adjustments.entry(date).or_default().stock_dividend += schedule.stock_dividend;
+        }
+    }
+
+    AdjustedQuote::replace(security_code, &adjust(security_code, &closes, &adjustments)).await
+}
+
+/// 以向後還原的方式計算，除權息日之前的收盤價乘上
+/// (前一日收盤價 - 現金股利) / (前一日收盤價 × (1 + 股票股利 / 面額))，最近一個交易日的係數為 1
+fn adjust(
+    security_code: &StockSymbol,
+    closes: &[(NaiveDate, Decimal)],
+    adjustments: &BTreeMap<NaiveDate, Adjustment>,
+) -> Vec<AdjustedQuote> {
+    let mut factors = vec![Decimal::ONE; closes.len()];
+
+    for (date, adjustment) in adjustments {
+        // 除權息日當天或之後的第一個交易日，之前沒有交易日時不需還原
+        let index = closes.partition_point(|(d, _)| d < date);
+        if index == 0 || index == closes.len() {
+            continue;
+        }
+
+        let previous_close = closes[index - 1].1;
+        let ratio = Decimal::ONE + adjustment.stock_dividend / PAR_VALUE;
+        let adjusted = previous_close - adjustment.cash_dividend;
+        if previous_close <= Decimal::ZERO || adjusted <= Decimal::ZERO {
+            continue;
+        }
+
+        let factor = adjusted / (previous_close * ratio);
+        for f in factors.iter_mut().take(index) {
+            *f *= factor;
+        }
+    }
+
+    closes
+        .iter()
+        .zip(factors)
+        .map(|((date, closing_price), factor)| AdjustedQuote {
+            security_code: security_code.clone(),
+            date: *date,
+            closing_price: *closing_price,
+            adjustment_factor: factor.round_dp(12),
+            adjusted_closing_price: (*closing_price * factor).round_dp(4),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_adjust() {
+        let closes = vec![
+            (date(2024, 6, 10), dec!(100)),
+            (date(2024, 6, 11), dec!(100)),
+            (date(2024, 6, 12), dec!(95)),
+            (date(2024, 6, 13), dec!(50)),
+        ];
+        let mut adjustments = BTreeMap::new();
+        // 除息 5 元
+        adjustments.insert(
+            date(2024, 6, 12),
+            Adjustment {
+                cash_dividend: dec!(5),
+                stock_dividend: Decimal::ZERO,
+            },
+        );
+        // 配股 10 元，即每股配發一股
+        adjustments.insert(
+            date(2024, 6, 13),
+            Adjustment {
+                cash_dividend: Decimal::ZERO,
+                stock_dividend: dec!(10),
+            },
+        );
+        // 尚無收盤價的除權息日不影響
+        adjustments.insert(date(2024, 7, 1), Adjustment::default());
+
+        let symbol: StockSymbol = "2330".parse().unwrap();
+        let quotes = adjust(&symbol, &closes, &adjustments);
+        let adjusted: Vec<Decimal> = quotes.iter().map(|q| q.adjusted_closing_price).collect();
+
+        assert_eq!(adjusted, vec![dec!(47.5), dec!(47.5), dec!(47.5), dec!(50)]);
+        assert_eq!(quotes[3].adjustment_factor, Decimal::ONE);
+        assert_eq!(quotes[2].adjustment_factor, dec!(0.5));
+        assert_eq!(quotes[0].closing_price, dec!(100));
+        assert!(adjust(&symbol, &[], &adjustments).is_empty());
+    }
+}
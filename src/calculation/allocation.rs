@@ -0,0 +1,302 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    pin::pin,
+};
+
+use anyhow::Result;
+use futures::TryStreamExt;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::{
+    cache::SHARE, config::SETTINGS, database::table::stock_ownership_details::StockOwnershipDetail,
+};
+
+/// 市值 1000 億以上為大型股
+const LARGE_CAP: Decimal = dec!(100000000000);
+/// 市值 100 億以上為中型股
+const MID_CAP: Decimal = dec!(10000000000);
+
+/// 依公司市值的分類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CapBucket {
+    Large,
+    Mid,
+    Small,
+    /// 查無發行股數(ETF 等)
+    Unknown,
+}
+
+impl CapBucket {
+    pub fn from_market_cap(market_cap: Decimal) -> Self {
+        if market_cap <= Decimal::ZERO {
+            CapBucket::Unknown
+        } else if market_cap >= LARGE_CAP {
+            CapBucket::Large
+        } else if market_cap >= MID_CAP {
+            CapBucket::Mid
+        } else {
+            CapBucket::Small
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CapBucket::Large => "大型股(千億以上)",
+            CapBucket::Mid => "中型股(百億以上)",
+            CapBucket::Small => "小型股",
+            CapBucket::Unknown => "其他",
+        }
+    }
+}
+
+/// 成員持有的一檔股票
+#[derive(Debug, Clone, PartialEq)]
+pub struct Holding {
+    pub member_id: i64,
+    pub stock_symbol: String,
+    pub name: String,
+    pub industry: String,
+    /// 持股市值
+    pub market_value: Decimal,
+    /// 公司市值
+    pub market_cap: Decimal,
+}
+
+/// 類別(股票、產業或市值分類)佔成員總市值的比重
+#[derive(Debug, Clone, PartialEq)]
+pub struct Weight {
+    pub label: String,
+    pub market_value: Decimal,
+    /// 百分比 ex. 25.5 代表 25.5%
+    pub percent: Decimal,
+}
+
+/// 成員的持股配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct Allocation {
+    pub member_id: i64,
+    pub total: Decimal,
+    pub by_stock: Vec<Weight>,
+    pub by_sector: Vec<Weight>,
+    pub by_cap: Vec<Weight>,
+    /// 超過設定檔 report.stock_concentration、report.sector_concentration 的項目
+    pub warnings: Vec<String>,
+}
+
+/// 依庫存與最後交易日的收盤價計算每位成員的持股配置
+pub async fn calculate() -> Result<Vec<Allocation>> {
+    let holdings = fetch_holdings().await?;
+    let report = &SETTINGS.load().report;
+    let stock_limit = limit_or(report.stock_concentration, dec!(25));
+    let sector_limit = limit_or(report.sector_concentration, dec!(50));
+
+    let mut members: BTreeMap<i64, Vec<Holding>> = BTreeMap::new();
+    for holding in holdings {
+        members.entry(holding.member_id).or_default().push(holding);
+    }
+
+    Ok(members
+        .into_iter()
+        .map(|(member_id, list)| analyze(member_id, &list, stock_limit, sector_limit))
+        .collect())
+}
+
+/// 同一成員同一檔股票的多筆買進合併成一筆
+async fn fetch_holdings() -> Result<Vec<Holding>> {
+    let mut shares: HashMap<(i64, String), i64> = HashMap::new();
+    let mut details = pin!(StockOwnershipDetail::stream_held());
+    while let Some(detail) = details.try_next().await? {
+        *shares
+            .entry((detail.member_id, detail.security_code.to_string()))
+            .or_default() += detail.share_quantity;
+    }
+
+    let mut holdings = Vec::with_capacity(shares.len());
+    for ((member_id, stock_symbol), quantity) in shares {
+        let Some(price) = SHARE.get_stock_last_price(&stock_symbol).await else {
+            continue;
+        };
+        let stock = SHARE.get_stock(&stock_symbol).await;
+        let (name, industry, issued_share) = match stock {
+            Some(stock) => (
+                stock.name,
+                SHARE
+                    .get_industry_name(stock.stock_industry_id)
+                    .unwrap_or_else(|| "其他".to_string()),
+                stock.issued_share,
+            ),
+            None => (stock_symbol.clone(), "其他".to_string(), 0),
+        };
+
+        holdings.push(Holding {
+            member_id,
+            name,
+            industry,
+            market_value: price.closing_price * Decimal::from(quantity),
+            market_cap: price.closing_price * Decimal::from(issued_share),
+            stock_symbol,
+        });
+    }
+
+    Ok(holdings)
+}
+
+fn limit_or(percent: i64, default: Decimal) -> Decimal {
+    if percent > 0 {
+        Decimal::from(percent)
+    } else {
+        default
+    }
+}
+
+/// 計算單一成員依股票、產業、市值分類的比重，比重由大到小排序
+pub fn analyze(
+    member_id: i64,
+    holdings: &[Holding],
+    stock_limit: Decimal,
+    sector_limit: Decimal,
+) -> Allocation {
+    let total: Decimal = holdings.iter().map(|h| h.market_value).sum();
+    let by_stock = weights(
+        holdings
+            .iter()
+            .map(|h| (format!("{} {}", h.stock_symbol, h.name), h.market_value)),
+        total,
+    );
+    let by_sector = weights(
+        holdings
+            .iter()
+            .map(|h| (h.industry.clone(), h.market_value)),
+        total,
+    );
+    let by_cap = weights(
+        holdings.iter().map(|h| {
+            (
+                CapBucket::from_market_cap(h.market_cap).label().to_string(),
+                h.market_value,
+            )
+        }),
+        total,
+    );
+
+    let warnings = by_stock
+        .iter()
+        .filter(|w| w.percent > stock_limit)
+        .map(|w| {
+            format!(
+                "單一股票 {} 佔 {}%，超過 {}%",
+                w.label,
+                w.percent.normalize(),
+                stock_limit
+            )
+        })
+        .chain(
+            by_sector
+                .iter()
+                .filter(|w| w.percent > sector_limit)
+                .map(|w| {
+                    format!(
+                        "產業 {} 佔 {}%，超過 {}%",
+                        w.label,
+                        w.percent.normalize(),
+                        sector_limit
+                    )
+                }),
+        )
+        .collect();
+
+    Allocation {
+        member_id,
+        total,
+        by_stock,
+        by_sector,
+        by_cap,
+        warnings,
+    }
+}
+
+/// 相同類別的市值加總後換算成百分比(小數兩位)
+fn weights<I>(items: I, total: Decimal) -> Vec<Weight>
+where
+    I: IntoIterator<Item = (String, Decimal)>,
+{
+    let mut sums: HashMap<String, Decimal> = HashMap::new();
+    for (label, value) in items {
+        *sums.entry(label).or_default() += value;
+    }
+
+    let mut list: Vec<Weight> = sums
+        .into_iter()
+        .map(|(label, market_value)| Weight {
+            percent: if total.is_zero() {
+                Decimal::ZERO
+            } else {
+                (market_value / total * dec!(100)).round_dp(2)
+            },
+            label,
+            market_value,
+        })
+        .collect();
+    list.sort_by_key(|w| (Reverse(w.market_value), w.label.clone()));
+    list
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn holding(symbol: &str, industry: &str, value: Decimal, market_cap: Decimal) -> Holding {
+        Holding {
+            member_id: 1,
+            stock_symbol: symbol.to_string(),
+            name: format!("{}名稱", symbol),
+            industry: industry.to_string(),
+            market_value: value,
+            market_cap,
+        }
+    }
+
+    #[test]
+    fn test_analyze() {
+        let holdings = vec![
+            holding("2330", "半導體業", dec!(600), dec!(20000000000000)),
+            holding("2303", "半導體業", dec!(200), dec!(700000000000)),
+            holding("2884", "金融保險業", dec!(150), dec!(50000000000)),
+            holding("0056", "其他", dec!(50), Decimal::ZERO),
+        ];
+
+        let allocation = analyze(1, &holdings, dec!(25), dec!(50));
+
+        assert_eq!(allocation.total, dec!(1000));
+        assert_eq!(allocation.by_stock[0].label, "2330 2330名稱");
+        assert_eq!(allocation.by_stock[0].percent, dec!(60));
+        assert_eq!(allocation.by_sector[0].label, "半導體業");
+        assert_eq!(allocation.by_sector[0].percent, dec!(80));
+        assert_eq!(
+            allocation
+                .by_cap
+                .iter()
+                .map(|w| (w.label.as_str(), w.percent))
+                .collect::<Vec<_>>(),
+            vec![
+                (CapBucket::Large.label(), dec!(80)),
+                (CapBucket::Mid.label(), dec!(15)),
+                (CapBucket::Unknown.label(), dec!(5)),
+            ]
+        );
+        assert_eq!(allocation.warnings.len(), 2);
+        assert!(allocation.warnings[0].contains("2330"));
+        assert!(allocation.warnings[1].contains("半導體業"));
+    }
+
+    #[test]
+    fn test_cap_bucket() {
+        assert_eq!(CapBucket::from_market_cap(dec!(0)), CapBucket::Unknown);
+        assert_eq!(CapBucket::from_market_cap(MID_CAP), CapBucket::Mid);
+        assert_eq!(CapBucket::from_market_cap(dec!(9999)), CapBucket::Small);
+        assert_eq!(CapBucket::from_market_cap(LARGE_CAP), CapBucket::Large);
+    }
+}
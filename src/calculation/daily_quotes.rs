@@ -1,6 +1,6 @@
 use anyhow::{anyhow, Result};
 use chrono::NaiveDate;
-use futures::{stream, StreamExt};
+use futures::StreamExt;
 use rust_decimal::Decimal;
 
 use crate::{
@@ -13,10 +13,13 @@ use crate::{
 
 /// 計算每家公司指定日期的均線值
 pub async fn calculate_moving_average(date: NaiveDate) -> Result<()> {
-    let quotes = daily_quote::fetch_daily_quotes_by_date(date).await?;
-    stream::iter(quotes)
+    daily_quote::stream_daily_quotes_by_date(date)
         .for_each_concurrent(util::concurrent_limit_32(), |dq| async move {
-            if let Err(why) = process_daily_quote_moving_average(dq).await {
+            let result = match dq {
+                Ok(dq) => process_daily_quote_moving_average(dq).await,
+                Err(why) => Err(why),
+            };
+            if let Err(why) = result {
                 logging::error_file_async(format!(
                     "Failed to moving_average::calculate because {:?}",
                     why
@@ -72,9 +75,9 @@ pub(crate) async fn process_daily_quote_moving_average(mut dq: DailyQuote) -> Re
 
     let qhr = match SHARE.quote_history_records.write() {
         Ok(mut quote_history_records_guard) => {
-            match quote_history_records_guard.get_mut(&dq.security_code) {
+            match quote_history_records_guard.get_mut(dq.security_code.as_str()) {
                 None => {
-                    let mut qhr = QuoteHistoryRecord::new(dq.security_code.to_string());
+                    let mut qhr = QuoteHistoryRecord::new(dq.security_code.clone());
                     qhr.maximum_price_date_on = dq.date;
                     qhr.maximum_price_to_book_ratio_date_on = dq.date;
                     qhr.minimum_price_date_on = dq.date;
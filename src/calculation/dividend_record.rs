@@ -49,7 +49,7 @@ async fn calculate_dividend(
 ) -> Result<()> {
     //計算股票於該年度可以領取的股利
     let mut d = dividend::Dividend::new();
-    d.security_code = sod.security_code.to_string();
+    d.security_code = sod.security_code.clone();
     d.year = year;
     let dividend_sum = d
         .fetch_yearly_dividends_sum_by_date(sod.created_time)
@@ -69,6 +69,7 @@ async fn calculate_dividend(
         dividend_total,
     );
 
+    // 股利記錄與明細在同一個交易內寫入，與 calculate_money_history 相同不以 database::with_retry 重試單一查詢
     let mut tx_option = database::get_tx().await.ok();
     //更新股利領取記錄
     let dividend_record_detail_serial = match drd.upsert(&mut tx_option).await {
@@ -229,7 +230,7 @@ mod tests {
         logging::debug_file_async("開始 calculate_dividend".to_string());
         let mut sod = stock_ownership_details::StockOwnershipDetail::new();
         sod.serial = 102;
-        sod.security_code = "2882".to_string();
+        sod.security_code = "2882".parse().unwrap();
         sod.member_id = 2;
         sod.share_quantity = 300;
         sod.created_time = Local.with_ymd_and_hms(2023, 4, 9, 0, 0, 0).unwrap();
@@ -0,0 +1,472 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::{prelude::FromPrimitive, Decimal};
+use rust_decimal_macros::dec;
+
+use crate::{
+    cache::SHARE,
+    calculation::rebalance::{self, Side},
+    config::SETTINGS,
+    database::{
+        self,
+        table::{
+            stock_lot_sale::StockLotSale,
+            stock_ownership_details::{Lot, StockOwnershipDetail},
+            trade_note::{self, TradeNote},
+        },
+    },
+    declare::SecurityType,
+};
+
+/// 沒有標籤的賣出明細依標籤彙總時使用的名稱
+pub const UNTAGGED: &str = "未標記";
+
+/// 賣出時配對買進批次的方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Method {
+    /// 先進先出，依買進日期由早到晚配對
+    Fifo,
+    /// 指定批次，只依列出的庫存序號順序配對
+    Specific(Vec<i64>),
+}
+
+/// 單一股票的已實現損益與持有期間統計
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Summary {
+    pub security_code: String,
+    /// 賣出的股數
+    pub quantity: i64,
+    pub cost: Decimal,
+    pub proceeds: Decimal,
+    pub fee: Decimal,
+    pub realized: Decimal,
+    /// 配對的批次筆數
+    pub lots: usize,
+    /// 依股數加權的平均持有天數
+    pub average_holding_days: Decimal,
+    pub shortest_holding_days: i32,
+    pub longest_holding_days: i32,
+}
+
+impl Summary {
+    /// 已實現報酬率(%)
+    pub fn return_rate(&self) -> Decimal {
+        if self.cost <= Decimal::ZERO {
+            return Decimal::ZERO;
+        }
+
+        self.realized / self.cost * dec!(100)
+    }
+}
+
+/// 單一標籤的已實現損益
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TagRealized {
+    pub tag: String,
+    /// 賣出明細的筆數
+    pub sales: usize,
+    pub cost: Decimal,
+    pub realized: Decimal,
+}
+
+impl TagRealized {
+    /// 已實現報酬率(%)
+    pub fn return_rate(&self) -> Decimal {
+        if self.cost <= Decimal::ZERO {
+            return Decimal::ZERO;
+        }
+
+        self.realized / self.cost * dec!(100)
+    }
+}
+
+/// 將賣出的股數配對到批次，批次的股數不足或指定的批次不存在時回傳 None
+///
+/// 成本依批次尚未賣出的持有成本按股數分攤，費用依股數分攤，尾差歸最後一個批次
+pub fn match_lots(
+    lots: &[Lot],
+    quantity: i64,
+    price: Decimal,
+    fee: Decimal,
+    date: NaiveDate,
+    method: &Method,
+) -> Option<Vec<StockLotSale>> {
+    if quantity <= 0 {
+        return None;
+    }
+
+    let ordered: Vec<&Lot> = match method {
+        Method::Fifo => {
+            let mut ordered: Vec<&Lot> = lots.iter().collect();
+            ordered.sort_by_key(|lot| (lot.date, lot.serial));
+            ordered
+        }
+        Method::Specific(serials) => {
+            let mut ordered = Vec::with_capacity(serials.len());
+            for serial in serials {
+                let lot = lots.iter().find(|lot| lot.serial == *serial)?;
+                if !ordered.contains(&lot) {
+                    ordered.push(lot);
+                }
+            }
+            ordered
+        }
+    };
+
+    let mut remaining = quantity;
+    let mut sales = Vec::new();
+    for lot in ordered {
+        if remaining == 0 {
+            break;
+        }
+        let sold = remaining.min(lot.share_quantity);
+        if sold <= 0 {
+            continue;
+        }
+
+        let cost = if sold == lot.share_quantity {
+            lot.holding_cost
+        } else {
+            (lot.holding_cost * Decimal::from(sold) / Decimal::from(lot.share_quantity)).round_dp(4)
+        };
+        sales.push(StockLotSale {
+            member_id: lot.member_id,
+            security_code: lot.security_code.clone(),
+            lot_serial: lot.serial,
+            quantity: sold,
+            buy_date: lot.date,
+            sell_date: date,
+            sell_price: price,
+            cost,
+            proceeds: price * Decimal::from(sold),
+            holding_days: (date - lot.date).num_days() as i32,
+            ..Default::default()
+        });
+        remaining -= sold;
+    }
+    if remaining > 0 {
+        return None;
+    }
+
+    let mut allocated = Decimal::ZERO;
+    let last = sales.len() - 1;
+    for (i, sale) in sales.iter_mut().enumerate() {
+        sale.fee = if i == last {
+            fee - allocated
+        } else {
+            (fee * Decimal::from(sale.quantity) / Decimal::from(quantity)).round_dp(4)
+        };
+        allocated += sale.fee;
+        sale.realized = sale.proceeds - sale.cost - sale.fee;
+    }
+
+    Some(sales)
+}
+
+/// 賣出股票並在同一個交易內扣除配對批次的股數與寫入配對明細，批次的股數不足時回傳 None
+///
+/// 費用以設定檔 rebalance.fee_discount 的手續費折扣估算手續費，另加證券交易稅
+pub async fn sell(
+    lots: &[Lot],
+    quantity: i64,
+    price: Decimal,
+    date: NaiveDate,
+    method: &Method,
+) -> Result<Option<Vec<StockLotSale>>> {
+    let Some(security_code) = lots.first().map(|lot| lot.security_code.as_str()) else {
+        return Ok(None);
+    };
+    let is_etf = SHARE
+        .get_stock(security_code)
+        .await
+        .is_some_and(|stock| stock.security_type == SecurityType::Etf.serial());
+    let discount = Decimal::from_f64(SETTINGS.load().rebalance.fee_discount)
+        .filter(|discount| *discount > Decimal::ZERO)
+        .unwrap_or(Decimal::ONE);
+    let fee = rebalance::cost(Side::Sell, quantity, price, discount, is_etf);
+
+    let Some(mut sales) = match_lots(lots, quantity, price, fee, date, method) else {
+        return Ok(None);
+    };
+
+    let mut tx = database::get_tx().await?;
+    for sale in &mut sales {
+        StockOwnershipDetail::reduce_lot(&mut tx, sale.lot_serial, sale.quantity, sale.cost)
+            .await?;
+        sale.serial = sale.insert(&mut tx).await?;
+    }
+    tx.commit().await.context(format!(
+        "Failed to lot::sell({}, {}) from database",
+        security_code, quantity
+    ))?;
+
+    Ok(Some(sales))
+}
+
+/// 依股票彙總配對明細的已實現損益與持有天數，依股票代號排序
+pub fn summarize(sales: &[StockLotSale]) -> Vec<Summary> {
+    let mut summaries: BTreeMap<&str, Summary> = BTreeMap::new();
+    let mut share_days: BTreeMap<&str, Decimal> = BTreeMap::new();
+
+    for sale in sales {
+        let summary = summaries
+            .entry(&sale.security_code)
+            .or_insert_with(|| Summary {
+                security_code: sale.security_code.to_string(),
+                shortest_holding_days: sale.holding_days,
+                longest_holding_days: sale.holding_days,
+                ..Default::default()
+            });
+        summary.quantity += sale.quantity;
+        summary.cost += sale.cost;
+        summary.proceeds += sale.proceeds;
+        summary.fee += sale.fee;
+        summary.realized += sale.realized;
+        summary.lots += 1;
+        summary.shortest_holding_days = summary.shortest_holding_days.min(sale.holding_days);
+        summary.longest_holding_days = summary.longest_holding_days.max(sale.holding_days);
+        *share_days.entry(&sale.security_code).or_default() +=
+            Decimal::from(sale.quantity) * Decimal::from(sale.holding_days);
+    }
+
+    summaries
+        .into_iter()
+        .map(|(security_code, mut summary)| {
+            if summary.quantity > 0 {
+                summary.average_holding_days =
+                    (share_days[security_code] / Decimal::from(summary.quantity)).round_dp(1);
+            }
+            summary
+        })
+        .collect()
+}
+
+/// 取得賣出日期在區間內的配對明細與標記在明細或其買進批次上的筆記
+pub async fn fetch_with_notes(
+    member_id: Option<i64>,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<(Vec<StockLotSale>, Vec<TradeNote>)> {
+    let sales = StockLotSale::fetch_between(member_id, start, end).await?;
+    if sales.is_empty() {
+        return Ok((sales, Vec::new()));
+    }
+
+    let lot_serials: Vec<i64> = sales.iter().map(|sale| sale.lot_serial).collect();
+    let sale_serials: Vec<i64> = sales.iter().map(|sale| sale.serial).collect();
+    let notes = TradeNote::fetch_for(&lot_serials, &sale_serials).await?;
+
+    Ok((sales, notes))
+}
+
+/// 賣出明細的標籤，包含標記在明細本身與其買進批次上的標籤
+pub fn tags<'a>(sale: &StockLotSale, notes: &'a [TradeNote]) -> BTreeSet<&'a str> {
+    notes
+        .iter()
+        .filter(|note| !note.tag.is_empty())
+        .filter(|note| {
+            (note.target == trade_note::LOT && note.target_serial == sale.lot_serial)
+                || (note.target == trade_note::SALE && note.target_serial == sale.serial)
+        })
+        .map(|note| note.tag.as_str())
+        .collect()
+}
+
+/// 依標籤彙總已實現損益，一筆明細有多個標籤時計入每個標籤，沒有標籤的明細彙總在最後的「未標記」
+pub fn realized_by_tag(sales: &[StockLotSale], notes: &[TradeNote]) -> Vec<TagRealized> {
+    let mut tagged: BTreeMap<&str, TagRealized> = BTreeMap::new();
+    let mut untagged = TagRealized {
+        tag: UNTAGGED.to_string(),
+        ..Default::default()
+    };
+
+    for sale in sales {
+        let tags = tags(sale, notes);
+        let add = |entry: &mut TagRealized| {
+            entry.sales += 1;
+            entry.cost += sale.cost;
+            entry.realized += sale.realized;
+        };
+        if tags.is_empty() {
+            add(&mut untagged);
+        }
+        for tag in tags {
+            add(tagged.entry(tag).or_insert_with(|| TagRealized {
+                tag: tag.to_string(),
+                ..Default::default()
+            }));
+        }
+    }
+
+    let mut list: Vec<TagRealized> = tagged.into_values().collect();
+    if untagged.sales > 0 {
+        list.push(untagged);
+    }
+    list
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    fn lots() -> Vec<Lot> {
+        let lot = |serial: i64, quantity: i64, cost: Decimal, date: NaiveDate| Lot {
+            serial,
+            member_id: 1,
+            security_code: "2330".parse().unwrap(),
+            share_quantity: quantity,
+            holding_cost: cost,
+            date,
+        };
+
+        vec![
+            lot(12, 1000, dec!(600855), date(3, 1)),
+            lot(10, 500, dec!(250356), date(1, 2)),
+            lot(15, 2000, dec!(1601425), date(6, 3)),
+        ]
+    }
+
+    #[test]
+    fn test_match_lots_fifo() {
+        let sales = match_lots(
+            &lots(),
+            1200,
+            dec!(900),
+            dec!(3779),
+            date(7, 1),
+            &Method::Fifo,
+        )
+        .unwrap();
+
+        assert_eq!(sales.len(), 2);
+        assert_eq!(sales[0].lot_serial, 10);
+        assert_eq!(sales[0].quantity, 500);
+        assert_eq!(sales[0].cost, dec!(250356));
+        assert_eq!(sales[0].holding_days, 181);
+        // 3779 * 500 / 1200 = 1574.5833
+        assert_eq!(sales[0].fee, dec!(1574.5833));
+        assert_eq!(
+            sales[0].realized,
+            dec!(450000) - dec!(250356) - dec!(1574.5833)
+        );
+        assert_eq!(sales[1].lot_serial, 12);
+        assert_eq!(sales[1].quantity, 700);
+        // 600855 * 700 / 1000
+        assert_eq!(sales[1].cost, dec!(420598.5));
+        assert_eq!(sales[1].fee, dec!(2204.4167));
+        assert_eq!(sales[0].fee + sales[1].fee, dec!(3779));
+    }
+
+    #[test]
+    fn test_match_lots_specific() {
+        let method = Method::Specific(vec![15, 10]);
+        let sales = match_lots(&lots(), 2200, dec!(900), dec!(0), date(7, 1), &method).unwrap();
+
+        assert_eq!(
+            sales
+                .iter()
+                .map(|sale| (sale.lot_serial, sale.quantity))
+                .collect::<Vec<_>>(),
+            vec![(15, 2000), (10, 200)]
+        );
+        assert_eq!(sales[0].holding_days, 28);
+
+        // 指定的批次股數不足、批次不存在或全部批次都不足時無法配對
+        let method = Method::Specific(vec![10]);
+        assert!(match_lots(&lots(), 600, dec!(900), dec!(0), date(7, 1), &method).is_none());
+        let method = Method::Specific(vec![99]);
+        assert!(match_lots(&lots(), 100, dec!(900), dec!(0), date(7, 1), &method).is_none());
+        assert!(match_lots(&lots(), 3501, dec!(900), dec!(0), date(7, 1), &Method::Fifo).is_none());
+        assert!(match_lots(&lots(), 0, dec!(900), dec!(0), date(7, 1), &Method::Fifo).is_none());
+    }
+
+    #[test]
+    fn test_summarize() {
+        let sale =
+            |code: &str, quantity: i64, cost: Decimal, realized: Decimal, days: i32| StockLotSale {
+                security_code: code.parse().unwrap(),
+                quantity,
+                cost,
+                realized,
+                holding_days: days,
+                ..Default::default()
+            };
+        let sales = vec![
+            sale("2330", 500, dec!(250000), dec!(50000), 181),
+            sale("0056", 1000, dec!(30000), dec!(-1000), 10),
+            sale("2330", 1500, dec!(750000), dec!(25000), 30),
+        ];
+
+        let summaries = summarize(&sales);
+
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].security_code, "0056");
+        let tsmc = &summaries[1];
+        assert_eq!(tsmc.quantity, 2000);
+        assert_eq!(tsmc.lots, 2);
+        assert_eq!(tsmc.realized, dec!(75000));
+        assert_eq!(tsmc.return_rate(), dec!(7.5));
+        // (500 * 181 + 1500 * 30) / 2000 = 67.75
+        assert_eq!(tsmc.average_holding_days, dec!(67.8));
+        assert_eq!(
+            (tsmc.shortest_holding_days, tsmc.longest_holding_days),
+            (30, 181)
+        );
+    }
+
+    #[test]
+    fn test_realized_by_tag() {
+        let sale = |serial: i64, lot_serial: i64, realized: Decimal| StockLotSale {
+            serial,
+            lot_serial,
+            security_code: "2330".parse().unwrap(),
+            cost: dec!(1000),
+            realized,
+            ..Default::default()
+        };
+        let note = |target: &str, target_serial: i64, tag: &str| TradeNote {
+            target: target.to_string(),
+            target_serial,
+            tag: tag.to_string(),
+            ..Default::default()
+        };
+        let sales = vec![
+            sale(1, 10, dec!(100)),
+            sale(2, 12, dec!(-50)),
+            sale(3, 15, dec!(30)),
+        ];
+        let notes = vec![
+            note(trade_note::LOT, 10, "dividend_capture"),
+            note(trade_note::SALE, 1, "earnings_play"),
+            note(trade_note::SALE, 2, "dividend_capture"),
+            note(trade_note::LOT, 15, ""),
+            // 序號相同但標記的是賣出明細，不屬於批次 12
+            note(trade_note::SALE, 12, "other"),
+        ];
+
+        assert_eq!(
+            tags(&sales[0], &notes).into_iter().collect::<Vec<_>>(),
+            vec!["dividend_capture", "earnings_play"]
+        );
+
+        let list = realized_by_tag(&sales, &notes);
+
+        assert_eq!(
+            list.iter()
+                .map(|t| (t.tag.as_str(), t.sales, t.realized))
+                .collect::<Vec<_>>(),
+            vec![
+                ("dividend_capture", 2, dec!(50)),
+                ("earnings_play", 1, dec!(100)),
+                (UNTAGGED, 1, dec!(30)),
+            ]
+        );
+    }
+}
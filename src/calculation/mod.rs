@@ -1,8 +1,26 @@
+/// 依除權息計算還原收盤價
+pub mod adjusted_price;
+/// 成員持股依股票、產業、市值分類的比重與集中度
+pub mod allocation;
 /// 股票每日行情
 pub mod daily_quotes;
 /// 計算股票股息收入
 pub mod dividend_record;
 /// 估算便宜、合理、昂貴價
 pub mod estimated_price;
+/// 賣出與買進批次的配對(先進先出或指定批次)與已實現損益
+pub mod lot;
 /// 計算每日市值
 pub mod money_history;
+/// 依設定檔的目標配置計算買賣股數的再平衡建議
+pub mod rebalance;
+/// 月營收公布前估算庫存股票的月營收
+pub mod revenue_estimate;
+/// 近 5 年同月份的營收季節性與偏離常態的月份
+pub mod revenue_seasonality;
+/// 庫存的 beta、年化波動度與夏普比率
+pub mod risk;
+/// 依資金進出與持股市值計算成員帳戶的年化內部報酬率
+pub mod xirr;
+/// 模擬交易委託的撮合與損益
+pub mod paper_trading;
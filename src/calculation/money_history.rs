@@ -1,20 +1,31 @@
-use anyhow::{anyhow, Result};
-use chrono::NaiveDate;
+use std::collections::HashSet;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
 
 use crate::{
+    crawler::twse,
     database::{
         self,
         table::{
+            adjusted_quote::AdjustedQuote,
             daily_money_history::DailyMoneyHistory,
             daily_money_history_detail::DailyMoneyHistoryDetail,
             daily_money_history_detail_more::DailyMoneyHistoryDetailMore,
-            daily_stock_price_stats::DailyStockPriceStats
+            daily_stock_price_stats::DailyStockPriceStats,
+            index::Index,
+            portfolio_return::PortfolioReturn
         }
-    }
+    },
+    logging
 };
 
 /// 計算指定日期帳戶內的市值
 pub async fn calculate_money_history(date: NaiveDate) -> Result<()> {
+    // 各表的 upsert 都在這個交易內執行，序列化失敗或死結後交易已中止，只重試單一查詢沒有意義，
+    // 因此不使用 database::with_retry，失敗時整個交易回滾並回傳錯誤
     let mut tx_option = database::get_tx().await.ok();
 
     if let Err(why) = DailyMoneyHistory::upsert(date, &mut tx_option).await {
@@ -38,6 +49,13 @@ pub async fn calculate_money_history(date: NaiveDate) -> Result<()> {
         return Err(anyhow!("{:?}", why));
     }
 
+    if let Err(why) = PortfolioReturn::upsert(date, &mut tx_option).await {
+        if let Some(tx) = tx_option {
+            tx.rollback().await?;
+        }
+        return Err(anyhow!("{:?}", why));
+    }
+
     if let Err(why) = DailyMoneyHistoryDetailMore::delete(date, &mut tx_option).await {
         if let Some(tx) = tx_option {
             tx.rollback().await?;
@@ -65,3 +83,162 @@ pub async fn calculate_money_history(date: NaiveDate) -> Result<()> {
 
     Ok(())
 }
+
+/// 重新計算 from 到 to(含)之間每個交易日帳戶內的市值，匯入舊的交易紀錄後用來重建歷史市值
+///
+/// 依證交所的休市日期略過週末與國定假日，每個交易日各自使用一個交易，遇到失敗時中止並回傳失敗的日期
+pub async fn calculate_money_history_range(from: NaiveDate, to: NaiveDate) -> Result<usize> {
+    let mut holidays = HashSet::new();
+    for year in from.year()..=to.year() {
+        let schedule = twse::holiday_schedule::visit(year).await?;
+        holidays.extend(schedule.into_iter().map(|holiday| holiday.date));
+    }
+
+    let days = trading_days(from, to, &holidays);
+    let total = days.len();
+    for (i, date) in days.into_iter().enumerate() {
+        calculate_money_history(date)
+            .await
+            .context(format!("Failed to calculate_money_history({})", date))?;
+        logging::info_file_async(format!("計算帳戶內市值 {} ({}/{})", date, i + 1, total));
+    }
+
+    Ok(total)
+}
+
+/// from 到 to(含)之間扣除週末與休市日期後的交易日
+fn trading_days(from: NaiveDate, to: NaiveDate, holidays: &HashSet<NaiveDate>) -> Vec<NaiveDate> {
+    from.iter_days()
+        .take_while(|date| *date <= to)
+        .filter(|date| !matches!(date.weekday(), Weekday::Sat | Weekday::Sun))
+        .filter(|date| !holidays.contains(date))
+        .collect()
+}
+
+/// 用來比較的 ETF
+const BENCHMARK_ETF: &str = "0050";
+/// 用來比較的大盤指數
+const BENCHMARK_INDEX: &str = "TAIEX";
+/// 往前找基期收盤價的日曆天數，需涵蓋連假
+const BASE_LOOKBACK_DAYS: u64 = 14;
+
+/// 庫存的時間加權報酬與同期間買進 0050、加權指數的報酬(%)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BenchmarkComparison {
+    /// 實際比較的期間，期初為第一筆庫存報酬的前一天
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub portfolio: Decimal,
+    /// 0050 以還原收盤價計算，含配息再投入
+    pub etf: Decimal,
+    /// 加權指數不含股利
+    pub index: Decimal,
+}
+
+impl BenchmarkComparison {
+    /// 庫存相對 0050 的超額報酬(百分點)
+    pub fn excess_over_etf(&self) -> Decimal {
+        self.portfolio - self.etf
+    }
+
+    /// 庫存相對加權指數的超額報酬(百分點)
+    pub fn excess_over_index(&self) -> Decimal {
+        self.portfolio - self.index
+    }
+}
+
+/// 比較 start 收盤後到 end 收盤的庫存時間加權報酬與同期間 0050、加權指數的報酬，沒有庫存報酬時回傳 None
+pub async fn compare_with_benchmarks(
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<Option<BenchmarkComparison>> {
+    let returns = PortfolioReturn::fetch_between(start.succ_opt().unwrap_or(start), end).await?;
+    let Some(first) = returns.first() else {
+        return Ok(None);
+    };
+    // 庫存報酬晚於期初才開始記錄時，基準改從同一天開始比較
+    let start = start.max(first.date.pred_opt().unwrap_or(first.date));
+    let since = start
+        .checked_sub_days(Days::new(BASE_LOOKBACK_DAYS))
+        .unwrap_or(start);
+
+    let etf: Vec<(NaiveDate, Decimal)> = AdjustedQuote::fetch(BENCHMARK_ETF, since)
+        .await?
+        .into_iter()
+        .map(|quote| (quote.date, quote.adjusted_closing_price))
+        .collect();
+    let index = Index::fetch_closing(BENCHMARK_INDEX, since, end).await?;
+    let daily: Vec<Decimal> = returns.iter().map(|r| r.daily_return).collect();
+
+    Ok(Some(BenchmarkComparison {
+        start,
+        end,
+        portfolio: chain_returns(&daily) * dec!(100),
+        etf: period_return(&etf, start, end).unwrap_or_default() * dec!(100),
+        index: period_return(&index, start, end).unwrap_or_default() * dec!(100),
+    }))
+}
+
+/// 將每日報酬連乘為期間報酬
+fn chain_returns(daily: &[Decimal]) -> Decimal {
+    daily
+        .iter()
+        .fold(Decimal::ONE, |acc, r| acc * (Decimal::ONE + r))
+        - Decimal::ONE
+}
+
+/// 以 start(含)以前最後一個收盤價為基期、end(含)以前最後一個收盤價為期末計算報酬，prices 需依日期由舊到新排序
+fn period_return(
+    prices: &[(NaiveDate, Decimal)],
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Option<Decimal> {
+    let base = prices
+        .iter()
+        .rev()
+        .find(|(date, _)| *date <= start)
+        .or_else(|| prices.first())?;
+    let last = prices.iter().rev().find(|(date, _)| *date <= end)?;
+    if base.1.is_zero() {
+        return None;
+    }
+
+    Some(last.1 / base.1 - Decimal::ONE)
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_trading_days() {
+        let date = |d: u32| NaiveDate::from_ymd_opt(2024, 12, d).unwrap();
+        let holidays = HashSet::from([date(25)]);
+
+        assert_eq!(
+            trading_days(date(20), date(27), &holidays),
+            vec![date(20), date(23), date(24), date(26), date(27)]
+        );
+        assert!(trading_days(date(27), date(20), &holidays).is_empty());
+    }
+
+    #[test]
+    fn test_benchmark_returns() {
+        let date = |d: u32| NaiveDate::from_ymd_opt(2024, 12, d).unwrap();
+
+        assert_eq!(chain_returns(&[dec!(0.1), dec!(-0.1)]), dec!(-0.01));
+        assert_eq!(chain_returns(&[]), Decimal::ZERO);
+
+        let prices = vec![
+            (date(19), dec!(100)),
+            (date(20), dec!(110)),
+            (date(23), dec!(121)),
+            (date(24), dec!(99)),
+        ];
+        // 期初 21 日為週末，以 20 日的收盤價為基期
+        assert_eq!(period_return(&prices, date(21), date(23)), Some(dec!(0.1)));
+        assert_eq!(period_return(&prices, date(1), date(20)), Some(dec!(0.1)));
+        assert_eq!(period_return(&prices, date(20), date(1)), None);
+    }
+}
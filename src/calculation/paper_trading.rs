@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+use crate::database::table::paper_order::{self, PaperOrder};
+
+/// 模擬交易帳戶內單一股票的部位
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Position {
+    pub security_code: String,
+    /// 持有股數，全部賣出後為 0
+    pub quantity: i64,
+    /// 持有股數的總成本
+    pub cost: Decimal,
+    /// 已實現損益
+    pub realized: Decimal,
+}
+
+impl Position {
+    /// 平均成本
+    pub fn average_cost(&self) -> Decimal {
+        if self.quantity == 0 {
+            return Decimal::ZERO;
+        }
+
+        self.cost / Decimal::from(self.quantity)
+    }
+
+    /// 依指定價格計算的未實現損益
+    pub fn unrealized(&self, price: Decimal) -> Decimal {
+        price * Decimal::from(self.quantity) - self.cost
+    }
+}
+
+/// 以指定日期的報價撮合先前下單的模擬交易委託，回傳成交的筆數
+pub async fn fill(date: NaiveDate) -> Result<u64> {
+    PaperOrder::fill(date).await
+}
+
+/// 依已成交的委託計算各股票的部位，賣出以平均成本計算已實現損益，超過持有股數的部分不計
+pub fn positions(orders: &[PaperOrder]) -> Vec<Position> {
+    let mut filled: Vec<&PaperOrder> = orders
+        .iter()
+        .filter(|order| order.status == paper_order::FILLED)
+        .collect();
+    filled.sort_by_key(|order| (order.fill_date, order.serial));
+
+    let mut positions: BTreeMap<&str, Position> = BTreeMap::new();
+    for order in filled {
+        let Some(price) = order.fill_price else {
+            continue;
+        };
+        let position = positions
+            .entry(&order.security_code)
+            .or_insert_with(|| Position {
+                security_code: order.security_code.to_string(),
+                ..Default::default()
+            });
+
+        if order.side == paper_order::BUY {
+            position.quantity += order.quantity;
+            position.cost += price * Decimal::from(order.quantity);
+            continue;
+        }
+
+        let quantity = order.quantity.min(position.quantity);
+        if quantity == 0 {
+            continue;
+        }
+        let cost = position.average_cost() * Decimal::from(quantity);
+        position.realized += price * Decimal::from(quantity) - cost;
+        position.cost -= cost;
+        position.quantity -= quantity;
+    }
+
+    positions.into_values().collect()
+}
+
+/// 還可以賣出的股數 = 持有股數 - 等待成交的賣出委託
+pub fn sellable(orders: &[PaperOrder], security_code: &str) -> i64 {
+    let held = positions(orders)
+        .into_iter()
+        .find(|position| position.security_code == security_code)
+        .map_or(0, |position| position.quantity);
+    let pending: i64 = orders
+        .iter()
+        .filter(|order| {
+            order.status == paper_order::PENDING
+                && order.side == paper_order::SELL
+                && order.security_code == security_code
+        })
+        .map(|order| order.quantity)
+        .sum();
+
+    held - pending
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn order(serial: i64, side: &str, quantity: i64, price: Option<Decimal>) -> PaperOrder {
+        let date = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        PaperOrder {
+            serial,
+            account_id: 1,
+            security_code: "2330".parse().unwrap(),
+            side: side.to_string(),
+            quantity,
+            price_type: paper_order::OPEN.to_string(),
+            status: if price.is_some() {
+                paper_order::FILLED
+            } else {
+                paper_order::PENDING
+            }
+            .to_string(),
+            order_date: date,
+            fill_date: price.map(|_| date + chrono::Duration::days(serial)),
+            fill_price: price,
+        }
+    }
+
+    #[test]
+    fn test_positions() {
+        let orders = vec![
+            order(1, paper_order::BUY, 1000, Some(dec!(100))),
+            order(2, paper_order::BUY, 1000, Some(dec!(110))),
+            order(3, paper_order::SELL, 500, Some(dec!(120))),
+            order(4, paper_order::BUY, 1000, None),
+        ];
+
+        let positions = positions(&orders);
+
+        assert_eq!(positions.len(), 1);
+        assert_eq!(positions[0].quantity, 1500);
+        assert_eq!(positions[0].average_cost(), dec!(105));
+        assert_eq!(positions[0].realized, dec!(7500));
+        assert_eq!(positions[0].unrealized(dec!(100)), dec!(-7500));
+    }
+
+    #[test]
+    fn test_sell_more_than_held() {
+        let orders = vec![
+            order(1, paper_order::BUY, 1000, Some(dec!(100))),
+            order(2, paper_order::SELL, 3000, Some(dec!(90))),
+        ];
+
+        let positions = positions(&orders);
+
+        assert_eq!(positions[0].quantity, 0);
+        assert_eq!(positions[0].realized, dec!(-10000));
+        assert_eq!(positions[0].cost, dec!(0));
+    }
+
+    #[test]
+    fn test_sellable() {
+        let orders = vec![
+            order(1, paper_order::BUY, 1000, Some(dec!(100))),
+            order(2, paper_order::SELL, 400, None),
+        ];
+
+        assert_eq!(sellable(&orders, "2330"), 600);
+        assert_eq!(sellable(&orders, "2317"), 0);
+    }
+}
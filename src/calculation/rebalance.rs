@@ -0,0 +1,349 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    pin::pin,
+};
+
+use anyhow::Result;
+use futures::TryStreamExt;
+use rust_decimal::{prelude::FromPrimitive, Decimal, RoundingStrategy};
+use rust_decimal_macros::dec;
+
+use crate::{
+    cache::SHARE, config::SETTINGS, database::table::stock_ownership_details::StockOwnershipDetail,
+    declare::SecurityType,
+};
+
+/// 一張的股數
+pub const SHARES_PER_LOT: i64 = 1000;
+/// 券商手續費率
+const FEE_RATE: Decimal = dec!(0.001425);
+/// 整股交易的最低手續費
+const MIN_FEE: Decimal = dec!(20);
+/// 零股交易的最低手續費，多數券商為 1 元
+const MIN_ODD_LOT_FEE: Decimal = dec!(1);
+/// 股票賣出的證券交易稅率
+const STOCK_TAX_RATE: Decimal = dec!(0.003);
+/// ETF 賣出的證券交易稅率
+const ETF_TAX_RATE: Decimal = dec!(0.001);
+/// 手續費加交易稅超過交易金額的幾 % 時不建議，避免小額零股被最低手續費吃掉
+const MAX_COST_PERCENT: Decimal = dec!(1);
+
+/// 買進或賣出
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+impl Side {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Side::Buy => "買進",
+            Side::Sell => "賣出",
+        }
+    }
+}
+
+/// 設定了目標比重的一檔股票，成員沒有持有時股數為 0
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Position {
+    pub stock_symbol: String,
+    pub name: String,
+    /// 最後交易日的收盤價
+    pub price: Decimal,
+    /// 持有股數
+    pub quantity: i64,
+    /// 設定檔的目標比重，尚未換算成百分比
+    pub target: Decimal,
+    /// ETF 的交易稅率較低
+    pub is_etf: bool,
+}
+
+/// 單一股票的調整建議
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub stock_symbol: String,
+    pub name: String,
+    pub side: Side,
+    /// 買賣的股數，整張的部分以整股交易，不足一張的部分以零股交易
+    pub quantity: i64,
+    pub price: Decimal,
+    /// 目前佔目標配置市值的百分比
+    pub current_percent: Decimal,
+    /// 目標百分比
+    pub target_percent: Decimal,
+    /// 預估的手續費加交易稅
+    pub cost: Decimal,
+}
+
+impl Suggestion {
+    /// 整張的張數
+    pub fn lots(&self) -> i64 {
+        self.quantity / SHARES_PER_LOT
+    }
+
+    /// 不足一張的零股股數
+    pub fn odd_shares(&self) -> i64 {
+        self.quantity % SHARES_PER_LOT
+    }
+
+    /// 交易金額
+    pub fn amount(&self) -> Decimal {
+        self.price * Decimal::from(self.quantity)
+    }
+}
+
+/// 成員的再平衡建議
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+    pub member_id: i64,
+    /// 設定了目標比重的股票目前的市值合計
+    pub total: Decimal,
+    /// 先賣後買，同方向依交易金額由大到小排序
+    pub suggestions: Vec<Suggestion>,
+    /// 持有但沒有設定目標比重的股票，不列入計算
+    pub untargeted: Vec<String>,
+}
+
+/// 依設定檔 rebalance.targets 與庫存計算每位成員的再平衡建議，未設定目標時回傳空的列表
+///
+/// 只在設定了目標的股票之間調整，賣出的金額用來買進，不需要投入新的資金
+pub async fn calculate() -> Result<Vec<Plan>> {
+    let settings = &SETTINGS.load().rebalance;
+    let targets: BTreeMap<&str, Decimal> = settings
+        .targets
+        .iter()
+        .filter_map(|(symbol, weight)| {
+            Decimal::from_f64(*weight)
+                .filter(|weight| *weight > Decimal::ZERO)
+                .map(|weight| (symbol.as_str(), weight))
+        })
+        .collect();
+    if targets.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let tolerance = Decimal::from_f64(settings.tolerance).unwrap_or(dec!(2));
+    let discount = Decimal::from_f64(settings.fee_discount)
+        .filter(|discount| *discount > Decimal::ZERO)
+        .unwrap_or(Decimal::ONE);
+
+    let mut members: BTreeMap<i64, HashMap<String, i64>> = BTreeMap::new();
+    let mut details = pin!(StockOwnershipDetail::stream_held());
+    while let Some(detail) = details.try_next().await? {
+        *members
+            .entry(detail.member_id)
+            .or_default()
+            .entry(detail.security_code.to_string())
+            .or_default() += detail.share_quantity;
+    }
+
+    let mut plans = Vec::with_capacity(members.len());
+    for (member_id, shares) in members {
+        let mut positions = Vec::with_capacity(targets.len());
+        for (symbol, target) in &targets {
+            let Some(price) = SHARE.get_stock_last_price(symbol).await else {
+                continue;
+            };
+            let stock = SHARE.get_stock(symbol).await;
+            positions.push(Position {
+                stock_symbol: symbol.to_string(),
+                name: stock
+                    .as_ref()
+                    .map(|stock| stock.name.clone())
+                    .unwrap_or_else(|| symbol.to_string()),
+                price: price.closing_price,
+                quantity: shares.get(*symbol).copied().unwrap_or_default(),
+                target: *target,
+                is_etf: stock
+                    .is_some_and(|stock| stock.security_type == SecurityType::Etf.serial()),
+            });
+        }
+
+        let mut untargeted: Vec<String> = shares
+            .into_keys()
+            .filter(|symbol| !targets.contains_key(symbol.as_str()))
+            .collect();
+        untargeted.sort();
+
+        plans.push(Plan {
+            member_id,
+            total: positions
+                .iter()
+                .map(|p| p.price * Decimal::from(p.quantity))
+                .sum(),
+            suggestions: suggest(&positions, tolerance, discount),
+            untargeted,
+        });
+    }
+
+    Ok(plans)
+}
+
+/// 比重與目標相差超過 tolerance 個百分點的股票換算成買賣股數，
+/// 買進時預留手續費，賣出不超過持有股數，手續費加交易稅太高的交易不列入
+pub fn suggest(positions: &[Position], tolerance: Decimal, discount: Decimal) -> Vec<Suggestion> {
+    let total: Decimal = positions
+        .iter()
+        .map(|p| p.price * Decimal::from(p.quantity))
+        .sum();
+    let weights: Decimal = positions.iter().map(|p| p.target).sum();
+    if total <= Decimal::ZERO || weights <= Decimal::ZERO {
+        return Vec::new();
+    }
+
+    let mut suggestions: Vec<Suggestion> = positions
+        .iter()
+        .filter(|p| p.price > Decimal::ZERO)
+        .filter_map(|p| {
+            let value = p.price * Decimal::from(p.quantity);
+            let current_percent = value / total * dec!(100);
+            let target_percent = p.target / weights * dec!(100);
+            if (target_percent - current_percent).abs() <= tolerance {
+                return None;
+            }
+
+            let difference = total * target_percent / dec!(100) - value;
+            let (side, quantity) = if difference > Decimal::ZERO {
+                let unit = p.price * (Decimal::ONE + FEE_RATE * discount);
+                (Side::Buy, shares(difference / unit))
+            } else {
+                (Side::Sell, shares(-difference / p.price).min(p.quantity))
+            };
+            if quantity <= 0 {
+                return None;
+            }
+
+            let cost = cost(side, quantity, p.price, discount, p.is_etf);
+            if cost > p.price * Decimal::from(quantity) * MAX_COST_PERCENT / dec!(100) {
+                return None;
+            }
+
+            Some(Suggestion {
+                stock_symbol: p.stock_symbol.clone(),
+                name: p.name.clone(),
+                side,
+                quantity,
+                price: p.price,
+                current_percent: current_percent.round_dp(2),
+                target_percent: target_percent.round_dp(2),
+                cost,
+            })
+        })
+        .collect();
+
+    suggestions.sort_by(|a, b| {
+        (a.side == Side::Buy)
+            .cmp(&(b.side == Side::Buy))
+            .then(b.amount().cmp(&a.amount()))
+    });
+    suggestions
+}
+
+/// 無條件捨去成整數股數
+fn shares(quantity: Decimal) -> i64 {
+    quantity
+        .round_dp_with_strategy(0, RoundingStrategy::ToZero)
+        .try_into()
+        .unwrap_or_default()
+}
+
+/// 整張與零股分開下單各自計算手續費，賣出另計交易稅，皆四捨五入到元
+pub fn cost(side: Side, quantity: i64, price: Decimal, discount: Decimal, is_etf: bool) -> Decimal {
+    let fee = |shares: i64, minimum: Decimal| {
+        if shares == 0 {
+            return Decimal::ZERO;
+        }
+
+        (price * Decimal::from(shares) * FEE_RATE * discount)
+            .round()
+            .max(minimum)
+    };
+    let lots = quantity / SHARES_PER_LOT * SHARES_PER_LOT;
+    let mut cost = fee(lots, MIN_FEE) + fee(quantity - lots, MIN_ODD_LOT_FEE);
+
+    if side == Side::Sell {
+        let rate = if is_etf { ETF_TAX_RATE } else { STOCK_TAX_RATE };
+        cost += (price * Decimal::from(quantity) * rate).round();
+    }
+
+    cost
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn position(symbol: &str, price: Decimal, quantity: i64, target: Decimal) -> Position {
+        Position {
+            stock_symbol: symbol.to_string(),
+            name: format!("{}名稱", symbol),
+            price,
+            quantity,
+            target,
+            is_etf: symbol.starts_with("00"),
+        }
+    }
+
+    #[test]
+    fn test_cost() {
+        // 整股 2 張的手續費 100*2000*0.1425% = 285，零股 500 股為 71.25 四捨五入 71
+        assert_eq!(
+            cost(Side::Buy, 2500, dec!(100), Decimal::ONE, false),
+            dec!(356)
+        );
+        // 打 2.8 折後整股低於最低手續費 20 元
+        assert_eq!(cost(Side::Buy, 1000, dec!(10), dec!(0.28), false), dec!(20));
+        // 賣出 ETF 另計 0.1% 交易稅
+        assert_eq!(
+            cost(Side::Sell, 1000, dec!(30), Decimal::ONE, true),
+            dec!(73)
+        );
+        assert_eq!(
+            cost(Side::Sell, 1000, dec!(30), Decimal::ONE, false),
+            dec!(133)
+        );
+    }
+
+    #[test]
+    fn test_suggest() {
+        let positions = vec![
+            position("2330", dec!(1000), 700, dec!(50)),
+            position("0056", dec!(30), 10000, dec!(50)),
+            position("2884", dec!(25), 0, Decimal::ZERO),
+        ];
+
+        let suggestions = suggest(&positions, dec!(2), Decimal::ONE);
+
+        assert_eq!(suggestions.len(), 2);
+        let sell = &suggestions[0];
+        assert_eq!(sell.stock_symbol, "2330");
+        assert_eq!(sell.side, Side::Sell);
+        assert_eq!(sell.quantity, 200);
+        assert_eq!(sell.current_percent, dec!(70));
+        assert_eq!(sell.target_percent, dec!(50));
+        let buy = &suggestions[1];
+        assert_eq!(buy.stock_symbol, "0056");
+        assert_eq!(buy.side, Side::Buy);
+        assert_eq!(buy.quantity, 6657);
+        assert_eq!((buy.lots(), buy.odd_shares()), (6, 657));
+    }
+
+    #[test]
+    fn test_suggest_within_tolerance() {
+        let positions = vec![
+            position("2330", dec!(1000), 510, dec!(50)),
+            position("0056", dec!(30), 16333, dec!(50)),
+        ];
+        assert!(suggest(&positions, dec!(2), Decimal::ONE).is_empty());
+
+        // 2330 不足 1 股，0056 買進 4 股的最低手續費 1 元超過交易金額的 1%
+        let positions = vec![
+            position("2330", dec!(200), 1, dec!(50)),
+            position("0056", dec!(10), 10, dec!(50)),
+        ];
+        assert!(suggest(&positions, dec!(2), Decimal::ONE).is_empty());
+        assert!(suggest(&[], dec!(2), Decimal::ONE).is_empty());
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::{
+    database::table::revenue_estimate::{
+        self, RevenueEstimate, BASIS_BOTH, BASIS_PEERS, BASIS_SEASONALITY,
+    },
+    declare::StockSymbol,
+    logging,
+};
+
+/// 同產業已公布營收的公司少於此數時不以同業年增率估算，避免單一公司的特殊狀況影響估算
+const MIN_PEERS: usize = 3;
+
+/// 估算時使用的自家歷史營收，沒有數據時為 0
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct History {
+    /// 去年同月營收
+    pub last_year: Decimal,
+    /// 上個月營收
+    pub previous: Decimal,
+    /// 去年上個月營收
+    pub last_year_previous: Decimal,
+}
+
+/// 估算庫存中尚未公布 month(yyyyMM) 營收的股票，已公布的估算改為正式值，回傳寫入的估算筆數
+///
+/// 每日抓取月營收後執行，公布期間(每月 1~10 日)同產業公布的公司越多估算越準
+pub async fn calculate(month: i64) -> Result<usize> {
+    RevenueEstimate::finalize(month).await?;
+
+    let pending = revenue_estimate::fetch_pending_held(month).await?;
+    if pending.is_empty() {
+        return Ok(0);
+    }
+
+    let mut peer_growths: HashMap<i32, Vec<Decimal>> = HashMap::new();
+    for (industry_id, growth) in revenue_estimate::fetch_peer_growths(month).await? {
+        peer_growths.entry(industry_id).or_default().push(growth);
+    }
+
+    let previous = previous_month(month);
+    let security_codes: Vec<StockSymbol> = pending.iter().map(|(code, _)| code.clone()).collect();
+    let revenues: HashMap<(StockSymbol, i64), Decimal> =
+        revenue_estimate::fetch_monthly(&security_codes, &[month - 100, previous, previous - 100])
+            .await?
+            .into_iter()
+            .map(|(security_code, date, monthly)| ((security_code, date), monthly))
+            .collect();
+
+    let mut count = 0;
+    for (security_code, industry_id) in pending {
+        let monthly = |date: i64| {
+            revenues
+                .get(&(security_code.clone(), date))
+                .copied()
+                .unwrap_or_default()
+        };
+        let history = History {
+            last_year: monthly(month - 100),
+            previous: monthly(previous),
+            last_year_previous: monthly(previous - 100),
+        };
+        let peers = peer_growths
+            .get(&industry_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let Some(estimate) = estimate(&security_code, month, &history, peers) else {
+            continue;
+        };
+
+        if let Err(why) = estimate.upsert().await {
+            logging::error_file_async(format!("{:?}", why));
+            continue;
+        }
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// 以同產業已公布公司的年增率中位數乘上去年同月營收，及去年同期的月增率乘上上個月營收估算當月營收，
+/// 兩者都能計算時取平均，都不能計算時回傳 None
+pub fn estimate(
+    security_code: &StockSymbol,
+    month: i64,
+    history: &History,
+    peer_growths: &[Decimal],
+) -> Option<RevenueEstimate> {
+    let peer_growth = median(peer_growths);
+    let by_peers = (peer_growths.len() >= MIN_PEERS && history.last_year > Decimal::ZERO)
+        .then(|| history.last_year * (Decimal::ONE + peer_growth / dec!(100)));
+    let by_seasonality = (history.previous > Decimal::ZERO
+        && history.last_year > Decimal::ZERO
+        && history.last_year_previous > Decimal::ZERO)
+        .then(|| history.previous * history.last_year / history.last_year_previous);
+
+    let (estimated, basis) = match (by_peers, by_seasonality) {
+        (Some(peers), Some(seasonality)) => ((peers + seasonality) / dec!(2), BASIS_BOTH),
+        (Some(peers), None) => (peers, BASIS_PEERS),
+        (None, Some(seasonality)) => (seasonality, BASIS_SEASONALITY),
+        (None, None) => return None,
+    };
+
+    Some(RevenueEstimate {
+        security_code: security_code.clone(),
+        month,
+        estimated: estimated.round_dp(0),
+        basis: basis.to_string(),
+        peer_count: peer_growths.len() as i32,
+        peer_growth: peer_growth.round_dp(2),
+        is_provisional: true,
+        actual: Decimal::ZERO,
+    })
+}
+
+/// 中位數，沒有數據時為 0
+fn median(values: &[Decimal]) -> Decimal {
+    let mut sorted = values.to_vec();
+    sorted.sort();
+    let middle = sorted.len() / 2;
+
+    match sorted.len() {
+        0 => Decimal::ZERO,
+        len if len % 2 == 0 => (sorted[middle - 1] + sorted[middle]) / dec!(2),
+        _ => sorted[middle],
+    }
+}
+
+/// yyyyMM 的上個月 ex. 202401 -> 202312
+fn previous_month(month: i64) -> i64 {
+    if month % 100 == 1 {
+        (month / 100 - 1) * 100 + 12
+    } else {
+        month - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_previous_month() {
+        assert_eq!(previous_month(202401), 202312);
+        assert_eq!(previous_month(202405), 202404);
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(&[]), Decimal::ZERO);
+        assert_eq!(median(&[dec!(30), dec!(-10), dec!(10)]), dec!(10));
+        assert_eq!(median(&[dec!(30), dec!(-10), dec!(10), dec!(20)]), dec!(15));
+    }
+
+    #[test]
+    fn test_estimate() {
+        let history = History {
+            last_year: dec!(1000),
+            previous: dec!(1200),
+            last_year_previous: dec!(800),
+        };
+        let peers = [dec!(10), dec!(20), dec!(30)];
+        let symbol: StockSymbol = "2330".parse().unwrap();
+
+        let both = estimate(&symbol, 202405, &history, &peers).unwrap();
+        // 同業 1000 × 1.2 = 1200，季節性 1200 × 1000 ÷ 800 = 1500
+        assert_eq!(both.estimated, dec!(1350));
+        assert_eq!(both.basis, BASIS_BOTH);
+        assert_eq!(both.peer_count, 3);
+        assert_eq!(both.peer_growth, dec!(20));
+        assert!(both.is_provisional);
+
+        let seasonality_only = estimate(&symbol, 202405, &history, &peers[..2]).unwrap();
+        assert_eq!(seasonality_only.estimated, dec!(1500));
+        assert_eq!(seasonality_only.basis, BASIS_SEASONALITY);
+
+        let no_previous = History {
+            previous: Decimal::ZERO,
+            ..history.clone()
+        };
+        let by_peers = estimate(&symbol, 202405, &no_previous, &peers).unwrap();
+        assert_eq!(by_peers.estimated, dec!(1200));
+        assert_eq!(by_peers.basis, BASIS_PEERS);
+
+        assert_eq!(estimate(&symbol, 202405, &History::default(), &peers), None);
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+
+use crate::{
+    database::repository::{PgRepository, RevenueRepository},
+    declare::StockSymbol,
+};
+
+/// 比較的年數，以近 5 年的同月份作為季節常態
+pub const YEARS: i64 = 5;
+/// 季節指數偏離常態超過此倍數的標準差時視為異常
+pub const THRESHOLD: f64 = 2.0;
+/// 同月份的歷史季節指數少於此數時不計算，避免新上市的股票數據失真
+const MIN_SAMPLES: usize = 3;
+
+/// 單一月份營收與近 5 年同月季節常態的比較
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Seasonality {
+    /// 當月營收除以前 12 個月平均月營收
+    pub index: f64,
+    /// 近 5 年同月季節指數的平均
+    pub mean: f64,
+    /// 近 5 年同月季節指數的樣本標準差
+    pub std_dev: f64,
+    /// (當月季節指數 - 平均) / 標準差
+    pub z_score: f64,
+    /// 計算平均與標準差的年數
+    pub samples: usize,
+}
+
+impl Seasonality {
+    /// 偏離季節常態超過 2 個標準差
+    pub fn is_anomaly(&self) -> bool {
+        self.z_score.abs() > THRESHOLD
+    }
+
+    /// 通知使用的說明 ex. 高於近 5 年同月常態 2.35σ
+    pub fn describe(&self) -> String {
+        if !self.is_anomaly() {
+            return format!("符合近 {} 年同月常態 {:.2}σ", self.samples, self.z_score);
+        }
+
+        let direction = if self.z_score > 0.0 {
+            "高於"
+        } else {
+            "低於"
+        };
+        format!(
+            "{}近 {} 年同月常態 {:.2}σ",
+            direction,
+            self.samples,
+            self.z_score.abs()
+        )
+    }
+}
+
+/// 計算 security_codes 在 month(yyyyMM) 的營收季節性，歷史營收不足的股票不列入
+pub async fn fetch(
+    security_codes: &[StockSymbol],
+    month: i64,
+) -> Result<HashMap<StockSymbol, Seasonality>> {
+    from_repository(&PgRepository::new(module_path!()), security_codes, month).await
+}
+
+/// 以 repository 內的月營收計算 security_codes 在 month(yyyyMM) 的營收季節性
+pub async fn from_repository(
+    repository: &dyn RevenueRepository,
+    security_codes: &[StockSymbol],
+    month: i64,
+) -> Result<HashMap<StockSymbol, Seasonality>> {
+    if security_codes.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let mut revenues: HashMap<StockSymbol, HashMap<i64, Decimal>> = HashMap::new();
+    for (security_code, date, monthly) in repository
+        .fetch_monthly(security_codes, &months(month))
+        .await?
+    {
+        revenues
+            .entry(security_code)
+            .or_default()
+            .insert(date, monthly);
+    }
+
+    Ok(revenues
+        .into_iter()
+        .filter_map(|(security_code, monthly)| {
+            analyze(month, &monthly).map(|seasonality| (security_code, seasonality))
+        })
+        .collect())
+}
+
+/// 以近 5 年同月份的季節指數計算平均與標準差，再算出 month 的季節指數偏離了幾個標準差
+pub fn analyze(month: i64, revenues: &HashMap<i64, Decimal>) -> Option<Seasonality> {
+    let index = seasonal_index(month, revenues)?;
+    let history: Vec<f64> = (1..=YEARS)
+        .filter_map(|year| seasonal_index(month - year * 100, revenues))
+        .collect();
+    if history.len() < MIN_SAMPLES {
+        return None;
+    }
+
+    let mean = history.iter().sum::<f64>() / history.len() as f64;
+    let std_dev = (history.iter().map(|v| (v - mean).powi(2)).sum::<f64>()
+        / (history.len() as f64 - 1.0))
+        .sqrt();
+    if std_dev <= f64::EPSILON {
+        return None;
+    }
+
+    Some(Seasonality {
+        index,
+        mean,
+        std_dev,
+        z_score: (index - mean) / std_dev,
+        samples: history.len(),
+    })
+}
+
+/// 季節指數 = 當月營收 / 前 12 個月平均月營收，任一個月份沒有營收時回傳 None
+fn seasonal_index(month: i64, revenues: &HashMap<i64, Decimal>) -> Option<f64> {
+    let positive = |date: i64| {
+        revenues
+            .get(&date)
+            .copied()
+            .filter(|monthly| *monthly > Decimal::ZERO)
+    };
+
+    let current = positive(month)?;
+    let mut date = month;
+    let mut total = Decimal::ZERO;
+    for _ in 0..12 {
+        date = previous_month(date);
+        total += positive(date)?;
+    }
+
+    (current / (total / Decimal::from(12))).to_f64()
+}
+
+/// 計算 month 季節性需要的全部月份，從 5 年又 12 個月前到 month
+fn months(month: i64) -> Vec<i64> {
+    let mut date = month;
+    let mut months = vec![date];
+    for _ in 0..(YEARS * 12 + 12) {
+        date = previous_month(date);
+        months.push(date);
+    }
+
+    months
+}
+
+/// yyyyMM 的上個月 ex. 202401 -> 202312
+fn previous_month(month: i64) -> i64 {
+    if month % 100 == 1 {
+        (month / 100 - 1) * 100 + 12
+    } else {
+        month - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use crate::database::repository::MemoryRepository;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    /// 其他月份營收皆為 100，最近幾年的 1 月營收依序為 100 的 january 倍
+    fn history(january: &[Decimal]) -> HashMap<i64, Decimal> {
+        let mut revenues = HashMap::new();
+        let first_year = 2024 - january.len() as i64 + 1;
+        for year in (first_year - 1)..=2024 {
+            for m in 1..=12 {
+                revenues.insert(year * 100 + m, dec!(100));
+            }
+        }
+        for (i, factor) in january.iter().enumerate() {
+            revenues.insert((first_year + i as i64) * 100 + 1, dec!(100) * factor);
+        }
+
+        revenues
+    }
+
+    #[test]
+    fn test_months() {
+        let months = months(202401);
+        assert_eq!(months.len(), 73);
+        assert_eq!(months.first(), Some(&202401));
+        assert_eq!(months.last(), Some(&201801));
+    }
+
+    #[test]
+    fn test_seasonal_index() {
+        let revenues = history(&[dec!(1.5)]);
+        assert_eq!(seasonal_index(202401, &revenues), Some(1.5));
+        let index = seasonal_index(202402, &revenues).unwrap();
+        assert!((index - 100.0 / (1250.0 / 12.0)).abs() < 1e-9);
+        assert_eq!(seasonal_index(202301, &revenues), None);
+    }
+
+    #[test]
+    fn test_analyze() {
+        let revenues = history(&[
+            dec!(1.2),
+            dec!(1.3),
+            dec!(1.1),
+            dec!(1.2),
+            dec!(1.25),
+            dec!(2),
+        ]);
+        let seasonality = analyze(202401, &revenues).unwrap();
+        assert_eq!(seasonality.samples, 5);
+        assert!(seasonality.is_anomaly());
+        assert!(seasonality.z_score > THRESHOLD);
+        assert!(seasonality.describe().starts_with("高於近 5 年同月常態"));
+
+        let revenues = history(&[
+            dec!(1.2),
+            dec!(1.3),
+            dec!(1.1),
+            dec!(1.2),
+            dec!(1.25),
+            dec!(1.2),
+        ]);
+        let seasonality = analyze(202401, &revenues).unwrap();
+        assert!(!seasonality.is_anomaly());
+
+        let revenues = history(&[dec!(1.2), dec!(1.3), dec!(2)]);
+        assert_eq!(analyze(202401, &revenues), None);
+    }
+
+    #[tokio::test]
+    async fn test_from_repository() {
+        let repository = MemoryRepository::default();
+        repository.monthly_revenues.lock().unwrap().extend(
+            history(&[
+                dec!(1.2),
+                dec!(1.3),
+                dec!(1.1),
+                dec!(1.2),
+                dec!(1.25),
+                dec!(2),
+            ])
+            .into_iter()
+            .map(|(month, monthly)| (("2330".parse().unwrap(), month), monthly)),
+        );
+
+        let codes = ["2330".parse().unwrap(), "2317".parse().unwrap()];
+        let seasonalities = from_repository(&repository, &codes, 202401).await.unwrap();
+        assert_eq!(seasonalities.len(), 1);
+        assert!(seasonalities["2330"].is_anomaly());
+
+        assert!(from_repository(&repository, &[], 202401)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
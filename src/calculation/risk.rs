@@ -0,0 +1,277 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use chrono::{Months, NaiveDate};
+use rust_decimal::{
+    prelude::{FromPrimitive, ToPrimitive},
+    Decimal,
+};
+
+use crate::{
+    config::SETTINGS,
+    database::{
+        repository::{
+            AdjustedQuoteRepository, DailyMoneyHistoryRepository, IndexRepository, PgRepository,
+            RiskMetricRepository, StockOwnershipDetailRepository,
+        },
+        table::risk_metric::RiskMetric,
+    },
+    logging,
+};
+
+/// risk_metrics 表內代表整體庫存的代號
+pub const PORTFOLIO: &str = "portfolio";
+/// 比較 beta 的大盤指數
+const BENCHMARK: &str = "TAIEX";
+/// 一年的交易日數，用來年化日報酬與波動度
+const TRADING_DAYS_PER_YEAR: f64 = 252.0;
+/// 日報酬筆數少於此數時不計算，避免新上市或剛買進的股票數據失真
+const MIN_SAMPLES: usize = 60;
+
+/// 近一年的風險指標
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Metrics {
+    pub beta: f64,
+    /// 年化波動度(%)
+    pub volatility: f64,
+    pub sharpe_ratio: f64,
+    pub sample_days: usize,
+}
+
+/// 計算庫存內每檔股票與整體庫存截至指定日期近一年的 beta、年化波動度與夏普比率並寫入 risk_metrics 表
+///
+/// 個股使用還原收盤價，整體庫存使用 daily_money_history 的每日市值，期間內的買賣會讓當日的報酬失真
+pub async fn calculate(date: NaiveDate) -> Result<usize> {
+    from_repository(
+        &PgRepository::new(module_path!()),
+        date,
+        SETTINGS.load().report.risk_free_rate,
+    )
+    .await
+}
+
+/// 以 repository 內的指數、還原收盤價與每日市值計算風險指標後寫回 repository，回傳寫入的筆數
+pub async fn from_repository<R>(
+    repository: &R,
+    date: NaiveDate,
+    risk_free_rate: f64,
+) -> Result<usize>
+where
+    R: IndexRepository
+        + AdjustedQuoteRepository
+        + DailyMoneyHistoryRepository
+        + StockOwnershipDetailRepository
+        + RiskMetricRepository,
+{
+    let since = date.checked_sub_months(Months::new(12)).unwrap_or(date);
+    let market: BTreeMap<NaiveDate, f64> = repository
+        .fetch_closing(BENCHMARK, since, date)
+        .await?
+        .into_iter()
+        .filter_map(|(day, index)| index.to_f64().map(|index| (day, index)))
+        .collect();
+
+    let symbols = repository.fetch_held_symbols().await?;
+    let mut series: Vec<(String, BTreeMap<NaiveDate, f64>)> = Vec::with_capacity(symbols.len() + 1);
+    for symbol in symbols {
+        let prices = AdjustedQuoteRepository::fetch(repository, &symbol, since)
+            .await?
+            .into_iter()
+            .filter(|quote| quote.date <= date)
+            .filter_map(|quote| {
+                quote
+                    .adjusted_closing_price
+                    .to_f64()
+                    .map(|price| (quote.date, price))
+            })
+            .collect();
+        series.push((symbol.to_string(), prices));
+    }
+
+    let portfolio = repository
+        .fetch_between(since, date)
+        .await?
+        .into_iter()
+        .filter_map(|mh| mh.sum.to_f64().map(|sum| (mh.date, sum)))
+        .collect();
+    series.push((PORTFOLIO.to_string(), portfolio));
+
+    let mut count = 0;
+    for (security_code, prices) in series {
+        let Some(metrics) = measure(&prices, &market, risk_free_rate) else {
+            continue;
+        };
+
+        let metric = RiskMetric {
+            date,
+            security_code,
+            beta: to_decimal(metrics.beta),
+            volatility: to_decimal(metrics.volatility),
+            sharpe_ratio: to_decimal(metrics.sharpe_ratio),
+            sample_days: metrics.sample_days as i32,
+            ..Default::default()
+        };
+        if let Err(why) = RiskMetricRepository::upsert(repository, &metric).await {
+            logging::error_file_async(format!("{:?}", why));
+            continue;
+        }
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+fn to_decimal(value: f64) -> Decimal {
+    Decimal::from_f64(value).unwrap_or_default().round_dp(4)
+}
+
+/// 以兩個交易日都有數據的日報酬計算指標，risk_free_rate 為年化的百分比
+pub fn measure(
+    prices: &BTreeMap<NaiveDate, f64>,
+    market: &BTreeMap<NaiveDate, f64>,
+    risk_free_rate: f64,
+) -> Option<Metrics> {
+    let asset = returns(prices);
+    let market = returns(market);
+    let (asset, market): (Vec<f64>, Vec<f64>) = asset
+        .iter()
+        .filter_map(|(day, r)| market.get(day).map(|m| (*r, *m)))
+        .unzip();
+    if asset.len() < MIN_SAMPLES {
+        return None;
+    }
+
+    let market_variance = variance(&market);
+    let beta = if market_variance > 0.0 {
+        covariance(&asset, &market) / market_variance
+    } else {
+        0.0
+    };
+    let volatility = variance(&asset).sqrt() * TRADING_DAYS_PER_YEAR.sqrt();
+    let annual_return = mean(&asset) * TRADING_DAYS_PER_YEAR;
+    let sharpe_ratio = if volatility > 0.0 {
+        (annual_return - risk_free_rate / 100.0) / volatility
+    } else {
+        0.0
+    };
+
+    Some(Metrics {
+        beta,
+        volatility: volatility * 100.0,
+        sharpe_ratio,
+        sample_days: asset.len(),
+    })
+}
+
+/// 每個交易日相對前一個交易日的報酬，第一天沒有報酬
+fn returns(prices: &BTreeMap<NaiveDate, f64>) -> BTreeMap<NaiveDate, f64> {
+    prices
+        .iter()
+        .zip(prices.iter().skip(1))
+        .filter(|((_, previous), _)| **previous > 0.0)
+        .map(|((_, previous), (day, current))| (*day, current / previous - 1.0))
+        .collect()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// 樣本變異數
+fn variance(values: &[f64]) -> f64 {
+    covariance(values, values)
+}
+
+/// 樣本共變異數
+fn covariance(a: &[f64], b: &[f64]) -> f64 {
+    let (mean_a, mean_b) = (mean(a), mean(b));
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - mean_a) * (y - mean_b))
+        .sum::<f64>()
+        / (a.len() as f64 - 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use crate::database::{
+        repository::MemoryRepository,
+        table::{adjusted_quote::AdjustedQuote, index::Index},
+    };
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    /// 以每日報酬產生價格序列
+    fn prices(daily_returns: impl Iterator<Item = f64>) -> BTreeMap<NaiveDate, f64> {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let mut price = 100.0;
+        let mut series = BTreeMap::from([(start, price)]);
+        for (i, r) in daily_returns.enumerate() {
+            price *= 1.0 + r;
+            series.insert(start + chrono::Days::new(i as u64 + 1), price);
+        }
+        series
+    }
+
+    #[test]
+    fn test_measure() {
+        let market_returns = || (0..100).map(|i| if i % 2 == 0 { 0.01 } else { -0.005 });
+        let market = prices(market_returns());
+        let doubled = prices(market_returns().map(|r| r * 2.0));
+
+        let metrics = measure(&doubled, &market, 0.0).unwrap();
+        assert!((metrics.beta - 2.0).abs() < 1e-9);
+        assert_eq!(metrics.sample_days, 100);
+
+        let market_metrics = measure(&market, &market, 0.0).unwrap();
+        assert!((market_metrics.beta - 1.0).abs() < 1e-9);
+        assert!((metrics.volatility / market_metrics.volatility - 2.0).abs() < 1e-9);
+        assert!(metrics.sharpe_ratio > 0.0);
+
+        let short = prices(market_returns().take(10));
+        assert_eq!(measure(&short, &market, 0.0), None);
+    }
+
+    #[tokio::test]
+    async fn test_from_repository() {
+        let market_returns = || (0..100).map(|i| if i % 2 == 0 { 0.01 } else { -0.005 });
+        let repository = MemoryRepository::default();
+        for (date, price) in prices(market_returns()) {
+            let mut index = Index::new();
+            index.category = BENCHMARK.to_string();
+            index.date = date;
+            index.index = Decimal::from_f64(price).unwrap();
+            IndexRepository::upsert(&repository, &index).await.unwrap();
+        }
+
+        let doubled = prices(market_returns().map(|r| r * 2.0));
+        let last = *doubled.keys().last().unwrap();
+        repository
+            .adjusted_quotes
+            .lock()
+            .unwrap()
+            .extend(doubled.iter().map(|(date, price)| AdjustedQuote {
+                security_code: "2330".parse().unwrap(),
+                date: *date,
+                closing_price: Decimal::from_f64(*price).unwrap(),
+                adjustment_factor: Decimal::ONE,
+                adjusted_closing_price: Decimal::from_f64(*price).unwrap(),
+            }));
+        repository
+            .held_symbols
+            .lock()
+            .unwrap()
+            .extend(["2330".parse().unwrap(), "2317".parse().unwrap()]);
+
+        // 2317 沒有還原收盤價、整體庫存沒有每日市值，都不會寫入
+        assert_eq!(from_repository(&repository, last, 0.0).await.unwrap(), 1);
+
+        let metrics = repository.risk_metrics.lock().unwrap();
+        let metric = &metrics[&(last, "2330".to_string())];
+        assert_eq!(metric.beta, dec!(2));
+        assert_eq!(metric.sample_days, 100);
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use rust_decimal::{
+    prelude::{FromPrimitive, ToPrimitive},
+    Decimal,
+};
+
+use crate::{
+    calculation::allocation,
+    database::{
+        repository::{CashLedgerRepository, PgRepository},
+        table::cash_ledger::CashLedger,
+    },
+};
+
+/// 二分法的最大次數
+const MAX_ITERATIONS: usize = 200;
+/// 年化報酬率的收斂精度
+const TOLERANCE: f64 = 1e-9;
+
+/// 成員帳戶的年化內部報酬率
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemberXirr {
+    pub member_id: i64,
+    /// 投入減提出的淨額
+    pub net_contribution: Decimal,
+    /// 最後交易日的持股市值
+    pub market_value: Decimal,
+    /// 年化報酬率(%)，資金進出不足以計算時為 None
+    pub rate: Option<Decimal>,
+}
+
+/// 以 cash_ledger 內指定日期(含)以前的資金進出與目前持股市值計算每位成員的 XIRR
+///
+/// 投入視為成員付出的現金流、提出視為收回，持股市值視為在 date 當天全部收回，未投入股票的現金不列入
+pub async fn calculate(date: NaiveDate) -> Result<Vec<MemberXirr>> {
+    let market_values: BTreeMap<i64, Decimal> = allocation::calculate()
+        .await?
+        .into_iter()
+        .map(|a| (a.member_id, a.total))
+        .collect();
+
+    from_ledgers(&PgRepository::new(module_path!()), date, &market_values).await
+}
+
+/// 以 repository 內指定日期(含)以前的資金進出與每位成員的持股市值計算 XIRR，沒有資金進出的成員不列入
+pub async fn from_ledgers(
+    repository: &dyn CashLedgerRepository,
+    date: NaiveDate,
+    market_values: &BTreeMap<i64, Decimal>,
+) -> Result<Vec<MemberXirr>> {
+    let mut ledgers: BTreeMap<i64, Vec<CashLedger>> = BTreeMap::new();
+    for ledger in repository.fetch_until(date).await? {
+        ledgers.entry(ledger.member_id).or_default().push(ledger);
+    }
+
+    Ok(ledgers
+        .into_iter()
+        .map(|(member_id, entries)| {
+            let market_value = market_values.get(&member_id).copied().unwrap_or_default();
+            evaluate(member_id, &entries, market_value, date)
+        })
+        .collect())
+}
+
+/// 計算單一成員的淨投入與 XIRR
+pub fn evaluate(
+    member_id: i64,
+    entries: &[CashLedger],
+    market_value: Decimal,
+    date: NaiveDate,
+) -> MemberXirr {
+    let mut flows: Vec<(NaiveDate, f64)> = entries
+        .iter()
+        .filter_map(|e| e.amount.to_f64().map(|amount| (e.date, -amount)))
+        .collect();
+    flows.push((date, market_value.to_f64().unwrap_or_default()));
+
+    MemberXirr {
+        member_id,
+        net_contribution: entries.iter().map(|e| e.amount).sum(),
+        market_value,
+        rate: xirr(&flows)
+            .and_then(|rate| Decimal::from_f64(rate * 100.0))
+            .map(|rate| rate.round_dp(2)),
+    }
+}
+
+/// 以二分法求出讓現金流淨現值為零的年化報酬率，付出為負數、收回為正數
+///
+/// 現金流需同時有正有負，否則沒有解
+pub fn xirr(flows: &[(NaiveDate, f64)]) -> Option<f64> {
+    let first = flows.iter().map(|(day, _)| *day).min()?;
+    if !flows.iter().any(|(_, v)| *v > 0.0) || !flows.iter().any(|(_, v)| *v < 0.0) {
+        return None;
+    }
+
+    let npv = |rate: f64| -> f64 {
+        flows
+            .iter()
+            .map(|(day, value)| {
+                let years = (*day - first).num_days() as f64 / 365.0;
+                value / (1.0 + rate).powf(years)
+            })
+            .sum()
+    };
+
+    let (mut low, mut high) = (-0.9999, 1.0);
+    while npv(low).signum() == npv(high).signum() {
+        high *= 2.0;
+        if high > 1e6 {
+            return None;
+        }
+    }
+
+    for _ in 0..MAX_ITERATIONS {
+        let mid = (low + high) / 2.0;
+        let value = npv(mid);
+        if value.abs() < TOLERANCE || (high - low) / 2.0 < TOLERANCE {
+            return Some(mid);
+        }
+        if value.signum() == npv(low).signum() {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    Some((low + high) / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+    use crate::database::repository::MemoryRepository;
+    use rust_decimal_macros::dec;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_xirr() {
+        // 2023 年不是閏年，投入一年後增值 10%
+        let rate = xirr(&[(date(2023, 1, 1), -1000.0), (date(2024, 1, 1), 1100.0)]).unwrap();
+        assert!((rate - 0.1).abs() < 1e-6);
+
+        let rate = xirr(&[
+            (date(2023, 1, 1), -1000.0),
+            (date(2023, 7, 1), -1000.0),
+            (date(2024, 1, 1), 2000.0),
+        ])
+        .unwrap();
+        assert!(rate.abs() < 1e-6);
+
+        assert_eq!(xirr(&[(date(2023, 1, 1), -1000.0)]), None);
+        assert_eq!(xirr(&[]), None);
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let entries = vec![
+            CashLedger {
+                member_id: 1,
+                date: date(2023, 1, 1),
+                amount: dec!(1000),
+            },
+            CashLedger {
+                member_id: 1,
+                date: date(2023, 7, 1),
+                amount: dec!(-100),
+            },
+        ];
+
+        let result = evaluate(1, &entries, dec!(1000), date(2024, 1, 1));
+
+        assert_eq!(result.net_contribution, dec!(900));
+        assert_eq!(result.market_value, dec!(1000));
+        assert!(result.rate.unwrap() > dec!(10));
+
+        let empty = evaluate(2, &entries, Decimal::ZERO, date(2024, 1, 1));
+        assert!(empty.rate.unwrap() < Decimal::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_from_ledgers() {
+        let ledger = |member_id, date, amount| CashLedger {
+            member_id,
+            date,
+            amount,
+        };
+        let repository = MemoryRepository::with_cash_ledgers(vec![
+            ledger(1, date(2023, 1, 1), dec!(1000)),
+            ledger(2, date(2023, 1, 1), dec!(500)),
+            ledger(1, date(2024, 6, 1), dec!(5000)),
+        ]);
+        let market_values = BTreeMap::from([(1, dec!(1100)), (3, dec!(800))]);
+
+        let results = from_ledgers(&repository, date(2024, 1, 1), &market_values)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            results.iter().map(|r| r.member_id).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(results[0].net_contribution, dec!(1000));
+        assert_eq!(results[0].rate, Some(dec!(10)));
+        assert_eq!(results[1].market_value, Decimal::ZERO);
+    }
+}
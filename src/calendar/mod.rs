@@ -0,0 +1,293 @@
+use std::fmt::Write;
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Local, NaiveDate, Utc};
+
+use crate::{
+    cache::SHARE,
+    database::table::{
+        corporate_event::CorporateEvent,
+        dividend::extension::dividend_schedule::{self, DividendSchedule},
+        stock_ownership_details,
+    },
+    logging,
+    quality::filing,
+    storage,
+};
+
+/// 行事曆檔案在儲存後端的 key，儲存後端可公開讀取時即可讓 Google 日曆以網址訂閱
+pub const KEY: &str = "calendar/stock.ics";
+/// 行事曆的名稱
+const CALENDAR_NAME: &str = "台股股利與財報";
+/// iCalendar 每行最多的位元組數，超過時需折行
+const MAX_LINE_OCTETS: usize = 75;
+
+/// 行事曆內的一個全天事件
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    /// 事件的唯一識別，重新匯出時相同的事件需維持相同的 uid 日曆才會更新而不是重複新增
+    pub uid: String,
+    pub date: NaiveDate,
+    pub summary: String,
+    pub description: String,
+}
+
+/// 匯出庫存與追踪中股票的除權息日、股利發放日、股東會、法說會及財報公布期限到儲存後端
+pub async fn execute() -> Result<()> {
+    let today = Local::now().date_naive();
+    let symbols: Vec<String> = stock_ownership_details::fetch_held_or_traced_symbols()
+        .await?
+        .into_iter()
+        .map(String::from)
+        .collect();
+    let schedules = dividend_schedule::fetch(&symbols, today.year() - 1).await?;
+
+    let mut events: Vec<Event> = schedules.iter().flat_map(dividend_events).collect();
+    let since = NaiveDate::from_ymd_opt(today.year() - 1, 1, 1).unwrap_or(today);
+    let until = NaiveDate::from_ymd_opt(today.year() + 1, 12, 31).unwrap_or(today);
+    for corporate in CorporateEvent::fetch_between(&symbols, since, until).await? {
+        let name = SHARE
+            .get_stock(&corporate.security_code)
+            .await
+            .map(|stock| stock.name)
+            .unwrap_or_default();
+        events.push(corporate_event(&corporate, &name));
+    }
+    events.extend(earnings_events(today.year()));
+    events.extend(earnings_events(today.year() + 1));
+    events.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.uid.cmp(&b.uid)));
+
+    let ics = render(&events, Utc::now());
+    storage::get_storage().put(KEY, ics.into_bytes()).await?;
+    logging::info_file_async(format!("行事曆已匯出 {} 筆事件至 {}", events.len(), KEY));
+
+    Ok(())
+}
+
+/// 由股利日程產生除息、除權、現金股利與股票股利發放的事件，尚未公布的日期略過
+fn dividend_events(schedule: &DividendSchedule) -> Vec<Event> {
+    let title = format!("{} {}", schedule.stock_symbol, schedule.name);
+    let id = format!(
+        "{}-{}-{}",
+        schedule.stock_symbol, schedule.year_of_dividend, schedule.quarter
+    );
+    let cash = format!("現金股利 {} 元", schedule.cash_dividend.normalize());
+    let stock = format!("股票股利 {} 元", schedule.stock_dividend.normalize());
+
+    [
+        ("ex-dividend", &schedule.ex_dividend_date1, "除息", &cash),
+        ("ex-right", &schedule.ex_dividend_date2, "除權", &stock),
+        (
+            "cash-payable",
+            &schedule.payable_date1,
+            "現金股利發放",
+            &cash,
+        ),
+        (
+            "stock-payable",
+            &schedule.payable_date2,
+            "股票股利發放",
+            &stock,
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(kind, date, action, detail)| {
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+        Some(Event {
+            uid: format!("{}-{}@stock_crawler", id, kind),
+            date,
+            summary: format!("{} {}", title, action),
+            description: detail.clone(),
+        })
+    })
+    .collect()
+}
+
+/// 由股東會、法說會產生事件
+fn corporate_event(event: &CorporateEvent, name: &str) -> Event {
+    Event {
+        uid: format!(
+            "{}-{}-{}@stock_crawler",
+            event.security_code,
+            event.kind,
+            event.event_date.format("%Y%m%d")
+        ),
+        date: event.event_date,
+        summary: format!("{} {} {}", event.security_code, name, event.kind_name()),
+        description: event.description.clone(),
+    }
+}
+
+/// 上市櫃公司財報的法定公布期限：年報 3/31、第一季 5/15、第二季 8/14、第三季 11/14
+fn earnings_events(year: i32) -> Vec<Event> {
+    filing::deadlines(year)
+        .into_iter()
+        .map(|deadline| Event {
+            uid: format!("earnings-{}@stock_crawler", deadline.date.format("%Y%m%d")),
+            date: deadline.date,
+            summary: format!("{}公布期限", deadline.report_name()),
+            description: "上市櫃公司財務報告的法定公布期限".to_string(),
+        })
+        .collect()
+}
+
+/// 產生 iCalendar(RFC 5545) 格式的內容，stamp 為產生的時間
+pub fn render(events: &[Event], stamp: DateTime<Utc>) -> String {
+    let stamp = stamp.format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//jiansoft//stock_crawler//ZH-TW".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        format!("X-WR-CALNAME:{}", escape(CALENDAR_NAME)),
+        "X-WR-TIMEZONE:Asia/Taipei".to_string(),
+    ];
+
+    for event in events {
+        let next_day = event.date.succ_opt().unwrap_or(event.date);
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}", escape(&event.uid)));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!(
+            "DTSTART;VALUE=DATE:{}",
+            event.date.format("%Y%m%d")
+        ));
+        lines.push(format!("DTEND;VALUE=DATE:{}", next_day.format("%Y%m%d")));
+        lines.push(format!("SUMMARY:{}", escape(&event.summary)));
+        lines.push(format!("DESCRIPTION:{}", escape(&event.description)));
+        lines.push("TRANSP:TRANSPARENT".to_string());
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut ics = String::with_capacity(lines.len() * 64);
+    for line in lines {
+        let _ = write!(&mut ics, "{}\r\n", fold(&line));
+    }
+
+    ics
+}
+
+/// 跳脫文字內容中的反斜線、分號、逗號與換行
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// 超過 75 個位元組的行需折行，續行以一個空白開頭，且不能從 UTF-8 字元的中間切開
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / MAX_LINE_OCTETS * 3);
+    let mut octets = 0;
+
+    for c in line.chars() {
+        let len = c.len_utf8();
+        if octets + len > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            // 續行開頭的空白也算一個位元組
+            octets = 1;
+        }
+        folded.push(c);
+        octets += len;
+    }
+
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_dividend_events() {
+        let schedule = DividendSchedule {
+            stock_symbol: "2330".parse().unwrap(),
+            name: "台積電".to_string(),
+            year_of_dividend: 2024,
+            quarter: "Q1".to_string(),
+            cash_dividend: dec!(4.50),
+            stock_dividend: dec!(0),
+            ex_dividend_date1: "2024-09-12".to_string(),
+            ex_dividend_date2: "-".to_string(),
+            payable_date1: "2024-10-09".to_string(),
+            payable_date2: "-".to_string(),
+        };
+
+        let events = dividend_events(&schedule);
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].uid, "2330-2024-Q1-ex-dividend@stock_crawler");
+        assert_eq!(
+            events[0].date,
+            NaiveDate::from_ymd_opt(2024, 9, 12).unwrap()
+        );
+        assert_eq!(events[0].summary, "2330 台積電 除息");
+        assert_eq!(events[0].description, "現金股利 4.5 元");
+        assert_eq!(events[1].summary, "2330 台積電 現金股利發放");
+    }
+
+    #[test]
+    fn test_corporate_event() {
+        let event = CorporateEvent::new(
+            "2330".parse().unwrap(),
+            crate::database::table::corporate_event::SHAREHOLDER_MEETING,
+            NaiveDate::from_ymd_opt(2024, 6, 4).unwrap(),
+            "股東常會".to_string(),
+        );
+
+        let event = corporate_event(&event, "台積電");
+
+        assert_eq!(event.uid, "2330-shareholder_meeting-20240604@stock_crawler");
+        assert_eq!(event.summary, "2330 台積電 股東會");
+        assert_eq!(event.description, "股東常會");
+    }
+
+    #[test]
+    fn test_earnings_events() {
+        let events = earnings_events(2024);
+
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0].summary, "2023年度財報公布期限");
+        assert_eq!(
+            events[3].date,
+            NaiveDate::from_ymd_opt(2024, 11, 14).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let events = vec![Event {
+            uid: "2330-2024-Q1-ex-dividend@stock_crawler".to_string(),
+            date: NaiveDate::from_ymd_opt(2024, 9, 12).unwrap(),
+            summary: "2330 台積電 除息".to_string(),
+            description: "現金股利 4.5 元, 除息前一日買進".to_string(),
+        }];
+        let stamp = DateTime::parse_from_rfc3339("2024-09-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let ics = render(&events, stamp);
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+        assert!(ics.contains("\r\nDTSTAMP:20240901T000000Z\r\n"));
+        assert!(ics.contains("\r\nDTSTART;VALUE=DATE:20240912\r\nDTEND;VALUE=DATE:20240913\r\n"));
+        assert!(ics.contains("\r\nDESCRIPTION:現金股利 4.5 元\\, 除息前一日買進\r\n"));
+    }
+
+    #[test]
+    fn test_fold() {
+        let line = format!("SUMMARY:{}", "台".repeat(30));
+        let folded = fold(&line);
+
+        assert!(folded.split("\r\n").all(|l| l.len() <= MAX_LINE_OCTETS));
+        assert_eq!(folded.replace("\r\n ", ""), line);
+        assert_eq!(fold("VERSION:2.0"), "VERSION:2.0");
+    }
+}
@@ -0,0 +1,295 @@
+use std::{io::Cursor, sync::OnceLock};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::NaiveDate;
+use image::{ImageOutputFormat, RgbImage};
+use plotters::{
+    prelude::*,
+    style::{register_font, FontStyle},
+};
+
+use crate::{config::SETTINGS, logging};
+
+/// 圖片的寬度(像素)
+const WIDTH: u32 = 1024;
+/// 圖片的高度(像素)
+const HEIGHT: u32 = 576;
+/// 上漲的顏色(台股紅漲)
+const RISE: RGBColor = RGBColor(220, 38, 38);
+/// 下跌的顏色(台股綠跌)
+const FALL: RGBColor = RGBColor(22, 163, 74);
+/// 均線依序使用的顏色
+const LINE_COLORS: [RGBColor; 4] = [
+    RGBColor(37, 99, 235),
+    RGBColor(234, 179, 8),
+    RGBColor(147, 51, 234),
+    RGBColor(20, 184, 166),
+];
+
+/// 一根 K 棒
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candle {
+    pub date: NaiveDate,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+/// 與 K 棒逐筆對齊的均線，值為 0 代表該日尚未計算均線
+#[derive(Debug, Clone, PartialEq)]
+pub struct MovingAverage {
+    pub label: String,
+    pub values: Vec<f64>,
+}
+
+/// 繪製 K 線與均線圖，回傳 PNG 圖檔的內容
+pub fn price_chart(title: &str, candles: &[Candle], averages: &[MovingAverage]) -> Result<Vec<u8>> {
+    if candles.is_empty() {
+        return Err(anyhow!("No candles to draw for {}", title));
+    }
+
+    let text = has_font();
+    let (low, high) = candles
+        .iter()
+        .flat_map(|c| [c.low, c.high])
+        .chain(averages.iter().flat_map(|ma| ma.values.iter().copied()))
+        .filter(|v| *v > 0.0)
+        .fold((f64::MAX, f64::MIN), |(low, high), v| {
+            (low.min(v), high.max(v))
+        });
+    let padding = ((high - low) * 0.05).max(0.01);
+    let dates: Vec<String> = candles
+        .iter()
+        .map(|c| c.date.format("%Y-%m-%d").to_string())
+        .collect();
+
+    render(|root| {
+        let mut builder = ChartBuilder::on(root);
+        builder.margin(16);
+        if text {
+            builder
+                .caption(title, ("sans-serif", 24))
+                .x_label_area_size(32)
+                .y_label_area_size(64);
+        }
+
+        let mut chart = builder.build_cartesian_2d(
+            -1f64..candles.len() as f64,
+            (low - padding)..(high + padding),
+        )?;
+
+        let x_formatter = |x: &f64| dates.get(x.round() as usize).cloned().unwrap_or_default();
+        let y_formatter = |y: &f64| format!("{:.2}", y);
+        let mut mesh = chart.configure_mesh();
+        mesh.disable_x_mesh();
+        if text {
+            mesh.x_labels(6)
+                .x_label_formatter(&x_formatter)
+                .y_label_formatter(&y_formatter);
+        } else {
+            mesh.x_labels(0).y_labels(0);
+        }
+        mesh.draw()?;
+
+        // K 棒的寬度依圖片寬度與根數調整
+        let width = ((WIDTH as f64 / candles.len() as f64) * 0.6).clamp(1.0, 16.0) as u32;
+        chart.draw_series(candles.iter().enumerate().map(|(i, c)| {
+            CandleStick::new(
+                i as f64,
+                c.open,
+                c.high,
+                c.low,
+                c.close,
+                RISE.filled(),
+                FALL.filled(),
+                width,
+            )
+        }))?;
+
+        for (ma, color) in averages.iter().zip(LINE_COLORS.iter().cycle()) {
+            let series = chart.draw_series(LineSeries::new(
+                ma.values
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, v)| **v > 0.0)
+                    .map(|(i, v)| (i as f64, *v)),
+                color.stroke_width(2),
+            ))?;
+
+            if text {
+                series
+                    .label(ma.label.as_str())
+                    .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], *color));
+            }
+        }
+
+        if text && !averages.is_empty() {
+            chart
+                .configure_series_labels()
+                .position(SeriesLabelPosition::UpperLeft)
+                .background_style(WHITE.mix(0.8))
+                .border_style(BLACK)
+                .draw()?;
+        }
+
+        Ok(())
+    })
+}
+
+/// 繪製月營收長條圖，bars 為(月份標籤, 營收)，回傳 PNG 圖檔的內容
+pub fn revenue_chart(title: &str, bars: &[(String, f64)]) -> Result<Vec<u8>> {
+    if bars.is_empty() {
+        return Err(anyhow!("No revenue to draw for {}", title));
+    }
+
+    let text = has_font();
+    let max = bars.iter().map(|(_, v)| *v).fold(0.0, f64::max);
+    let max = if max > 0.0 { max * 1.1 } else { 1.0 };
+
+    render(|root| {
+        let mut builder = ChartBuilder::on(root);
+        builder.margin(16);
+        if text {
+            builder
+                .caption(title, ("sans-serif", 24))
+                .x_label_area_size(32)
+                .y_label_area_size(80);
+        }
+
+        let mut chart = builder.build_cartesian_2d(0f64..bars.len() as f64, 0f64..max)?;
+
+        let x_formatter = |x: &f64| {
+            bars.get(x.floor() as usize)
+                .map(|(label, _)| label.clone())
+                .unwrap_or_default()
+        };
+        let y_formatter = |y: &f64| format!("{:.0}", y);
+        let mut mesh = chart.configure_mesh();
+        mesh.disable_x_mesh();
+        if text {
+            mesh.x_labels(bars.len().min(12))
+                .x_label_formatter(&x_formatter)
+                .y_label_formatter(&y_formatter);
+        } else {
+            mesh.x_labels(0).y_labels(0);
+        }
+        mesh.draw()?;
+
+        chart.draw_series(bars.iter().enumerate().map(|(i, (_, value))| {
+            Rectangle::new(
+                [(i as f64 + 0.15, 0.0), (i as f64 + 0.85, value.max(0.0))],
+                LINE_COLORS[0].filled(),
+            )
+        }))?;
+
+        Ok(())
+    })
+}
+
+/// 在記憶體中的畫布上繪圖並編碼為 PNG
+fn render<F>(draw: F) -> Result<Vec<u8>>
+where
+    F: FnOnce(&DrawingArea<BitMapBackend, plotters::coord::Shift>) -> Result<()>,
+{
+    let mut buffer = vec![0u8; (WIDTH * HEIGHT * 3) as usize];
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, (WIDTH, HEIGHT)).into_drawing_area();
+        root.fill(&WHITE)?;
+        draw(&root)?;
+        root.present()?;
+    }
+
+    let image = RgbImage::from_raw(WIDTH, HEIGHT, buffer)
+        .ok_or_else(|| anyhow!("Failed to create image from chart buffer"))?;
+    let mut png = Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, ImageOutputFormat::Png)
+        .context("Failed to encode chart to png")?;
+
+    Ok(png.into_inner())
+}
+
+/// 是否已載入設定檔 chart.font_path 的字型，沒有字型時圖表不繪製文字
+fn has_font() -> bool {
+    static LOADED: OnceLock<bool> = OnceLock::new();
+
+    *LOADED.get_or_init(|| {
+        let path = &SETTINGS.load().chart.font_path;
+        if path.is_empty() {
+            return false;
+        }
+
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(why) => {
+                logging::error_file_async(format!(
+                    "Failed to read chart font({}) because {:?}",
+                    path, why
+                ));
+                return false;
+            }
+        };
+
+        // 字型需存活到程式結束，plotters 才能持續引用
+        match register_font(
+            "sans-serif",
+            FontStyle::Normal,
+            Box::leak(bytes.into_boxed_slice()),
+        ) {
+            Ok(_) => true,
+            Err(_) => {
+                logging::error_file_async(format!("Failed to register chart font({})", path));
+                false
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+    #[test]
+    fn test_price_chart() {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let candles: Vec<Candle> = (0..30)
+            .map(|i| {
+                let base = 580.0 + (i % 7) as f64 * 3.0;
+                Candle {
+                    date: start + chrono::TimeDelta::try_days(i).unwrap(),
+                    open: base,
+                    high: base + 6.0,
+                    low: base - 4.0,
+                    close: if i % 2 == 0 { base + 2.0 } else { base - 2.0 },
+                }
+            })
+            .collect();
+        let ma20 = MovingAverage {
+            label: "MA20".to_string(),
+            values: (0..30).map(|i| if i < 19 { 0.0 } else { 585.0 }).collect(),
+        };
+
+        let png = price_chart("2330 台積電", &candles, &[ma20]).unwrap();
+        assert_eq!(png[..8], PNG_SIGNATURE);
+
+        assert!(price_chart("2330 台積電", &[], &[]).is_err());
+    }
+
+    #[test]
+    fn test_revenue_chart() {
+        let bars = vec![
+            ("2024/01".to_string(), 215785.0),
+            ("2024/02".to_string(), 181648.0),
+            ("2024/03".to_string(), 195211.0),
+        ];
+
+        let png = revenue_chart("2330 月營收", &bars).unwrap();
+        assert_eq!(png[..8], PNG_SIGNATURE);
+
+        assert!(revenue_chart("2330 月營收", &[]).is_err());
+    }
+}
@@ -1,24 +1,80 @@
-use std::{collections::HashMap, env, path::PathBuf, str::FromStr, u8};
-
-use anyhow::Result;
+use std::{
+    collections::HashMap,
+    env,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, RwLock},
+    u8,
+};
+
+use anyhow::{Context, Result};
+use chrono::NaiveTime;
 use config::{Config as config_config, File as config_file, FileFormat};
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 
-use crate::logging;
+use crate::{backfill::priority::CrawlFrequency, logging, util::http::policy::RequestPolicy};
 
 const CONFIG_PATH: &str = "app.json";
+/// 選擇執行環境的 env ex. APP_ENV=dev 時讀取 app.json 後以 app.dev.json 覆蓋
+const APP_ENV: &str = "APP_ENV";
+/// 未設定 APP_ENV 時的環境名稱，不讀取覆蓋用的設定檔
+pub const DEFAULT_PROFILE: &str = "prod";
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone)]
 pub struct App {
     pub afraid: Afraid,
     pub dyny: Dynu,
     pub noip: NoIp,
+    #[serde(default)]
+    pub ddns: Ddns,
     pub bot: Bot,
     pub postgresql: PostgreSQL,
     pub rpc: Rpc,
     pub nosql: NoSQL,
     pub system: System,
+    #[serde(default)]
+    pub storage: Storage,
+    #[serde(default)]
+    pub backup: Backup,
+    #[serde(default)]
+    pub report: Report,
+    #[serde(default)]
+    pub rebalance: Rebalance,
+    #[serde(default)]
+    pub announcement: Announcement,
+    #[serde(default)]
+    pub alert: Alert,
+    #[serde(default)]
+    pub signals: Signals,
+    #[serde(default)]
+    pub pipeline: Pipeline,
+    #[serde(default)]
+    pub catch_up: CatchUp,
+    #[serde(default)]
+    pub leader_election: LeaderElection,
+    #[serde(default)]
+    pub logging: Logging,
+    #[serde(default)]
+    pub chart: Chart,
+    #[serde(default)]
+    pub sqlite: Sqlite,
+    #[serde(default)]
+    pub partition: Partition,
+    #[serde(default)]
+    pub telemetry: Telemetry,
+    #[serde(default)]
+    pub publisher: Publisher,
+    #[serde(default)]
+    pub limits: Limits,
+    #[serde(default)]
+    pub profile: Profile,
+    /// 各爬蟲來源的請求逾時與重試，key 為來源名稱 ex. goodinfo、twse、yahoo
+    #[serde(default)]
+    pub crawler: HashMap<String, RequestPolicy>,
+    /// 各爬蟲來源依庫存、追踪、其餘股票區分的採集間隔，key 為來源名稱 ex. goodinfo
+    #[serde(default)]
+    pub crawl_priority: HashMap<String, CrawlFrequency>,
 }
 
 const SYSTEM_GRPC_USE_PORT: &str = "SYSTEM_GRPC_USE_PORT";
@@ -86,11 +142,43 @@ pub struct NoIp {
     pub hostnames: Vec<String>,
 }
 
+const DDNS_HOSTS: &str = "DDNS_HOSTS";
+
+/// 動態 DNS，afraid、dyny、noip 區段的設定仍然有效，會與 hosts 一起更新
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Ddns {
+    #[serde(default)]
+    pub hosts: Vec<DdnsHost>,
+}
+
+/// 要更新 IP 的主機
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct DdnsHost {
+    /// 服務商 ex. afraid、cloudflare、duckdns、dynu、noip
+    pub provider: String,
+    /// 主機名稱 ex. home.example.com，duckdns 為 duckdns.org 前面的子網域
+    #[serde(default)]
+    pub hostname: String,
+    /// afraid 的更新 token、cloudflare 的 API token、duckdns 的 token
+    #[serde(default)]
+    pub token: String,
+    /// dynu、noip 的帳號
+    #[serde(default)]
+    pub username: String,
+    /// dynu、noip 的密碼
+    #[serde(default)]
+    pub password: String,
+    /// cloudflare 主機所在的 zone id
+    #[serde(default)]
+    pub zone_id: String,
+}
+
 const POSTGRESQL_HOST: &str = "POSTGRESQL_HOST";
 const POSTGRESQL_PORT: &str = "POSTGRESQL_PORT";
 const POSTGRESQL_USER: &str = "POSTGRESQL_USER";
 const POSTGRESQL_PASSWORD: &str = "POSTGRESQL_PASSWORD";
 const POSTGRESQL_DB: &str = "POSTGRESQL_DB";
+const POSTGRESQL_SLOW_QUERY_MS: &str = "POSTGRESQL_SLOW_QUERY_MS";
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone)]
 pub struct PostgreSQL {
@@ -104,15 +192,38 @@ pub struct PostgreSQL {
     pub password: String,
     #[serde(default)]
     pub db: String,
+    /// 查詢超過幾毫秒時記錄為慢查詢，0 代表不記錄
+    #[serde(default)]
+    pub slow_query_ms: u64,
 }
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone)]
 pub struct Bot {
     pub telegram: Telegram,
+    #[serde(default)]
+    pub sandbox: Sandbox,
+}
+
+const SANDBOX_ENABLED: &str = "SANDBOX_ENABLED";
+const SANDBOX_CHAT_ID: &str = "SANDBOX_CHAT_ID";
+
+/// 沙箱模式，開啟時所有對外發送的訊息都改送到測試用的聊天室，並在開頭加上 [SANDBOX]
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Sandbox {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 測試用的聊天室 id
+    #[serde(default)]
+    pub chat_id: i64,
 }
 
 const TELEGRAM_TOKEN: &str = "TELEGRAM_TOKEN";
 const TELEGRAM_ALLOWED: &str = "TELEGRAM_ALLOWED";
+const TELEGRAM_POLL_COMMANDS: &str = "TELEGRAM_POLL_COMMANDS";
+const TELEGRAM_ADMINS: &str = "TELEGRAM_ADMINS";
+const TELEGRAM_QUIET_HOURS: &str = "TELEGRAM_QUIET_HOURS";
+const TELEGRAM_LANGUAGES: &str = "TELEGRAM_LANGUAGES";
+const TELEGRAM_TEMPLATES_PATH: &str = "TELEGRAM_TEMPLATES_PATH";
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone)]
 pub struct Telegram {
@@ -120,6 +231,39 @@ pub struct Telegram {
     pub allowed: HashMap<i64, String>,
     #[serde(default)]
     pub token: String,
+    /// 是否輪詢聊天室的訊息並回應指令，同一個 token 只能有一個服務輪詢
+    #[serde(default)]
+    pub poll_commands: bool,
+    /// 可以使用 /jobs、/cache、/config 等管理指令的使用者 id
+    #[serde(default)]
+    pub admins: Vec<i64>,
+    /// 各聊天室的勿擾時段，非緊急的通知在時段內會延後到時段結束才發送
+    #[serde(default)]
+    pub quiet_hours: HashMap<i64, QuietHours>,
+    /// 各聊天室接收通知的語系 ex. zh-TW、en，未設定時為 zh-TW
+    #[serde(default)]
+    pub languages: HashMap<i64, String>,
+    /// 訊息範本的 TOML 檔路徑，未設定時使用工作目錄下的 templates.toml
+    #[serde(default)]
+    pub templates_path: String,
+}
+
+/// 勿擾時段(本地時間)，start 晚於 end 時代表跨過午夜 ex. 23:00 ~ 08:00
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    /// 指定的時間是否在勿擾時段內，包含 start 不包含 end
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone)]
@@ -140,7 +284,492 @@ pub struct Redis {
     pub db: i32,
 }
 
-pub static SETTINGS: Lazy<App> = Lazy::new(|| App::get().expect("Config error"));
+const STORAGE_BACKEND: &str = "STORAGE_BACKEND";
+const STORAGE_LOCAL_ROOT: &str = "STORAGE_LOCAL_ROOT";
+const STORAGE_S3_ENDPOINT: &str = "STORAGE_S3_ENDPOINT";
+const STORAGE_S3_REGION: &str = "STORAGE_S3_REGION";
+const STORAGE_S3_BUCKET: &str = "STORAGE_S3_BUCKET";
+const STORAGE_S3_ACCESS_KEY: &str = "STORAGE_S3_ACCESS_KEY";
+const STORAGE_S3_SECRET_KEY: &str = "STORAGE_S3_SECRET_KEY";
+
+/// 檔案儲存(匯出檔、原始回應封存、日誌)
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Storage {
+    /// 使用的儲存後端 local 或 s3，未設定時為 local
+    #[serde(default)]
+    pub backend: String,
+    #[serde(default)]
+    pub local: LocalStorage,
+    #[serde(default)]
+    pub s3: S3,
+    /// 是否封存爬蟲取得的原始回應
+    #[serde(default)]
+    pub archive_raw_response: bool,
+    /// 是否將前一日之前的日誌搬移至儲存後端
+    #[serde(default)]
+    pub ship_logs: bool,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct LocalStorage {
+    /// 存放檔案的根目錄
+    #[serde(default)]
+    pub root: String,
+}
+
+/// S3 相容的物件儲存(AWS S3、MinIO、Cloudflare R2 等)
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct S3 {
+    /// ex. https://s3.ap-northeast-1.amazonaws.com
+    #[serde(default)]
+    pub endpoint: String,
+    #[serde(default)]
+    pub region: String,
+    #[serde(default)]
+    pub bucket: String,
+    #[serde(default)]
+    pub access_key: String,
+    #[serde(default)]
+    pub secret_key: String,
+}
+
+const BACKUP_ENABLED: &str = "BACKUP_ENABLED";
+const BACKUP_RETENTION_DAYS: &str = "BACKUP_RETENTION_DAYS";
+const BACKUP_PG_DUMP_PATH: &str = "BACKUP_PG_DUMP_PATH";
+
+/// 資料庫備份
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Backup {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 備份檔保留的天數，未設定時為 7 天
+    #[serde(default)]
+    pub retention_days: i64,
+    /// pg_dump 的執行檔路徑，未設定時從 PATH 尋找
+    #[serde(default)]
+    pub pg_dump_path: String,
+}
+
+const PARTITION_ENABLED: &str = "PARTITION_ENABLED";
+const PARTITION_YEARS_AHEAD: &str = "PARTITION_YEARS_AHEAD";
+const PARTITION_RETENTION_YEARS: &str = "PARTITION_RETENTION_YEARS";
+
+/// DailyQuotes 年度分區的維護，需先執行 etc/sql/daily_quote_partition.sql 將資料表改為分區表
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Partition {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 預先建立今年之後幾個年度的分區，未設定時為 1
+    #[serde(default)]
+    pub years_ahead: i32,
+    /// 保留今年之前幾個年度的分區，更早的分區會移到 archive schema，0 代表全部保留
+    #[serde(default)]
+    pub retention_years: i32,
+}
+
+const LIMITS_MAX_CRAWLS: &str = "LIMITS_MAX_CRAWLS";
+const LIMITS_MAX_DB_WRITES: &str = "LIMITS_MAX_DB_WRITES";
+const LIMITS_MAX_TELEGRAM_MESSAGES: &str = "LIMITS_MAX_TELEGRAM_MESSAGES";
+const LIMITS_WRITE_QUEUE_CAPACITY: &str = "LIMITS_WRITE_QUEUE_CAPACITY";
+const LIMITS_WRITE_BATCH_SIZE: &str = "LIMITS_WRITE_BATCH_SIZE";
+
+/// 各階段同時執行的數量上限，0 代表依 CPU 數量決定，配備較低的機器(ex. Raspberry Pi)可以調低
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Limits {
+    /// 同時送出的爬蟲請求數，未設定時為 CPU 數量 × 8
+    #[serde(default)]
+    pub max_crawls: usize,
+    /// 同時寫入資料庫的查詢數，未設定時為 CPU 數量 × 4 且至少 16
+    #[serde(default)]
+    pub max_db_writes: usize,
+    /// 同時傳送中的 Telegram 訊息數，未設定時為 4
+    #[serde(default)]
+    pub max_telegram_messages: usize,
+    /// 回補時爬蟲送往寫入端的數據最多排隊幾筆，排滿時爬蟲等待寫入，未設定時為 2048
+    #[serde(default)]
+    pub write_queue_capacity: usize,
+    /// 寫入端每批最多寫入的筆數，未設定時為 500
+    #[serde(default)]
+    pub write_batch_size: usize,
+}
+
+const PROFILE_DISABLE_NOTIFICATIONS: &str = "PROFILE_DISABLE_NOTIFICATIONS";
+
+/// 執行環境(dev、staging、prod)，非 prod 的環境以 app.{環境}.json 覆蓋 app.json，
+/// 可以指定不同的資料庫名稱等設定，日誌寫入 log/{環境} 目錄
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Profile {
+    /// 環境名稱，由 env APP_ENV 決定，未設定時為 prod
+    #[serde(skip_deserializing)]
+    pub name: String,
+    /// 不發送 Telegram 訊息只寫入日誌，測試環境避免打擾聊天室
+    #[serde(default)]
+    pub disable_notifications: bool,
+}
+
+const CRAWLER_POLICIES: &str = "CRAWLER_POLICIES";
+const CRAWL_PRIORITIES: &str = "CRAWL_PRIORITIES";
+
+const TELEMETRY_ENABLED: &str = "TELEMETRY_ENABLED";
+const TELEMETRY_ENDPOINT: &str = "TELEMETRY_ENDPOINT";
+const TELEMETRY_SERVICE_NAME: &str = "TELEMETRY_SERVICE_NAME";
+
+/// OpenTelemetry 追蹤，以 OTLP/HTTP JSON 將排程、爬蟲與資料庫的 span 送到 Jaeger、Tempo 等收集器
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Telemetry {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 收集器接收 traces 的網址 ex. http://localhost:4318/v1/traces
+    #[serde(default)]
+    pub endpoint: String,
+    /// 服務名稱，未設定時為 stock_crawler
+    #[serde(default)]
+    pub service_name: String,
+}
+
+const PUBLISHER_BACKEND: &str = "PUBLISHER_BACKEND";
+const PUBLISHER_URL: &str = "PUBLISHER_URL";
+const PUBLISHER_TOPIC: &str = "PUBLISHER_TOPIC";
+
+/// 將寫入的收盤報價、月營收與警示以 JSON 發布到 Kafka 或 NATS，需以 `--features kafka` 或 `--features nats` 編譯
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Publisher {
+    /// kafka 或 nats，未設定時不發布
+    #[serde(default)]
+    pub backend: String,
+    /// Kafka 的 bootstrap.servers ex. localhost:9092，NATS 的伺服器 ex. nats://localhost:4222
+    #[serde(default)]
+    pub url: String,
+    /// Kafka 的 topic，NATS 則為 subject 的前綴 ex. stock_crawler.quote，未設定時為 stock_crawler
+    #[serde(default)]
+    pub topic: String,
+}
+
+const REPORT_YIELD_RANK_INDUSTRIES: &str = "REPORT_YIELD_RANK_INDUSTRIES";
+const REPORT_YIELD_RANK_LIMIT: &str = "REPORT_YIELD_RANK_LIMIT";
+const REPORT_STOCK_CONCENTRATION: &str = "REPORT_STOCK_CONCENTRATION";
+const REPORT_SECTOR_CONCENTRATION: &str = "REPORT_SECTOR_CONCENTRATION";
+const REPORT_RISK_FREE_RATE: &str = "REPORT_RISK_FREE_RATE";
+const REPORT_INCLUDE_WARRANTS: &str = "REPORT_INCLUDE_WARRANTS";
+
+/// 收盤後發送的報表
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Report {
+    /// 要發送殖利率排行的產業分類編號(stock_industry)，未設定時不發送
+    #[serde(default)]
+    pub yield_rank_industries: Vec<i32>,
+    /// 每個產業列出的筆數，未設定時為 10 筆
+    #[serde(default)]
+    pub yield_rank_limit: i64,
+    /// 單一股票佔成員市值超過幾 % 時提醒，未設定時為 25
+    #[serde(default)]
+    pub stock_concentration: i64,
+    /// 單一產業佔成員市值超過幾 % 時提醒，未設定時為 50
+    #[serde(default)]
+    pub sector_concentration: i64,
+    /// 計算夏普比率使用的年化無風險利率(%)，ex. 1.7 代表一年期定存利率 1.7%
+    #[serde(default)]
+    pub risk_free_rate: f64,
+    /// 排行、選股與殖利率報表是否包含權證，未設定時排除
+    #[serde(default)]
+    pub include_warrants: bool,
+}
+
+const REBALANCE_TARGETS: &str = "REBALANCE_TARGETS";
+const REBALANCE_TOLERANCE: &str = "REBALANCE_TOLERANCE";
+const REBALANCE_FEE_DISCOUNT: &str = "REBALANCE_FEE_DISCOUNT";
+
+/// 依目標配置計算的再平衡建議
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Rebalance {
+    /// 目標配置，key 為股票代號，value 為比重 ex. {"0056": 40, "2330": 60}，加總不是 100 時依比例換算，未設定時不計算
+    #[serde(default)]
+    pub targets: HashMap<String, f64>,
+    /// 比重與目標相差幾個百分點以內不調整，未設定時為 2
+    #[serde(default)]
+    pub tolerance: f64,
+    /// 券商手續費的折扣 ex. 0.28 代表 2.8 折，未設定時為 1(不打折)
+    #[serde(default)]
+    pub fee_discount: f64,
+}
+
+const ANNOUNCEMENT_KEYWORDS: &str = "ANNOUNCEMENT_KEYWORDS";
+
+/// 重大訊息
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Announcement {
+    /// 主旨含有這些關鍵字時發送通知，未設定時使用預設的關鍵字(減資、合併、處分等)
+    #[serde(default)]
+    pub keywords: Vec<String>,
+}
+
+const ALERT_DEDUP_MINUTES: &str = "ALERT_DEDUP_MINUTES";
+const ALERT_DIGEST_HOURS: &str = "ALERT_DIGEST_HOURS";
+const ALERT_QFII_DROP_POINTS: &str = "ALERT_QFII_DROP_POINTS";
+const ALERT_INTRADAY_PROTECTION: &str = "ALERT_INTRADAY_PROTECTION";
+
+/// 警示通知的去重與摘要
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Alert {
+    /// 同一規則相同內容的警示在幾分鐘內只發送一次，0 代表不去重
+    #[serde(default)]
+    pub dedup_minutes: u64,
+    /// 改為摘要發送的規則，key 為規則名稱 ex. price_alert，value 為每幾小時彙整成一則訊息
+    #[serde(default)]
+    pub digest_hours: HashMap<String, u64>,
+    /// 庫存股票的外資持股比率一週內減少超過幾個百分點時提醒，未設定時為 2
+    #[serde(default)]
+    pub qfii_drop_points: f64,
+    /// 盤中是否每分鐘檢查庫存的停損價與停利價，未設定時只在收盤後檢查
+    #[serde(default)]
+    pub intraday_protection: bool,
+}
+
+const SIGNALS_RULES_PATH: &str = "SIGNALS_RULES_PATH";
+
+/// 策略訊號
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Signals {
+    /// 規則定義的 TOML 檔路徑，未設定時與 app.json 相同使用工作目錄下的 signals.toml
+    #[serde(default)]
+    pub rules_path: String,
+}
+
+const PIPELINE_CLOSING: &str = "PIPELINE_CLOSING";
+
+/// 排程任務內依序執行的步驟
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Pipeline {
+    /// 收盤後依序執行的步驟，未設定時執行全部的步驟
+    #[serde(default)]
+    pub closing: Vec<PipelineStep>,
+}
+
+/// 步驟名稱與是否啟用
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PipelineStep {
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+const CATCH_UP_ENABLED: &str = "CATCH_UP_ENABLED";
+const CATCH_UP_EXCLUDED: &str = "CATCH_UP_EXCLUDED";
+
+/// 啟動時補跑停機期間錯過的排程任務
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CatchUp {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 不需要補跑的任務名稱 ex. backfill::revenue::execute
+    #[serde(default)]
+    pub excluded: Vec<String>,
+}
+
+impl Default for CatchUp {
+    fn default() -> Self {
+        CatchUp {
+            enabled: true,
+            excluded: Vec::new(),
+        }
+    }
+}
+
+const LEADER_ELECTION_ENABLED: &str = "LEADER_ELECTION_ENABLED";
+const LEADER_ELECTION_TTL_SECONDS: &str = "LEADER_ELECTION_TTL_SECONDS";
+
+/// 多個執行個體同時運行時(ex. 部署期間)以 Redis 選出唯一執行排程任務的 leader
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LeaderElection {
+    /// 關閉時每個執行個體都視為 leader，與單一執行個體的行為相同
+    #[serde(default)]
+    pub enabled: bool,
+    /// leader 鎖的存活秒數，leader 每隔三分之一的時間續約，停止續約超過這個時間後由其他執行個體接手
+    #[serde(default = "default_leader_ttl_seconds")]
+    pub ttl_seconds: u64,
+}
+
+fn default_leader_ttl_seconds() -> u64 {
+    30
+}
+
+impl Default for LeaderElection {
+    fn default() -> Self {
+        LeaderElection {
+            enabled: false,
+            ttl_seconds: default_leader_ttl_seconds(),
+        }
+    }
+}
+
+const LOGGING_INFO: &str = "LOGGING_INFO";
+const LOGGING_WARN: &str = "LOGGING_WARN";
+const LOGGING_ERROR: &str = "LOGGING_ERROR";
+const LOGGING_DEBUG: &str = "LOGGING_DEBUG";
+const LOGGING_SYSLOG_PATH: &str = "LOGGING_SYSLOG_PATH";
+const LOGGING_SYSLOG_IDENTIFIER: &str = "LOGGING_SYSLOG_IDENTIFIER";
+const LOGGING_CONSOLE_COLOR: &str = "LOGGING_CONSOLE_COLOR";
+const LOGGING_CONSOLE_FORMAT: &str = "LOGGING_CONSOLE_FORMAT";
+
+/// 日誌各等級的輸出目的地
+///
+/// 目的地可為 file、console、both(file 與 console)、syslog，空陣列代表不輸出該等級的日誌
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Logging {
+    #[serde(default = "default_log_outputs")]
+    pub info: Vec<String>,
+    #[serde(default = "default_log_outputs")]
+    pub warn: Vec<String>,
+    #[serde(default = "default_log_outputs")]
+    pub error: Vec<String>,
+    #[serde(default = "default_log_outputs")]
+    pub debug: Vec<String>,
+    #[serde(default)]
+    pub syslog: Syslog,
+    #[serde(default)]
+    pub console: ConsoleLog,
+}
+
+/// 未設定時只寫入檔案
+fn default_log_outputs() -> Vec<String> {
+    vec!["file".to_string()]
+}
+
+impl Default for Logging {
+    fn default() -> Self {
+        Logging {
+            info: default_log_outputs(),
+            warn: default_log_outputs(),
+            error: default_log_outputs(),
+            debug: default_log_outputs(),
+            syslog: Syslog::default(),
+            console: ConsoleLog::default(),
+        }
+    }
+}
+
+/// 輸出到 syslog 的設定，systemd 環境由 journald 接收 /dev/log 的日誌
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Syslog {
+    /// syslog 的 unix socket
+    #[serde(default = "default_syslog_path")]
+    pub path: String,
+    /// 日誌的識別名稱，journalctl -t 以這個名稱篩選
+    #[serde(default = "default_syslog_identifier")]
+    pub identifier: String,
+}
+
+fn default_syslog_path() -> String {
+    "/dev/log".to_string()
+}
+
+fn default_syslog_identifier() -> String {
+    "stock_crawler".to_string()
+}
+
+impl Default for Syslog {
+    fn default() -> Self {
+        Syslog {
+            path: default_syslog_path(),
+            identifier: default_syslog_identifier(),
+        }
+    }
+}
+
+/// 輸出到 console 的格式
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConsoleLog {
+    /// 以 ANSI 顏色標示等級，stdout 不是終端機時自動關閉
+    #[serde(default = "default_true")]
+    pub color: bool,
+    /// compact 只有時間、等級與訊息，verbose 另外加上日期、執行緒與 tokio 任務的識別
+    #[serde(default = "default_console_format")]
+    pub format: String,
+}
+
+fn default_console_format() -> String {
+    "compact".to_string()
+}
+
+impl Default for ConsoleLog {
+    fn default() -> Self {
+        ConsoleLog {
+            color: true,
+            format: default_console_format(),
+        }
+    }
+}
+
+const CHART_FONT_PATH: &str = "CHART_FONT_PATH";
+
+/// 圖表繪製
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Chart {
+    /// 繪製文字使用的 TTF 字型檔，未設定時圖表不顯示標題與座標刻度
+    #[serde(default)]
+    pub font_path: String,
+}
+
+const SQLITE_PATH: &str = "SQLITE_PATH";
+
+/// 本機模式(需啟用 sqlite feature)使用的 SQLite 資料庫
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Sqlite {
+    /// 資料庫檔案路徑，未設定時使用 stock_crawler.db
+    #[serde(default)]
+    pub path: String,
+}
+
+pub static SETTINGS: Lazy<Settings> =
+    Lazy::new(|| Settings::new(App::get().expect("Config error")));
+
+/// 目前生效的設定，以 `SETTINGS.load()` 取得，可透過 `SETTINGS.reload()` 重新讀取設定檔與 env
+///
+/// 重新載入時替換成新的 `Arc<App>`，已取得的舊設定在用完後釋放；
+/// 資料庫連線池、Telegram token 等啟動時就建立的資源不受重新載入影響
+pub struct Settings {
+    current: RwLock<Arc<App>>,
+}
+
+impl Settings {
+    fn new(app: App) -> Self {
+        logging::configure(&app.logging);
+        Settings {
+            current: RwLock::new(Arc::new(app)),
+        }
+    }
+
+    /// 取得目前生效的設定
+    pub fn load(&self) -> Arc<App> {
+        match self.current.read() {
+            Ok(current) => Arc::clone(&current),
+            Err(poisoned) => Arc::clone(&poisoned.into_inner()),
+        }
+    }
+
+    /// 重新讀取設定檔與 env，讀取失敗時回傳錯誤並保留目前的設定，日誌的輸出目的地立即套用新的設定
+    pub fn reload(&self) -> Result<()> {
+        let config_path = config_path();
+        let app = if config_path.exists() {
+            App::from_file(&config_path)?
+        } else {
+            App::from_env()?
+        };
+
+        logging::configure(&app.logging);
+        let app = Arc::new(app);
+        match self.current.write() {
+            Ok(mut current) => *current = app,
+            Err(poisoned) => *poisoned.into_inner() = app,
+        }
+
+        Ok(())
+    }
+}
 
 impl App {
     /*pub fn new() -> Self {
@@ -182,13 +811,8 @@ impl App {
         }*/
 
         if config_path.exists() {
-            let config: Result<App, _> = config::Config::builder()
-                .add_source(config::File::from(config_path.clone()).format(FileFormat::Json))
-                .build()
-                .and_then(|cfg| cfg.try_deserialize());
-
-            match config {
-                Ok(cfg) => return Ok(cfg.override_with_env()),
+            match App::from_file(&config_path) {
+                Ok(cfg) => return Ok(cfg),
                 Err(e) => {
                     // 列印錯誤資訊和設定檔內容
                     eprintln!(
@@ -201,19 +825,43 @@ impl App {
             }
         }
 
-        Ok(App::from_env())
+        App::from_env()
+    }
+
+    /// 讀取 json 設定檔與目前環境的覆蓋設定檔後以 env 覆蓋
+    fn from_file(config_path: &Path) -> Result<Self> {
+        let mut builder = config::Config::builder()
+            .add_source(config::File::from(config_path).format(FileFormat::Json));
+        let profile = profile();
+        if profile != DEFAULT_PROFILE {
+            builder = builder.add_source(
+                config::File::from(profile_config_path(config_path, &profile))
+                    .format(FileFormat::Json)
+                    .required(false),
+            );
+        }
+
+        let cfg: App = builder
+            .build()
+            .and_then(|cfg| cfg.try_deserialize())
+            .context(format!(
+                "Failed to load config file {}",
+                config_path.display()
+            ))?;
+
+        Ok(cfg.override_with_env())
     }
 
-    /// 從 env 中讀取設定值
-    fn from_env() -> Self {
-        let tg_allowed = env::var(TELEGRAM_ALLOWED).expect(TELEGRAM_ALLOWED);
+    /// 從 env 中讀取設定值，缺少必要的 env 時回傳錯誤
+    fn from_env() -> Result<Self> {
+        let tg_allowed = env::var(TELEGRAM_ALLOWED).context(TELEGRAM_ALLOWED)?;
         let mut allowed_list: HashMap<i64, String> = Default::default();
         if !tg_allowed.is_empty() {
             if let Ok(allowed) = serde_json::from_str::<HashMap<i64, String>>(&tg_allowed) {
                 allowed_list = allowed;
             }
         }
-        let noip_hostnames = env::var(NOIP_HOSTNAMES).expect(NOIP_HOSTNAMES);
+        let noip_hostnames = env::var(NOIP_HOSTNAMES).context(NOIP_HOSTNAMES)?;
         let mut noip_hostnames_list: Vec<String> = Default::default();
 
         match serde_json::from_str::<Vec<String>>(&noip_hostnames) {
@@ -228,34 +876,67 @@ impl App {
             }
         }
 
-        App {
+        Ok(App {
             afraid: Afraid {
-                token: env::var(AFRAID_TOKEN).expect(AFRAID_TOKEN),
+                token: env::var(AFRAID_TOKEN).context(AFRAID_TOKEN)?,
                 url: "".to_string(),
                 path: "".to_string(),
             },
             postgresql: PostgreSQL {
-                host: env::var(POSTGRESQL_HOST).expect(POSTGRESQL_HOST),
+                host: env::var(POSTGRESQL_HOST).context(POSTGRESQL_HOST)?,
                 port: i32::from_str(
                     &env::var(POSTGRESQL_PORT).unwrap_or_else(|_| "5432".to_string()),
                 )
                 .unwrap_or(5432),
-                user: env::var(POSTGRESQL_USER).expect(POSTGRESQL_USER),
-                password: env::var(POSTGRESQL_PASSWORD).expect(POSTGRESQL_PASSWORD),
-                db: env::var(POSTGRESQL_DB).expect(POSTGRESQL_DB),
+                user: env::var(POSTGRESQL_USER).context(POSTGRESQL_USER)?,
+                password: env::var(POSTGRESQL_PASSWORD).context(POSTGRESQL_PASSWORD)?,
+                db: env::var(POSTGRESQL_DB).context(POSTGRESQL_DB)?,
+                slow_query_ms: env::var(POSTGRESQL_SLOW_QUERY_MS)
+                    .ok()
+                    .and_then(|ms| ms.parse().ok())
+                    .unwrap_or_default(),
             },
             bot: Bot {
                 telegram: Telegram {
                     allowed: allowed_list,
-                    token: env::var(TELEGRAM_TOKEN).expect(TELEGRAM_TOKEN),
+                    token: env::var(TELEGRAM_TOKEN).context(TELEGRAM_TOKEN)?,
+                    poll_commands: env::var(TELEGRAM_POLL_COMMANDS)
+                        .map(|enabled| enabled == "true")
+                        .unwrap_or(false),
+                    admins: env::var(TELEGRAM_ADMINS)
+                        .ok()
+                        .and_then(|admins| serde_json::from_str::<Vec<i64>>(&admins).ok())
+                        .unwrap_or_default(),
+                    quiet_hours: env::var(TELEGRAM_QUIET_HOURS)
+                        .ok()
+                        .and_then(|quiet| {
+                            serde_json::from_str::<HashMap<i64, QuietHours>>(&quiet).ok()
+                        })
+                        .unwrap_or_default(),
+                    languages: env::var(TELEGRAM_LANGUAGES)
+                        .ok()
+                        .and_then(|languages| {
+                            serde_json::from_str::<HashMap<i64, String>>(&languages).ok()
+                        })
+                        .unwrap_or_default(),
+                    templates_path: env::var(TELEGRAM_TEMPLATES_PATH).unwrap_or_default(),
+                },
+                sandbox: Sandbox {
+                    enabled: env::var(SANDBOX_ENABLED)
+                        .map(|enabled| enabled == "true")
+                        .unwrap_or(false),
+                    chat_id: env::var(SANDBOX_CHAT_ID)
+                        .unwrap_or_default()
+                        .parse::<i64>()
+                        .unwrap_or_default(),
                 },
             },
 
             nosql: NoSQL {
                 redis: Redis {
-                    addr: env::var(REDIS_ADDR).expect(REDIS_ADDR),
-                    account: env::var(REDIS_ACCOUNT).expect(REDIS_ACCOUNT),
-                    password: env::var(REDIS_PASSWORD).expect(REDIS_PASSWORD),
+                    addr: env::var(REDIS_ADDR).context(REDIS_ADDR)?,
+                    account: env::var(REDIS_ACCOUNT).context(REDIS_ACCOUNT)?,
+                    password: env::var(REDIS_PASSWORD).context(REDIS_PASSWORD)?,
                     db: i32::from_str(&env::var(REDIS_DB).unwrap_or_else(|_| "6379".to_string()))
                         .unwrap_or(6379),
                 },
@@ -263,10 +944,10 @@ impl App {
 
             rpc: Rpc {
                 go_service: Grpc {
-                    target: env::var(GO_GRPC_TARGET).expect(GO_GRPC_TARGET),
-                    tls_cert_file: env::var(GO_GRPC_TLS_CERT_FILE).expect(GO_GRPC_TLS_CERT_FILE),
-                    tls_key_file: env::var(GO_GRPC_TLS_KEY_FILE).expect(GO_GRPC_TLS_KEY_FILE),
-                    domain_name: env::var(GO_GRPC_DOMAIN_NAME).expect(GO_GRPC_DOMAIN_NAME),
+                    target: env::var(GO_GRPC_TARGET).context(GO_GRPC_TARGET)?,
+                    tls_cert_file: env::var(GO_GRPC_TLS_CERT_FILE).context(GO_GRPC_TLS_CERT_FILE)?,
+                    tls_key_file: env::var(GO_GRPC_TLS_KEY_FILE).context(GO_GRPC_TLS_KEY_FILE)?,
+                    domain_name: env::var(GO_GRPC_DOMAIN_NAME).context(GO_GRPC_DOMAIN_NAME)?,
                 },
             },
             system: System {
@@ -274,19 +955,228 @@ impl App {
                     .unwrap_or_else(|_| "0".to_string())
                     .parse::<i32>()
                     .unwrap_or(0),
-                ssl_cert_file: env::var(SYSTEM_SSL_CERT_FILE).expect(SYSTEM_SSL_CERT_FILE),
-                ssl_key_file: env::var(SYSTEM_SSL_KEY_FILE).expect(SYSTEM_SSL_KEY_FILE),
+                ssl_cert_file: env::var(SYSTEM_SSL_CERT_FILE).context(SYSTEM_SSL_CERT_FILE)?,
+                ssl_key_file: env::var(SYSTEM_SSL_KEY_FILE).context(SYSTEM_SSL_KEY_FILE)?,
             },
             dyny: Dynu {
-                username: env::var(DYNU_USERNAME).expect(DYNU_USERNAME),
-                password: env::var(DYNU_PASSWORD).expect(DYNU_PASSWORD),
+                username: env::var(DYNU_USERNAME).context(DYNU_USERNAME)?,
+                password: env::var(DYNU_PASSWORD).context(DYNU_PASSWORD)?,
             },
             noip: NoIp {
-                username: env::var(NOIP_USERNAME).expect(NOIP_USERNAME),
-                password: env::var(NOIP_USERNAME).expect(NOIP_USERNAME),
+                username: env::var(NOIP_USERNAME).context(NOIP_USERNAME)?,
+                password: env::var(NOIP_USERNAME).context(NOIP_USERNAME)?,
                 hostnames: noip_hostnames_list,
             },
-        }
+            ddns: Ddns {
+                hosts: env::var(DDNS_HOSTS)
+                    .ok()
+                    .and_then(|hosts| serde_json::from_str::<Vec<DdnsHost>>(&hosts).ok())
+                    .unwrap_or_default(),
+            },
+            storage: Storage {
+                backend: env::var(STORAGE_BACKEND).unwrap_or_default(),
+                local: LocalStorage {
+                    root: env::var(STORAGE_LOCAL_ROOT).unwrap_or_default(),
+                },
+                s3: S3 {
+                    endpoint: env::var(STORAGE_S3_ENDPOINT).unwrap_or_default(),
+                    region: env::var(STORAGE_S3_REGION).unwrap_or_default(),
+                    bucket: env::var(STORAGE_S3_BUCKET).unwrap_or_default(),
+                    access_key: env::var(STORAGE_S3_ACCESS_KEY).unwrap_or_default(),
+                    secret_key: env::var(STORAGE_S3_SECRET_KEY).unwrap_or_default(),
+                },
+                archive_raw_response: false,
+                ship_logs: false,
+            },
+            backup: Backup {
+                enabled: env::var(BACKUP_ENABLED)
+                    .map(|enabled| enabled == "true")
+                    .unwrap_or(false),
+                retention_days: env::var(BACKUP_RETENTION_DAYS)
+                    .unwrap_or_else(|_| "7".to_string())
+                    .parse::<i64>()
+                    .unwrap_or(7),
+                pg_dump_path: env::var(BACKUP_PG_DUMP_PATH).unwrap_or_default(),
+            },
+            report: Report {
+                yield_rank_industries: env::var(REPORT_YIELD_RANK_INDUSTRIES)
+                    .ok()
+                    .and_then(|industries| serde_json::from_str::<Vec<i32>>(&industries).ok())
+                    .unwrap_or_default(),
+                yield_rank_limit: env::var(REPORT_YIELD_RANK_LIMIT)
+                    .unwrap_or_else(|_| "10".to_string())
+                    .parse::<i64>()
+                    .unwrap_or(10),
+                stock_concentration: env::var(REPORT_STOCK_CONCENTRATION)
+                    .unwrap_or_else(|_| "25".to_string())
+                    .parse::<i64>()
+                    .unwrap_or(25),
+                sector_concentration: env::var(REPORT_SECTOR_CONCENTRATION)
+                    .unwrap_or_else(|_| "50".to_string())
+                    .parse::<i64>()
+                    .unwrap_or(50),
+                risk_free_rate: env::var(REPORT_RISK_FREE_RATE)
+                    .unwrap_or_else(|_| "1.7".to_string())
+                    .parse::<f64>()
+                    .unwrap_or(1.7),
+                include_warrants: env::var(REPORT_INCLUDE_WARRANTS)
+                    .map(|enabled| enabled == "true")
+                    .unwrap_or(false),
+            },
+            rebalance: Rebalance {
+                targets: env::var(REBALANCE_TARGETS)
+                    .ok()
+                    .and_then(|targets| serde_json::from_str::<HashMap<String, f64>>(&targets).ok())
+                    .unwrap_or_default(),
+                tolerance: env::var(REBALANCE_TOLERANCE)
+                    .unwrap_or_else(|_| "2".to_string())
+                    .parse::<f64>()
+                    .unwrap_or(2.0),
+                fee_discount: env::var(REBALANCE_FEE_DISCOUNT)
+                    .unwrap_or_else(|_| "1".to_string())
+                    .parse::<f64>()
+                    .unwrap_or(1.0),
+            },
+            announcement: Announcement {
+                keywords: env::var(ANNOUNCEMENT_KEYWORDS)
+                    .ok()
+                    .and_then(|keywords| serde_json::from_str::<Vec<String>>(&keywords).ok())
+                    .unwrap_or_default(),
+            },
+            alert: Alert {
+                dedup_minutes: env::var(ALERT_DEDUP_MINUTES)
+                    .unwrap_or_else(|_| "30".to_string())
+                    .parse::<u64>()
+                    .unwrap_or(30),
+                digest_hours: env::var(ALERT_DIGEST_HOURS)
+                    .ok()
+                    .and_then(|digest| serde_json::from_str::<HashMap<String, u64>>(&digest).ok())
+                    .unwrap_or_default(),
+                qfii_drop_points: env::var(ALERT_QFII_DROP_POINTS)
+                    .unwrap_or_else(|_| "2".to_string())
+                    .parse::<f64>()
+                    .unwrap_or(2.0),
+                intraday_protection: env::var(ALERT_INTRADAY_PROTECTION)
+                    .map(|enabled| enabled == "true")
+                    .unwrap_or(false),
+            },
+            signals: Signals {
+                rules_path: env::var(SIGNALS_RULES_PATH).unwrap_or_default(),
+            },
+            pipeline: Pipeline {
+                closing: env::var(PIPELINE_CLOSING)
+                    .ok()
+                    .and_then(|steps| serde_json::from_str::<Vec<PipelineStep>>(&steps).ok())
+                    .unwrap_or_default(),
+            },
+            catch_up: CatchUp {
+                enabled: env::var(CATCH_UP_ENABLED)
+                    .map(|enabled| enabled == "true")
+                    .unwrap_or(true),
+                excluded: env::var(CATCH_UP_EXCLUDED)
+                    .ok()
+                    .and_then(|excluded| serde_json::from_str::<Vec<String>>(&excluded).ok())
+                    .unwrap_or_default(),
+            },
+            leader_election: LeaderElection {
+                enabled: env::var(LEADER_ELECTION_ENABLED)
+                    .map(|enabled| enabled == "true")
+                    .unwrap_or(false),
+                ttl_seconds: env::var(LEADER_ELECTION_TTL_SECONDS)
+                    .ok()
+                    .and_then(|ttl| ttl.parse::<u64>().ok())
+                    .unwrap_or_else(default_leader_ttl_seconds),
+            },
+            logging: Logging {
+                info: env_log_outputs(LOGGING_INFO),
+                warn: env_log_outputs(LOGGING_WARN),
+                error: env_log_outputs(LOGGING_ERROR),
+                debug: env_log_outputs(LOGGING_DEBUG),
+                syslog: Syslog {
+                    path: env::var(LOGGING_SYSLOG_PATH).unwrap_or_else(|_| default_syslog_path()),
+                    identifier: env::var(LOGGING_SYSLOG_IDENTIFIER)
+                        .unwrap_or_else(|_| default_syslog_identifier()),
+                },
+                console: ConsoleLog {
+                    color: env::var(LOGGING_CONSOLE_COLOR)
+                        .map(|color| color == "true")
+                        .unwrap_or(true),
+                    format: env::var(LOGGING_CONSOLE_FORMAT)
+                        .unwrap_or_else(|_| default_console_format()),
+                },
+            },
+            chart: Chart {
+                font_path: env::var(CHART_FONT_PATH).unwrap_or_default(),
+            },
+            sqlite: Sqlite {
+                path: env::var(SQLITE_PATH).unwrap_or_default(),
+            },
+            partition: Partition {
+                enabled: env::var(PARTITION_ENABLED)
+                    .map(|enabled| enabled == "true")
+                    .unwrap_or(false),
+                years_ahead: env::var(PARTITION_YEARS_AHEAD)
+                    .unwrap_or_else(|_| "1".to_string())
+                    .parse::<i32>()
+                    .unwrap_or(1),
+                retention_years: env::var(PARTITION_RETENTION_YEARS)
+                    .unwrap_or_default()
+                    .parse::<i32>()
+                    .unwrap_or_default(),
+            },
+            telemetry: Telemetry {
+                enabled: env::var(TELEMETRY_ENABLED)
+                    .map(|enabled| enabled == "true")
+                    .unwrap_or(false),
+                endpoint: env::var(TELEMETRY_ENDPOINT).unwrap_or_default(),
+                service_name: env::var(TELEMETRY_SERVICE_NAME).unwrap_or_default(),
+            },
+            publisher: Publisher {
+                backend: env::var(PUBLISHER_BACKEND).unwrap_or_default(),
+                url: env::var(PUBLISHER_URL).unwrap_or_default(),
+                topic: env::var(PUBLISHER_TOPIC).unwrap_or_default(),
+            },
+            limits: Limits {
+                max_crawls: env::var(LIMITS_MAX_CRAWLS)
+                    .unwrap_or_default()
+                    .parse::<usize>()
+                    .unwrap_or_default(),
+                max_db_writes: env::var(LIMITS_MAX_DB_WRITES)
+                    .unwrap_or_default()
+                    .parse::<usize>()
+                    .unwrap_or_default(),
+                max_telegram_messages: env::var(LIMITS_MAX_TELEGRAM_MESSAGES)
+                    .unwrap_or_default()
+                    .parse::<usize>()
+                    .unwrap_or_default(),
+                write_queue_capacity: env::var(LIMITS_WRITE_QUEUE_CAPACITY)
+                    .unwrap_or_default()
+                    .parse::<usize>()
+                    .unwrap_or_default(),
+                write_batch_size: env::var(LIMITS_WRITE_BATCH_SIZE)
+                    .unwrap_or_default()
+                    .parse::<usize>()
+                    .unwrap_or_default(),
+            },
+            profile: Profile {
+                name: profile(),
+                disable_notifications: env::var(PROFILE_DISABLE_NOTIFICATIONS)
+                    .map(|disabled| disabled == "true")
+                    .unwrap_or(false),
+            },
+            crawler: env::var(CRAWLER_POLICIES)
+                .ok()
+                .and_then(|policies| {
+                    serde_json::from_str::<HashMap<String, RequestPolicy>>(&policies).ok()
+                })
+                .unwrap_or_default(),
+            crawl_priority: env::var(CRAWL_PRIORITIES)
+                .ok()
+                .and_then(|priorities| {
+                    serde_json::from_str::<HashMap<String, CrawlFrequency>>(&priorities).ok()
+                })
+                .unwrap_or_default(),
+        })
     }
 
     /// 將來至於 env 的設定值覆蓋掉 json 上的設定值
@@ -325,6 +1215,20 @@ impl App {
             }
         }
 
+        if let Ok(hosts) = env::var(DDNS_HOSTS) {
+            match serde_json::from_str::<Vec<DdnsHost>>(&hosts) {
+                Ok(result) => {
+                    self.ddns.hosts = result;
+                }
+                Err(why) => {
+                    logging::error_file_async(format!(
+                        "Failed to serde_json because: {:?} \r\n {}",
+                        why, &hosts
+                    ));
+                }
+            }
+        }
+
         if let Ok(cert_file) = env::var(SYSTEM_SSL_CERT_FILE) {
             self.system.ssl_cert_file = cert_file;
         }
@@ -368,6 +1272,10 @@ impl App {
             self.postgresql.db = db;
         }
 
+        if let Ok(ms) = env::var(POSTGRESQL_SLOW_QUERY_MS) {
+            self.postgresql.slow_query_ms = ms.parse().unwrap_or_default();
+        }
+
         if let Ok(tg_allowed) = env::var(TELEGRAM_ALLOWED) {
             match serde_json::from_str::<HashMap<i64, String>>(&tg_allowed) {
                 Ok(allowed) => {
@@ -386,6 +1294,64 @@ impl App {
             self.bot.telegram.token = token
         }
 
+        if let Ok(enabled) = env::var(TELEGRAM_POLL_COMMANDS) {
+            self.bot.telegram.poll_commands = enabled == "true"
+        }
+
+        if let Ok(admins) = env::var(TELEGRAM_ADMINS) {
+            match serde_json::from_str::<Vec<i64>>(&admins) {
+                Ok(result) => {
+                    self.bot.telegram.admins = result;
+                }
+                Err(why) => {
+                    logging::error_file_async(format!(
+                        "Failed to serde_json because: {:?} \r\n {}",
+                        why, &admins
+                    ));
+                }
+            }
+        }
+
+        if let Ok(quiet) = env::var(TELEGRAM_QUIET_HOURS) {
+            match serde_json::from_str::<HashMap<i64, QuietHours>>(&quiet) {
+                Ok(result) => {
+                    self.bot.telegram.quiet_hours = result;
+                }
+                Err(why) => {
+                    logging::error_file_async(format!(
+                        "Failed to serde_json because: {:?} \r\n {}",
+                        why, &quiet
+                    ));
+                }
+            }
+        }
+
+        if let Ok(languages) = env::var(TELEGRAM_LANGUAGES) {
+            match serde_json::from_str::<HashMap<i64, String>>(&languages) {
+                Ok(result) => {
+                    self.bot.telegram.languages = result;
+                }
+                Err(why) => {
+                    logging::error_file_async(format!(
+                        "Failed to serde_json because: {:?} \r\n {}",
+                        why, &languages
+                    ));
+                }
+            }
+        }
+
+        if let Ok(path) = env::var(TELEGRAM_TEMPLATES_PATH) {
+            self.bot.telegram.templates_path = path;
+        }
+
+        if let Ok(enabled) = env::var(SANDBOX_ENABLED) {
+            self.bot.sandbox.enabled = enabled == "true"
+        }
+
+        if let Ok(chat_id) = env::var(SANDBOX_CHAT_ID) {
+            self.bot.sandbox.chat_id = i64::from_str(&chat_id).unwrap_or_default()
+        }
+
         if let Ok(addr) = env::var(REDIS_ADDR) {
             self.nosql.redis.addr = addr
         }
@@ -402,15 +1368,359 @@ impl App {
             self.nosql.redis.password = password
         }
 
+        if let Ok(backend) = env::var(STORAGE_BACKEND) {
+            self.storage.backend = backend
+        }
+
+        if let Ok(root) = env::var(STORAGE_LOCAL_ROOT) {
+            self.storage.local.root = root
+        }
+
+        if let Ok(endpoint) = env::var(STORAGE_S3_ENDPOINT) {
+            self.storage.s3.endpoint = endpoint
+        }
+
+        if let Ok(region) = env::var(STORAGE_S3_REGION) {
+            self.storage.s3.region = region
+        }
+
+        if let Ok(bucket) = env::var(STORAGE_S3_BUCKET) {
+            self.storage.s3.bucket = bucket
+        }
+
+        if let Ok(access_key) = env::var(STORAGE_S3_ACCESS_KEY) {
+            self.storage.s3.access_key = access_key
+        }
+
+        if let Ok(secret_key) = env::var(STORAGE_S3_SECRET_KEY) {
+            self.storage.s3.secret_key = secret_key
+        }
+
+        if let Ok(enabled) = env::var(BACKUP_ENABLED) {
+            self.backup.enabled = enabled == "true"
+        }
+
+        if let Ok(days) = env::var(BACKUP_RETENTION_DAYS) {
+            self.backup.retention_days = i64::from_str(&days).unwrap_or(7)
+        }
+
+        if let Ok(path) = env::var(BACKUP_PG_DUMP_PATH) {
+            self.backup.pg_dump_path = path
+        }
+
+        if let Ok(industries) = env::var(REPORT_YIELD_RANK_INDUSTRIES) {
+            match serde_json::from_str::<Vec<i32>>(&industries) {
+                Ok(result) => {
+                    self.report.yield_rank_industries = result;
+                }
+                Err(why) => {
+                    logging::error_file_async(format!(
+                        "Failed to serde_json because: {:?} \r\n {}",
+                        why, &industries
+                    ));
+                }
+            }
+        }
+
+        if let Ok(limit) = env::var(REPORT_YIELD_RANK_LIMIT) {
+            self.report.yield_rank_limit = i64::from_str(&limit).unwrap_or(10)
+        }
+
+        if let Ok(percent) = env::var(REPORT_STOCK_CONCENTRATION) {
+            self.report.stock_concentration = i64::from_str(&percent).unwrap_or(25)
+        }
+
+        if let Ok(percent) = env::var(REPORT_SECTOR_CONCENTRATION) {
+            self.report.sector_concentration = i64::from_str(&percent).unwrap_or(50)
+        }
+
+        if let Ok(rate) = env::var(REPORT_RISK_FREE_RATE) {
+            self.report.risk_free_rate = f64::from_str(&rate).unwrap_or(1.7)
+        }
+
+        if let Ok(enabled) = env::var(REPORT_INCLUDE_WARRANTS) {
+            self.report.include_warrants = enabled == "true"
+        }
+
+        if let Ok(targets) = env::var(REBALANCE_TARGETS) {
+            match serde_json::from_str::<HashMap<String, f64>>(&targets) {
+                Ok(result) => {
+                    self.rebalance.targets = result;
+                }
+                Err(why) => {
+                    logging::error_file_async(format!(
+                        "Failed to serde_json because: {:?} \r\n {}",
+                        why, &targets
+                    ));
+                }
+            }
+        }
+
+        if let Ok(tolerance) = env::var(REBALANCE_TOLERANCE) {
+            self.rebalance.tolerance = f64::from_str(&tolerance).unwrap_or(2.0)
+        }
+
+        if let Ok(discount) = env::var(REBALANCE_FEE_DISCOUNT) {
+            self.rebalance.fee_discount = f64::from_str(&discount).unwrap_or(1.0)
+        }
+
+        if let Ok(keywords) = env::var(ANNOUNCEMENT_KEYWORDS) {
+            match serde_json::from_str::<Vec<String>>(&keywords) {
+                Ok(result) => {
+                    self.announcement.keywords = result;
+                }
+                Err(why) => {
+                    logging::error_file_async(format!(
+                        "Failed to serde_json because: {:?} \r\n {}",
+                        why, &keywords
+                    ));
+                }
+            }
+        }
+
+        if let Ok(minutes) = env::var(ALERT_DEDUP_MINUTES) {
+            self.alert.dedup_minutes = u64::from_str(&minutes).unwrap_or(30)
+        }
+
+        if let Ok(digest) = env::var(ALERT_DIGEST_HOURS) {
+            match serde_json::from_str::<HashMap<String, u64>>(&digest) {
+                Ok(result) => {
+                    self.alert.digest_hours = result;
+                }
+                Err(why) => {
+                    logging::error_file_async(format!(
+                        "Failed to serde_json because: {:?} \r\n {}",
+                        why, &digest
+                    ));
+                }
+            }
+        }
+
+        if let Ok(points) = env::var(ALERT_QFII_DROP_POINTS) {
+            self.alert.qfii_drop_points = f64::from_str(&points).unwrap_or(2.0)
+        }
+
+        if let Ok(enabled) = env::var(ALERT_INTRADAY_PROTECTION) {
+            self.alert.intraday_protection = enabled == "true"
+        }
+
+        if let Ok(path) = env::var(SIGNALS_RULES_PATH) {
+            self.signals.rules_path = path;
+        }
+
+        if let Ok(steps) = env::var(PIPELINE_CLOSING) {
+            match serde_json::from_str::<Vec<PipelineStep>>(&steps) {
+                Ok(result) => {
+                    self.pipeline.closing = result;
+                }
+                Err(why) => {
+                    logging::error_file_async(format!(
+                        "Failed to serde_json because: {:?} \r\n {}",
+                        why, &steps
+                    ));
+                }
+            }
+        }
+
+        if let Ok(enabled) = env::var(CATCH_UP_ENABLED) {
+            self.catch_up.enabled = enabled == "true"
+        }
+
+        if let Ok(excluded) = env::var(CATCH_UP_EXCLUDED) {
+            match serde_json::from_str::<Vec<String>>(&excluded) {
+                Ok(result) => {
+                    self.catch_up.excluded = result;
+                }
+                Err(why) => {
+                    logging::error_file_async(format!(
+                        "Failed to serde_json because: {:?} \r\n {}",
+                        why, &excluded
+                    ));
+                }
+            }
+        }
+
+        if let Ok(enabled) = env::var(LEADER_ELECTION_ENABLED) {
+            self.leader_election.enabled = enabled == "true"
+        }
+
+        if let Ok(ttl) = env::var(LEADER_ELECTION_TTL_SECONDS) {
+            self.leader_election.ttl_seconds = u64::from_str(&ttl).unwrap_or(30)
+        }
+
+        for (name, outputs) in [
+            (LOGGING_INFO, &mut self.logging.info),
+            (LOGGING_WARN, &mut self.logging.warn),
+            (LOGGING_ERROR, &mut self.logging.error),
+            (LOGGING_DEBUG, &mut self.logging.debug),
+        ] {
+            if let Ok(value) = env::var(name) {
+                match serde_json::from_str::<Vec<String>>(&value) {
+                    Ok(result) => {
+                        *outputs = result;
+                    }
+                    Err(why) => {
+                        logging::error_file_async(format!(
+                            "Failed to serde_json because: {:?} \r\n {}",
+                            why, &value
+                        ));
+                    }
+                }
+            }
+        }
+
+        if let Ok(path) = env::var(LOGGING_SYSLOG_PATH) {
+            self.logging.syslog.path = path;
+        }
+
+        if let Ok(identifier) = env::var(LOGGING_SYSLOG_IDENTIFIER) {
+            self.logging.syslog.identifier = identifier;
+        }
+
+        if let Ok(color) = env::var(LOGGING_CONSOLE_COLOR) {
+            self.logging.console.color = color == "true"
+        }
+
+        if let Ok(format) = env::var(LOGGING_CONSOLE_FORMAT) {
+            self.logging.console.format = format;
+        }
+
+        if let Ok(font_path) = env::var(CHART_FONT_PATH) {
+            self.chart.font_path = font_path;
+        }
+
+        if let Ok(path) = env::var(SQLITE_PATH) {
+            self.sqlite.path = path;
+        }
+
+        if let Ok(enabled) = env::var(PARTITION_ENABLED) {
+            self.partition.enabled = enabled == "true"
+        }
+
+        if let Ok(years) = env::var(PARTITION_YEARS_AHEAD) {
+            self.partition.years_ahead = i32::from_str(&years).unwrap_or(1)
+        }
+
+        if let Ok(years) = env::var(PARTITION_RETENTION_YEARS) {
+            self.partition.retention_years = i32::from_str(&years).unwrap_or_default()
+        }
+
+        if let Ok(enabled) = env::var(TELEMETRY_ENABLED) {
+            self.telemetry.enabled = enabled == "true"
+        }
+
+        if let Ok(endpoint) = env::var(TELEMETRY_ENDPOINT) {
+            self.telemetry.endpoint = endpoint;
+        }
+
+        if let Ok(service_name) = env::var(TELEMETRY_SERVICE_NAME) {
+            self.telemetry.service_name = service_name;
+        }
+
+        if let Ok(backend) = env::var(PUBLISHER_BACKEND) {
+            self.publisher.backend = backend;
+        }
+
+        if let Ok(url) = env::var(PUBLISHER_URL) {
+            self.publisher.url = url;
+        }
+
+        if let Ok(topic) = env::var(PUBLISHER_TOPIC) {
+            self.publisher.topic = topic;
+        }
+
+        if let Ok(max) = env::var(LIMITS_MAX_CRAWLS) {
+            self.limits.max_crawls = usize::from_str(&max).unwrap_or_default()
+        }
+
+        if let Ok(max) = env::var(LIMITS_MAX_DB_WRITES) {
+            self.limits.max_db_writes = usize::from_str(&max).unwrap_or_default()
+        }
+
+        if let Ok(max) = env::var(LIMITS_MAX_TELEGRAM_MESSAGES) {
+            self.limits.max_telegram_messages = usize::from_str(&max).unwrap_or_default()
+        }
+
+        if let Ok(capacity) = env::var(LIMITS_WRITE_QUEUE_CAPACITY) {
+            self.limits.write_queue_capacity = usize::from_str(&capacity).unwrap_or_default()
+        }
+
+        if let Ok(size) = env::var(LIMITS_WRITE_BATCH_SIZE) {
+            self.limits.write_batch_size = usize::from_str(&size).unwrap_or_default()
+        }
+
+        self.profile.name = profile();
+
+        if let Ok(disabled) = env::var(PROFILE_DISABLE_NOTIFICATIONS) {
+            self.profile.disable_notifications = disabled == "true"
+        }
+
+        if let Ok(policies) = env::var(CRAWLER_POLICIES) {
+            match serde_json::from_str::<HashMap<String, RequestPolicy>>(&policies) {
+                Ok(result) => {
+                    self.crawler.extend(result);
+                }
+                Err(why) => {
+                    logging::error_file_async(format!(
+                        "Failed to serde_json because: {:?} \r\n {}",
+                        why, &policies
+                    ));
+                }
+            }
+        }
+
+        if let Ok(priorities) = env::var(CRAWL_PRIORITIES) {
+            match serde_json::from_str::<HashMap<String, CrawlFrequency>>(&priorities) {
+                Ok(result) => {
+                    self.crawl_priority.extend(result);
+                }
+                Err(why) => {
+                    logging::error_file_async(format!(
+                        "Failed to serde_json because: {:?} \r\n {}",
+                        why, &priorities
+                    ));
+                }
+            }
+        }
+
         self
     }
 }
 
+/// 從 env 讀取日誌的輸出目的地(JSON 陣列) ex. LOGGING_ERROR=["file","console"]
+fn env_log_outputs(name: &str) -> Vec<String> {
+    env::var(name)
+        .ok()
+        .and_then(|outputs| serde_json::from_str::<Vec<String>>(&outputs).ok())
+        .unwrap_or_else(default_log_outputs)
+}
+
 /// 回傳設定檔的路徑
 fn config_path() -> PathBuf {
     PathBuf::from(CONFIG_PATH)
 }
 
+/// 目前的執行環境名稱(小寫)，由 env APP_ENV 決定，未設定時為 prod
+///
+/// 日誌在設定檔載入前就會使用，所以直接讀取 env 而不是透過 SETTINGS
+pub fn profile() -> String {
+    env::var(APP_ENV)
+        .map(|profile| profile.trim().to_lowercase())
+        .ok()
+        .filter(|profile| !profile.is_empty())
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+/// 環境的覆蓋設定檔與 app.json 放在同一個目錄 ex. app.json → app.dev.json
+fn profile_config_path(config_path: &Path, profile: &str) -> PathBuf {
+    let stem = config_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("app");
+
+    config_path.with_file_name(format!("{}.{}.json", stem, profile))
+}
+
 /*/// 讀取預設的設定檔
 fn read_config_file() -> Result<String, io::Error> {
     let p = config_path();
@@ -429,21 +1739,53 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_quiet_hours() {
+        let overnight: QuietHours =
+            serde_json::from_str(r#"{"start": "23:00:00", "end": "08:00:00"}"#).unwrap();
+        let at = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+
+        assert!(overnight.contains(at(3, 0)));
+        assert!(overnight.contains(at(23, 0)));
+        assert!(!overnight.contains(at(8, 0)));
+        assert!(!overnight.contains(at(12, 0)));
+
+        let lunch = QuietHours {
+            start: at(12, 0),
+            end: at(13, 30),
+        };
+        assert!(lunch.contains(at(12, 30)));
+        assert!(!lunch.contains(at(14, 0)));
+    }
+
+    #[test]
+    fn test_profile_config_path() {
+        assert_eq!(
+            profile_config_path(Path::new("app.json"), "dev"),
+            PathBuf::from("app.dev.json")
+        );
+        assert_eq!(
+            profile_config_path(Path::new("/etc/stock_crawler/app.json"), "staging"),
+            PathBuf::from("/etc/stock_crawler/app.staging.json")
+        );
+    }
+
     #[tokio::test]
     async fn test_init() {
         dotenv::dotenv().ok();
-        logging::debug_file_async(format!("SETTINGS.system: {:#?}\r\n", SETTINGS.system));
+        let settings = SETTINGS.load();
+        logging::debug_file_async(format!("SETTINGS.system: {:#?}\r\n", settings.system));
         logging::debug_file_async(format!(
             "SETTINGS.postgresql: {:#?}\r\nSETTINGS.secret: {:#?}\r\n",
-            SETTINGS.postgresql, SETTINGS.bot
+            settings.postgresql, settings.bot
         ));
 
         logging::debug_file_async(format!(
             "SETTINGS.nosql.redis: {:#?}\r\n",
-            SETTINGS.nosql.redis
+            settings.nosql.redis
         ));
 
-        logging::debug_file_async(format!("SETTINGS.rpc: {:#?}\r\n", SETTINGS.rpc));
+        logging::debug_file_async(format!("SETTINGS.rpc: {:#?}\r\n", settings.rpc));
 
         let mut map: HashMap<i64, String> = HashMap::new();
         map.insert(123, "QQ".to_string());
@@ -1,36 +1,43 @@
-use std::sync::OnceLock;
-
 use anyhow::{anyhow, Result};
 
 use crate::{config, logging, util};
 
-static DDNS_URL: OnceLock<String> = OnceLock::new();
+pub(super) const HOST: &str = "sync.afraid.org";
 
-/// 向ddns服務更新目前的IP
-pub async fn visit() -> Result<()> {
-    let url = DDNS_URL.get_or_init(|| {
+/// 以更新 token 向 afraid.org 更新目前的IP，afraid.org 以請求的來源 IP 作為新的 IP
+pub async fn visit(token: &str) -> Result<()> {
+    let url = if config::SETTINGS.load().afraid.url.is_empty() {
+        format!("https://{}/u/{}/", HOST, token)
+    } else {
         format!(
             "{}{}/{}/",
-            config::SETTINGS.afraid.url,
-            config::SETTINGS.afraid.path,
-            config::SETTINGS.afraid.token,
+            config::SETTINGS.load().afraid.url,
+            config::SETTINGS.load().afraid.path,
+            token
         )
-    });
-
-    match util::http::get(url, None).await {
-        Ok(t) => {
-            if t.contains("Updated") {
-                logging::info_file_async(t);
-            }
-        }
-        Err(why) => {
-            return Err(anyhow!("Failed to afraid.visit because {:?}", why));
-        }
+    };
+
+    let text = util::http::get(&url, None)
+        .await
+        .map_err(|why| anyhow!("Failed to afraid.visit because {:?}", why))?;
+
+    check(&text)?;
+    if text.contains("Updated") {
+        logging::info_file_async(text);
     }
 
     Ok(())
 }
 
+/// 更新成功或 IP 沒有變動時回應 Updated ... 或 ... has not changed.，其餘視為失敗
+fn check(text: &str) -> Result<()> {
+    if text.contains("Updated") || text.contains("has not changed") {
+        return Ok(());
+    }
+
+    Err(anyhow!("Failed to afraid.visit because {}", text.trim()))
+}
+
 #[cfg(test)]
 mod tests {
     use tokio_test;
@@ -43,10 +50,17 @@ mod tests {
         };
     }
 
+    #[test]
+    fn test_check() {
+        assert!(check("Updated 1 host(s) home.example.com to 1.2.3.4 in 0.2 seconds").is_ok());
+        assert!(check("ERROR: Address 1.2.3.4 has not changed.").is_ok());
+        assert!(check("ERROR: Unable to locate this record").is_err());
+    }
+
     #[test]
     #[ignore]
     fn test_visit() {
         dotenv::dotenv().ok();
-        aw!(visit());
+        aw!(visit(&config::SETTINGS.load().afraid.token));
     }
 }
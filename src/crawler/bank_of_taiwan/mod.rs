@@ -1,6 +1,6 @@
 
 
-const HOST: &str = "fund.bot.com.tw";
+pub(super) const HOST: &str = "fund.bot.com.tw";
 
 /// 財務比率表
 pub mod financial_statement;
@@ -8,7 +8,7 @@ use crate::util;
 
 static DDNS_URL: OnceLock<String> = OnceLock::new();
 
-const HOST: &str = "api.bigdatacloud.net";
+pub(super) const HOST: &str = "api.bigdatacloud.net";
 
 #[derive(Serialize, Deserialize)]
 struct ApiResponse {
@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use reqwest::{header, Method};
+use serde::{Deserialize, Serialize};
+
+use crate::{logging, util};
+
+pub(super) const HOST: &str = "api.cloudflare.com";
+
+/// 更新 DNS 記錄的逾時秒數
+const TIMEOUT_SECONDS: u64 = 30;
+
+#[derive(Deserialize, Debug)]
+struct Response<T> {
+    success: bool,
+    #[serde(default)]
+    errors: Vec<ApiError>,
+    result: Option<T>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ApiError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+struct DnsRecord {
+    id: String,
+    content: String,
+}
+
+#[derive(Serialize)]
+struct PatchRecord<'a> {
+    content: &'a str,
+}
+
+impl<T> Response<T> {
+    /// success 為 false 時將 errors 組成錯誤訊息
+    fn into_result(self, action: &str) -> Result<Option<T>> {
+        if self.success {
+            return Ok(self.result);
+        }
+
+        let errors = self
+            .errors
+            .iter()
+            .map(|e| format!("{} {}", e.code, e.message))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        Err(anyhow!(
+            "Failed to cloudflare.{} because {}",
+            action,
+            errors
+        ))
+    }
+}
+
+/// 以 API token 將 zone 內名稱為 hostname 的 A 記錄更新為目前的IP，記錄的內容已經是目前的IP時不更新
+pub async fn visit(token: &str, zone_id: &str, hostname: &str, ip: &str) -> Result<()> {
+    let headers = headers(token)?;
+    let url = format!(
+        "https://{host}/client/v4/zones/{zone_id}/dns_records?type=A&name={hostname}",
+        host = HOST,
+        zone_id = zone_id,
+        hostname = hostname
+    );
+    let records = util::http::get_response(&url, Some(headers.clone()))
+        .await?
+        .json::<Response<Vec<DnsRecord>>>()
+        .await
+        .map_err(|why| anyhow!("Failed to parse cloudflare dns_records because {:?}", why))?
+        .into_result("list")?
+        .unwrap_or_default();
+
+    let record = records
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("The A record of {} is not found in cloudflare", hostname))?;
+
+    if record.content == ip {
+        return Ok(());
+    }
+
+    let url = format!(
+        "https://{host}/client/v4/zones/{zone_id}/dns_records/{id}",
+        host = HOST,
+        zone_id = zone_id,
+        id = record.id
+    );
+    util::http::request_bytes(
+        Method::PATCH,
+        &url,
+        Some(headers),
+        Some(serde_json::to_vec(&PatchRecord { content: ip })?),
+        Duration::from_secs(TIMEOUT_SECONDS),
+    )
+    .await?
+    .json::<Response<DnsRecord>>()
+    .await
+    .map_err(|why| anyhow!("Failed to parse cloudflare dns_records because {:?}", why))?
+    .into_result("patch")?;
+
+    logging::info_file_async(format!(
+        "cloudflare {} 已由 {} 更新為 {}",
+        hostname, record.content, ip
+    ));
+
+    Ok(())
+}
+
+fn headers(token: &str) -> Result<header::HeaderMap> {
+    let mut headers = header::HeaderMap::new();
+    headers.insert(
+        header::AUTHORIZATION,
+        header::HeaderValue::from_str(&format!("Bearer {}", token))?,
+    );
+    headers.insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static("application/json"),
+    );
+
+    Ok(headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_into_result() {
+        let ok: Response<Vec<DnsRecord>> = serde_json::from_str(
+            r#"{"success":true,"errors":[],"result":[{"id":"372e67954025e0ba6aaa6d586b9e0b59","content":"1.2.3.4","type":"A"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            ok.into_result("list").unwrap().unwrap(),
+            vec![DnsRecord {
+                id: "372e67954025e0ba6aaa6d586b9e0b59".to_string(),
+                content: "1.2.3.4".to_string(),
+            }]
+        );
+
+        let failed: Response<Vec<DnsRecord>> = serde_json::from_str(
+            r#"{"success":false,"errors":[{"code":10000,"message":"Authentication error"}],"result":null}"#,
+        )
+        .unwrap();
+        assert!(failed
+            .into_result("list")
+            .unwrap_err()
+            .to_string()
+            .contains("10000 Authentication error"));
+    }
+}
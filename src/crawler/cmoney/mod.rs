@@ -1,6 +1,6 @@
 /// 即時報價
 pub mod price;
 
-const HOST: &str = "www.cmoney.tw";
+pub(super) const HOST: &str = "www.cmoney.tw";
 
 pub struct CMoney {}
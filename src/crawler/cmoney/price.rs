@@ -8,13 +8,13 @@ use crate::{
         cmoney::{CMoney, HOST},
         StockInfo,
     },
-    declare,
+    declare::{self, StockSymbol},
     util::{self, text},
 };
 
 #[async_trait]
 impl StockInfo for CMoney {
-    async fn get_stock_price(stock_symbol: &str) -> Result<Decimal> {
+    async fn get_stock_price(stock_symbol: &StockSymbol) -> Result<Decimal> {
         let url = format!(
             "https://{host}/forum/stock/{symbol}",
             host = HOST,
@@ -33,7 +33,7 @@ impl StockInfo for CMoney {
         util::http::element::get_one_element_as_decimal(target)
     }
 
-    async fn get_stock_quotes(stock_symbol: &str) -> Result<declare::StockQuotes> {
+    async fn get_stock_quotes(stock_symbol: &StockSymbol) -> Result<declare::StockQuotes> {
         let url = &format!(
             "https://{host}/forum/stock/{symbol}",
             host = HOST,
@@ -72,7 +72,7 @@ impl StockInfo for CMoney {
         let change_range = text::parse_f64(&change_range, Some(['(', ')'].to_vec()))?;
 
         Ok(declare::StockQuotes {
-            stock_symbol: stock_symbol.to_string(),
+            stock_symbol: stock_symbol.clone(),
             price,
             change,
             change_range,
@@ -90,7 +90,7 @@ mod tests {
         dotenv::dotenv().ok();
         logging::debug_file_async("開始 get_stock_price".to_string());
 
-        match CMoney::get_stock_price("3008").await {
+        match CMoney::get_stock_price(&"3008".parse().unwrap()).await {
             Ok(e) => {
                 dbg!(&e);
                 logging::debug_file_async(format!("price : {:#?}", e));
@@ -108,7 +108,7 @@ mod tests {
         dotenv::dotenv().ok();
         logging::debug_file_async("開始 get_stock_quotes".to_string());
 
-        match CMoney::get_stock_quotes("6792").await {
+        match CMoney::get_stock_quotes(&"6792".parse().unwrap()).await {
             Ok(e) => {
                 dbg!(&e);
                 logging::debug_file_async(format!("get_stock_quotes : {:#?}", e));
@@ -8,7 +8,7 @@ use crate::{
         cnyes::{CnYes, HOST},
         StockInfo,
     },
-    declare::{self, StockQuotes},
+    declare::{self, StockQuotes, StockSymbol},
     util::{self},
 };
 
@@ -50,17 +50,17 @@ async fn fetch_data(stock_symbol: &str) -> Result<QuotesResponse> {
 
 #[async_trait]
 impl StockInfo for CnYes {
-    async fn get_stock_price(stock_symbol: &str) -> Result<Decimal> {
+    async fn get_stock_price(stock_symbol: &StockSymbol) -> Result<Decimal> {
         let r = fetch_data(stock_symbol).await?;
 
         Ok(Decimal::try_from(r.current_price)?)
     }
 
-    async fn get_stock_quotes(stock_symbol: &str) -> Result<declare::StockQuotes> {
+    async fn get_stock_quotes(stock_symbol: &StockSymbol) -> Result<declare::StockQuotes> {
         let r = fetch_data(stock_symbol).await?;
 
         Ok(StockQuotes {
-            stock_symbol: stock_symbol.to_string(),
+            stock_symbol: stock_symbol.clone(),
             price: r.current_price,
             change: r.change,
             change_range: r.change_range,
@@ -80,7 +80,7 @@ mod tests {
         logging::debug_file_async("開始 get_stock_price".to_string());
 
         // match get("2330").await {
-        match CnYes::get_stock_price("2330").await {
+        match CnYes::get_stock_price(&"2330".parse().unwrap()).await {
             Ok(e) => {
                 dbg!(&e);
                 logging::debug_file_async(format!("price : {:#?}", e));
@@ -98,7 +98,7 @@ mod tests {
         dotenv::dotenv().ok();
         logging::debug_file_async("開始 get_stock_quotes".to_string());
 
-        match CnYes::get_stock_quotes("2330").await {
+        match CnYes::get_stock_quotes(&"2330".parse().unwrap()).await {
             Ok(e) => {
                 dbg!(&e);
                 logging::debug_file_async(format!("get_stock_quotes : {:#?}", e));
@@ -0,0 +1,56 @@
+use anyhow::{anyhow, Result};
+
+use crate::{logging, util};
+
+pub(super) const HOST: &str = "www.duckdns.org";
+
+/// 以 token 向 DuckDNS 更新子網域目前的IP，domain 為 duckdns.org 前面的子網域 ex. myhome
+pub async fn visit(domain: &str, token: &str, ip: &str) -> Result<()> {
+    let url = format!(
+        "https://{host}/update?domains={domain}&token={token}&ip={ip}",
+        host = HOST,
+        domain = domain,
+        token = token,
+        ip = ip
+    );
+
+    let text = util::http::get(&url, None)
+        .await
+        .map_err(|why| anyhow!("Failed to duckdns.visit because {:?}", why))?;
+
+    // 更新成功回應 OK，失敗回應 KO 且沒有其他原因
+    if text.trim() != "OK" {
+        return Err(anyhow!(
+            "Failed to duckdns.visit({}) because {}",
+            domain,
+            text.trim()
+        ));
+    }
+
+    logging::info_file_async(format!("duckdns {} 已更新為 {}", domain, ip));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crawler::ipify;
+
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_visit() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 visit".to_string());
+        let ip_now = ipify::visit().await.unwrap();
+        match visit("myhome", "token", &ip_now).await {
+            Ok(_) => {}
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to visit because {:?}", why));
+            }
+        }
+
+        logging::debug_file_async("結束 visit".to_string());
+    }
+}
@@ -1,56 +1,74 @@
-use std::sync::OnceLock;
-
 use anyhow::{anyhow, Result};
 use sha2::{Digest, Sha256};
 
-use crate::{config, logging, util};
-
-static DDNS_URL: OnceLock<String> = OnceLock::new();
+use crate::{logging, util};
 
-const HOST: &str = "api.dynu.com";
+pub(super) const HOST: &str = "api.dynu.com";
 
-pub async fn visit(ip: &str) -> Result<()> {
-    let url = DDNS_URL.get_or_init(|| {
-        let mut hasher = Sha256::new();
+/// 以帳號密碼向 dynu 更新目前的IP，hostname 為空字串時更新帳號下全部的主機
+pub async fn visit(username: &str, password: &str, hostname: &str, ip: &str) -> Result<()> {
+    let mut hasher = Sha256::new();
+    hasher.update(password.as_bytes());
+    let mut url = format!(
+        "https://{host}/nic/update?username={username}&password={pw}&myip={ip}",
+        host = HOST,
+        username = username,
+        pw = hex::encode(hasher.finalize()),
+        ip = ip
+    );
+    if !hostname.is_empty() {
+        url.push_str(&format!("&hostname={}", hostname));
+    }
 
-        hasher.update(config::SETTINGS.dyny.password.as_bytes());
+    let text = util::http::get(&url, None)
+        .await
+        .map_err(|why| anyhow!("Failed to dynu.visit because {:?}", why))?;
 
-        let pw = hasher.finalize();
+    check(&text)?;
+    if text.contains("good") {
+        logging::info_file_async(text);
+    }
 
-        format!(
-            "https://{host}/nic/update?username={username}&password={pw}",
-            host = HOST,
-            username = config::SETTINGS.dyny.username,
-            pw = hex::encode(pw)
-        )
-    });
-    let url = format!("{url}&myip={ip}", url = url, ip = ip);
+    Ok(())
+}
 
-    match util::http::get(&url, None).await {
-        Ok(t) => {
-            if t.contains("good") {
-                logging::info_file_async(t);
-            }
-        }
-        Err(why) => {
-            return Err(anyhow!("Failed to dynu.visit because {:?}", why));
-        }
+/// dyndns2 協定更新成功回應 good，IP 沒有變動回應 nochg，其餘(badauth、nohost...)視為失敗
+pub(super) fn check(text: &str) -> Result<()> {
+    let text = text.trim();
+    if text.starts_with("good") || text.starts_with("nochg") {
+        return Ok(());
     }
 
-    Ok(())
+    Err(anyhow!("Failed to update the ddns because {}", text))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::crawler::ipify;
+    use crate::{config, crawler::ipify};
+
     use super::*;
 
+    #[test]
+    fn test_check() {
+        assert!(check("good 1.2.3.4").is_ok());
+        assert!(check("nochg 1.2.3.4\n").is_ok());
+        assert!(check("badauth").is_err());
+        assert!(check("nohost").is_err());
+    }
+
     #[tokio::test]
     async fn test_execute() {
         dotenv::dotenv().ok();
         logging::debug_file_async("開始 visit".to_string());
         let ip_now = ipify::visit().await.unwrap();
-        match visit(&ip_now).await {
+        match visit(
+            &config::SETTINGS.load().dyny.username,
+            &config::SETTINGS.load().dyny.password,
+            "",
+            &ip_now,
+        )
+        .await
+        {
             Ok(e) => {
                 dbg!(e);
             }
@@ -1,3 +1,3 @@
 pub mod annual_profit;
 
-const HOST: &str = "fubon-ebrokerdj.fbs.com.tw";
+pub(super) const HOST: &str = "fubon-ebrokerdj.fbs.com.tw";
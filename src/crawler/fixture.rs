@@ -0,0 +1,188 @@
+use std::{fmt::Debug, path::PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::NaiveDate;
+use serde::de::DeserializeOwned;
+
+use crate::{
+    crawler::{
+        tpex,
+        twse::{
+            self, announcement::Announcement, buyback::Buyback,
+            insider_shareholding::InsiderShareholding, shareholder_meeting::ShareholderMeeting,
+        },
+    },
+    declare::Quarter,
+};
+
+/// 存檔的原始回應所在的目錄，每個來源一個子目錄 ex. fixtures/twse/odd_lot/
+pub const FIXTURES_DIR: &str = "fixtures";
+
+const USAGE: &str = "usage: stock_crawler parse-fixture <source> <file>";
+
+/// 一個可以重播的解析器，parse 回傳每筆解析結果的 Debug 輸出
+struct Parser {
+    /// 來源名稱，同時也是 FIXTURES_DIR 下的子目錄 ex. twse/odd_lot
+    source: &'static str,
+    parse: fn(&str) -> Result<Vec<String>>,
+}
+
+/// 所有可以用存檔的原始回應重播的解析器，新增爬蟲時一併登記並放入至少一個 fixture
+const PARSERS: &[Parser] = &[
+    Parser {
+        source: "twse/announcement",
+        parse: json::<Announcement>,
+    },
+    Parser {
+        source: "twse/buyback",
+        parse: json::<Buyback>,
+    },
+    Parser {
+        source: "twse/cash_flow",
+        parse: |text| records(twse::cash_flow::parse(2024, Quarter::Q1, text)),
+    },
+    Parser {
+        source: "twse/earnings_call",
+        parse: |text| records(twse::earnings_call::parse(text)),
+    },
+    Parser {
+        source: "twse/insider_shareholding",
+        parse: json::<InsiderShareholding>,
+    },
+    Parser {
+        source: "twse/odd_lot",
+        parse: |text| records(twse::odd_lot::parse(fixture_date(), text)),
+    },
+    Parser {
+        source: "twse/shareholder_meeting",
+        parse: json::<ShareholderMeeting>,
+    },
+    Parser {
+        source: "twse/suspend_listing",
+        parse: |text| records(twse::suspend_listing::parse(text)),
+    },
+    Parser {
+        source: "twse/valuation",
+        parse: |text| records(twse::valuation::parse(fixture_date(), text)),
+    },
+    Parser {
+        source: "tpex/emerging_quote",
+        parse: |text| records(tpex::emerging_quote::parse(fixture_date(), text)),
+    },
+    Parser {
+        source: "tpex/odd_lot",
+        parse: |text| records(tpex::odd_lot::parse(fixture_date(), text)),
+    },
+    Parser {
+        source: "tpex/valuation",
+        parse: |text| records(tpex::valuation::parse(fixture_date(), text)),
+    },
+];
+
+/// 回應內沒有日期的解析器(零股、本益比)使用的交易日，只影響解析結果的 date 欄位
+fn fixture_date() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 1, 2).unwrap_or_default()
+}
+
+/// openapi 回應的 JSON 陣列
+fn json<T: DeserializeOwned + Debug>(text: &str) -> Result<Vec<String>> {
+    records(serde_json::from_str::<Vec<T>>(text).map_err(Into::into))
+}
+
+fn records<T: Debug>(list: Result<Vec<T>>) -> Result<Vec<String>> {
+    list.map(|list| list.iter().map(|item| format!("{:#?}", item)).collect())
+}
+
+/// 已登記的來源名稱
+pub fn sources() -> Vec<&'static str> {
+    PARSERS.iter().map(|parser| parser.source).collect()
+}
+
+/// 以指定來源的解析器解析原始回應
+pub fn parse(source: &str, text: &str) -> Result<Vec<String>> {
+    let parser = PARSERS
+        .iter()
+        .find(|parser| parser.source == source)
+        .ok_or_else(|| {
+            anyhow!(
+                "Unknown source {}, available sources: {}",
+                source,
+                sources().join(", ")
+            )
+        })?;
+
+    (parser.parse)(text).context(format!("Failed to parse fixture as {}", source))
+}
+
+/// 取得指定來源在 FIXTURES_DIR 下的所有 fixture，依檔名排序
+pub fn fixtures(source: &str) -> Result<Vec<PathBuf>> {
+    let dir = PathBuf::from(FIXTURES_DIR).join(source);
+    let mut paths = std::fs::read_dir(&dir)
+        .context(format!("Failed to read fixtures from {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    Ok(paths)
+}
+
+/// stock_crawler parse-fixture <source> <file>
+/// 以指定來源的解析器解析存檔的原始回應並輸出每筆結果，用來在沒有網路時追查解析器的問題
+pub async fn command(args: &[String]) -> Result<()> {
+    let [source, file] = args else {
+        return Err(anyhow!(USAGE));
+    };
+    let text = tokio::fs::read_to_string(file)
+        .await
+        .context(format!("Failed to read {}", file))?;
+    let records = parse(source, &text)?;
+
+    for record in &records {
+        println!("{}", record);
+    }
+    println!("{} records", records.len());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_fixtures() {
+        for source in sources() {
+            let paths = fixtures(source).unwrap();
+            assert!(!paths.is_empty(), "{} has no fixture", source);
+
+            for path in paths {
+                let text = std::fs::read_to_string(&path).unwrap();
+                match parse(source, &text) {
+                    Ok(records) => {
+                        assert!(!records.is_empty(), "{} parsed nothing", path.display())
+                    }
+                    Err(why) => panic!("Failed to parse {} because {:?}", path.display(), why),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let records = parse(
+            "twse/odd_lot",
+            r#"{"stat":"OK","fields":["證券代號","證券名稱","成交股數","成交筆數","成交金額","成交價"],"data":[["2330","台積電","1,234","56","728,060","590.00"]]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(records.len(), 1);
+        assert!(records[0].contains("\"2330\""));
+        assert!(parse("twse/odd_lot", "<html></html>").is_err());
+        assert!(parse("unknown", "[]")
+            .unwrap_err()
+            .to_string()
+            .contains("twse/odd_lot"));
+    }
+}
@@ -10,6 +10,7 @@ use urlencoding::encode;
 use crate::cache::SHARE;
 use crate::{
     crawler::goodinfo::HOST,
+    declare::StockSymbol,
     logging,
     util::{
         http::{self, element},
@@ -23,7 +24,7 @@ const UNSET_DATE: &str = "-";
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GoodInfoDividend {
     /// Security code
-    pub stock_symbol: String,
+    pub stock_symbol: StockSymbol,
     /// 盈餘現金股利 (Cash Dividend)
     pub earnings_cash: Decimal,
     /// 公積現金股利 (Capital Reserve)
@@ -64,7 +65,7 @@ pub struct GoodInfoDividend {
 }
 
 impl GoodInfoDividend {
-    pub fn new(stock_symbol: String) -> Self {
+    pub fn new(stock_symbol: StockSymbol) -> Self {
         GoodInfoDividend {
             quarter: "".to_string(),
             stock_symbol,
@@ -104,6 +105,7 @@ impl Keyable for GoodInfoDividend {
 
 /// 抓取年度股利資料
 pub async fn visit(stock_symbol: &str) -> Result<HashMap<i32, Vec<GoodInfoDividend>>> {
+    let symbol = StockSymbol::parse(stock_symbol)?;
     let url = format!(
         "https://{}/tw/StockDividendPolicy.asp?STOCK_ID={}&STEP=DATA&SHEET={}&INITIALIZED=T",
         HOST,
@@ -166,7 +168,7 @@ pub async fn visit(stock_symbol: &str) -> Result<HashMap<i32, Vec<GoodInfoDivide
             }
 
             //logging::debug_file_async(format!("tds({}):{:#?}",tds.len(), tds));
-            let mut e = GoodInfoDividend::new(stock_symbol.to_string());
+            let mut e = GoodInfoDividend::new(symbol.clone());
             //#tblDetail > tbody > tr:nth-child(5) > td:nth-child(2) > nobr > b
             let year_str = element::parse_value(&element, "td:nth-child(2) > nobr > b")?; //tds[1];
             if year_str.is_empty() {
@@ -1,4 +1,4 @@
 /// 股利
 pub mod dividend;
 
-const HOST: &str = "goodinfo.tw";
+pub(super) const HOST: &str = "goodinfo.tw";
@@ -1,6 +1,6 @@
 /// 即時報價
 pub mod price;
 
-const HOST: &str = "histock.tw";
+pub(super) const HOST: &str = "histock.tw";
 
 pub struct HiStock {}
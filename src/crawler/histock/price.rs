@@ -8,13 +8,13 @@ use crate::{
         histock::{HiStock, HOST},
         StockInfo,
     },
-    declare,
+    declare::{self, StockSymbol},
     util::{self, text},
 };
 
 #[async_trait]
 impl StockInfo for HiStock {
-    async fn get_stock_price(stock_symbol: &str) -> Result<Decimal> {
+    async fn get_stock_price(stock_symbol: &StockSymbol) -> Result<Decimal> {
         let url = format!(
             "https://{host}/stock/{symbol}",
             host = HOST,
@@ -33,7 +33,7 @@ impl StockInfo for HiStock {
         util::http::element::get_one_element_as_decimal(target)
     }
 
-    async fn get_stock_quotes(stock_symbol: &str) -> Result<declare::StockQuotes> {
+    async fn get_stock_quotes(stock_symbol: &StockSymbol) -> Result<declare::StockQuotes> {
         let url = &format!(
             "https://{host}/stock/{symbol}",
             host = HOST,
@@ -74,7 +74,7 @@ impl StockInfo for HiStock {
         }
 
         Ok(declare::StockQuotes {
-            stock_symbol: stock_symbol.to_string(),
+            stock_symbol: stock_symbol.clone(),
             price,
             change,
             change_range,
@@ -92,7 +92,7 @@ mod tests {
         dotenv::dotenv().ok();
         logging::debug_file_async("開始 get_stock_price".to_string());
 
-        match HiStock::get_stock_price("3008").await {
+        match HiStock::get_stock_price(&"3008".parse().unwrap()).await {
             Ok(e) => {
                 dbg!(&e);
                 logging::debug_file_async(format!("price : {:#?}", e));
@@ -110,7 +110,7 @@ mod tests {
         dotenv::dotenv().ok();
         logging::debug_file_async("開始 get_stock_quotes".to_string());
 
-        match HiStock::get_stock_quotes("2888").await {
+        match HiStock::get_stock_quotes(&"2888".parse().unwrap()).await {
             Ok(e) => {
                 dbg!(&e);
                 logging::debug_file_async(format!("get_stock_quotes : {:#?}", e));
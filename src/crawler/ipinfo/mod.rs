@@ -6,7 +6,7 @@ use crate::util;
 
 static DDNS_URL: OnceLock<String> = OnceLock::new();
 
-const HOST: &str = "ipinfo.io";
+pub(super) const HOST: &str = "ipinfo.io";
 
 /// 取得目前的IP
 pub async fn visit() -> Result<String> {
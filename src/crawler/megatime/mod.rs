@@ -1,6 +1,6 @@
 /// 即時報價
 pub mod price;
 
-const HOST: &str = "pchome.megatime.com.tw";
+pub(super) const HOST: &str = "pchome.megatime.com.tw";
 
 pub struct PcHome {}
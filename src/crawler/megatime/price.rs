@@ -15,7 +15,7 @@ use crate::{
         megatime::{PcHome, HOST},
         StockInfo,
     },
-    declare,
+    declare::{self, StockSymbol},
 };
 
 //#stock_info_data_a > span.data_close
@@ -23,7 +23,7 @@ const SELECTOR: &str = r"#stock_info_data_a";
 
 #[async_trait]
 impl StockInfo for PcHome {
-    async fn get_stock_price(stock_symbol: &str) -> Result<Decimal> {
+    async fn get_stock_price(stock_symbol: &StockSymbol) -> Result<Decimal> {
         let url = format!(
             "https://{host}/stock/sid{symbol}.html",
             host = HOST,
@@ -46,7 +46,7 @@ impl StockInfo for PcHome {
         Err(anyhow!("Price element not found from pchome"))
     }
 
-    async fn get_stock_quotes(stock_symbol: &str) -> Result<declare::StockQuotes> {
+    async fn get_stock_quotes(stock_symbol: &StockSymbol) -> Result<declare::StockQuotes> {
         let url = &format!(
             "https://{host}/stock/sid{symbol}.html",
             host = HOST,
@@ -87,7 +87,7 @@ impl StockInfo for PcHome {
         let change_range = text::parse_f64(&change_range, None)?;
 
         Ok(declare::StockQuotes {
-            stock_symbol: stock_symbol.to_string(),
+            stock_symbol: stock_symbol.clone(),
             price,
             change,
             change_range,
@@ -105,7 +105,7 @@ mod tests {
         dotenv::dotenv().ok();
         logging::debug_file_async("開始 get_stock_price".to_string());
 
-        match PcHome::get_stock_price("2330").await {
+        match PcHome::get_stock_price(&"2330".parse().unwrap()).await {
             Ok(e) => {
                 dbg!(&e);
                 logging::debug_file_async(format!("price : {:#?}", e));
@@ -123,7 +123,7 @@ mod tests {
         dotenv::dotenv().ok();
         logging::debug_file_async("開始 get_stock_quotes".to_string());
 
-        match PcHome::get_stock_quotes("2330").await {
+        match PcHome::get_stock_quotes(&"2330".parse().unwrap()).await {
             Ok(e) => {
                 dbg!(&e);
                 logging::debug_file_async(format!("get_stock_quotes : {:#?}", e));
@@ -9,7 +9,7 @@ use crate::{
         cmoney::CMoney, megatime::PcHome, nstock::NStock,
         yahoo::Yahoo,
     },
-    declare,
+    declare::{self, StockSymbol},
 };
 use crate::crawler::cnyes::CnYes;
 
@@ -17,13 +17,19 @@ pub mod afraid;
 /// 臺灣銀行
 pub mod bank_of_taiwan;
 pub mod bigdatacloud;
+/// Cloudflare DNS 記錄
+pub mod cloudflare;
 /// 理財寶-股市爆料同學會
 pub mod cmoney;
 /// 鉅亨網
 pub mod cnyes;
+/// DuckDNS
+pub mod duckdns;
 pub mod dynu;
 /// 富邦證券
 pub mod fbs;
+/// 以存檔的原始回應重播各爬蟲的解析
+pub mod fixture;
 /// 股市資訊網
 pub mod goodinfo;
 /// 嗨投資
@@ -58,8 +64,8 @@ pub mod localhost;
 
 #[async_trait]
 pub trait StockInfo {
-    async fn get_stock_price(stock_symbol: &str) -> Result<Decimal>;
-    async fn get_stock_quotes(stock_symbol: &str) -> Result<declare::StockQuotes>;
+    async fn get_stock_price(stock_symbol: &StockSymbol) -> Result<Decimal>;
+    async fn get_stock_quotes(stock_symbol: &StockSymbol) -> Result<declare::StockQuotes>;
 }
 
 /// 標記採集站點的遊標，每採集一次遊標就會+1，分別對應6個站點，每個站點都輪過一次時就會歸零從頭開始
@@ -80,8 +86,48 @@ fn get_and_increment_index(max: usize) -> usize {
     }).unwrap_or(0)
 }
 
+/// 各爬蟲來源的名稱與網域，名稱對應設定檔 crawler.<名稱> 的請求逾時與重試
+const SOURCES: [(&str, &str); 25] = [
+    ("afraid", afraid::HOST),
+    ("bank_of_taiwan", bank_of_taiwan::HOST),
+    ("bigdatacloud", bigdatacloud::HOST),
+    ("cloudflare", cloudflare::HOST),
+    ("cmoney", cmoney::HOST),
+    ("cnyes", cnyes::HOST),
+    ("duckdns", duckdns::HOST),
+    ("dynu", dynu::HOST),
+    ("fbs", fbs::HOST),
+    ("goodinfo", goodinfo::HOST),
+    ("histock", histock::HOST),
+    ("ipify", ipify::HOST),
+    ("ipinfo", ipinfo::HOST),
+    ("megatime", megatime::HOST),
+    ("moneydj", moneydj::HOST),
+    ("myip", myip::HOST),
+    ("noip", noip::HOST),
+    ("nstock", nstock::HOST),
+    ("seeip", seeip::HOST),
+    ("taifex", taifex::HOST),
+    ("tpex", tpex::HOST),
+    ("twse", twse::HOST),
+    ("wespai", wespai::HOST),
+    ("yahoo", yahoo::HOST),
+    ("yuanta", yuanta::HOST),
+];
+
+/// 依網址的網域取得爬蟲來源的名稱 ex. https://www.twse.com.tw/... => twse
+pub fn source_of(url: &str) -> Option<&'static str> {
+    let url = reqwest::Url::parse(url).ok()?;
+    let host = url.host_str()?;
+
+    SOURCES
+        .iter()
+        .find(|(_, domain)| host == *domain || host.ends_with(&format!(".{}", domain)))
+        .map(|(name, _)| *name)
+}
+
 /// 取得股票的目前的報價
-pub async fn fetch_stock_price_from_remote_site(stock_symbol: &str) -> Result<Decimal> {
+pub async fn fetch_stock_price_from_remote_site(stock_symbol: &StockSymbol) -> Result<Decimal> {
     let sites = [
         Yahoo::get_stock_price,
         NStock::get_stock_price,
@@ -110,7 +156,7 @@ pub async fn fetch_stock_price_from_remote_site(stock_symbol: &str) -> Result<De
 
 /// 取得股票目前的報價含漲跌、漲幅
 pub async fn fetch_stock_quotes_from_remote_site(
-    stock_symbol: &str,
+    stock_symbol: &StockSymbol,
 ) -> Result<declare::StockQuotes> {
     let sites = [
         Yahoo::get_stock_quotes,
@@ -154,7 +200,7 @@ mod tests {
         ];
 
         for site in sites {
-            match fetch_stock_price_from_remote_site(site).await {
+            match fetch_stock_price_from_remote_site(&site.parse().unwrap()).await {
                 Ok(e) => {
                     //dbg!(e);
                     println!("{}:{}", site, e);
@@ -178,7 +224,7 @@ mod tests {
         ];
 
         for site in sites {
-            match fetch_stock_quotes_from_remote_site(site).await {
+            match fetch_stock_quotes_from_remote_site(&site.parse().unwrap()).await {
                 Ok(e) => {
                     //dbg!(e);
                     println!("{}:{:?}", site, e);
@@ -191,4 +237,23 @@ mod tests {
 
         logging::debug_file_async("結束 fetch_stock_quotes_from_remote_site".to_string());
     }
+
+    #[test]
+    fn test_source_of() {
+        assert_eq!(
+            source_of("https://goodinfo.tw/tw/StockDividendPolicy.asp"),
+            Some("goodinfo")
+        );
+        assert_eq!(
+            source_of("https://mops.twse.com.tw/mops/web/t163sb19"),
+            Some("twse")
+        );
+        assert_eq!(
+            source_of("https://tw.stock.yahoo.com/quote/2330/dividend"),
+            Some("yahoo")
+        );
+        assert_eq!(source_of("https://histock.tw/stock/2330"), Some("histock"));
+        assert_eq!(source_of("https://example.com"), None);
+        assert_eq!(source_of("not a url"), None);
+    }
 }
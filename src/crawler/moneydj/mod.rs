@@ -1,3 +1,3 @@
 pub mod annual_profit;
 
-const HOST: &str = "justdata.moneydj.com";
+pub(super) const HOST: &str = "justdata.moneydj.com";
@@ -8,7 +8,7 @@ use crate::util;
 
 static DDNS_URL: OnceLock<String> = OnceLock::new();
 
-const HOST: &str = "api.myip.com";
+pub(super) const HOST: &str = "api.myip.com";
 
 #[derive(Serialize, Deserialize)]
 struct MyIpResponse {
@@ -1,34 +1,28 @@
-
 use anyhow::{anyhow, Result};
 
-use crate::{config, logging, util};
-
-const HOST: &str = "dynupdate.no-ip.com";
-
-/// 向ddns服務更新目前的IP
-pub async fn visit(ip :&str) -> Result<()> {
-    for hostname in &config::SETTINGS.noip.hostnames {
-        let url =
-            &format!(
-                "https://{acount}:{pw}@{host}/nic/update?hostname={hostname}&myip={ip}",
-                acount = config::SETTINGS.noip.username,
-                pw = config::SETTINGS.noip.password,
-                host = HOST,
-                ip = ip,
-                hostname = hostname
-            );
-
-        match util::http::get(url, None).await {
-            Ok(t) => {
-                if t.contains("good") {
-                    logging::info_file_async(t);
-                }
-
-            }
-            Err(why) => {
-                return Err(anyhow!("Failed to noip.visit because {:?}", why));
-            }
-        }
+use crate::{crawler::dynu, logging, util};
+
+pub(super) const HOST: &str = "dynupdate.no-ip.com";
+
+/// 以帳號密碼向 no-ip 更新主機目前的IP
+pub async fn visit(username: &str, password: &str, hostname: &str, ip: &str) -> Result<()> {
+    let url = format!(
+        "https://{acount}:{pw}@{host}/nic/update?hostname={hostname}&myip={ip}",
+        acount = username,
+        pw = password,
+        host = HOST,
+        ip = ip,
+        hostname = hostname
+    );
+
+    let text = util::http::get(&url, None)
+        .await
+        .map_err(|why| anyhow!("Failed to noip.visit because {:?}", why))?;
+
+    // no-ip 與 dynu 同樣使用 dyndns2 協定的回應
+    dynu::check(&text)?;
+    if text.contains("good") {
+        logging::info_file_async(text);
     }
 
     Ok(())
@@ -36,7 +30,8 @@ pub async fn visit(ip :&str) -> Result<()> {
 
 #[cfg(test)]
 mod tests {
-    use crate::crawler::ipify;
+    use crate::{config, crawler::ipify};
+
     use super::*;
 
     #[tokio::test]
@@ -44,12 +39,21 @@ mod tests {
         dotenv::dotenv().ok();
         logging::debug_file_async("開始 visit".to_string());
         let ip_now = ipify::visit().await.unwrap();
-        match visit(&ip_now).await {
-            Ok(e) => {
-                dbg!(e);
-            }
-            Err(why) => {
-                logging::debug_file_async(format!("Failed to visit because {:?}", why));
+        for hostname in &config::SETTINGS.load().noip.hostnames {
+            match visit(
+                &config::SETTINGS.load().noip.username,
+                &config::SETTINGS.load().noip.password,
+                hostname,
+                &ip_now,
+            )
+            .await
+            {
+                Ok(e) => {
+                    dbg!(e);
+                }
+                Err(why) => {
+                    logging::debug_file_async(format!("Failed to visit because {:?}", why));
+                }
             }
         }
 
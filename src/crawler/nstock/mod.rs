@@ -1,6 +1,6 @@
 pub mod eps;
 pub mod price;
 
-const HOST: &str = "www.nstock.tw";
+pub(super) const HOST: &str = "www.nstock.tw";
 
 pub struct NStock {}
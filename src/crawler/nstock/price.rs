@@ -8,7 +8,7 @@ use crate::{
         nstock::{NStock, HOST},
         StockInfo,
     },
-    declare::StockQuotes,
+    declare::{StockQuotes, StockSymbol},
     util::{self, text},
 };
 
@@ -57,19 +57,19 @@ async fn fetch_data(stock_symbol: &str) -> Result<RealTimeQuotesResponse> {
 
 #[async_trait]
 impl StockInfo for NStock {
-    async fn get_stock_price(stock_symbol: &str) -> Result<Decimal> {
+    async fn get_stock_price(stock_symbol: &StockSymbol) -> Result<Decimal> {
         let r = fetch_data(stock_symbol).await?;
         text::parse_decimal(&r.current_price, None)
     }
 
-    async fn get_stock_quotes(stock_symbol: &str) -> Result<StockQuotes> {
+    async fn get_stock_quotes(stock_symbol: &StockSymbol) -> Result<StockQuotes> {
         let r = fetch_data(stock_symbol).await?;
 
         let price = text::parse_f64(&r.current_price, None)?;
         let change = text::parse_f64(&r.change, None)?;
         let change_range = text::parse_f64(&r.change_range, None)?;
         Ok(StockQuotes {
-            stock_symbol: stock_symbol.to_string(),
+            stock_symbol: stock_symbol.clone(),
             price,
             change,
             change_range,
@@ -89,7 +89,7 @@ mod tests {
         logging::debug_file_async("開始 get_stock_price".to_string());
 
         // match get("2330").await {
-        match NStock::get_stock_price("2330").await {
+        match NStock::get_stock_price(&"2330".parse().unwrap()).await {
             Ok(e) => {
                 dbg!(&e);
                 logging::debug_file_async(format!("price : {:#?}", e));
@@ -107,7 +107,7 @@ mod tests {
         dotenv::dotenv().ok();
         logging::debug_file_async("開始 get_stock_quotes".to_string());
 
-        match NStock::get_stock_quotes("2330").await {
+        match NStock::get_stock_quotes(&"2330".parse().unwrap()).await {
             Ok(e) => {
                 dbg!(&e);
                 logging::debug_file_async(format!("get_stock_quotes : {:#?}", e));
@@ -6,7 +6,7 @@ use crate::util;
 
 static DDNS_URL: OnceLock<String> = OnceLock::new();
 
-const HOST: &str = "ipv4.seeip.org";
+pub(super) const HOST: &str = "ipv4.seeip.org";
 
 /// 取得目前的IP
 pub async fn visit() -> Result<String> {
@@ -9,6 +9,7 @@ use scraper::{ElementRef, Html, Selector};
 use crate::crawler::{bigdatacloud, myip};
 use crate::{
     crawler::{ipify, ipinfo, seeip},
+    declare::StockSymbol,
     util::{self, map::Keyable, text},
 };
 
@@ -16,7 +17,7 @@ use crate::{
 #[derive(Debug, Clone, PartialEq)]
 pub struct AnnualProfit {
     /// Security code
-    pub stock_symbol: String,
+    pub stock_symbol: StockSymbol,
     /// 財報年度 (Year)
     pub year: i32,
     /// 每股營收
@@ -28,7 +29,7 @@ pub struct AnnualProfit {
 }
 
 impl AnnualProfit {
-    pub fn new(stock_symbol: String) -> Self {
+    pub fn new(stock_symbol: StockSymbol) -> Self {
         Self {
             stock_symbol,
             year: 0,
@@ -58,6 +59,7 @@ pub(super) async fn fetch_annual_profits(
     url: &str,
     stock_symbol: &str,
 ) -> Result<Vec<AnnualProfit>> {
+    let stock_symbol = StockSymbol::parse(stock_symbol)?;
     let text = util::http::get(url, None).await?;
     let document = Html::parse_document(&text);
     let selector = Selector::parse("#oMainTable > tbody > tr:nth-child(n+4)")
@@ -65,7 +67,7 @@ pub(super) async fn fetch_annual_profits(
     let mut result: Vec<AnnualProfit> = Vec::with_capacity(24);
 
     for node in document.select(&selector) {
-        if let Some(ap) = parse_annual_profit(node, stock_symbol) {
+        if let Some(ap) = parse_annual_profit(node, &stock_symbol) {
             result.push(ap);
         }
     }
@@ -73,7 +75,7 @@ pub(super) async fn fetch_annual_profits(
     Ok(result)
 }
 
-fn parse_annual_profit(node: ElementRef, stock_symbol: &str) -> Option<AnnualProfit> {
+fn parse_annual_profit(node: ElementRef, stock_symbol: &StockSymbol) -> Option<AnnualProfit> {
     let tds: Vec<&str> = node.text().map(str::trim).collect();
 
     if tds.len() < 8 {
@@ -88,7 +90,7 @@ fn parse_annual_profit(node: ElementRef, stock_symbol: &str) -> Option<AnnualPro
     let sales_per_share = text::parse_decimal(tds.get(5)?, None).unwrap_or(Decimal::ZERO);
 
     Some(AnnualProfit {
-        stock_symbol: stock_symbol.to_string(),
+        stock_symbol: stock_symbol.clone(),
         earnings_per_share,
         profit_before_tax,
         sales_per_share,
@@ -1,3 +1,3 @@
 pub mod stock_weight;
 
-const HOST: &str = "www.taifex.com.tw";
+pub(super) const HOST: &str = "www.taifex.com.tw";
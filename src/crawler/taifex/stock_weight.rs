@@ -10,14 +10,14 @@ use crate::{
         taifex,
         taifex::HOST
     },
-    declare::StockExchange,
+    declare::{StockExchange, StockSymbol},
     util::{self, http::element},
 };
 
 #[derive(Default, Debug, Clone, PartialEq)]
 pub struct StockWeight {
     pub rank: i32,
-    pub stock_symbol: String,
+    pub stock_symbol: StockSymbol,
     pub weight: Decimal,
 }
 
@@ -123,10 +123,11 @@ fn get_stock_weight(
     symbol_selector: &str,
     weight_selector: &str,
 ) -> Option<StockWeight> {
-    let stock_symbol = element::parse_to_string(element, symbol_selector);
+    let stock_symbol =
+        StockSymbol::parse(&element::parse_to_string(element, symbol_selector)).ok()?;
     let weight = element::parse_to_decimal(element, weight_selector);
 
-    if !stock_symbol.is_empty() && !weight.is_zero() {
+    if !weight.is_zero() {
         let sw = StockWeight {
             rank: element::parse_to_i32(element, rank_selector),
             stock_symbol,
@@ -0,0 +1,178 @@
+use anyhow::Result;
+use chrono::{Datelike, Local, NaiveDate, TimeZone};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Deserialize;
+
+use crate::{
+    crawler::tpex,
+    database::table::daily_quote::DailyQuote,
+    declare::StockSymbol,
+    logging,
+    util::{self, http},
+};
+
+#[derive(Deserialize, Debug)]
+struct EmergingResponse {
+    pub tables: Vec<Table>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Table {
+    pub fields: Option<Vec<String>>,
+    pub data: Option<Vec<Vec<String>>>,
+}
+
+/// 抓取興櫃股票的每日行情
+pub async fn visit(date: NaiveDate) -> Result<Vec<DailyQuote>> {
+    let url = format!(
+        "https://{}/web/emergingstock/historical/daily/EMDaily_result.php?l=zh-tw&o=json&d={}{}&_={}",
+        tpex::HOST,
+        util::datetime::gregorian_year_to_roc_year(date.year()),
+        date.format("/%m/%d"),
+        date
+    );
+
+    let json = http::get(&url, None).await?;
+
+    parse(date, &json)
+}
+
+/// 解析興櫃股票每日行情的回應，只取第一個表格。
+/// 興櫃採議價交易沒有開盤價與收盤價，以日均價作為收盤價(與開盤價)，漲跌以前日均價計算，
+/// 當日沒有成交(日均價為 0 或 ---)的股票不列入
+pub(crate) fn parse(date: NaiveDate, json: &str) -> Result<Vec<DailyQuote>> {
+    let res = serde_json::from_str::<EmergingResponse>(json)?;
+    let Some(table) = res.tables.into_iter().next() else {
+        return Ok(Vec::new());
+    };
+    let (Some(fields), Some(data)) = (table.fields, table.data) else {
+        return Ok(Vec::new());
+    };
+
+    let column = |keywords: &[&str]| {
+        fields
+            .iter()
+            .position(|f| keywords.iter().any(|keyword| f.trim() == *keyword))
+    };
+    let (Some(code), Some(average)) = (column(&["代號"]), column(&["日均價", "均價"]))
+    else {
+        return Ok(Vec::new());
+    };
+    let previous_average = column(&["前日均價"]);
+    let highest = column(&["日最高", "最高"]);
+    let lowest = column(&["日最低", "最低"]);
+    let volume = column(&["成交量", "成交股數"]);
+    let value = column(&["成交金額"]);
+    let transaction = column(&["成交筆數", "筆數"]);
+    let bid = column(&["報買價", "最後最佳報買價"]);
+    let ask = column(&["報賣價", "最後最佳報賣價"]);
+
+    let parse = |row: &Vec<String>, index: Option<usize>| {
+        index
+            .and_then(|index| row.get(index))
+            .and_then(|v| util::text::parse_decimal(v.trim(), Some(vec![','])).ok())
+            .unwrap_or_default()
+    };
+
+    let record_time = date
+        .and_hms_opt(15, 0, 0)
+        .and_then(|naive| Local.from_local_datetime(&naive).single())
+        .unwrap_or_else(|| {
+            logging::warn_file_async(
+                "Failed to create DateTime<Local> from NaiveDateTime, using current time as default."
+                    .to_string(),
+            );
+            Local::now()
+        });
+
+    let quotes = data
+        .iter()
+        .filter_map(|row| {
+            let security_code = StockSymbol::parse(row.get(code)?).ok()?;
+            let average_price = parse(row, Some(average));
+            if average_price <= Decimal::ZERO {
+                return None;
+            }
+
+            let mut dq = DailyQuote::new(security_code);
+            dq.date = date;
+            dq.year = date.year();
+            dq.month = date.month() as i32;
+            dq.day = date.day() as i32;
+            dq.opening_price = average_price;
+            dq.closing_price = average_price;
+            dq.highest_price = parse(row, highest).max(average_price);
+            dq.lowest_price = match parse(row, lowest) {
+                lowest if lowest > Decimal::ZERO => lowest.min(average_price),
+                _ => average_price,
+            };
+            dq.trading_volume = parse(row, volume);
+            dq.trade_value = parse(row, value);
+            dq.transaction = parse(row, transaction);
+            dq.last_best_bid_price = parse(row, bid);
+            dq.last_best_ask_price = parse(row, ask);
+
+            let previous_average_price = parse(row, previous_average);
+            if previous_average_price > Decimal::ZERO {
+                dq.change = average_price - previous_average_price;
+                dq.change_range = dq.change / previous_average_price * dec!(100);
+            }
+
+            dq.record_time = record_time;
+            dq.create_time = Local::now();
+
+            Some(dq)
+        })
+        .collect();
+
+    Ok(quotes)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logging;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let json = r#"{"tables":[{"fields":["代號","名稱","前日均價","報買價","報買量","報賣價","報賣量","日最高","日最低","日均價","成交","買賣","漲跌","成交量","成交金額","成交筆數"],
+"data":[["6610","台驊","1,020.50","1,010.00","1,000","1,030.00","2,000","1,050.00","990.00","1,030.75","1,030.00","買","+9.50","12,000","12,369,000","15"],
+["7566","測試","25.00","---","0","---","0","---","---","---","---","","","0","0","0"]]}]}"#;
+        let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+
+        let quotes = parse(date, json).unwrap();
+
+        assert_eq!(quotes.len(), 1);
+        let dq = &quotes[0];
+        assert_eq!(dq.security_code, "6610");
+        assert_eq!(dq.closing_price, dec!(1030.75));
+        assert_eq!(dq.opening_price, dec!(1030.75));
+        assert_eq!(dq.highest_price, dec!(1050));
+        assert_eq!(dq.lowest_price, dec!(990));
+        assert_eq!(dq.change, dec!(10.25));
+        assert_eq!(dq.trading_volume, dec!(12000));
+        assert_eq!(dq.transaction, dec!(15));
+        assert_eq!(dq.date, date);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_visit() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 visit".to_string());
+
+        match visit(Local::now().date_naive()).await {
+            Ok(list) => {
+                logging::debug_file_async(format!("data({}):{:#?}", list.len(), list));
+            }
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to visit because {:?}", why));
+            }
+        }
+
+        logging::debug_file_async("結束 visit".to_string());
+    }
+}
@@ -1,6 +1,12 @@
+/// 興櫃股票每日行情
+pub mod emerging_quote;
 /// 興櫃每股淨值
 pub mod net_asset_value_per_share;
+/// 盤後零股交易行情-上櫃
+pub mod odd_lot;
 /// 台股收盤報價-上櫃
 pub(crate) mod quote;
+/// 個股日本益比、殖利率及股價淨值比-上櫃
+pub mod valuation;
 
-const HOST: &str = "www.tpex.org.tw";
+pub(super) const HOST: &str = "www.tpex.org.tw";
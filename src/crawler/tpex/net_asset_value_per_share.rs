@@ -4,17 +4,17 @@ use anyhow::Result;
 use rust_decimal::Decimal;
 use scraper::{Html, Selector};
 
-use crate::{crawler::tpex, util};
+use crate::{crawler::tpex, declare::StockSymbol, util};
 
 #[derive(Default, Debug, Clone, PartialEq)]
 //#[serde(rename_all = "camelCase")]
 pub struct Emerging {
-    pub stock_symbol: String,
+    pub stock_symbol: StockSymbol,
     pub net_asset_value_per_share: Decimal,
 }
 
 impl Emerging {
-    pub fn new(stock_symbol: String, net_asset_value_per_share: Decimal) -> Self {
+    pub fn new(stock_symbol: StockSymbol, net_asset_value_per_share: Decimal) -> Self {
         Emerging {
             stock_symbol,
             net_asset_value_per_share,
@@ -45,8 +45,12 @@ pub async fn visit() -> Result<Vec<Emerging>> {
                 continue;
             }
 
+            let stock_symbol = match StockSymbol::parse(tds[1]) {
+                Ok(stock_symbol) => stock_symbol,
+                Err(_) => continue,
+            };
             let e = Emerging::new(
-                tds[1].to_string(),
+                stock_symbol,
                 Decimal::from_str(tds[5]).unwrap_or_default(),
             );
             result.push(e);
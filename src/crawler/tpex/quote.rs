@@ -84,7 +84,10 @@ pub async fn visit(date: NaiveDate) -> Result<Vec<table::daily_quote::DailyQuote
     if !quote_response.tables.is_empty() {
         if let Some(tpex_dqs) = &quote_response.tables[0].data {
             for item in tpex_dqs {
-                let mut dq = table::daily_quote::DailyQuote::from_with_exchange(StockExchange::TPEx, &item);
+                // 代號不是股票代號格式的列略過
+                let Ok(mut dq) = table::daily_quote::DailyQuote::from_with_exchange(StockExchange::TPEx, &item) else {
+                    continue;
+                };
                 logging::debug_file_async(format!("item:{:?}", item));
 
                 if dq.closing_price.is_zero()
@@ -116,7 +119,9 @@ pub async fn visit(date: NaiveDate) -> Result<Vec<table::daily_quote::DailyQuote
                     }
                 }
 
-                if let Some(pe_ratio_analysis_response) = pe_ratio_analysis.get(&dq.security_code) {
+                if let Some(pe_ratio_analysis_response) =
+                    pe_ratio_analysis.get(dq.security_code.as_str())
+                {
                     dq.price_earning_ratio = pe_ratio_analysis_response
                         .price_earning_ratio
                         .parse::<Decimal>()
@@ -0,0 +1,80 @@
+use anyhow::Result;
+use chrono::{Datelike, NaiveDate};
+use serde::Deserialize;
+
+use crate::{
+    crawler::tpex, database::table::daily_valuation::DailyValuation, declare::StockExchange,
+    util::{self, http},
+};
+
+#[derive(Deserialize, Debug)]
+struct ValuationResponse {
+    pub tables: Vec<Table>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Table {
+    pub fields: Option<Vec<String>>,
+    pub data: Option<Vec<Vec<String>>>,
+}
+
+/// 抓取上櫃個股日本益比、殖利率及股價淨值比
+pub async fn visit(date: NaiveDate) -> Result<Vec<DailyValuation>> {
+    let url = format!(
+        "https://{}/web/stock/aftertrading/peratio_analysis/pera_result.php?l=zh-tw&o=json&d={}{}&_={}",
+        tpex::HOST,
+        util::datetime::gregorian_year_to_roc_year(date.year()),
+        date.format("/%m/%d"),
+        date
+    );
+
+    let json = http::get(&url, None).await?;
+
+    parse(date, &json)
+}
+
+/// 解析上櫃個股日本益比、殖利率及股價淨值比的回應，只取第一個表格
+pub(crate) fn parse(date: NaiveDate, json: &str) -> Result<Vec<DailyValuation>> {
+    let res = serde_json::from_str::<ValuationResponse>(json)?;
+    let Some(table) = res.tables.into_iter().next() else {
+        return Ok(Vec::new());
+    };
+
+    match (table.fields, table.data) {
+        (Some(fields), Some(data)) => Ok(DailyValuation::from_table(
+            date,
+            StockExchange::TPEx,
+            &fields,
+            &data,
+        )),
+        _ => Ok(Vec::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+
+    use crate::logging;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_visit() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 visit".to_string());
+
+        match visit(Local::now().date_naive()).await {
+            Ok(list) => {
+                logging::debug_file_async(format!("data({}):{:#?}", list.len(), list));
+            }
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to visit because {:?}", why));
+            }
+        }
+
+        logging::debug_file_async("結束 visit".to_string());
+    }
+}
@@ -0,0 +1,71 @@
+use anyhow::Result;
+use serde::Deserialize;
+
+use crate::{crawler::twse, util};
+
+/// 調用 twse openapi t187ap04_L(上市公司每日重大訊息) 後其回應的數據
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct Announcement {
+    /// 民國年格式的發言日期 ex. 1130102
+    #[serde(rename(deserialize = "發言日期"))]
+    pub announcement_date: String,
+    /// 發言時間 ex. 143015
+    #[serde(rename(deserialize = "發言時間"))]
+    pub announcement_time: String,
+    #[serde(rename(deserialize = "公司代號"))]
+    pub stock_symbol: String,
+    #[serde(rename(deserialize = "公司名稱"))]
+    pub name: String,
+    #[serde(rename(deserialize = "主旨 "), alias = "主旨")]
+    pub subject: String,
+    #[serde(rename(deserialize = "符合條款"), default)]
+    pub clause: String,
+    /// 民國年格式的事實發生日
+    #[serde(rename(deserialize = "事實發生日"), default)]
+    pub occurred_date: String,
+    #[serde(rename(deserialize = "說明"), default)]
+    pub description: String,
+}
+
+/// 取得上市公司當日的重大訊息
+pub async fn visit() -> Result<Vec<Announcement>> {
+    let url = format!("https://openapi.{}/v1/opendata/t187ap04_L", twse::HOST);
+
+    util::http::get_json::<Vec<Announcement>>(&url).await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logging;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_deserialize() {
+        let json = r#"[{"出表日期":"1130102","發言日期":"1130102","發言時間":"173012","公司代號":"1101","公司名稱":"台泥","主旨 ":"公告本公司董事會決議辦理減資","符合條款":"第11款","事實發生日":"1130102","說明":"1.董事會決議日期:113/01/02"}]"#;
+        let list: Vec<Announcement> = serde_json::from_str(json).unwrap();
+
+        assert_eq!(list[0].stock_symbol, "1101");
+        assert_eq!(list[0].subject, "公告本公司董事會決議辦理減資");
+        assert_eq!(list[0].announcement_time, "173012");
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_visit() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 visit".to_string());
+
+        match visit().await {
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to visit because: {:?}", why));
+            }
+            Ok(list) => {
+                logging::debug_file_async(format!("data:{:#?}", list));
+            }
+        }
+
+        logging::debug_file_async("結束 visit".to_string());
+    }
+}
@@ -0,0 +1,83 @@
+use anyhow::Result;
+use serde::Deserialize;
+
+use crate::{crawler::twse, util};
+
+/// 調用 twse openapi t187ap13_L(上市公司買回本公司股份) 後其回應的數據
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct Buyback {
+    #[serde(rename(deserialize = "公司代號"))]
+    pub stock_symbol: String,
+    #[serde(rename(deserialize = "公司名稱"), default)]
+    pub name: String,
+    /// 民國年格式的董事會決議日期 ex. 1130102
+    #[serde(rename(deserialize = "董事會決議日期"))]
+    pub resolution_date: String,
+    #[serde(rename(deserialize = "買回目的"), default)]
+    pub purpose: String,
+    /// 預定買回股數(股)
+    #[serde(rename(deserialize = "預定買回股數"), default)]
+    pub target_shares: String,
+    /// 買回價格區間-最低(元)
+    #[serde(rename(deserialize = "買回價格區間-最低"), default)]
+    pub price_low: String,
+    /// 買回價格區間-最高(元)
+    #[serde(rename(deserialize = "買回價格區間-最高"), default)]
+    pub price_high: String,
+    /// 民國年格式的預定買回期間-起
+    #[serde(rename(deserialize = "預定買回期間-起"), default)]
+    pub start_date: String,
+    /// 民國年格式的預定買回期間-迄
+    #[serde(rename(deserialize = "預定買回期間-迄"), default)]
+    pub end_date: String,
+    /// 已買回股數(股)
+    #[serde(rename(deserialize = "本次已買回股數"), default)]
+    pub executed_shares: String,
+    /// 是否執行完畢 ex. Y、N
+    #[serde(rename(deserialize = "是否執行完畢"), default)]
+    pub completed: String,
+}
+
+/// 取得上市公司申報的庫藏股買回計畫與執行情形
+pub async fn visit() -> Result<Vec<Buyback>> {
+    let url = format!("https://openapi.{}/v1/opendata/t187ap13_L", twse::HOST);
+
+    util::http::get_json::<Vec<Buyback>>(&url).await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logging;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_deserialize() {
+        let json = r#"[{"出表日期":"1130301","公司代號":"2330","公司名稱":"台積電","董事會決議日期":"1130213","買回目的":"轉讓股份予員工","預定買回股數":"2,000,000","買回價格區間-最低":"500","買回價格區間-最高":"800","預定買回期間-起":"1130214","預定買回期間-迄":"1130413","本次已買回股數":"1,500,000","是否執行完畢":"N"}]"#;
+        let list: Vec<Buyback> = serde_json::from_str(json).unwrap();
+
+        assert_eq!(list[0].stock_symbol, "2330");
+        assert_eq!(list[0].resolution_date, "1130213");
+        assert_eq!(list[0].target_shares, "2,000,000");
+        assert_eq!(list[0].completed, "N");
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_visit() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 visit".to_string());
+
+        match visit().await {
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to visit because: {:?}", why));
+            }
+            Ok(list) => {
+                logging::debug_file_async(format!("data:{:#?}", list));
+            }
+        }
+
+        logging::debug_file_async("結束 visit".to_string());
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use rust_decimal::Decimal;
+use scraper::{Html, Selector};
+
+use crate::{
+    crawler::twse,
+    declare::{Quarter, StatementType, StockExchangeMarket, StockSymbol},
+    util::{self, datetime, text},
+};
+
+/// 公開資訊觀測站現金流量表彙總表的一筆數據，金額為當年度累計至該季的仟元
+#[derive(Debug, Clone, PartialEq)]
+pub struct CashFlow {
+    pub stock_symbol: StockSymbol,
+    /// 年度
+    pub year: i32,
+    /// 季度 Q4 Q3 Q2 Q1
+    pub quarter: Quarter,
+    /// 營業活動之淨現金流入(流出)
+    pub operating_cash_flow: Decimal,
+    /// 投資活動之淨現金流入(流出)
+    pub investing_cash_flow: Decimal,
+    /// 籌資活動之淨現金流入(流出)
+    pub financing_cash_flow: Decimal,
+    /// 財報類別，依表格的標題判斷
+    pub statement_type: StatementType,
+}
+
+/// 取得指定市場(上市、上櫃)某年某季的現金流量表彙總
+pub async fn visit(
+    market: StockExchangeMarket,
+    year: i32,
+    quarter: Quarter,
+) -> Result<Vec<CashFlow>> {
+    let url = format!("https://mops.{}/mops/web/ajax_t163sb20", twse::HOST);
+    let typek = match market {
+        StockExchangeMarket::OverTheCounter => "otc",
+        _ => "sii",
+    };
+    let roc_year = datetime::gregorian_year_to_roc_year(year).to_string();
+    let season = format!("0{}", quarter.serial());
+    let mut params = HashMap::with_capacity(7);
+    params.insert("encodeURIComponent", "1");
+    params.insert("step", "1");
+    params.insert("firstin", "1");
+    params.insert("off", "1");
+    params.insert("TYPEK", typek);
+    params.insert("year", &roc_year);
+    params.insert("season", &season);
+
+    let response = util::http::post(&url, None, Some(params)).await?;
+
+    parse(year, quarter, &response)
+}
+
+/// 解析現金流量表彙總表，各產業的表格欄位不同，所以依表頭的名稱找出公司代號與三項現金流量的欄位
+pub(crate) fn parse(year: i32, quarter: Quarter, html: &str) -> Result<Vec<CashFlow>> {
+    let document = Html::parse_document(html);
+    let selector_table =
+        Selector::parse("table").map_err(|_| anyhow!("Failed to parse table selector"))?;
+    let selector_tr = Selector::parse("tr").map_err(|_| anyhow!("Failed to parse tr selector"))?;
+    let selector_th = Selector::parse("th").map_err(|_| anyhow!("Failed to parse th selector"))?;
+    let selector_td = Selector::parse("td").map_err(|_| anyhow!("Failed to parse td selector"))?;
+    let mut result = Vec::with_capacity(1024);
+
+    for table in document.select(&selector_table) {
+        let headers: Vec<String> = table
+            .select(&selector_th)
+            .map(|th| th.text().collect::<String>().trim().to_string())
+            .collect();
+        let column = |name: &str| headers.iter().position(|header| header.contains(name));
+        let (Some(symbol), Some(operating), Some(investing), Some(financing)) = (
+            column("公司代號"),
+            column("營業活動"),
+            column("投資活動"),
+            column("籌資活動"),
+        ) else {
+            continue;
+        };
+        let statement_type = StatementType::detect(&headers.concat());
+
+        for tr in table.select(&selector_tr) {
+            let tds: Vec<String> = tr
+                .select(&selector_td)
+                .map(|td| td.text().collect::<String>().trim().to_string())
+                .collect();
+            let amount = |index: usize| {
+                tds.get(index)
+                    .and_then(|cell| text::parse_decimal(cell, None).ok())
+            };
+            let (Some(stock_symbol), Some(operating), Some(investing), Some(financing)) = (
+                tds.get(symbol)
+                    .and_then(|code| StockSymbol::parse(code).ok()),
+                amount(operating),
+                amount(investing),
+                amount(financing),
+            ) else {
+                continue;
+            };
+
+            result.push(CashFlow {
+                stock_symbol,
+                year,
+                quarter,
+                operating_cash_flow: operating,
+                investing_cash_flow: investing,
+                financing_cash_flow: financing,
+                statement_type,
+            });
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logging;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let html = r#"<table class="hasBorder">
+<tr class="tblHead"><th>公司代號</th><th>公司名稱</th><th>營業活動之淨現金流入(流出)</th><th>投資活動之淨現金流入(流出)</th><th>籌資活動之淨現金流入(流出)</th></tr>
+<tr class="even"><td>2330</td><td>台積電</td><td>1,241,967,000</td><td>-763,122,000</td><td>-254,011,000</td></tr>
+<tr class="odd"><td>1101</td><td>台泥</td><td>--</td><td>--</td><td>--</td></tr>
+</table>
+<table class="hasBorder">
+<tr class="tblHead"><th>公司代號</th><th>公司名稱</th><th>個別營業活動之淨現金流入(流出)</th><th>投資活動之淨現金流入(流出)</th><th>籌資活動之淨現金流入(流出)</th></tr>
+<tr class="even"><td>9999</td><td>測試</td><td>100</td><td>-30</td><td>0</td></tr>
+</table>"#;
+
+        let list = parse(2024, Quarter::Q4, html).unwrap();
+
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].stock_symbol, "2330");
+        assert_eq!(list[0].operating_cash_flow, Decimal::from(1_241_967_000));
+        assert_eq!(list[0].investing_cash_flow, Decimal::from(-763_122_000));
+        assert_eq!(list[0].statement_type, StatementType::Consolidated);
+        assert_eq!(list[1].statement_type, StatementType::Individual);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_visit() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 visit".to_string());
+
+        match visit(StockExchangeMarket::Listed, 2024, Quarter::Q1).await {
+            Ok(list) => logging::debug_file_async(format!("list:{:#?}", list)),
+            Err(why) => logging::debug_file_async(format!("Failed to visit because {:?}", why)),
+        }
+
+        logging::debug_file_async("結束 visit".to_string());
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use scraper::{Html, Selector};
+
+use crate::{
+    crawler::twse,
+    declare::StockExchangeMarket,
+    util::{self, datetime},
+};
+
+/// 公開資訊觀測站法人說明會一覽表的一筆數據
+#[derive(Debug, Clone, PartialEq)]
+pub struct EarningsCall {
+    pub stock_symbol: String,
+    pub name: String,
+    /// 召開法人說明會的日期，多日的法說會取第一天
+    pub date: NaiveDate,
+    /// 召開時間 ex. 14:00
+    pub time: String,
+    /// 召開地點
+    pub location: String,
+}
+
+/// 取得指定市場(上市、上櫃)在某年某月召開的法人說明會
+pub async fn visit(
+    market: StockExchangeMarket,
+    year: i32,
+    month: u32,
+) -> Result<Vec<EarningsCall>> {
+    let url = format!("https://mops.{}/mops/web/ajax_t100sb02_1", twse::HOST);
+    let typek = match market {
+        StockExchangeMarket::OverTheCounter => "otc",
+        _ => "sii",
+    };
+    let roc_year = datetime::gregorian_year_to_roc_year(year).to_string();
+    let month = format!("{:02}", month);
+    let mut params = HashMap::with_capacity(7);
+    params.insert("encodeURIComponent", "1");
+    params.insert("step", "1");
+    params.insert("firstin", "1");
+    params.insert("off", "1");
+    params.insert("TYPEK", typek);
+    params.insert("year", &roc_year);
+    params.insert("month", &month);
+
+    let response = util::http::post(&url, None, Some(params)).await?;
+
+    parse(&response)
+}
+
+/// 解析法人說明會一覽表，欄位依序為公司代號、公司名稱、召開日期、召開時間、召開地點...
+pub(crate) fn parse(html: &str) -> Result<Vec<EarningsCall>> {
+    let document = Html::parse_document(html);
+    let selector_tr = Selector::parse("tr").map_err(|_| anyhow!("Failed to parse tr selector"))?;
+    let selector_td = Selector::parse("td").map_err(|_| anyhow!("Failed to parse td selector"))?;
+    let mut result = Vec::with_capacity(256);
+
+    for tr in document.select(&selector_tr) {
+        let tds: Vec<String> = tr
+            .select(&selector_td)
+            .map(|td| td.text().collect::<String>().trim().to_string())
+            .collect();
+        if tds.len() < 5 {
+            continue;
+        }
+
+        let Some(date) = tds[2]
+            .split_whitespace()
+            .next()
+            .and_then(datetime::parse_taiwan_date)
+        else {
+            continue;
+        };
+
+        result.push(EarningsCall {
+            stock_symbol: tds[0].clone(),
+            name: tds[1].clone(),
+            date,
+            time: tds[3].clone(),
+            location: tds[4].clone(),
+        });
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Datelike, Local};
+
+    use crate::logging;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let html = r#"<table class="hasBorder">
+<tr class="tblHead"><th>公司代號</th><th>公司名稱</th><th>召開法人說明會日期</th><th>召開法人說明會時間</th><th>召開法人說明會地點</th><th>法人說明會擇要訊息</th></tr>
+<tr class="even"><td>2330</td><td>台積電</td><td>113/04/18</td><td>14:00</td><td>線上法說會</td><td>說明本公司營運狀況</td></tr>
+<tr class="odd"><td>2317</td><td>鴻海</td><td>113/05/14 至 113/05/15</td><td>15:00</td><td>台北</td><td></td></tr>
+<tr class="even"><td>1101</td><td>台泥</td><td>未定</td><td></td><td></td><td></td></tr>
+</table>"#;
+
+        let list = parse(html).unwrap();
+
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].stock_symbol, "2330");
+        assert_eq!(list[0].date, NaiveDate::from_ymd_opt(2024, 4, 18).unwrap());
+        assert_eq!(list[0].time, "14:00");
+        assert_eq!(list[0].location, "線上法說會");
+        assert_eq!(list[1].date, NaiveDate::from_ymd_opt(2024, 5, 14).unwrap());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_visit() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 visit".to_string());
+        let today = Local::now().date_naive();
+
+        match visit(StockExchangeMarket::Listed, today.year(), today.month()).await {
+            Ok(list) => logging::debug_file_async(format!("list:{:#?}", list)),
+            Err(why) => logging::debug_file_async(format!("Failed to visit because: {:?}", why)),
+        }
+
+        logging::debug_file_async("結束 visit".to_string());
+    }
+}
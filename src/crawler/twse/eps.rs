@@ -7,7 +7,7 @@ use scraper::{Html, Selector};
 use crate::{
     cache::SHARE,
     crawler::twse,
-    declare::{Quarter, StockExchangeMarket},
+    declare::{Quarter, StatementType, StockExchangeMarket, StockSymbol},
     util::{self, convert::FromValue, datetime},
 };
 
@@ -18,18 +18,27 @@ pub struct Eps {
     pub year: i32,
     /// 季度 Q4 Q3 Q2 Q1
     pub quarter: Quarter,
-    pub stock_symbol: String,
+    pub stock_symbol: StockSymbol,
     /// 每股稅後淨利
     pub earnings_per_share: Decimal,
+    /// 財報類別，依表格的標題判斷
+    pub statement_type: StatementType,
 }
 
 impl Eps {
-    pub fn new(stock_symbol: String, year: i32, quarter: Quarter, eps: Decimal) -> Self {
+    pub fn new(
+        stock_symbol: StockSymbol,
+        year: i32,
+        quarter: Quarter,
+        eps: Decimal,
+        statement_type: StatementType,
+    ) -> Self {
         Self {
             year,
             quarter,
             stock_symbol,
             earnings_per_share: eps,
+            statement_type,
         }
     }
 }
@@ -65,29 +74,33 @@ pub async fn visit(
     let selector_table =
         Selector::parse("table").map_err(|_| anyhow!("Failed to parse table selector"))?;
     let selector_tr = Selector::parse("tr").map_err(|_| anyhow!("Failed to parse tr selector"))?;
+    let selector_th = Selector::parse("th").map_err(|_| anyhow!("Failed to parse th selector"))?;
 
     for table in document.select(&selector_table) {
+        // 公開資訊觀測站的彙總表會在表頭註明合併或個別財報
+        let title: String = table.select(&selector_th).flat_map(|th| th.text()).collect();
+        let statement_type = StatementType::detect(&title);
+
         for tr in table.select(&selector_tr) {
             let tds: Vec<&str> = tr.text().map(str::trim).collect();
             if tds.len() != 19 {
                 continue;
             }
 
-            let stock_symbol = tds[1];
-
-            if stock_symbol.is_empty() {
+            let Ok(stock_symbol) = StockSymbol::parse(tds[1]) else {
                 continue;
-            }
+            };
 
-            if !SHARE.stock_contains_key(stock_symbol) {
+            if !SHARE.stock_contains_key(&stock_symbol) {
                 continue;
             }
             
             let eps = Eps::new(
-                stock_symbol.to_string(),
+                stock_symbol,
                 year,
                 quarter,
                 tds[7].to_string().get_decimal(None),
+                statement_type,
             );
 
             result.push(eps);
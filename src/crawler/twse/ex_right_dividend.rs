@@ -0,0 +1,110 @@
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::{
+    crawler::twse,
+    declare::StockSymbol,
+    util::{self, datetime},
+};
+
+/// 調用 twse TWT49U(除權除息計算結果表) 後其回應的數據
+#[derive(Deserialize, Debug)]
+struct ExRightDividendResponse {
+    stat: Option<String>,
+    /// 資料日期、股票代號、股票名稱、除權息前收盤價、除權息參考價、權值+息值、權/息、...
+    data: Option<Vec<Vec<String>>>,
+}
+
+/// 上市股票的除權除息結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExRightDividend {
+    /// 除權息日
+    pub date: NaiveDate,
+    pub stock_symbol: StockSymbol,
+    /// 權值+息值
+    pub value: Decimal,
+    /// 權、息、權息
+    pub kind: String,
+}
+
+impl ExRightDividend {
+    /// 只有除息，權值+息值即為現金股利
+    pub fn is_cash_only(&self) -> bool {
+        self.kind == "息"
+    }
+}
+
+/// 取得上市股票在指定期間內的除權除息結果
+pub async fn visit(start: NaiveDate, end: NaiveDate) -> Result<Vec<ExRightDividend>> {
+    let url = format!(
+        "https://www.{}/rwd/zh/exRight/TWT49U?startDate={}&endDate={}&response=json",
+        twse::HOST,
+        start.format("%Y%m%d"),
+        end.format("%Y%m%d")
+    );
+    let res = util::http::get_json::<ExRightDividendResponse>(&url).await?;
+
+    match res.stat.as_deref() {
+        Some("OK") => Ok(res
+            .data
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|row| parse_row(row))
+            .collect()),
+        Some(stat) if stat.contains("沒有符合條件") => Ok(Vec::new()),
+        stat => Err(anyhow!(
+            "Failed to visit TWT49U({} ~ {}) because stat is {:?}",
+            start,
+            end,
+            stat
+        )),
+    }
+}
+
+fn parse_row(row: &[String]) -> Option<ExRightDividend> {
+    if row.len() < 7 {
+        return None;
+    }
+
+    // 資料日期的格式為 113年06月13日
+    let date = row[0].replace(['年', '月'], "/").replace('日', "");
+
+    Some(ExRightDividend {
+        date: datetime::parse_taiwan_date(&date)?,
+        stock_symbol: StockSymbol::parse(&row[1]).ok()?,
+        value: util::text::parse_decimal(&row[5], Some(vec![','])).ok()?,
+        kind: row[6].trim().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn row(cells: &[&str]) -> Vec<String> {
+        cells.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_row() {
+        let e = parse_row(&row(&[
+            "113年06月13日",
+            "2330",
+            "台積電",
+            "914.00",
+            "910.00",
+            "4.00",
+            "息",
+        ]))
+        .unwrap();
+
+        assert_eq!(e.date, NaiveDate::from_ymd_opt(2024, 6, 13).unwrap());
+        assert_eq!(e.stock_symbol, "2330");
+        assert_eq!(e.value, Decimal::new(4, 0));
+        assert!(e.is_cash_only());
+        assert_eq!(parse_row(&row(&["113年06月13日", "2330"])), None);
+    }
+}
@@ -0,0 +1,57 @@
+use anyhow::Result;
+use serde::Deserialize;
+
+use crate::{crawler::twse, util};
+
+/// 調用 twse openapi t187ap11_L(上市公司董事、監察人持股餘額明細) 後其回應的數據，每位董監事一筆
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct InsiderShareholding {
+    /// 民國年月 ex. 11301
+    #[serde(rename(deserialize = "資料年月"))]
+    pub year_month: String,
+    #[serde(rename(deserialize = "公司代號"))]
+    pub stock_symbol: String,
+    #[serde(rename(deserialize = "公司名稱"), default)]
+    pub name: String,
+    #[serde(rename(deserialize = "職稱"), default)]
+    pub title: String,
+    /// 目前持股(股)
+    #[serde(rename(deserialize = "目前持股"), default)]
+    pub shares_held: String,
+    /// 設質股數(股)
+    #[serde(rename(deserialize = "設質股數"), default)]
+    pub shares_pledged: String,
+}
+
+/// 取得上市公司最近一個月的董事、監察人持股餘額明細
+pub async fn visit() -> Result<Vec<InsiderShareholding>> {
+    let url = format!("https://openapi.{}/v1/opendata/t187ap11_L", twse::HOST);
+
+    util::http::get_json::<Vec<InsiderShareholding>>(&url).await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logging;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_visit() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 visit".to_string());
+
+        match visit().await {
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to visit because: {:?}", why));
+            }
+            Ok(list) => {
+                logging::debug_file_async(format!("data({}):{:#?}", list.len(), list));
+            }
+        }
+
+        logging::debug_file_async("結束 visit".to_string());
+    }
+}
@@ -6,23 +6,33 @@ use crate::{
     cache::SHARE,
     crawler::twse,
     database::table,
-    declare::StockExchangeMarket,
+    declare::{SecurityType, StockExchangeMarket, StockSymbol},
     util::{self, datetime::Weekend},
 };
 
-const REQUIRED_CATEGORIES: [&str; 4] = ["股票", "特別股", "普通股", "臺灣存託憑證(TDR)"];
+const REQUIRED_CATEGORIES: [&str; 7] = [
+    "股票",
+    "特別股",
+    "普通股",
+    "臺灣存託憑證(TDR)",
+    "ETF",
+    "上市認購(售)權證",
+    "上櫃認購(售)權證",
+];
 
 /// twse 國際證券識別碼
 #[derive(Debug)]
 pub struct InternationalSecuritiesIdentificationNumber {
     //pub exchange: StockExchangeMarket,
-    pub stock_symbol: String,
+    pub stock_symbol: StockSymbol,
     pub name: String,
     pub isin_code: String,
     pub listing_date: String,
     //pub market_category: String,
     pub industry: String,
     pub cfi_code: String,
+    /// 證券類別
+    pub security_type: SecurityType,
     pub exchange_market: table::stock_exchange_market::StockExchangeMarket,
     pub industry_id: i32,
 }
@@ -36,6 +46,7 @@ impl Clone for InternationalSecuritiesIdentificationNumber {
             listing_date: self.listing_date.clone(),
             industry: self.industry.clone(),
             cfi_code: self.cfi_code.clone(),
+            security_type: self.security_type,
             exchange_market: self.exchange_market.clone(),
             industry_id: self.industry_id,
         }
@@ -63,10 +74,12 @@ pub async fn visit(
 
     if let Ok(selector) = Selector::parse("body > table.h4 > tbody > tr") {
         let mut is_required_category = false;
+        let mut category = String::new();
         for node in document.select(&selector).skip(1) {
             let tds: Vec<&str> = node.text().map(str::trim).collect();
             if tds.len() == 2 {
                 is_required_category = REQUIRED_CATEGORIES.contains(&tds[0].trim());
+                category = tds[0].trim().to_string();
                 continue;
             }
 
@@ -80,6 +93,11 @@ pub async fn visit(
                 continue;
             }
 
+            let stock_symbol = match StockSymbol::parse(split[0]) {
+                Ok(stock_symbol) => stock_symbol,
+                Err(_) => continue,
+            };
+
             let industry: String;
             let cfi_code: String;
 
@@ -107,13 +125,15 @@ pub async fn visit(
                     Some(em) => em,
                 };
             let industry_id = SHARE.get_industry_id(&industry).unwrap_or(99);
+            let security_type = SecurityType::classify(&category, &cfi_code);
             let isin = InternationalSecuritiesIdentificationNumber {
-                stock_symbol: split[0].trim().to_owned(),
+                stock_symbol,
                 name: split[1].to_owned(),
                 isin_code: tds[1].to_owned(),
                 listing_date: tds[2].to_owned(),
                 industry,
                 cfi_code,
+                security_type,
                 exchange_market,
                 industry_id,
             };
@@ -0,0 +1,161 @@
+use std::str::FromStr;
+
+use anyhow::Result;
+use chrono::{Local, TimeZone};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::{
+    database::table::order_book_snapshot::OrderBookSnapshot, declare::StockSymbol, util::http,
+};
+
+/// 單次查詢的股票數量上限，超過時分批查詢
+const BATCH_SIZE: usize = 50;
+
+#[derive(Deserialize, Debug)]
+struct MisResponse {
+    #[serde(rename = "msgArray", default)]
+    pub msg_array: Vec<MisStockInfo>,
+}
+
+/// 基本市況報導的個股即時資訊，五檔以底線分隔 ex. `1080.0000_1075.0000_`
+#[derive(Deserialize, Debug, Default)]
+struct MisStockInfo {
+    /// 股票代號
+    #[serde(default)]
+    pub c: String,
+    /// 資料時間(毫秒)
+    #[serde(default)]
+    pub tlong: String,
+    /// 最近成交價，尚未成交時為 -
+    #[serde(default)]
+    pub z: String,
+    /// 累積成交張數
+    #[serde(default)]
+    pub v: String,
+    /// 委買價
+    #[serde(default)]
+    pub b: String,
+    /// 委買張數
+    #[serde(default)]
+    pub g: String,
+    /// 委賣價
+    #[serde(default)]
+    pub a: String,
+    /// 委賣張數
+    #[serde(default)]
+    pub f: String,
+}
+
+/// 查詢個股的最佳五檔，symbols 為 (股票代號, 是否為上櫃)
+pub async fn visit(symbols: &[(StockSymbol, bool)]) -> Result<Vec<OrderBookSnapshot>> {
+    let mut snapshots = Vec::with_capacity(symbols.len());
+    for batch in symbols.chunks(BATCH_SIZE) {
+        let channels: Vec<String> = batch
+            .iter()
+            .map(|(symbol, otc)| format!("{}_{}.tw", if *otc { "otc" } else { "tse" }, symbol))
+            .collect();
+        let url = format!(
+            "https://mis.twse.com.tw/stock/api/getStockInfo.jsp?ex_ch={}&json=1&delay=0&_={}",
+            channels.join("%7c"),
+            Local::now().timestamp_millis()
+        );
+        let res = http::get_json::<MisResponse>(&url).await?;
+        snapshots.extend(res.msg_array.iter().filter_map(to_snapshot));
+    }
+
+    Ok(snapshots)
+}
+
+fn to_snapshot(info: &MisStockInfo) -> Option<OrderBookSnapshot> {
+    let millis = info.tlong.parse::<i64>().ok()?;
+    let quote_time = Local.timestamp_millis_opt(millis).single()?;
+    let bid_prices = levels::<Decimal>(&info.b);
+    let ask_prices = levels::<Decimal>(&info.a);
+    if bid_prices.is_empty() && ask_prices.is_empty() {
+        return None;
+    }
+
+    Some(OrderBookSnapshot {
+        security_code: StockSymbol::parse(&info.c).ok()?,
+        quote_time,
+        last_price: Decimal::from_str(&info.z).unwrap_or_default(),
+        volume: info.v.parse().unwrap_or_default(),
+        bid_volumes: levels(&info.g),
+        bid_prices,
+        ask_volumes: levels(&info.f),
+        ask_prices,
+    })
+}
+
+/// 拆解以底線分隔的五檔價量，無法解析的值略過
+fn levels<T: FromStr>(text: &str) -> Vec<T> {
+    text.split('_')
+        .filter(|level| !level.is_empty())
+        .filter_map(|level| level.parse().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use crate::logging;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_to_snapshot() {
+        let info: MisStockInfo = serde_json::from_str(
+            r#"{"c":"2330","tlong":"1714541400000","z":"-","v":"25031",
+            "b":"798.0000_797.0000_796.0000_795.0000_794.0000_",
+            "g":"320_512_85_96_120_",
+            "a":"799.0000_800.0000_",
+            "f":"45_1200_","n":"台積電"}"#,
+        )
+        .unwrap();
+
+        let snapshot = to_snapshot(&info).unwrap();
+
+        assert_eq!(snapshot.security_code, "2330");
+        assert_eq!(snapshot.quote_time.timestamp_millis(), 1714541400000);
+        assert_eq!(snapshot.last_price, dec!(0));
+        assert_eq!(snapshot.volume, 25031);
+        assert_eq!(snapshot.bid_prices.len(), 5);
+        assert_eq!(snapshot.bid_prices[0], dec!(798));
+        assert_eq!(snapshot.bid_volumes, vec![320, 512, 85, 96, 120]);
+        assert_eq!(snapshot.ask_prices, vec![dec!(799), dec!(800)]);
+        assert_eq!(snapshot.ask_volumes, vec![45, 1200]);
+
+        let empty = MisStockInfo {
+            c: "2330".to_string(),
+            tlong: "1714541400000".to_string(),
+            ..Default::default()
+        };
+        assert!(to_snapshot(&empty).is_none());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_visit() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 visit".to_string());
+
+        match visit(&[
+            ("2330".parse().unwrap(), false),
+            ("6488".parse().unwrap(), true),
+        ])
+        .await
+        {
+            Ok(list) => {
+                logging::debug_file_async(format!("data({}):{:#?}", list.len(), list));
+            }
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to visit because {:?}", why));
+            }
+        }
+
+        logging::debug_file_async("結束 visit".to_string());
+    }
+}
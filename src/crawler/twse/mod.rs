@@ -1,11 +1,26 @@
+use anyhow::{anyhow, Result};
 use reqwest::header::{HeaderMap, HeaderValue};
 
 use crate::util::http;
 
+/// 上市公司每日重大訊息
+pub mod announcement;
+/// 上市公司買回本公司股份
+pub mod buyback;
+/// 現金流量表彙總
+pub mod cash_flow;
 /// 台股財報
 pub mod eps;
+/// 除權除息計算結果表
+pub mod ex_right_dividend;
 /// 國際證券辨識
 pub mod international_securities_identification_number;
+/// 上市公司董事、監察人持股餘額明細
+pub mod insider_shareholding;
+/// 基本市況報導的即時最佳五檔
+pub mod mis;
+/// 盤後零股交易行情-上市
+pub mod odd_lot;
 /// 公開申購公告-抽籤日程表
 pub mod public;
 /// 外資及陸資投資持股
@@ -14,14 +29,22 @@ pub mod qualified_foreign_institutional_investor;
 pub mod quote;
 /// 月營收
 pub mod revenue;
+/// 個股日成交資訊-上市
+pub mod stock_day;
 /// 終止上市公司
 pub mod suspend_listing;
 /// 台股加權指數
 pub mod taiwan_capitalization_weighted_stock_index;
 /// 台股休市日期
 pub mod holiday_schedule;
+/// 個股日本益比、殖利率及股價淨值比-上市
+pub mod valuation;
+/// 上市公司召集股東常(臨時)會的公告
+pub mod shareholder_meeting;
+/// 上市櫃公司召開的法人說明會
+pub mod earnings_call;
 
-const HOST: &str = "twse.com.tw";
+pub(super) const HOST: &str = "twse.com.tw";
 
 pub(crate) async fn build_headers() -> HeaderMap {
     let mut h = HeaderMap::with_capacity(4);
@@ -31,3 +54,14 @@ pub(crate) async fn build_headers() -> HeaderMap {
     h.insert("User-Agent", http::user_agent::gen_random_ua().parse().unwrap());
     h
 }
+
+/// 以 OpenAPI 的每日市場成交資訊確認可以連線到證交所，部署後的自我檢查使用
+pub async fn ping() -> Result<()> {
+    let url = format!("https://openapi.{}/v1/exchangeReport/FMTQIK", HOST);
+    let text = http::get(&url, None).await?;
+    if !text.trim_start().starts_with('[') {
+        return Err(anyhow!("Unexpected response from {}", url));
+    }
+
+    Ok(())
+}
@@ -55,7 +55,9 @@ pub async fn visit(
         if item.len() != 12 {
             continue;
         }
-        let qfii = QualifiedForeignInstitutionalInvestor::from(item);
+        let Ok(qfii) = QualifiedForeignInstitutionalInvestor::try_from(item) else {
+            continue;
+        };
         result.push(qfii);
     }
 
@@ -24,7 +24,10 @@ pub async fn visit() -> Result<Vec<QualifiedForeignInstitutionalInvestor>> {
         if tds.len() != 23 {
             continue;
         }
-        let qfii = QualifiedForeignInstitutionalInvestor::from(tds);
+        // 表頭等代號不是股票代號格式的列略過
+        let Ok(qfii) = QualifiedForeignInstitutionalInvestor::try_from(tds) else {
+            continue;
+        };
         result.push(qfii);
     }
 
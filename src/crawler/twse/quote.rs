@@ -62,8 +62,12 @@ pub async fn visit(date: NaiveDate) -> Result<Vec<table::daily_quote::DailyQuote
         if let Some(twse_dqs) = &data.tables[8].data {
             for item in twse_dqs {
                 //logging::debug_file_async(format!("item:{:?}", item));
-                let mut dq =
-                    table::daily_quote::DailyQuote::from_with_exchange(StockExchange::TWSE, item);
+                // 代號不是股票代號格式的列略過
+                let Ok(mut dq) =
+                    table::daily_quote::DailyQuote::from_with_exchange(StockExchange::TWSE, item)
+                else {
+                    continue;
+                };
 
                 if dq.closing_price.is_zero()
                     && dq.highest_price.is_zero()
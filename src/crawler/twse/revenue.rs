@@ -50,7 +50,9 @@ async fn download_revenue(url: String, year: i32, month: u32) -> Result<Vec<reve
             continue;
         }
 
-        let mut entity = revenue::Revenue::from(tds);
+        let Ok(mut entity) = revenue::Revenue::try_from(tds) else {
+            continue;
+        };
         entity.date = date;
         revenues.push(entity);
     }
@@ -0,0 +1,62 @@
+use anyhow::Result;
+use serde::Deserialize;
+
+use crate::{crawler::twse, util};
+
+/// 調用 twse openapi t187ap38_L(上市公司股東會公告-召集股東常(臨時)會公告資料彙總表) 後其回應的數據
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct ShareholderMeeting {
+    #[serde(rename(deserialize = "公司代號"))]
+    pub stock_symbol: String,
+    #[serde(rename(deserialize = "公司名稱"), default)]
+    pub name: String,
+    /// 常會或臨時會
+    #[serde(rename(deserialize = "股東常(臨時)會日期-常或臨時"), default)]
+    pub kind: String,
+    /// 民國年格式的股東會日期 ex. 1130604
+    #[serde(rename(deserialize = "股東常(臨時)會日期-日期"))]
+    pub meeting_date: String,
+}
+
+/// 取得上市公司已公告召集的股東會
+pub async fn visit() -> Result<Vec<ShareholderMeeting>> {
+    let url = format!("https://openapi.{}/v1/opendata/t187ap38_L", twse::HOST);
+
+    util::http::get_json::<Vec<ShareholderMeeting>>(&url).await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logging;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_deserialize() {
+        let json = r#"[{"出表日期":"1130415","公司代號":"2330","公司名稱":"台積電","股東常(臨時)會日期-常或臨時":"常會","股東常(臨時)會日期-日期":"1130604","停止過戶起訖日期-起":"1130406","停止過戶起訖日期-訖":"1130604"}]"#;
+        let list: Vec<ShareholderMeeting> = serde_json::from_str(json).unwrap();
+
+        assert_eq!(list[0].stock_symbol, "2330");
+        assert_eq!(list[0].kind, "常會");
+        assert_eq!(list[0].meeting_date, "1130604");
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_visit() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 visit".to_string());
+
+        match visit().await {
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to visit because: {:?}", why));
+            }
+            Ok(list) => {
+                logging::debug_file_async(format!("data:{:#?}", list));
+            }
+        }
+
+        logging::debug_file_async("結束 visit".to_string());
+    }
+}
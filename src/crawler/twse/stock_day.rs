@@ -0,0 +1,166 @@
+use anyhow::{anyhow, Result};
+use chrono::{Datelike, Local, NaiveDate, TimeZone};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Deserialize;
+
+use crate::{
+    crawler::twse,
+    database::table::daily_quote::DailyQuote,
+    declare::StockSymbol,
+    util::{self, datetime},
+};
+
+/// 調用 twse STOCK_DAY(個股日成交資訊) 後其回應的數據
+#[derive(Deserialize, Debug)]
+struct StockDayResponse {
+    stat: Option<String>,
+    /// 日期、成交股數、成交金額、開盤價、最高價、最低價、收盤價、漲跌價差、成交筆數
+    data: Option<Vec<Vec<String>>>,
+}
+
+/// 取得上市股票指定月份每個交易日的收盤資訊
+pub async fn visit(stock_symbol: &str, month: NaiveDate) -> Result<Vec<DailyQuote>> {
+    let symbol = StockSymbol::parse(stock_symbol)?;
+    let url = format!(
+        "https://www.{}/exchangeReport/STOCK_DAY?response=json&date={}&stockNo={}",
+        twse::HOST,
+        month.with_day(1).unwrap_or(month).format("%Y%m%d"),
+        symbol
+    );
+    let res = util::http::get_json::<StockDayResponse>(&url).await?;
+
+    match res.stat.as_deref() {
+        Some("OK") => Ok(res
+            .data
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|row| parse_row(&symbol, row))
+            .collect()),
+        // 該月份沒有交易資料(尚未上市或整月停牌)
+        Some(stat) if stat.contains("沒有符合條件") => Ok(Vec::new()),
+        stat => Err(anyhow!(
+            "Failed to visit STOCK_DAY({}, {}) because stat is {:?}",
+            stock_symbol,
+            month,
+            stat
+        )),
+    }
+}
+
+/// 將一列數據轉為收盤資訊，當日沒有成交(價格為 --)時略過
+fn parse_row(stock_symbol: &StockSymbol, row: &[String]) -> Option<DailyQuote> {
+    if row.len() < 9 {
+        return None;
+    }
+
+    let date = datetime::parse_taiwan_date(&row[0])?;
+    let price = |s: &str| util::text::parse_decimal(s, None).ok();
+    let opening_price = price(&row[3])?;
+    let highest_price = price(&row[4])?;
+    let lowest_price = price(&row[5])?;
+    let closing_price = price(&row[6])?;
+    // 漲跌價差可能帶有 + 號，除權息當日則以 X 開頭
+    let change = price(row[7].trim().trim_start_matches(['X', '+'])).unwrap_or_default();
+    let previous_close = closing_price - change;
+
+    let mut dq = DailyQuote::new(stock_symbol.clone());
+    dq.date = date;
+    dq.year = date.year();
+    dq.month = date.month() as i32;
+    dq.day = date.day() as i32;
+    dq.trading_volume = price(&row[1]).unwrap_or_default();
+    dq.trade_value = price(&row[2]).unwrap_or_default();
+    dq.opening_price = opening_price;
+    dq.highest_price = highest_price;
+    dq.lowest_price = lowest_price;
+    dq.closing_price = closing_price;
+    dq.change = change;
+    dq.change_range = if previous_close > Decimal::ZERO {
+        change / previous_close * dec!(100)
+    } else {
+        Decimal::ZERO
+    };
+    dq.transaction = price(&row[8]).unwrap_or_default();
+    dq.record_time = date
+        .and_hms_opt(15, 0, 0)
+        .and_then(|naive| Local.from_local_datetime(&naive).single())
+        .unwrap_or_else(Local::now);
+    dq.create_time = Local::now();
+
+    Some(dq)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logging;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn row(cells: &[&str]) -> Vec<String> {
+        cells.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_row() {
+        let symbol: StockSymbol = "2330".parse().unwrap();
+        let dq = parse_row(
+            &symbol,
+            &row(&[
+                "113/01/02",
+                "26,059,058",
+                "15,376,609,314",
+                "590.00",
+                "593.00",
+                "589.00",
+                "593.00",
+                "-0.00",
+                "24,442",
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(dq.date, NaiveDate::from_ymd_opt(2024, 1, 2).unwrap());
+        assert_eq!(dq.trading_volume, dec!(26059058));
+        assert_eq!(dq.closing_price, dec!(593));
+        assert_eq!(dq.change, dec!(0));
+        assert_eq!(dq.transaction, dec!(24442));
+
+        let dq = parse_row(
+            &symbol,
+            &row(&[
+                "113/06/13",
+                "1",
+                "1",
+                "900.00",
+                "910.00",
+                "895.00",
+                "900.00",
+                "X+4.50",
+                "1",
+            ]),
+        )
+        .unwrap();
+        assert_eq!(dq.change, dec!(4.5));
+        assert_eq!(dq.change_range.round_dp(2), dec!(0.50));
+
+        let no_trade = row(&["113/01/03", "0", "0", "--", "--", "--", "--", " 0.00", "0"]);
+        assert!(parse_row(&symbol, &no_trade).is_none());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_visit() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 visit".to_string());
+
+        let month = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        match visit("2330", month).await {
+            Ok(list) => logging::debug_file_async(format!("data:{:#?}", list)),
+            Err(why) => logging::debug_file_async(format!("Failed to visit because {:?}", why)),
+        }
+
+        logging::debug_file_async("結束 visit".to_string());
+    }
+}
@@ -1,17 +1,42 @@
 use anyhow::Result;
+use async_trait::async_trait;
 use serde::Deserialize;
 
-use crate::{crawler::twse, util};
+use crate::{crawler::twse, declare::StockSymbol, error, logging, util};
 
 /// 調用 twse suspendListingCsvAndHtml API 後其回應的數據
-#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct SuspendListing {
-    #[serde(rename(deserialize = "DelistingDate"))]
     pub delisting_date: String,
-    #[serde(rename(deserialize = "Company"))]
     pub name: String,
+    pub stock_symbol: StockSymbol,
+}
+
+/// twse 回應的原始數據，代號先以字串接收，格式不符的資料列不影響其他公司
+#[derive(Debug, Deserialize)]
+struct SuspendListingRow {
+    #[serde(rename(deserialize = "DelistingDate"))]
+    delisting_date: String,
+    #[serde(rename(deserialize = "Company"))]
+    name: String,
     #[serde(rename(deserialize = "Code"))]
-    pub stock_symbol: String,
+    stock_symbol: String,
+}
+
+/// 終止上市公司名單的資料來源，測試時可替換成固定的數據
+#[async_trait]
+pub trait SuspendListingSource: Send + Sync {
+    async fn fetch(&self) -> Result<Vec<SuspendListing>>;
+}
+
+/// 以 twse openapi 作為終止上市公司名單的資料來源
+pub struct Twse;
+
+#[async_trait]
+impl SuspendListingSource for Twse {
+    async fn fetch(&self) -> Result<Vec<SuspendListing>> {
+        visit().await
+    }
 }
 
 /// 取得終止上市公司名單
@@ -21,7 +46,37 @@ pub async fn visit() -> Result<Vec<SuspendListing>> {
         twse::HOST,
     );
 
-    util::http::get_json::<Vec<SuspendListing>>(&url).await
+    let rows = util::http::get_json::<Vec<SuspendListingRow>>(&url).await?;
+
+    Ok(from_rows(rows))
+}
+
+/// 解析 suspendListingCsvAndHtml 回應的 JSON
+pub fn parse(text: &str) -> Result<Vec<SuspendListing>> {
+    let rows = serde_json::from_str::<Vec<SuspendListingRow>>(text)
+        .map_err(|why| error::Error::parse("suspend listing", why))?;
+
+    Ok(from_rows(rows))
+}
+
+/// 略過代號不是合法股票代號的資料列並記錄於日誌
+fn from_rows(rows: Vec<SuspendListingRow>) -> Vec<SuspendListing> {
+    rows.into_iter()
+        .filter_map(|row| match row.stock_symbol.parse::<StockSymbol>() {
+            Ok(stock_symbol) => Some(SuspendListing {
+                delisting_date: row.delisting_date,
+                name: row.name,
+                stock_symbol,
+            }),
+            Err(why) => {
+                logging::warn_file_async(format!(
+                    "Skip suspend listing of {}({}) because {:?}",
+                    row.name, row.stock_symbol, why
+                ));
+                None
+            }
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -33,6 +88,21 @@ mod tests {
     // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
     use super::*;
 
+    #[test]
+    fn test_parse() {
+        let list = parse(
+            r#"[
+                {"DelistingDate": "1130601", "Company": "台泥", "Code": "1101"},
+                {"DelistingDate": "1130602", "Company": "格式錯誤", "Code": "N/A"}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].stock_symbol.as_str(), "1101");
+        assert_eq!(list[0].delisting_date, "1130601");
+    }
+
     #[tokio::test]
     #[ignore]
     async fn test_visit() {
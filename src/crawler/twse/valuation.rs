@@ -0,0 +1,75 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+use crate::{
+    crawler::twse, database::table::daily_valuation::DailyValuation, declare::StockExchange,
+    util::http,
+};
+
+#[derive(Deserialize, Debug)]
+struct ValuationResponse {
+    pub stat: Option<String>,
+    pub fields: Option<Vec<String>>,
+    pub data: Option<Vec<Vec<String>>>,
+}
+
+/// 抓取上市個股日本益比、殖利率及股價淨值比
+pub async fn visit(date: NaiveDate) -> Result<Vec<DailyValuation>> {
+    let url = format!(
+        "https://www.{}/rwd/zh/afterTrading/BWIBBU_d?date={}&selectType=ALL&response=json&_={}",
+        twse::HOST,
+        date.format("%Y%m%d"),
+        date
+    );
+
+    let json = http::get(&url, None).await?;
+
+    parse(date, &json)
+}
+
+/// 解析個股日本益比、殖利率及股價淨值比的回應，stat 不為 OK 時表示當日無資料
+pub(crate) fn parse(date: NaiveDate, json: &str) -> Result<Vec<DailyValuation>> {
+    let res = serde_json::from_str::<ValuationResponse>(json)?;
+    if res.stat.as_deref() != Some("OK") {
+        return Ok(Vec::new());
+    }
+
+    match (res.fields, res.data) {
+        (Some(fields), Some(data)) => Ok(DailyValuation::from_table(
+            date,
+            StockExchange::TWSE,
+            &fields,
+            &data,
+        )),
+        _ => Ok(Vec::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+
+    use crate::logging;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_visit() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 visit".to_string());
+
+        match visit(Local::now().date_naive()).await {
+            Ok(list) => {
+                logging::debug_file_async(format!("data({}):{:#?}", list.len(), list));
+            }
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to visit because {:?}", why));
+            }
+        }
+
+        logging::debug_file_async("結束 visit".to_string());
+    }
+}
@@ -1,3 +1,3 @@
 pub mod profit;
 
-const HOST: &str = "wespai.com";
+pub(super) const HOST: &str = "wespai.com";
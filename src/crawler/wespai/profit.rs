@@ -5,13 +5,13 @@ use rust_decimal::Decimal;
 use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
 
-use crate::{crawler::wespai::HOST, util::http, util::http::element};
+use crate::{crawler::wespai::HOST, declare::StockSymbol, util::http, util::http::element};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Profit {
     /// 季度 Q4 Q3 Q2 Q1
     pub quarter: String,
-    pub security_code: String,
+    pub security_code: StockSymbol,
     /// 營業毛利率
     pub gross_profit: Decimal,
     /// 營業利益率
@@ -37,7 +37,7 @@ pub struct Profit {
 }
 
 impl Profit {
-    pub fn new(year: i32, security_code: String) -> Self {
+    pub fn new(year: i32, security_code: StockSymbol) -> Self {
         Profit {
             quarter: "".to_string(),
             security_code,
@@ -109,7 +109,9 @@ pub async fn visit() -> Result<Vec<Profit>> {
     for element in document.select(&selector) {
         //let tds: Vec<&str> = element.text().collect();
         //println!("tds:{:#?}",tds);
-        let security_code = match element::parse_value(&element, "td:nth-child(1)") {
+        let security_code = match element::parse_value(&element, "td:nth-child(1)")
+            .and_then(|code| StockSymbol::parse(&code).ok())
+        {
             None => continue,
             Some(security_code) => security_code,
         };
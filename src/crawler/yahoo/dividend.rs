@@ -2,26 +2,40 @@ use std::collections::HashMap;
 
 use anyhow::{anyhow, Result};
 use regex::Regex;
+use rust_decimal::Decimal;
 use scraper::{Html, Selector};
 
-use crate::{crawler::yahoo::HOST, util::http};
+use crate::{
+    crawler::yahoo::HOST,
+    declare::StockSymbol,
+    util::{http, text},
+};
+
+/// 特別股利的季度代碼，與全年度、季配、半年配的股利分開記錄
+pub const SPECIAL_QUARTER: &str = "S";
 
 #[derive(Debug, Clone)]
 pub struct YahooDividend {
     /// 股票代碼。
-    pub stock_symbol: String,
+    pub stock_symbol: StockSymbol,
     /// 股利詳情的對應表，鍵為年份，值為該年份的股利詳情列表。
     pub dividend: HashMap<i32, Vec<YahooDividendDetail>>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct YahooDividendDetail {
+    /// 股票代碼
+    pub stock_symbol: StockSymbol,
     /// 發放年度
     pub year: i32,
     /// 股利所屬年度
     pub year_of_dividend: i32,
-    /// 季度 Q4 Q3 Q2 Q1 H2 H1
+    /// 季度 空字串:全年度 Q1~Q4:第一季~第四季 H1~H2:上半年~下半年 S:特別股利
     pub quarter: String,
+    /// 現金股利
+    pub cash_dividend: Decimal,
+    /// 股票股利
+    pub stock_dividend: Decimal,
     /// 除息日
     pub ex_dividend_date1: String,
     /// 除權日
@@ -33,29 +47,14 @@ pub struct YahooDividendDetail {
 }
 
 impl YahooDividendDetail {
-    pub fn new(
-        year: i32,
-        year_of_dividend: i32,
-        quarter: String,
-        ex_dividend_date1: String,
-        ex_dividend_date2: String,
-        payable_date1: String,
-        payable_date2: String,
-    ) -> Self {
-        YahooDividendDetail {
-            year,
-            year_of_dividend,
-            quarter,
-            ex_dividend_date1,
-            ex_dividend_date2,
-            payable_date1,
-            payable_date2,
-        }
+    /// 是否為特別股利
+    pub fn is_special(&self) -> bool {
+        self.quarter == SPECIAL_QUARTER
     }
 }
 
 impl YahooDividend {
-    pub fn new(stock_symbol: String) -> Self {
+    pub fn new(stock_symbol: StockSymbol) -> Self {
         YahooDividend {
             stock_symbol,
             dividend: Default::default(),
@@ -63,7 +62,8 @@ impl YahooDividend {
     }
 }
 
-/// 從 Yahoo 網站抓取指定股票代碼的股利除息日、除權日、現金股利發放日、股票股利發放日等資訊。
+/// 從 Yahoo 網站抓取指定股票代碼的現金股利、股票股利、除息日、除權日、現金股利發放日、股票股利發放日等資訊。
+/// 季配、半年配的每一期與特別股利各自為一筆，季度分別為 Q1~Q4、H1~H2 與 S。
 ///
 /// # 參數
 ///
@@ -79,6 +79,7 @@ impl YahooDividend {
 ///
 /// 此函數可能因為網路請求失敗、網頁解析失敗或正規表示式解析失敗等原因導致錯誤。
 pub async fn visit(stock_symbol: &str) -> Result<YahooDividend> {
+    let symbol = StockSymbol::parse(stock_symbol)?;
     let url = format!("https://{}/quote/{}/dividend", HOST, stock_symbol);
     let text = http::get(&url, None).await?;
     let document = Html::parse_document(&text);
@@ -93,7 +94,7 @@ pub async fn visit(stock_symbol: &str) -> Result<YahooDividend> {
     };
 
     let re = Regex::new(r"(\d+)(Q\d|H\d)?")?;
-    let mut e = YahooDividend::new(stock_symbol.to_string());
+    let mut e = YahooDividend::new(symbol.clone());
 
     for element in document.select(&selector) {
         let dividend_period = http::element::parse_value(&element, "div > div.Fxg\\(1\\).Fxs\\(1\\).Fxb\\(0\\%\\).Ta\\(end\\).Mend\\(0\\)\\:lc.Mend\\(12px\\).W\\(88px\\).Miw\\(88px\\)");
@@ -121,6 +122,11 @@ pub async fn visit(stock_symbol: &str) -> Result<YahooDividend> {
 
         //股利所屬期間
         let (year_of_dividend, quarter) = parse_period(&dividend_period, &re)?;
+        //現金股利、股票股利
+        let cash_dividend =
+            parse_amount(&http::element::parse_value(&element, "div > div:nth-child(2)"));
+        let stock_dividend =
+            parse_amount(&http::element::parse_value(&element, "div > div:nth-child(3)"));
 
         let payout_date1 = http::element::parse_value(&element, "div > div:nth-child(9)")
             .unwrap_or_default()
@@ -131,15 +137,18 @@ pub async fn visit(stock_symbol: &str) -> Result<YahooDividend> {
         e.dividend
             .entry(year)
             .or_default()
-            .push(YahooDividendDetail::new(
+            .push(YahooDividendDetail {
+                stock_symbol: symbol.clone(),
                 year,
                 year_of_dividend,
                 quarter,
-                dividend_date_1,
-                dividend_date_2,
-                payout_date1,
-                payout_date2,
-            ));
+                cash_dividend,
+                stock_dividend,
+                ex_dividend_date1: dividend_date_1,
+                ex_dividend_date2: dividend_date_2,
+                payable_date1: payout_date1,
+                payable_date2: payout_date2,
+            });
     }
 
     Ok(e)
@@ -163,7 +172,7 @@ fn parse_date(date: &Option<String>, year: &mut i32) -> String {
     }
 }
 
-/// 解析股利期間，返回股利所屬的年份和季度。
+/// 解析股利期間，返回股利所屬的年份和季度，特別股利 ex. 2023特別股利 的季度為 S。
 fn parse_period(period: &Option<String>, re: &Regex) -> Result<(i32, String)> {
     let mut year_of_dividend = 0;
     let mut quarter = String::from("");
@@ -177,17 +186,59 @@ fn parse_period(period: &Option<String>, re: &Regex) -> Result<(i32, String)> {
                 quarter = q.as_str().to_string();
             }
         }
+
+        if period.contains("特別") {
+            quarter = SPECIAL_QUARTER.to_string();
+        }
     }
 
     Ok((year_of_dividend, quarter))
 }
 
+/// 解析股利金額，未配發時網頁顯示 "-" 回傳 0。
+fn parse_amount(amount: &Option<String>) -> Decimal {
+    amount
+        .as_deref()
+        .and_then(|amount| text::parse_decimal(amount, None).ok())
+        .unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     use crate::logging;
 
     use super::*;
 
+    #[test]
+    fn test_parse_period() {
+        let re = Regex::new(r"(\d+)(Q\d|H\d)?").unwrap();
+
+        assert_eq!(
+            parse_period(&Some("2023".to_string()), &re).unwrap(),
+            (2023, "".to_string())
+        );
+        assert_eq!(
+            parse_period(&Some("2024Q1".to_string()), &re).unwrap(),
+            (2024, "Q1".to_string())
+        );
+        assert_eq!(
+            parse_period(&Some("2023H2".to_string()), &re).unwrap(),
+            (2023, "H2".to_string())
+        );
+        assert_eq!(
+            parse_period(&Some("2023特別股利".to_string()), &re).unwrap(),
+            (2023, SPECIAL_QUARTER.to_string())
+        );
+        assert_eq!(parse_period(&None, &re).unwrap(), (0, "".to_string()));
+    }
+
+    #[test]
+    fn test_parse_amount() {
+        assert_eq!(parse_amount(&Some("3.5".to_string())), Decimal::new(35, 1));
+        assert_eq!(parse_amount(&Some("-".to_string())), Decimal::ZERO);
+        assert_eq!(parse_amount(&None), Decimal::ZERO);
+    }
+
     #[tokio::test]
     #[ignore]
     async fn test_visit() {
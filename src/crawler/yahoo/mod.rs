@@ -5,6 +5,6 @@ pub mod price;
 /// 從 yahoo 取回股票的基本數據
 pub mod profile;
 
-const HOST: &str = "tw.stock.yahoo.com";
+pub(super) const HOST: &str = "tw.stock.yahoo.com";
 
 pub struct Yahoo {}
@@ -8,13 +8,13 @@ use crate::{
         yahoo::{Yahoo, HOST},
         StockInfo,
     },
-    declare,
+    declare::{self, StockSymbol},
     util::{self, text},
 };
 
 #[async_trait]
 impl StockInfo for Yahoo {
-    async fn get_stock_price(stock_symbol: &str) -> Result<Decimal> {
+    async fn get_stock_price(stock_symbol: &StockSymbol) -> Result<Decimal> {
         let url = &format!(
             "https://{host}/quote/{symbol}",
             host = HOST,
@@ -36,7 +36,7 @@ impl StockInfo for Yahoo {
         }
     }
 
-    async fn get_stock_quotes(stock_symbol: &str) -> Result<declare::StockQuotes> {
+    async fn get_stock_quotes(stock_symbol: &StockSymbol) -> Result<declare::StockQuotes> {
         let url = &format!(
             "https://{host}/quote/{symbol}",
             host = HOST,
@@ -93,7 +93,7 @@ impl StockInfo for Yahoo {
         }
 
         Ok(declare::StockQuotes {
-            stock_symbol: stock_symbol.to_string(),
+            stock_symbol: stock_symbol.clone(),
             price,
             change,
             change_range,
@@ -111,7 +111,7 @@ mod tests {
         dotenv::dotenv().ok();
         logging::debug_file_async("開始 visit".to_string());
 
-        match Yahoo::get_stock_price("2330").await {
+        match Yahoo::get_stock_price(&"2330".parse().unwrap()).await {
             Ok(e) => {
                 dbg!(&e);
                 logging::debug_file_async(format!("dividend : {:#?}", e));
@@ -129,7 +129,7 @@ mod tests {
         dotenv::dotenv().ok();
         logging::debug_file_async("開始 get_stock_quotes".to_string());
 
-        match Yahoo::get_stock_quotes("2364").await {
+        match Yahoo::get_stock_quotes(&"2364".parse().unwrap()).await {
             Ok(e) => {
                 dbg!(&e);
                 logging::debug_file_async(format!("get_stock_quotes : {:#?}", e));
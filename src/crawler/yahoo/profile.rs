@@ -4,13 +4,13 @@ use rust_decimal::Decimal;
 use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
 
-use crate::{crawler::yahoo::HOST, util, util::http::element};
+use crate::{crawler::yahoo::HOST, declare::StockSymbol, util, util::http::element};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Profile {
     /// 季度 Q4 Q3 Q2 Q1
     pub quarter: String,
-    pub security_code: String,
+    pub security_code: StockSymbol,
     /// 營業毛利率
     pub gross_profit: Decimal,
     /// 營業利益率
@@ -36,7 +36,7 @@ pub struct Profile {
 }
 
 impl Profile {
-    pub fn new(security_code: String) -> Self {
+    pub fn new(security_code: StockSymbol) -> Self {
         Profile {
             quarter: "".to_string(),
             security_code,
@@ -57,6 +57,7 @@ impl Profile {
 
 /// 從雅虎抓取指定股票的 profile
 pub async fn visit(stock_symbol: &str) -> Result<Profile> {
+    let symbol = StockSymbol::parse(stock_symbol)?;
     let url = format!("https://{}/quote/{}/profile", HOST, stock_symbol);
     let text = util::http::get(&url, None).await?;
     let document = Html::parse_document(text.as_str());
@@ -67,7 +68,7 @@ pub async fn visit(stock_symbol: &str) -> Result<Profile> {
             return Err(anyhow!("Failed to Selector::parse because: {:?}", why));
         }
     };
-    let mut e = Profile::new(stock_symbol.to_string());
+    let mut e = Profile::new(symbol);
     let css_base = "div.table-grid.Mb\\(20px\\).row-fit-half > div:nth-child";
 
     for element in document.select(&selector) {
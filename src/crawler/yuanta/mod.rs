@@ -1,3 +1,3 @@
 pub mod annual_profit;
 
-const HOST: &str = "jdata.yuanta.com.tw";
+pub(super) const HOST: &str = "jdata.yuanta.com.tw";
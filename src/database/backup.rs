@@ -0,0 +1,245 @@
+use std::{io::Write, process::Stdio};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{Local, NaiveDateTime, TimeDelta};
+use flate2::{write::GzEncoder, Compression};
+use tokio::{
+    io::AsyncReadExt,
+    process::{ChildStdout, Command},
+    sync::mpsc,
+};
+
+use crate::{bot, config::SETTINGS, logging, storage};
+
+const PREFIX: &str = "backups/";
+const KEY_TIME_FORMAT: &str = "%Y%m%d%H%M%S";
+/// 等待上傳的壓縮區塊數上限，上傳較慢時讀取 pg_dump 的輸出會跟著暫停
+const UPLOAD_QUEUE_SIZE: usize = 16;
+
+/// 以 pg_dump 備份資料庫，壓縮後上傳至儲存後端並清除過期的備份，結果以 Telegram 通知
+pub async fn execute() -> Result<()> {
+    if !SETTINGS.load().backup.enabled {
+        return Ok(());
+    }
+
+    let result = backup().await;
+    let msg = match &result {
+        Ok((key, size)) => {
+            let pruned = prune().await.unwrap_or_else(|why| {
+                logging::error_file_async(format!("Failed to prune backups because {:?}", why));
+                0
+            });
+            format!(
+                "資料庫備份完成\r\n檔案:{}\r\n大小:{} KB\r\n清除過期備份:{} 個",
+                key,
+                size / 1024,
+                pruned
+            )
+        }
+        Err(why) => format!("資料庫備份失敗\r\n{:?}", why),
+    };
+
+    // 備份失敗需要立即處理，成功的通知可以等勿擾時段結束
+    if result.is_ok() {
+        bot::telegram::send_deferrable(&msg).await;
+    } else {
+        bot::telegram::send(&msg).await;
+    }
+
+    result.map(|_| ())
+}
+
+/// 執行 pg_dump 並將輸出以 gzip 壓縮後邊壓縮邊上傳，回傳備份檔的 key 與壓縮後的大小
+async fn backup() -> Result<(String, usize)> {
+    let settings = SETTINGS.load();
+    let pg = &settings.postgresql;
+    let pg_dump = if settings.backup.pg_dump_path.is_empty() {
+        "pg_dump"
+    } else {
+        settings.backup.pg_dump_path.as_str()
+    };
+
+    let mut child = Command::new(pg_dump)
+        .args([
+            "--host",
+            &pg.host,
+            "--port",
+            &pg.port.to_string(),
+            "--username",
+            &pg.user,
+            "--dbname",
+            &pg.db,
+            "--no-owner",
+            "--no-password",
+        ])
+        .env("PGPASSWORD", &pg.password)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context(format!("Failed to spawn {}", pg_dump))?;
+
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| anyhow!("Failed to take stdout of pg_dump"))?;
+    let mut stderr = child
+        .stderr
+        .take()
+        .ok_or_else(|| anyhow!("Failed to take stderr of pg_dump"))?;
+    let key = backup_key(&pg.db, Local::now().naive_local());
+    let storage = storage::get_storage();
+    let (tx, rx) = mpsc::channel(UPLOAD_QUEUE_SIZE);
+
+    // stdout 與 stderr 需同時讀取，只讀 stdout 時 pg_dump 可能因 stderr 的管道塞滿而卡住
+    let mut errors = Vec::new();
+    let (compressed, uploaded, read_errors) = tokio::join!(
+        compress(stdout, tx),
+        storage.put_stream(&key, rx),
+        stderr.read_to_end(&mut errors)
+    );
+    let status = child.wait().await?;
+
+    let result = uploaded.and(compressed).and_then(|size| {
+        read_errors?;
+        if status.success() {
+            return Ok(size);
+        }
+
+        Err(anyhow!(
+            "pg_dump exited with {}: {}",
+            status,
+            String::from_utf8_lossy(&errors)
+        ))
+    });
+    let size = match result {
+        Ok(size) => size,
+        Err(why) => {
+            // 不完整的備份檔不能留下，以免之後被誤用來還原
+            if let Err(delete) = storage.delete(&key).await {
+                logging::error_file_async(format!(
+                    "Failed to delete incomplete backup({}) because {:?}",
+                    key, delete
+                ));
+            }
+
+            return Err(why);
+        }
+    };
+
+    logging::info_file_async(format!(
+        "the database has been backed up:{} {} bytes",
+        key, size
+    ));
+
+    Ok((key, size))
+}
+
+/// 將 pg_dump 的輸出以 gzip 壓縮後分段送往儲存後端，回傳壓縮後的大小
+async fn compress(mut stdout: ChildStdout, tx: mpsc::Sender<Vec<u8>>) -> Result<usize> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut size = 0;
+
+    loop {
+        let n = stdout.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+
+        encoder.write_all(&buf[..n])?;
+        let compressed = encoder.get_mut();
+        if compressed.is_empty() {
+            continue;
+        }
+
+        size += compressed.len();
+        tx.send(std::mem::take(compressed))
+            .await
+            .map_err(|_| anyhow!("The storage stopped receiving the backup"))?;
+    }
+
+    let rest = encoder.finish()?;
+    size += rest.len();
+    tx.send(rest)
+        .await
+        .map_err(|_| anyhow!("The storage stopped receiving the backup"))?;
+
+    Ok(size)
+}
+
+/// 刪除超過保留天數的備份檔，回傳刪除的數量
+async fn prune() -> Result<usize> {
+    let retention_days = if SETTINGS.load().backup.retention_days > 0 {
+        SETTINGS.load().backup.retention_days
+    } else {
+        7
+    };
+    let cut_off = Local::now().naive_local()
+        - TimeDelta::try_days(retention_days).ok_or_else(|| anyhow!("Invalid retention days"))?;
+    let storage = storage::get_storage();
+    let mut pruned = 0;
+
+    for key in storage.list(PREFIX).await? {
+        match parse_backup_time(&key) {
+            Some(time) if time < cut_off => {
+                storage.delete(&key).await?;
+                pruned += 1;
+            }
+            _ => continue,
+        }
+    }
+
+    Ok(pruned)
+}
+
+/// backups/{db}_{%Y%m%d%H%M%S}.sql.gz
+fn backup_key(db: &str, time: NaiveDateTime) -> String {
+    format!("{}{}_{}.sql.gz", PREFIX, db, time.format(KEY_TIME_FORMAT))
+}
+
+fn parse_backup_time(key: &str) -> Option<NaiveDateTime> {
+    let name = key.strip_prefix(PREFIX)?.strip_suffix(".sql.gz")?;
+    let (_, time) = name.rsplit_once('_')?;
+
+    NaiveDateTime::parse_from_str(time, KEY_TIME_FORMAT).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_backup_key() {
+        let time = NaiveDate::from_ymd_opt(2024, 3, 9)
+            .unwrap()
+            .and_hms_opt(2, 0, 5)
+            .unwrap();
+        let key = backup_key("stock_db", time);
+
+        assert_eq!(key, "backups/stock_db_20240309020005.sql.gz");
+        assert_eq!(parse_backup_time(&key), Some(time));
+        assert_eq!(parse_backup_time("backups/readme.txt"), None);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_execute() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 execute".to_string());
+
+        match execute().await {
+            Ok(_) => {
+                logging::debug_file_async("execute executed successfully.".to_string());
+            }
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to execute because {:?}", why));
+            }
+        }
+
+        logging::debug_file_async("結束 execute".to_string());
+    }
+}
@@ -0,0 +1,106 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use tokio::time::Instant;
+
+use crate::{database, logging};
+
+/// 健康檢查的間隔
+pub const PROBE_INTERVAL: Duration = Duration::from_secs(15);
+/// 單次健康檢查等待資料庫回應的時間
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 斷路器狀態，false 代表資料庫無法連線，排程任務會延後到恢復後才執行
+static AVAILABLE: AtomicBool = AtomicBool::new(true);
+
+/// 資料庫目前是否可以連線
+pub fn is_available() -> bool {
+    AVAILABLE.load(Ordering::SeqCst)
+}
+
+/// 更新斷路器狀態，狀態有變化時回傳 true
+fn set_available(available: bool) -> bool {
+    AVAILABLE.swap(available, Ordering::SeqCst) != available
+}
+
+/// 以 SELECT 1 確認資料庫可以連線
+pub async fn ping() -> Result<()> {
+    tokio::time::timeout(
+        PROBE_TIMEOUT,
+        sqlx::query("SELECT 1").execute(database::get_connection()),
+    )
+    .await
+    .context("Timed out to ping database")?
+    .context("Failed to ping database")?;
+
+    Ok(())
+}
+
+/// 檢查一次資料庫並更新斷路器狀態，回傳資料庫是否可以連線
+pub async fn probe() -> bool {
+    match ping().await {
+        Ok(_) => {
+            if set_available(true) {
+                logging::info_file_async("資料庫已恢復連線，排程任務恢復執行".to_string());
+            }
+            true
+        }
+        Err(why) => {
+            if set_available(false) {
+                logging::error_file_async(format!(
+                    "資料庫無法連線，排程任務延後到恢復後執行 because {:?}",
+                    why
+                ));
+            }
+            false
+        }
+    }
+}
+
+/// 定期檢查資料庫，斷線時開啟斷路器，恢復後關閉
+pub async fn monitor() {
+    let mut interval = tokio::time::interval(PROBE_INTERVAL);
+    loop {
+        interval.tick().await;
+        probe().await;
+    }
+}
+
+/// 斷路器開啟時等待資料庫恢復連線，超過 limit 仍無法連線時回傳 false
+pub async fn wait_until_available(limit: Duration) -> bool {
+    if is_available() {
+        return true;
+    }
+
+    let deadline = Instant::now() + limit;
+    while Instant::now() < deadline {
+        tokio::time::sleep(PROBE_INTERVAL).await;
+        if probe().await {
+            return true;
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_set_available() {
+        assert!(is_available());
+        assert!(!set_available(true));
+
+        assert!(set_available(false));
+        assert!(!is_available());
+        assert!(!set_available(false));
+
+        assert!(set_available(true));
+        assert!(is_available());
+    }
+}
@@ -1,12 +1,40 @@
-use std::sync::{Arc, OnceLock};
+use std::{
+    future::Future,
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use once_cell::sync::Lazy;
 use sqlx::{postgres::PgPoolOptions, PgPool, Postgres, Transaction};
-
-use crate::config;
-
+use tokio_retry::{
+    strategy::{jitter, ExponentialBackoff},
+    RetryIf,
+};
+
+use crate::{
+    config, error,
+    limits::{self, Stage},
+};
+
+/// 資料庫備份
+pub mod backup;
+/// 連線健康檢查與斷路器
+pub mod health;
+/// 大型資料表的分頁查詢參數
+pub mod page;
+/// DailyQuotes 年度分區的維護
+pub mod partition;
+/// 資料表的存取介面，可替換成記憶體實作讓單元測試不需要資料庫
+pub mod repository;
+/// 定時重新寫入 failed_writes 內寫入失敗的數據
+pub mod retry_queue;
+/// 本機模式使用的 SQLite 資料庫
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
 pub mod table;
+/// 查詢耗時統計與慢查詢記錄
+pub mod timing;
 
 static POSTGRES: Lazy<Arc<OnceLock<PostgresSQL>>> = Lazy::new(|| Arc::new(OnceLock::new()));
 
@@ -21,7 +49,7 @@ pub(super) trait CopyIn: Send {
 pub(super) async fn copy_in_raw(copy_in_query: &str, items: &[impl CopyIn]) -> Result<u64> {
     let data: String = items.iter().map(CopyIn::to_csv).collect();
     let data_as_bytes = data.as_bytes();
-    let mut conn = get_connection().acquire().await?;
+    let mut conn = get_pool().await?.acquire().await?;
     let mut writer = conn.copy_in_raw(copy_in_query).await?;
 
     writer.send(data_as_bytes).await?;
@@ -31,13 +59,14 @@ pub(super) async fn copy_in_raw(copy_in_query: &str, items: &[impl CopyIn]) -> R
 
 impl PostgresSQL {
     pub fn new() -> PostgresSQL {
+        let settings = config::SETTINGS.load();
         let database_url = format!(
             "postgres://{}:{}@{}:{}/{}?application_name=stock_crawler_rust",
-            config::SETTINGS.postgresql.user,
-            config::SETTINGS.postgresql.password,
-            config::SETTINGS.postgresql.host,
-            config::SETTINGS.postgresql.port,
-            config::SETTINGS.postgresql.db
+            settings.postgresql.user,
+            settings.postgresql.password,
+            settings.postgresql.host,
+            settings.postgresql.port,
+            settings.postgresql.db
         );
         let db = PgPoolOptions::new()
             .max_lifetime(None)
@@ -71,6 +100,69 @@ pub fn get_connection() -> &'static PgPool {
     get_postgresql().pool()
 }
 
+/// 資料庫重新啟動時最多重試的次數
+const RECONNECT_ATTEMPTS: usize = 5;
+
+/// 取得確認可以連線的連線池，連線中斷時以指數退避重試，超過次數後回傳錯誤
+pub async fn get_pool() -> Result<&'static PgPool> {
+    let pool = get_connection();
+    let strategy = ExponentialBackoff::from_millis(2)
+        .factor(250)
+        .max_delay(Duration::from_secs(10))
+        .map(jitter)
+        .take(RECONNECT_ATTEMPTS);
+
+    RetryIf::start(
+        strategy,
+        || sqlx::query("SELECT 1").execute(pool),
+        error::is_retryable_database_error,
+    )
+    .await
+    .context("Failed to reconnect to database")?;
+
+    Ok(pool)
+}
+
+/// 關閉連線池，等待借出的連線歸還後結束
+pub async fn close() {
+    if let Some(postgres) = POSTGRES.get() {
+        postgres.pool().close().await;
+    }
+}
+
+/// 序列化失敗或死結時最多重試的次數
+const CONFLICT_RETRY_ATTEMPTS: usize = 3;
+
+/// 執行查詢，遇到序列化失敗(40001)或死結(40P01)時以指數退避重新執行
+/// 每次重試都會呼叫 action 重新建立查詢，不適用於交易內的查詢(整個交易需重來)
+/// 同時執行的數量受設定檔 limits.max_db_writes 限制
+/// ex. `database::with_retry(|| sqlx::query(sql).bind(code).execute(database::get_connection())).await`
+pub async fn with_retry<T, F, Fut>(action: F) -> std::result::Result<T, sqlx::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = std::result::Result<T, sqlx::Error>>,
+{
+    let strategy = ExponentialBackoff::from_millis(2)
+        .factor(25)
+        .max_delay(Duration::from_secs(1))
+        .map(jitter)
+        .take(CONFLICT_RETRY_ATTEMPTS);
+
+    limits::run(
+        Stage::DbWrite,
+        RetryIf::start(strategy, action, is_conflict),
+    )
+    .await
+}
+
+/// 是否為並行寫入造成的序列化失敗或死結
+fn is_conflict(why: &sqlx::Error) -> bool {
+    match why {
+        sqlx::Error::Database(db) => matches!(db.code().as_deref(), Some("40001") | Some("40P01")),
+        _ => false,
+    }
+}
+
 pub async fn get_tx() -> Result<Transaction<'static, Postgres>> {
-    get_postgresql().tx().await
+    Ok(get_pool().await?.begin().await?)
 }
@@ -0,0 +1,154 @@
+use anyhow::{anyhow, Result};
+
+/// 未指定時每頁的筆數
+pub const DEFAULT_LIMIT: i64 = 20;
+/// 每頁最多的筆數，避免一次將整張表載入記憶體
+pub const MAX_LIMIT: i64 = 500;
+
+/// 分頁查詢的參數，order_by 為可排序的欄位名稱，前面加上 - 代表由大到小 ex. `-date`，空字串使用各查詢的預設排序
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+    pub limit: i64,
+    pub offset: i64,
+    pub order_by: String,
+}
+
+impl Default for Page {
+    fn default() -> Self {
+        Page {
+            limit: DEFAULT_LIMIT,
+            offset: 0,
+            order_by: String::new(),
+        }
+    }
+}
+
+impl Page {
+    /// 每頁 limit 筆的第 number 頁(由 1 開始)
+    pub fn nth(number: i64, limit: i64, order_by: &str) -> Self {
+        let limit = clamp_limit(limit);
+        Page {
+            limit,
+            offset: (number.max(1) - 1) * limit,
+            order_by: order_by.to_string(),
+        }
+    }
+
+    /// 限制在 1 到 MAX_LIMIT 之間的筆數
+    pub fn limit(&self) -> i64 {
+        clamp_limit(self.limit)
+    }
+
+    pub fn offset(&self) -> i64 {
+        self.offset.max(0)
+    }
+
+    /// 產生 ORDER BY 後面的排序語法，columns 為(欄位名稱, SQL 欄位)，只允許 columns 內的欄位，避免將輸入直接組進 SQL
+    /// ex. `page.order_clause(&[("date", r#""Date""#)], "-date")` 回傳 `"Date" DESC`
+    pub fn order_clause(&self, columns: &[(&str, &str)], default: &str) -> Result<String> {
+        let order_by = match self.order_by.as_str() {
+            "" => default,
+            order_by => order_by,
+        };
+        let (name, direction) = match order_by.strip_prefix('-') {
+            Some(name) => (name, "DESC"),
+            None => (order_by, "ASC"),
+        };
+
+        columns
+            .iter()
+            .find(|(column, _)| *column == name)
+            .map(|(_, sql)| format!("{} {}", sql, direction))
+            .ok_or_else(|| {
+                anyhow!(
+                    "Unable to order by {}, available columns are {}",
+                    name,
+                    columns
+                        .iter()
+                        .map(|(column, _)| *column)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+    }
+}
+
+fn clamp_limit(limit: i64) -> i64 {
+    limit.clamp(1, MAX_LIMIT)
+}
+
+/// 一頁的查詢結果與符合條件的總筆數
+#[derive(Debug, Clone, PartialEq)]
+pub struct Paged<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+impl<T> Paged<T> {
+    pub fn new(items: Vec<T>, total: i64, page: &Page) -> Self {
+        Paged {
+            items,
+            total,
+            limit: page.limit(),
+            offset: page.offset(),
+        }
+    }
+
+    /// 目前的頁數(由 1 開始)
+    pub fn number(&self) -> i64 {
+        self.offset / self.limit + 1
+    }
+
+    /// 總頁數
+    pub fn pages(&self) -> i64 {
+        (self.total + self.limit - 1) / self.limit
+    }
+
+    /// 之後是否還有數據
+    pub fn has_more(&self) -> bool {
+        self.offset + (self.items.len() as i64) < self.total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    const COLUMNS: [(&str, &str); 2] = [("date", r#""Date""#), ("monthly", r#""Monthly""#)];
+
+    #[test]
+    fn test_order_clause() {
+        let mut page = Page::default();
+        assert_eq!(
+            page.order_clause(&COLUMNS, "-date").unwrap(),
+            r#""Date" DESC"#
+        );
+
+        page.order_by = "monthly".to_string();
+        assert_eq!(
+            page.order_clause(&COLUMNS, "-date").unwrap(),
+            r#""Monthly" ASC"#
+        );
+
+        page.order_by = r#"-"Date"; DROP TABLE "Revenue""#.to_string();
+        assert!(page.order_clause(&COLUMNS, "-date").is_err());
+    }
+
+    #[test]
+    fn test_paged() {
+        let page = Page::nth(3, 10, "");
+        assert_eq!(page.offset(), 20);
+        assert_eq!(Page::nth(0, 10_000, "").limit(), MAX_LIMIT);
+
+        let paged = Paged::new(vec![1; 10], 35, &page);
+        assert_eq!(paged.number(), 3);
+        assert_eq!(paged.pages(), 4);
+        assert!(paged.has_more());
+
+        let last = Paged::new(vec![1; 5], 35, &Page::nth(4, 10, ""));
+        assert!(!last.has_more());
+    }
+}
@@ -0,0 +1,190 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{Datelike, Local};
+
+use crate::{bot, config::SETTINGS, database, logging};
+
+/// 分區的前綴，分區名稱為 "DailyQuotes_YYYY"
+const PREFIX: &str = "DailyQuotes_";
+/// 超過保留年限的分區移到的 schema
+const ARCHIVE_SCHEMA: &str = "archive";
+
+/// 預先建立之後年度的 DailyQuotes 分區，並將超過保留年限的分區移到 archive schema，有異動時以 Telegram 通知
+pub async fn execute() -> Result<()> {
+    if !SETTINGS.load().partition.enabled {
+        return Ok(());
+    }
+
+    if !is_partitioned().await? {
+        return Err(anyhow!(
+            "DailyQuotes is not a partitioned table, run etc/sql/daily_quote_partition.sql first"
+        ));
+    }
+
+    let years_ahead = if SETTINGS.load().partition.years_ahead > 0 {
+        SETTINGS.load().partition.years_ahead
+    } else {
+        1
+    };
+    let existing = fetch_years().await?;
+    let (to_create, to_detach) = plan(
+        &existing,
+        Local::now().year(),
+        years_ahead,
+        SETTINGS.load().partition.retention_years,
+    );
+
+    for year in &to_create {
+        create(*year).await?;
+    }
+
+    for year in &to_detach {
+        detach(*year).await?;
+    }
+
+    if to_create.is_empty() && to_detach.is_empty() {
+        return Ok(());
+    }
+
+    let msg = format!(
+        "DailyQuotes 分區維護完成\r\n新增分區:{:?}\r\n封存分區:{:?}",
+        to_create, to_detach
+    );
+    logging::info_file_async(msg.clone());
+    bot::telegram::send_deferrable(&msg).await;
+
+    Ok(())
+}
+
+/// 依既有分區的年度計算需要建立與封存的年度
+/// 建立今年到今年 + years_ahead 之間缺少的分區，retention_years 大於 0 時封存早於今年 - retention_years 的分區
+fn plan(
+    existing: &[i32],
+    current_year: i32,
+    years_ahead: i32,
+    retention_years: i32,
+) -> (Vec<i32>, Vec<i32>) {
+    let to_create = (current_year..=current_year + years_ahead)
+        .filter(|year| !existing.contains(year))
+        .collect();
+    let to_detach = if retention_years > 0 {
+        let mut years: Vec<i32> = existing
+            .iter()
+            .copied()
+            .filter(|year| *year < current_year - retention_years)
+            .collect();
+        years.sort_unstable();
+        years
+    } else {
+        Vec::new()
+    };
+
+    (to_create, to_detach)
+}
+
+fn partition_name(year: i32) -> String {
+    format!("{}{}", PREFIX, year)
+}
+
+fn parse_year(name: &str) -> Option<i32> {
+    name.strip_prefix(PREFIX)?.parse().ok()
+}
+
+/// DailyQuotes 是否已改為分區表
+async fn is_partitioned() -> Result<bool> {
+    let sql = r#"
+SELECT EXISTS (
+    SELECT 1 FROM pg_partitioned_table WHERE partrelid = 'public."DailyQuotes"'::regclass
+);
+"#;
+    sqlx::query_scalar(sql)
+        .fetch_one(database::get_connection())
+        .await
+        .context("Failed to partition::is_partitioned() from database")
+}
+
+/// 目前掛在 DailyQuotes 底下的分區年度
+async fn fetch_years() -> Result<Vec<i32>> {
+    let sql = r#"
+SELECT c.relname::text
+FROM pg_inherits i
+JOIN pg_class c ON c.oid = i.inhrelid
+WHERE i.inhparent = 'public."DailyQuotes"'::regclass;
+"#;
+    let names: Vec<String> = sqlx::query_scalar(sql)
+        .fetch_all(database::get_connection())
+        .await
+        .context("Failed to partition::fetch_years() from database")?;
+
+    Ok(names.iter().filter_map(|name| parse_year(name)).collect())
+}
+
+async fn create(year: i32) -> Result<()> {
+    let sql = format!(
+        r#"CREATE TABLE IF NOT EXISTS public."{}" PARTITION OF public."DailyQuotes" FOR VALUES FROM ('{}-01-01') TO ('{}-01-01');"#,
+        partition_name(year),
+        year,
+        year + 1
+    );
+    sqlx::query(&sql)
+        .execute(database::get_connection())
+        .await
+        .context(format!(
+            "Failed to partition::create({}) from database",
+            year
+        ))?;
+
+    Ok(())
+}
+
+/// 將分區自 DailyQuotes 卸離後移到 archive schema，資料保留但不再參與查詢
+async fn detach(year: i32) -> Result<()> {
+    let name = partition_name(year);
+    let mut tx = database::get_tx().await?;
+    sqlx::query(&format!("CREATE SCHEMA IF NOT EXISTS {};", ARCHIVE_SCHEMA))
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(&format!(
+        r#"ALTER TABLE public."DailyQuotes" DETACH PARTITION public."{}";"#,
+        name
+    ))
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(&format!(
+        r#"ALTER TABLE public."{}" SET SCHEMA {};"#,
+        name, ARCHIVE_SCHEMA
+    ))
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await.context(format!(
+        "Failed to partition::detach({}) from database",
+        year
+    ))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_plan() {
+        let existing = vec![2019, 2020, 2021, 2022, 2023, 2024];
+
+        assert_eq!(plan(&existing, 2024, 1, 0), (vec![2025], vec![]));
+        assert_eq!(
+            plan(&existing, 2024, 2, 3),
+            (vec![2025, 2026], vec![2019, 2020])
+        );
+        assert_eq!(plan(&[], 2024, 1, 0), (vec![2024, 2025], vec![]));
+    }
+
+    #[test]
+    fn test_partition_name() {
+        assert_eq!(partition_name(2024), "DailyQuotes_2024");
+        assert_eq!(parse_year("DailyQuotes_2024"), Some(2024));
+        assert_eq!(parse_year("DailyQuotes_default"), None);
+        assert_eq!(parse_year("yield_rank"), None);
+    }
+}
@@ -0,0 +1,808 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    future::Future,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, MutexGuard,
+    },
+};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use futures::{stream, StreamExt, TryStreamExt};
+use rust_decimal::Decimal;
+use sqlx::postgres::PgQueryResult;
+
+use crate::{
+    database::table::{
+        adjusted_quote::AdjustedQuote,
+        audit_log::{self, Audit},
+        cash_ledger::CashLedger,
+        corporate_event::CorporateEvent,
+        daily_money_history::DailyMoneyHistory,
+        daily_valuation::DailyValuation,
+        failed_write::Requeue,
+        financial_statement::{self, FinancialStatement},
+        index::Index,
+        insider_shareholding::InsiderShareholding,
+        odd_lot_quote::OddLotQuote,
+        revenue_estimate,
+        risk_metric::RiskMetric,
+        stock_ownership_details::StockOwnershipDetail,
+    },
+    declare::StockSymbol,
+    logging, util,
+    util::map::Keyable,
+};
+
+/// 盤後零股成交行情 odd_lot_quotes 的存取
+#[async_trait]
+pub trait OddLotQuoteRepository: Send + Sync {
+    /// 一次寫入一批，date 與 security_code 為組合鍵，已存在時更新，回傳寫入成功的筆數
+    async fn upsert_batch(&self, quotes: &[OddLotQuote]) -> Result<usize>;
+}
+
+/// 個股日本益比、殖利率及股價淨值比 daily_valuation 的存取
+#[async_trait]
+pub trait DailyValuationRepository: Send + Sync {
+    /// 一次寫入一批，date 與 security_code 為組合鍵，已存在時更新，回傳寫入成功的筆數
+    async fn upsert_batch(&self, valuations: &[DailyValuation]) -> Result<usize>;
+}
+
+/// 成員資金進出 cash_ledger 的存取
+#[async_trait]
+pub trait CashLedgerRepository: Send + Sync {
+    /// 指定日期(含)以前全部成員的資金進出，依成員、日期排序
+    async fn fetch_until(&self, date: NaiveDate) -> Result<Vec<CashLedger>>;
+}
+
+/// 大盤指數 index 的存取
+#[async_trait]
+pub trait IndexRepository: Send + Sync {
+    /// date 與 category 為組合鍵，已存在時更新
+    async fn upsert(&self, index: &Index) -> Result<()>;
+
+    /// 指定指數自 since 到 to(含)之間每日的收盤指數，依日期由舊到新排序
+    async fn fetch_closing(
+        &self,
+        category: &str,
+        since: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<(NaiveDate, Decimal)>>;
+}
+
+/// 還原收盤價 adjusted_quotes 的存取
+#[async_trait]
+pub trait AdjustedQuoteRepository: Send + Sync {
+    /// 股票自 since(含)以後的還原收盤價，依日期由舊到新排序
+    async fn fetch(&self, security_code: &str, since: NaiveDate) -> Result<Vec<AdjustedQuote>>;
+}
+
+/// 每日市值 daily_money_history 的存取
+#[async_trait]
+pub trait DailyMoneyHistoryRepository: Send + Sync {
+    /// from 到 to(含)之間每日的市值，依日期由舊到新排序
+    async fn fetch_between(&self, from: NaiveDate, to: NaiveDate)
+        -> Result<Vec<DailyMoneyHistory>>;
+}
+
+/// 持股明細 stock_ownership_details 的存取
+#[async_trait]
+pub trait StockOwnershipDetailRepository: Send + Sync {
+    /// 目前庫存中的股票代號，依代號排序且不重複
+    async fn fetch_held_symbols(&self) -> Result<Vec<StockSymbol>>;
+}
+
+/// 風險指標 risk_metrics 的存取
+#[async_trait]
+pub trait RiskMetricRepository: Send + Sync {
+    /// date 與 security_code 為組合鍵，已存在時更新
+    async fn upsert(&self, metric: &RiskMetric) -> Result<()>;
+}
+
+/// 月營收 Revenue 的存取
+#[async_trait]
+pub trait RevenueRepository: Send + Sync {
+    /// security_codes 在 months(yyyyMM) 的月營收，回傳 (股票代號, yyyyMM, 月營收)
+    async fn fetch_monthly(
+        &self,
+        security_codes: &[StockSymbol],
+        months: &[i64],
+    ) -> Result<Vec<(StockSymbol, i64, Decimal)>>;
+}
+
+/// 股東會與法說會 corporate_events 的存取
+#[async_trait]
+pub trait CorporateEventRepository: Send + Sync {
+    /// 一次寫入一批，security_code、kind 與 event_date 為組合鍵，已存在時更新，回傳寫入成功的筆數
+    async fn upsert_batch(&self, events: &[CorporateEvent]) -> Result<usize>;
+}
+
+/// 財務報表 financial_statement 的存取
+#[async_trait]
+pub trait FinancialStatementRepository: Send + Sync {
+    /// 指定年度的年報
+    async fn fetch_annual(&self, year: i32) -> Result<Vec<FinancialStatement>>;
+
+    /// 一次寫入一批，security_code、year 與 quarter 為組合鍵，已存在時更新，回傳寫入成功的筆數
+    async fn upsert_batch(&self, statements: &[FinancialStatement]) -> Result<usize>;
+}
+
+/// 董監事持股 insider_shareholding 的存取
+#[async_trait]
+pub trait InsiderShareholdingRepository: Send + Sync {
+    /// 指定月份是否已有數據
+    async fn exists(&self, year: i32, month: i32) -> Result<bool>;
+
+    /// 指定月份全部公司的董監事持股
+    async fn fetch_by_month(&self, year: i32, month: i32) -> Result<Vec<InsiderShareholding>>;
+
+    /// 一次寫入一批，security_code、year、month 為組合鍵，已存在時更新，回傳寫入成功的筆數
+    async fn upsert_batch(&self, shareholdings: &[InsiderShareholding]) -> Result<usize>;
+
+    /// 標記該月的董監事持股較上個月大幅減少
+    async fn flag_large_decrease(
+        &self,
+        shareholding: &InsiderShareholding,
+        previous_shares_held: i64,
+        decrease_percent: Decimal,
+    ) -> Result<()>;
+}
+
+/// 以 Postgres 存取資料表，寫入時一併記錄 audit_log，失敗時放入 failed_writes 等待重試
+pub struct PgRepository {
+    /// 寫入 audit_log 的來源，由呼叫端傳入自己的 module_path!()
+    source: &'static str,
+}
+
+impl PgRepository {
+    /// ex. `PgRepository::new(module_path!())`
+    pub fn new(source: &'static str) -> Self {
+        Self { source }
+    }
+
+    /// 整批寫入後為每個 key 記錄 audit_log，有寫入的 key 影響 1 筆，被 ON CONFLICT 條件略過的為 0 筆
+    fn audit_all(
+        &self,
+        table_name: &str,
+        keys: impl Iterator<Item = String>,
+        written: Vec<String>,
+    ) {
+        let written: HashSet<String> = written.into_iter().collect();
+        let records = keys
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|key| {
+                let rows_affected = u64::from(written.contains(&key));
+                (key, rows_affected)
+            })
+            .collect();
+
+        audit_log::audit_all(table_name, records, self.source);
+    }
+}
+
+/// 整批寫入失敗時(ex. 其中一筆違反限制)改為逐筆寫入，避免一筆數據拖累整批，失敗的只記錄日誌，回傳寫入成功的筆數
+async fn upsert_each<'a, T, F, Fut>(items: &'a [T], upsert: F) -> usize
+where
+    T: Sync,
+    F: Fn(&'a T) -> Fut,
+    Fut: Future<Output = Result<PgQueryResult>>,
+{
+    let written = AtomicUsize::new(0);
+    stream::iter(items)
+        .for_each_concurrent(util::concurrent_limit_16(), |item| {
+            let written = &written;
+            let upsert = upsert(item);
+            async move {
+                match upsert.await {
+                    Ok(_) => {
+                        written.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Err(why) => logging::error_file_async(format!("{:?}", why)),
+                }
+            }
+        })
+        .await;
+
+    written.into_inner()
+}
+
+#[async_trait]
+impl OddLotQuoteRepository for PgRepository {
+    async fn upsert_batch(&self, quotes: &[OddLotQuote]) -> Result<usize> {
+        let key = |date: NaiveDate, security_code: &str| format!("{}-{}", security_code, date);
+        match OddLotQuote::upsert_batch(quotes).await {
+            Ok(written) => {
+                self.audit_all(
+                    "odd_lot_quotes",
+                    quotes.iter().map(|q| key(q.date, &q.security_code)),
+                    written
+                        .iter()
+                        .map(|(date, code)| key(*date, code))
+                        .collect(),
+                );
+                Ok(quotes.len())
+            }
+            Err(why) => {
+                logging::error_file_async(format!("{:?}", why));
+                Ok(upsert_each(quotes, |quote| async move {
+                    let key = key(quote.date, &quote.security_code);
+                    quote
+                        .upsert()
+                        .await
+                        .requeue("odd_lot_quotes", key.clone(), quote)
+                        .audit("odd_lot_quotes", key, self.source)
+                })
+                .await)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl DailyValuationRepository for PgRepository {
+    async fn upsert_batch(&self, valuations: &[DailyValuation]) -> Result<usize> {
+        let key = |date: NaiveDate, security_code: &str| format!("{}-{}", security_code, date);
+        match DailyValuation::upsert_batch(valuations).await {
+            Ok(written) => {
+                self.audit_all(
+                    "daily_valuation",
+                    valuations.iter().map(|dv| key(dv.date, &dv.security_code)),
+                    written
+                        .iter()
+                        .map(|(date, code)| key(*date, code))
+                        .collect(),
+                );
+                Ok(valuations.len())
+            }
+            Err(why) => {
+                logging::error_file_async(format!("{:?}", why));
+                Ok(upsert_each(valuations, |valuation| async move {
+                    let key = key(valuation.date, &valuation.security_code);
+                    valuation
+                        .upsert()
+                        .await
+                        .requeue("daily_valuation", key.clone(), valuation)
+                        .audit("daily_valuation", key, self.source)
+                })
+                .await)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl CashLedgerRepository for PgRepository {
+    async fn fetch_until(&self, date: NaiveDate) -> Result<Vec<CashLedger>> {
+        CashLedger::fetch_until(date).await
+    }
+}
+
+#[async_trait]
+impl IndexRepository for PgRepository {
+    async fn upsert(&self, index: &Index) -> Result<()> {
+        index.upsert().await
+    }
+
+    async fn fetch_closing(
+        &self,
+        category: &str,
+        since: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<(NaiveDate, Decimal)>> {
+        Index::fetch_closing(category, since, to).await
+    }
+}
+
+#[async_trait]
+impl AdjustedQuoteRepository for PgRepository {
+    async fn fetch(&self, security_code: &str, since: NaiveDate) -> Result<Vec<AdjustedQuote>> {
+        AdjustedQuote::fetch(security_code, since).await
+    }
+}
+
+#[async_trait]
+impl DailyMoneyHistoryRepository for PgRepository {
+    async fn fetch_between(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<DailyMoneyHistory>> {
+        DailyMoneyHistory::fetch_between(from, to).await
+    }
+}
+
+#[async_trait]
+impl StockOwnershipDetailRepository for PgRepository {
+    async fn fetch_held_symbols(&self) -> Result<Vec<StockSymbol>> {
+        let symbols: BTreeSet<StockSymbol> = StockOwnershipDetail::stream_held()
+            .map_ok(|detail| detail.security_code)
+            .try_collect()
+            .await?;
+
+        Ok(symbols.into_iter().collect())
+    }
+}
+
+#[async_trait]
+impl RiskMetricRepository for PgRepository {
+    async fn upsert(&self, metric: &RiskMetric) -> Result<()> {
+        metric.upsert().await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RevenueRepository for PgRepository {
+    async fn fetch_monthly(
+        &self,
+        security_codes: &[StockSymbol],
+        months: &[i64],
+    ) -> Result<Vec<(StockSymbol, i64, Decimal)>> {
+        revenue_estimate::fetch_monthly(security_codes, months).await
+    }
+}
+
+#[async_trait]
+impl CorporateEventRepository for PgRepository {
+    async fn upsert_batch(&self, events: &[CorporateEvent]) -> Result<usize> {
+        let key = |security_code: &str, kind: &str, event_date: NaiveDate| {
+            format!("{}-{}-{}", security_code, kind, event_date)
+        };
+        match CorporateEvent::upsert_batch(events).await {
+            Ok(written) => {
+                self.audit_all(
+                    "corporate_events",
+                    events
+                        .iter()
+                        .map(|e| key(&e.security_code, &e.kind, e.event_date)),
+                    written
+                        .iter()
+                        .map(|(code, kind, date)| key(code, kind, *date))
+                        .collect(),
+                );
+                Ok(events.len())
+            }
+            Err(why) => {
+                logging::error_file_async(format!("{:?}", why));
+                Ok(upsert_each(events, |event| async move {
+                    event.upsert().await.audit(
+                        "corporate_events",
+                        key(&event.security_code, &event.kind, event.event_date),
+                        self.source,
+                    )
+                })
+                .await)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl FinancialStatementRepository for PgRepository {
+    async fn fetch_annual(&self, year: i32) -> Result<Vec<FinancialStatement>> {
+        financial_statement::fetch_annual(year).await
+    }
+
+    async fn upsert_batch(&self, statements: &[FinancialStatement]) -> Result<usize> {
+        match FinancialStatement::upsert_batch(statements).await {
+            Ok(written) => {
+                self.audit_all(
+                    "financial_statement",
+                    statements.iter().map(Keyable::key),
+                    written
+                        .iter()
+                        .map(|(code, year, quarter)| format!("{}-{}-{}", code, year, quarter))
+                        .collect(),
+                );
+                Ok(statements.len())
+            }
+            Err(why) => {
+                logging::error_file_async(format!("{:?}", why));
+                Ok(upsert_each(statements, |statement| async move {
+                    let key = statement.key();
+                    statement
+                        .clone()
+                        .upsert()
+                        .await
+                        .requeue("financial_statement", key.clone(), statement)
+                        .audit("financial_statement", key, self.source)
+                })
+                .await)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl InsiderShareholdingRepository for PgRepository {
+    async fn exists(&self, year: i32, month: i32) -> Result<bool> {
+        InsiderShareholding::exists(year, month).await
+    }
+
+    async fn fetch_by_month(&self, year: i32, month: i32) -> Result<Vec<InsiderShareholding>> {
+        InsiderShareholding::fetch_by_month(year, month).await
+    }
+
+    async fn upsert_batch(&self, shareholdings: &[InsiderShareholding]) -> Result<usize> {
+        match InsiderShareholding::upsert_batch(shareholdings).await {
+            Ok(written) => {
+                self.audit_all(
+                    "insider_shareholding",
+                    shareholdings.iter().map(insider_shareholding_key),
+                    written
+                        .iter()
+                        .map(|(code, year, month)| format!("{}-{}{:02}", code, year, month))
+                        .collect(),
+                );
+                Ok(shareholdings.len())
+            }
+            Err(why) => {
+                logging::error_file_async(format!("{:?}", why));
+                Ok(upsert_each(shareholdings, |shareholding| async move {
+                    shareholding.upsert().await.audit(
+                        "insider_shareholding",
+                        insider_shareholding_key(shareholding),
+                        self.source,
+                    )
+                })
+                .await)
+            }
+        }
+    }
+
+    async fn flag_large_decrease(
+        &self,
+        shareholding: &InsiderShareholding,
+        previous_shares_held: i64,
+        decrease_percent: Decimal,
+    ) -> Result<()> {
+        shareholding
+            .flag_large_decrease(previous_shares_held, decrease_percent)
+            .await
+            .audit(
+                "insider_shareholding",
+                insider_shareholding_key(shareholding),
+                self.source,
+            )?;
+
+        Ok(())
+    }
+}
+
+fn insider_shareholding_key(shareholding: &InsiderShareholding) -> String {
+    format!(
+        "{}-{}{:02}",
+        shareholding.security_code, shareholding.year, shareholding.month
+    )
+}
+
+/// 以 (股票代號, 年, 月) 為 key 的月資料
+pub type MonthKey = (StockSymbol, i32, i32);
+
+/// 以記憶體保存數據，單元測試以此取代 PgRepository，不需要連線資料庫
+#[derive(Debug, Default)]
+pub struct MemoryRepository {
+    pub odd_lot_quotes: Mutex<BTreeMap<(NaiveDate, StockSymbol), OddLotQuote>>,
+    pub daily_valuations: Mutex<BTreeMap<(NaiveDate, StockSymbol), DailyValuation>>,
+    /// 依加入的順序視為 cash_ledger 的 serial
+    pub cash_ledgers: Mutex<Vec<CashLedger>>,
+    pub indexes: Mutex<BTreeMap<(String, NaiveDate), Index>>,
+    pub adjusted_quotes: Mutex<Vec<AdjustedQuote>>,
+    pub daily_money_histories: Mutex<Vec<DailyMoneyHistory>>,
+    pub held_symbols: Mutex<BTreeSet<StockSymbol>>,
+    pub risk_metrics: Mutex<BTreeMap<(NaiveDate, String), RiskMetric>>,
+    /// key 為 (股票代號, yyyyMM)
+    pub monthly_revenues: Mutex<BTreeMap<(StockSymbol, i64), Decimal>>,
+    pub corporate_events: Mutex<BTreeMap<(StockSymbol, String, NaiveDate), CorporateEvent>>,
+    /// key 為 FinancialStatement::key()
+    pub financial_statements: Mutex<BTreeMap<String, FinancialStatement>>,
+    pub insider_shareholdings: Mutex<BTreeMap<MonthKey, InsiderShareholding>>,
+    /// 被標記為持股大幅減少的月份，value 為 (上個月的持股, 減少的百分比)
+    pub large_decreases: Mutex<BTreeMap<MonthKey, (i64, Decimal)>>,
+}
+
+impl MemoryRepository {
+    /// 以指定的資金進出建立
+    pub fn with_cash_ledgers(cash_ledgers: Vec<CashLedger>) -> Self {
+        MemoryRepository {
+            cash_ledgers: Mutex::new(cash_ledgers),
+            ..Default::default()
+        }
+    }
+}
+
+/// 取得 mutex 的鎖，name 為錯誤訊息內的資料表名稱
+fn lock<'a, T>(mutex: &'a Mutex<T>, name: &str) -> Result<MutexGuard<'a, T>> {
+    mutex
+        .lock()
+        .map_err(|why| anyhow!("Failed to lock {} because {:?}", name, why))
+}
+
+#[async_trait]
+impl OddLotQuoteRepository for MemoryRepository {
+    async fn upsert_batch(&self, quotes: &[OddLotQuote]) -> Result<usize> {
+        let mut stored = lock(&self.odd_lot_quotes, "odd_lot_quotes")?;
+        for quote in quotes {
+            stored.insert((quote.date, quote.security_code.clone()), quote.clone());
+        }
+
+        Ok(quotes.len())
+    }
+}
+
+#[async_trait]
+impl DailyValuationRepository for MemoryRepository {
+    async fn upsert_batch(&self, valuations: &[DailyValuation]) -> Result<usize> {
+        let mut stored = lock(&self.daily_valuations, "daily_valuations")?;
+        for valuation in valuations {
+            stored.insert(
+                (valuation.date, valuation.security_code.clone()),
+                valuation.clone(),
+            );
+        }
+
+        Ok(valuations.len())
+    }
+}
+
+#[async_trait]
+impl CashLedgerRepository for MemoryRepository {
+    async fn fetch_until(&self, date: NaiveDate) -> Result<Vec<CashLedger>> {
+        let mut ledgers: Vec<CashLedger> = lock(&self.cash_ledgers, "cash_ledgers")?
+            .iter()
+            .filter(|ledger| ledger.date <= date)
+            .cloned()
+            .collect();
+        ledgers.sort_by_key(|ledger| (ledger.member_id, ledger.date));
+
+        Ok(ledgers)
+    }
+}
+
+#[async_trait]
+impl IndexRepository for MemoryRepository {
+    async fn upsert(&self, index: &Index) -> Result<()> {
+        lock(&self.indexes, "indexes")?.insert((index.category.clone(), index.date), index.clone());
+
+        Ok(())
+    }
+
+    async fn fetch_closing(
+        &self,
+        category: &str,
+        since: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<(NaiveDate, Decimal)>> {
+        Ok(lock(&self.indexes, "indexes")?
+            .values()
+            .filter(|index| index.category == category && index.date >= since && index.date <= to)
+            .map(|index| (index.date, index.index))
+            .collect())
+    }
+}
+
+#[async_trait]
+impl AdjustedQuoteRepository for MemoryRepository {
+    async fn fetch(&self, security_code: &str, since: NaiveDate) -> Result<Vec<AdjustedQuote>> {
+        let mut quotes: Vec<AdjustedQuote> = lock(&self.adjusted_quotes, "adjusted_quotes")?
+            .iter()
+            .filter(|quote| quote.security_code == security_code && quote.date >= since)
+            .cloned()
+            .collect();
+        quotes.sort_by_key(|quote| quote.date);
+
+        Ok(quotes)
+    }
+}
+
+#[async_trait]
+impl DailyMoneyHistoryRepository for MemoryRepository {
+    async fn fetch_between(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<DailyMoneyHistory>> {
+        let mut histories: Vec<DailyMoneyHistory> =
+            lock(&self.daily_money_histories, "daily_money_histories")?
+                .iter()
+                .filter(|history| history.date >= from && history.date <= to)
+                .cloned()
+                .collect();
+        histories.sort_by_key(|history| history.date);
+
+        Ok(histories)
+    }
+}
+
+#[async_trait]
+impl StockOwnershipDetailRepository for MemoryRepository {
+    async fn fetch_held_symbols(&self) -> Result<Vec<StockSymbol>> {
+        Ok(lock(&self.held_symbols, "held_symbols")?
+            .iter()
+            .cloned()
+            .collect())
+    }
+}
+
+#[async_trait]
+impl RiskMetricRepository for MemoryRepository {
+    async fn upsert(&self, metric: &RiskMetric) -> Result<()> {
+        lock(&self.risk_metrics, "risk_metrics")?
+            .insert((metric.date, metric.security_code.clone()), metric.clone());
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RevenueRepository for MemoryRepository {
+    async fn fetch_monthly(
+        &self,
+        security_codes: &[StockSymbol],
+        months: &[i64],
+    ) -> Result<Vec<(StockSymbol, i64, Decimal)>> {
+        Ok(lock(&self.monthly_revenues, "monthly_revenues")?
+            .iter()
+            .filter(|((security_code, month), _)| {
+                security_codes.contains(security_code) && months.contains(month)
+            })
+            .map(|((security_code, month), monthly)| (security_code.clone(), *month, *monthly))
+            .collect())
+    }
+}
+
+#[async_trait]
+impl CorporateEventRepository for MemoryRepository {
+    async fn upsert_batch(&self, events: &[CorporateEvent]) -> Result<usize> {
+        let mut stored = lock(&self.corporate_events, "corporate_events")?;
+        for event in events {
+            stored.insert(
+                (
+                    event.security_code.clone(),
+                    event.kind.clone(),
+                    event.event_date,
+                ),
+                event.clone(),
+            );
+        }
+
+        Ok(events.len())
+    }
+}
+
+#[async_trait]
+impl FinancialStatementRepository for MemoryRepository {
+    async fn fetch_annual(&self, year: i32) -> Result<Vec<FinancialStatement>> {
+        Ok(lock(&self.financial_statements, "financial_statements")?
+            .values()
+            .filter(|statement| statement.year == year as i64 && statement.quarter.is_empty())
+            .cloned()
+            .collect())
+    }
+
+    async fn upsert_batch(&self, statements: &[FinancialStatement]) -> Result<usize> {
+        let mut stored = lock(&self.financial_statements, "financial_statements")?;
+        for statement in statements {
+            stored.insert(statement.key(), statement.clone());
+        }
+
+        Ok(statements.len())
+    }
+}
+
+#[async_trait]
+impl InsiderShareholdingRepository for MemoryRepository {
+    async fn exists(&self, year: i32, month: i32) -> Result<bool> {
+        Ok(lock(&self.insider_shareholdings, "insider_shareholdings")?
+            .values()
+            .any(|shareholding| shareholding.year == year && shareholding.month == month))
+    }
+
+    async fn fetch_by_month(&self, year: i32, month: i32) -> Result<Vec<InsiderShareholding>> {
+        Ok(lock(&self.insider_shareholdings, "insider_shareholdings")?
+            .values()
+            .filter(|shareholding| shareholding.year == year && shareholding.month == month)
+            .cloned()
+            .collect())
+    }
+
+    async fn upsert_batch(&self, shareholdings: &[InsiderShareholding]) -> Result<usize> {
+        let mut stored = lock(&self.insider_shareholdings, "insider_shareholdings")?;
+        for shareholding in shareholdings {
+            stored.insert(
+                (
+                    shareholding.security_code.clone(),
+                    shareholding.year,
+                    shareholding.month,
+                ),
+                shareholding.clone(),
+            );
+        }
+
+        Ok(shareholdings.len())
+    }
+
+    async fn flag_large_decrease(
+        &self,
+        shareholding: &InsiderShareholding,
+        previous_shares_held: i64,
+        decrease_percent: Decimal,
+    ) -> Result<()> {
+        lock(&self.large_decreases, "large_decreases")?.insert(
+            (
+                shareholding.security_code.clone(),
+                shareholding.year,
+                shareholding.month,
+            ),
+            (previous_shares_held, decrease_percent),
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, day).unwrap()
+    }
+
+    fn ledger(member_id: i64, day: u32, amount: rust_decimal::Decimal) -> CashLedger {
+        CashLedger {
+            member_id,
+            date: date(day),
+            amount,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_repository() {
+        let repository = MemoryRepository::with_cash_ledgers(vec![
+            ledger(2, 3, dec!(100)),
+            ledger(1, 5, dec!(-50)),
+            ledger(1, 2, dec!(300)),
+            ledger(1, 9, dec!(10)),
+        ]);
+
+        let ledgers = CashLedgerRepository::fetch_until(&repository, date(5))
+            .await
+            .unwrap();
+        assert_eq!(
+            ledgers,
+            vec![
+                ledger(1, 2, dec!(300)),
+                ledger(1, 5, dec!(-50)),
+                ledger(2, 3, dec!(100))
+            ]
+        );
+
+        let mut quote = OddLotQuote {
+            date: date(2),
+            security_code: "2330".parse().unwrap(),
+            closing_price: dec!(590),
+            ..Default::default()
+        };
+        let first = quote.clone();
+        quote.closing_price = dec!(593);
+        let written = OddLotQuoteRepository::upsert_batch(&repository, &[first, quote])
+            .await
+            .unwrap();
+        assert_eq!(written, 2);
+
+        let quotes = repository.odd_lot_quotes.lock().unwrap();
+        assert_eq!(quotes.len(), 1);
+        assert_eq!(
+            quotes[&(date(2), "2330".parse().unwrap())].closing_price,
+            dec!(593)
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use anyhow::{anyhow, Context, Result};
+use serde::de::DeserializeOwned;
+use sqlx::postgres::PgQueryResult;
+
+use crate::{
+    database::table::{
+        audit_log::Audit, daily_valuation::DailyValuation, failed_write::FailedWrite,
+        financial_statement::FinancialStatement, odd_lot_quote::OddLotQuote, revenue::Revenue,
+    },
+    logging,
+};
+
+/// 重新寫入失敗達此次數的記錄不再自動重試，留在 failed_writes 等待以 /failed 指令檢視或刪除
+pub const MAX_ATTEMPTS: i32 = 5;
+
+/// 每次排程最多重試的筆數
+const BATCH_SIZE: i64 = 500;
+
+/// 重新寫入 failed_writes 內的數據，成功的記錄刪除，失敗的累加重試次數
+pub async fn execute() -> Result<()> {
+    let (succeeded, failed) = retry_pending().await?;
+    if succeeded + failed > 0 {
+        logging::info_file_async(format!(
+            "重試 failed_writes 結束，成功:{} 失敗:{}",
+            succeeded, failed
+        ));
+    }
+
+    Ok(())
+}
+
+/// 重試尚未達上限的記錄，回傳(成功筆數, 失敗筆數)
+pub async fn retry_pending() -> Result<(usize, usize)> {
+    let pending = FailedWrite::fetch_pending(MAX_ATTEMPTS, BATCH_SIZE).await?;
+    let mut succeeded = 0;
+    let mut failed = 0;
+
+    for entry in pending {
+        match replay(&entry).await {
+            Ok(_) => {
+                succeeded += 1;
+                FailedWrite::delete(entry.serial).await?;
+            }
+            Err(why) => {
+                failed += 1;
+                FailedWrite::record_attempt(entry.serial, &format!("{:?}", why)).await?;
+            }
+        }
+    }
+
+    Ok((succeeded, failed))
+}
+
+/// 依資料表名稱還原數據後重新寫入
+async fn replay(entry: &FailedWrite) -> Result<PgQueryResult> {
+    let result = match entry.table_name.as_str() {
+        "revenue" => decode::<Revenue>(entry)?.upsert().await,
+        "odd_lot_quotes" => decode::<OddLotQuote>(entry)?.upsert().await,
+        "daily_valuation" => decode::<DailyValuation>(entry)?.upsert().await,
+        "financial_statement" => decode::<FinancialStatement>(entry)?.upsert().await,
+        table_name => return Err(anyhow!("{} does not support retry", table_name)),
+    };
+
+    result.audit(&entry.table_name, entry.record_key.clone(), module_path!())
+}
+
+fn decode<T: DeserializeOwned>(entry: &FailedWrite) -> Result<T> {
+    serde_json::from_str(&entry.payload).context(format!(
+        "Failed to deserialize failed_writes {} {}",
+        entry.table_name, entry.record_key
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use rust_decimal_macros::dec;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let valuation = DailyValuation {
+            date: NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+            security_code: "2330".parse().unwrap(),
+            stock_exchange_id: 1,
+            price_earning_ratio: dec!(16.53),
+            price_to_book_ratio: dec!(4.87),
+            dividend_yield: dec!(1.86),
+        };
+        let entry = FailedWrite::new(
+            "daily_valuation",
+            "2330-2024-01-02".to_string(),
+            &valuation,
+            String::new(),
+        )
+        .unwrap();
+
+        assert_eq!(decode::<DailyValuation>(&entry).unwrap(), valuation);
+        assert!(decode::<OddLotQuote>(&entry).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_replay_unsupported() {
+        let entry = FailedWrite {
+            table_name: "stocks".to_string(),
+            ..Default::default()
+        };
+
+        assert!(replay(&entry)
+            .await
+            .unwrap_err()
+            .to_string()
+            .contains("stocks"));
+    }
+}
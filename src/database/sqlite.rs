@@ -0,0 +1,188 @@
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    SqlitePool,
+};
+
+use crate::{
+    config::SETTINGS,
+    database::table::{daily_quote::DailyQuote, dividend::Dividend, revenue::Revenue},
+};
+
+/// 未設定 sqlite.path 時使用的資料庫檔案
+const DEFAULT_PATH: &str = "stock_crawler.db";
+/// 本機模式的資料表定義
+const SCHEMA: &str = include_str!("../../etc/sql/sqlite/schema.sql");
+
+/// 開啟設定檔 sqlite.path 指定的資料庫，檔案不存在時建立並補上資料表
+pub async fn connect() -> Result<SqlitePool> {
+    let path = match SETTINGS.load().sqlite.path.as_str() {
+        "" => DEFAULT_PATH,
+        path => path,
+    };
+
+    open(path).await
+}
+
+async fn open(path: &str) -> Result<SqlitePool> {
+    let options = SqliteConnectOptions::from_str(&format!("sqlite://{}", path))
+        .context(format!("Invalid sqlite path {}", path))?
+        .create_if_missing(true);
+    // SQLite 同時只允許一個寫入者，單一連線即可
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await
+        .context(format!("Failed to open sqlite database {}", path))?;
+
+    sqlx::raw_sql(SCHEMA)
+        .execute(&pool)
+        .await
+        .context("Failed to create sqlite tables")?;
+
+    Ok(pool)
+}
+
+/// 寫入收盤報價，同一天已存在的報價會被覆蓋
+pub async fn save_quotes(pool: &SqlitePool, quotes: &[DailyQuote]) -> Result<u64> {
+    let sql = r#"
+INSERT OR REPLACE INTO daily_quotes (
+    security_code, date, opening_price, highest_price, lowest_price, closing_price,
+    change, change_range, trading_volume, trade_value, "transaction")
+VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11);
+"#;
+    let mut tx = pool.begin().await?;
+    let mut rows = 0;
+    for dq in quotes {
+        rows += sqlx::query(sql)
+            .bind(&dq.security_code)
+            .bind(dq.date.to_string())
+            .bind(dq.opening_price.to_string())
+            .bind(dq.highest_price.to_string())
+            .bind(dq.lowest_price.to_string())
+            .bind(dq.closing_price.to_string())
+            .bind(dq.change.to_string())
+            .bind(dq.change_range.to_string())
+            .bind(dq.trading_volume.to_string())
+            .bind(dq.trade_value.to_string())
+            .bind(dq.transaction.to_string())
+            .execute(&mut *tx)
+            .await
+            .context(format!(
+                "Failed to save_quotes({}, {}) to sqlite",
+                dq.security_code, dq.date
+            ))?
+            .rows_affected();
+    }
+    tx.commit().await?;
+
+    Ok(rows)
+}
+
+/// 寫入月營收，同一個月份已存在的營收會被覆蓋
+pub async fn save_revenues(pool: &SqlitePool, revenues: &[Revenue]) -> Result<u64> {
+    let sql = r#"
+INSERT OR REPLACE INTO revenues (
+    security_code, date, monthly, last_month, last_year_this_month, monthly_accumulated,
+    last_year_monthly_accumulated, compared_with_last_month,
+    compared_with_last_year_same_month, accumulated_compared_with_last_year)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10);
+"#;
+    let mut tx = pool.begin().await?;
+    let mut rows = 0;
+    for revenue in revenues {
+        rows += sqlx::query(sql)
+            .bind(&revenue.security_code)
+            .bind(revenue.date)
+            .bind(revenue.monthly.to_string())
+            .bind(revenue.last_month.to_string())
+            .bind(revenue.last_year_this_month.to_string())
+            .bind(revenue.monthly_accumulated.to_string())
+            .bind(revenue.last_year_monthly_accumulated.to_string())
+            .bind(revenue.compared_with_last_month.to_string())
+            .bind(revenue.compared_with_last_year_same_month.to_string())
+            .bind(revenue.accumulated_compared_with_last_year.to_string())
+            .execute(&mut *tx)
+            .await
+            .context(format!(
+                "Failed to save_revenues({}, {}) to sqlite",
+                revenue.security_code, revenue.date
+            ))?
+            .rows_affected();
+    }
+    tx.commit().await?;
+
+    Ok(rows)
+}
+
+/// 寫入股利，同一個股利年度與季度已存在的股利會被覆蓋
+pub async fn save_dividends(pool: &SqlitePool, dividends: &[Dividend]) -> Result<u64> {
+    let sql = r#"
+INSERT OR REPLACE INTO dividends (
+    security_code, year, year_of_dividend, quarter, earnings_cash_dividend,
+    capital_reserve_cash_dividend, cash_dividend, earnings_stock_dividend,
+    capital_reserve_stock_dividend, stock_dividend, sum, payout_ratio_cash,
+    payout_ratio_stock, payout_ratio)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14);
+"#;
+    let mut tx = pool.begin().await?;
+    let mut rows = 0;
+    for dividend in dividends {
+        rows += sqlx::query(sql)
+            .bind(&dividend.security_code)
+            .bind(dividend.year)
+            .bind(dividend.year_of_dividend)
+            .bind(&dividend.quarter)
+            .bind(dividend.earnings_cash_dividend.to_string())
+            .bind(dividend.capital_reserve_cash_dividend.to_string())
+            .bind(dividend.cash_dividend.to_string())
+            .bind(dividend.earnings_stock_dividend.to_string())
+            .bind(dividend.capital_reserve_stock_dividend.to_string())
+            .bind(dividend.stock_dividend.to_string())
+            .bind(dividend.sum.to_string())
+            .bind(dividend.payout_ratio_cash.to_string())
+            .bind(dividend.payout_ratio_stock.to_string())
+            .bind(dividend.payout_ratio.to_string())
+            .execute(&mut *tx)
+            .await
+            .context(format!(
+                "Failed to save_dividends({}, {}) to sqlite",
+                dividend.security_code, dividend.year_of_dividend
+            ))?
+            .rows_affected();
+    }
+    tx.commit().await?;
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use rust_decimal_macros::dec;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[tokio::test]
+    async fn test_save_quotes() {
+        let pool = open(":memory:").await.unwrap();
+        let mut dq = DailyQuote::new("2330".to_string());
+        dq.date = NaiveDate::from_ymd_opt(2024, 6, 12).unwrap();
+        dq.closing_price = dec!(900.5);
+
+        assert_eq!(save_quotes(&pool, &[dq.clone()]).await.unwrap(), 1);
+        dq.closing_price = dec!(905);
+        save_quotes(&pool, &[dq]).await.unwrap();
+
+        let (count, closing_price): (i64, String) =
+            sqlx::query_as("SELECT COUNT(*), CAST(MAX(closing_price) AS TEXT) FROM daily_quotes")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(closing_price, "905");
+    }
+}
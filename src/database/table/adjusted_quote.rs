@@ -0,0 +1,104 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use sqlx::{postgres::PgQueryResult, FromRow};
+
+use crate::{
+    database::{self, timing::Timed, CopyIn},
+    declare::StockSymbol,
+};
+
+const COPY_IN_QUERY: &str = r#"COPY adjusted_quotes(
+    security_code,
+    date,
+    closing_price,
+    adjustment_factor,
+    adjusted_closing_price) FROM STDIN WITH (FORMAT CSV)"#;
+
+/// 依除權息還原的收盤價 原表名 adjusted_quotes
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct AdjustedQuote {
+    pub security_code: StockSymbol,
+    pub date: NaiveDate,
+    /// 原始收盤價
+    pub closing_price: Decimal,
+    /// 調整係數
+    pub adjustment_factor: Decimal,
+    /// 還原收盤價
+    pub adjusted_closing_price: Decimal,
+}
+
+impl CopyIn for AdjustedQuote {
+    fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{},{}\n",
+            self.security_code,
+            self.date,
+            self.closing_price,
+            self.adjustment_factor,
+            self.adjusted_closing_price
+        )
+    }
+}
+
+impl AdjustedQuote {
+    /// 以新的還原價格取代股票原有的數據
+    pub async fn replace(security_code: &str, quotes: &[Self]) -> Result<u64> {
+        sqlx::query("DELETE FROM adjusted_quotes WHERE security_code = $1")
+            .bind(security_code)
+            .execute(database::get_connection())
+            .timed("adjusted_quotes", "replace")
+            .await
+            .context(format!(
+                "Failed to AdjustedQuote::replace({}) from database",
+                security_code
+            ))?;
+
+        database::copy_in_raw(COPY_IN_QUERY, quotes).await
+    }
+
+    /// 將指定日期的收盤價以調整係數 1 寫入，已存在時保留原有的係數並重算還原價
+    pub async fn upsert_by_date(date: NaiveDate) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO adjusted_quotes (security_code, date, closing_price, adjustment_factor, adjusted_closing_price)
+SELECT "SecurityCode", "Date", "ClosingPrice", 1, "ClosingPrice"
+FROM "DailyQuotes"
+WHERE "Date" = $1
+ON CONFLICT (security_code, date) DO UPDATE SET
+    closing_price = EXCLUDED.closing_price,
+    adjusted_closing_price = round(EXCLUDED.closing_price * adjusted_quotes.adjustment_factor, 4),
+    updated_time = now();
+"#;
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(date)
+                .execute(database::get_connection())
+        })
+        .timed("adjusted_quotes", "upsert_by_date")
+        .await
+        .context(format!(
+            "Failed to AdjustedQuote::upsert_by_date({}) from database",
+            date
+        ))
+    }
+
+    /// 取得股票自 since(含)以後的還原收盤價，依日期由舊到新排序
+    pub async fn fetch(security_code: &str, since: NaiveDate) -> Result<Vec<Self>> {
+        let sql = r#"
+SELECT security_code, date, closing_price, adjustment_factor, adjusted_closing_price
+FROM adjusted_quotes
+WHERE security_code = $1 AND date >= $2
+ORDER BY date;
+"#;
+        sqlx::query_as::<_, Self>(sql)
+            .bind(security_code)
+            .bind(since)
+            .fetch_all(database::get_connection())
+            .timed("adjusted_quotes", "fetch")
+            .await
+            .context(format!(
+                "Failed to AdjustedQuote::fetch({}, {}) from database",
+                security_code, since
+            ))
+    }
+}
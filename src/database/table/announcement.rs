@@ -0,0 +1,108 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, TimeZone};
+use sqlx::FromRow;
+
+use crate::{crawler::twse, database, declare::StockSymbol, util};
+
+/// 上市公司重大訊息 原表名 announcement
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct Announcement {
+    pub security_code: StockSymbol,
+    pub name: String,
+    /// 發言時間
+    pub announced_time: DateTime<Local>,
+    /// 主旨
+    pub subject: String,
+    /// 符合條款
+    pub clause: String,
+    /// 事實發生日
+    pub occurred_date: Option<NaiveDate>,
+    /// 說明
+    pub description: String,
+}
+
+impl Announcement {
+    /// 寫入資料庫，已存在時不做任何事，回傳是否為新的重大訊息
+    pub async fn insert(&self) -> Result<bool> {
+        let sql = r#"
+INSERT INTO announcement (security_code, name, announced_time, subject, clause, occurred_date, description)
+VALUES ($1, $2, $3, $4, $5, $6, $7)
+ON CONFLICT (security_code, announced_time, md5(subject)) DO NOTHING;
+"#;
+        let result = sqlx::query(sql)
+            .bind(&self.security_code)
+            .bind(&self.name)
+            .bind(self.announced_time)
+            .bind(&self.subject)
+            .bind(&self.clause)
+            .bind(self.occurred_date)
+            .bind(&self.description)
+            .execute(database::get_connection())
+            .await
+            .context(format!(
+                "Failed to Announcement::insert({:?}) from database",
+                self
+            ))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+//let entity: Announcement = fs.into(); // 或者 let entity = Announcement::from(fs);
+impl TryFrom<twse::announcement::Announcement> for Announcement {
+    type Error = anyhow::Error;
+
+    fn try_from(item: twse::announcement::Announcement) -> Result<Self> {
+        let date = util::datetime::parse_taiwan_date(&item.announcement_date).context(format!(
+            "Failed to parse 發言日期 {}",
+            item.announcement_date
+        ))?;
+        let time =
+            NaiveTime::parse_from_str(item.announcement_time.trim(), "%H%M%S").unwrap_or_default();
+        let announced_time = Local
+            .from_local_datetime(&date.and_time(time))
+            .single()
+            .context(format!("Failed to convert {} {} to local time", date, time))?;
+
+        Ok(Announcement {
+            security_code: StockSymbol::parse(&item.stock_symbol)?,
+            name: item.name.trim().to_string(),
+            announced_time,
+            subject: item.subject.trim().to_string(),
+            clause: item.clause.trim().to_string(),
+            occurred_date: util::datetime::parse_taiwan_date(&item.occurred_date),
+            description: item.description,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_try_from() {
+        let item = twse::announcement::Announcement {
+            announcement_date: "1130102".to_string(),
+            announcement_time: "173012".to_string(),
+            stock_symbol: "1101".to_string(),
+            name: "台泥".to_string(),
+            subject: "公告本公司董事會決議辦理減資".to_string(),
+            clause: "第11款".to_string(),
+            occurred_date: "1130102".to_string(),
+            description: "".to_string(),
+        };
+
+        let announcement = Announcement::try_from(item).unwrap();
+
+        assert_eq!(
+            announcement.announced_time.naive_local().to_string(),
+            "2024-01-02 17:30:12"
+        );
+        assert_eq!(
+            announcement.occurred_date,
+            NaiveDate::from_ymd_opt(2024, 1, 2)
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use anyhow::{Context, Result};
+use chrono::Local;
+use once_cell::sync::Lazy;
+use sqlx::postgres::PgQueryResult;
+
+use crate::{
+    database,
+    logging::{self, run_id},
+};
+
+/// 不在排程任務內時使用的執行批次識別碼，同一個行程寫入的記錄相同
+static RUN_ID: Lazy<String> = Lazy::new(|| Local::now().format("%Y%m%d%H%M%S%3f").to_string());
+
+/// 回補模組寫入資料的記錄
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AuditLog {
+    pub table_name: String,
+    pub record_key: String,
+    pub source: String,
+    pub rows_affected: i64,
+    pub run_id: String,
+}
+
+impl AuditLog {
+    pub fn new(table_name: &str, record_key: String, source: &str, rows_affected: u64) -> Self {
+        AuditLog {
+            table_name: table_name.to_string(),
+            record_key,
+            source: source.to_string(),
+            rows_affected: rows_affected as i64,
+            run_id: run_id::current().unwrap_or_else(|| RUN_ID.clone()),
+        }
+    }
+
+    pub async fn insert(&self) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO audit_log (table_name, record_key, source, rows_affected, run_id)
+VALUES ($1, $2, $3, $4, $5);
+"#;
+        sqlx::query(sql)
+            .bind(&self.table_name)
+            .bind(&self.record_key)
+            .bind(&self.source)
+            .bind(self.rows_affected)
+            .bind(&self.run_id)
+            .execute(database::get_connection())
+            .await
+            .context(format!(
+                "Failed to AuditLog::insert({:?}) from database",
+                self
+            ))
+    }
+
+    /// 以 UNNEST 將多筆記錄合併成一個 INSERT
+    pub async fn insert_all(logs: &[AuditLog]) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO audit_log (table_name, record_key, source, rows_affected, run_id)
+SELECT * FROM UNNEST($1::varchar[], $2::varchar[], $3::varchar[], $4::bigint[], $5::varchar[]);
+"#;
+        sqlx::query(sql)
+            .bind(
+                logs.iter()
+                    .map(|l| l.table_name.clone())
+                    .collect::<Vec<_>>(),
+            )
+            .bind(
+                logs.iter()
+                    .map(|l| l.record_key.clone())
+                    .collect::<Vec<_>>(),
+            )
+            .bind(logs.iter().map(|l| l.source.clone()).collect::<Vec<_>>())
+            .bind(logs.iter().map(|l| l.rows_affected).collect::<Vec<_>>())
+            .bind(logs.iter().map(|l| l.run_id.clone()).collect::<Vec<_>>())
+            .execute(database::get_connection())
+            .await
+            .context(format!(
+                "Failed to AuditLog::insert_all({} rows) from database",
+                logs.len()
+            ))
+    }
+}
+
+/// 批次寫入成功後在背景為每一筆數據記錄 audit_log，records 為 (key, 影響的筆數)
+pub fn audit_all(table_name: &str, records: Vec<(String, u64)>, source: &str) {
+    let logs: Vec<AuditLog> = records
+        .into_iter()
+        .map(|(key, rows_affected)| AuditLog::new(table_name, key, source, rows_affected))
+        .collect();
+    if logs.is_empty() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        if let Err(why) = AuditLog::insert_all(&logs).await {
+            logging::error_file_async(format!("{:?}", why));
+        }
+    });
+}
+
+/// 寫入成功時在背景記錄 audit_log，不影響原本的結果
+/// ex. `revenue.upsert().await.audit("revenue", revenue.security_code.clone(), module_path!())?`
+pub trait Audit {
+    fn audit(self, table_name: &str, record_key: String, source: &str) -> Self;
+}
+
+impl Audit for Result<PgQueryResult> {
+    fn audit(self, table_name: &str, record_key: String, source: &str) -> Self {
+        if let Ok(result) = &self {
+            let log = AuditLog::new(table_name, record_key, source, result.rows_affected());
+            tokio::spawn(async move {
+                if let Err(why) = log.insert().await {
+                    logging::error_file_async(format!("{:?}", why));
+                }
+            });
+        }
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        let log = AuditLog::new("revenue", "2330-202405".to_string(), module_path!(), 1);
+
+        assert_eq!(log.table_name, "revenue");
+        assert_eq!(log.record_key, "2330-202405");
+        assert_eq!(
+            log.source,
+            "stock_crawler::database::table::audit_log::tests"
+        );
+        assert_eq!(log.rows_affected, 1);
+        assert_eq!(log.run_id, *RUN_ID);
+        assert_eq!(
+            log.run_id,
+            AuditLog::new("dividend", String::new(), "", 0).run_id
+        );
+    }
+}
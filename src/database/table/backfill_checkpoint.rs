@@ -0,0 +1,42 @@
+use anyhow::{Context, Result};
+use sqlx::postgres::PgQueryResult;
+
+use crate::database;
+
+/// 歷史數據回補的進度 原表名 backfill_checkpoints
+pub struct BackfillCheckpoint;
+
+impl BackfillCheckpoint {
+    /// 取得回補最後完成的位置，尚未執行過時為 None
+    pub async fn fetch(name: &str) -> Result<Option<String>> {
+        let sql = "SELECT checkpoint FROM backfill_checkpoints WHERE name = $1";
+        sqlx::query_scalar(sql)
+            .bind(name)
+            .fetch_optional(database::get_connection())
+            .await
+            .context(format!(
+                "Failed to BackfillCheckpoint::fetch({}) from database",
+                name
+            ))
+    }
+
+    /// 記錄回補已完成到 checkpoint
+    pub async fn save(name: &str, checkpoint: &str) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO backfill_checkpoints (name, checkpoint)
+VALUES ($1, $2)
+ON CONFLICT (name) DO UPDATE SET
+    checkpoint = EXCLUDED.checkpoint,
+    updated_time = now();
+"#;
+        sqlx::query(sql)
+            .bind(name)
+            .bind(checkpoint)
+            .execute(database::get_connection())
+            .await
+            .context(format!(
+                "Failed to BackfillCheckpoint::save({}, {}) from database",
+                name, checkpoint
+            ))
+    }
+}
@@ -0,0 +1,98 @@
+use anyhow::{Context, Result};
+use sqlx::{postgres::PgQueryResult, FromRow};
+
+use crate::database::{self, timing::Timed};
+
+/// Telegram 使用者可以使用的指令角色 原表名 bot_roles
+#[derive(FromRow, Debug, Clone, Default, PartialEq)]
+pub struct BotRole {
+    /// Telegram 使用者編號
+    pub user_id: i64,
+    /// viewer、trader 或 admin
+    pub role: String,
+    /// 備註 ex. 使用者的稱呼
+    pub note: String,
+}
+
+impl BotRole {
+    pub fn new(user_id: i64, role: &str, note: String) -> Self {
+        BotRole {
+            user_id,
+            role: role.to_string(),
+            note,
+        }
+    }
+
+    /// 取得全部使用者的角色，依使用者編號排序
+    pub async fn fetch_all() -> Result<Vec<BotRole>> {
+        let sql = "SELECT user_id, role, note FROM bot_roles ORDER BY user_id;";
+        sqlx::query_as::<_, BotRole>(sql)
+            .fetch_all(database::get_connection())
+            .timed("bot_roles", "fetch_all")
+            .await
+            .context("Failed to BotRole::fetch_all from database")
+    }
+
+    /// 以使用者編號新增或更新角色
+    pub async fn upsert(&self) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO bot_roles (user_id, role, note)
+VALUES ($1, $2, $3)
+ON CONFLICT (user_id) DO UPDATE SET
+    role = EXCLUDED.role,
+    note = EXCLUDED.note,
+    updated_time = now();
+"#;
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(self.user_id)
+                .bind(&self.role)
+                .bind(&self.note)
+                .execute(database::get_connection())
+        })
+        .timed("bot_roles", "upsert")
+        .await
+        .context(format!(
+            "Failed to BotRole::upsert({}) from database",
+            self.user_id
+        ))
+    }
+
+    /// 刪除使用者的角色
+    pub async fn delete(user_id: i64) -> Result<PgQueryResult> {
+        let sql = "DELETE FROM bot_roles WHERE user_id = $1;";
+        sqlx::query(sql)
+            .bind(user_id)
+            .execute(database::get_connection())
+            .timed("bot_roles", "delete")
+            .await
+            .context(format!(
+                "Failed to BotRole::delete({}) from database",
+                user_id
+            ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logging;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_fetch_all() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 BotRole::fetch_all".to_string());
+
+        match BotRole::fetch_all().await {
+            Ok(roles) => logging::debug_file_async(format!("data:{:#?}", roles)),
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to BotRole::fetch_all because {:?}", why))
+            }
+        }
+
+        logging::debug_file_async("結束 BotRole::fetch_all".to_string());
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::HashSet;
+
+use anyhow::{Context, Result};
+use sqlx::{postgres::PgQueryResult, FromRow};
+
+use crate::{
+    database::{self, timing::Timed},
+    declare::StockSymbol,
+};
+
+/// 聊天室以 /subscribe 訂閱的個股通知 原表名 bot_subscriptions
+#[derive(FromRow, Debug, Clone, Default, PartialEq)]
+pub struct BotSubscription {
+    /// Telegram 聊天室編號
+    pub chat_id: i64,
+    pub security_code: StockSymbol,
+    /// revenue、dividend 或 announcement
+    pub event: String,
+}
+
+impl BotSubscription {
+    pub fn new(chat_id: i64, security_code: StockSymbol, event: &str) -> Self {
+        BotSubscription {
+            chat_id,
+            security_code,
+            event: event.to_string(),
+        }
+    }
+
+    /// 取得全部聊天室的訂閱，依聊天室、股票代號排序
+    pub async fn fetch_all() -> Result<Vec<BotSubscription>> {
+        let sql = r#"
+SELECT chat_id, security_code, event
+FROM bot_subscriptions
+ORDER BY chat_id, security_code, event;
+"#;
+        sqlx::query_as::<_, BotSubscription>(sql)
+            .fetch_all(database::get_connection())
+            .timed("bot_subscriptions", "fetch_all")
+            .await
+            .context("Failed to BotSubscription::fetch_all from database")
+    }
+
+    /// 取得任一聊天室訂閱了指定事件的股票
+    pub async fn fetch_symbols(event: &str) -> Result<HashSet<StockSymbol>> {
+        let sql = "SELECT DISTINCT security_code FROM bot_subscriptions WHERE event = $1;";
+        let symbols: Vec<StockSymbol> = sqlx::query_scalar(sql)
+            .bind(event)
+            .fetch_all(database::get_connection())
+            .timed("bot_subscriptions", "fetch_symbols")
+            .await
+            .context(format!(
+                "Failed to BotSubscription::fetch_symbols({}) from database",
+                event
+            ))?;
+
+        Ok(symbols.into_iter().collect())
+    }
+
+    /// 新增訂閱，已訂閱時不做任何事
+    pub async fn insert(&self) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO bot_subscriptions (chat_id, security_code, event)
+VALUES ($1, $2, $3)
+ON CONFLICT (chat_id, security_code, event) DO NOTHING;
+"#;
+        sqlx::query(sql)
+            .bind(self.chat_id)
+            .bind(&self.security_code)
+            .bind(&self.event)
+            .execute(database::get_connection())
+            .timed("bot_subscriptions", "insert")
+            .await
+            .context(format!(
+                "Failed to BotSubscription::insert({:?}) from database",
+                self
+            ))
+    }
+
+    /// 刪除聊天室對指定股票的訂閱，events 為空時刪除該股票全部的事件
+    pub async fn delete(
+        chat_id: i64,
+        security_code: &str,
+        events: &[String],
+    ) -> Result<PgQueryResult> {
+        let sql = r#"
+DELETE FROM bot_subscriptions
+WHERE chat_id = $1
+  AND security_code = $2
+  AND (cardinality($3::varchar[]) = 0 OR event = ANY($3));
+"#;
+        sqlx::query(sql)
+            .bind(chat_id)
+            .bind(security_code)
+            .bind(events)
+            .execute(database::get_connection())
+            .timed("bot_subscriptions", "delete")
+            .await
+            .context(format!(
+                "Failed to BotSubscription::delete({}, {}) from database",
+                chat_id, security_code
+            ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logging;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_fetch_all() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 BotSubscription::fetch_all".to_string());
+
+        match BotSubscription::fetch_all().await {
+            Ok(subscriptions) => logging::debug_file_async(format!("data:{:#?}", subscriptions)),
+            Err(why) => logging::debug_file_async(format!(
+                "Failed to BotSubscription::fetch_all because {:?}",
+                why
+            )),
+        }
+
+        logging::debug_file_async("結束 BotSubscription::fetch_all".to_string());
+    }
+}
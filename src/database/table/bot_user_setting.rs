@@ -0,0 +1,103 @@
+use anyhow::{Context, Result};
+use chrono::NaiveTime;
+use sqlx::{postgres::PgQueryResult, FromRow};
+
+use crate::database::{self, timing::Timed};
+
+/// 聊天室以 /settings 指令設定的偏好 原表名 bot_user_settings
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct BotUserSetting {
+    /// Telegram 聊天室編號，私人聊天室與使用者編號相同
+    pub chat_id: i64,
+    /// 接收通知的語系，空字串時使用設定檔的語系
+    pub language: String,
+    /// /allocation、/xirr 預設查詢的成員，0 表示全部成員
+    pub member_id: i64,
+    /// 勿擾時段，都為 None 時使用設定檔的勿擾時段
+    pub quiet_start: Option<NaiveTime>,
+    pub quiet_end: Option<NaiveTime>,
+    /// 是否接收彙整後的警示摘要
+    pub digest: bool,
+}
+
+impl BotUserSetting {
+    pub fn new(chat_id: i64) -> Self {
+        BotUserSetting {
+            chat_id,
+            language: String::new(),
+            member_id: 0,
+            quiet_start: None,
+            quiet_end: None,
+            digest: true,
+        }
+    }
+
+    /// 取得全部聊天室的偏好
+    pub async fn fetch_all() -> Result<Vec<BotUserSetting>> {
+        let sql = r#"
+SELECT chat_id, language, member_id, quiet_start, quiet_end, digest
+FROM bot_user_settings;
+"#;
+        sqlx::query_as::<_, BotUserSetting>(sql)
+            .fetch_all(database::get_connection())
+            .timed("bot_user_settings", "fetch_all")
+            .await
+            .context("Failed to BotUserSetting::fetch_all from database")
+    }
+
+    /// 以聊天室編號新增或更新偏好
+    pub async fn upsert(&self) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO bot_user_settings (chat_id, language, member_id, quiet_start, quiet_end, digest)
+VALUES ($1, $2, $3, $4, $5, $6)
+ON CONFLICT (chat_id) DO UPDATE SET
+    language = EXCLUDED.language,
+    member_id = EXCLUDED.member_id,
+    quiet_start = EXCLUDED.quiet_start,
+    quiet_end = EXCLUDED.quiet_end,
+    digest = EXCLUDED.digest,
+    updated_time = now();
+"#;
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(self.chat_id)
+                .bind(&self.language)
+                .bind(self.member_id)
+                .bind(self.quiet_start)
+                .bind(self.quiet_end)
+                .bind(self.digest)
+                .execute(database::get_connection())
+        })
+        .timed("bot_user_settings", "upsert")
+        .await
+        .context(format!(
+            "Failed to BotUserSetting::upsert({}) from database",
+            self.chat_id
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logging;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_fetch_all() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 BotUserSetting::fetch_all".to_string());
+
+        match BotUserSetting::fetch_all().await {
+            Ok(settings) => logging::debug_file_async(format!("data:{:#?}", settings)),
+            Err(why) => logging::debug_file_async(format!(
+                "Failed to BotUserSetting::fetch_all because {:?}",
+                why
+            )),
+        }
+
+        logging::debug_file_async("結束 BotUserSetting::fetch_all".to_string());
+    }
+}
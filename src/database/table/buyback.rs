@@ -0,0 +1,185 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use sqlx::{postgres::PgQueryResult, FromRow};
+
+use crate::{crawler::twse, database, declare::StockSymbol, util};
+
+/// 上市公司庫藏股買回計畫 原表名 buyback
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct Buyback {
+    pub security_code: StockSymbol,
+    pub name: String,
+    /// 董事會決議日期
+    pub resolution_date: NaiveDate,
+    /// 買回目的
+    pub purpose: String,
+    /// 預定買回股數(股)
+    pub target_shares: i64,
+    /// 買回價格區間-最低(元)
+    pub price_low: Decimal,
+    /// 買回價格區間-最高(元)
+    pub price_high: Decimal,
+    /// 預定買回期間-起
+    pub start_date: Option<NaiveDate>,
+    /// 預定買回期間-迄
+    pub end_date: Option<NaiveDate>,
+    /// 已買回股數(股)
+    pub executed_shares: i64,
+    /// 是否執行完畢
+    pub completed: bool,
+}
+
+impl Buyback {
+    /// security_code、resolution_date 為組合鍵 unique
+    pub async fn upsert(&self) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO buyback (
+    security_code, name, resolution_date, purpose, target_shares, price_low, price_high,
+    start_date, end_date, executed_shares, completed)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+ON CONFLICT (security_code, resolution_date) DO UPDATE SET
+    name = EXCLUDED.name,
+    purpose = EXCLUDED.purpose,
+    target_shares = EXCLUDED.target_shares,
+    price_low = EXCLUDED.price_low,
+    price_high = EXCLUDED.price_high,
+    start_date = EXCLUDED.start_date,
+    end_date = EXCLUDED.end_date,
+    executed_shares = EXCLUDED.executed_shares,
+    completed = EXCLUDED.completed,
+    updated_time = now();
+"#;
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(&self.security_code)
+                .bind(&self.name)
+                .bind(self.resolution_date)
+                .bind(&self.purpose)
+                .bind(self.target_shares)
+                .bind(self.price_low)
+                .bind(self.price_high)
+                .bind(self.start_date)
+                .bind(self.end_date)
+                .bind(self.executed_shares)
+                .bind(self.completed)
+                .execute(database::get_connection())
+        })
+        .await
+        .context(format!(
+            "Failed to Buyback::upsert({:?}) from database",
+            self
+        ))
+    }
+
+    /// 取得資料庫內指定股票與董事會決議日期的買回計畫
+    pub async fn fetch_one(
+        security_code: &str,
+        resolution_date: NaiveDate,
+    ) -> Result<Option<Buyback>> {
+        let sql = r#"
+SELECT security_code, name, resolution_date, purpose, target_shares, price_low, price_high,
+    start_date, end_date, executed_shares, completed
+FROM buyback
+WHERE security_code = $1 AND resolution_date = $2;
+"#;
+        sqlx::query_as::<_, Buyback>(sql)
+            .bind(security_code)
+            .bind(resolution_date)
+            .fetch_optional(database::get_connection())
+            .await
+            .context(format!(
+                "Failed to Buyback::fetch_one({}, {}) from database",
+                security_code, resolution_date
+            ))
+    }
+
+    /// 已買回股數佔預定買回股數的百分比
+    pub fn execution_rate(&self) -> Decimal {
+        if self.target_shares <= 0 {
+            return Decimal::ZERO;
+        }
+
+        (Decimal::from(self.executed_shares) / Decimal::from(self.target_shares) * dec!(100))
+            .round_dp(2)
+    }
+}
+
+//let entity: Buyback = fs.into(); // 或者 let entity = Buyback::from(fs);
+impl TryFrom<twse::buyback::Buyback> for Buyback {
+    type Error = anyhow::Error;
+
+    fn try_from(item: twse::buyback::Buyback) -> Result<Self> {
+        let resolution_date = util::datetime::parse_taiwan_date(&item.resolution_date).context(
+            format!("Failed to parse 董事會決議日期 {}", item.resolution_date),
+        )?;
+
+        Ok(Buyback {
+            security_code: StockSymbol::parse(&item.stock_symbol)?,
+            name: item.name.trim().to_string(),
+            resolution_date,
+            purpose: item.purpose.trim().to_string(),
+            target_shares: util::text::parse_i64(&item.target_shares, None).unwrap_or(0),
+            price_low: util::text::parse_decimal(&item.price_low, None).unwrap_or_default(),
+            price_high: util::text::parse_decimal(&item.price_high, None).unwrap_or_default(),
+            start_date: util::datetime::parse_taiwan_date(&item.start_date),
+            end_date: util::datetime::parse_taiwan_date(&item.end_date),
+            executed_shares: util::text::parse_i64(&item.executed_shares, None).unwrap_or(0),
+            completed: matches!(item.completed.trim(), "Y" | "y" | "是"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logging;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_try_from() {
+        let item = twse::buyback::Buyback {
+            stock_symbol: "2330".to_string(),
+            name: "台積電".to_string(),
+            resolution_date: "1130213".to_string(),
+            purpose: "轉讓股份予員工".to_string(),
+            target_shares: "2,000,000".to_string(),
+            price_low: "500".to_string(),
+            price_high: "800".to_string(),
+            start_date: "1130214".to_string(),
+            end_date: "1130413".to_string(),
+            executed_shares: "500,000".to_string(),
+            completed: "Y".to_string(),
+        };
+
+        let buyback = Buyback::try_from(item).unwrap();
+
+        assert_eq!(
+            buyback.resolution_date,
+            NaiveDate::from_ymd_opt(2024, 2, 13).unwrap()
+        );
+        assert_eq!(buyback.target_shares, 2_000_000);
+        assert_eq!(buyback.price_high, dec!(800));
+        assert_eq!(buyback.end_date, NaiveDate::from_ymd_opt(2024, 4, 13));
+        assert!(buyback.completed);
+        assert_eq!(buyback.execution_rate(), dec!(25));
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_fetch_one() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 Buyback::fetch_one".to_string());
+
+        match Buyback::fetch_one("2330", NaiveDate::from_ymd_opt(2024, 2, 13).unwrap()).await {
+            Ok(buyback) => logging::debug_file_async(format!("data:{:#?}", buyback)),
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to Buyback::fetch_one because {:?}", why))
+            }
+        }
+
+        logging::debug_file_async("結束 Buyback::fetch_one".to_string());
+    }
+}
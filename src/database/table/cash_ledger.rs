@@ -0,0 +1,36 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use sqlx::FromRow;
+
+use crate::database::{self, timing::Timed};
+
+/// 成員投入與提出的資金 原表名 cash_ledger
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct CashLedger {
+    pub member_id: i64,
+    pub date: NaiveDate,
+    /// 金額，投入為正數、提出為負數
+    pub amount: Decimal,
+}
+
+impl CashLedger {
+    /// 取得指定日期(含)以前全部成員的資金進出，依成員、日期排序
+    pub async fn fetch_until(date: NaiveDate) -> Result<Vec<CashLedger>> {
+        let sql = r#"
+SELECT member_id, date, amount
+FROM cash_ledger
+WHERE date <= $1
+ORDER BY member_id, date, serial;
+"#;
+        sqlx::query_as::<_, CashLedger>(sql)
+            .bind(date)
+            .fetch_all(database::get_connection())
+            .timed("cash_ledger", "fetch_until")
+            .await
+            .context(format!(
+                "Failed to CashLedger::fetch_until({}) from database",
+                date
+            ))
+    }
+}
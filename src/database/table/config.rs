@@ -39,15 +39,17 @@ VALUES
     ($1, $2)
 ON CONFLICT (key)
 DO UPDATE SET val = excluded.val;"#;
-        sqlx::query(sql)
-            .bind(&self.key)
-            .bind(&self.val)
-            .execute(database::get_connection())
-            .await
-            .context(format!(
-                "Failed to Config::upsert({:#?}) from database",
-                self
-            ))
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(&self.key)
+                .bind(&self.val)
+                .execute(database::get_connection())
+        })
+        .await
+        .context(format!(
+            "Failed to Config::upsert({:#?}) from database",
+            self
+        ))
     }
 
     pub async fn set_val_as_naive_date(&self) -> Result<PgQueryResult> {
@@ -0,0 +1,131 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use sqlx::{postgres::PgQueryResult, FromRow};
+
+use crate::{
+    database::{self, timing::Timed},
+    declare::StockSymbol,
+    util,
+};
+
+/// 股東常會或臨時會
+pub const SHAREHOLDER_MEETING: &str = "shareholder_meeting";
+/// 法人說明會
+pub const EARNINGS_CALL: &str = "earnings_call";
+
+/// 公司的股東會與法人說明會等事件 原表名 corporate_events
+#[derive(FromRow, Debug, Clone, Default, PartialEq)]
+pub struct CorporateEvent {
+    pub security_code: StockSymbol,
+    /// 事件類別 SHAREHOLDER_MEETING、EARNINGS_CALL
+    pub kind: String,
+    pub event_date: NaiveDate,
+    /// 事件的說明 ex. 常會或臨時會、法說會的時間地點
+    pub description: String,
+}
+
+impl CorporateEvent {
+    pub fn new(
+        security_code: StockSymbol,
+        kind: &str,
+        event_date: NaiveDate,
+        description: String,
+    ) -> Self {
+        CorporateEvent {
+            security_code,
+            kind: kind.to_string(),
+            event_date,
+            description,
+        }
+    }
+
+    /// 事件類別的中文名稱
+    pub fn kind_name(&self) -> &str {
+        match self.kind.as_str() {
+            SHAREHOLDER_MEETING => "股東會",
+            EARNINGS_CALL => "法說會",
+            kind => kind,
+        }
+    }
+
+    /// security_code、kind 與 event_date 為組合鍵 unique
+    pub async fn upsert(&self) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO corporate_events (security_code, kind, event_date, description)
+VALUES ($1, $2, $3, $4)
+ON CONFLICT (security_code, kind, event_date) DO UPDATE SET
+    description = EXCLUDED.description,
+    updated_time = now();
+"#;
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(&self.security_code)
+                .bind(&self.kind)
+                .bind(self.event_date)
+                .bind(&self.description)
+                .execute(database::get_connection())
+        })
+        .timed("corporate_events", "upsert")
+        .await
+        .context(format!(
+            "Failed to CorporateEvent::upsert({}, {}, {}) from database",
+            self.security_code, self.kind, self.event_date
+        ))
+    }
+
+    /// 以 UNNEST 將一批事件合併成一個 INSERT，同一個事件重複時以最後一筆為準，回傳有寫入的 (股票代號, 類別, 日期)
+    pub async fn upsert_batch(
+        events: &[CorporateEvent],
+    ) -> Result<Vec<(String, String, NaiveDate)>> {
+        let sql = r#"
+INSERT INTO corporate_events (security_code, kind, event_date, description)
+SELECT * FROM UNNEST($1::varchar[], $2::varchar[], $3::date[], $4::text[])
+ON CONFLICT (security_code, kind, event_date) DO UPDATE SET
+    description = EXCLUDED.description,
+    updated_time = now()
+RETURNING security_code, kind, event_date;
+"#;
+        let events = util::map::dedup_by_key(events, |e| {
+            (e.security_code.clone(), e.kind.clone(), e.event_date)
+        });
+        database::with_retry(|| {
+            sqlx::query_as::<_, (String, String, NaiveDate)>(sql)
+                .bind(util::map::column(&events, |e| e.security_code.clone()))
+                .bind(util::map::column(&events, |e| e.kind.clone()))
+                .bind(util::map::column(&events, |e| e.event_date))
+                .bind(util::map::column(&events, |e| e.description.clone()))
+                .fetch_all(database::get_connection())
+        })
+        .timed("corporate_events", "upsert_batch")
+        .await
+        .context(format!(
+            "Failed to CorporateEvent::upsert_batch({} rows) from database",
+            events.len()
+        ))
+    }
+
+    /// 取得指定股票在 start 至 end(含)之間的事件，依日期、股票代號排序
+    pub async fn fetch_between(
+        symbols: &[String],
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<CorporateEvent>> {
+        let sql = r#"
+SELECT security_code, kind, event_date, description
+FROM corporate_events
+WHERE security_code = ANY($1) AND event_date BETWEEN $2 AND $3
+ORDER BY event_date, security_code, kind;
+"#;
+        sqlx::query_as::<_, CorporateEvent>(sql)
+            .bind(symbols)
+            .bind(start)
+            .bind(end)
+            .fetch_all(database::get_connection())
+            .timed("corporate_events", "fetch_between")
+            .await
+            .context(format!(
+                "Failed to CorporateEvent::fetch_between({}, {}) from database",
+                start, end
+            ))
+    }
+}
@@ -1,14 +1,14 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use chrono::{DateTime, Local, NaiveDate};
 use rust_decimal::Decimal;
 use sqlx::{postgres::PgQueryResult, Postgres, Transaction};
 
-use crate::database;
+use crate::database::{self, timing::Timed};
 
 pub(crate) mod extension;
 
 /// 每日市值變化歷史記錄
-#[derive(sqlx::FromRow, Debug)]
+#[derive(sqlx::FromRow, Debug, Clone)]
 pub struct DailyMoneyHistory {
     pub date: NaiveDate,
     pub created_at: DateTime<Local>,
@@ -25,6 +25,7 @@ impl DailyMoneyHistory {
                 .bind(date.year())
                 .bind(date.format("%Y-%m-%d").to_string())
                 .fetch_all(database::get_connection())
+                .timed("daily_money_history", "fetch")
                 .await
                 .context(format!(
                     "Failed to fetch_stocks_with_dividends_on_date({}) from database",
@@ -32,6 +33,45 @@ impl DailyMoneyHistory {
                 ))
         }
     */
+    /// 取得指定日期(含)以前最近一個交易日的市值
+    pub async fn fetch_on_or_before(date: NaiveDate) -> Result<Option<DailyMoneyHistory>> {
+        let sql = r#"
+select date, sum, eddie, unice, created_time as created_at, updated_time as updated_at
+from daily_money_history
+where date <= $1
+order by date desc
+limit 1;"#;
+        sqlx::query_as::<_, DailyMoneyHistory>(sql)
+            .bind(date)
+            .fetch_optional(database::get_connection())
+            .timed("daily_money_history", "fetch_on_or_before")
+            .await
+            .context(format!(
+                "Failed to DailyMoneyHistory::fetch_on_or_before({}) from database",
+                date
+            ))
+    }
+
+    /// 取得 from 到 to(含)之間每日的市值，依日期由舊到新排序
+    pub async fn fetch_between(from: NaiveDate, to: NaiveDate) -> Result<Vec<DailyMoneyHistory>> {
+        let sql = r#"
+select date, sum, eddie, unice, created_time as created_at, updated_time as updated_at
+from daily_money_history
+where date between $1 and $2
+order by date;"#;
+        sqlx::query_as::<_, DailyMoneyHistory>(sql)
+            .bind(from)
+            .bind(to)
+            .fetch_all(database::get_connection())
+            .timed("daily_money_history", "fetch_between")
+            .await
+            .context(format!(
+                "Failed to DailyMoneyHistory::fetch_between({}, {}) from database",
+                from, to
+            ))
+    }
+
+    /// 在 calculate_money_history 的交易內執行，不以 database::with_retry 重試
     pub async fn upsert(
         date: NaiveDate,
         tx: &mut Option<Transaction<'_, Postgres>>,
@@ -78,8 +118,18 @@ ON CONFLICT (date) DO UPDATE SET
 
         let query = sqlx::query(&sql).bind(date);
         let result = match tx {
-            None => query.execute(database::get_connection()).await,
-            Some(t) => query.execute(&mut **t).await,
+            None => {
+                query
+                    .execute(database::get_connection())
+                    .timed("daily_money_history", "upsert")
+                    .await
+            }
+            Some(t) => {
+                query
+                    .execute(&mut **t)
+                    .timed("daily_money_history", "upsert")
+                    .await
+            }
         };
 
         match result {
@@ -2,14 +2,17 @@ use anyhow::{Context, Result};
 use chrono::{DateTime, Local, NaiveDate, TimeDelta};
 use sqlx::{Postgres, postgres::PgQueryResult, Transaction};
 
-use crate::database;
+use crate::{
+    database::{self, timing::Timed},
+    declare::StockSymbol,
+};
 
 #[derive(sqlx::FromRow, Default, Debug)]
 pub struct DailyMoneyHistoryDetail {
     pub date: NaiveDate,
     pub created_time: DateTime<Local>,
     pub updated_time: DateTime<Local>,
-    pub security_code: String,
+    pub security_code: StockSymbol,
     pub total_shares: i64,
     pub serial: i64,
     pub previous_day_market_value: f64,
@@ -34,8 +37,18 @@ impl DailyMoneyHistoryDetail {
         let sql = "DELETE FROM daily_money_history_detail WHERE date = $1;";
         let query = sqlx::query(sql).bind(date);
         let result = match tx {
-            None => query.execute(database::get_connection()).await,
-            Some(t) => query.execute(&mut **t).await,
+            None => {
+                query
+                    .execute(database::get_connection())
+                    .timed("daily_money_history_detail", "delete")
+                    .await
+            }
+            Some(t) => {
+                query
+                    .execute(&mut **t)
+                    .timed("daily_money_history_detail", "delete")
+                    .await
+            }
         };
 
         result.context(format!(
@@ -44,6 +57,7 @@ impl DailyMoneyHistoryDetail {
         ))
     }
 
+    /// 在 calculate_money_history 的交易內執行，不以 database::with_retry 重試
     pub async fn upsert(
         date: NaiveDate,
         tx: &mut Option<Transaction<'_, Postgres>>,
@@ -231,8 +245,18 @@ ON CONFLICT (date, security_code, member_id) DO UPDATE SET
 
         let query = sqlx::query(&sql).bind(date);
         let result = match tx {
-            None => query.execute(database::get_connection()).await,
-            Some(t) => query.execute(&mut **t).await,
+            None => {
+                query
+                    .execute(database::get_connection())
+                    .timed("daily_money_history_detail", "upsert")
+                    .await
+            }
+            Some(t) => {
+                query
+                    .execute(&mut **t)
+                    .timed("daily_money_history_detail", "upsert")
+                    .await
+            }
         };
 
         result.context(format!(
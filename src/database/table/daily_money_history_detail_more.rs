@@ -3,7 +3,10 @@ use chrono::NaiveDate;
 use rust_decimal::Decimal;
 use sqlx::{postgres::PgQueryResult, Postgres, Transaction};
 
-use crate::database;
+use crate::{
+    database::{self, timing::Timed},
+    declare::StockSymbol,
+};
 
 #[derive(Debug, sqlx::FromRow)]
 pub struct DailyMoneyHistoryDetailMore {
@@ -11,7 +14,7 @@ pub struct DailyMoneyHistoryDetailMore {
     pub member_id: i64,
     pub date: NaiveDate,
     pub transaction_date: NaiveDate,
-    pub security_code: String,
+    pub security_code: StockSymbol,
     pub closing_price: Decimal,
     pub number_of_shares_held: i64,
     pub unit_price_per_share: Decimal,
@@ -31,8 +34,18 @@ impl DailyMoneyHistoryDetailMore {
         let sql = "DELETE FROM daily_money_history_detail_more WHERE date = $1;";
         let query = sqlx::query(sql).bind(date);
         let result = match tx {
-            None => query.execute(database::get_connection()).await,
-            Some(t) => query.execute(&mut **t).await,
+            None => {
+                query
+                    .execute(database::get_connection())
+                    .timed("daily_money_history_detail_more", "delete")
+                    .await
+            }
+            Some(t) => {
+                query
+                    .execute(&mut **t)
+                    .timed("daily_money_history_detail_more", "delete")
+                    .await
+            }
         };
 
         result.context(format!(
@@ -41,6 +54,7 @@ impl DailyMoneyHistoryDetailMore {
         ))
     }
 
+    /// 在 calculate_money_history 的交易內執行，不以 database::with_retry 重試
     pub async fn upsert(
         date: NaiveDate,
         tx: &mut Option<Transaction<'_, Postgres>>,
@@ -124,8 +138,18 @@ ORDER BY
 
         let query = sqlx::query(&sql).bind(date);
         let result = match tx {
-            None => query.execute(database::get_connection()).await,
-            Some(t) => query.execute(&mut **t).await,
+            None => {
+                query
+                    .execute(database::get_connection())
+                    .timed("daily_money_history_detail_more", "upsert")
+                    .await
+            }
+            Some(t) => {
+                query
+                    .execute(&mut **t)
+                    .timed("daily_money_history_detail_more", "upsert")
+                    .await
+            }
         };
 
         result.context(format!(
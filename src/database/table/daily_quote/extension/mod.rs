@@ -1,5 +1,7 @@
 use rust_decimal::Decimal;
 
+use crate::declare::StockSymbol;
+
 #[derive(sqlx::Type, sqlx::FromRow, Default, Debug)]
 pub struct MonthlyStockPriceSummary {
     /// 最高價
@@ -9,3 +11,84 @@ pub struct MonthlyStockPriceSummary {
     /// 平均價
     pub avg_price: Decimal,
 }
+
+/// 繪製 K 線圖所需的每日價格與均線
+#[derive(sqlx::Type, sqlx::FromRow, Default, Debug, Clone)]
+pub struct DailyPrice {
+    pub date: chrono::NaiveDate,
+    /// 開盤價
+    pub opening_price: Decimal,
+    /// 最高價
+    pub highest_price: Decimal,
+    /// 最低價
+    pub lowest_price: Decimal,
+    /// 收盤價
+    pub closing_price: Decimal,
+    pub moving_average_20: Decimal,
+    pub moving_average_60: Decimal,
+}
+
+/// 庫存股票在一段期間的收盤價變化
+#[derive(sqlx::Type, sqlx::FromRow, Default, Debug, Clone, PartialEq)]
+pub struct PriceChange {
+    pub stock_symbol: StockSymbol,
+    pub name: String,
+    /// 期初收盤價
+    pub start_price: Decimal,
+    /// 期末收盤價
+    pub end_price: Decimal,
+}
+
+/// 當日的漲跌與成交量，用於漲跌幅排行與爆量的報表
+#[derive(sqlx::Type, sqlx::FromRow, Default, Debug, Clone, PartialEq)]
+pub struct DailyMover {
+    pub stock_symbol: StockSymbol,
+    pub name: String,
+    /// 收盤價
+    pub closing_price: Decimal,
+    /// 漲跌價差
+    pub change: Decimal,
+    /// 漲跌幅(%)
+    pub change_range: Decimal,
+    /// 成交股數
+    pub trading_volume: Decimal,
+    /// 前 20 個交易日的平均成交股數
+    pub average_volume_20: Decimal,
+    /// 是否為庫存中(未賣出)的股票
+    pub is_held: bool,
+}
+
+/// 計算策略訊號所需的每日收盤價與均線
+#[derive(sqlx::Type, sqlx::FromRow, Default, Debug, Clone, PartialEq)]
+pub struct SignalBar {
+    pub security_code: StockSymbol,
+    pub date: chrono::NaiveDate,
+    /// 收盤價
+    pub closing_price: Decimal,
+    pub moving_average_5: Decimal,
+    pub moving_average_10: Decimal,
+    pub moving_average_20: Decimal,
+    pub moving_average_60: Decimal,
+    pub moving_average_120: Decimal,
+    pub moving_average_240: Decimal,
+}
+
+impl SignalBar {
+    /// 可使用的均線天數
+    pub const MOVING_AVERAGE_DAYS: [u32; 6] = [5, 10, 20, 60, 120, 240];
+
+    /// 指定天數的均線，沒有該天數的均線或尚未計算(0)時回傳 None
+    pub fn moving_average(&self, days: u32) -> Option<Decimal> {
+        let value = match days {
+            5 => self.moving_average_5,
+            10 => self.moving_average_10,
+            20 => self.moving_average_20,
+            60 => self.moving_average_60,
+            120 => self.moving_average_120,
+            240 => self.moving_average_240,
+            _ => return None,
+        };
+
+        (value > Decimal::ZERO).then_some(value)
+    }
+}
@@ -2,6 +2,8 @@ use std::fmt::Write;
 
 use anyhow::{Context, Result};
 use chrono::{DateTime, Local, NaiveDate, TimeDelta};
+use futures::{Stream, StreamExt};
+use once_cell::sync::Lazy;
 use rust_decimal::Decimal;
 use sqlx::{postgres::PgQueryResult, Row};
 
@@ -9,9 +11,12 @@ use crate::{
     database::{
         self,
         CopyIn,
-        table::daily_quote::extension::MonthlyStockPriceSummary
+        page::{Page, Paged},
+        timing::Timed,
+        table::daily_quote::extension::{DailyMover, DailyPrice, MonthlyStockPriceSummary, PriceChange, SignalBar},
+        table::stock,
     },
-    declare::StockExchange,
+    declare::{StockExchange, StockExchangeMarket, StockSymbol},
     util::{datetime, map::Keyable}
 };
 
@@ -63,7 +68,7 @@ pub struct DailyQuote {
     pub transaction: Decimal,
     /// 股價淨值比=每股股價 ÷ 每股淨值
     pub price_to_book_ratio: Decimal,
-    pub security_code: String,
+    pub security_code: StockSymbol,
     pub serial: i64,
     pub year: i32,
     pub month: i32,
@@ -122,7 +127,7 @@ impl Keyable for DailyQuote {
 }
 
 impl DailyQuote {
-    pub fn new(security_code: String) -> Self {
+    pub fn new(security_code: StockSymbol) -> Self {
         DailyQuote {
             security_code,
             ..Default::default()
@@ -226,46 +231,49 @@ impl DailyQuote {
             "TradeValue" = excluded."TradeValue",
             "Transaction" = excluded."Transaction"
     "#;
-        sqlx::query(sql)
-            .bind(self.maximum_price_in_year_date_on)
-            .bind(self.minimum_price_in_year_date_on)
-            .bind(self.date)
-            .bind(self.create_time)
-            .bind(self.record_time)
-            .bind(self.price_earning_ratio)
-            .bind(self.moving_average_60)
-            .bind(self.closing_price)
-            .bind(self.change_range)
-            .bind(self.change)
-            .bind(self.last_best_bid_price)
-            .bind(self.last_best_bid_volume)
-            .bind(self.last_best_ask_price)
-            .bind(self.last_best_ask_volume)
-            .bind(self.moving_average_5)
-            .bind(self.moving_average_10)
-            .bind(self.moving_average_20)
-            .bind(self.lowest_price)
-            .bind(self.moving_average_120)
-            .bind(self.moving_average_240)
-            .bind(self.maximum_price_in_year)
-            .bind(self.minimum_price_in_year)
-            .bind(self.average_price_in_year)
-            .bind(self.highest_price)
-            .bind(self.opening_price)
-            .bind(self.trading_volume)
-            .bind(self.trade_value)
-            .bind(self.transaction)
-            .bind(self.price_to_book_ratio)
-            .bind(&self.security_code)
-            .bind(self.year)
-            .bind(self.month)
-            .bind(self.day)
-            .execute(database::get_connection())
-            .await
-            .context(format!(
-                "Failed to DailyQuote::upsert({:#?}) from database",
-                self
-            ))
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(self.maximum_price_in_year_date_on)
+                .bind(self.minimum_price_in_year_date_on)
+                .bind(self.date)
+                .bind(self.create_time)
+                .bind(self.record_time)
+                .bind(self.price_earning_ratio)
+                .bind(self.moving_average_60)
+                .bind(self.closing_price)
+                .bind(self.change_range)
+                .bind(self.change)
+                .bind(self.last_best_bid_price)
+                .bind(self.last_best_bid_volume)
+                .bind(self.last_best_ask_price)
+                .bind(self.last_best_ask_volume)
+                .bind(self.moving_average_5)
+                .bind(self.moving_average_10)
+                .bind(self.moving_average_20)
+                .bind(self.lowest_price)
+                .bind(self.moving_average_120)
+                .bind(self.moving_average_240)
+                .bind(self.maximum_price_in_year)
+                .bind(self.minimum_price_in_year)
+                .bind(self.average_price_in_year)
+                .bind(self.highest_price)
+                .bind(self.opening_price)
+                .bind(self.trading_volume)
+                .bind(self.trade_value)
+                .bind(self.transaction)
+                .bind(self.price_to_book_ratio)
+                .bind(&self.security_code)
+                .bind(self.year)
+                .bind(self.month)
+                .bind(self.day)
+                .execute(database::get_connection())
+        })
+        .timed("DailyQuotes", "upsert")
+        .await
+        .context(format!(
+            "Failed to DailyQuote::upsert({:#?}) from database",
+            self
+        ))
     }
 
     /// 依指定日期取得收盤資料的均線
@@ -313,6 +321,7 @@ SELECT
                 Ok(())
             })
             .fetch_one(database::get_connection())
+            .timed("DailyQuotes", "fill_moving_average")
             .await
             .context(format!(
                 "Failed to fetch_moving_average(security_code:{},date:{}) from database",
@@ -354,6 +363,7 @@ WHERE "Serial" = $1
             .bind(self.minimum_price_in_year_date_on)
             .bind(self.price_to_book_ratio)
             .execute(database::get_connection())
+            .timed("DailyQuotes", "update_moving_average")
             .await
             .context(format!(
                 "Failed to update_moving_average({:#?}) from database",
@@ -366,14 +376,15 @@ WHERE "Serial" = $1
     }
 }
 
-pub trait FromWithExchange<T, U> {
-    fn from_with_exchange(exchange: T, item: &U) -> Self;
+pub trait FromWithExchange<T, U>: Sized {
+    /// 代號不是合法的股票代號時回傳錯誤
+    fn from_with_exchange(exchange: T, item: &U) -> Result<Self>;
 }
 
 //let entity: Entity = fs.into(); // 或者 let entity = Entity::from(fs);
 impl FromWithExchange<StockExchange, Vec<String>> for DailyQuote {
-    fn from_with_exchange(exchange: StockExchange, item: &Vec<String>) -> Self {
-        let mut e = DailyQuote::new(item[0].to_string());
+    fn from_with_exchange(exchange: StockExchange, item: &Vec<String>) -> Result<Self> {
+        let mut e = DailyQuote::new(StockSymbol::parse(&item[0])?);
 
         match exchange {
             StockExchange::TWSE => {
@@ -433,10 +444,30 @@ impl FromWithExchange<StockExchange, Vec<String>> for DailyQuote {
         e.maximum_price_in_year_date_on = default_date.date_naive();
         e.minimum_price_in_year_date_on = default_date.date_naive();
 
-        e
+        Ok(e)
     }
 }
 
+/// 每次 COPY 寫入的筆數上限，避免歷史回補時一次組出過大的資料
+const COPY_IN_BATCH_SIZE: usize = 5000;
+
+/// 以 COPY ... FROM STDIN 批次寫入收盤報價，回傳寫入的筆數
+/// COPY 不處理衝突，呼叫端需先排除資料庫內已存在的日期
+pub async fn copy_in(quotes: &[DailyQuote]) -> Result<u64> {
+    let mut total = 0;
+    for chunk in quotes.chunks(COPY_IN_BATCH_SIZE) {
+        total += database::copy_in_raw(COPY_IN_QUERY, chunk)
+            .timed("DailyQuotes", "copy_in")
+            .await
+            .context(format!(
+                "Failed to copy_in({} rows) DailyQuotes from database",
+                chunk.len()
+            ))?;
+    }
+
+    Ok(total)
+}
+
 /// 補上當日缺少的每日收盤數據
 pub async fn makeup_for_the_lack_daily_quotes(date: NaiveDate) -> Result<PgQueryResult> {
     let date_str = date.format("%Y-%m-%d").to_string();
@@ -512,6 +543,7 @@ WHERE "Serial" IN
 
     sqlx::query(&sql)
         .execute(database::get_connection())
+        .timed("DailyQuotes", "makeup_for_the_lack_daily_quotes")
         .await
         .context(format!(
             "Failed to makeup_for_the_lack_daily_quotes from database\r\n{}",
@@ -539,9 +571,199 @@ GROUP BY "SecurityCode", "year", "month";
         .bind(year)
         .bind(month)
         .fetch_one(database::get_connection())
+        .timed("DailyQuotes", "fetch_monthly_stock_price_summary")
         .await?)
 }
 
+/// 取得股票自 since(含)以後每日的價格與均線，依日期由舊到新排序
+pub async fn fetch_daily_prices(security_code: &str, since: NaiveDate) -> Result<Vec<DailyPrice>> {
+    let sql = r#"
+SELECT
+    "Date" as date,
+    "OpeningPrice" as opening_price,
+    "HighestPrice" as highest_price,
+    "LowestPrice" as lowest_price,
+    "ClosingPrice" as closing_price,
+    "MovingAverage20" as moving_average_20,
+    "MovingAverage60" as moving_average_60
+FROM "DailyQuotes"
+WHERE "SecurityCode" = $1 AND "Date" >= $2
+ORDER BY "Date";
+"#;
+    sqlx::query_as::<_, DailyPrice>(sql)
+        .bind(security_code)
+        .bind(since)
+        .fetch_all(database::get_connection())
+        .timed("DailyQuotes", "fetch_daily_prices")
+        .await
+        .context(format!(
+            "Failed to fetch_daily_prices({}, {}) from database",
+            security_code, since
+        ))
+}
+
+/// 取得股票在 from 到 to(含)之間已有收盤資料的 (Serial, 日期, 收盤價)，依日期由舊到新排序
+pub async fn fetch_trading_days(
+    security_code: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<(i64, NaiveDate, Decimal)>> {
+    let sql = r#"
+SELECT "Serial", "Date", "ClosingPrice"
+FROM "DailyQuotes"
+WHERE "SecurityCode" = $1 AND "Date" BETWEEN $2 AND $3
+ORDER BY "Date";
+"#;
+    sqlx::query_as::<_, (i64, NaiveDate, Decimal)>(sql)
+        .bind(security_code)
+        .bind(from)
+        .bind(to)
+        .fetch_all(database::get_connection())
+        .timed("DailyQuotes", "fetch_trading_days")
+        .await
+        .context(format!(
+            "Failed to fetch_trading_days({}, {}, {}) from database",
+            security_code, from, to
+        ))
+}
+
+/// 取得庫存股票期初(start 以前最近一個交易日)與期末(end 以前最近一個交易日)的收盤價
+pub async fn fetch_held_price_changes(
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<Vec<PriceChange>> {
+    let sql = r#"
+WITH held AS (
+    SELECT DISTINCT security_code FROM stock_ownership_details WHERE is_sold = false
+),
+start_quotes AS (
+    SELECT DISTINCT ON ("SecurityCode") "SecurityCode", "ClosingPrice"
+    FROM "DailyQuotes"
+    WHERE "Date" BETWEEN $1 - 14 AND $1 AND "SecurityCode" IN (SELECT security_code FROM held)
+    ORDER BY "SecurityCode", "Date" DESC
+),
+end_quotes AS (
+    SELECT DISTINCT ON ("SecurityCode") "SecurityCode", "ClosingPrice"
+    FROM "DailyQuotes"
+    WHERE "Date" BETWEEN $2 - 14 AND $2 AND "SecurityCode" IN (SELECT security_code FROM held)
+    ORDER BY "SecurityCode", "Date" DESC
+)
+SELECT
+    s.stock_symbol,
+    s."Name" AS name,
+    sq."ClosingPrice" AS start_price,
+    eq."ClosingPrice" AS end_price
+FROM start_quotes sq
+INNER JOIN end_quotes eq ON eq."SecurityCode" = sq."SecurityCode"
+INNER JOIN stocks s ON s.stock_symbol = sq."SecurityCode"
+WHERE sq."ClosingPrice" > 0;
+"#;
+    sqlx::query_as::<_, PriceChange>(sql)
+        .bind(start)
+        .bind(end)
+        .fetch_all(database::get_connection())
+        .timed("DailyQuotes", "fetch_held_price_changes")
+        .await
+        .context(format!(
+            "Failed to fetch_held_price_changes({}, {}) from database",
+            start, end
+        ))
+}
+
+/// 取得指定日期有收盤價的股票(預設不含權證)漲跌、成交量與前 20 個交易日的平均成交量，
+/// 興櫃股票沒有漲跌幅限制，只列入庫存中的
+pub async fn fetch_daily_movers(date: NaiveDate) -> Result<Vec<DailyMover>> {
+    let sql = r#"
+WITH history AS (
+    SELECT
+        "SecurityCode",
+        "TradingVolume",
+        row_number() OVER (PARTITION BY "SecurityCode" ORDER BY "Date" DESC) AS row_number
+    FROM "DailyQuotes"
+    WHERE "Date" < $1 AND "Date" >= $1 - 60
+),
+average_volume AS (
+    SELECT "SecurityCode", AVG("TradingVolume") AS average_volume_20
+    FROM history
+    WHERE row_number <= 20
+    GROUP BY "SecurityCode"
+),
+held AS (
+    SELECT DISTINCT security_code FROM stock_ownership_details WHERE is_sold = false
+)
+SELECT
+    dq."SecurityCode" AS stock_symbol,
+    s."Name" AS name,
+    dq."ClosingPrice" AS closing_price,
+    dq."Change" AS change,
+    dq."ChangeRange" AS change_range,
+    dq."TradingVolume" AS trading_volume,
+    COALESCE(av.average_volume_20, 0) AS average_volume_20,
+    held.security_code IS NOT NULL AS is_held
+FROM "DailyQuotes" dq
+INNER JOIN stocks s ON s.stock_symbol = dq."SecurityCode"
+LEFT JOIN average_volume av ON av."SecurityCode" = dq."SecurityCode"
+LEFT JOIN held ON held.security_code = dq."SecurityCode"
+WHERE dq."Date" = $1 AND dq."ClosingPrice" > 0 AND s.security_type <> ALL($2)
+  AND (s.stock_exchange_market_id <> $3 OR held.security_code IS NOT NULL);
+"#;
+    sqlx::query_as::<_, DailyMover>(sql)
+        .bind(date)
+        .bind(stock::excluded_security_types())
+        .bind(StockExchangeMarket::Emerging.serial())
+        .fetch_all(database::get_connection())
+        .timed("DailyQuotes", "fetch_daily_movers")
+        .await
+        .context(format!("Failed to fetch_daily_movers({}) from database", date))
+}
+
+/// 取得指定日期有收盤價的股票最近 days 個交易日(含當日)的收盤價與均線，依股票代號、日期由舊到新排序
+pub async fn fetch_signal_bars(date: NaiveDate, days: i64) -> Result<Vec<SignalBar>> {
+    let sql = r#"
+WITH recent AS (
+    SELECT
+        "SecurityCode",
+        "Date",
+        "ClosingPrice",
+        "MovingAverage5",
+        "MovingAverage10",
+        "MovingAverage20",
+        "MovingAverage60",
+        "MovingAverage120",
+        "MovingAverage240",
+        row_number() OVER (PARTITION BY "SecurityCode" ORDER BY "Date" DESC) AS row_number
+    FROM "DailyQuotes"
+    WHERE "Date" <= $1 AND "Date" >= $1 - ($2 * 2 + 14)::int AND "ClosingPrice" > 0
+)
+SELECT
+    r."SecurityCode" AS security_code,
+    r."Date" AS date,
+    r."ClosingPrice" AS closing_price,
+    r."MovingAverage5" AS moving_average_5,
+    r."MovingAverage10" AS moving_average_10,
+    r."MovingAverage20" AS moving_average_20,
+    r."MovingAverage60" AS moving_average_60,
+    r."MovingAverage120" AS moving_average_120,
+    r."MovingAverage240" AS moving_average_240
+FROM recent r
+WHERE r.row_number <= $2
+  AND EXISTS (
+    SELECT 1 FROM recent t WHERE t."SecurityCode" = r."SecurityCode" AND t."Date" = $1
+  )
+ORDER BY r."SecurityCode", r."Date";
+"#;
+    sqlx::query_as::<_, SignalBar>(sql)
+        .bind(date)
+        .bind(days)
+        .fetch_all(database::get_connection())
+        .timed("DailyQuotes", "fetch_signal_bars")
+        .await
+        .context(format!(
+            "Failed to fetch_signal_bars({}, {}) from database",
+            date, days
+        ))
+}
+
 /// # fetch_count_by_date
 ///
 /// Fetches the count of daily quotes for the specified date.
@@ -577,13 +799,13 @@ pub async fn fetch_count_by_date(date: NaiveDate) -> Result<i64> {
     let row: (i64,) = sqlx::query_as(sql)
         .bind(date)
         .fetch_one(database::get_connection())
+        .timed("DailyQuotes", "fetch_count_by_date")
         .await?;
     Ok(row.0)
 }
 
-pub async fn fetch_daily_quotes_by_date(date: NaiveDate) -> Result<Vec<DailyQuote>> {
-    let sql = r#"
-    SELECT
+/// DailyQuote 需要的全部欄位
+const DAILY_QUOTE_COLUMNS: &str = r#"
         "Serial",
         "Date",
         "SecurityCode",
@@ -617,56 +839,127 @@ pub async fn fetch_daily_quotes_by_date(date: NaiveDate) -> Result<Vec<DailyQuot
         "price-to-book_ratio",
         year,
         month,
-        day
+        day"#;
+
+/// 取得指定日期全部股票的報價 SQL
+static DAILY_QUOTES_BY_DATE_SQL: Lazy<String> = Lazy::new(|| {
+    format!(
+        r#"
+    SELECT {}
     FROM "DailyQuotes"
-    WHERE "Date" = $1"#;
-    sqlx::query(sql)
+    WHERE "Date" = $1"#,
+        DAILY_QUOTE_COLUMNS
+    )
+});
+
+/// 分頁查詢可排序的欄位
+const PAGE_COLUMNS: [(&str, &str); 4] = [
+    ("date", r#""Date""#),
+    ("closing_price", r#""ClosingPrice""#),
+    ("change_range", r#""ChangeRange""#),
+    ("trading_volume", r#""TradingVolume""#),
+];
+
+/// 將 DailyQuotes 的一列轉成 DailyQuote
+fn daily_quote_from_row(row: sqlx::postgres::PgRow) -> Result<DailyQuote, sqlx::Error> {
+    let dq = DailyQuote {
+        maximum_price_in_year_date_on: row.get("maximum_price_in_year_date_on"),
+        minimum_price_in_year_date_on: row.get("minimum_price_in_year_date_on"),
+        date: row.get("Date"),
+        create_time: row.try_get("CreateTime")?,
+        record_time: row.try_get("RecordTime")?,
+        price_earning_ratio: row.get("PriceEarningRatio"),
+        moving_average_60: row.get("MovingAverage60"),
+        closing_price: row.get("ClosingPrice"),
+        change_range: row.get("ChangeRange"),
+        change: row.get("Change"),
+        last_best_bid_price: row.get("LastBestBidPrice"),
+        last_best_bid_volume: row.get("LastBestBidVolume"),
+        last_best_ask_price: row.get("LastBestAskPrice"),
+        last_best_ask_volume: row.get("LastBestAskVolume"),
+        moving_average_5: row.get("MovingAverage5"),
+        moving_average_10: row.get("MovingAverage10"),
+        moving_average_20: row.get("MovingAverage20"),
+        lowest_price: row.get("LowestPrice"),
+        moving_average_120: row.get("MovingAverage120"),
+        moving_average_240: row.get("MovingAverage240"),
+        maximum_price_in_year: row.get("maximum_price_in_year"),
+        minimum_price_in_year: row.get("minimum_price_in_year"),
+        average_price_in_year: row.get("average_price_in_year"),
+        highest_price: row.get("HighestPrice"),
+        opening_price: row.get("OpeningPrice"),
+        trading_volume: row.get("TradingVolume"),
+        trade_value: row.get("TradeValue"),
+        transaction: row.get("Transaction"),
+        price_to_book_ratio: row.get("price-to-book_ratio"),
+        security_code: row.get("SecurityCode"),
+        serial: row.get("Serial"),
+        year: row.get("year"),
+        month: row.get("month"),
+        day: row.get("day"),
+    };
+
+    Ok(dq)
+}
+
+pub async fn fetch_daily_quotes_by_date(date: NaiveDate) -> Result<Vec<DailyQuote>> {
+    sqlx::query(DAILY_QUOTES_BY_DATE_SQL.as_str())
         .bind(date)
-        .try_map(|row: sqlx::postgres::PgRow| {
-            let dq = DailyQuote {
-                maximum_price_in_year_date_on: row.get("maximum_price_in_year_date_on"),
-                minimum_price_in_year_date_on: row.get("minimum_price_in_year_date_on"),
-                date: row.get("Date"),
-                create_time: row.try_get("CreateTime")?,
-                record_time: row.try_get("RecordTime")?,
-                price_earning_ratio: row.get("PriceEarningRatio"),
-                moving_average_60: row.get("MovingAverage60"),
-                closing_price: row.get("ClosingPrice"),
-                change_range: row.get("ChangeRange"),
-                change: row.get("Change"),
-                last_best_bid_price: row.get("LastBestBidPrice"),
-                last_best_bid_volume: row.get("LastBestBidVolume"),
-                last_best_ask_price: row.get("LastBestAskPrice"),
-                last_best_ask_volume: row.get("LastBestAskVolume"),
-                moving_average_5: row.get("MovingAverage5"),
-                moving_average_10: row.get("MovingAverage10"),
-                moving_average_20: row.get("MovingAverage20"),
-                lowest_price: row.get("LowestPrice"),
-                moving_average_120: row.get("MovingAverage120"),
-                moving_average_240: row.get("MovingAverage240"),
-                maximum_price_in_year: row.get("maximum_price_in_year"),
-                minimum_price_in_year: row.get("minimum_price_in_year"),
-                average_price_in_year: row.get("average_price_in_year"),
-                highest_price: row.get("HighestPrice"),
-                opening_price: row.get("OpeningPrice"),
-                trading_volume: row.get("TradingVolume"),
-                trade_value: row.get("TradeValue"),
-                transaction: row.get("Transaction"),
-                price_to_book_ratio: row.get("price-to-book_ratio"),
-                security_code: row.get("SecurityCode"),
-                serial: row.get("Serial"),
-                year: row.get("year"),
-                month: row.get("month"),
-                day: row.get("day"),
-            };
-
-            Ok(dq)
-        })
+        .try_map(daily_quote_from_row)
         .fetch_all(database::get_connection())
+        .timed("DailyQuotes", "fetch_daily_quotes_by_date")
         .await
         .context("Failed to fetch_daily_quotes_by_date from database")
 }
 
+/// 分頁取得股票的每日報價，預設依日期由新到舊
+pub async fn fetch_page(security_code: &str, page: &Page) -> Result<Paged<DailyQuote>> {
+    let sql = format!(
+        r#"
+SELECT {}
+FROM "DailyQuotes"
+WHERE "SecurityCode" = $1
+ORDER BY {}, "Serial"
+LIMIT $2 OFFSET $3;
+"#,
+        DAILY_QUOTE_COLUMNS,
+        page.order_clause(&PAGE_COLUMNS, "-date")?
+    );
+    let items = sqlx::query(&sql)
+        .bind(security_code)
+        .bind(page.limit())
+        .bind(page.offset())
+        .try_map(daily_quote_from_row)
+        .fetch_all(database::get_connection())
+        .timed("DailyQuotes", "fetch_page")
+        .await
+        .context(format!(
+            "Failed to daily_quote::fetch_page({}) from database",
+            security_code
+        ))?;
+    let (total,): (i64,) =
+        sqlx::query_as(r#"SELECT COUNT(*) FROM "DailyQuotes" WHERE "SecurityCode" = $1;"#)
+            .bind(security_code)
+            .fetch_one(database::get_connection())
+            .timed("DailyQuotes", "count_page")
+            .await
+            .context(format!(
+                "Failed to count daily_quote::fetch_page({}) from database",
+                security_code
+            ))?;
+
+    Ok(Paged::new(items, total, page))
+}
+
+/// 逐筆取得指定日期全部股票的報價，只需要走訪一次時使用，不會一次將數千筆報價載入記憶體
+pub fn stream_daily_quotes_by_date(date: NaiveDate) -> impl Stream<Item = Result<DailyQuote>> {
+    sqlx::query(DAILY_QUOTES_BY_DATE_SQL.as_str())
+        .bind(date)
+        .try_map(daily_quote_from_row)
+        .fetch(database::get_connection())
+        .map(|dq| dq.context("Failed to stream_daily_quotes_by_date from database"))
+}
+
 #[cfg(test)]
 mod tests {
     use chrono::Datelike;
@@ -682,7 +975,7 @@ mod tests {
         dotenv::dotenv().ok();
         logging::debug_file_async("開始 fetch_moving_average".to_string());
         let date = NaiveDate::from_ymd_opt(2023, 8, 1);
-        let mut dq = DailyQuote::new("2330".to_string());
+        let mut dq = DailyQuote::new("2330".parse().unwrap());
         dq.date = date.unwrap();
         match dq.fill_moving_average().await {
             Ok(_) => {
@@ -721,6 +1014,23 @@ mod tests {
         logging::debug_file_async("結束 fetch_daily_quotes_by_date".to_string());
     }
 
+    #[tokio::test]
+    #[ignore]
+    async fn test_stream_daily_quotes_by_date() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 stream_daily_quotes_by_date".to_string());
+        let date = NaiveDate::from_ymd_opt(2023, 7, 31).unwrap();
+        let expected = fetch_daily_quotes_by_date(date).await.unwrap().len();
+
+        let count = stream_daily_quotes_by_date(date)
+            .filter(|dq| futures::future::ready(dq.is_ok()))
+            .count()
+            .await;
+
+        assert_eq!(count, expected);
+        logging::debug_file_async("結束 stream_daily_quotes_by_date".to_string());
+    }
+
     #[tokio::test]
     #[ignore]
     async fn test_fetch_count_by_date() {
@@ -811,7 +1121,7 @@ mod tests {
             "51.28".to_string(),
         ];
 
-        let mut e = DailyQuote::from_with_exchange(StockExchange::TWSE, &data);
+        let mut e = DailyQuote::from_with_exchange(StockExchange::TWSE, &data).unwrap();
         e.date = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
         e.year = e.date.year();
         e.month = e.date.month() as i32;
@@ -852,7 +1162,7 @@ mod tests {
             "41.90".to_string(),
         ];
 
-        let mut e = DailyQuote::from_with_exchange(StockExchange::TPEx, &otc);
+        let mut e = DailyQuote::from_with_exchange(StockExchange::TPEx, &otc).unwrap();
         e.date = NaiveDate::from_ymd_opt(2000, 1, 2).unwrap();
         e.year = e.date.year();
         e.month = e.date.month() as i32;
@@ -9,7 +9,7 @@ use sqlx::{
     Type,
 };
 
-use crate::database;
+use crate::database::{self, timing::Timed};
 
 #[derive(Debug, Serialize, Deserialize, Type, FromRow)]
 pub struct DailyStockPriceStats {
@@ -37,6 +37,7 @@ pub struct DailyStockPriceStats {
 }
 
 impl DailyStockPriceStats {
+    /// 在 calculate_money_history 的交易內執行，不以 database::with_retry 重試
     pub async fn upsert(date: NaiveDate, tx: &mut Option<Transaction<'_, Postgres>>) -> Result<PgQueryResult> {
         let sql = r#"
 WITH cte AS (
@@ -183,8 +184,18 @@ ON CONFLICT (date, stock_exchange_market_id) DO UPDATE SET
 
         let query = sqlx::query(&sql).bind(date);
         let result = match tx {
-            None => query.execute(database::get_connection()).await,
-            Some(t) => query.execute(&mut **t).await,
+            None => {
+                query
+                    .execute(database::get_connection())
+                    .timed("daily_stock_price_stats", "upsert")
+                    .await
+            }
+            Some(t) => {
+                query
+                    .execute(&mut **t)
+                    .timed("daily_stock_price_stats", "upsert")
+                    .await
+            }
         };
 
         result.context(format!(
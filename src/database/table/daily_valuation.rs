@@ -0,0 +1,242 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgQueryResult, FromRow};
+
+use crate::{
+    database::{self, timing::Timed},
+    declare::{StockExchange, StockSymbol},
+    util,
+};
+
+/// 交易所公布的個股日本益比、殖利率及股價淨值比
+#[derive(FromRow, Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct DailyValuation {
+    pub date: NaiveDate,
+    pub security_code: StockSymbol,
+    /// 交易所 1:twse 2:tpex
+    pub stock_exchange_id: i32,
+    /// 本益比，虧損時交易所不提供，以 0 表示
+    pub price_earning_ratio: Decimal,
+    /// 股價淨值比
+    pub price_to_book_ratio: Decimal,
+    /// 殖利率(%)
+    pub dividend_yield: Decimal,
+}
+
+impl DailyValuation {
+    /// 依欄位名稱將交易所回傳的表格轉成 DailyValuation，twse 與 tpex 的欄位順序不同所以不依賴位置
+    pub fn from_table(
+        date: NaiveDate,
+        exchange: StockExchange,
+        fields: &[String],
+        data: &[Vec<String>],
+    ) -> Vec<DailyValuation> {
+        let column = |keyword: &str| fields.iter().position(|f| f.contains(keyword));
+        let (Some(code), Some(pe), Some(pb), Some(dy)) = (
+            column("代號"),
+            column("本益比"),
+            column("淨值比"),
+            column("殖利率"),
+        ) else {
+            return Vec::new();
+        };
+
+        let parse = |row: &Vec<String>, index: usize| {
+            row.get(index)
+                .and_then(|v| util::text::parse_decimal(v, Some(vec![','])).ok())
+                .unwrap_or_default()
+        };
+
+        data.iter()
+            .filter_map(|row| {
+                let security_code = StockSymbol::parse(row.get(code)?).ok()?;
+
+                Some(DailyValuation {
+                    date,
+                    security_code,
+                    stock_exchange_id: exchange.serial_number(),
+                    price_earning_ratio: parse(row, pe),
+                    price_to_book_ratio: parse(row, pb),
+                    dividend_yield: parse(row, dy),
+                })
+            })
+            .collect()
+    }
+
+    /// date 與 security_code 為組合鍵 unique
+    pub async fn upsert(&self) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO daily_valuation (date, security_code, stock_exchange_id, price_earning_ratio, price_to_book_ratio, dividend_yield)
+VALUES ($1, $2, $3, $4, $5, $6)
+ON CONFLICT (date, security_code) DO UPDATE SET
+    stock_exchange_id = EXCLUDED.stock_exchange_id,
+    price_earning_ratio = EXCLUDED.price_earning_ratio,
+    price_to_book_ratio = EXCLUDED.price_to_book_ratio,
+    dividend_yield = EXCLUDED.dividend_yield,
+    updated_time = now();
+"#;
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(self.date)
+                .bind(&self.security_code)
+                .bind(self.stock_exchange_id)
+                .bind(self.price_earning_ratio)
+                .bind(self.price_to_book_ratio)
+                .bind(self.dividend_yield)
+                .execute(database::get_connection())
+        })
+        .await
+        .context(format!("Failed to DailyValuation::upsert({:?}) from database", self))
+    }
+
+    /// 以 UNNEST 將一批數據合併成一個 INSERT，同一天同一檔重複時以最後一筆為準，回傳有寫入的 (日期, 股票代號)
+    pub async fn upsert_batch(valuations: &[DailyValuation]) -> Result<Vec<(NaiveDate, String)>> {
+        let sql = r#"
+INSERT INTO daily_valuation (date, security_code, stock_exchange_id, price_earning_ratio, price_to_book_ratio, dividend_yield)
+SELECT * FROM UNNEST($1::date[], $2::varchar[], $3::integer[], $4::numeric[], $5::numeric[], $6::numeric[])
+ON CONFLICT (date, security_code) DO UPDATE SET
+    stock_exchange_id = EXCLUDED.stock_exchange_id,
+    price_earning_ratio = EXCLUDED.price_earning_ratio,
+    price_to_book_ratio = EXCLUDED.price_to_book_ratio,
+    dividend_yield = EXCLUDED.dividend_yield,
+    updated_time = now()
+RETURNING date, security_code;
+"#;
+        let valuations =
+            util::map::dedup_by_key(valuations, |dv| (dv.date, dv.security_code.clone()));
+        database::with_retry(|| {
+            sqlx::query_as::<_, (NaiveDate, String)>(sql)
+                .bind(util::map::column(&valuations, |dv| dv.date))
+                .bind(util::map::column(&valuations, |dv| {
+                    dv.security_code.clone()
+                }))
+                .bind(util::map::column(&valuations, |dv| dv.stock_exchange_id))
+                .bind(util::map::column(&valuations, |dv| dv.price_earning_ratio))
+                .bind(util::map::column(&valuations, |dv| dv.price_to_book_ratio))
+                .bind(util::map::column(&valuations, |dv| dv.dividend_yield))
+                .fetch_all(database::get_connection())
+        })
+        .timed("daily_valuation", "upsert_batch")
+        .await
+        .context(format!(
+            "Failed to DailyValuation::upsert_batch({} rows) from database",
+            valuations.len()
+        ))
+    }
+
+    /// 取得指定股票最近一筆的數據
+    pub async fn fetch_latest(security_code: &str) -> Result<Option<DailyValuation>> {
+        let sql = r#"
+SELECT date, security_code, stock_exchange_id, price_earning_ratio, price_to_book_ratio, dividend_yield
+FROM daily_valuation
+WHERE security_code = $1
+ORDER BY date DESC
+LIMIT 1;
+"#;
+        sqlx::query_as::<_, DailyValuation>(sql)
+            .bind(security_code)
+            .fetch_optional(database::get_connection())
+            .await
+            .context(format!(
+                "Failed to DailyValuation::fetch_latest({}) from database",
+                security_code
+            ))
+    }
+
+    /// 取得指定日期各股票的殖利率與近 years 年(含當日)殖利率大於 0 的平均，回傳 (股票代號, 殖利率, 平均殖利率)
+    pub async fn fetch_yield_averages(
+        date: NaiveDate,
+        years: i32,
+    ) -> Result<Vec<(StockSymbol, Decimal, Decimal)>> {
+        let sql = r#"
+SELECT
+    today.security_code,
+    today.dividend_yield,
+    AVG(history.dividend_yield) AS average_yield
+FROM daily_valuation AS today
+INNER JOIN daily_valuation AS history ON history.security_code = today.security_code
+WHERE today.date = $1
+  AND today.dividend_yield > 0
+  AND history.date > $1 - make_interval(years => $2)
+  AND history.date <= $1
+  AND history.dividend_yield > 0
+GROUP BY today.security_code, today.dividend_yield;
+"#;
+        sqlx::query_as::<_, (StockSymbol, Decimal, Decimal)>(sql)
+            .bind(date)
+            .bind(years)
+            .fetch_all(database::get_connection())
+            .await
+            .context(format!(
+                "Failed to DailyValuation::fetch_yield_averages({}, {}) from database",
+                date, years
+            ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use crate::logging;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_from_table() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let fields = strings(&[
+            "證券代號", "證券名稱", "殖利率(%)", "股利年度", "本益比", "股價淨值比", "財報年/季",
+        ]);
+        let data = vec![
+            strings(&["2330", "台積電", "1.86", "111", "16.53", "4.87", "112/3"]),
+            strings(&["1101", "台泥", "3.22", "111", "-", "1.02", "112/3"]),
+            strings(&["", "", "", "", "", "", ""]),
+        ];
+
+        let list = DailyValuation::from_table(date, StockExchange::TWSE, &fields, &data);
+
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].security_code, "2330");
+        assert_eq!(list[0].price_earning_ratio, dec!(16.53));
+        assert_eq!(list[0].price_to_book_ratio, dec!(4.87));
+        assert_eq!(list[0].dividend_yield, dec!(1.86));
+        assert_eq!(list[0].stock_exchange_id, 1);
+        assert_eq!(list[1].price_earning_ratio, Decimal::ZERO);
+
+        let fields = strings(&["股票代號", "名稱", "本益比", "每股股利", "股利年度", "殖利率(%)", "股價淨值比"]);
+        let data = vec![strings(&["6488", "環球晶", "12.34", "14.00", "111", "2.50", "3.10"])];
+        let list = DailyValuation::from_table(date, StockExchange::TPEx, &fields, &data);
+
+        assert_eq!(list[0].price_earning_ratio, dec!(12.34));
+        assert_eq!(list[0].dividend_yield, dec!(2.50));
+        assert_eq!(list[0].price_to_book_ratio, dec!(3.10));
+        assert_eq!(list[0].stock_exchange_id, 2);
+
+        assert!(DailyValuation::from_table(date, StockExchange::TPEx, &[], &data).is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_fetch_latest() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 DailyValuation::fetch_latest".to_string());
+
+        match DailyValuation::fetch_latest("2330").await {
+            Ok(dv) => logging::debug_file_async(format!("DailyValuation:{:?}", dv)),
+            Err(why) => logging::debug_file_async(format!(
+                "Failed to DailyValuation::fetch_latest because {:?}",
+                why
+            )),
+        }
+
+        logging::debug_file_async("結束 DailyValuation::fetch_latest".to_string());
+    }
+}
@@ -0,0 +1,57 @@
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use sqlx::FromRow;
+
+use crate::{database, declare::StockSymbol};
+
+/// 股票的除權息日與股利發放日，日期格式為 YYYY-MM-DD，尚未公布時為 `-`
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct DividendSchedule {
+    pub stock_symbol: StockSymbol,
+    pub name: String,
+    /// 股利所屬年度
+    pub year_of_dividend: i32,
+    /// 發放季度
+    pub quarter: String,
+    pub cash_dividend: Decimal,
+    pub stock_dividend: Decimal,
+    /// 除息日
+    pub ex_dividend_date1: String,
+    /// 除權日
+    pub ex_dividend_date2: String,
+    /// 現金股利發放日
+    pub payable_date1: String,
+    /// 股票股利發放日
+    pub payable_date2: String,
+}
+
+/// 取得指定股票於 year(含)之後發放的股利日程
+pub async fn fetch(security_codes: &[String], year: i32) -> Result<Vec<DividendSchedule>> {
+    let sql = r#"
+SELECT
+    s.stock_symbol,
+    s."Name" AS name,
+    d.year_of_dividend,
+    d.quarter,
+    d.cash_dividend,
+    d.stock_dividend,
+    d."ex-dividend_date1" AS ex_dividend_date1,
+    d."ex-dividend_date2" AS ex_dividend_date2,
+    d.payable_date1,
+    d.payable_date2
+FROM dividend AS d
+INNER JOIN stocks AS s ON s.stock_symbol = d.security_code
+WHERE d.security_code = ANY($1) AND d.year >= $2
+ORDER BY s.stock_symbol, d.year, d.quarter;
+"#;
+
+    sqlx::query_as::<_, DividendSchedule>(sql)
+        .bind(security_codes)
+        .bind(year)
+        .fetch_all(database::get_connection())
+        .await
+        .context(format!(
+            "Failed to dividend_schedule::fetch({}) from database",
+            year
+        ))
+}
@@ -0,0 +1,99 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use sqlx::FromRow;
+
+use crate::{database, declare::StockSymbol};
+
+/// 庫存股票在期間內入帳的現金股利
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct ReceivedDividend {
+    pub stock_symbol: StockSymbol,
+    pub name: String,
+    /// 現金股利發放日
+    pub payable_date: String,
+    /// 每股現金股利
+    pub cash_dividend: Decimal,
+    /// 持有股數
+    pub share_quantity: i64,
+}
+
+/// 庫存股票在期間內的除權息日
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct UpcomingExDividend {
+    pub stock_symbol: StockSymbol,
+    pub name: String,
+    /// 除息日在期間內時為除息日，否則為除權日
+    pub ex_dividend_date: String,
+    pub cash_dividend: Decimal,
+    pub stock_dividend: Decimal,
+}
+
+/// 取得庫存股票中現金股利發放日介於 start 與 end(皆含)之間的股利
+pub async fn fetch_received(start: NaiveDate, end: NaiveDate) -> Result<Vec<ReceivedDividend>> {
+    let sql = r#"
+SELECT
+    s.stock_symbol,
+    s."Name" AS name,
+    d.payable_date1 AS payable_date,
+    d.cash_dividend,
+    SUM(sod.share_quantity)::bigint AS share_quantity
+FROM dividend AS d
+INNER JOIN stocks AS s ON s.stock_symbol = d.security_code
+INNER JOIN stock_ownership_details AS sod
+    ON sod.security_code = d.security_code AND sod.is_sold = false
+WHERE d.payable_date1 <> '-'
+    AND d.payable_date1 BETWEEN $1 AND $2
+    AND d.cash_dividend > 0
+GROUP BY s.stock_symbol, s."Name", d.payable_date1, d.cash_dividend
+ORDER BY d.payable_date1, s.stock_symbol;
+"#;
+
+    sqlx::query_as::<_, ReceivedDividend>(sql)
+        .bind(start.format("%Y-%m-%d").to_string())
+        .bind(end.format("%Y-%m-%d").to_string())
+        .fetch_all(database::get_connection())
+        .await
+        .context(format!(
+            "Failed to held_dividend::fetch_received({}, {}) from database",
+            start, end
+        ))
+}
+
+/// 取得庫存股票中除權息日介於 start 與 end(皆含)之間的股票
+pub async fn fetch_upcoming(start: NaiveDate, end: NaiveDate) -> Result<Vec<UpcomingExDividend>> {
+    let sql = r#"
+SELECT *
+FROM (
+    SELECT
+        s.stock_symbol,
+        s."Name" AS name,
+        CASE
+            WHEN d."ex-dividend_date1" BETWEEN $1 AND $2 THEN d."ex-dividend_date1"
+            ELSE d."ex-dividend_date2"
+        END AS ex_dividend_date,
+        d.cash_dividend,
+        d.stock_dividend
+    FROM dividend AS d
+    INNER JOIN stocks AS s ON s.stock_symbol = d.security_code
+    WHERE d.security_code IN (
+            SELECT security_code FROM stock_ownership_details WHERE is_sold = false
+        )
+        AND (
+            d."ex-dividend_date1" BETWEEN $1 AND $2
+            OR d."ex-dividend_date2" BETWEEN $1 AND $2
+        )
+) AS upcoming
+ORDER BY ex_dividend_date, stock_symbol;
+"#;
+
+    sqlx::query_as::<_, UpcomingExDividend>(sql)
+        .bind(start.format("%Y-%m-%d").to_string())
+        .bind(end.format("%Y-%m-%d").to_string())
+        .fetch_all(database::get_connection())
+        .await
+        .context(format!(
+            "Failed to held_dividend::fetch_upcoming({}, {}) from database",
+            start, end
+        ))
+}
@@ -1,4 +1,7 @@
+pub mod dividend_schedule;
+pub mod held_dividend;
 pub mod stock_dividend_info;
 pub mod stock_dividend_payable_date_info;
+pub mod taxable_dividend;
 
 pub mod payout_ratio_info;
@@ -2,7 +2,7 @@ use anyhow::{Context, Result};
 use rust_decimal::Decimal;
 use sqlx::{postgres::PgQueryResult, FromRow};
 
-use crate::{database, util::map::Keyable};
+use crate::{database, declare::StockSymbol, util::map::Keyable};
 
 /// 股票除息的資料
 #[derive(FromRow, Debug)]
@@ -10,7 +10,7 @@ pub struct PayoutRatioInfo {
     pub serial: i64,
     pub year: i32,
     pub quarter: String,
-    pub security_code: String,
+    pub security_code: StockSymbol,
     pub payout_ratio_cash: Decimal,
     pub payout_ratio_stock: Decimal,
     pub payout_ratio: Decimal,
@@ -3,12 +3,12 @@ use chrono::{Datelike, NaiveDate};
 use rust_decimal::Decimal;
 use sqlx::FromRow;
 
-use crate::database;
+use crate::{database, declare::StockSymbol};
 
 /// 股票除息的資料
 #[derive(FromRow, Debug)]
 pub struct StockDividendInfo {
-    pub stock_symbol: String,
+    pub stock_symbol: StockSymbol,
     pub name: String,
     pub cash_dividend: Decimal,
     pub stock_dividend: Decimal,
@@ -3,21 +3,23 @@ use chrono::NaiveDate;
 use rust_decimal::Decimal;
 use sqlx::FromRow;
 
-use crate::database;
+use crate::{database, declare::StockSymbol};
 
 /// 股票除息的資料
 #[derive(FromRow, Debug)]
 pub struct StockDividendPayableDateInfo {
-    pub stock_symbol: String,
+    pub stock_symbol: StockSymbol,
     pub name: String,
     pub cash_dividend: Decimal,
     pub stock_dividend: Decimal,
     pub sum: Decimal,
     pub payable_date1: String,
     pub payable_date2: String,
+    /// 是否為庫存股票，否則為 /subscribe 訂閱了股利的股票
+    pub is_held: bool,
 }
 
-/// 取得指定日期為股利發放日的股票
+/// 取得指定日期為股利發放日的庫存股票與以 /subscribe 訂閱了股利的股票
 pub async fn fetch(date: NaiveDate) -> Result<Vec<StockDividendPayableDateInfo>> {
     let sql = r#"
 SELECT
@@ -27,12 +29,16 @@ SELECT
     d.stock_dividend,
     d.sum,
     d."payable_date1",
-    d."payable_date2"
+    d."payable_date2",
+    security_code IN (SELECT security_code FROM stock_ownership_details WHERE is_sold = false) AS is_held
 FROM
     dividend AS d
 INNER JOIN
     stocks AS s ON s.stock_symbol = d.security_code
-WHERE security_code in (select security_code from stock_ownership_details where is_sold = false)
+WHERE (
+        security_code IN (SELECT security_code FROM stock_ownership_details WHERE is_sold = false)
+        OR security_code IN (SELECT security_code FROM bot_subscriptions WHERE event = 'dividend')
+    )
     AND (d."payable_date1" = $1 OR d."payable_date2" = $1);
 "#;
 
@@ -0,0 +1,56 @@
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use sqlx::FromRow;
+
+use crate::{
+    database::{self, timing::Timed},
+    declare::StockSymbol,
+};
+
+/// 成員單次領取的股利，同一成員同一次發放的多筆買進合併成一筆
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct TaxableDividend {
+    pub member_id: i64,
+    pub security_code: StockSymbol,
+    pub name: String,
+    /// 現金股利發放日，尚未公布時為 -
+    pub payable_date: String,
+    /// 現金股利(元)
+    pub cash: Decimal,
+    /// 股票股利(股)
+    pub stock: Decimal,
+    /// 股票股利以面額計算的金額(元)
+    pub stock_money: Decimal,
+}
+
+/// 取得 dividend_record_detail_more 內指定年度發放的股利，依成員、發放日、股票代號排序
+pub async fn fetch(year: i32) -> Result<Vec<TaxableDividend>> {
+    let sql = r#"
+SELECT
+    sod.member_id,
+    sod.security_code,
+    COALESCE(s."Name", '') AS name,
+    d.payable_date1 AS payable_date,
+    SUM(m.cash) AS cash,
+    SUM(m.stock) AS stock,
+    SUM(m.stock_money) AS stock_money
+FROM dividend_record_detail_more AS m
+INNER JOIN stock_ownership_details AS sod ON sod.serial = m.stock_ownership_details_serial
+INNER JOIN dividend AS d ON d.serial = m.dividend_serial
+LEFT JOIN stocks AS s ON s.stock_symbol = sod.security_code
+WHERE d.year = $1
+GROUP BY sod.member_id, sod.security_code, s."Name", d.serial, d.payable_date1
+HAVING SUM(m.cash) + SUM(m.stock_money) > 0
+ORDER BY sod.member_id, d.payable_date1, sod.security_code;
+"#;
+
+    sqlx::query_as::<_, TaxableDividend>(sql)
+        .bind(year)
+        .fetch_all(database::get_connection())
+        .timed("dividend_record_detail_more", "fetch_taxable")
+        .await
+        .context(format!(
+            "Failed to taxable_dividend::fetch({}) from database",
+            year
+        ))
+}
@@ -6,7 +6,15 @@ use sqlx::{
     Row,
 };
 
-use crate::{crawler::goodinfo, database, util::map::Keyable};
+use crate::{
+    crawler::{goodinfo, yahoo},
+    database::{
+        self,
+        page::{Page, Paged},
+    },
+    declare::StockSymbol,
+    util::map::Keyable,
+};
 
 pub(crate) mod extension;
 
@@ -22,7 +30,7 @@ pub struct Dividend {
     /// 發放季度
     pub quarter: String,
     /// 股票代號
-    pub security_code: String,
+    pub security_code: StockSymbol,
     /// 盈餘現金股利 (Cash Dividend)
     pub earnings_cash_dividend: Decimal,
     /// 公積現金股利 (Capital Reserve)
@@ -53,6 +61,8 @@ pub struct Dividend {
     pub payable_date2: String,
     pub created_time: DateTime<Local>,
     pub updated_time: DateTime<Local>,
+    /// 寫入此筆數據的 ingestion_batches 批次
+    pub batch_id: String,
 }
 
 impl Keyable for Dividend {
@@ -89,7 +99,15 @@ const TABLE_COLUMNS: &str = r#"
     earnings_stock_dividend,
     payout_ratio_cash,
     payout_ratio_stock,
-    payout_ratio"#;
+    payout_ratio,
+    batch_id"#;
+
+/// 分頁查詢可排序的欄位
+const PAGE_COLUMNS: [(&str, &str); 3] = [
+    ("year", "year"),
+    ("cash_dividend", "cash_dividend"),
+    ("sum", "sum"),
+];
 
 impl Dividend {
     pub fn new() -> Self {
@@ -115,6 +133,7 @@ impl Dividend {
             payable_date2: "".to_string(),
             created_time: Local::now(),
             updated_time: Local::now(),
+            batch_id: String::new(),
         }
     }
 
@@ -142,8 +161,8 @@ INSERT INTO dividend (
     cash_dividend, stock_dividend, "sum","ex-dividend_date1", "ex-dividend_date2",
     payable_date1, payable_date2, created_time, updated_time, capital_reserve_cash_dividend,
     earnings_cash_dividend, capital_reserve_stock_dividend, earnings_stock_dividend,
-    payout_ratio_cash, payout_ratio_stock, payout_ratio)
-VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20)
+    payout_ratio_cash, payout_ratio_stock, payout_ratio, batch_id)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21)
 ON CONFLICT (security_code,"year",quarter) DO UPDATE SET
     year_of_dividend = EXCLUDED.year_of_dividend,
     cash_dividend = EXCLUDED.cash_dividend,
@@ -156,39 +175,43 @@ ON CONFLICT (security_code,"year",quarter) DO UPDATE SET
     earnings_stock_dividend = EXCLUDED.earnings_stock_dividend,
     payout_ratio_cash = EXCLUDED.payout_ratio_cash,
     payout_ratio_stock = EXCLUDED.payout_ratio_stock,
-    payout_ratio = EXCLUDED.payout_ratio;
+    payout_ratio = EXCLUDED.payout_ratio,
+    batch_id = EXCLUDED.batch_id;
 "#;
-        sqlx::query(sql)
-            .bind(&self.security_code)
-            .bind(self.year)
-            .bind(self.year_of_dividend)
-            .bind(&self.quarter)
-            .bind(self.cash_dividend)
-            .bind(self.stock_dividend)
-            .bind(self.sum)
-            .bind(&self.ex_dividend_date1)
-            .bind(&self.ex_dividend_date2)
-            .bind(&self.payable_date1)
-            .bind(&self.payable_date2)
-            .bind(self.created_time)
-            .bind(self.updated_time)
-            .bind(self.capital_reserve_cash_dividend)
-            .bind(self.earnings_cash_dividend)
-            .bind(self.capital_reserve_stock_dividend)
-            .bind(self.earnings_stock_dividend)
-            .bind(self.payout_ratio_cash)
-            .bind(self.payout_ratio_stock)
-            .bind(self.payout_ratio)
-            .execute(database::get_connection())
-            .await
-            .map_err(|why| {
-                anyhow!(
-                    "Failed to upsert({:#?}) from database\nsql:{}\n{:?}",
-                    self,
-                    sql,
-                    why,
-                )
-            })
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(&self.security_code)
+                .bind(self.year)
+                .bind(self.year_of_dividend)
+                .bind(&self.quarter)
+                .bind(self.cash_dividend)
+                .bind(self.stock_dividend)
+                .bind(self.sum)
+                .bind(&self.ex_dividend_date1)
+                .bind(&self.ex_dividend_date2)
+                .bind(&self.payable_date1)
+                .bind(&self.payable_date2)
+                .bind(self.created_time)
+                .bind(self.updated_time)
+                .bind(self.capital_reserve_cash_dividend)
+                .bind(self.earnings_cash_dividend)
+                .bind(self.capital_reserve_stock_dividend)
+                .bind(self.earnings_stock_dividend)
+                .bind(self.payout_ratio_cash)
+                .bind(self.payout_ratio_stock)
+                .bind(self.payout_ratio)
+                .bind(&self.batch_id)
+                .execute(database::get_connection())
+        })
+        .await
+        .map_err(|why| {
+            anyhow!(
+                "Failed to upsert({:#?}) from database\nsql:{}\n{:?}",
+                self,
+                sql,
+                why,
+            )
+        })
     }
 
     /// 更新年度內有多次配息記錄時將其合併計算成年度股利
@@ -248,19 +271,21 @@ ON CONFLICT (security_code,year,quarter) DO UPDATE SET
             year_of_dividend = self.year - 1
         );
 
-        sqlx::query(&sql)
-            .bind(&self.security_code)
-            .bind(self.year)
-            .execute(database::get_connection())
-            .await
-            .map_err(|why| {
-                anyhow!(
-                    "Failed to update_annual_total_dividend({:#?}) from database\nsql:{}\n{:?}",
-                    self,
-                    sql,
-                    why,
-                )
-            })
+        database::with_retry(|| {
+            sqlx::query(&sql)
+                .bind(&self.security_code)
+                .bind(self.year)
+                .execute(database::get_connection())
+        })
+        .await
+        .map_err(|why| {
+            anyhow!(
+                "Failed to update_annual_total_dividend({:#?}) from database\nsql:{}\n{:?}",
+                self,
+                sql,
+                why,
+            )
+        })
     }
 
     /// 更新股息的配息日、發放日
@@ -385,6 +410,43 @@ WHERE
             ))
     }
 
+    /// 分頁取得股票歷年的股利，預設依發放年度由新到舊
+    pub async fn fetch_page(security_code: &str, page: &Page) -> Result<Paged<Dividend>> {
+        let sql = format!(
+            r#"
+SELECT {}
+FROM dividend
+WHERE security_code = $1
+ORDER BY {}, quarter DESC, serial
+LIMIT $2 OFFSET $3;
+"#,
+            TABLE_COLUMNS,
+            page.order_clause(&PAGE_COLUMNS, "-year")?
+        );
+        let items = sqlx::query(&sql)
+            .bind(security_code)
+            .bind(page.limit())
+            .bind(page.offset())
+            .try_map(Self::row_to_entity)
+            .fetch_all(database::get_connection())
+            .await
+            .context(format!(
+                "Failed to Dividend::fetch_page({}) from database",
+                security_code
+            ))?;
+        let (total,): (i64,) =
+            sqlx::query_as("SELECT COUNT(*) FROM dividend WHERE security_code = $1;")
+                .bind(security_code)
+                .fetch_one(database::get_connection())
+                .await
+                .context(format!(
+                    "Failed to count Dividend::fetch_page({}) from database",
+                    security_code
+                ))?;
+
+        Ok(Paged::new(items, total, page))
+    }
+
     /// 取得指定年度內有多次配息的配息資料
     pub async fn fetch_multiple_dividends_for_year(year: i32) -> Result<Vec<Dividend>> {
         let sql = format!(
@@ -432,6 +494,27 @@ WHERE "SuspendListing" = false
         Ok(stock_symbols)
     }
 
+    /// 取得指定股票自 from_year 起各發放年度的全年度現金股利
+    pub async fn fetch_annual_cash_dividends(
+        security_codes: &[StockSymbol],
+        from_year: i32,
+    ) -> Result<Vec<(StockSymbol, i32, Decimal)>> {
+        let sql = r#"
+SELECT security_code, year, cash_dividend
+FROM dividend
+WHERE quarter = '' AND year >= $1 AND security_code = ANY($2);
+"#;
+        sqlx::query_as(sql)
+            .bind(from_year)
+            .bind(security_codes)
+            .fetch_all(database::get_connection())
+            .await
+            .context(format!(
+                "Failed to Dividend::fetch_annual_cash_dividends({}) from database",
+                from_year
+            ))
+    }
+
     /*    /// 取得尚未有指定年度配息的股票代號
         pub async fn fetch_stock_symbol_that_without_payout_ratio() -> Result<Vec<String>> {
             let sql = r#"
@@ -475,6 +558,7 @@ WHERE "SuspendListing" = false
             payout_ratio_cash: row.try_get("payout_ratio_cash")?,
             payout_ratio_stock: row.try_get("payout_ratio_stock")?,
             payout_ratio: row.try_get("payout_ratio")?,
+            batch_id: row.try_get("batch_id")?,
         })
     }
 }
@@ -542,6 +626,28 @@ impl From<goodinfo::dividend::GoodInfoDividend> for Dividend {
     }
 }
 
+impl From<yahoo::dividend::YahooDividendDetail> for Dividend {
+    fn from(d: yahoo::dividend::YahooDividendDetail) -> Self {
+        let mut e = Dividend::new();
+        e.quarter = d.quarter;
+        e.year = d.year;
+        e.year_of_dividend = d.year_of_dividend;
+        e.security_code = d.stock_symbol;
+        e.earnings_cash_dividend = d.cash_dividend;
+        e.cash_dividend = d.cash_dividend;
+        e.earnings_stock_dividend = d.stock_dividend;
+        e.stock_dividend = d.stock_dividend;
+        e.sum = d.cash_dividend + d.stock_dividend;
+        e.ex_dividend_date1 = d.ex_dividend_date1;
+        e.ex_dividend_date2 = d.ex_dividend_date2;
+        e.payable_date1 = d.payable_date1;
+        e.payable_date2 = d.payable_date2;
+        e.created_time = Local::now();
+        e.updated_time = Local::now();
+        e
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use chrono::TimeZone;
@@ -640,7 +746,7 @@ mod tests {
         dotenv::dotenv().ok();
         logging::debug_file_async("開始 fetch_yearly_dividends_sum_by_date".to_string());
         let mut e = Dividend::new();
-        e.security_code = "2887".to_string();
+        e.security_code = "2887".parse().unwrap();
         e.year = 2022;
         let datetime = Local.with_ymd_and_hms(2022, 3, 9, 0, 0, 0).unwrap();
 
@@ -659,7 +765,7 @@ mod tests {
         dotenv::dotenv().ok();
         logging::debug_file_async("開始 upsert".to_string());
         let mut e = Dividend::new();
-        e.security_code = String::from("79979");
+        e.security_code = "79979".parse().unwrap();
         e.year = 2023;
         e.year_of_dividend = 2023;
         e.quarter = String::from("H1");
@@ -0,0 +1,62 @@
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use sqlx::FromRow;
+
+use crate::{database, declare::StockSymbol};
+
+/// 缺少該年度的股利
+pub const KIND_MISSING: &str = "missing";
+/// 現金股利金額不一致
+pub const KIND_MISMATCH: &str = "mismatch";
+
+/// 股利數據與證交所除權除息結果比對不一致的記錄 原表名 dividend_discrepancies
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct DividendDiscrepancy {
+    pub security_code: StockSymbol,
+    /// 發放年度
+    pub year: i32,
+    /// missing、mismatch
+    pub kind: String,
+    /// 證交所除息的息值合計
+    pub expected: Decimal,
+    /// dividend 表的現金股利
+    pub actual: Decimal,
+    pub detail: String,
+}
+
+impl DividendDiscrepancy {
+    /// 清除上一次比對的結果後寫入本次的結果
+    pub async fn replace_all(list: &[DividendDiscrepancy]) -> Result<()> {
+        let mut tx = database::get_tx().await?;
+        sqlx::query("DELETE FROM dividend_discrepancies;")
+            .execute(&mut *tx)
+            .await
+            .context("Failed to DividendDiscrepancy::replace_all() from database")?;
+
+        let sql = r#"
+INSERT INTO dividend_discrepancies (security_code, year, kind, expected, actual, detail)
+VALUES ($1, $2, $3, $4, $5, $6);
+"#;
+        for item in list {
+            sqlx::query(sql)
+                .bind(&item.security_code)
+                .bind(item.year)
+                .bind(&item.kind)
+                .bind(item.expected)
+                .bind(item.actual)
+                .bind(&item.detail)
+                .execute(&mut *tx)
+                .await
+                .context(format!(
+                    "Failed to DividendDiscrepancy::replace_all({:?}) from database",
+                    item
+                ))?;
+        }
+
+        tx.commit()
+            .await
+            .context("Failed to DividendDiscrepancy::replace_all() commit")?;
+
+        Ok(())
+    }
+}
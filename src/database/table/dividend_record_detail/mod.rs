@@ -50,6 +50,7 @@ impl DividendRecordDetail {
     }
 
     /// 更新持股股息發放記錄
+    /// 在 calculation::dividend_record 的交易內執行，不以 database::with_retry 重試
     pub async fn upsert(&mut self, tx: &mut Option<Transaction<'_, Postgres>>) -> Result<i64> {
         let sql = r#"
         insert into dividend_record_detail (stock_ownership_details_serial, "year", cash, stock_money, stock, total)
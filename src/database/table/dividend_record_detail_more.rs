@@ -52,6 +52,7 @@ impl DividendRecordDetailMore {
     }
 
     /// 更新持股股息發放明細記錄
+    /// 在 calculation::dividend_record 的交易內執行，不以 database::with_retry 重試
     pub async fn upsert(&mut self, tx: &mut Option<Transaction<'_, Postgres>>) -> Result<i64> {
         let sql = r#"
 INSERT INTO dividend_record_detail_more (
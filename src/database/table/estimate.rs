@@ -2,14 +2,17 @@ use anyhow::{anyhow, Result};
 use chrono::NaiveDate;
 use sqlx::postgres::PgQueryResult;
 
-use crate::database;
+use crate::{
+    database::{self, timing::Timed},
+    declare::StockSymbol,
+};
 
 #[derive(sqlx::FromRow, Debug, Default)]
 pub struct Estimate {
     pub date: NaiveDate,
     // 使用 chrono 庫來處理日期和時間
     pub last_daily_quote_date: String,
-    pub security_code: String,
+    pub security_code: StockSymbol,
     pub name: String,
     pub closing_price: f64,
     pub percentage: f64,
@@ -33,7 +36,7 @@ pub struct Estimate {
 }
 
 impl Estimate {
-    pub fn new(security_code: String, date: NaiveDate) -> Self {
+    pub fn new(security_code: StockSymbol, date: NaiveDate) -> Self {
         Estimate {
             date,
             last_daily_quote_date: "".to_string(),
@@ -212,12 +215,57 @@ per AS(
         GROUP BY
             "SecurityCode"
     ) AS dq on dq.stock_symbol = calc.stock_symbol
+),
+pbr_band AS (
+    SELECT
+        calc.stock_symbol,
+        calc.cheap * s.net_asset_value_per_share AS cheap,
+        calc.fair * s.net_asset_value_per_share AS fair,
+        calc.expensive * s.net_asset_value_per_share AS expensive
+    FROM
+    (
+        SELECT
+            dq."SecurityCode" AS stock_symbol,
+            PERCENTILE_CONT(0.1) WITHIN GROUP (ORDER BY dq."ClosingPrice" / nav.net_asset_value_per_share) AS cheap,
+            PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY dq."ClosingPrice" / nav.net_asset_value_per_share) AS fair,
+            PERCENTILE_CONT(0.8) WITHIN GROUP (ORDER BY dq."ClosingPrice" / nav.net_asset_value_per_share) AS expensive
+        FROM "DailyQuotes" AS dq
+        INNER JOIN LATERAL (
+            SELECT h.net_asset_value_per_share
+            FROM net_asset_value_histories AS h
+            WHERE h.security_code = dq."SecurityCode"
+              AND h.date <= dq."Date"
+            ORDER BY h.date DESC
+            LIMIT 1
+        ) AS nav ON nav.net_asset_value_per_share > 0
+        WHERE
+            dq."Date" <= '{1}'
+            AND dq."year" IN ({0})
+            AND dq."ClosingPrice" > 0
+        GROUP BY dq."SecurityCode"
+    ) AS calc
+    INNER JOIN stocks AS s ON calc.stock_symbol = s.stock_symbol
+    WHERE s.net_asset_value_per_share > 0
+),
+yield_band AS (
+    SELECT
+        security_code AS stock_symbol,
+        dividend_yield,
+        percentile,
+        yield_p20,
+        yield_p50,
+        yield_p80
+    FROM
+        yield_percentiles
+    WHERE
+        "date" = '{1}'
 )
 INSERT INTO estimate (
     security_code, "date", percentage, closing_price, cheap, fair, expensive, price_cheap,
     price_fair, price_expensive, dividend_cheap, dividend_fair, dividend_expensive, year_count,
     eps_cheap, eps_fair, eps_expensive, pbr_cheap, pbr_fair, pbr_expensive,
-    per_cheap, per_fair, per_expensive, update_time
+    per_cheap, per_fair, per_expensive, yield_percentile, yield_cheap, yield_fair, yield_expensive,
+    pbr_band_cheap, pbr_band_fair, pbr_band_expensive, update_time
 )
 SELECT
     s.stock_symbol,
@@ -243,6 +291,13 @@ SELECT
     per.cheap,
     per.fair,
     per.expensive,
+    COALESCE(yb.percentile, 0),
+    CASE WHEN yb.yield_p80 > 0 THEN dq."ClosingPrice" * yb.dividend_yield / yb.yield_p80 ELSE 0 END,
+    CASE WHEN yb.yield_p50 > 0 THEN dq."ClosingPrice" * yb.dividend_yield / yb.yield_p50 ELSE 0 END,
+    CASE WHEN yb.yield_p20 > 0 THEN dq."ClosingPrice" * yb.dividend_yield / yb.yield_p20 ELSE 0 END,
+    COALESCE(pb.cheap, 0),
+    COALESCE(pb.fair, 0),
+    COALESCE(pb.expensive, 0),
     NOW()
 FROM stocks AS s
 INNER JOIN "DailyQuotes" AS dq ON dq."SecurityCode" = s.stock_symbol AND dq."Date" = '{1}'
@@ -251,6 +306,8 @@ INNER JOIN dividend ON dividend.stock_symbol = s.stock_symbol
 INNER JOIN eps ON eps.stock_symbol = s.stock_symbol
 INNER JOIN pbr ON pbr.stock_symbol = s.stock_symbol
 INNER JOIN per ON per.stock_symbol = s.stock_symbol
+LEFT JOIN yield_band AS yb ON yb.stock_symbol = s.stock_symbol
+LEFT JOIN pbr_band AS pb ON pb.stock_symbol = s.stock_symbol
 ON CONFLICT (date,security_code) DO UPDATE SET
     percentage = EXCLUDED.percentage,
     closing_price = EXCLUDED.closing_price,
@@ -273,12 +330,19 @@ ON CONFLICT (date,security_code) DO UPDATE SET
     per_cheap = EXCLUDED.per_cheap,
     per_fair = EXCLUDED.per_fair,
     per_expensive = EXCLUDED.per_expensive,
+    yield_percentile = EXCLUDED.yield_percentile,
+    yield_cheap = EXCLUDED.yield_cheap,
+    yield_fair = EXCLUDED.yield_fair,
+    yield_expensive = EXCLUDED.yield_expensive,
+    pbr_band_cheap = EXCLUDED.pbr_band_cheap,
+    pbr_band_fair = EXCLUDED.pbr_band_fair,
+    pbr_band_expensive = EXCLUDED.pbr_band_expensive,
     update_time = NOW();
 "#,
             years, date
         );
-        sqlx::query(&sql)
-            .execute(database::get_connection())
+        database::with_retry(|| sqlx::query(&sql).execute(database::get_connection()))
+            .timed("estimate", "upsert_all")
             .await
             .map_err(|why| {
                 anyhow!(
@@ -406,6 +470,48 @@ per AS(
                                        AND "PriceEarningRatio" > 0
         GROUP BY p.security_code_filter
     ) as dq on dq.security_code = calc.stock_symbol
+),
+pbr_band AS (
+    SELECT
+        inner_band.security_code,
+        inner_band.cheap * s.net_asset_value_per_share AS pbr_band_cheap,
+        inner_band.fair * s.net_asset_value_per_share AS pbr_band_fair,
+        inner_band.expensive * s.net_asset_value_per_share AS pbr_band_expensive
+    FROM (
+        SELECT
+            p.security_code_filter AS security_code,
+            PERCENTILE_CONT(0.1) WITHIN GROUP (ORDER BY dq."ClosingPrice" / nav.net_asset_value_per_share) AS cheap,
+            PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY dq."ClosingPrice" / nav.net_asset_value_per_share) AS fair,
+            PERCENTILE_CONT(0.8) WITHIN GROUP (ORDER BY dq."ClosingPrice" / nav.net_asset_value_per_share) AS expensive
+        FROM params AS p
+        INNER JOIN "DailyQuotes" AS dq ON p.security_code_filter = dq."SecurityCode"
+                                       AND dq."Date" <= p.date_filter
+                                       AND dq."year" = ANY(p.year_filter)
+                                       AND dq."ClosingPrice" > 0
+        INNER JOIN LATERAL (
+            SELECT h.net_asset_value_per_share
+            FROM net_asset_value_histories AS h
+            WHERE h.security_code = dq."SecurityCode"
+              AND h.date <= dq."Date"
+            ORDER BY h.date DESC
+            LIMIT 1
+        ) AS nav ON nav.net_asset_value_per_share > 0
+        GROUP BY p.security_code_filter
+    ) AS inner_band
+    INNER JOIN stocks AS s ON inner_band.security_code = s.stock_symbol
+    WHERE s.net_asset_value_per_share > 0
+),
+yield_band AS (
+    SELECT
+        yp.security_code,
+        yp.dividend_yield,
+        yp.percentile,
+        yp.yield_p20,
+        yp.yield_p50,
+        yp.yield_p80
+    FROM params AS p
+    INNER JOIN yield_percentiles AS yp ON p.security_code_filter = yp.security_code
+                                       AND yp."date" = p.date_filter
 )
 INSERT INTO estimate (
     security_code, "date", percentage, closing_price, cheap, fair, expensive,
@@ -414,6 +520,8 @@ INSERT INTO estimate (
     eps_cheap, eps_fair, eps_expensive,
     pbr_cheap, pbr_fair, pbr_expensive,
     per_cheap, per_fair, per_expensive,
+    yield_percentile, yield_cheap, yield_fair, yield_expensive,
+    pbr_band_cheap, pbr_band_fair, pbr_band_expensive,
     year_count, update_time
 )
 SELECT
@@ -439,6 +547,13 @@ SELECT
     per_cheap,
     per_fair,
     per_expensive,
+    COALESCE(yb.percentile, 0),
+    CASE WHEN yb.yield_p80 > 0 THEN dq."ClosingPrice" * yb.dividend_yield / yb.yield_p80 ELSE 0 END,
+    CASE WHEN yb.yield_p50 > 0 THEN dq."ClosingPrice" * yb.dividend_yield / yb.yield_p50 ELSE 0 END,
+    CASE WHEN yb.yield_p20 > 0 THEN dq."ClosingPrice" * yb.dividend_yield / yb.yield_p20 ELSE 0 END,
+    COALESCE(pb.pbr_band_cheap, 0),
+    COALESCE(pb.pbr_band_fair, 0),
+    COALESCE(pb.pbr_band_expensive, 0),
     year_count,
     NOW()
 FROM params AS p
@@ -449,6 +564,8 @@ INNER JOIN dividend ON p.security_code_filter = dividend.security_code
 INNER JOIN eps ON p.security_code_filter = eps.stock_symbol
 INNER JOIN pbr ON p.security_code_filter = pbr.security_code
 INNER JOIN per ON p.security_code_filter = per.stock_symbol
+LEFT JOIN yield_band AS yb ON p.security_code_filter = yb.security_code
+LEFT JOIN pbr_band AS pb ON p.security_code_filter = pb.security_code
 ON CONFLICT (date, security_code) DO UPDATE SET
     percentage = EXCLUDED.percentage,
     closing_price = EXCLUDED.closing_price,
@@ -470,14 +587,21 @@ ON CONFLICT (date, security_code) DO UPDATE SET
     per_cheap = EXCLUDED.per_cheap,
     per_fair = EXCLUDED.per_fair,
     per_expensive = EXCLUDED.per_expensive,
+    yield_percentile = EXCLUDED.yield_percentile,
+    yield_cheap = EXCLUDED.yield_cheap,
+    yield_fair = EXCLUDED.yield_fair,
+    yield_expensive = EXCLUDED.yield_expensive,
+    pbr_band_cheap = EXCLUDED.pbr_band_cheap,
+    pbr_band_fair = EXCLUDED.pbr_band_fair,
+    pbr_band_expensive = EXCLUDED.pbr_band_expensive,
     year_count = EXCLUDED.year_count,
     update_time = NOW();
 "#,
             years, &self.security_code, self.date
         );
 
-        sqlx::query(&sql)
-            .execute(database::get_connection())
+        database::with_retry(|| sqlx::query(&sql).execute(database::get_connection()))
+            .timed("estimate", "upsert")
             .await
             .map_err(|why| {
                 anyhow!(
@@ -508,7 +632,7 @@ mod tests {
         let years: Vec<i32> = (0..10).map(|i| current_date.year() - i).collect();
         let years_vec: Vec<String> = years.iter().map(|&year| year.to_string()).collect();
         let years_str = years_vec.join(",");
-        let estimate = Estimate::new("9921".to_string(), current_date);
+        let estimate = Estimate::new("9921".parse().unwrap(), current_date);
 
         match estimate.upsert(years_str).await {
             Ok(r) => logging::debug_file_async(format!("Estimate::upsert:{:#?}", r)),
@@ -0,0 +1,148 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use sqlx::{postgres::PgQueryResult, FromRow};
+
+use crate::database::{self, timing::Timed};
+
+/// 估價模型的實際報酬 原表名 estimate_performance
+pub struct EstimatePerformance;
+
+/// 估價模型在某個驗證期間的命中率
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct ModelHitRate {
+    /// 估價模型 overall、price、dividend、eps、pbr、per
+    pub model: String,
+    /// 驗證的期間(月)
+    pub months: i32,
+    /// 訊號的數量
+    pub samples: i64,
+    /// 命中的數量
+    pub hits: i64,
+    /// 便宜價訊號的平均報酬率(%)
+    pub cheap_return: Option<Decimal>,
+    /// 昂貴價訊號的平均報酬率(%)
+    pub expensive_return: Option<Decimal>,
+}
+
+impl EstimatePerformance {
+    /// 驗證 3、6、12 個月前的估價，估價日往前兩週內的數據一併驗證，停機錯過的日子下次執行時會補上
+    ///
+    /// 報酬率以 adjusted_quotes 的還原收盤價計算，除權息不會被當成下跌
+    pub async fn upsert(date: NaiveDate) -> Result<PgQueryResult> {
+        let sql = r#"
+WITH horizons AS (
+    SELECT unnest(ARRAY[3, 6, 12]) AS months
+),
+signals AS (
+    SELECT
+        e.security_code,
+        e.date,
+        m.model,
+        h.months,
+        e.closing_price,
+        m.cheap,
+        m.fair,
+        m.expensive,
+        (e.date + make_interval(months => h.months))::date AS forward_date
+    FROM estimate AS e
+    CROSS JOIN horizons AS h
+    CROSS JOIN LATERAL (
+        VALUES
+            ('overall', e.cheap, e.fair, e.expensive),
+            ('price', e.price_cheap, e.price_fair, e.price_expensive),
+            ('dividend', e.dividend_cheap, e.dividend_fair, e.dividend_expensive),
+            ('eps', e.eps_cheap, e.eps_fair, e.eps_expensive),
+            ('pbr', e.pbr_cheap, e.pbr_fair, e.pbr_expensive),
+            ('per', e.per_cheap, e.per_fair, e.per_expensive),
+            ('yield', e.yield_cheap, e.yield_fair, e.yield_expensive),
+            ('pbr_band', e.pbr_band_cheap, e.pbr_band_fair, e.pbr_band_expensive)
+    ) AS m(model, cheap, fair, expensive)
+    WHERE e.date BETWEEN ($1::date - make_interval(months => h.months))::date - 14
+                     AND ($1::date - make_interval(months => h.months))::date
+      AND e.closing_price > 0
+      AND m.cheap > 0
+      AND m.expensive > 0
+      AND (e.closing_price <= m.cheap OR e.closing_price >= m.expensive)
+),
+returns AS (
+    SELECT DISTINCT ON (s.security_code, s.date, s.model, s.months)
+        s.*,
+        fq.date AS traded_date,
+        (fq.adjusted_closing_price - sq.adjusted_closing_price) / sq.adjusted_closing_price * 100 AS forward_return
+    FROM signals AS s
+    INNER JOIN adjusted_quotes AS sq ON sq.security_code = s.security_code AND sq.date = s.date
+    INNER JOIN adjusted_quotes AS fq ON fq.security_code = s.security_code
+        AND fq.date BETWEEN s.forward_date - 14 AND s.forward_date
+    WHERE sq.adjusted_closing_price > 0
+    ORDER BY s.security_code, s.date, s.model, s.months, fq.date DESC
+)
+INSERT INTO estimate_performance (
+    security_code, date, model, months, closing_price, cheap, fair, expensive,
+    signal, forward_date, forward_return, hit
+)
+SELECT
+    security_code,
+    date,
+    model,
+    months,
+    closing_price,
+    cheap,
+    fair,
+    expensive,
+    CASE WHEN closing_price <= cheap THEN 'cheap' ELSE 'expensive' END,
+    traded_date,
+    ROUND(forward_return, 4),
+    CASE WHEN closing_price <= cheap THEN forward_return > 0 ELSE forward_return < 0 END
+FROM returns
+ON CONFLICT (security_code, date, model, months) DO UPDATE SET
+    closing_price = EXCLUDED.closing_price,
+    cheap = EXCLUDED.cheap,
+    fair = EXCLUDED.fair,
+    expensive = EXCLUDED.expensive,
+    signal = EXCLUDED.signal,
+    forward_date = EXCLUDED.forward_date,
+    forward_return = EXCLUDED.forward_return,
+    hit = EXCLUDED.hit,
+    updated_time = now();
+"#;
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(date)
+                .execute(database::get_connection())
+        })
+        .timed("estimate_performance", "upsert")
+        .await
+        .context(format!(
+            "Failed to EstimatePerformance::upsert({}) from database",
+            date
+        ))
+    }
+
+    /// 統計驗證日在 start 到 end(含)之間各模型、各驗證期間的命中率
+    pub async fn fetch_hit_rates(start: NaiveDate, end: NaiveDate) -> Result<Vec<ModelHitRate>> {
+        let sql = r#"
+SELECT
+    model,
+    months,
+    COUNT(*) AS samples,
+    COUNT(*) FILTER (WHERE hit) AS hits,
+    ROUND(AVG(forward_return) FILTER (WHERE signal = 'cheap'), 2) AS cheap_return,
+    ROUND(AVG(forward_return) FILTER (WHERE signal = 'expensive'), 2) AS expensive_return
+FROM estimate_performance
+WHERE forward_date BETWEEN $1 AND $2
+GROUP BY model, months
+ORDER BY months, model;
+"#;
+        sqlx::query_as::<_, ModelHitRate>(sql)
+            .bind(start)
+            .bind(end)
+            .fetch_all(database::get_connection())
+            .timed("estimate_performance", "fetch_hit_rates")
+            .await
+            .context(format!(
+                "Failed to EstimatePerformance::fetch_hit_rates({}, {}) from database",
+                start, end
+            ))
+    }
+}
@@ -0,0 +1,231 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use sqlx::{postgres::PgQueryResult, FromRow};
+
+use crate::{
+    database::{self, timing::Timed},
+    logging,
+};
+
+/// 重試後仍寫入失敗的數據 原表名 failed_writes
+#[derive(FromRow, Debug, Clone, Default, PartialEq)]
+pub struct FailedWrite {
+    pub serial: i64,
+    /// 寫入的資料表
+    pub table_name: String,
+    /// 資料的鍵值 ex. 股票代號-年月
+    pub record_key: String,
+    /// 序列化後的完整數據(JSON)
+    pub payload: String,
+    /// 最後一次寫入失敗的原因
+    pub error: String,
+    /// 排程重新寫入失敗的次數
+    pub attempts: i32,
+    pub updated_time: DateTime<Local>,
+}
+
+impl FailedWrite {
+    pub fn new<T: Serialize>(
+        table_name: &str,
+        record_key: String,
+        entity: &T,
+        error: String,
+    ) -> Result<Self> {
+        Ok(FailedWrite {
+            table_name: table_name.to_string(),
+            record_key,
+            payload: serde_json::to_string(entity).context(format!(
+                "Failed to serialize {} for failed_writes",
+                table_name
+            ))?,
+            error,
+            updated_time: Local::now(),
+            ..Default::default()
+        })
+    }
+
+    /// table_name 與 record_key 為組合鍵，同一筆數據再次失敗時以新的數據取代並重新計算重試次數
+    pub async fn upsert(&self) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO failed_writes (table_name, record_key, payload, error)
+VALUES ($1, $2, $3::jsonb, $4)
+ON CONFLICT (table_name, record_key) DO UPDATE SET
+    payload = EXCLUDED.payload,
+    error = EXCLUDED.error,
+    attempts = 0,
+    updated_time = now();
+"#;
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(&self.table_name)
+                .bind(&self.record_key)
+                .bind(&self.payload)
+                .bind(&self.error)
+                .execute(database::get_connection())
+        })
+        .timed("failed_writes", "upsert")
+        .await
+        .context(format!(
+            "Failed to FailedWrite::upsert({}, {}) from database",
+            self.table_name, self.record_key
+        ))
+    }
+
+    /// 取得重試次數小於 max_attempts 的記錄，依加入的順序
+    pub async fn fetch_pending(max_attempts: i32, limit: i64) -> Result<Vec<FailedWrite>> {
+        let sql = r#"
+SELECT serial, table_name, record_key, payload::text AS payload, error, attempts, updated_time
+FROM failed_writes
+WHERE attempts < $1
+ORDER BY serial
+LIMIT $2;
+"#;
+        sqlx::query_as::<_, FailedWrite>(sql)
+            .bind(max_attempts)
+            .bind(limit)
+            .fetch_all(database::get_connection())
+            .timed("failed_writes", "fetch_pending")
+            .await
+            .context(format!(
+                "Failed to FailedWrite::fetch_pending({}) from database",
+                max_attempts
+            ))
+    }
+
+    /// 取得最近更新的記錄
+    pub async fn fetch_recent(limit: i64) -> Result<Vec<FailedWrite>> {
+        let sql = r#"
+SELECT serial, table_name, record_key, payload::text AS payload, error, attempts, updated_time
+FROM failed_writes
+ORDER BY updated_time DESC
+LIMIT $1;
+"#;
+        sqlx::query_as::<_, FailedWrite>(sql)
+            .bind(limit)
+            .fetch_all(database::get_connection())
+            .timed("failed_writes", "fetch_recent")
+            .await
+            .context("Failed to FailedWrite::fetch_recent from database")
+    }
+
+    /// 各資料表的記錄筆數，依資料表名稱排序
+    pub async fn count_by_table() -> Result<Vec<(String, i64)>> {
+        let sql = r#"
+SELECT table_name, COUNT(*)
+FROM failed_writes
+GROUP BY table_name
+ORDER BY table_name;
+"#;
+        sqlx::query_as::<_, (String, i64)>(sql)
+            .fetch_all(database::get_connection())
+            .timed("failed_writes", "count_by_table")
+            .await
+            .context("Failed to FailedWrite::count_by_table from database")
+    }
+
+    /// 重新寫入仍失敗時累加重試次數並記錄原因
+    pub async fn record_attempt(serial: i64, error: &str) -> Result<PgQueryResult> {
+        let sql = r#"
+UPDATE failed_writes
+SET attempts = attempts + 1, error = $2, updated_time = now()
+WHERE serial = $1;
+"#;
+        sqlx::query(sql)
+            .bind(serial)
+            .bind(error)
+            .execute(database::get_connection())
+            .timed("failed_writes", "record_attempt")
+            .await
+            .context(format!(
+                "Failed to FailedWrite::record_attempt({}) from database",
+                serial
+            ))
+    }
+
+    /// 刪除指定序號的記錄
+    pub async fn delete(serial: i64) -> Result<PgQueryResult> {
+        let sql = "DELETE FROM failed_writes WHERE serial = $1;";
+        sqlx::query(sql)
+            .bind(serial)
+            .execute(database::get_connection())
+            .timed("failed_writes", "delete")
+            .await
+            .context(format!(
+                "Failed to FailedWrite::delete({}) from database",
+                serial
+            ))
+    }
+
+    /// 刪除指定資料表的全部記錄，table_name 為 None 時刪除全部
+    pub async fn purge(table_name: Option<&str>) -> Result<PgQueryResult> {
+        let sql = "DELETE FROM failed_writes WHERE $1::text IS NULL OR table_name = $1;";
+        sqlx::query(sql)
+            .bind(table_name)
+            .execute(database::get_connection())
+            .timed("failed_writes", "purge")
+            .await
+            .context(format!(
+                "Failed to FailedWrite::purge({:?}) from database",
+                table_name
+            ))
+    }
+}
+
+/// 寫入失敗時在背景將數據放入 failed_writes 等待排程重試，不影響原本的結果
+/// ex. `revenue.upsert().await.requeue("revenue", key, &revenue)?`
+///
+/// 只有 database::retry_queue 能重新寫入的資料表才需要呼叫
+pub trait Requeue {
+    fn requeue<T: Serialize>(self, table_name: &str, record_key: String, entity: &T) -> Self;
+}
+
+impl Requeue for Result<PgQueryResult> {
+    fn requeue<T: Serialize>(self, table_name: &str, record_key: String, entity: &T) -> Self {
+        if let Err(why) = &self {
+            match FailedWrite::new(table_name, record_key, entity, format!("{:?}", why)) {
+                Ok(failed) => {
+                    tokio::spawn(async move {
+                        if let Err(why) = failed.upsert().await {
+                            logging::error_file_async(format!("{:?}", why));
+                        }
+                    });
+                }
+                Err(why) => logging::error_file_async(format!("{:?}", why)),
+            }
+        }
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::table::odd_lot_quote::OddLotQuote;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        let quote = OddLotQuote {
+            security_code: "2330".parse().unwrap(),
+            trading_volume: 1234,
+            ..Default::default()
+        };
+        let failed = FailedWrite::new(
+            "odd_lot_quotes",
+            "2330-2024-01-02".to_string(),
+            &quote,
+            "timeout".to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(failed.table_name, "odd_lot_quotes");
+        assert_eq!(failed.attempts, 0);
+        assert_eq!(
+            serde_json::from_str::<OddLotQuote>(&failed.payload).unwrap(),
+            quote
+        );
+    }
+}
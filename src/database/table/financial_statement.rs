@@ -1,4 +1,4 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use chrono::{DateTime, Local};
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
@@ -11,8 +11,8 @@ use sqlx::{
 use crate::{
     crawler::{self, twse, wespai, yahoo},
     database,
-    declare::Quarter,
-    util::map::Keyable,
+    declare::{Quarter, StatementType, StockSymbol},
+    util::{self, map::Keyable},
 };
 
 #[derive(sqlx::Type, sqlx::FromRow, Debug, Clone, Deserialize, Serialize)]
@@ -22,7 +22,7 @@ pub struct FinancialStatement {
     created_time: DateTime<Local>,
     /// 季度 Q4 Q3 Q2 Q1
     pub quarter: String,
-    pub security_code: String,
+    pub security_code: StockSymbol,
     /// 營業毛利率
     pub gross_profit: Decimal,
     /// 營業利益率
@@ -46,6 +46,22 @@ pub struct FinancialStatement {
     serial: i64,
     /// 年度
     pub year: i64,
+    /// 財報類別 1:合併 2:個別，同一期已有合併財報時不會被個別財報覆蓋
+    #[serde(default = "consolidated")]
+    pub statement_type: i32,
+    /// 營業活動之淨現金流入(流出)，當年度累計至該季的仟元
+    #[serde(default)]
+    pub operating_cash_flow: Decimal,
+    /// 投資活動之淨現金流入(流出)，當年度累計至該季的仟元
+    #[serde(default)]
+    pub investing_cash_flow: Decimal,
+    /// 籌資活動之淨現金流入(流出)，當年度累計至該季的仟元
+    #[serde(default)]
+    pub financing_cash_flow: Decimal,
+}
+
+fn consolidated() -> i32 {
+    StatementType::Consolidated.serial()
 }
 
 impl Keyable for FinancialStatement {
@@ -59,7 +75,7 @@ impl Keyable for FinancialStatement {
 }
 
 impl FinancialStatement {
-    pub fn new(security_code: String) -> Self {
+    pub fn new(security_code: StockSymbol) -> Self {
         FinancialStatement {
             updated_time: Default::default(),
             created_time: Default::default(),
@@ -77,17 +93,26 @@ impl FinancialStatement {
             return_on_assets: Default::default(),
             serial: 0,
             year: 0,
+            statement_type: consolidated(),
+            operating_cash_flow: Default::default(),
+            investing_cash_flow: Default::default(),
+            financing_cash_flow: Default::default(),
         }
     }
 
+    /// 自由現金流量，以營業活動與投資活動的淨現金流量合計，當年度累計至該季的仟元
+    pub fn free_cash_flow(&self) -> Decimal {
+        self.operating_cash_flow + self.investing_cash_flow
+    }
+
     pub async fn upsert(self) -> Result<PgQueryResult> {
         let sql = r#"
 INSERT INTO financial_statement (
     security_code, "year", quarter, gross_profit, operating_profit_margin,
     "pre-tax_income", net_income, net_asset_value_per_share, sales_per_share,
     earnings_per_share, profit_before_tax, return_on_equity, return_on_assets,
-    created_time, updated_time)
-VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+    created_time, updated_time, statement_type)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
 ON CONFLICT (security_code,"year",quarter) DO UPDATE SET
     gross_profit = EXCLUDED.gross_profit,
     operating_profit_margin = EXCLUDED.operating_profit_margin,
@@ -99,91 +124,214 @@ ON CONFLICT (security_code,"year",quarter) DO UPDATE SET
     profit_before_tax = EXCLUDED.profit_before_tax,
     return_on_equity = EXCLUDED.return_on_equity,
     return_on_assets = EXCLUDED.return_on_assets,
-    updated_time = EXCLUDED.updated_time;
+    statement_type = EXCLUDED.statement_type,
+    updated_time = EXCLUDED.updated_time
+WHERE EXCLUDED.statement_type <= financial_statement.statement_type;
 "#;
-        sqlx::query(sql)
-            .bind(&self.security_code)
-            .bind(self.year)
-            .bind(&self.quarter)
-            .bind(self.gross_profit)
-            .bind(self.operating_profit_margin)
-            .bind(self.pre_tax_income)
-            .bind(self.net_income)
-            .bind(self.net_asset_value_per_share)
-            .bind(self.sales_per_share)
-            .bind(self.earnings_per_share)
-            .bind(self.profit_before_tax)
-            .bind(self.return_on_equity)
-            .bind(self.return_on_assets)
-            .bind(self.created_time)
-            .bind(self.updated_time)
-            .execute(database::get_connection())
-            .await
-            .map_err(|why| {
-                anyhow!(
-                    "Failed to upsert({:#?}) from database\nsql:{}\n {:?}",
-                    self,
-                    &sql,
-                    why
-                )
-            })
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(&self.security_code)
+                .bind(self.year)
+                .bind(&self.quarter)
+                .bind(self.gross_profit)
+                .bind(self.operating_profit_margin)
+                .bind(self.pre_tax_income)
+                .bind(self.net_income)
+                .bind(self.net_asset_value_per_share)
+                .bind(self.sales_per_share)
+                .bind(self.earnings_per_share)
+                .bind(self.profit_before_tax)
+                .bind(self.return_on_equity)
+                .bind(self.return_on_assets)
+                .bind(self.created_time)
+                .bind(self.updated_time)
+                .bind(self.statement_type)
+                .execute(database::get_connection())
+        })
+        .await
+        .map_err(|why| {
+            anyhow!(
+                "Failed to upsert({:#?}) from database\nsql:{}\n {:?}",
+                self,
+                &sql,
+                why
+            )
+        })
+    }
+
+    /// 以 UNNEST 將一批財報合併成一個 INSERT，同一期重複時以最後一筆為準，
+    /// 與 upsert 相同已有合併財報時不會被個別財報覆蓋，回傳有寫入的 (股票代號, 年度, 季度)
+    pub async fn upsert_batch(
+        statements: &[FinancialStatement],
+    ) -> Result<Vec<(String, i64, String)>> {
+        let sql = r#"
+INSERT INTO financial_statement (
+    security_code, "year", quarter, gross_profit, operating_profit_margin,
+    "pre-tax_income", net_income, net_asset_value_per_share, sales_per_share,
+    earnings_per_share, profit_before_tax, return_on_equity, return_on_assets,
+    created_time, updated_time, statement_type)
+SELECT * FROM UNNEST(
+    $1::varchar[], $2::bigint[], $3::varchar[], $4::numeric[], $5::numeric[],
+    $6::numeric[], $7::numeric[], $8::numeric[], $9::numeric[],
+    $10::numeric[], $11::numeric[], $12::numeric[], $13::numeric[],
+    $14::timestamptz[], $15::timestamptz[], $16::integer[])
+ON CONFLICT (security_code,"year",quarter) DO UPDATE SET
+    gross_profit = EXCLUDED.gross_profit,
+    operating_profit_margin = EXCLUDED.operating_profit_margin,
+    "pre-tax_income" = EXCLUDED."pre-tax_income",
+    net_income = EXCLUDED.net_income,
+    net_asset_value_per_share = EXCLUDED.net_asset_value_per_share,
+    sales_per_share = EXCLUDED.sales_per_share,
+    earnings_per_share = EXCLUDED.earnings_per_share,
+    profit_before_tax = EXCLUDED.profit_before_tax,
+    return_on_equity = EXCLUDED.return_on_equity,
+    return_on_assets = EXCLUDED.return_on_assets,
+    statement_type = EXCLUDED.statement_type,
+    updated_time = EXCLUDED.updated_time
+WHERE EXCLUDED.statement_type <= financial_statement.statement_type
+RETURNING security_code, "year", quarter;
+"#;
+        let statements = util::map::dedup_by_key(statements, |fs| fs.key());
+        database::with_retry(|| {
+            sqlx::query_as::<_, (String, i64, String)>(sql)
+                .bind(util::map::column(&statements, |fs| {
+                    fs.security_code.clone()
+                }))
+                .bind(util::map::column(&statements, |fs| fs.year))
+                .bind(util::map::column(&statements, |fs| fs.quarter.clone()))
+                .bind(util::map::column(&statements, |fs| fs.gross_profit))
+                .bind(util::map::column(&statements, |fs| {
+                    fs.operating_profit_margin
+                }))
+                .bind(util::map::column(&statements, |fs| fs.pre_tax_income))
+                .bind(util::map::column(&statements, |fs| fs.net_income))
+                .bind(util::map::column(&statements, |fs| {
+                    fs.net_asset_value_per_share
+                }))
+                .bind(util::map::column(&statements, |fs| fs.sales_per_share))
+                .bind(util::map::column(&statements, |fs| fs.earnings_per_share))
+                .bind(util::map::column(&statements, |fs| fs.profit_before_tax))
+                .bind(util::map::column(&statements, |fs| fs.return_on_equity))
+                .bind(util::map::column(&statements, |fs| fs.return_on_assets))
+                .bind(util::map::column(&statements, |fs| fs.created_time))
+                .bind(util::map::column(&statements, |fs| fs.updated_time))
+                .bind(util::map::column(&statements, |fs| fs.statement_type))
+                .fetch_all(database::get_connection())
+        })
+        .await
+        .context(format!(
+            "Failed to FinancialStatement::upsert_batch({} rows) from database",
+            statements.len()
+        ))
     }
 
     pub async fn upsert_earnings_per_share(&self) -> Result<PgQueryResult> {
         let sql = r#"
 INSERT INTO financial_statement (
-    security_code, "year", quarter, earnings_per_share, created_time, updated_time)
-VALUES ($1, $2, $3, $4, $5, $6)
+    security_code, "year", quarter, earnings_per_share, created_time, updated_time, statement_type)
+VALUES ($1, $2, $3, $4, $5, $6, $7)
 ON CONFLICT (security_code,"year",quarter)
 DO UPDATE SET 
     earnings_per_share = excluded.earnings_per_share,
-    updated_time = EXCLUDED.updated_time;
+    statement_type = EXCLUDED.statement_type,
+    updated_time = EXCLUDED.updated_time
+WHERE EXCLUDED.statement_type <= financial_statement.statement_type;
 "#;
-        sqlx::query(sql)
-            .bind(&self.security_code)
-            .bind(self.year)
-            .bind(&self.quarter)
-            .bind(self.earnings_per_share)
-            .bind(self.created_time)
-            .bind(self.updated_time)
-            .execute(database::get_connection())
-            .await
-            .map_err(|why| {
-                anyhow!(
-                    "Failed to upsert_earnings_per_share({:#?}) from database\nsql:{}\n {:?}",
-                    self,
-                    &sql,
-                    why
-                )
-            })
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(&self.security_code)
+                .bind(self.year)
+                .bind(&self.quarter)
+                .bind(self.earnings_per_share)
+                .bind(self.created_time)
+                .bind(self.updated_time)
+                .bind(self.statement_type)
+                .execute(database::get_connection())
+        })
+        .await
+        .map_err(|why| {
+            anyhow!(
+                "Failed to upsert_earnings_per_share({:#?}) from database\nsql:{}\n {:?}",
+                self,
+                &sql,
+                why
+            )
+        })
     }
 
     pub async fn upsert_annual_eps(&self) -> Result<PgQueryResult> {
         let sql = r#"
 INSERT INTO financial_statement (
-    security_code, "year", quarter, earnings_per_share, profit_before_tax, sales_per_share, created_time, updated_time)
-VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-ON CONFLICT (security_code,"year",quarter) DO NOTHING;
+    security_code, "year", quarter, earnings_per_share, profit_before_tax, sales_per_share, created_time, updated_time, statement_type)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+ON CONFLICT (security_code,"year",quarter) DO UPDATE SET
+    earnings_per_share = EXCLUDED.earnings_per_share,
+    profit_before_tax = EXCLUDED.profit_before_tax,
+    sales_per_share = EXCLUDED.sales_per_share,
+    statement_type = EXCLUDED.statement_type,
+    updated_time = EXCLUDED.updated_time
+WHERE EXCLUDED.statement_type < financial_statement.statement_type;
 "#;
-        sqlx::query(sql)
-            .bind(&self.security_code)
-            .bind(self.year)
-            .bind(&self.quarter)
-            .bind(self.earnings_per_share)
-            .bind(self.profit_before_tax)
-            .bind(self.sales_per_share)
-            .bind(self.created_time)
-            .bind(self.updated_time)
-            .execute(database::get_connection())
-            .await
-            .map_err(|why| {
-                anyhow!(
-                    "Failed to upsert_annual_eps({:#?}) from database\nsql:{}\n {:?}",
-                    self,
-                    &sql,
-                    why
-                )
-            })
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(&self.security_code)
+                .bind(self.year)
+                .bind(&self.quarter)
+                .bind(self.earnings_per_share)
+                .bind(self.profit_before_tax)
+                .bind(self.sales_per_share)
+                .bind(self.created_time)
+                .bind(self.updated_time)
+                .bind(self.statement_type)
+                .execute(database::get_connection())
+        })
+        .await
+        .map_err(|why| {
+            anyhow!(
+                "Failed to upsert_annual_eps({:#?}) from database\nsql:{}\n {:?}",
+                self,
+                &sql,
+                why
+            )
+        })
+    }
+
+    /// 寫入現金流量，只更新相同財報類別的數據，避免合併與個別財報混在同一筆
+    pub async fn upsert_cash_flow(&self) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO financial_statement (
+    security_code, "year", quarter, operating_cash_flow, investing_cash_flow, financing_cash_flow,
+    created_time, updated_time, statement_type)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+ON CONFLICT (security_code,"year",quarter) DO UPDATE SET
+    operating_cash_flow = EXCLUDED.operating_cash_flow,
+    investing_cash_flow = EXCLUDED.investing_cash_flow,
+    financing_cash_flow = EXCLUDED.financing_cash_flow,
+    updated_time = EXCLUDED.updated_time
+WHERE EXCLUDED.statement_type = financial_statement.statement_type;
+"#;
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(&self.security_code)
+                .bind(self.year)
+                .bind(&self.quarter)
+                .bind(self.operating_cash_flow)
+                .bind(self.investing_cash_flow)
+                .bind(self.financing_cash_flow)
+                .bind(self.created_time)
+                .bind(self.updated_time)
+                .bind(self.statement_type)
+                .execute(database::get_connection())
+        })
+        .await
+        .map_err(|why| {
+            anyhow!(
+                "Failed to upsert_cash_flow({:#?}) from database\nsql:{}\n {:?}",
+                self,
+                &sql,
+                why
+            )
+        })
     }
 
     pub async fn update_roe_roa(&self) -> Result<PgQueryResult> {
@@ -234,7 +382,11 @@ SELECT
     return_on_equity,
     return_on_assets,
     created_time,
-    updated_time
+    updated_time,
+    statement_type,
+    operating_cash_flow,
+    investing_cash_flow,
+    financing_cash_flow
 FROM financial_statement
 WHERE "year" = $1 AND quarter= ''
 "#;
@@ -258,6 +410,10 @@ WHERE "year" = $1 AND quarter= ''
                 return_on_assets: row.try_get("return_on_assets")?,
                 serial: row.try_get("serial")?,
                 year: row.try_get("year")?,
+                statement_type: row.try_get("statement_type")?,
+                operating_cash_flow: row.try_get("operating_cash_flow")?,
+                investing_cash_flow: row.try_get("investing_cash_flow")?,
+                financing_cash_flow: row.try_get("financing_cash_flow")?,
             })
         })
         .fetch_all(database::get_connection())
@@ -289,7 +445,11 @@ SELECT
     return_on_equity,
     return_on_assets,
     created_time,
-    updated_time
+    updated_time,
+    statement_type,
+    operating_cash_flow,
+    investing_cash_flow,
+    financing_cash_flow
 FROM financial_statement
 WHERE quarter = $1 AND (return_on_equity = 0 OR return_on_assets = 0 OR net_asset_value_per_share = 0)
 "#,
@@ -324,6 +484,10 @@ WHERE quarter = $1 AND (return_on_equity = 0 OR return_on_assets = 0 OR net_asse
                 return_on_assets: row.try_get("return_on_assets")?,
                 serial: row.try_get("serial")?,
                 year: row.try_get("year")?,
+                statement_type: row.try_get("statement_type")?,
+                operating_cash_flow: row.try_get("operating_cash_flow")?,
+                investing_cash_flow: row.try_get("investing_cash_flow")?,
+                financing_cash_flow: row.try_get("financing_cash_flow")?,
             })
         })
         .fetch_all(database::get_connection())
@@ -390,6 +554,10 @@ ORDER BY
                 return_on_assets: Default::default(),
                 serial: Default::default(),
                 year: row.try_get("year")?,
+                statement_type: consolidated(),
+                operating_cash_flow: Default::default(),
+                investing_cash_flow: Default::default(),
+                financing_cash_flow: Default::default(),
             })
         })
         .fetch_all(database::get_connection())
@@ -429,6 +597,93 @@ pub async fn fetch_cumulative_eps(security_code:&str, year: i32, quarters: Vec<Q
     Ok(result.0.unwrap_or_else(|| dec!(0)))
 }
 
+/// 取得指定股票最近幾季的季報，由新到舊排序
+pub async fn fetch_recent_quarters(
+    security_code: &str,
+    limit: i64,
+) -> Result<Vec<FinancialStatement>> {
+    let sql = r#"
+SELECT
+    serial,
+    security_code,
+    year,
+    quarter,
+    gross_profit,
+    operating_profit_margin,
+    "pre-tax_income" AS pre_tax_income,
+    net_income,
+    net_asset_value_per_share,
+    sales_per_share,
+    earnings_per_share,
+    profit_before_tax,
+    return_on_equity,
+    return_on_assets,
+    created_time,
+    updated_time,
+    statement_type,
+    operating_cash_flow,
+    investing_cash_flow,
+    financing_cash_flow
+FROM financial_statement
+WHERE security_code = $1 AND quarter IN ('Q1', 'Q2', 'Q3', 'Q4')
+ORDER BY "year" DESC, quarter DESC
+LIMIT $2
+"#;
+
+    sqlx::query_as::<_, FinancialStatement>(sql)
+        .bind(security_code)
+        .bind(limit)
+        .fetch_all(database::get_connection())
+        .await
+        .context(format!(
+            "Failed to fetch_recent_quarters({}) from database",
+            security_code
+        ))
+}
+
+/// 取得指定年度與季度已有現金流量的股票代號
+pub async fn fetch_symbols_with_cash_flow(year: i32, quarter: Quarter) -> Result<Vec<StockSymbol>> {
+    let sql = r#"
+SELECT security_code
+FROM financial_statement
+WHERE "year" = $1 AND quarter = $2 AND operating_cash_flow <> 0
+"#;
+
+    sqlx::query_scalar(sql)
+        .bind(year as i64)
+        .bind(quarter.to_string())
+        .fetch_all(database::get_connection())
+        .await
+        .context(format!(
+            "Failed to fetch_symbols_with_cash_flow({}, {}) from database",
+            year, quarter
+        ))
+}
+
+/// 取得指定股票中已收錄指定年度與季度財報的股票代號
+pub async fn fetch_recorded_symbols(
+    year: i32,
+    quarters: &[String],
+    security_codes: &[StockSymbol],
+) -> Result<Vec<StockSymbol>> {
+    let sql = r#"
+SELECT DISTINCT security_code
+FROM financial_statement
+WHERE "year" = $1 AND quarter = ANY($2) AND security_code = ANY($3)
+"#;
+
+    sqlx::query_scalar(sql)
+        .bind(year as i64)
+        .bind(quarters)
+        .bind(security_codes)
+        .fetch_all(database::get_connection())
+        .await
+        .context(format!(
+            "Failed to fetch_recorded_symbols({}, {:?}) from database",
+            year, quarters
+        ))
+}
+
 //let entity: Entity = fs.into(); // 或者 let entity = Entity::from(fs);
 impl From<yahoo::profile::Profile> for FinancialStatement {
     fn from(fs: yahoo::profile::Profile) -> Self {
@@ -490,6 +745,22 @@ impl From<twse::eps::Eps> for FinancialStatement {
         e.return_on_equity = Default::default();
         e.return_on_assets = Default::default();
         e.year = fs.year as i64;
+        e.statement_type = fs.statement_type.serial();
+        e
+    }
+}
+
+impl From<twse::cash_flow::CashFlow> for FinancialStatement {
+    fn from(cf: twse::cash_flow::CashFlow) -> Self {
+        let mut e = FinancialStatement::new(cf.stock_symbol);
+        e.updated_time = Local::now();
+        e.created_time = Local::now();
+        e.quarter = cf.quarter.to_string();
+        e.operating_cash_flow = cf.operating_cash_flow;
+        e.investing_cash_flow = cf.investing_cash_flow;
+        e.financing_cash_flow = cf.financing_cash_flow;
+        e.year = cf.year as i64;
+        e.statement_type = cf.statement_type.serial();
         e
     }
 }
@@ -1,12 +1,16 @@
 use std::str::FromStr;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use chrono::{Datelike, Local, NaiveDate};
 use concat_string::concat_string;
 use rust_decimal::Decimal;
 use sqlx::{self, FromRow};
 
-use crate::{database, logging, util, util::map::Keyable};
+use crate::{
+    database::{self, timing::Timed},
+    logging, util,
+    util::map::Keyable,
+};
 
 #[derive(sqlx::Type, FromRow, Debug)]
 pub struct Index {
@@ -71,6 +75,31 @@ LIMIT 30;
             })
     }
 
+    /// 取得指定指數自 since 到 to(含)之間每日的收盤指數，依日期由舊到新排序
+    pub async fn fetch_closing(
+        category: &str,
+        since: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<(NaiveDate, Decimal)>> {
+        let sql = r#"
+SELECT "date", index
+FROM index
+WHERE category = $1 AND "date" BETWEEN $2 AND $3
+ORDER BY "date";
+"#;
+        sqlx::query_as::<_, (NaiveDate, Decimal)>(sql)
+            .bind(category)
+            .bind(since)
+            .bind(to)
+            .fetch_all(database::get_connection())
+            .timed("index", "fetch_closing")
+            .await
+            .context(format!(
+                "Failed to Index::fetch_closing({}, {}, {}) from database",
+                category, since, to
+            ))
+    }
+
     /// 將twse取回來的原始資料轉成 Entity
     pub fn from_strings(item: &[String]) -> Result<Self> {
         let split_date: Vec<&str> = item[0].split('/').collect();
@@ -131,18 +160,20 @@ ON CONFLICT
 DO UPDATE
     SET update_time = EXCLUDED.update_time;
 "#;
-        sqlx::query(sql)
-            .bind(&self.category)
-            .bind(self.date)
-            .bind(self.trading_volume)
-            .bind(self.transaction)
-            .bind(self.trade_value)
-            .bind(self.change)
-            .bind(self.index)
-            .bind(self.create_time)
-            .bind(self.update_time)
-            .execute(database::get_connection())
-            .await?;
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(&self.category)
+                .bind(self.date)
+                .bind(self.trading_volume)
+                .bind(self.transaction)
+                .bind(self.trade_value)
+                .bind(self.change)
+                .bind(self.index)
+                .bind(self.create_time)
+                .bind(self.update_time)
+                .execute(database::get_connection())
+        })
+        .await?;
         Ok(())
     }
 }
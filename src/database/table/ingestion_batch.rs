@@ -0,0 +1,161 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::{postgres::PgQueryResult, FromRow};
+
+use crate::{
+    database::{self, timing::Timed},
+    logging::run_id,
+};
+
+/// 抓取來源與期間的一次寫入批次 原表名 ingestion_batches
+///
+/// 同一來源、期間重新執行時，抓到的內容與上一次完成的批次相同就略過寫入；內容不同時以新的 batch_id 寫入，
+/// 完成後只刪除上一個批次寫入但這次沒有再出現的數據，不影響其他來源寫入的數據
+#[derive(FromRow, Debug, Clone, Default, PartialEq)]
+pub struct IngestionBatch {
+    /// 抓取來源 ex. revenue
+    pub source: String,
+    /// 批次涵蓋的期間 ex. yyyyMM
+    pub period: String,
+    /// 批次識別碼，同時寫入該批次的每一筆數據
+    pub batch_id: String,
+    /// 抓取內容的 SHA-256
+    pub checksum: String,
+    pub row_count: i32,
+    /// 同一來源、期間上一次完成的批次識別碼，沒有時為空字串，不存入資料表
+    #[sqlx(skip)]
+    pub previous_batch_id: String,
+}
+
+impl IngestionBatch {
+    /// 開始一個批次，抓到的內容與上一次完成的批次相同時回傳 None 表示不需要重新寫入
+    pub async fn begin<T: Serialize>(
+        source: &str,
+        period: &str,
+        rows: &[T],
+    ) -> Result<Option<IngestionBatch>> {
+        let checksum = checksum(rows)?;
+        let previous = Self::fetch(source, period).await?;
+
+        Ok(Self::next(source, period, checksum, previous))
+    }
+
+    fn next(
+        source: &str,
+        period: &str,
+        checksum: String,
+        previous: Option<IngestionBatch>,
+    ) -> Option<IngestionBatch> {
+        if previous
+            .as_ref()
+            .is_some_and(|previous| previous.checksum == checksum)
+        {
+            return None;
+        }
+
+        Some(IngestionBatch {
+            source: source.to_string(),
+            period: period.to_string(),
+            batch_id: run_id::generate(),
+            checksum,
+            row_count: 0,
+            previous_batch_id: previous
+                .map(|previous| previous.batch_id)
+                .unwrap_or_default(),
+        })
+    }
+
+    /// 取得來源與期間上一次完成的批次
+    pub async fn fetch(source: &str, period: &str) -> Result<Option<IngestionBatch>> {
+        let sql = r#"
+SELECT source, period, batch_id, checksum, row_count
+FROM ingestion_batches
+WHERE source = $1 AND period = $2;
+"#;
+        sqlx::query_as::<_, IngestionBatch>(sql)
+            .bind(source)
+            .bind(period)
+            .fetch_optional(database::get_connection())
+            .timed("ingestion_batches", "fetch")
+            .await
+            .context(format!(
+                "Failed to IngestionBatch::fetch({}, {}) from database",
+                source, period
+            ))
+    }
+
+    /// 批次內的數據全部寫入成功後記錄，之後內容相同的重新執行會被略過；有寫入失敗時不要呼叫，下次執行會重新寫入
+    pub async fn complete(&mut self, row_count: usize) -> Result<PgQueryResult> {
+        self.row_count = row_count as i32;
+        let sql = r#"
+INSERT INTO ingestion_batches (source, period, batch_id, checksum, row_count)
+VALUES ($1, $2, $3, $4, $5)
+ON CONFLICT (source, period) DO UPDATE SET
+    batch_id = EXCLUDED.batch_id,
+    checksum = EXCLUDED.checksum,
+    row_count = EXCLUDED.row_count,
+    updated_time = now();
+"#;
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(&self.source)
+                .bind(&self.period)
+                .bind(&self.batch_id)
+                .bind(&self.checksum)
+                .bind(self.row_count)
+                .execute(database::get_connection())
+        })
+        .timed("ingestion_batches", "complete")
+        .await
+        .context(format!(
+            "Failed to IngestionBatch::complete({}, {}) from database",
+            self.source, self.period
+        ))
+    }
+}
+
+/// 抓取內容的 SHA-256，與數據的順序無關
+pub fn checksum<T: Serialize>(rows: &[T]) -> Result<String> {
+    let mut lines = rows
+        .iter()
+        .map(serde_json::to_string)
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to serialize rows for checksum")?;
+    lines.sort();
+
+    Ok(hex::encode(Sha256::digest(lines.join("\n").as_bytes())))
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_checksum() {
+        let a = checksum(&[("2330", 1), ("2317", 2)]).unwrap();
+
+        assert_eq!(a.len(), 64);
+        assert_eq!(a, checksum(&[("2317", 2), ("2330", 1)]).unwrap());
+        assert_ne!(a, checksum(&[("2330", 1), ("2317", 3)]).unwrap());
+    }
+
+    #[test]
+    fn test_next() {
+        let first = IngestionBatch::next("revenue", "202405", "a".to_string(), None).unwrap();
+        assert_eq!(first.previous_batch_id, "");
+        assert!(!first.batch_id.is_empty());
+
+        assert_eq!(
+            IngestionBatch::next("revenue", "202405", "a".to_string(), Some(first.clone())),
+            None
+        );
+
+        let second =
+            IngestionBatch::next("revenue", "202405", "b".to_string(), Some(first.clone()))
+                .unwrap();
+        assert_eq!(second.previous_batch_id, first.batch_id);
+        assert_ne!(second.batch_id, first.batch_id);
+    }
+}
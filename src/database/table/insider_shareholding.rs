@@ -0,0 +1,231 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use sqlx::{postgres::PgQueryResult, FromRow};
+
+use crate::{
+    database::{self, timing::Timed},
+    declare::StockSymbol,
+    util,
+};
+
+/// 董事、監察人每月持股彙總 原表名 insider_shareholding
+#[derive(FromRow, Debug, Clone, PartialEq, Default)]
+pub struct InsiderShareholding {
+    pub security_code: StockSymbol,
+    pub year: i32,
+    pub month: i32,
+    /// 董監事合計持股(股)
+    pub shares_held: i64,
+    /// 董監事合計設質股數(股)
+    pub shares_pledged: i64,
+    /// 董監事持股佔已發行股數比率(%)
+    pub holding_ratio: Decimal,
+    /// 設質股數佔董監事持股比率(%)
+    pub pledge_ratio: Decimal,
+}
+
+/// 被標記為持股大幅減少的庫存股票，列入庫存月報
+#[derive(FromRow, Debug, Clone, PartialEq, Default)]
+pub struct LargeDecrease {
+    pub security_code: StockSymbol,
+    pub name: String,
+    pub year: i32,
+    pub month: i32,
+    /// 上個月的董監事合計持股(股)
+    pub previous_shares_held: i64,
+    /// 本月的董監事合計持股(股)
+    pub shares_held: i64,
+    /// 減少的百分比
+    pub decrease_percent: Decimal,
+}
+
+impl InsiderShareholding {
+    /// security_code、year、month 為組合鍵 unique
+    pub async fn upsert(&self) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO insider_shareholding (security_code, year, month, shares_held, shares_pledged, holding_ratio, pledge_ratio)
+VALUES ($1, $2, $3, $4, $5, $6, $7)
+ON CONFLICT (security_code, year, month) DO UPDATE SET
+    shares_held = EXCLUDED.shares_held,
+    shares_pledged = EXCLUDED.shares_pledged,
+    holding_ratio = EXCLUDED.holding_ratio,
+    pledge_ratio = EXCLUDED.pledge_ratio,
+    updated_time = now();
+"#;
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(&self.security_code)
+                .bind(self.year)
+                .bind(self.month)
+                .bind(self.shares_held)
+                .bind(self.shares_pledged)
+                .bind(self.holding_ratio)
+                .bind(self.pledge_ratio)
+                .execute(database::get_connection())
+        })
+        .await
+        .context(format!(
+            "Failed to InsiderShareholding::upsert({:?}) from database",
+            self
+        ))
+    }
+
+    /// 以 UNNEST 將一批董監事持股合併成一個 INSERT，同一個月份重複時以最後一筆為準，回傳有寫入的 (股票代號, 年, 月)
+    pub async fn upsert_batch(
+        shareholdings: &[InsiderShareholding],
+    ) -> Result<Vec<(String, i32, i32)>> {
+        let sql = r#"
+INSERT INTO insider_shareholding (security_code, year, month, shares_held, shares_pledged, holding_ratio, pledge_ratio)
+SELECT * FROM UNNEST($1::varchar[], $2::integer[], $3::integer[], $4::bigint[], $5::bigint[], $6::numeric[], $7::numeric[])
+ON CONFLICT (security_code, year, month) DO UPDATE SET
+    shares_held = EXCLUDED.shares_held,
+    shares_pledged = EXCLUDED.shares_pledged,
+    holding_ratio = EXCLUDED.holding_ratio,
+    pledge_ratio = EXCLUDED.pledge_ratio,
+    updated_time = now()
+RETURNING security_code, year, month;
+"#;
+        let shareholdings = util::map::dedup_by_key(shareholdings, |s| {
+            (s.security_code.clone(), s.year, s.month)
+        });
+        database::with_retry(|| {
+            sqlx::query_as::<_, (String, i32, i32)>(sql)
+                .bind(util::map::column(&shareholdings, |s| {
+                    s.security_code.clone()
+                }))
+                .bind(util::map::column(&shareholdings, |s| s.year))
+                .bind(util::map::column(&shareholdings, |s| s.month))
+                .bind(util::map::column(&shareholdings, |s| s.shares_held))
+                .bind(util::map::column(&shareholdings, |s| s.shares_pledged))
+                .bind(util::map::column(&shareholdings, |s| s.holding_ratio))
+                .bind(util::map::column(&shareholdings, |s| s.pledge_ratio))
+                .fetch_all(database::get_connection())
+        })
+        .timed("insider_shareholding", "upsert_batch")
+        .await
+        .context(format!(
+            "Failed to InsiderShareholding::upsert_batch({} rows) from database",
+            shareholdings.len()
+        ))
+    }
+
+    /// 取得指定月份所有股票的董監事持股
+    pub async fn fetch_by_month(year: i32, month: i32) -> Result<Vec<InsiderShareholding>> {
+        let sql = r#"
+SELECT security_code, year, month, shares_held, shares_pledged, holding_ratio, pledge_ratio
+FROM insider_shareholding
+WHERE year = $1 AND month = $2;
+"#;
+        sqlx::query_as::<_, InsiderShareholding>(sql)
+            .bind(year)
+            .bind(month)
+            .fetch_all(database::get_connection())
+            .await
+            .context(format!(
+                "Failed to InsiderShareholding::fetch_by_month({}, {}) from database",
+                year, month
+            ))
+    }
+
+    /// 標記指定月份的董監事持股較上個月大幅減少
+    pub async fn flag_large_decrease(
+        &self,
+        previous_shares_held: i64,
+        decrease_percent: Decimal,
+    ) -> Result<PgQueryResult> {
+        let sql = r#"
+UPDATE insider_shareholding
+SET large_decrease = true,
+    previous_shares_held = $4,
+    decrease_percent = $5,
+    updated_time = now()
+WHERE security_code = $1 AND year = $2 AND month = $3;
+"#;
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(&self.security_code)
+                .bind(self.year)
+                .bind(self.month)
+                .bind(previous_shares_held)
+                .bind(decrease_percent)
+                .execute(database::get_connection())
+        })
+        .await
+        .context(format!(
+            "Failed to InsiderShareholding::flag_large_decrease({:?}) from database",
+            self
+        ))
+    }
+
+    /// 取得區間內寫入且被標記為持股大幅減少的月份，依減少的百分比由大到小排序
+    pub async fn fetch_large_decreases(
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<LargeDecrease>> {
+        let sql = r#"
+SELECT
+    ish.security_code,
+    COALESCE(s."Name", '') AS name,
+    ish.year,
+    ish.month,
+    ish.previous_shares_held,
+    ish.shares_held,
+    ish.decrease_percent
+FROM insider_shareholding AS ish
+    LEFT JOIN stocks AS s ON s.stock_symbol = ish.security_code
+WHERE ish.large_decrease = true
+    AND ish.created_time::date BETWEEN $1 AND $2
+ORDER BY ish.decrease_percent DESC, ish.security_code;
+"#;
+        sqlx::query_as::<_, LargeDecrease>(sql)
+            .bind(start)
+            .bind(end)
+            .fetch_all(database::get_connection())
+            .await
+            .context(format!(
+                "Failed to InsiderShareholding::fetch_large_decreases({}, {}) from database",
+                start, end
+            ))
+    }
+
+    /// 指定月份是否已有數據
+    pub async fn exists(year: i32, month: i32) -> Result<bool> {
+        let sql =
+            "SELECT EXISTS(SELECT 1 FROM insider_shareholding WHERE year = $1 AND month = $2)";
+        sqlx::query_scalar::<_, bool>(sql)
+            .bind(year)
+            .bind(month)
+            .fetch_one(database::get_connection())
+            .await
+            .context(format!(
+                "Failed to InsiderShareholding::exists({}, {}) from database",
+                year, month
+            ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logging;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_fetch_by_month() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 InsiderShareholding::fetch_by_month".to_string());
+
+        match InsiderShareholding::fetch_by_month(2024, 1).await {
+            Ok(list) => logging::debug_file_async(format!("data({}):{:#?}", list.len(), list)),
+            Err(why) => logging::debug_file_async(format!(
+                "Failed to InsiderShareholding::fetch_by_month because {:?}",
+                why
+            )),
+        }
+
+        logging::debug_file_async("結束 InsiderShareholding::fetch_by_month".to_string());
+    }
+}
@@ -0,0 +1,67 @@
+use std::collections::HashSet;
+
+use anyhow::{Context, Result};
+use sqlx::postgres::PgQueryResult;
+
+use crate::database;
+
+/// 停用的排程任務 原表名 job_controls
+pub struct JobControl;
+
+impl JobControl {
+    /// 取得停用中的任務名稱
+    pub async fn fetch_disabled() -> Result<HashSet<String>> {
+        let sql = "SELECT job_name FROM job_controls WHERE disabled";
+        let names: Vec<String> = sqlx::query_scalar(sql)
+            .fetch_all(database::get_connection())
+            .await
+            .context("Failed to JobControl::fetch_disabled() from database")?;
+
+        Ok(names.into_iter().collect())
+    }
+
+    /// 記錄任務是否停用
+    pub async fn save(job_name: &str, disabled: bool) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO job_controls (job_name, disabled)
+VALUES ($1, $2)
+ON CONFLICT (job_name) DO UPDATE SET
+    disabled = EXCLUDED.disabled,
+    updated_time = now();
+"#;
+        sqlx::query(sql)
+            .bind(job_name)
+            .bind(disabled)
+            .execute(database::get_connection())
+            .await
+            .context(format!(
+                "Failed to JobControl::save({}, {}) from database",
+                job_name, disabled
+            ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logging;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_fetch_disabled() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 JobControl::fetch_disabled".to_string());
+
+        match JobControl::fetch_disabled().await {
+            Ok(names) => logging::debug_file_async(format!("data:{:#?}", names)),
+            Err(why) => logging::debug_file_async(format!(
+                "Failed to JobControl::fetch_disabled because {:?}",
+                why
+            )),
+        }
+
+        logging::debug_file_async("結束 JobControl::fetch_disabled".to_string());
+    }
+}
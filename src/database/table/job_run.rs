@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use sqlx::{postgres::PgQueryResult, FromRow};
+
+use crate::database;
+
+/// 排程任務最後一次成功執行的時間 原表名 job_runs
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct JobRun {
+    pub job_name: String,
+    pub last_success_time: DateTime<Local>,
+}
+
+impl JobRun {
+    /// 記錄任務於現在成功執行
+    pub async fn record_success(job_name: &str) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO job_runs (job_name, last_success_time)
+VALUES ($1, now())
+ON CONFLICT (job_name) DO UPDATE SET
+    last_success_time = EXCLUDED.last_success_time,
+    updated_time = now();
+"#;
+        sqlx::query(sql)
+            .bind(job_name)
+            .execute(database::get_connection())
+            .await
+            .context(format!(
+                "Failed to JobRun::record_success({}) from database",
+                job_name
+            ))
+    }
+
+    /// 取得所有任務最後一次成功執行的時間，key 為任務名稱
+    pub async fn fetch_all() -> Result<HashMap<String, DateTime<Local>>> {
+        let sql = "SELECT job_name, last_success_time FROM job_runs";
+        let runs = sqlx::query_as::<_, JobRun>(sql)
+            .fetch_all(database::get_connection())
+            .await
+            .context("Failed to JobRun::fetch_all() from database")?;
+
+        Ok(runs
+            .into_iter()
+            .map(|run| (run.job_name, run.last_success_time))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logging;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_fetch_all() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 JobRun::fetch_all".to_string());
+
+        match JobRun::fetch_all().await {
+            Ok(runs) => logging::debug_file_async(format!("data:{:#?}", runs)),
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to JobRun::fetch_all because {:?}", why))
+            }
+        }
+
+        logging::debug_file_async("結束 JobRun::fetch_all".to_string());
+    }
+}
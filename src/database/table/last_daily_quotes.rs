@@ -3,13 +3,55 @@ use chrono::{Local, NaiveDate, TimeDelta};
 use rust_decimal::Decimal;
 use sqlx::postgres::PgQueryResult;
 
-use crate::database;
+use crate::{
+    database::{self, table::config, timing::Timed},
+    declare::StockSymbol,
+};
+
+/// 由 "DailyQuotes" 寫入 last_daily_quotes 的欄位，順序與 last_daily_quotes 的欄位相同
+const SELECT_COLUMNS: &str = r#"
+	"Date",
+	"SecurityCode",
+	"TradingVolume",
+	"Transaction",
+	"TradeValue",
+	"OpeningPrice",
+	"HighestPrice",
+	"LowestPrice",
+	"ClosingPrice",
+	"ChangeRange",
+	"Change",
+	"LastBestBidPrice",
+	"LastBestBidVolume",
+	"LastBestAskPrice",
+	"LastBestAskVolume",
+	"PriceEarningRatio",
+	"MovingAverage5",
+	"MovingAverage10",
+	"MovingAverage20",
+	"MovingAverage60",
+	"MovingAverage120",
+	"MovingAverage240",
+	maximum_price_in_year,
+	minimum_price_in_year,
+	average_price_in_year,
+	maximum_price_in_year_date_on,
+	minimum_price_in_year_date_on,
+	"price-to-book_ratio",
+	"RecordTime",
+	current_timestamp
+"#;
+
+/// 距離上次全表重建超過幾天時改為全表重建，其餘交易日只更新當日有成交的股票
+const FULL_REBUILD_DAYS: i64 = 7;
+/// 記錄上次全表重建日期的 config key
+const FULL_REBUILD_KEY: &str = "last-daily-quotes-rebuild";
 
 #[derive(sqlx::FromRow, Debug)]
 /// 最後交易日股票報價數據
 pub struct LastDailyQuotes {
     pub date: NaiveDate,
-    pub security_code: String,
+    pub security_code: StockSymbol,
     /// 收盤價
     pub closing_price: Decimal,
 }
@@ -34,9 +76,84 @@ FROM
 "#,
         )
         .fetch_all(database::get_connection())
+        .timed("last_daily_quotes", "fetch")
         .await?)
     }
 
+    /// 只更新指定日期有成交的股票，每週一次改為全表重建，回傳是否進行全表重建
+    pub async fn refresh(date: NaiveDate) -> Result<bool> {
+        let last_rebuild = config::Config::first(FULL_REBUILD_KEY)
+            .await
+            .ok()
+            .and_then(|c| NaiveDate::parse_from_str(&c.val, "%Y-%m-%d").ok());
+        if !needs_full_rebuild(last_rebuild, date) {
+            Self::upsert_by_date(date).await?;
+            return Ok(false);
+        }
+
+        Self::rebuild().await?;
+        config::Config::new(FULL_REBUILD_KEY.to_string(), date.to_string())
+            .upsert()
+            .await?;
+
+        Ok(true)
+    }
+
+    /// 將指定日期的收盤數據寫入 last_daily_quotes，已存在較新日期的股票不會被覆蓋
+    pub async fn upsert_by_date(date: NaiveDate) -> Result<PgQueryResult> {
+        let sql = format!(
+            r#"
+INSERT INTO last_daily_quotes
+SELECT{}FROM "DailyQuotes"
+WHERE "Date" = $1
+ON CONFLICT (security_code) DO UPDATE SET
+    date = excluded.date,
+    trading_volume = excluded.trading_volume,
+    transaction = excluded.transaction,
+    trade_value = excluded.trade_value,
+    opening_price = excluded.opening_price,
+    highest_price = excluded.highest_price,
+    lowest_price = excluded.lowest_price,
+    closing_price = excluded.closing_price,
+    change_range = excluded.change_range,
+    change = excluded.change,
+    last_best_bid_price = excluded.last_best_bid_price,
+    last_best_bid_volume = excluded.last_best_bid_volume,
+    last_best_ask_price = excluded.last_best_ask_price,
+    last_best_ask_volume = excluded.last_best_ask_volume,
+    price_earning_ratio = excluded.price_earning_ratio,
+    moving_average_5 = excluded.moving_average_5,
+    moving_average_10 = excluded.moving_average_10,
+    moving_average_20 = excluded.moving_average_20,
+    moving_average_60 = excluded.moving_average_60,
+    moving_average_120 = excluded.moving_average_120,
+    moving_average_240 = excluded.moving_average_240,
+    maximum_price_in_year = excluded.maximum_price_in_year,
+    minimum_price_in_year = excluded.minimum_price_in_year,
+    average_price_in_year = excluded.average_price_in_year,
+    maximum_price_in_year_date_on = excluded.maximum_price_in_year_date_on,
+    minimum_price_in_year_date_on = excluded.minimum_price_in_year_date_on,
+    "price-to-book_ratio" = excluded."price-to-book_ratio",
+    record_time = excluded.record_time,
+    updated_time = excluded.updated_time
+WHERE last_daily_quotes.date <= excluded.date
+"#,
+            SELECT_COLUMNS
+        );
+
+        database::with_retry(|| {
+            sqlx::query(&sql)
+                .bind(date)
+                .execute(database::get_connection())
+        })
+        .timed("last_daily_quotes", "upsert_by_date")
+        .await
+        .context(format!(
+            "Failed to LastDailyQuotes::upsert_by_date({}) from database",
+            date
+        ))
+    }
+
     pub async fn rebuild() -> Result<PgQueryResult> {
         let mut tx = database::get_tx()
             .await
@@ -44,6 +161,7 @@ FROM
 
         if let Err(why) = sqlx::query("TRUNCATE last_daily_quotes;")
             .execute(&mut *tx)
+            .timed("last_daily_quotes", "rebuild")
             .await
             .context("Failed to TRUNCATE last_daily_quotes;")
         {
@@ -51,40 +169,10 @@ FROM
             return Err(anyhow!("{:?}", why));
         }
 
-        let sql = r#"
+        let sql = format!(
+            r#"
 INSERT INTO last_daily_quotes
-SELECT
-	"Date",
-	"SecurityCode",
-	"TradingVolume",
-	"Transaction",
-	"TradeValue",
-	"OpeningPrice",
-	"HighestPrice",
-	"LowestPrice",
-	"ClosingPrice",
-	"ChangeRange",
-	"Change",
-	"LastBestBidPrice",
-	"LastBestBidVolume",
-	"LastBestAskPrice",
-	"LastBestAskVolume",
-	"PriceEarningRatio",
-	"MovingAverage5",
-	"MovingAverage10",
-	"MovingAverage20",
-	"MovingAverage60",
-	"MovingAverage120",
-	"MovingAverage240",
-	maximum_price_in_year,
-	minimum_price_in_year,
-	average_price_in_year,
-	maximum_price_in_year_date_on,
-	minimum_price_in_year_date_on,
-	"price-to-book_ratio",
-	"RecordTime",
-	current_timestamp
-FROM "DailyQuotes"
+SELECT{}FROM "DailyQuotes"
 WHERE "Serial" IN
 (
 	select max("Serial")
@@ -93,11 +181,14 @@ WHERE "Serial" IN
 	group by "SecurityCode"
 )
 ORDER BY "SecurityCode"
-"#;
+"#,
+            SELECT_COLUMNS
+        );
         let month_ago = Local::now() - TimeDelta::try_days(30).unwrap();
-        match sqlx::query(sql)
+        match sqlx::query(&sql)
             .bind(month_ago)
             .execute(&mut *tx)
+            .timed("last_daily_quotes", "rebuild")
             .await
             .context("Failed to LastDailyQuotes::rebuild from database")
         {
@@ -113,6 +204,14 @@ ORDER BY "SecurityCode"
     }
 }
 
+/// 從未重建或距離上次重建已達 FULL_REBUILD_DAYS 天時需要全表重建，以清除已下市或長期未成交的股票
+fn needs_full_rebuild(last_rebuild: Option<NaiveDate>, date: NaiveDate) -> bool {
+    match last_rebuild {
+        Some(last) => (date - last).num_days() >= FULL_REBUILD_DAYS || date < last,
+        None => true,
+    }
+}
+
 impl Clone for LastDailyQuotes {
     fn clone(&self) -> Self {
         LastDailyQuotes {
@@ -135,6 +234,16 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_needs_full_rebuild() {
+        let date = |d: u32| NaiveDate::from_ymd_opt(2024, 6, d).unwrap();
+
+        assert!(needs_full_rebuild(None, date(12)));
+        assert!(!needs_full_rebuild(Some(date(7)), date(12)));
+        assert!(needs_full_rebuild(Some(date(5)), date(12)));
+        assert!(needs_full_rebuild(Some(date(20)), date(12)));
+    }
+
     #[tokio::test]
     #[ignore]
     async fn test_calculate() {
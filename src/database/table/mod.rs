@@ -35,4 +35,74 @@ pub mod trace;
 /// 殖利率排行
 pub mod yield_rank;
 /// 每日股票價格估值統計
-pub mod daily_stock_price_stats;
\ No newline at end of file
+pub mod daily_stock_price_stats;
+/// 交易所公布的個股日本益比、殖利率及股價淨值比
+pub mod daily_valuation;
+/// 上市公司重大訊息
+pub mod announcement;
+/// 董事、監察人每月持股與設質比率
+pub mod insider_shareholding;
+/// 上市公司庫藏股買回計畫
+pub mod buyback;
+/// 每日數據品質檢查結果
+pub mod quality_report;
+/// 排程任務最後一次成功執行的時間
+pub mod job_run;
+/// 停用的排程任務
+pub mod job_control;
+/// 歷史數據回補的進度
+pub mod backfill_checkpoint;
+/// 依除權息還原的收盤價
+pub mod adjusted_quote;
+/// 個股的 52 週高低點與回檔幅度
+pub mod week52_stat;
+/// 估價模型的實際報酬
+pub mod estimate_performance;
+/// 回補模組寫入資料的記錄
+pub mod audit_log;
+/// 股利數據與證交所除權除息結果比對不一致的記錄
+pub mod dividend_discrepancy;
+/// 收盤價與雅虎報價不一致的記錄
+pub mod price_discrepancy;
+/// 各產業每日的平均漲跌幅
+pub mod sector_daily_performance;
+/// 庫存股票與整體庫存的風險指標
+pub mod risk_metric;
+/// 整體庫存每日的時間加權報酬
+pub mod portfolio_return;
+/// 成員投入與提出的資金
+pub mod cash_ledger;
+/// 模擬交易的委託
+pub mod paper_order;
+/// 策略規則觸發的訊號
+pub mod signal;
+/// 盤中最佳五檔委買委賣的快照
+pub mod order_book_snapshot;
+/// 盤後零股交易的成交行情
+pub mod odd_lot_quote;
+/// 外資及陸資每日持股的歷史紀錄
+pub mod qfii_holding;
+/// 公司的股東會與法人說明會等事件
+pub mod corporate_event;
+/// 月營收公布前估算的庫存股票月營收
+pub mod revenue_estimate;
+/// 個股殖利率在自己近 5 年殖利率分佈中的百分位
+pub mod yield_percentile;
+/// 重試後仍寫入失敗、等待排程重新寫入的數據
+pub mod failed_write;
+/// 抓取來源各期間最後一次完整寫入的批次
+pub mod ingestion_batch;
+/// 公網 IP 的變動記錄
+pub mod public_ip;
+/// 聊天室以 /settings 指令設定的偏好
+pub mod bot_user_setting;
+/// Telegram 使用者可以使用的指令角色
+pub mod bot_role;
+/// 個股每股淨值的歷史記錄
+pub mod net_asset_value_history;
+/// 聊天室以 /subscribe 訂閱的個股通知
+pub mod bot_subscription;
+/// 賣出時與買進批次配對的明細與已實現損益
+pub mod stock_lot_sale;
+/// 庫存批次或賣出明細的交易筆記與標籤
+pub mod trade_note;
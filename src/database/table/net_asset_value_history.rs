@@ -0,0 +1,140 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use sqlx::{postgres::PgQueryResult, FromRow};
+
+use crate::{
+    database::{self, timing::Timed},
+    declare::StockSymbol,
+};
+
+/// 個股每股淨值的歷史記錄 原表名 net_asset_value_histories
+#[derive(FromRow, Debug, Clone, Default, PartialEq)]
+pub struct NetAssetValueHistory {
+    pub security_code: StockSymbol,
+    /// 每股淨值開始生效的日期
+    pub date: NaiveDate,
+    /// 每股淨值
+    pub net_asset_value_per_share: Decimal,
+}
+
+impl NetAssetValueHistory {
+    pub fn new(
+        security_code: StockSymbol,
+        date: NaiveDate,
+        net_asset_value_per_share: Decimal,
+    ) -> Self {
+        NetAssetValueHistory {
+            security_code,
+            date,
+            net_asset_value_per_share,
+        }
+    }
+
+    /// 新增或更新一筆每股淨值，同一天以最後一次的數值為準
+    pub async fn upsert(&self) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO net_asset_value_histories (security_code, date, net_asset_value_per_share)
+VALUES ($1, $2, $3)
+ON CONFLICT (security_code, date) DO UPDATE SET
+    net_asset_value_per_share = EXCLUDED.net_asset_value_per_share,
+    updated_time = now();
+"#;
+        sqlx::query(sql)
+            .bind(&self.security_code)
+            .bind(self.date)
+            .bind(self.net_asset_value_per_share)
+            .execute(database::get_connection())
+            .timed("net_asset_value_histories", "upsert")
+            .await
+            .context(format!(
+                "Failed to NetAssetValueHistory::upsert({:#?}) from database",
+                self
+            ))
+    }
+
+    /// 將 financial_statement 各季的每股淨值以季底日期寫入，已存在的日期不覆蓋
+    pub async fn upsert_from_financial_statement() -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO net_asset_value_histories (security_code, date, net_asset_value_per_share)
+SELECT
+    security_code,
+    (make_date(year, CAST(substr(quarter, 2, 1) AS INT) * 3, 1) + interval '1 month - 1 day')::date,
+    net_asset_value_per_share
+FROM financial_statement
+WHERE quarter IN ('Q1', 'Q2', 'Q3', 'Q4')
+  AND net_asset_value_per_share > 0
+ON CONFLICT (security_code, date) DO NOTHING;
+"#;
+        database::with_retry(|| sqlx::query(sql).execute(database::get_connection()))
+            .timed(
+                "net_asset_value_histories",
+                "upsert_from_financial_statement",
+            )
+            .await
+            .context(
+                "Failed to NetAssetValueHistory::upsert_from_financial_statement() from database",
+            )
+    }
+
+    /// 取得指定股票最近 limit 筆的每股淨值，依日期由新到舊排序
+    pub async fn fetch(security_code: &str, limit: i64) -> Result<Vec<NetAssetValueHistory>> {
+        let sql = r#"
+SELECT security_code, date, net_asset_value_per_share
+FROM net_asset_value_histories
+WHERE security_code = $1
+ORDER BY date DESC
+LIMIT $2;
+"#;
+        sqlx::query_as::<_, NetAssetValueHistory>(sql)
+            .bind(security_code)
+            .bind(limit)
+            .fetch_all(database::get_connection())
+            .timed("net_asset_value_histories", "fetch")
+            .await
+            .context(format!(
+                "Failed to NetAssetValueHistory::fetch({}) from database",
+                security_code
+            ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logging;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_upsert_from_financial_statement() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async(
+            "開始 NetAssetValueHistory::upsert_from_financial_statement".to_string(),
+        );
+
+        match NetAssetValueHistory::upsert_from_financial_statement().await {
+            Ok(result) => logging::debug_file_async(format!(
+                "NetAssetValueHistory::upsert_from_financial_statement rows:{}",
+                result.rows_affected()
+            )),
+            Err(why) => logging::debug_file_async(format!(
+                "Failed to NetAssetValueHistory::upsert_from_financial_statement because {:?}",
+                why
+            )),
+        }
+
+        match NetAssetValueHistory::fetch("2330", 8).await {
+            Ok(history) => logging::debug_file_async(format!("{:#?}", history)),
+            Err(why) => logging::debug_file_async(format!(
+                "Failed to NetAssetValueHistory::fetch because {:?}",
+                why
+            )),
+        }
+
+        logging::debug_file_async(
+            "結束 NetAssetValueHistory::upsert_from_financial_statement".to_string(),
+        );
+    }
+}
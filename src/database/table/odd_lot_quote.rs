@@ -0,0 +1,242 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgQueryResult, FromRow};
+
+use crate::{
+    database::{self, timing::Timed},
+    declare::{StockExchange, StockSymbol},
+    util,
+};
+
+/// 盤後零股交易的成交行情 原表名 odd_lot_quotes
+#[derive(FromRow, Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct OddLotQuote {
+    pub date: NaiveDate,
+    pub security_code: StockSymbol,
+    /// 交易所 1:twse 2:tpex
+    pub stock_exchange_id: i32,
+    /// 成交股數
+    pub trading_volume: i64,
+    /// 成交筆數
+    pub transaction: i64,
+    /// 成交金額
+    pub trade_value: Decimal,
+    /// 成交價，沒有成交時為 0
+    pub closing_price: Decimal,
+    /// 最後揭示買價
+    pub last_bid_price: Decimal,
+    /// 最後揭示賣價
+    pub last_ask_price: Decimal,
+}
+
+impl OddLotQuote {
+    /// 依欄位名稱將交易所回傳的表格轉成 OddLotQuote，twse 與 tpex 的欄位順序不同所以不依賴位置
+    pub fn from_table(
+        date: NaiveDate,
+        exchange: StockExchange,
+        fields: &[String],
+        data: &[Vec<String>],
+    ) -> Vec<OddLotQuote> {
+        let column = |keyword: &str| fields.iter().position(|f| f.contains(keyword));
+        let (Some(code), Some(volume), Some(value), Some(price)) = (
+            column("代號"),
+            column("成交股數"),
+            column("成交金額"),
+            column("成交價"),
+        ) else {
+            return Vec::new();
+        };
+        let (transaction, bid, ask) = (column("筆數"), column("買價"), column("賣價"));
+
+        let parse = |row: &Vec<String>, index: Option<usize>| {
+            index
+                .and_then(|index| row.get(index))
+                .and_then(|v| util::text::parse_decimal(v, Some(vec![','])).ok())
+                .unwrap_or_default()
+        };
+        let parse_i64 = |row: &Vec<String>, index: Option<usize>| {
+            index
+                .and_then(|index| row.get(index))
+                .and_then(|v| util::text::parse_i64(v, Some(vec![','])).ok())
+                .unwrap_or_default()
+        };
+
+        data.iter()
+            .filter_map(|row| {
+                let security_code = StockSymbol::parse(row.get(code)?).ok()?;
+
+                Some(OddLotQuote {
+                    date,
+                    security_code,
+                    stock_exchange_id: exchange.serial_number(),
+                    trading_volume: parse_i64(row, Some(volume)),
+                    transaction: parse_i64(row, transaction),
+                    trade_value: parse(row, Some(value)),
+                    closing_price: parse(row, Some(price)),
+                    last_bid_price: parse(row, bid),
+                    last_ask_price: parse(row, ask),
+                })
+            })
+            .collect()
+    }
+
+    /// date 與 security_code 為組合鍵 unique
+    pub async fn upsert(&self) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO odd_lot_quotes (
+    date, security_code, stock_exchange_id, trading_volume, transaction, trade_value,
+    closing_price, last_bid_price, last_ask_price
+)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+ON CONFLICT (date, security_code) DO UPDATE SET
+    stock_exchange_id = EXCLUDED.stock_exchange_id,
+    trading_volume = EXCLUDED.trading_volume,
+    transaction = EXCLUDED.transaction,
+    trade_value = EXCLUDED.trade_value,
+    closing_price = EXCLUDED.closing_price,
+    last_bid_price = EXCLUDED.last_bid_price,
+    last_ask_price = EXCLUDED.last_ask_price,
+    updated_time = now();
+"#;
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(self.date)
+                .bind(&self.security_code)
+                .bind(self.stock_exchange_id)
+                .bind(self.trading_volume)
+                .bind(self.transaction)
+                .bind(self.trade_value)
+                .bind(self.closing_price)
+                .bind(self.last_bid_price)
+                .bind(self.last_ask_price)
+                .execute(database::get_connection())
+        })
+        .timed("odd_lot_quotes", "upsert")
+        .await
+        .context(format!(
+            "Failed to OddLotQuote::upsert({}, {}) from database",
+            self.date, self.security_code
+        ))
+    }
+
+    /// 以 UNNEST 將一批成交行情合併成一個 INSERT，同一天同一檔重複時以最後一筆為準，回傳有寫入的 (日期, 股票代號)
+    pub async fn upsert_batch(quotes: &[OddLotQuote]) -> Result<Vec<(NaiveDate, String)>> {
+        let sql = r#"
+INSERT INTO odd_lot_quotes (
+    date, security_code, stock_exchange_id, trading_volume, transaction, trade_value,
+    closing_price, last_bid_price, last_ask_price
+)
+SELECT * FROM UNNEST(
+    $1::date[], $2::varchar[], $3::integer[], $4::bigint[], $5::bigint[], $6::numeric[],
+    $7::numeric[], $8::numeric[], $9::numeric[]
+)
+ON CONFLICT (date, security_code) DO UPDATE SET
+    stock_exchange_id = EXCLUDED.stock_exchange_id,
+    trading_volume = EXCLUDED.trading_volume,
+    transaction = EXCLUDED.transaction,
+    trade_value = EXCLUDED.trade_value,
+    closing_price = EXCLUDED.closing_price,
+    last_bid_price = EXCLUDED.last_bid_price,
+    last_ask_price = EXCLUDED.last_ask_price,
+    updated_time = now()
+RETURNING date, security_code;
+"#;
+        let quotes = util::map::dedup_by_key(quotes, |q| (q.date, q.security_code.clone()));
+        database::with_retry(|| {
+            sqlx::query_as::<_, (NaiveDate, String)>(sql)
+                .bind(util::map::column(&quotes, |q| q.date))
+                .bind(util::map::column(&quotes, |q| q.security_code.clone()))
+                .bind(util::map::column(&quotes, |q| q.stock_exchange_id))
+                .bind(util::map::column(&quotes, |q| q.trading_volume))
+                .bind(util::map::column(&quotes, |q| q.transaction))
+                .bind(util::map::column(&quotes, |q| q.trade_value))
+                .bind(util::map::column(&quotes, |q| q.closing_price))
+                .bind(util::map::column(&quotes, |q| q.last_bid_price))
+                .bind(util::map::column(&quotes, |q| q.last_ask_price))
+                .fetch_all(database::get_connection())
+        })
+        .timed("odd_lot_quotes", "upsert_batch")
+        .await
+        .context(format!(
+            "Failed to OddLotQuote::upsert_batch({} rows) from database",
+            quotes.len()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_from_table() {
+        let date = NaiveDate::from_ymd_opt(2024, 5, 2).unwrap();
+        let fields = strings(&[
+            "證券代號",
+            "證券名稱",
+            "成交股數",
+            "成交筆數",
+            "成交金額",
+            "成交價格",
+            "最後揭示買價",
+            "最後揭示買量",
+            "最後揭示賣價",
+            "最後揭示賣量",
+        ]);
+        let data = vec![
+            strings(&[
+                "2330",
+                "台積電",
+                "1,234,567",
+                "8,901",
+                "985,432,100",
+                "798.00",
+                "797.00",
+                "1,200",
+                "798.00",
+                "3,400",
+            ]),
+            strings(&["", "", "", "", "", "", "", "", "", ""]),
+            strings(&[
+                "9999",
+                "無成交",
+                "0",
+                "0",
+                "0",
+                "--",
+                "10.00",
+                "5",
+                "--",
+                "0",
+            ]),
+        ];
+
+        let quotes = OddLotQuote::from_table(date, StockExchange::TWSE, &fields, &data);
+
+        assert_eq!(quotes.len(), 2);
+        assert_eq!(quotes[0].security_code, "2330");
+        assert_eq!(quotes[0].stock_exchange_id, 1);
+        assert_eq!(quotes[0].trading_volume, 1234567);
+        assert_eq!(quotes[0].transaction, 8901);
+        assert_eq!(quotes[0].trade_value, dec!(985432100));
+        assert_eq!(quotes[0].closing_price, dec!(798));
+        assert_eq!(quotes[0].last_bid_price, dec!(797));
+        assert_eq!(quotes[0].last_ask_price, dec!(798));
+        assert_eq!(quotes[1].closing_price, dec!(0));
+        assert_eq!(quotes[1].last_ask_price, dec!(0));
+
+        assert!(
+            OddLotQuote::from_table(date, StockExchange::TWSE, &strings(&["代號"]), &data)
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use rust_decimal::Decimal;
+use sqlx::FromRow;
+
+use crate::{
+    database::{self, timing::Timed},
+    declare::StockSymbol,
+};
+
+/// 盤中最佳五檔委買委賣的快照 原表名 order_book_snapshots
+#[derive(FromRow, Debug, Clone, Default, PartialEq)]
+pub struct OrderBookSnapshot {
+    pub security_code: StockSymbol,
+    /// 交易所揭示資料的時間
+    pub quote_time: DateTime<Local>,
+    /// 最近成交價，尚未成交或集合競價期間為 0
+    pub last_price: Decimal,
+    /// 累積成交張數
+    pub volume: i64,
+    /// 委買價由高到低
+    pub bid_prices: Vec<Decimal>,
+    /// 委買張數，與 bid_prices 對應
+    pub bid_volumes: Vec<i64>,
+    /// 委賣價由低到高
+    pub ask_prices: Vec<Decimal>,
+    /// 委賣張數，與 ask_prices 對應
+    pub ask_volumes: Vec<i64>,
+}
+
+impl OrderBookSnapshot {
+    /// 寫入快照，相同揭示時間的快照已存在時略過，回傳是否有寫入
+    pub async fn insert(&self) -> Result<bool> {
+        let sql = r#"
+INSERT INTO order_book_snapshots (
+    security_code, quote_time, last_price, volume, bid_prices, bid_volumes, ask_prices, ask_volumes
+)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+ON CONFLICT (security_code, quote_time) DO NOTHING;
+"#;
+        sqlx::query(sql)
+            .bind(&self.security_code)
+            .bind(self.quote_time)
+            .bind(self.last_price)
+            .bind(self.volume)
+            .bind(&self.bid_prices)
+            .bind(&self.bid_volumes)
+            .bind(&self.ask_prices)
+            .bind(&self.ask_volumes)
+            .execute(database::get_connection())
+            .timed("order_book_snapshots", "insert")
+            .await
+            .map(|result| result.rows_affected() > 0)
+            .context(format!(
+                "Failed to OrderBookSnapshot::insert({}, {}) from database",
+                self.security_code, self.quote_time
+            ))
+    }
+}
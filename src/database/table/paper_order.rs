@@ -0,0 +1,156 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use sqlx::FromRow;
+
+use crate::{
+    database::{self, timing::Timed},
+    declare::StockSymbol,
+};
+
+/// 買進
+pub const BUY: &str = "buy";
+/// 賣出
+pub const SELL: &str = "sell";
+/// 以開盤價成交
+pub const OPEN: &str = "open";
+/// 以收盤價成交
+pub const CLOSE: &str = "close";
+/// 等待成交
+pub const PENDING: &str = "pending";
+/// 已成交
+pub const FILLED: &str = "filled";
+
+/// 模擬交易的委託 原表名 paper_orders
+#[derive(FromRow, Debug, Clone, Default, PartialEq)]
+pub struct PaperOrder {
+    pub serial: i64,
+    /// 下單的 Telegram 使用者編號
+    pub account_id: i64,
+    pub security_code: StockSymbol,
+    /// buy 或 sell
+    pub side: String,
+    /// 股數
+    pub quantity: i64,
+    /// open 或 close
+    pub price_type: String,
+    /// pending、filled 或 cancelled
+    pub status: String,
+    pub order_date: NaiveDate,
+    pub fill_date: Option<NaiveDate>,
+    pub fill_price: Option<Decimal>,
+}
+
+impl PaperOrder {
+    pub fn new(
+        account_id: i64,
+        security_code: StockSymbol,
+        side: &str,
+        quantity: i64,
+        price_type: &str,
+        order_date: NaiveDate,
+    ) -> Self {
+        PaperOrder {
+            account_id,
+            security_code,
+            side: side.to_string(),
+            quantity,
+            price_type: price_type.to_string(),
+            status: PENDING.to_string(),
+            order_date,
+            ..Default::default()
+        }
+    }
+
+    /// 新增委託，回傳委託編號
+    pub async fn insert(&self) -> Result<i64> {
+        let sql = r#"
+INSERT INTO paper_orders (account_id, security_code, side, quantity, price_type, status, order_date)
+VALUES ($1, $2, $3, $4, $5, $6, $7)
+RETURNING serial;
+"#;
+        sqlx::query_scalar::<_, i64>(sql)
+            .bind(self.account_id)
+            .bind(&self.security_code)
+            .bind(&self.side)
+            .bind(self.quantity)
+            .bind(&self.price_type)
+            .bind(&self.status)
+            .bind(self.order_date)
+            .fetch_one(database::get_connection())
+            .timed("paper_orders", "insert")
+            .await
+            .context(format!(
+                "Failed to PaperOrder::insert({:?}) from database",
+                self
+            ))
+    }
+
+    /// 以指定日期的開盤價或收盤價撮合該日以前下單且仍在等待的委託，回傳成交的筆數
+    ///
+    /// 當日沒有成交資料(停牌)的股票維持等待，直到有報價的交易日
+    pub async fn fill(date: NaiveDate) -> Result<u64> {
+        let sql = r#"
+UPDATE paper_orders AS po
+SET status = 'filled',
+    fill_date = dq."Date",
+    fill_price = CASE po.price_type WHEN 'close' THEN dq."ClosingPrice" ELSE dq."OpeningPrice" END,
+    updated_time = now()
+FROM "DailyQuotes" AS dq
+WHERE po.status = 'pending'
+  AND po.order_date < $1
+  AND dq."Date" = $1
+  AND dq."SecurityCode" = po.security_code
+  AND dq."OpeningPrice" > 0;
+"#;
+        sqlx::query(sql)
+            .bind(date)
+            .execute(database::get_connection())
+            .timed("paper_orders", "fill")
+            .await
+            .map(|result| result.rows_affected())
+            .context(format!(
+                "Failed to PaperOrder::fill({}) from database",
+                date
+            ))
+    }
+
+    /// 取得帳戶的委託，依委託編號排序
+    pub async fn fetch_by_account(account_id: i64) -> Result<Vec<PaperOrder>> {
+        let sql = r#"
+SELECT serial, account_id, security_code, side, quantity, price_type, status, order_date, fill_date, fill_price
+FROM paper_orders
+WHERE account_id = $1
+ORDER BY serial;
+"#;
+        sqlx::query_as::<_, PaperOrder>(sql)
+            .bind(account_id)
+            .fetch_all(database::get_connection())
+            .timed("paper_orders", "fetch_by_account")
+            .await
+            .context(format!(
+                "Failed to PaperOrder::fetch_by_account({}) from database",
+                account_id
+            ))
+    }
+
+    /// 取消帳戶內仍在等待的委託，回傳是否有取消
+    pub async fn cancel(account_id: i64, serial: i64) -> Result<bool> {
+        let sql = r#"
+UPDATE paper_orders
+SET status = 'cancelled', updated_time = now()
+WHERE serial = $1 AND account_id = $2 AND status = 'pending';
+"#;
+        sqlx::query(sql)
+            .bind(serial)
+            .bind(account_id)
+            .execute(database::get_connection())
+            .timed("paper_orders", "cancel")
+            .await
+            .map(|result| result.rows_affected() > 0)
+            .context(format!(
+                "Failed to PaperOrder::cancel({}, {}) from database",
+                account_id, serial
+            ))
+    }
+}
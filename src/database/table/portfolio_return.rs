@@ -0,0 +1,74 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use sqlx::{postgres::PgQueryResult, FromRow, Postgres, Transaction};
+
+use crate::database::{self, timing::Timed};
+
+/// 整體庫存每日的時間加權報酬 原表名 portfolio_returns
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct PortfolioReturn {
+    pub date: NaiveDate,
+    /// 當日持股相對前一個交易日收盤的報酬率 ex. 0.0123 代表 1.23%
+    pub daily_return: Decimal,
+}
+
+impl PortfolioReturn {
+    /// 以 daily_money_history_detail 內全部成員(member_id = 0)當日與前一日的市值計算報酬，
+    /// 兩者都以當日的持股數計算，當日的買進不會被當成獲利；在 calculate_money_history 的交易內執行，不以 database::with_retry 重試
+    pub async fn upsert(
+        date: NaiveDate,
+        tx: &mut Option<Transaction<'_, Postgres>>,
+    ) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO portfolio_returns (date, daily_return)
+SELECT $1, ROUND(SUM(market_value) / SUM(previous_day_market_value) - 1, 8)
+FROM daily_money_history_detail
+WHERE date = $1 AND member_id = 0
+HAVING SUM(previous_day_market_value) > 0
+ON CONFLICT (date) DO UPDATE SET
+    daily_return = EXCLUDED.daily_return,
+    updated_time = now();
+"#;
+        let query = sqlx::query(sql).bind(date);
+        let result = match tx {
+            None => {
+                query
+                    .execute(database::get_connection())
+                    .timed("portfolio_returns", "upsert")
+                    .await
+            }
+            Some(t) => {
+                query
+                    .execute(&mut **t)
+                    .timed("portfolio_returns", "upsert")
+                    .await
+            }
+        };
+
+        result.context(format!(
+            "Failed to PortfolioReturn::upsert({}) from database",
+            date
+        ))
+    }
+
+    /// 取得 from 到 to(含)之間每日的報酬，依日期由舊到新排序
+    pub async fn fetch_between(from: NaiveDate, to: NaiveDate) -> Result<Vec<PortfolioReturn>> {
+        let sql = r#"
+SELECT date, daily_return
+FROM portfolio_returns
+WHERE date BETWEEN $1 AND $2
+ORDER BY date;
+"#;
+        sqlx::query_as::<_, PortfolioReturn>(sql)
+            .bind(from)
+            .bind(to)
+            .fetch_all(database::get_connection())
+            .timed("portfolio_returns", "fetch_between")
+            .await
+            .context(format!(
+                "Failed to PortfolioReturn::fetch_between({}, {}) from database",
+                from, to
+            ))
+    }
+}
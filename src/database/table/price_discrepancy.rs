@@ -0,0 +1,49 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use sqlx::{postgres::PgQueryResult, FromRow};
+
+use crate::{database, declare::StockSymbol};
+
+/// 收盤價與雅虎報價不一致的記錄 原表名 price_discrepancies
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct PriceDiscrepancy {
+    /// 收盤日
+    pub date: NaiveDate,
+    pub security_code: StockSymbol,
+    /// DailyQuotes 內來自證交所、櫃買中心的收盤價
+    pub closing_price: Decimal,
+    /// 雅虎的收盤價
+    pub yahoo_price: Decimal,
+    /// 差異百分比
+    pub diff_percent: Decimal,
+}
+
+impl PriceDiscrepancy {
+    /// date、security_code 為組合鍵 unique，同一天重跑時覆蓋報價但保留人工修正的標記
+    pub async fn upsert(&self) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO price_discrepancies (date, security_code, closing_price, yahoo_price, diff_percent)
+VALUES ($1, $2, $3, $4, $5)
+ON CONFLICT (date, security_code) DO UPDATE SET
+    closing_price = EXCLUDED.closing_price,
+    yahoo_price = EXCLUDED.yahoo_price,
+    diff_percent = EXCLUDED.diff_percent,
+    updated_time = now();
+"#;
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(self.date)
+                .bind(&self.security_code)
+                .bind(self.closing_price)
+                .bind(self.yahoo_price)
+                .bind(self.diff_percent)
+                .execute(database::get_connection())
+        })
+        .await
+        .context(format!(
+            "Failed to PriceDiscrepancy::upsert({:?}) from database",
+            self
+        ))
+    }
+}
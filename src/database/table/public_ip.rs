@@ -0,0 +1,85 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use sqlx::{postgres::PgQueryResult, FromRow};
+
+use crate::database::{self, timing::Timed};
+
+/// 公網 IP 的變動記錄 原表名 public_ips
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct PublicIp {
+    pub serial: i64,
+    /// 變動後的公網 IP
+    pub ip: String,
+    /// 變動前的公網 IP，第一次記錄時為空字串
+    pub previous_ip: String,
+    /// 偵測到變動的時間
+    pub created_time: DateTime<Local>,
+}
+
+impl PublicIp {
+    pub fn new(ip: String, previous_ip: String) -> Self {
+        PublicIp {
+            serial: 0,
+            ip,
+            previous_ip,
+            created_time: Local::now(),
+        }
+    }
+
+    pub async fn insert(&self) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO public_ips (ip, previous_ip, created_time)
+VALUES ($1, $2, $3);
+"#;
+        sqlx::query(sql)
+            .bind(&self.ip)
+            .bind(&self.previous_ip)
+            .bind(self.created_time)
+            .execute(database::get_connection())
+            .timed("public_ips", "insert")
+            .await
+            .context(format!(
+                "Failed to PublicIp::insert({}) from database",
+                self.ip
+            ))
+    }
+
+    /// 取得最後一次記錄的公網 IP，尚未記錄過時為 None
+    pub async fn fetch_latest() -> Result<Option<PublicIp>> {
+        let sql = r#"
+SELECT serial, ip, previous_ip, created_time
+FROM public_ips
+ORDER BY serial DESC
+LIMIT 1;
+"#;
+        sqlx::query_as::<_, PublicIp>(sql)
+            .fetch_optional(database::get_connection())
+            .timed("public_ips", "fetch_latest")
+            .await
+            .context("Failed to PublicIp::fetch_latest() from database")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logging;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_fetch_latest() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 fetch_latest".to_string());
+
+        match PublicIp::fetch_latest().await {
+            Ok(ip) => logging::debug_file_async(format!("ip: {:?}", ip)),
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to fetch_latest because {:?}", why));
+            }
+        }
+
+        logging::debug_file_async("結束 fetch_latest".to_string());
+    }
+}
@@ -0,0 +1,172 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use sqlx::{postgres::PgQueryResult, FromRow};
+
+use crate::{
+    database::{
+        self,
+        table::stock::extension::qualified_foreign_institutional_investor::QualifiedForeignInstitutionalInvestor,
+        timing::Timed,
+    },
+    declare::StockSymbol,
+};
+
+/// 外資及陸資每日持股的歷史紀錄 原表名 qfii_holdings
+#[derive(FromRow, Debug, Clone, Default, PartialEq)]
+pub struct QfiiHolding {
+    pub date: NaiveDate,
+    pub security_code: StockSymbol,
+    /// 發行股數
+    pub issued_share: i64,
+    /// 全體外資及陸資持有股數
+    pub shares_held: i64,
+    /// 全體外資及陸資持股比率
+    pub holding_percentage: Decimal,
+}
+
+/// 外資持股比率在一段期間內的變化
+#[derive(FromRow, Debug, Clone, Default, PartialEq)]
+pub struct QfiiChange {
+    pub security_code: StockSymbol,
+    pub name: String,
+    /// 比較基準的日期
+    pub previous_date: NaiveDate,
+    pub previous_percentage: Decimal,
+    pub current_percentage: Decimal,
+}
+
+impl QfiiChange {
+    /// 持股比率增減的百分點
+    pub fn points(&self) -> Decimal {
+        self.current_percentage - self.previous_percentage
+    }
+}
+
+impl QfiiHolding {
+    pub fn new(date: NaiveDate, qfii: &QualifiedForeignInstitutionalInvestor) -> Self {
+        QfiiHolding {
+            date,
+            security_code: qfii.stock_symbol.clone(),
+            issued_share: qfii.issued_share,
+            shares_held: qfii.qfii_shares_held,
+            holding_percentage: qfii.qfii_share_holding_percentage,
+        }
+    }
+
+    /// date 與 security_code 為組合鍵 unique
+    pub async fn upsert(&self) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO qfii_holdings (date, security_code, issued_share, shares_held, holding_percentage)
+VALUES ($1, $2, $3, $4, $5)
+ON CONFLICT (date, security_code) DO UPDATE SET
+    issued_share = EXCLUDED.issued_share,
+    shares_held = EXCLUDED.shares_held,
+    holding_percentage = EXCLUDED.holding_percentage,
+    updated_time = now();
+"#;
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(self.date)
+                .bind(&self.security_code)
+                .bind(self.issued_share)
+                .bind(self.shares_held)
+                .bind(self.holding_percentage)
+                .execute(database::get_connection())
+        })
+        .timed("qfii_holdings", "upsert")
+        .await
+        .context(format!(
+            "Failed to QfiiHolding::upsert({}, {}) from database",
+            self.date, self.security_code
+        ))
+    }
+
+    /// 取得股票最近 limit 筆的外資持股紀錄，由舊到新排序
+    pub async fn fetch_trend(security_code: &str, limit: i64) -> Result<Vec<QfiiHolding>> {
+        let sql = r#"
+SELECT date, security_code, issued_share, shares_held, holding_percentage
+FROM (
+    SELECT date, security_code, issued_share, shares_held, holding_percentage
+    FROM qfii_holdings
+    WHERE security_code = $1
+    ORDER BY date DESC
+    LIMIT $2
+) AS recent
+ORDER BY date;
+"#;
+        sqlx::query_as::<_, QfiiHolding>(sql)
+            .bind(security_code)
+            .bind(limit)
+            .fetch_all(database::get_connection())
+            .timed("qfii_holdings", "fetch_trend")
+            .await
+            .context(format!(
+                "Failed to QfiiHolding::fetch_trend({}, {}) from database",
+                security_code, limit
+            ))
+    }
+
+    /// 取得庫存股票 date 當日與 days 天前(含)最近一筆的外資持股比率
+    pub async fn fetch_held_changes(date: NaiveDate, days: i32) -> Result<Vec<QfiiChange>> {
+        let sql = r#"
+WITH held AS (
+    SELECT DISTINCT security_code FROM stock_ownership_details WHERE is_sold = false
+),
+previous AS (
+    SELECT DISTINCT ON (security_code) security_code, date, holding_percentage
+    FROM qfii_holdings
+    WHERE date <= $1 - $2 AND date > $1 - $2 * 2
+    ORDER BY security_code, date DESC
+)
+SELECT
+    c.security_code,
+    s."Name" AS name,
+    p.date AS previous_date,
+    p.holding_percentage AS previous_percentage,
+    c.holding_percentage AS current_percentage
+FROM qfii_holdings AS c
+INNER JOIN held AS h ON h.security_code = c.security_code
+INNER JOIN previous AS p ON p.security_code = c.security_code
+INNER JOIN stocks AS s ON s.stock_symbol = c.security_code
+WHERE c.date = $1
+ORDER BY c.security_code;
+"#;
+        sqlx::query_as::<_, QfiiChange>(sql)
+            .bind(date)
+            .bind(days)
+            .fetch_all(database::get_connection())
+            .timed("qfii_holdings", "fetch_held_changes")
+            .await
+            .context(format!(
+                "Failed to QfiiHolding::fetch_held_changes({}, {}) from database",
+                date, days
+            ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+
+    use crate::logging;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_fetch_held_changes() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 fetch_held_changes".to_string());
+
+        match QfiiHolding::fetch_held_changes(Local::now().date_naive(), 7).await {
+            Ok(list) => logging::debug_file_async(format!("list:{:#?}", list)),
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to fetch_held_changes because {:?}", why))
+            }
+        }
+
+        logging::debug_file_async("結束 fetch_held_changes".to_string());
+    }
+}
@@ -0,0 +1,89 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use sqlx::{postgres::PgQueryResult, FromRow};
+
+use crate::database;
+
+/// 每日數據品質檢查結果 原表名 quality_report
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct QualityReport {
+    /// 檢查的收盤日
+    pub date: NaiveDate,
+    /// 檢查項目
+    pub check_name: String,
+    /// 是否通過
+    pub passed: bool,
+    /// 檢查結果說明
+    pub detail: String,
+}
+
+impl QualityReport {
+    /// date、check_name 為組合鍵 unique，同一天重跑時覆蓋前一次的結果
+    pub async fn upsert(&self) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO quality_report (date, check_name, passed, detail)
+VALUES ($1, $2, $3, $4)
+ON CONFLICT (date, check_name) DO UPDATE SET
+    passed = EXCLUDED.passed,
+    detail = EXCLUDED.detail,
+    updated_time = now();
+"#;
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(self.date)
+                .bind(&self.check_name)
+                .bind(self.passed)
+                .bind(&self.detail)
+                .execute(database::get_connection())
+        })
+        .await
+        .context(format!(
+            "Failed to QualityReport::upsert({:?}) from database",
+            self
+        ))
+    }
+
+    /// 取得指定日期的檢查結果
+    pub async fn fetch_by_date(date: NaiveDate) -> Result<Vec<QualityReport>> {
+        let sql = r#"
+SELECT date, check_name, passed, detail
+FROM quality_report
+WHERE date = $1
+ORDER BY check_name;
+"#;
+        sqlx::query_as::<_, QualityReport>(sql)
+            .bind(date)
+            .fetch_all(database::get_connection())
+            .await
+            .context(format!(
+                "Failed to QualityReport::fetch_by_date({}) from database",
+                date
+            ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logging;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_fetch_by_date() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 QualityReport::fetch_by_date".to_string());
+
+        let date = NaiveDate::from_ymd_opt(2024, 12, 23).unwrap();
+        match QualityReport::fetch_by_date(date).await {
+            Ok(list) => logging::debug_file_async(format!("data:{:#?}", list)),
+            Err(why) => logging::debug_file_async(format!(
+                "Failed to QualityReport::fetch_by_date because {:?}",
+                why
+            )),
+        }
+
+        logging::debug_file_async("結束 QualityReport::fetch_by_date".to_string());
+    }
+}
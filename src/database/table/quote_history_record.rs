@@ -3,7 +3,7 @@ use chrono::NaiveDate;
 use rust_decimal::Decimal;
 use sqlx::postgres::PgQueryResult;
 
-use crate::database;
+use crate::{database, declare::StockSymbol};
 
 #[derive(sqlx::Type, sqlx::FromRow, Debug, Default, Clone)]
 pub struct QuoteHistoryRecord {
@@ -16,7 +16,7 @@ pub struct QuoteHistoryRecord {
     // 歷史最低股價淨值比出現在哪一天
     pub minimum_price_to_book_ratio_date_on: NaiveDate,
     // 股票代號
-    pub security_code: String,
+    pub security_code: StockSymbol,
     // 歷史最高價
     pub maximum_price: Decimal,
     // 歷史最低價
@@ -28,7 +28,7 @@ pub struct QuoteHistoryRecord {
 }
 
 impl QuoteHistoryRecord {
-    pub fn new(security_code: String) -> Self {
+    pub fn new(security_code: StockSymbol) -> Self {
         QuoteHistoryRecord {
             security_code,
             ..Default::default()
@@ -89,19 +89,21 @@ SET
     "minimum_price-to-book_ratio" = EXCLUDED."minimum_price-to-book_ratio",
     "minimum_price-to-book_ratio_date_on" = EXCLUDED."minimum_price-to-book_ratio_date_on"
 "#;
-        sqlx::query(sql)
-            .bind(&self.security_code)
-            .bind(self.maximum_price)
-            .bind(self.maximum_price_date_on)
-            .bind(self.minimum_price)
-            .bind(self.minimum_price_date_on)
-            .bind(self.maximum_price_to_book_ratio)
-            .bind(self.maximum_price_to_book_ratio_date_on)
-            .bind(self.minimum_price_to_book_ratio)
-            .bind(self.minimum_price_to_book_ratio_date_on)
-            .execute(database::get_connection())
-            .await
-            .context(format!("Failed to upsert({:#?}) from database", self))
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(&self.security_code)
+                .bind(self.maximum_price)
+                .bind(self.maximum_price_date_on)
+                .bind(self.minimum_price)
+                .bind(self.minimum_price_date_on)
+                .bind(self.maximum_price_to_book_ratio)
+                .bind(self.maximum_price_to_book_ratio_date_on)
+                .bind(self.minimum_price_to_book_ratio)
+                .bind(self.minimum_price_to_book_ratio_date_on)
+                .execute(database::get_connection())
+        })
+        .await
+        .context(format!("Failed to upsert({:#?}) from database", self))
     }
 }
 
@@ -121,7 +123,7 @@ mod tests {
         dotenv::dotenv().ok();
         logging::info_file_async("開始 upsert".to_string());
         let date = NaiveDate::from_ymd_opt(2023, 8, 2);
-        let mut qhr = QuoteHistoryRecord::new("79979".to_string());
+        let mut qhr = QuoteHistoryRecord::new("79979".parse().unwrap());
         qhr.maximum_price = dec!(1.1);
         qhr.maximum_price_date_on = date.unwrap();
         qhr.maximum_price_to_book_ratio = dec!(1.11);
@@ -2,17 +2,25 @@ use std::str::FromStr;
 
 use anyhow::{Context, Result};
 use chrono::{Datelike, DateTime, FixedOffset, Local, NaiveDate, TimeDelta, TimeZone};
+use futures::{Stream, StreamExt};
 use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 use sqlx::{
     postgres::{PgQueryResult, PgRow},
     Row,
 };
 
-use crate::database;
+use crate::{
+    database::{
+        self,
+        page::{Page, Paged},
+    },
+    declare::StockSymbol,
+};
 
-#[derive(sqlx::Type, sqlx::FromRow, Debug)]
+#[derive(sqlx::Type, sqlx::FromRow, Debug, Deserialize, Serialize)]
 pub struct Revenue {
-    pub security_code: String,
+    pub security_code: StockSymbol,
     /// 當月營收
     pub monthly: Decimal,
     /// 上月營收
@@ -37,7 +45,12 @@ pub struct Revenue {
     pub highest_price: Decimal,
     /// 那個月份的營收
     pub date: i64,
+    /// 寫入的時間不是抓取的數據，不列入序列化(failed_writes、ingestion_batches 的 checksum)
+    #[serde(skip, default = "Local::now")]
     pub create_time: DateTime<Local>,
+    /// 寫入此筆數據的 ingestion_batches 批次，查詢時不帶出
+    #[sqlx(default)]
+    pub batch_id: String,
 }
 
 impl Revenue {
@@ -57,6 +70,7 @@ impl Revenue {
             highest_price: Default::default(),
             date: 0,
             create_time: Local::now(),
+            batch_id: String::new(),
         }
     }
 
@@ -76,11 +90,12 @@ INSERT INTO
         "AccumulatedComparedWithLastYear",
         "avg_price",
         "lowest_price",
-        "highest_price"
+        "highest_price",
+        batch_id
     )
 VALUES
     (
-        $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13
+        $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14
     )
 ON CONFLICT
     ("SecurityCode", "Date")
@@ -96,25 +111,44 @@ SET
     "AccumulatedComparedWithLastYear" = EXCLUDED."AccumulatedComparedWithLastYear",
     "avg_price" = EXCLUDED."avg_price",
     "lowest_price" = EXCLUDED."lowest_price",
-    "highest_price" = EXCLUDED."highest_price";
+    "highest_price" = EXCLUDED."highest_price",
+    batch_id = EXCLUDED.batch_id;
 "#;
-        sqlx::query(sql)
-            .bind(self.security_code.as_str())
-            .bind(self.date)
-            .bind(self.monthly)
-            .bind(self.last_month)
-            .bind(self.last_year_this_month)
-            .bind(self.monthly_accumulated)
-            .bind(self.compared_with_last_month)
-            .bind(self.compared_with_last_year_same_month)
-            .bind(self.last_year_monthly_accumulated)
-            .bind(self.accumulated_compared_with_last_year)
-            .bind(self.avg_price)
-            .bind(self.lowest_price)
-            .bind(self.highest_price)
-            .execute(database::get_connection())
-            .await
-            .context(format!("Failed to upsert({:#?}) from database", self))
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(self.security_code.as_str())
+                .bind(self.date)
+                .bind(self.monthly)
+                .bind(self.last_month)
+                .bind(self.last_year_this_month)
+                .bind(self.monthly_accumulated)
+                .bind(self.compared_with_last_month)
+                .bind(self.compared_with_last_year_same_month)
+                .bind(self.last_year_monthly_accumulated)
+                .bind(self.accumulated_compared_with_last_year)
+                .bind(self.avg_price)
+                .bind(self.lowest_price)
+                .bind(self.highest_price)
+                .bind(&self.batch_id)
+                .execute(database::get_connection())
+        })
+        .await
+        .context(format!("Failed to upsert({:#?}) from database", self))
+    }
+
+    /// 刪除指定批次寫入的數據，用來移除上一個批次有、重新抓取後已不存在的數據，batch_id 為空字串時不刪除
+    pub async fn delete_batch(batch_id: &str) -> Result<PgQueryResult> {
+        let sql = r#"DELETE FROM "Revenue" WHERE batch_id = $1 AND $1 <> '';"#;
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(batch_id)
+                .execute(database::get_connection())
+        })
+        .await
+        .context(format!(
+            "Failed to Revenue::delete_batch({}) from database",
+            batch_id
+        ))
     }
 }
 
@@ -127,7 +161,7 @@ impl Default for Revenue {
 impl Clone for Revenue {
     fn clone(&self) -> Self {
         Revenue {
-            security_code: self.security_code.to_string(),
+            security_code: self.security_code.clone(),
             monthly: self.monthly,
             last_month: self.last_month,
             last_year_this_month: self.last_year_this_month,
@@ -141,16 +175,19 @@ impl Clone for Revenue {
             highest_price: self.highest_price,
             date: self.date,
             create_time: self.create_time,
+            batch_id: self.batch_id.clone(),
         }
     }
 }
 
 //let entity: Entity = fs.into(); // 或者 let entity = Entity::from(fs);
-impl From<Vec<String>> for Revenue {
-    fn from(item: Vec<String>) -> Self {
+impl TryFrom<Vec<String>> for Revenue {
+    type Error = anyhow::Error;
+
+    fn try_from(item: Vec<String>) -> Result<Self> {
         let mut e = Revenue::new();
 
-        e.security_code = item[0].to_string();
+        e.security_code = StockSymbol::parse(&item[0])?;
         /*
         0公司代號	1公司名稱	2當月營收	3上月營收	4去年當月營收	5上月比較增減(%) 6去年同月增減(%) 7當月累計營收 8去年累計營收 9前期比較增減(%)
         */
@@ -213,31 +250,12 @@ impl From<Vec<String>> for Revenue {
                 Default::default()
             });
 
-        e
+        Ok(e)
     }
 }
 
-pub async fn fetch_last_two_month() -> Result<Vec<Revenue>> {
-    let now = Local::now();
-    let now_first_day = NaiveDate::from_ymd_opt(now.year(), now.month(), 1)
-        .unwrap()
-        .and_hms_opt(0, 0, 0)
-        .unwrap();
-    let last_month = now_first_day - TimeDelta::try_minutes(1).unwrap();
-    let timezone = FixedOffset::east_opt(8 * 60 * 60).unwrap();
-    let last_month_timezone = timezone.from_local_datetime(&last_month).unwrap();
-    let two_month_ago_first_day = NaiveDate::from_ymd_opt(last_month.year(), last_month.month(), 1)
-        .unwrap()
-        .and_hms_opt(0, 0, 0)
-        .unwrap();
-    let two_month_ago = two_month_ago_first_day - TimeDelta::try_minutes(1).unwrap();
-    let timezone = FixedOffset::east_opt(8 * 60 * 60).unwrap();
-    let two_month_ago_timezone = timezone.from_local_datetime(&two_month_ago).unwrap();
-    let last_month_int = (last_month_timezone.year() * 100) + last_month_timezone.month() as i32;
-    let two_month_ago_int =
-        (two_month_ago_timezone.year() * 100) + two_month_ago_timezone.month() as i32;
-    let revenue = sqlx::query(
-        r#"
+/// 取得上個月與上上個月營收的 SQL
+const LAST_TWO_MONTH_SQL: &str = r#"
 select
     "SecurityCode",
     "Date",
@@ -257,48 +275,175 @@ from "Revenue"
 where
     "Date" = $1 or "Date" = $2
 order by "Serial" desc
-        "#,
-    )
-    .bind(last_month_int)
-    .bind(two_month_ago_int)
-    .try_map(|row: PgRow| {
-        let date = row.try_get("Date")?;
-        let security_code = row.try_get("SecurityCode")?;
-        let monthly = row.try_get("Monthly")?;
-        let last_month = row.try_get("LastMonth")?;
-        let last_year_this_month = row.try_get("LastYearThisMonth")?;
-        let monthly_accumulated = row.try_get("MonthlyAccumulated")?;
-        let last_year_monthly_accumulated = row.try_get("LastYearMonthlyAccumulated")?;
-        let compared_with_last_month = row.try_get("ComparedWithLastMonth")?;
-        let compared_with_last_year_same_month = row.try_get("ComparedWithLastYearSameMonth")?;
-        let accumulated_compared_with_last_year = row.try_get("AccumulatedComparedWithLastYear")?;
-        let avg_price = row.try_get("avg_price")?;
-        let lowest_price = row.try_get("lowest_price")?;
-        let highest_price = row.try_get("highest_price")?;
-        let create_time = row.try_get("CreateTime")?;
-        Ok(Revenue {
-            date,
-            security_code,
-            monthly,
-            last_month,
-            last_year_this_month,
-            monthly_accumulated,
-            last_year_monthly_accumulated,
-            compared_with_last_month,
-            compared_with_last_year_same_month,
-            accumulated_compared_with_last_year,
-            avg_price,
-            lowest_price,
-            highest_price,
-            create_time,
-        })
+"#;
+
+/// revenue_from_row 需要的欄位
+const REVENUE_COLUMNS: &str = r#"
+    "SecurityCode",
+    "Date",
+    "Monthly",
+    "LastMonth",
+    "LastYearThisMonth",
+    "MonthlyAccumulated",
+    "LastYearMonthlyAccumulated",
+    "ComparedWithLastMonth",
+    "ComparedWithLastYearSameMonth",
+    "AccumulatedComparedWithLastYear",
+    "CreateTime",
+    avg_price,
+    lowest_price,
+    highest_price"#;
+
+/// 分頁查詢可排序的欄位
+const PAGE_COLUMNS: [(&str, &str); 3] = [
+    ("date", r#""Date""#),
+    ("monthly", r#""Monthly""#),
+    ("yoy", r#""ComparedWithLastYearSameMonth""#),
+];
+
+/// 上個月與上上個月的年月 ex. (202405, 202404)
+fn last_two_month() -> (i32, i32) {
+    let now = Local::now();
+    let now_first_day = NaiveDate::from_ymd_opt(now.year(), now.month(), 1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap();
+    let last_month = now_first_day - TimeDelta::try_minutes(1).unwrap();
+    let timezone = FixedOffset::east_opt(8 * 60 * 60).unwrap();
+    let last_month_timezone = timezone.from_local_datetime(&last_month).unwrap();
+    let two_month_ago_first_day = NaiveDate::from_ymd_opt(last_month.year(), last_month.month(), 1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap();
+    let two_month_ago = two_month_ago_first_day - TimeDelta::try_minutes(1).unwrap();
+    let timezone = FixedOffset::east_opt(8 * 60 * 60).unwrap();
+    let two_month_ago_timezone = timezone.from_local_datetime(&two_month_ago).unwrap();
+    let last_month_int = (last_month_timezone.year() * 100) + last_month_timezone.month() as i32;
+    let two_month_ago_int =
+        (two_month_ago_timezone.year() * 100) + two_month_ago_timezone.month() as i32;
+
+    (last_month_int, two_month_ago_int)
+}
+
+/// 將 Revenue 的一列轉成 Revenue
+fn revenue_from_row(row: PgRow) -> Result<Revenue, sqlx::Error> {
+    let date = row.try_get("Date")?;
+    let security_code = row.try_get("SecurityCode")?;
+    let monthly = row.try_get("Monthly")?;
+    let last_month = row.try_get("LastMonth")?;
+    let last_year_this_month = row.try_get("LastYearThisMonth")?;
+    let monthly_accumulated = row.try_get("MonthlyAccumulated")?;
+    let last_year_monthly_accumulated = row.try_get("LastYearMonthlyAccumulated")?;
+    let compared_with_last_month = row.try_get("ComparedWithLastMonth")?;
+    let compared_with_last_year_same_month = row.try_get("ComparedWithLastYearSameMonth")?;
+    let accumulated_compared_with_last_year = row.try_get("AccumulatedComparedWithLastYear")?;
+    let avg_price = row.try_get("avg_price")?;
+    let lowest_price = row.try_get("lowest_price")?;
+    let highest_price = row.try_get("highest_price")?;
+    let create_time = row.try_get("CreateTime")?;
+    Ok(Revenue {
+        date,
+        security_code,
+        monthly,
+        last_month,
+        last_year_this_month,
+        monthly_accumulated,
+        last_year_monthly_accumulated,
+        compared_with_last_month,
+        compared_with_last_year_same_month,
+        accumulated_compared_with_last_year,
+        avg_price,
+        lowest_price,
+        highest_price,
+        create_time,
+        batch_id: String::new(),
     })
-    .fetch_all(database::get_connection())
-    .await?;
+}
+
+pub async fn fetch_last_two_month() -> Result<Vec<Revenue>> {
+    let (last_month, two_month_ago) = last_two_month();
+    let revenue = sqlx::query(LAST_TWO_MONTH_SQL)
+        .bind(last_month)
+        .bind(two_month_ago)
+        .try_map(revenue_from_row)
+        .fetch_all(database::get_connection())
+        .await?;
 
     Ok(revenue)
 }
 
+/// 逐筆取得上個月與上上個月的營收，只需要走訪一次時使用，不會一次將全部的營收載入記憶體
+pub fn stream_last_two_month() -> impl Stream<Item = Result<Revenue>> {
+    let (last_month, two_month_ago) = last_two_month();
+    sqlx::query(LAST_TWO_MONTH_SQL)
+        .bind(last_month)
+        .bind(two_month_ago)
+        .try_map(revenue_from_row)
+        .fetch(database::get_connection())
+        .map(|revenue| revenue.context("Failed to stream_last_two_month from database"))
+}
+
+/// 分頁取得股票的月營收，預設依月份由新到舊
+pub async fn fetch_page(security_code: &str, page: &Page) -> Result<Paged<Revenue>> {
+    let sql = format!(
+        r#"
+SELECT {}
+FROM "Revenue"
+WHERE "SecurityCode" = $1
+ORDER BY {}, "Serial"
+LIMIT $2 OFFSET $3;
+"#,
+        REVENUE_COLUMNS,
+        page.order_clause(&PAGE_COLUMNS, "-date")?
+    );
+    let items = sqlx::query(&sql)
+        .bind(security_code)
+        .bind(page.limit())
+        .bind(page.offset())
+        .try_map(revenue_from_row)
+        .fetch_all(database::get_connection())
+        .await
+        .context(format!(
+            "Failed to revenue::fetch_page({}) from database",
+            security_code
+        ))?;
+    let (total,): (i64,) =
+        sqlx::query_as(r#"SELECT COUNT(*) FROM "Revenue" WHERE "SecurityCode" = $1;"#)
+            .bind(security_code)
+            .fetch_one(database::get_connection())
+            .await
+            .context(format!(
+                "Failed to count revenue::fetch_page({}) from database",
+                security_code
+            ))?;
+
+    Ok(Paged::new(items, total, page))
+}
+
+/// 取得股票最近 limit 個月的月營收(月份 YYYYMM, 當月營收)，依月份由舊到新排序
+pub async fn fetch_recent_monthly(security_code: &str, limit: i64) -> Result<Vec<(i64, Decimal)>> {
+    let sql = r#"
+SELECT "Date", "Monthly"
+FROM (
+    SELECT "Date", "Monthly"
+    FROM "Revenue"
+    WHERE "SecurityCode" = $1
+    ORDER BY "Date" DESC
+    LIMIT $2
+) AS recent
+ORDER BY "Date";
+"#;
+    sqlx::query_as::<_, (i64, Decimal)>(sql)
+        .bind(security_code)
+        .bind(limit)
+        .fetch_all(database::get_connection())
+        .await
+        .context(format!(
+            "Failed to fetch_recent_monthly({}) from database",
+            security_code
+        ))
+}
+
 pub async fn rebuild_revenue_last_date() -> Result<PgQueryResult> {
     let sql = r#"
 -- SET TIMEZONE = 'Asia/Taipei';
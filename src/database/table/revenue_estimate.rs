@@ -0,0 +1,174 @@
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use sqlx::{postgres::PgQueryResult, FromRow};
+
+use crate::{
+    database::{self, timing::Timed},
+    declare::StockSymbol,
+};
+
+/// 只依同產業已公布公司的年增率估算
+pub const BASIS_PEERS: &str = "peers";
+/// 只依去年同期的月增率估算
+pub const BASIS_SEASONALITY: &str = "seasonality";
+/// 兩種估算的平均
+pub const BASIS_BOTH: &str = "peers+seasonality";
+
+/// 月營收公布前估算的庫存股票月營收 原表名 revenue_estimates
+#[derive(FromRow, Debug, Clone, Default, PartialEq)]
+pub struct RevenueEstimate {
+    pub security_code: StockSymbol,
+    /// 營收月份 yyyyMM
+    pub month: i64,
+    /// 估算的當月營收
+    pub estimated: Decimal,
+    /// 估算依據 BASIS_PEERS、BASIS_SEASONALITY、BASIS_BOTH
+    pub basis: String,
+    /// 同產業已公布營收的公司數
+    pub peer_count: i32,
+    /// 同產業已公布公司的年增率中位數(%)
+    pub peer_growth: Decimal,
+    /// 是否為暫估值，公司公布營收後改為 false
+    pub is_provisional: bool,
+    /// 公司公布的當月營收，暫估時為 0
+    pub actual: Decimal,
+}
+
+impl RevenueEstimate {
+    /// security_code 與 month 為組合鍵，已公布營收(is_provisional = false)的數據不再更新
+    pub async fn upsert(&self) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO revenue_estimates (security_code, month, estimated, basis, peer_count, peer_growth)
+VALUES ($1, $2, $3, $4, $5, $6)
+ON CONFLICT (security_code, month) DO UPDATE SET
+    estimated = EXCLUDED.estimated,
+    basis = EXCLUDED.basis,
+    peer_count = EXCLUDED.peer_count,
+    peer_growth = EXCLUDED.peer_growth,
+    updated_time = now()
+WHERE revenue_estimates.is_provisional;
+"#;
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(&self.security_code)
+                .bind(self.month)
+                .bind(self.estimated)
+                .bind(&self.basis)
+                .bind(self.peer_count)
+                .bind(self.peer_growth)
+                .execute(database::get_connection())
+        })
+        .timed("revenue_estimates", "upsert")
+        .await
+        .context(format!(
+            "Failed to RevenueEstimate::upsert({}, {}) from database",
+            self.security_code, self.month
+        ))
+    }
+
+    /// 指定月份已公布營收的公司，將暫估值改為正式並記錄公布的營收
+    pub async fn finalize(month: i64) -> Result<PgQueryResult> {
+        let sql = r#"
+UPDATE revenue_estimates AS e
+SET is_provisional = false,
+    actual = r."Monthly",
+    updated_time = now()
+FROM "Revenue" AS r
+WHERE e.month = $1
+  AND e.is_provisional
+  AND r."SecurityCode" = e.security_code
+  AND r."Date" = e.month;
+"#;
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(month)
+                .execute(database::get_connection())
+        })
+        .timed("revenue_estimates", "finalize")
+        .await
+        .context(format!(
+            "Failed to RevenueEstimate::finalize({}) from database",
+            month
+        ))
+    }
+
+    /// 取得指定月份的全部估算，依股票代號排序
+    pub async fn fetch(month: i64) -> Result<Vec<RevenueEstimate>> {
+        let sql = r#"
+SELECT security_code, month, estimated, basis, peer_count, peer_growth, is_provisional, actual
+FROM revenue_estimates
+WHERE month = $1
+ORDER BY security_code;
+"#;
+        sqlx::query_as::<_, RevenueEstimate>(sql)
+            .bind(month)
+            .fetch_all(database::get_connection())
+            .timed("revenue_estimates", "fetch")
+            .await
+            .context(format!(
+                "Failed to RevenueEstimate::fetch({}) from database",
+                month
+            ))
+    }
+}
+
+/// 取得庫存中(未賣出)尚未公布指定月份營收的股票與其產業編號
+pub async fn fetch_pending_held(month: i64) -> Result<Vec<(StockSymbol, i32)>> {
+    let sql = r#"
+SELECT DISTINCT d.security_code, s.stock_industry_id
+FROM stock_ownership_details AS d
+INNER JOIN stocks AS s ON s.stock_symbol = d.security_code
+WHERE d.is_sold = false
+  AND NOT EXISTS (
+      SELECT 1 FROM "Revenue" AS r WHERE r."SecurityCode" = d.security_code AND r."Date" = $1
+  )
+ORDER BY d.security_code;
+"#;
+    sqlx::query_as::<_, (StockSymbol, i32)>(sql)
+        .bind(month)
+        .fetch_all(database::get_connection())
+        .timed("revenue_estimates", "fetch_pending_held")
+        .await
+        .context(format!(
+            "Failed to revenue_estimate::fetch_pending_held({}) from database",
+            month
+        ))
+}
+
+/// 取得指定月份已公布營收且去年同月有營收的公司之產業編號與年增率(%)
+pub async fn fetch_peer_growths(month: i64) -> Result<Vec<(i32, Decimal)>> {
+    let sql = r#"
+SELECT s.stock_industry_id, r."ComparedWithLastYearSameMonth"
+FROM "Revenue" AS r
+INNER JOIN stocks AS s ON s.stock_symbol = r."SecurityCode"
+WHERE r."Date" = $1 AND r."LastYearThisMonth" > 0;
+"#;
+    sqlx::query_as::<_, (i32, Decimal)>(sql)
+        .bind(month)
+        .fetch_all(database::get_connection())
+        .timed("Revenue", "fetch_peer_growths")
+        .await
+        .context(format!(
+            "Failed to revenue_estimate::fetch_peer_growths({}) from database",
+            month
+        ))
+}
+
+/// 取得股票在指定月份的營收(股票代號, 月份, 當月營收)
+pub async fn fetch_monthly(
+    security_codes: &[StockSymbol],
+    months: &[i64],
+) -> Result<Vec<(StockSymbol, i64, Decimal)>> {
+    let sql = r#"
+SELECT "SecurityCode", "Date", "Monthly"
+FROM "Revenue"
+WHERE "SecurityCode" = ANY($1) AND "Date" = ANY($2);
+"#;
+    sqlx::query_as::<_, (StockSymbol, i64, Decimal)>(sql)
+        .bind(security_codes)
+        .bind(months)
+        .fetch_all(database::get_connection())
+        .timed("Revenue", "fetch_monthly")
+        .await
+        .context("Failed to revenue_estimate::fetch_monthly from database")
+}
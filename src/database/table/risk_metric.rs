@@ -0,0 +1,82 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use sqlx::{postgres::PgQueryResult, FromRow};
+
+use crate::database::{self, timing::Timed};
+
+/// 庫存股票與整體庫存近一年的風險指標 原表名 risk_metrics
+#[derive(FromRow, Debug, Clone, Default, PartialEq)]
+pub struct RiskMetric {
+    pub date: NaiveDate,
+    /// 股票代號，整體庫存為 portfolio
+    pub security_code: String,
+    /// 股票名稱，只在查詢時由 stocks 表帶出
+    pub name: String,
+    /// 相對加權指數(TAIEX)的 beta
+    pub beta: Decimal,
+    /// 年化波動度(%)
+    pub volatility: Decimal,
+    /// 年化夏普比率
+    pub sharpe_ratio: Decimal,
+    /// 計算時使用的日報酬筆數
+    pub sample_days: i32,
+}
+
+impl RiskMetric {
+    pub async fn upsert(&self) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO risk_metrics (date, security_code, beta, volatility, sharpe_ratio, sample_days)
+VALUES ($1, $2, $3, $4, $5, $6)
+ON CONFLICT (date, security_code) DO UPDATE SET
+    beta = EXCLUDED.beta,
+    volatility = EXCLUDED.volatility,
+    sharpe_ratio = EXCLUDED.sharpe_ratio,
+    sample_days = EXCLUDED.sample_days,
+    updated_time = now();
+"#;
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(self.date)
+                .bind(&self.security_code)
+                .bind(self.beta)
+                .bind(self.volatility)
+                .bind(self.sharpe_ratio)
+                .bind(self.sample_days)
+                .execute(database::get_connection())
+        })
+        .timed("risk_metrics", "upsert")
+        .await
+        .context(format!(
+            "Failed to RiskMetric::upsert({}, {}) from database",
+            self.date, self.security_code
+        ))
+    }
+
+    /// 取得指定日期(含)以前最近一次計算的全部風險指標，依代號排序
+    pub async fn fetch_on_or_before(date: NaiveDate) -> Result<Vec<RiskMetric>> {
+        let sql = r#"
+SELECT
+    r.date,
+    r.security_code,
+    COALESCE(s."Name", '') AS name,
+    r.beta,
+    r.volatility,
+    r.sharpe_ratio,
+    r.sample_days
+FROM risk_metrics AS r
+LEFT JOIN stocks AS s ON s.stock_symbol = r.security_code
+WHERE r.date = (SELECT MAX(date) FROM risk_metrics WHERE date <= $1)
+ORDER BY r.security_code;
+"#;
+        sqlx::query_as::<_, RiskMetric>(sql)
+            .bind(date)
+            .fetch_all(database::get_connection())
+            .timed("risk_metrics", "fetch_on_or_before")
+            .await
+            .context(format!(
+                "Failed to RiskMetric::fetch_on_or_before({}) from database",
+                date
+            ))
+    }
+}
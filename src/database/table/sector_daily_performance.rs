@@ -0,0 +1,113 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use sqlx::{postgres::PgQueryResult, FromRow};
+
+use crate::database::{self, timing::Timed};
+
+/// 各產業每日的平均漲跌幅 原表名 sector_daily_performance
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct SectorDailyPerformance {
+    pub date: NaiveDate,
+    /// 股票的產業分類編號 stock_industry
+    pub stock_industry_id: i32,
+    /// 當日有收盤價的股票數
+    pub stock_count: i32,
+    /// 上漲的股票數
+    pub advancing: i32,
+    /// 下跌的股票數
+    pub declining: i32,
+    /// 產業內股票漲跌幅(%)的平均
+    pub average_change_range: Decimal,
+    /// 成交金額合計
+    pub trade_value: Decimal,
+}
+
+const SELECT_SQL: &str = r#"
+SELECT
+    date,
+    stock_industry_id,
+    stock_count,
+    advancing,
+    declining,
+    average_change_range,
+    trade_value
+FROM sector_daily_performance
+"#;
+
+impl SectorDailyPerformance {
+    /// 依指定日期的收盤數據彙總各產業的漲跌，同一天重算時覆蓋
+    pub async fn upsert(date: NaiveDate) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO sector_daily_performance (
+    date, stock_industry_id, stock_count, advancing, declining, average_change_range, trade_value
+)
+SELECT
+    $1,
+    s.stock_industry_id,
+    COUNT(*),
+    COUNT(*) FILTER (WHERE dq."ChangeRange" > 0),
+    COUNT(*) FILTER (WHERE dq."ChangeRange" < 0),
+    ROUND(AVG(dq."ChangeRange"), 4),
+    SUM(dq."TradeValue")
+FROM "DailyQuotes" AS dq
+INNER JOIN stocks AS s ON s.stock_symbol = dq."SecurityCode"
+WHERE dq."Date" = $1 AND dq."ClosingPrice" > 0 AND s.stock_industry_id > 0
+GROUP BY s.stock_industry_id
+ON CONFLICT (date, stock_industry_id) DO UPDATE SET
+    stock_count = EXCLUDED.stock_count,
+    advancing = EXCLUDED.advancing,
+    declining = EXCLUDED.declining,
+    average_change_range = EXCLUDED.average_change_range,
+    trade_value = EXCLUDED.trade_value,
+    updated_time = now();
+"#;
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(date)
+                .execute(database::get_connection())
+        })
+        .timed("sector_daily_performance", "upsert")
+        .await
+        .context(format!(
+            "Failed to SectorDailyPerformance::upsert({}) from database",
+            date
+        ))
+    }
+
+    /// 取得 from 到 to(含)之間各產業每日的表現，依日期由舊到新排序
+    pub async fn fetch_between(from: NaiveDate, to: NaiveDate) -> Result<Vec<Self>> {
+        let sql = format!(
+            "{} WHERE date BETWEEN $1 AND $2 ORDER BY date, stock_industry_id",
+            SELECT_SQL
+        );
+        sqlx::query_as::<_, Self>(&sql)
+            .bind(from)
+            .bind(to)
+            .fetch_all(database::get_connection())
+            .timed("sector_daily_performance", "fetch_between")
+            .await
+            .context(format!(
+                "Failed to SectorDailyPerformance::fetch_between({}, {}) from database",
+                from, to
+            ))
+    }
+
+    /// 取得指定產業最近 limit 個交易日的表現，依日期由舊到新排序
+    pub async fn fetch_trend(stock_industry_id: i32, limit: i64) -> Result<Vec<Self>> {
+        let sql = format!(
+            "SELECT * FROM ({} WHERE stock_industry_id = $1 ORDER BY date DESC LIMIT $2) AS recent ORDER BY date",
+            SELECT_SQL
+        );
+        sqlx::query_as::<_, Self>(&sql)
+            .bind(stock_industry_id)
+            .bind(limit)
+            .fetch_all(database::get_connection())
+            .timed("sector_daily_performance", "fetch_trend")
+            .await
+            .context(format!(
+                "Failed to SectorDailyPerformance::fetch_trend({}, {}) from database",
+                stock_industry_id, limit
+            ))
+    }
+}
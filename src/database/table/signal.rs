@@ -0,0 +1,68 @@
+use std::collections::HashSet;
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use sqlx::{postgres::PgQueryResult, FromRow};
+
+use crate::{
+    database::{self, timing::Timed},
+    declare::StockSymbol,
+};
+
+/// 策略規則觸發的訊號 原表名 signals
+#[derive(FromRow, Debug, Clone, Default, PartialEq)]
+pub struct Signal {
+    pub date: NaiveDate,
+    pub security_code: StockSymbol,
+    /// signals.toml 內的規則名稱
+    pub rule: String,
+    /// 觸發時的指標值
+    pub value: Decimal,
+}
+
+impl Signal {
+    pub async fn upsert(&self) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO signals (date, security_code, rule, value)
+VALUES ($1, $2, $3, $4)
+ON CONFLICT (date, security_code, rule) DO UPDATE SET
+    value = EXCLUDED.value,
+    updated_time = now();
+"#;
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(self.date)
+                .bind(&self.security_code)
+                .bind(&self.rule)
+                .bind(self.value)
+                .execute(database::get_connection())
+        })
+        .timed("signals", "upsert")
+        .await
+        .context(format!(
+            "Failed to Signal::upsert({}, {}, {}) from database",
+            self.date, self.security_code, self.rule
+        ))
+    }
+
+    /// 取得指定日期觸發的 (股票代號, 規則名稱)
+    pub async fn fetch_keys(date: NaiveDate) -> Result<HashSet<(StockSymbol, String)>> {
+        let sql = r#"
+SELECT security_code, rule
+FROM signals
+WHERE date = $1;
+"#;
+        let keys: Vec<(StockSymbol, String)> = sqlx::query_as(sql)
+            .bind(date)
+            .fetch_all(database::get_connection())
+            .timed("signals", "fetch_keys")
+            .await
+            .context(format!(
+                "Failed to Signal::fetch_keys({}) from database",
+                date
+            ))?;
+
+        Ok(keys.into_iter().collect())
+    }
+}
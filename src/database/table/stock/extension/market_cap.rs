@@ -0,0 +1,97 @@
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use sqlx::{postgres::PgQueryResult, FromRow};
+
+use crate::{
+    database::{self, table::stock},
+    declare::StockSymbol,
+};
+
+/// 股票的市值
+#[derive(FromRow, Debug, Clone)]
+pub struct SymbolAndMarketCap {
+    pub stock_symbol: StockSymbol,
+    pub name: String,
+    /// 最近一個交易日的收盤價
+    pub closing_price: Decimal,
+    /// 市值 = 已發行股數 * 收盤價
+    pub market_cap: Decimal,
+}
+
+impl SymbolAndMarketCap {
+    /// 以已發行股數與 last_daily_quotes 的收盤價重新計算所有上市櫃股票的市值
+    pub async fn refresh() -> Result<PgQueryResult> {
+        let sql = r#"
+UPDATE
+    stocks AS s
+SET
+    market_cap = s.issued_share * ldq.closing_price
+FROM
+    last_daily_quotes AS ldq
+WHERE
+    ldq.security_code = s.stock_symbol
+    AND s.stock_exchange_market_id IN (2, 4)
+    AND s.market_cap IS DISTINCT FROM s.issued_share * ldq.closing_price;
+"#;
+        sqlx::query(sql)
+            .execute(database::get_connection())
+            .await
+            .context("Failed to SymbolAndMarketCap::refresh from database")
+    }
+
+    /// 取得市值最高的股票，預設不含權證
+    pub async fn fetch_top(limit: i64) -> Result<Vec<SymbolAndMarketCap>> {
+        let sql = r#"
+SELECT
+    s.stock_symbol,
+    s."Name" AS name,
+    ldq.closing_price,
+    s.market_cap
+FROM
+    stocks AS s
+    INNER JOIN last_daily_quotes AS ldq ON ldq.security_code = s.stock_symbol
+WHERE
+    s."SuspendListing" = false
+    AND s.market_cap > 0
+    AND s.security_type <> ALL($2)
+ORDER BY
+    s.market_cap DESC
+LIMIT $1;
+"#;
+        sqlx::query_as::<_, SymbolAndMarketCap>(sql)
+            .bind(limit)
+            .bind(stock::excluded_security_types())
+            .fetch_all(database::get_connection())
+            .await
+            .context("Failed to SymbolAndMarketCap::fetch_top from database")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logging;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_fetch_top() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 SymbolAndMarketCap::fetch_top".to_string());
+
+        match SymbolAndMarketCap::refresh().await {
+            Ok(r) => logging::debug_file_async(format!("refresh:{:?}", r)),
+            Err(why) => logging::debug_file_async(format!("Failed to refresh because {:?}", why)),
+        }
+
+        match SymbolAndMarketCap::fetch_top(10).await {
+            Ok(list) => logging::debug_file_async(format!("fetch_top:{:#?}", list)),
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to fetch_top because {:?}", why))
+            }
+        }
+
+        logging::debug_file_async("結束 SymbolAndMarketCap::fetch_top".to_string());
+    }
+}
@@ -0,0 +1,149 @@
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use sqlx::FromRow;
+
+use crate::{
+    database::{self, table::stock},
+    declare::StockSymbol,
+};
+
+/// 股票最新的衍生指標，沒有數據的指標為 None
+#[derive(FromRow, Debug, Clone, Default, PartialEq)]
+pub struct StockMetrics {
+    pub stock_symbol: StockSymbol,
+    pub name: String,
+    /// 最近一個交易日的收盤價
+    pub closing_price: Decimal,
+    /// 漲跌幅(%)
+    pub change_range: Decimal,
+    /// 成交股數
+    pub trading_volume: Decimal,
+    pub moving_average_20: Decimal,
+    pub moving_average_60: Decimal,
+    /// 近四季 EPS
+    pub last_four_eps: Decimal,
+    /// 股東權益報酬率(%)
+    pub return_on_equity: Decimal,
+    pub market_cap: Decimal,
+    /// 交易所公布的本益比
+    pub price_earning_ratio: Option<Decimal>,
+    /// 交易所公布的股價淨值比
+    pub price_to_book_ratio: Option<Decimal>,
+    /// 交易所公布的殖利率(%)
+    pub dividend_yield: Option<Decimal>,
+    /// 殖利率在近 5 年殖利率分佈中的百分位(%)
+    pub yield_percentile: Option<Decimal>,
+    /// 最近一個月營收的年增率(%)
+    pub revenue_yoy: Option<Decimal>,
+    /// 最近一個月營收的月增率(%)
+    pub revenue_mom: Option<Decimal>,
+    /// 收盤價距 52 週最高價(%)
+    pub distance_from_high: Option<Decimal>,
+    /// 收盤價自歷史最高收盤價的回檔幅度(%)
+    pub drawdown: Option<Decimal>,
+    /// 近四季的自由現金流量(仟元)，營業與投資活動的淨現金流量合計
+    pub free_cash_flow: Option<Decimal>,
+    /// 自由現金流量殖利率(%)，近四季的自由現金流量除以市值
+    pub fcf_yield: Option<Decimal>,
+}
+
+/// 最新衍生指標的查詢，$2 為空字串時取得所有股票
+///
+/// 財報的現金流量為當年度累計，非第四季時以去年全年度加上今年累計再減去年同期累計換算為近四季
+const LATEST_SQL: &str = r#"
+WITH valuation AS (
+    SELECT DISTINCT ON (security_code) security_code, price_earning_ratio, price_to_book_ratio, dividend_yield
+    FROM daily_valuation
+    WHERE date >= CURRENT_DATE - 14
+    ORDER BY security_code, date DESC
+),
+yield_band AS (
+    SELECT DISTINCT ON (security_code) security_code, percentile
+    FROM yield_percentiles
+    WHERE date >= CURRENT_DATE - 14
+    ORDER BY security_code, date DESC
+),
+revenue AS (
+    SELECT DISTINCT ON ("SecurityCode")
+        "SecurityCode" AS security_code,
+        "ComparedWithLastYearSameMonth" AS revenue_yoy,
+        "ComparedWithLastMonth" AS revenue_mom
+    FROM "Revenue"
+    WHERE "Date" >= CAST(TO_CHAR(CURRENT_DATE - INTERVAL '3 months', 'YYYYMM') AS BIGINT)
+    ORDER BY "SecurityCode", "Date" DESC
+),
+cash_flow AS (
+    SELECT DISTINCT ON (cur.security_code)
+        cur.security_code,
+        CASE
+            WHEN cur.quarter = 'Q4' THEN cur.operating_cash_flow + cur.investing_cash_flow
+            ELSE cur.operating_cash_flow + cur.investing_cash_flow
+                + q4.operating_cash_flow + q4.investing_cash_flow
+                - same.operating_cash_flow - same.investing_cash_flow
+        END AS free_cash_flow
+    FROM financial_statement AS cur
+    LEFT JOIN financial_statement AS q4
+        ON q4.security_code = cur.security_code AND q4.year = cur.year - 1 AND q4.quarter = 'Q4'
+        AND q4.operating_cash_flow <> 0
+    LEFT JOIN financial_statement AS same
+        ON same.security_code = cur.security_code AND same.year = cur.year - 1 AND same.quarter = cur.quarter
+        AND same.operating_cash_flow <> 0
+    WHERE cur.quarter IN ('Q1', 'Q2', 'Q3', 'Q4') AND cur.operating_cash_flow <> 0
+    ORDER BY cur.security_code, cur.year DESC, cur.quarter DESC
+)
+SELECT
+    s.stock_symbol,
+    s."Name" AS name,
+    ldq.closing_price,
+    ldq.change_range,
+    ldq.trading_volume,
+    ldq.moving_average_20,
+    ldq.moving_average_60,
+    s.last_four_eps,
+    s.return_on_equity,
+    s.market_cap,
+    v.price_earning_ratio,
+    v.price_to_book_ratio,
+    v.dividend_yield,
+    y.percentile AS yield_percentile,
+    r.revenue_yoy,
+    r.revenue_mom,
+    w.distance_from_high,
+    w.drawdown,
+    cf.free_cash_flow,
+    CASE WHEN s.market_cap > 0 THEN cf.free_cash_flow * 100000 / s.market_cap END AS fcf_yield
+FROM stocks AS s
+INNER JOIN last_daily_quotes AS ldq ON ldq.security_code = s.stock_symbol
+LEFT JOIN valuation AS v ON v.security_code = s.stock_symbol
+LEFT JOIN yield_band AS y ON y.security_code = s.stock_symbol
+LEFT JOIN revenue AS r ON r.security_code = s.stock_symbol
+LEFT JOIN week52_stats AS w ON w.security_code = s.stock_symbol
+LEFT JOIN cash_flow AS cf ON cf.security_code = s.stock_symbol
+WHERE s."SuspendListing" = false
+    AND s.security_type <> ALL($1)
+    AND ($2 = '' OR s.stock_symbol = $2)
+ORDER BY s.stock_symbol;
+"#;
+
+/// 取得所有未下市股票(預設不含權證)的最新衍生指標，估值與殖利率百分位取近兩週內最新的一筆，營收取近三個月內最新的一個月
+pub async fn fetch_latest() -> Result<Vec<StockMetrics>> {
+    sqlx::query_as::<_, StockMetrics>(LATEST_SQL)
+        .bind(stock::excluded_security_types())
+        .bind("")
+        .fetch_all(database::get_connection())
+        .await
+        .context("Failed to metrics::fetch_latest from database")
+}
+
+/// 取得指定股票的最新衍生指標
+pub async fn fetch_one(stock_symbol: &str) -> Result<Option<StockMetrics>> {
+    sqlx::query_as::<_, StockMetrics>(LATEST_SQL)
+        .bind(stock::excluded_security_types())
+        .bind(stock_symbol)
+        .fetch_optional(database::get_connection())
+        .await
+        .context(format!(
+            "Failed to metrics::fetch_one({}) from database",
+            stock_symbol
+        ))
+}
@@ -1,3 +1,7 @@
+/// 市值
+pub(crate) mod market_cap;
+/// 選股用的最新衍生指標
+pub(crate) mod metrics;
 pub(crate) mod net_asset_value_per_share;
 /// 合格境外機構投資者(外資及陸資)
 pub(crate) mod qualified_foreign_institutional_investor;
@@ -2,12 +2,15 @@ use anyhow::*;
 use rust_decimal::Decimal;
 use sqlx::{postgres::PgQueryResult, FromRow};
 
-use crate::database::{self, table::stock};
+use crate::{
+    database::{self, table::stock},
+    declare::StockSymbol,
+};
 
 /// 更新股票的每股淨值
 #[derive(FromRow, Debug)]
 pub struct SymbolAndNetAssetValuePerShare {
-    pub stock_symbol: String,
+    pub stock_symbol: StockSymbol,
     //每股淨值
     pub net_asset_value_per_share: Decimal,
 }
@@ -24,7 +27,7 @@ impl From<&stock::Stock> for SymbolAndNetAssetValuePerShare {
 
 /// 股號和每股淨值
 impl SymbolAndNetAssetValuePerShare {
-    pub fn new(stock_symbol: String, net_asset_value_per_share: Decimal) -> Self {
+    pub fn new(stock_symbol: StockSymbol, net_asset_value_per_share: Decimal) -> Self {
         SymbolAndNetAssetValuePerShare {
             stock_symbol,
             net_asset_value_per_share,
@@ -2,12 +2,12 @@ use anyhow::{Context, Result};
 use rust_decimal::Decimal;
 use sqlx::{postgres::PgQueryResult, FromRow};
 
-use crate::{database, util::convert::FromValue};
+use crate::{database, declare::StockSymbol, util::convert::FromValue};
 
 ///
 #[derive(FromRow, Debug)]
 pub struct QualifiedForeignInstitutionalInvestor {
-    pub stock_symbol: String,
+    pub stock_symbol: StockSymbol,
     /// 已發行股數
     pub issued_share: i64,
     /// 全體外資及陸資持有股數
@@ -18,7 +18,7 @@ pub struct QualifiedForeignInstitutionalInvestor {
 
 impl QualifiedForeignInstitutionalInvestor {
     pub fn new(
-        stock_symbol: String,
+        stock_symbol: StockSymbol,
         issued_share: i64,
         qfii_shares_held: i64,
         qfii_share_holding_percentage: Decimal,
@@ -55,35 +55,39 @@ WHERE
 }
 
 //上櫃股票
-impl From<Vec<String>> for QualifiedForeignInstitutionalInvestor {
-    fn from(item: Vec<String>) -> Self {
-        let stock_symbol = item[1].get_string(None);
+impl TryFrom<Vec<String>> for QualifiedForeignInstitutionalInvestor {
+    type Error = anyhow::Error;
+
+    fn try_from(item: Vec<String>) -> Result<Self> {
+        let stock_symbol = StockSymbol::parse(&item[1].get_string(None))?;
         let issued_share = item[5].get_i64(None);
         let qfii_shares_held = item[9].get_i64(None);
         let qfii_share_holding_percentage = item[13].get_decimal(Some(vec!['\u{a0}']));
 
-        QualifiedForeignInstitutionalInvestor::new(
+        Ok(QualifiedForeignInstitutionalInvestor::new(
             stock_symbol,
             issued_share,
             qfii_shares_held,
             qfii_share_holding_percentage,
-        )
+        ))
     }
 }
 
 // 上市股票
-impl From<Vec<serde_json::Value>> for QualifiedForeignInstitutionalInvestor {
-    fn from(item: Vec<serde_json::Value>) -> Self {
-        let stock_symbol = item[0].get_string(None);
+impl TryFrom<Vec<serde_json::Value>> for QualifiedForeignInstitutionalInvestor {
+    type Error = anyhow::Error;
+
+    fn try_from(item: Vec<serde_json::Value>) -> Result<Self> {
+        let stock_symbol = StockSymbol::parse(&item[0].get_string(None))?;
         let issued_share = item[3].get_i64(None);
         let qfii_shares_held = item[5].get_i64(None);
         let qfii_share_holding_percentage = item[7].get_decimal(None);
 
-        QualifiedForeignInstitutionalInvestor::new(
+        Ok(QualifiedForeignInstitutionalInvestor::new(
             stock_symbol,
             issued_share,
             qfii_shares_held,
             qfii_share_holding_percentage,
-        )
+        ))
     }
 }
@@ -1,12 +1,15 @@
 use anyhow::{Context, Result};
 use sqlx::{postgres::PgQueryResult, FromRow};
 
-use crate::database::{self, table::stock};
+use crate::{
+    database::{self, table::stock},
+    declare::StockSymbol,
+};
 
 /// 更新股票的下市狀態
 #[derive(FromRow, Debug)]
 pub struct SymbolAndSuspendListing {
-    pub stock_symbol: String,
+    pub stock_symbol: StockSymbol,
     pub suspend_listing: bool,
 }
 
@@ -19,7 +22,7 @@ impl From<&stock::Stock> for SymbolAndSuspendListing {
 
 /// 股號和每股淨值
 impl SymbolAndSuspendListing {
-    pub fn new(stock_symbol: String, suspend_listing: bool) -> Self {
+    pub fn new(stock_symbol: StockSymbol, suspend_listing: bool) -> Self {
         SymbolAndSuspendListing {
             stock_symbol,
             suspend_listing,
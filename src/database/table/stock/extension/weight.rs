@@ -2,12 +2,12 @@ use anyhow::{anyhow, Context, Result};
 use rust_decimal::Decimal;
 use sqlx::{postgres::PgQueryResult, FromRow};
 
-use crate::{crawler::taifex::stock_weight::StockWeight, database};
+use crate::{crawler::taifex::stock_weight::StockWeight, database, declare::StockSymbol};
 
 /// 更新股票的權重
 #[derive(FromRow, Debug, Clone)]
 pub struct SymbolAndWeight {
-    pub stock_symbol: String,
+    pub stock_symbol: StockSymbol,
     //權植佔比
     pub weight: Decimal,
 }
@@ -25,7 +25,7 @@ pub fn from(weights: Vec<StockWeight>) -> Vec<SymbolAndWeight> {
 }
 
 impl SymbolAndWeight {
-    pub fn new(stock_symbol: String, weight: Decimal) -> Self {
+    pub fn new(stock_symbol: StockSymbol, weight: Decimal) -> Self {
         SymbolAndWeight {
             stock_symbol,
             weight,
@@ -85,7 +85,7 @@ mod tests {
         logging::debug_file_async("開始 update".to_string());
         let stock_weight = StockWeight {
             rank: 0,
-            stock_symbol: "2330".to_string(),
+            stock_symbol: "2330".parse().unwrap(),
             weight: dec!(28.3278),
         };
 
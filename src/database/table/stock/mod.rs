@@ -4,21 +4,25 @@ use rust_decimal::Decimal;
 use sqlx::{postgres::PgQueryResult, postgres::PgRow, Row};
 
 use crate::{
+    cache::SHARE,
     crawler::{tpex, twse},
     database::{
         self,
         table::{stock_index, stock_word},
     },
+    declare::{SecurityType, StockSymbol},
     logging,
     util::{self, map::Keyable},
 };
 
 pub(crate) mod extension;
+/// 以代號或名稱模糊搜尋股票的索引
+pub mod search;
 
 #[derive(sqlx::Type, sqlx::FromRow, Debug)]
 /// 原表名 stocks
 pub struct Stock {
-    pub stock_symbol: String,
+    pub stock_symbol: StockSymbol,
     pub name: String,
     pub suspend_listing: bool,
     pub net_asset_value_per_share: Decimal,
@@ -31,6 +35,8 @@ pub struct Stock {
     pub stock_exchange_market_id: i32,
     /// 股票的產業分類編號 stock_industry
     pub stock_industry_id: i32,
+    /// 證券類別參考 SecurityType
+    pub security_type: i32,
     /// 已發行股數
     pub issued_share: i64,
     /// 全體外資及陸資持有股數
@@ -42,7 +48,7 @@ pub struct Stock {
 impl Stock {
     pub fn new() -> Self {
         Stock {
-            stock_symbol: "".to_string(),
+            stock_symbol: Default::default(),
             name: "".to_string(),
             suspend_listing: false,
             net_asset_value_per_share: Default::default(),
@@ -51,6 +57,7 @@ impl Stock {
             create_time: Local::now(),
             stock_exchange_market_id: 0,
             stock_industry_id: 0,
+            security_type: 0,
             issued_share: 0,
             qfii_shares_held: 0,
             qfii_share_holding_percentage: Default::default(),
@@ -64,7 +71,12 @@ impl Stock {
 
     /// 是否為臺灣存託憑證
     pub fn is_tdr(&self) -> bool {
-        self.name.contains("-DR")
+        self.security_type == SecurityType::Tdr.serial() || self.name.contains("-DR")
+    }
+
+    /// 是否為認購(售)權證
+    pub fn is_warrant(&self) -> bool {
+        self.security_type == SecurityType::Warrant.serial()
     }
 
     /// 更新個股最新一季、近四季的EPS、ROE
@@ -132,29 +144,33 @@ WHERE
             .context("Failed to update_last_eps from database")
     }
 
-    /// 衝突時更新 "Name" "SuspendListing" stock_exchange_market_id stock_industry_id
+    /// 衝突時更新 "Name" "SuspendListing" stock_exchange_market_id stock_industry_id security_type
     pub async fn upsert(&self) -> Result<PgQueryResult> {
         let sql = r#"
 INSERT INTO stocks (
     stock_symbol, "Name", "CreateTime",
-    "SuspendListing", stock_exchange_market_id, stock_industry_id,weight)
-VALUES ($1, $2, $3, $4, $5, $6, 0)
+    "SuspendListing", stock_exchange_market_id, stock_industry_id, security_type, weight)
+VALUES ($1, $2, $3, $4, $5, $6, $7, 0)
 ON CONFLICT (stock_symbol) DO UPDATE SET
     "Name" = EXCLUDED."Name",
     "SuspendListing" = EXCLUDED."SuspendListing",
     stock_exchange_market_id = EXCLUDED.stock_exchange_market_id,
-    stock_industry_id = EXCLUDED.stock_industry_id;
+    stock_industry_id = EXCLUDED.stock_industry_id,
+    security_type = EXCLUDED.security_type;
 "#;
-        let result = sqlx::query(sql)
-            .bind(&self.stock_symbol)
-            .bind(&self.name)
-            .bind(self.create_time)
-            .bind(self.suspend_listing)
-            .bind(self.stock_exchange_market_id)
-            .bind(self.stock_industry_id)
-            .execute(database::get_connection())
-            .await
-            .context("Failed to stock.upsert from database");
+        let result = database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(&self.stock_symbol)
+                .bind(&self.name)
+                .bind(self.create_time)
+                .bind(self.suspend_listing)
+                .bind(self.stock_exchange_market_id)
+                .bind(self.stock_industry_id)
+                .bind(self.security_type)
+                .execute(database::get_connection())
+        })
+        .await
+        .context("Failed to stock.upsert from database");
         self.create_index().await;
 
         result
@@ -182,7 +198,7 @@ ON CONFLICT (stock_symbol) DO UPDATE SET
         };
 
         for word in words {
-            let mut stock_index_e = stock_index::StockIndex::new(self.stock_symbol.to_string());
+            let mut stock_index_e = stock_index::StockIndex::new(self.stock_symbol.clone());
 
             match exist_words.get(&word) {
                 Some(w) => {
@@ -228,6 +244,7 @@ SELECT
     weight,
     stock_exchange_market_id,
     stock_industry_id,
+    security_type,
     issued_share,
     qfii_shares_held,
     qfii_share_holding_percentage
@@ -248,6 +265,7 @@ ORDER BY
                     create_time: row.try_get("create_time")?,
                     stock_exchange_market_id: row.try_get("stock_exchange_market_id")?,
                     stock_industry_id: row.try_get("stock_industry_id")?,
+                    security_type: row.try_get("security_type")?,
                     issued_share: row.try_get("issued_share")?,
                     qfii_shares_held: row.try_get("qfii_shares_held")?,
                     return_on_equity: row.try_get("return_on_equity")?,
@@ -259,7 +277,7 @@ ORDER BY
             .map_err(|why| {
                 anyhow!(
                     "Failed to Stock::fetch from database({:#?}) because:{:?}",
-                    crate::config::SETTINGS.postgresql,
+                    crate::config::SETTINGS.load().postgresql,
                     why
                 )
             })
@@ -268,7 +286,7 @@ ORDER BY
 
 impl Keyable for Stock {
     fn key(&self) -> String {
-        self.stock_symbol.clone()
+        self.stock_symbol.to_string()
     }
 
     fn key_with_prefix(&self) -> String {
@@ -288,6 +306,7 @@ impl Clone for Stock {
             create_time: self.create_time,
             stock_exchange_market_id: self.stock_exchange_market_id,
             stock_industry_id: self.stock_industry_id,
+            security_type: self.security_type,
             issued_share: self.issued_share,
             qfii_shares_held: self.qfii_shares_held,
             qfii_share_holding_percentage: self.qfii_share_holding_percentage,
@@ -314,6 +333,7 @@ impl From<twse::international_securities_identification_number::InternationalSec
             create_time: Local::now(),
             stock_exchange_market_id: isin.exchange_market.stock_exchange_market_id,
             stock_industry_id: isin.industry_id,
+            security_type: isin.security_type.serial(),
             issued_share: 0,
             qfii_shares_held: 0,
             qfii_share_holding_percentage: Default::default(),
@@ -334,6 +354,7 @@ impl From<tpex::net_asset_value_per_share::Emerging> for Stock {
             create_time: Local::now(),
             stock_exchange_market_id: Default::default(),
             stock_industry_id: Default::default(),
+            security_type: Default::default(),
             issued_share: 0,
             qfii_shares_held: 0,
             qfii_share_holding_percentage: Default::default(),
@@ -353,6 +374,7 @@ SELECT
     s.return_on_equity,
     s.stock_exchange_market_id,
     s.stock_industry_id,
+    s.security_type,
     s.weight,
     s.issued_share,
     s.qfii_shares_held,
@@ -369,7 +391,7 @@ WHERE s.stock_exchange_market_id in (2, 4)
         .context("Failed to fetch_net_asset_value_per_share_is_zero from database")
 }
 
-/// 取得尚未有指定年度的季報的股票或者財報的每股淨值為零的股票
+/// 取得尚未有指定年度的季報的股票或者財報的每股淨值為零的股票，只有個別財報的股票也會列出以便改用合併財報
 pub async fn fetch_stocks_without_financial_statement(
     year: i32,
     quarter: &str,
@@ -384,6 +406,7 @@ SELECT
     s.return_on_equity,
     s.stock_exchange_market_id,
     s.stock_industry_id,
+    s.security_type,
     s.weight,
     s.issued_share,
     s.qfii_shares_held,
@@ -395,6 +418,7 @@ WHERE s.stock_exchange_market_id in(2, 4)
         SELECT 1
         FROM financial_statement f
         WHERE f.security_code = s.stock_symbol AND f.year = $1 AND f.quarter = $2
+            AND f.statement_type = 1
     )
 "#;
 
@@ -406,6 +430,37 @@ WHERE s.stock_exchange_market_id in(2, 4)
         .context("Failed to fetch_stocks_without_financial_statement from database")
 }
 
+/// 取得指定產業分類(stock_industry)下未下市的上市櫃股票
+pub async fn fetch_by_industry(industry_id: i32) -> Result<Vec<Stock>> {
+    let sql = r#"
+SELECT
+    s.stock_symbol,
+    s."Name" AS name,
+    s."SuspendListing" AS suspend_listing,
+    s."CreateTime" AS create_time,
+    s.net_asset_value_per_share,
+    s.return_on_equity,
+    s.stock_exchange_market_id,
+    s.stock_industry_id,
+    s.security_type,
+    s.weight,
+    s.issued_share,
+    s.qfii_shares_held,
+    s.qfii_share_holding_percentage
+FROM stocks AS s
+WHERE s.stock_industry_id = $1
+    AND s.stock_exchange_market_id in (2, 4)
+    AND s."SuspendListing" = false
+ORDER BY s.stock_symbol
+"#;
+
+    sqlx::query_as::<_, Stock>(sql)
+        .bind(industry_id)
+        .fetch_all(database::get_connection())
+        .await
+        .context(format!("Failed to fetch_by_industry({}) from database", industry_id))
+}
+
 /// 是否為特別股
 pub fn is_preference_shares(stock_symbol: &str) -> bool {
     stock_symbol
@@ -413,9 +468,26 @@ pub fn is_preference_shares(stock_symbol: &str) -> bool {
         .any(|c| c.is_ascii_uppercase() || c.is_ascii_lowercase())
 }
 
+/// 排行、選股與殖利率報表要排除的證券類別，設定檔 report.include_warrants 為 false 時排除權證
+pub fn excluded_security_types() -> Vec<i32> {
+    if crate::config::SETTINGS.load().report.include_warrants {
+        Vec::new()
+    } else {
+        vec![SecurityType::Warrant.serial()]
+    }
+}
+
+/// 以代號或名稱模糊搜尋快取內的股票 ex. `stock::search("台積", 5)` 的第一筆為 2330
+pub fn search(query: &str, limit: usize) -> Vec<search::StockMatch> {
+    match SHARE.stocks.read() {
+        Ok(stocks) => search::SearchIndex::build(stocks.values()).search(query, limit),
+        Err(_) => Vec::new(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::logging;
+    use crate::{declare::Industry, logging};
 
     use super::*;
 
@@ -494,12 +566,32 @@ mod tests {
         logging::debug_file_async("結束 fetch_stocks_without_financial_statement".to_string());
     }
 
+    #[tokio::test]
+    #[ignore]
+    async fn test_fetch_by_industry() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 fetch_by_industry".to_string());
+        match fetch_by_industry(Industry::Semiconductor.serial()).await {
+            Ok(stocks) => {
+                logging::debug_file_async(format!("stocks({}):{:#?}", stocks.len(), stocks));
+            }
+            Err(why) => {
+                logging::debug_file_async(format!(
+                    "Failed to fetch_by_industry because: {:?}",
+                    why
+                ));
+            }
+        }
+
+        logging::debug_file_async("結束 fetch_by_industry".to_string());
+    }
+
     #[tokio::test]
     #[ignore]
     async fn test_create_index() {
         dotenv::dotenv().ok();
         let mut e = Stock::new();
-        e.stock_symbol = "2330".to_string();
+        e.stock_symbol = "2330".parse().unwrap();
         e.name = "台積電".to_string();
         e.create_index().await;
     }
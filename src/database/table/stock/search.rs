@@ -0,0 +1,196 @@
+use std::cmp::Reverse;
+
+use crate::{database::table::stock::Stock, declare::StockSymbol};
+
+/// 代號完全相同
+const SCORE_SYMBOL_EXACT: u32 = 100;
+/// 名稱完全相同
+const SCORE_NAME_EXACT: u32 = 95;
+/// 代號開頭相同 ex. 233 => 2330
+const SCORE_SYMBOL_PREFIX: u32 = 80;
+/// 名稱開頭相同 ex. 台積 => 台積電
+const SCORE_NAME_PREFIX: u32 = 70;
+/// 名稱包含 ex. 積電 => 台積電
+const SCORE_NAME_CONTAINS: u32 = 60;
+/// 名稱依序包含每個字 ex. 台電 => 台積電
+const SCORE_NAME_SUBSEQUENCE: u32 = 40;
+/// 名稱只差一個字 ex. 台積店 => 台積電
+const SCORE_NAME_TYPO: u32 = 30;
+
+/// 搜尋到的股票，score 越高越符合
+#[derive(Debug, Clone, PartialEq)]
+pub struct StockMatch {
+    pub stock_symbol: StockSymbol,
+    pub name: String,
+    pub score: u32,
+}
+
+struct Entry {
+    stock_symbol: StockSymbol,
+    name: String,
+    /// 正規化後的代號與名稱
+    symbol_key: String,
+    name_key: String,
+    suspend_listing: bool,
+}
+
+/// 以股票代號與名稱建立的搜尋索引
+pub struct SearchIndex {
+    entries: Vec<Entry>,
+}
+
+impl SearchIndex {
+    pub fn build<'a, I>(stocks: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Stock>,
+    {
+        let entries = stocks
+            .into_iter()
+            .map(|stock| Entry {
+                stock_symbol: stock.stock_symbol.clone(),
+                name: stock.name.clone(),
+                symbol_key: normalize(&stock.stock_symbol),
+                name_key: normalize(&stock.name),
+                suspend_listing: stock.suspend_listing,
+            })
+            .collect();
+
+        SearchIndex { entries }
+    }
+
+    /// 依符合程度排序後回傳前 limit 筆，分數相同時上市櫃中的、代號較短(普通股)的排前面
+    pub fn search(&self, query: &str, limit: usize) -> Vec<StockMatch> {
+        let query = normalize(query);
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let mut matches: Vec<(u32, &Entry)> = self
+            .entries
+            .iter()
+            .filter_map(|entry| score(entry, &query).map(|score| (score, entry)))
+            .collect();
+        matches.sort_by_key(|(score, entry)| {
+            (
+                Reverse(*score),
+                entry.suspend_listing,
+                entry.stock_symbol.len(),
+                entry.stock_symbol.clone(),
+            )
+        });
+
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|(score, entry)| StockMatch {
+                stock_symbol: entry.stock_symbol.clone(),
+                name: entry.name.clone(),
+                score,
+            })
+            .collect()
+    }
+}
+
+fn score(entry: &Entry, query: &str) -> Option<u32> {
+    if entry.symbol_key == query {
+        Some(SCORE_SYMBOL_EXACT)
+    } else if entry.name_key == query {
+        Some(SCORE_NAME_EXACT)
+    } else if entry.symbol_key.starts_with(query) {
+        Some(SCORE_SYMBOL_PREFIX)
+    } else if entry.name_key.starts_with(query) {
+        Some(SCORE_NAME_PREFIX)
+    } else if entry.name_key.contains(query) {
+        Some(SCORE_NAME_CONTAINS)
+    } else if is_subsequence(query, &entry.name_key) {
+        Some(SCORE_NAME_SUBSEQUENCE)
+    } else if query.chars().count() >= 2 && edit_distance(query, &entry.name_key) <= 1 {
+        Some(SCORE_NAME_TYPO)
+    } else {
+        None
+    }
+}
+
+/// 去掉空白、轉小寫、全形英數轉半形，臺統一為台
+fn normalize(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            '臺' => '台',
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            _ => c,
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// query 的每個字是否依序出現在 text 內
+fn is_subsequence(query: &str, text: &str) -> bool {
+    let mut chars = text.chars();
+    query.chars().all(|q| chars.any(|c| c == q))
+}
+
+/// 以字為單位的編輯距離
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            current[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(current[j] + 1);
+        }
+        prev = current;
+    }
+
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn stock(symbol: &str, name: &str) -> Stock {
+        let mut stock = Stock::new();
+        stock.stock_symbol = symbol.parse().unwrap();
+        stock.name = name.to_string();
+        stock
+    }
+
+    fn symbols(matches: Vec<StockMatch>) -> Vec<String> {
+        matches.into_iter().map(|m| m.stock_symbol.into()).collect()
+    }
+
+    #[test]
+    fn test_search() {
+        let stocks = [
+            stock("2330", "台積電"),
+            stock("2303", "聯電"),
+            stock("3443", "創意"),
+            stock("00632R", "元大台灣50反1"),
+            stock("0050", "元大台灣50"),
+        ];
+        let index = SearchIndex::build(&stocks);
+
+        assert_eq!(symbols(index.search("台積", 5)), vec!["2330"]);
+        assert_eq!(symbols(index.search("臺積電", 5)), vec!["2330"]);
+        assert_eq!(symbols(index.search("台積店", 5)), vec!["2330"]);
+        assert_eq!(symbols(index.search("2330", 5)), vec!["2330"]);
+        assert_eq!(symbols(index.search("233", 5)), vec!["2330"]);
+        assert_eq!(symbols(index.search("００６３２ｒ", 5)), vec!["00632R"]);
+        assert_eq!(symbols(index.search("元大台灣", 5)), vec!["0050", "00632R"]);
+        assert_eq!(symbols(index.search("元大台灣", 1)), vec!["0050"]);
+        assert!(index.search(" ", 5).is_empty());
+        assert!(index.search("鴻海", 5).is_empty());
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("台積電", "台積電"), 0);
+        assert_eq!(edit_distance("台積店", "台積電"), 1);
+        assert_eq!(edit_distance("台積", "台積電"), 1);
+        assert_eq!(edit_distance("聯電", "台積電"), 2);
+    }
+}
@@ -39,7 +39,7 @@ FROM
         .map_err(|why| {
             anyhow!(
                 "Failed to StockExchangeMarket::fetch from database({:#?}) because:{:?}",
-                crate::config::SETTINGS.postgresql,
+                crate::config::SETTINGS.load().postgresql,
                 why
             )
         })
@@ -2,18 +2,18 @@ use anyhow::{anyhow, Result};
 use chrono::{DateTime, Local};
 use sqlx::{postgres::PgQueryResult, Postgres, Transaction};
 
-use crate::database;
+use crate::{database, declare::StockSymbol};
 
 #[derive(sqlx::Type, sqlx::FromRow, Debug)]
 pub struct StockIndex {
     pub word_id: i64,
-    pub security_code: String,
+    pub security_code: StockSymbol,
     pub created_time: DateTime<Local>,
     pub updated_time: DateTime<Local>,
 }
 
 impl StockIndex {
-    pub fn new(security_code: String) -> Self {
+    pub fn new(security_code: StockSymbol) -> Self {
         StockIndex {
             word_id: Default::default(),
             security_code,
@@ -99,7 +99,7 @@ mod tests {
     #[tokio::test]
     async fn test_insert() {
         dotenv::dotenv().ok();
-        let mut e = StockIndex::new("79979".to_string());
+        let mut e = StockIndex::new("79979".parse().unwrap());
         e.word_id = 79979;
         match e.insert().await {
             Ok(_) => {
@@ -0,0 +1,95 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use sqlx::{FromRow, Postgres, Transaction};
+
+use crate::{
+    database::{self, timing::Timed},
+    declare::StockSymbol,
+};
+
+/// 賣出時與買進批次配對的明細 原表名 stock_lot_sales
+#[derive(FromRow, Debug, Clone, Default, PartialEq)]
+pub struct StockLotSale {
+    pub serial: i64,
+    pub member_id: i64,
+    pub security_code: StockSymbol,
+    /// 配對的買進批次 stock_ownership_details.serial
+    pub lot_serial: i64,
+    /// 自該批次賣出的股數
+    pub quantity: i64,
+    pub buy_date: NaiveDate,
+    pub sell_date: NaiveDate,
+    /// 每股賣出價
+    pub sell_price: Decimal,
+    /// 賣出股數分攤的買入成本
+    pub cost: Decimal,
+    /// 賣出金額(未扣費用)
+    pub proceeds: Decimal,
+    /// 分攤的賣出手續費與交易稅
+    pub fee: Decimal,
+    /// 已實現損益 = 賣出金額 - 成本 - 費用
+    pub realized: Decimal,
+    pub holding_days: i32,
+}
+
+impl StockLotSale {
+    /// 在交易內新增一筆配對明細，回傳明細的序號
+    pub async fn insert(&self, tx: &mut Transaction<'_, Postgres>) -> Result<i64> {
+        let sql = r#"
+INSERT INTO stock_lot_sales (
+    member_id, security_code, lot_serial, quantity, buy_date, sell_date,
+    sell_price, cost, proceeds, fee, realized, holding_days)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+RETURNING serial;
+"#;
+        sqlx::query_scalar::<_, i64>(sql)
+            .bind(self.member_id)
+            .bind(&self.security_code)
+            .bind(self.lot_serial)
+            .bind(self.quantity)
+            .bind(self.buy_date)
+            .bind(self.sell_date)
+            .bind(self.sell_price)
+            .bind(self.cost)
+            .bind(self.proceeds)
+            .bind(self.fee)
+            .bind(self.realized)
+            .bind(self.holding_days)
+            .fetch_one(&mut **tx)
+            .timed("stock_lot_sales", "insert")
+            .await
+            .context(format!(
+                "Failed to StockLotSale::insert({:?}) from database",
+                self
+            ))
+    }
+
+    /// 取得賣出日期在區間內的配對明細，member_id 為 None 時為全部成員，依賣出日期排序
+    pub async fn fetch_between(
+        member_id: Option<i64>,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<StockLotSale>> {
+        let sql = r#"
+SELECT
+    serial, member_id, security_code, lot_serial, quantity, buy_date, sell_date,
+    sell_price, cost, proceeds, fee, realized, holding_days
+FROM stock_lot_sales
+WHERE ($1::bigint IS NULL OR member_id = $1)
+  AND sell_date BETWEEN $2 AND $3
+ORDER BY sell_date, serial;
+"#;
+        sqlx::query_as::<_, StockLotSale>(sql)
+            .bind(member_id)
+            .bind(start)
+            .bind(end)
+            .fetch_all(database::get_connection())
+            .timed("stock_lot_sales", "fetch_between")
+            .await
+            .context(format!(
+                "Failed to StockLotSale::fetch_between({:?}, {}, {}) from database",
+                member_id, start, end
+            ))
+    }
+}
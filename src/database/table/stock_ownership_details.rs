@@ -1,9 +1,32 @@
-use anyhow::Result;
-use chrono::{DateTime, Local};
+use std::collections::HashSet;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Local, NaiveDate};
+use futures::{Stream, StreamExt};
 use rust_decimal::Decimal;
 use sqlx::{postgres::PgQueryResult, Postgres, Transaction};
 
-use crate::database;
+use crate::{database, declare::StockSymbol};
+
+/// 取得庫存(未賣出)股票的 SQL
+const HELD_SQL: &str = "
+SELECT
+    serial,
+    member_id,
+    security_code,
+    share_quantity,
+    holding_cost,
+    created_time,
+    share_price_average,
+    is_sold,
+    cumulate_dividends_cash,
+    cumulate_dividends_stock,
+    cumulate_dividends_stock_money,
+    cumulate_dividends_total,
+    stop_loss_price,
+    take_profit_price
+FROM stock_ownership_details
+WHERE is_sold = false";
 
 #[derive(sqlx::Type, sqlx::FromRow, Debug)]
 /// 股票庫存(持股名細) 原表名 stock_ownership_details
@@ -11,7 +34,7 @@ pub struct StockOwnershipDetail {
     /// 序號 原 Id
     pub serial: i64,
     /// 股票代號
-    pub security_code: String,
+    pub security_code: StockSymbol,
     /// 當會員編號
     pub member_id: i64,
     /// 持有股數
@@ -30,9 +53,46 @@ pub struct StockOwnershipDetail {
     pub cumulate_dividends_stock_money: Decimal,
     /// 總計累積股利(元)
     pub cumulate_dividends_total: Decimal,
+    /// 停損價，0 代表未設定
+    pub stop_loss_price: Decimal,
+    /// 停利價，0 代表未設定
+    pub take_profit_price: Decimal,
     pub created_time: DateTime<Local>,
 }
 
+/// 設定了停損或停利價的庫存與收盤價
+#[derive(sqlx::FromRow, Debug, Clone, Default, PartialEq)]
+pub struct ProtectedPosition {
+    pub serial: i64,
+    pub member_id: i64,
+    pub security_code: StockSymbol,
+    pub share_quantity: i64,
+    /// 每股成本
+    pub share_price_average: Decimal,
+    /// 停損價，0 代表未設定
+    pub stop_loss_price: Decimal,
+    /// 停利價，0 代表未設定
+    pub take_profit_price: Decimal,
+    /// 收盤價，盤中檢查時為即時成交價
+    pub closing_price: Decimal,
+    /// 前一個交易日的收盤價
+    pub previous_closing_price: Decimal,
+}
+
+/// 尚未賣完的買進批次，每一筆庫存即為一個批次
+#[derive(sqlx::FromRow, Debug, Clone, Default, PartialEq)]
+pub struct Lot {
+    pub serial: i64,
+    pub member_id: i64,
+    pub security_code: StockSymbol,
+    /// 尚未賣出的股數
+    pub share_quantity: i64,
+    /// 尚未賣出股數的買入成本
+    pub holding_cost: Decimal,
+    /// 買進的交易日期
+    pub date: NaiveDate,
+}
+
 impl StockOwnershipDetail {
     pub fn new() -> Self {
         StockOwnershipDetail {
@@ -47,28 +107,15 @@ impl StockOwnershipDetail {
             cumulate_dividends_stock: Default::default(),
             cumulate_dividends_stock_money: Default::default(),
             cumulate_dividends_total: Default::default(),
+            stop_loss_price: Default::default(),
+            take_profit_price: Default::default(),
             created_time: Default::default(),
         }
     }
 
     /// 取得庫存股票的數據
     pub async fn fetch(security_codes: Option<Vec<String>>) -> Result<Vec<StockOwnershipDetail>> {
-        let base_sql = "
-SELECT
-    serial,
-    member_id,
-    security_code,
-    share_quantity,
-    holding_cost,
-    created_time,
-    share_price_average,
-    is_sold,
-    cumulate_dividends_cash,
-    cumulate_dividends_stock,
-    cumulate_dividends_stock_money,
-    cumulate_dividends_total
-FROM stock_ownership_details
-WHERE is_sold = false";
+        let base_sql = HELD_SQL;
         let (sql, bind_params) = security_codes
             .map(|scs| {
                 let params = scs
@@ -93,6 +140,15 @@ WHERE is_sold = false";
         Ok(rows)
     }
 
+    /// 逐筆取得全部庫存股票的數據，只需要走訪一次時使用，不會一次將全部的庫存載入記憶體
+    pub fn stream_held() -> impl Stream<Item = Result<StockOwnershipDetail>> {
+        sqlx::query_as::<_, StockOwnershipDetail>(HELD_SQL)
+            .fetch(database::get_connection())
+            .map(|detail| {
+                detail.context("Failed to StockOwnershipDetail::stream_held from database")
+            })
+    }
+
     /// 更新指定股票累積的股利
     pub async fn update_cumulate_dividends(
         &self,
@@ -121,6 +177,145 @@ WHERE
 
         Ok(result)
     }
+
+    /// 設定停損價與停利價，None 代表不變更，0 代表取消，serial 為 None 時更新成員(member_id 為 None 時為全部成員)該股票全部的庫存，回傳更新的筆數
+    pub async fn update_protection(
+        serial: Option<i64>,
+        security_code: &str,
+        member_id: Option<i64>,
+        stop_loss_price: Option<Decimal>,
+        take_profit_price: Option<Decimal>,
+    ) -> Result<u64> {
+        let sql = r#"
+UPDATE stock_ownership_details
+SET
+    stop_loss_price = COALESCE($4, stop_loss_price),
+    take_profit_price = COALESCE($5, take_profit_price)
+WHERE is_sold = false
+  AND ($1::bigint IS NULL OR serial = $1)
+  AND ($1::bigint IS NOT NULL OR security_code = $2)
+  AND ($3::bigint IS NULL OR member_id = $3)
+"#;
+        let result = sqlx::query(sql)
+            .bind(serial)
+            .bind(security_code)
+            .bind(member_id)
+            .bind(stop_loss_price)
+            .bind(take_profit_price)
+            .execute(database::get_connection())
+            .await
+            .context(format!(
+                "Failed to StockOwnershipDetail::update_protection({:?}, {}) from database",
+                serial, security_code
+            ))?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// 在交易內自買進批次扣除賣出的股數與分攤的成本，股數歸零時標記為已賣出，
+    /// 批次的股數不足(已被其他交易賣出)時回傳錯誤讓交易復原
+    pub async fn reduce_lot(
+        tx: &mut Transaction<'_, Postgres>,
+        serial: i64,
+        quantity: i64,
+        cost: Decimal,
+    ) -> Result<()> {
+        let sql = r#"
+UPDATE stock_ownership_details
+SET
+    share_quantity = share_quantity - $2,
+    holding_cost = holding_cost - $3,
+    is_sold = share_quantity - $2 = 0
+WHERE serial = $1
+  AND is_sold = false
+  AND share_quantity >= $2
+"#;
+        let result = sqlx::query(sql)
+            .bind(serial)
+            .bind(quantity)
+            .bind(cost)
+            .execute(&mut **tx)
+            .await
+            .context(format!(
+                "Failed to StockOwnershipDetail::reduce_lot({}, {}) from database",
+                serial, quantity
+            ))?;
+        if result.rows_affected() == 0 {
+            return Err(anyhow!(
+                "Failed to StockOwnershipDetail::reduce_lot({}, {}) because the lot does not have enough shares",
+                serial,
+                quantity
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// 取得股票尚未賣完的買進批次，member_id 為 None 時為全部成員，依買進日期、序號排序
+pub async fn fetch_open_lots(security_code: &str, member_id: Option<i64>) -> Result<Vec<Lot>> {
+    let sql = r#"
+SELECT serial, member_id, security_code, share_quantity, holding_cost, date
+FROM stock_ownership_details
+WHERE is_sold = false
+  AND share_quantity > 0
+  AND security_code = $1
+  AND ($2::bigint IS NULL OR member_id = $2)
+ORDER BY date, serial
+"#;
+
+    sqlx::query_as::<_, Lot>(sql)
+        .bind(security_code)
+        .bind(member_id)
+        .fetch_all(database::get_connection())
+        .await
+        .context(format!(
+            "Failed to fetch_open_lots({}, {:?}) from database",
+            security_code, member_id
+        ))
+}
+
+/// 取得設定了停損或停利價的庫存，收盤價取指定日期的收盤數據，當日沒有成交的股票收盤價為 0
+pub async fn fetch_protected(date: NaiveDate) -> Result<Vec<ProtectedPosition>> {
+    let sql = r#"
+SELECT
+    sod.serial,
+    sod.member_id,
+    sod.security_code,
+    sod.share_quantity,
+    sod.share_price_average,
+    sod.stop_loss_price,
+    sod.take_profit_price,
+    COALESCE(dq."ClosingPrice", 0) AS closing_price,
+    COALESCE(dq."ClosingPrice" - dq."Change", 0) AS previous_closing_price
+FROM stock_ownership_details AS sod
+LEFT JOIN "DailyQuotes" AS dq ON dq."SecurityCode" = sod.security_code AND dq."Date" = $1
+WHERE sod.is_sold = false
+  AND (sod.stop_loss_price > 0 OR sod.take_profit_price > 0)
+ORDER BY sod.member_id, sod.security_code, sod.serial
+"#;
+
+    sqlx::query_as::<_, ProtectedPosition>(sql)
+        .bind(date)
+        .fetch_all(database::get_connection())
+        .await
+        .context(format!("Failed to fetch_protected({}) from database", date))
+}
+
+/// 取得庫存中(未賣出)與 trace 表內追踪中的股票代號
+pub async fn fetch_held_or_traced_symbols() -> Result<HashSet<StockSymbol>> {
+    let sql = r#"
+SELECT security_code FROM stock_ownership_details WHERE is_sold = false
+UNION
+SELECT stock_symbol FROM trace
+"#;
+
+    let symbols: Vec<StockSymbol> = sqlx::query_scalar(sql)
+        .fetch_all(database::get_connection())
+        .await
+        .context("Failed to fetch_held_or_traced_symbols from database")?;
+
+    Ok(symbols.into_iter().collect())
 }
 
 impl Default for StockOwnershipDetail {
@@ -133,7 +328,7 @@ impl Clone for StockOwnershipDetail {
     fn clone(&self) -> Self {
         StockOwnershipDetail {
             serial: self.serial,
-            security_code: self.security_code.to_string(),
+            security_code: self.security_code.clone(),
             member_id: self.member_id,
             share_quantity: self.share_quantity,
             share_price_average: self.share_price_average,
@@ -143,6 +338,8 @@ impl Clone for StockOwnershipDetail {
             cumulate_dividends_stock: self.cumulate_dividends_stock,
             cumulate_dividends_stock_money: self.cumulate_dividends_stock_money,
             cumulate_dividends_total: self.cumulate_dividends_total,
+            stop_loss_price: self.stop_loss_price,
+            take_profit_price: self.take_profit_price,
             created_time: self.created_time,
         }
     }
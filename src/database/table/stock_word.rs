@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use chrono::{DateTime, Local};
 use sqlx::{postgres::PgRow, QueryBuilder, Row};
 
@@ -41,12 +41,18 @@ ON CONFLICT (word) DO UPDATE SET
     updated_time = EXCLUDED.updated_time
 RETURNING word_id";
 
-        let row = sqlx::query(sql)
-            .bind(&self.word)
-            .bind(self.created_time)
-            .bind(self.updated_time)
-            .fetch_one(database::get_connection())
-            .await?;
+        let row = database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(&self.word)
+                .bind(self.created_time)
+                .bind(self.updated_time)
+                .fetch_one(database::get_connection())
+        })
+        .await
+        .context(format!(
+            "Failed to StockWord::upsert({:?}) from database",
+            self
+        ))?;
 
         self.word_id = row.try_get("word_id")?;
 
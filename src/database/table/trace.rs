@@ -2,17 +2,17 @@ use anyhow::{Context, Result};
 use rust_decimal::Decimal;
 use sqlx::{postgres::PgRow, QueryBuilder, Row};
 
-use crate::{database, util::map::Keyable};
+use crate::{database, declare::StockSymbol, util::map::Keyable};
 
 #[derive(sqlx::Type, sqlx::FromRow, Debug)]
 pub struct Trace {
-    pub stock_symbol: String,
+    pub stock_symbol: StockSymbol,
     pub floor: Decimal,
     pub ceiling: Decimal,
 }
 
 impl Trace {
-    pub fn new(stock_symbol: String, floor: Decimal, ceiling: Decimal) -> Self {
+    pub fn new(stock_symbol: StockSymbol, floor: Decimal, ceiling: Decimal) -> Self {
         Trace {
             stock_symbol,
             floor,
@@ -0,0 +1,148 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use sqlx::FromRow;
+
+use crate::{
+    database::{self, timing::Timed},
+    declare::StockSymbol,
+};
+
+/// 標記庫存批次 stock_ownership_details
+pub const LOT: &str = "lot";
+/// 標記賣出明細 stock_lot_sales
+pub const SALE: &str = "sale";
+
+const SELECT_SQL: &str = "
+SELECT serial, target, target_serial, member_id, security_code, tag, note, created_time::date AS created_date
+FROM trade_notes";
+
+/// 庫存批次或賣出明細的交易筆記與標籤 原表名 trade_notes
+#[derive(FromRow, Debug, Clone, Default, PartialEq)]
+pub struct TradeNote {
+    pub serial: i64,
+    /// lot 或 sale
+    pub target: String,
+    /// 標記對象的序號
+    pub target_serial: i64,
+    pub member_id: i64,
+    pub security_code: StockSymbol,
+    /// 標籤，空字串代表沒有標籤
+    pub tag: String,
+    pub note: String,
+    pub created_date: NaiveDate,
+}
+
+impl TradeNote {
+    pub fn new(target: &str, target_serial: i64, tag: String, note: String) -> Self {
+        TradeNote {
+            target: target.to_string(),
+            target_serial,
+            tag,
+            note,
+            ..Default::default()
+        }
+    }
+
+    /// 新增筆記，成員與股票代號取自標記的對象，對象不存在(或不屬於指定的成員)時回傳 None
+    pub async fn insert(&self, member_id: Option<i64>) -> Result<Option<TradeNote>> {
+        let sql = r#"
+INSERT INTO trade_notes (target, target_serial, member_id, security_code, tag, note)
+SELECT $1, $2, member_id, security_code, $3, $4
+FROM (
+    SELECT member_id, security_code FROM stock_ownership_details WHERE $1 = 'lot' AND serial = $2
+    UNION ALL
+    SELECT member_id, security_code FROM stock_lot_sales WHERE $1 = 'sale' AND serial = $2
+) AS t
+WHERE $5::bigint IS NULL OR member_id = $5
+RETURNING serial, target, target_serial, member_id, security_code, tag, note, created_time::date AS created_date;
+"#;
+        sqlx::query_as::<_, TradeNote>(sql)
+            .bind(&self.target)
+            .bind(self.target_serial)
+            .bind(&self.tag)
+            .bind(&self.note)
+            .bind(member_id)
+            .fetch_optional(database::get_connection())
+            .timed("trade_notes", "insert")
+            .await
+            .context(format!(
+                "Failed to TradeNote::insert({:?}) from database",
+                self
+            ))
+    }
+
+    /// 刪除筆記，member_id 不為 None 時只能刪除該成員的筆記，回傳是否有刪除
+    pub async fn delete(serial: i64, member_id: Option<i64>) -> Result<bool> {
+        let sql = r#"
+DELETE FROM trade_notes
+WHERE serial = $1 AND ($2::bigint IS NULL OR member_id = $2);
+"#;
+        sqlx::query(sql)
+            .bind(serial)
+            .bind(member_id)
+            .execute(database::get_connection())
+            .timed("trade_notes", "delete")
+            .await
+            .map(|result| result.rows_affected() > 0)
+            .context(format!(
+                "Failed to TradeNote::delete({}, {:?}) from database",
+                serial, member_id
+            ))
+    }
+
+    /// 取得最近的筆記，member_id、tag 為 None 時不篩選，依序號由新到舊排序
+    pub async fn fetch_recent(
+        member_id: Option<i64>,
+        tag: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<TradeNote>> {
+        let sql = format!(
+            "{} WHERE ($1::bigint IS NULL OR member_id = $1) AND ($2::varchar IS NULL OR tag = $2) ORDER BY serial DESC LIMIT $3;",
+            SELECT_SQL
+        );
+        sqlx::query_as::<_, TradeNote>(&sql)
+            .bind(member_id)
+            .bind(tag)
+            .bind(limit)
+            .fetch_all(database::get_connection())
+            .timed("trade_notes", "fetch_recent")
+            .await
+            .context(format!(
+                "Failed to TradeNote::fetch_recent({:?}, {:?}) from database",
+                member_id, tag
+            ))
+    }
+
+    /// 取得區間內新增的筆記，依序號排序
+    pub async fn fetch_created_between(start: NaiveDate, end: NaiveDate) -> Result<Vec<TradeNote>> {
+        let sql = format!(
+            "{} WHERE created_time::date BETWEEN $1 AND $2 ORDER BY serial;",
+            SELECT_SQL
+        );
+        sqlx::query_as::<_, TradeNote>(&sql)
+            .bind(start)
+            .bind(end)
+            .fetch_all(database::get_connection())
+            .timed("trade_notes", "fetch_created_between")
+            .await
+            .context(format!(
+                "Failed to TradeNote::fetch_created_between({}, {}) from database",
+                start, end
+            ))
+    }
+
+    /// 取得標記在指定庫存批次或賣出明細上的筆記
+    pub async fn fetch_for(lot_serials: &[i64], sale_serials: &[i64]) -> Result<Vec<TradeNote>> {
+        let sql = format!(
+            "{} WHERE (target = 'lot' AND target_serial = ANY($1)) OR (target = 'sale' AND target_serial = ANY($2)) ORDER BY serial;",
+            SELECT_SQL
+        );
+        sqlx::query_as::<_, TradeNote>(&sql)
+            .bind(lot_serials)
+            .bind(sale_serials)
+            .fetch_all(database::get_connection())
+            .timed("trade_notes", "fetch_for")
+            .await
+            .context("Failed to TradeNote::fetch_for from database")
+    }
+}
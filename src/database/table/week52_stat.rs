@@ -0,0 +1,160 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use sqlx::{postgres::PgQueryResult, FromRow};
+
+use crate::{
+    database::{self, timing::Timed},
+    declare::StockSymbol,
+};
+
+/// 個股的 52 週高低點與回檔幅度 原表名 week52_stats
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct Week52Stat {
+    pub security_code: StockSymbol,
+    pub name: String,
+    pub date: NaiveDate,
+    pub closing_price: Decimal,
+    /// 52 週最高價
+    pub high_price: Decimal,
+    pub high_date: NaiveDate,
+    /// 52 週最低價
+    pub low_price: Decimal,
+    pub low_date: NaiveDate,
+    /// 收盤價距 52 週最高價(%)
+    pub distance_from_high: Decimal,
+    /// 收盤價距 52 週最低價(%)
+    pub distance_from_low: Decimal,
+    /// 歷史最高收盤價
+    pub peak_price: Decimal,
+    /// 收盤價自歷史最高收盤價的回檔幅度(%)
+    pub drawdown: Decimal,
+}
+
+const SELECT_SQL: &str = r#"
+SELECT
+    w.security_code,
+    s."Name" AS name,
+    w.date,
+    w.closing_price,
+    w.high_price,
+    w.high_date,
+    w.low_price,
+    w.low_date,
+    w.distance_from_high,
+    w.distance_from_low,
+    w.peak_price,
+    w.drawdown
+FROM week52_stats AS w
+INNER JOIN stocks AS s ON s.stock_symbol = w.security_code
+"#;
+
+impl Week52Stat {
+    /// 依指定日期有交易的股票重新計算 52 週高低點、距高低點的幅度與自歷史最高收盤價的回檔幅度
+    pub async fn upsert(date: NaiveDate) -> Result<PgQueryResult> {
+        let sql = r#"
+WITH traded AS (
+    SELECT "SecurityCode" AS security_code, "ClosingPrice" AS closing_price
+    FROM "DailyQuotes"
+    WHERE "Date" = $1 AND "ClosingPrice" > 0
+),
+highs AS (
+    SELECT DISTINCT ON ("SecurityCode") "SecurityCode" AS security_code, "HighestPrice" AS high_price, "Date" AS high_date
+    FROM "DailyQuotes"
+    WHERE "Date" > $1 - 365 AND "Date" <= $1 AND "HighestPrice" > 0
+    ORDER BY "SecurityCode", "HighestPrice" DESC, "Date" DESC
+),
+lows AS (
+    SELECT DISTINCT ON ("SecurityCode") "SecurityCode" AS security_code, "LowestPrice" AS low_price, "Date" AS low_date
+    FROM "DailyQuotes"
+    WHERE "Date" > $1 - 365 AND "Date" <= $1 AND "LowestPrice" > 0
+    ORDER BY "SecurityCode", "LowestPrice", "Date" DESC
+),
+peaks AS (
+    SELECT "SecurityCode" AS security_code, MAX("ClosingPrice") AS peak_price
+    FROM "DailyQuotes"
+    WHERE "Date" <= $1 AND "SecurityCode" IN (SELECT security_code FROM traded)
+    GROUP BY "SecurityCode"
+)
+INSERT INTO week52_stats (
+    security_code, date, closing_price, high_price, high_date, low_price, low_date,
+    distance_from_high, distance_from_low, peak_price, drawdown
+)
+SELECT
+    t.security_code,
+    $1,
+    t.closing_price,
+    h.high_price,
+    h.high_date,
+    l.low_price,
+    l.low_date,
+    ROUND((t.closing_price - h.high_price) / h.high_price * 100, 4),
+    ROUND((t.closing_price - l.low_price) / l.low_price * 100, 4),
+    p.peak_price,
+    ROUND((t.closing_price - p.peak_price) / p.peak_price * 100, 4)
+FROM traded AS t
+INNER JOIN highs AS h ON h.security_code = t.security_code
+INNER JOIN lows AS l ON l.security_code = t.security_code
+INNER JOIN peaks AS p ON p.security_code = t.security_code
+ON CONFLICT (security_code) DO UPDATE SET
+    date = EXCLUDED.date,
+    closing_price = EXCLUDED.closing_price,
+    high_price = EXCLUDED.high_price,
+    high_date = EXCLUDED.high_date,
+    low_price = EXCLUDED.low_price,
+    low_date = EXCLUDED.low_date,
+    distance_from_high = EXCLUDED.distance_from_high,
+    distance_from_low = EXCLUDED.distance_from_low,
+    peak_price = EXCLUDED.peak_price,
+    drawdown = EXCLUDED.drawdown,
+    updated_time = now();
+"#;
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(date)
+                .execute(database::get_connection())
+        })
+        .timed("week52_stats", "upsert")
+        .await
+        .context(format!(
+            "Failed to Week52Stat::upsert({}) from database",
+            date
+        ))
+    }
+
+    /// 取得股票最近一個交易日的統計
+    pub async fn fetch(security_code: &str) -> Result<Option<Self>> {
+        let sql = format!("{} WHERE w.security_code = $1", SELECT_SQL);
+        sqlx::query_as::<_, Self>(&sql)
+            .bind(security_code)
+            .fetch_optional(database::get_connection())
+            .timed("week52_stats", "fetch")
+            .await
+            .context(format!(
+                "Failed to Week52Stat::fetch({}) from database",
+                security_code
+            ))
+    }
+
+    /// 篩選收盤價距 52 週最高價在 within 個百分點以內的股票，依距離由近到遠排序
+    pub async fn fetch_near_high(within: Decimal, limit: i64) -> Result<Vec<Self>> {
+        let sql = format!(
+            r#"{}
+WHERE w.distance_from_high >= -$1 AND w.date = (SELECT MAX(date) FROM week52_stats)
+ORDER BY w.distance_from_high DESC, w.security_code
+LIMIT $2
+"#,
+            SELECT_SQL
+        );
+        sqlx::query_as::<_, Self>(&sql)
+            .bind(within)
+            .bind(limit)
+            .fetch_all(database::get_connection())
+            .timed("week52_stats", "fetch_near_high")
+            .await
+            .context(format!(
+                "Failed to Week52Stat::fetch_near_high({}, {}) from database",
+                within, limit
+            ))
+    }
+}
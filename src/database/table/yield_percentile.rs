@@ -0,0 +1,141 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use sqlx::{postgres::PgQueryResult, FromRow};
+
+use crate::{
+    database::{self, timing::Timed},
+    declare::StockSymbol,
+};
+
+/// 計算百分位時回顧的年數
+pub const YEARS: i32 = 5;
+
+/// 殖利率筆數少於此數(約一年的交易日)時不計算，避免上市不久的股票百分位失真
+const MIN_SAMPLE_DAYS: i64 = 240;
+
+/// 個股當日殖利率在自己近 5 年殖利率分佈中的百分位 原表名 yield_percentiles
+#[derive(FromRow, Debug, Clone, Default, PartialEq)]
+pub struct YieldPercentile {
+    pub date: NaiveDate,
+    pub security_code: StockSymbol,
+    /// 當日殖利率(%)
+    pub dividend_yield: Decimal,
+    /// 近 5 年殖利率小於等於當日殖利率的比例(%)，越高代表殖利率相對歷史越高
+    pub percentile: Decimal,
+    /// 近 5 年殖利率的 20% 百分位數
+    pub yield_p20: Decimal,
+    /// 近 5 年殖利率的中位數
+    pub yield_p50: Decimal,
+    /// 近 5 年殖利率的 80% 百分位數
+    pub yield_p80: Decimal,
+    /// 計算時使用的殖利率筆數
+    pub sample_days: i32,
+}
+
+impl YieldPercentile {
+    /// 依 daily_valuation 計算指定日期有殖利率的股票在近 YEARS 年(含當日)殖利率大於 0 的分佈中的百分位
+    pub async fn upsert(date: NaiveDate) -> Result<PgQueryResult> {
+        let sql = r#"
+INSERT INTO yield_percentiles (
+    date, security_code, dividend_yield, percentile, yield_p20, yield_p50, yield_p80, sample_days
+)
+SELECT
+    today.date,
+    today.security_code,
+    today.dividend_yield,
+    ROUND(100.0 * COUNT(*) FILTER (WHERE history.dividend_yield <= today.dividend_yield) / COUNT(*), 2),
+    PERCENTILE_CONT(0.2) WITHIN GROUP (ORDER BY history.dividend_yield),
+    PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY history.dividend_yield),
+    PERCENTILE_CONT(0.8) WITHIN GROUP (ORDER BY history.dividend_yield),
+    COUNT(*)
+FROM daily_valuation AS today
+INNER JOIN daily_valuation AS history ON history.security_code = today.security_code
+WHERE today.date = $1
+  AND today.dividend_yield > 0
+  AND history.date > $1 - make_interval(years => $2)
+  AND history.date <= $1
+  AND history.dividend_yield > 0
+GROUP BY today.date, today.security_code, today.dividend_yield
+HAVING COUNT(*) >= $3
+ON CONFLICT (date, security_code) DO UPDATE SET
+    dividend_yield = EXCLUDED.dividend_yield,
+    percentile = EXCLUDED.percentile,
+    yield_p20 = EXCLUDED.yield_p20,
+    yield_p50 = EXCLUDED.yield_p50,
+    yield_p80 = EXCLUDED.yield_p80,
+    sample_days = EXCLUDED.sample_days,
+    updated_time = now();
+"#;
+        database::with_retry(|| {
+            sqlx::query(sql)
+                .bind(date)
+                .bind(YEARS)
+                .bind(MIN_SAMPLE_DAYS)
+                .execute(database::get_connection())
+        })
+        .timed("yield_percentiles", "upsert")
+        .await
+        .context(format!(
+            "Failed to YieldPercentile::upsert({}) from database",
+            date
+        ))
+    }
+
+    /// 取得指定股票最近一筆的百分位
+    pub async fn fetch_latest(security_code: &str) -> Result<Option<YieldPercentile>> {
+        let sql = r#"
+SELECT date, security_code, dividend_yield, percentile, yield_p20, yield_p50, yield_p80, sample_days
+FROM yield_percentiles
+WHERE security_code = $1
+ORDER BY date DESC
+LIMIT 1;
+"#;
+        sqlx::query_as::<_, YieldPercentile>(sql)
+            .bind(security_code)
+            .fetch_optional(database::get_connection())
+            .timed("yield_percentiles", "fetch_latest")
+            .await
+            .context(format!(
+                "Failed to YieldPercentile::fetch_latest({}) from database",
+                security_code
+            ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logging;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_upsert() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 YieldPercentile::upsert".to_string());
+        let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+
+        match YieldPercentile::upsert(date).await {
+            Ok(result) => logging::debug_file_async(format!(
+                "YieldPercentile::upsert rows:{}",
+                result.rows_affected()
+            )),
+            Err(why) => logging::debug_file_async(format!(
+                "Failed to YieldPercentile::upsert because {:?}",
+                why
+            )),
+        }
+
+        match YieldPercentile::fetch_latest("2330").await {
+            Ok(percentile) => logging::debug_file_async(format!("{:?}", percentile)),
+            Err(why) => logging::debug_file_async(format!(
+                "Failed to YieldPercentile::fetch_latest because {:?}",
+                why
+            )),
+        }
+
+        logging::debug_file_async("結束 YieldPercentile::upsert".to_string());
+    }
+}
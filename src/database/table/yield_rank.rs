@@ -1,12 +1,15 @@
-use anyhow::{anyhow, Context, Result};
+use anyhow::{Context, Result};
 use chrono::{Datelike, NaiveDate, TimeDelta};
 use sqlx::postgres::PgQueryResult;
 
-use crate::database;
+use crate::{
+    database::{self, table::stock, timing::Timed},
+    declare::StockSymbol,
+};
 
 #[derive(sqlx::FromRow, Debug, Default)]
 pub struct YieldRank {
-    pub security_code: String,
+    pub security_code: StockSymbol,
     pub daily_quotes_serial: i64,
     pub dividend: f64,
     pub closing_price: f64,
@@ -14,11 +17,8 @@ pub struct YieldRank {
 }
 
 impl YieldRank {
+    /// 計算指定日期的殖利率排名，交易由本函式建立，序列化失敗或死結時整個交易重新執行
     pub async fn upsert(date: NaiveDate) -> Result<PgQueryResult> {
-        let mut tx = database::get_tx()
-            .await
-            .context("Failed to get_tx in yield_rank")?;
-
         let month_ago = date - TimeDelta::try_days(30).unwrap();
         let sql = format!(
             r#"
@@ -76,23 +76,62 @@ ON CONFLICT (date, security_code) DO UPDATE SET
             date
         );
 
-        match sqlx::query(&sql)
-            .bind(date.year()-1)
+        database::with_retry(|| async {
+            let mut tx = database::get_connection().begin().await?;
+            let pg = sqlx::query(&sql)
+                .bind(date.year() - 1)
+                .bind(date)
+                .bind(month_ago)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            Ok(pg)
+        })
+        .timed("yield_rank", "upsert")
+        .await
+        .context("Failed to YieldRank::upsert from database")
+    }
+
+    /// 取得指定日期殖利率最高的股票(預設不含權證)，有指定產業分類(stock_industry)時只在該產業內排名
+    pub async fn fetch_top(
+        date: NaiveDate,
+        industry_id: Option<i32>,
+        limit: i64,
+    ) -> Result<Vec<YieldRank>> {
+        let sql = r#"
+SELECT
+    yr.security_code,
+    yr.daily_quotes_serial,
+    d."sum"::float8 AS dividend,
+    dq."ClosingPrice"::float8 AS closing_price,
+    yr.yield::float8 AS yield
+FROM
+    yield_rank AS yr
+    INNER JOIN stocks AS s ON s.stock_symbol = yr.security_code
+    INNER JOIN dividend AS d ON d.serial = yr.dividend_serial
+    INNER JOIN "DailyQuotes" AS dq ON dq."Serial" = yr.daily_quotes_serial
+WHERE
+    yr.date = $1
+    AND s."SuspendListing" = false
+    AND ($2::int IS NULL OR s.stock_industry_id = $2)
+    AND s.security_type <> ALL($4)
+ORDER BY
+    yr.yield DESC
+LIMIT $3
+"#;
+
+        sqlx::query_as::<_, YieldRank>(sql)
             .bind(date)
-            .bind(month_ago)
-            .execute(&mut *tx)
+            .bind(industry_id)
+            .bind(limit)
+            .bind(stock::excluded_security_types())
+            .fetch_all(database::get_connection())
+            .timed("yield_rank", "fetch_top")
             .await
-            .context("Failed to YieldRank::upsert from database")
-        {
-            Ok(pg) => {
-                tx.commit().await?;
-                Ok(pg)
-            }
-            Err(why) => {
-                tx.rollback().await?;
-                Err(anyhow!("{:?}", why))
-            }
-        }
+            .context(format!(
+                "Failed to YieldRank::fetch_top({}, {:?}) from database",
+                date, industry_id
+            ))
     }
 }
 
@@ -119,4 +158,23 @@ mod tests {
 
         logging::debug_file_async("結束 YieldRank::upsert".to_string());
     }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_fetch_top() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 YieldRank::fetch_top".to_string());
+        let current_date = Local::now().date_naive();
+        match YieldRank::fetch_top(current_date, Some(24), 10).await {
+            Ok(ranks) => logging::debug_file_async(format!("YieldRank::fetch_top:{:#?}", ranks)),
+            Err(why) => {
+                logging::debug_file_async(format!(
+                    "Failed to YieldRank::fetch_top because {:?}",
+                    why
+                ));
+            }
+        }
+
+        logging::debug_file_async("結束 YieldRank::fetch_top".to_string());
+    }
 }
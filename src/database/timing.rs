@@ -0,0 +1,172 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use futures::future::BoxFuture;
+
+use crate::{config::SETTINGS, logging, telemetry};
+
+/// 各資料表累計的查詢耗時，key 為資料表名稱
+static LATENCIES: Mutex<Option<HashMap<String, Latency>>> = Mutex::new(None);
+
+/// 資料表的查詢次數與耗時
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Latency {
+    pub count: u64,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+impl Latency {
+    fn record(&mut self, elapsed: Duration) {
+        self.count += 1;
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+    }
+
+    /// 平均耗時
+    pub fn average(&self) -> Duration {
+        if self.count == 0 {
+            return Duration::ZERO;
+        }
+
+        self.total / self.count as u32
+    }
+}
+
+/// 執行查詢並記錄耗時，超過設定檔 postgresql.slow_query_ms 時記錄為慢查詢
+/// ex. `timing::timed("DailyQuotes", "upsert", sqlx::query(sql).execute(pool)).await`
+pub async fn timed<T>(table: &str, operation: &str, query: impl Future<Output = T>) -> T {
+    let start = Instant::now();
+    let result = telemetry::span(format!("db {}::{}", table, operation), query).await;
+    let elapsed = start.elapsed();
+
+    record(table, elapsed);
+
+    let threshold = SETTINGS.load().postgresql.slow_query_ms;
+    if threshold > 0 && elapsed >= Duration::from_millis(threshold) {
+        logging::warn_file_async(format!(
+            "Slow query {}::{} took {} ms",
+            table,
+            operation,
+            elapsed.as_millis()
+        ));
+    }
+
+    result
+}
+
+/// 讓查詢的 future 可以直接串接 `.timed(資料表, 操作)` 記錄耗時
+/// ex. `sqlx::query(sql).execute(pool).timed("DailyQuotes", "upsert").await`
+pub trait Timed: Future + Sized + Send {
+    fn timed<'a>(self, table: &'a str, operation: &'a str) -> BoxFuture<'a, Self::Output>
+    where
+        Self: 'a,
+        Self::Output: Send,
+    {
+        Box::pin(timed(table, operation, self))
+    }
+}
+
+impl<F: Future + Send> Timed for F {}
+
+fn record(table: &str, elapsed: Duration) {
+    if let Ok(mut latencies) = LATENCIES.lock() {
+        latencies
+            .get_or_insert_with(HashMap::new)
+            .entry(table.to_string())
+            .or_default()
+            .record(elapsed);
+    }
+}
+
+/// 取出目前累計的耗時後歸零，依總耗時由多到少排序
+pub fn take() -> Vec<(String, Latency)> {
+    let latencies = match LATENCIES.lock() {
+        Ok(mut latencies) => latencies.take().unwrap_or_default(),
+        Err(_) => return Vec::new(),
+    };
+
+    let mut list: Vec<(String, Latency)> = latencies.into_iter().collect();
+    list.sort_by(|a, b| b.1.total.cmp(&a.1.total).then_with(|| a.0.cmp(&b.0)));
+
+    list
+}
+
+/// 將累計的各資料表耗時寫入日誌後歸零
+pub fn log_summary(title: &str) {
+    let latencies = take();
+    if latencies.is_empty() {
+        return;
+    }
+
+    logging::info_file_async(summary(title, &latencies));
+}
+
+fn summary(title: &str, latencies: &[(String, Latency)]) -> String {
+    let mut msg = format!("{} 資料表查詢耗時:", title);
+    for (table, latency) in latencies {
+        let _ = write!(
+            &mut msg,
+            "\n    {} count:{} total:{}ms avg:{}ms max:{}ms",
+            table,
+            latency.count,
+            latency.total.as_millis(),
+            latency.average().as_millis(),
+            latency.max.as_millis()
+        );
+    }
+
+    msg
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_latency() {
+        let mut latency = Latency::default();
+        assert_eq!(latency.average(), Duration::ZERO);
+
+        latency.record(Duration::from_millis(10));
+        latency.record(Duration::from_millis(30));
+
+        assert_eq!(latency.count, 2);
+        assert_eq!(latency.total, Duration::from_millis(40));
+        assert_eq!(latency.max, Duration::from_millis(30));
+        assert_eq!(latency.average(), Duration::from_millis(20));
+    }
+
+    #[test]
+    fn test_summary() {
+        let latencies = vec![
+            (
+                "DailyQuotes".to_string(),
+                Latency {
+                    count: 2,
+                    total: Duration::from_millis(1500),
+                    max: Duration::from_millis(1200),
+                },
+            ),
+            (
+                "yield_rank".to_string(),
+                Latency {
+                    count: 1,
+                    total: Duration::from_millis(80),
+                    max: Duration::from_millis(80),
+                },
+            ),
+        ];
+
+        assert_eq!(
+            summary("收盤", &latencies),
+            "收盤 資料表查詢耗時:\n    DailyQuotes count:2 total:1500ms avg:750ms max:1200ms\n    yield_rank count:1 total:80ms avg:80ms max:80ms"
+        );
+    }
+}
@@ -1,5 +1,14 @@
+use std::{borrow::Borrow, fmt, ops::Deref, str::FromStr};
+
+use anyhow::anyhow;
 use chrono::{Local, NaiveTime};
 use serde_derive::{Deserialize, Serialize};
+use sqlx::{
+    encode::IsNull,
+    error::BoxDynError,
+    postgres::{PgArgumentBuffer, PgHasArrayType, PgTypeInfo, PgValueRef},
+    Postgres,
+};
 use strum_macros::{Display, EnumString};
 
 #[derive(
@@ -183,6 +192,113 @@ impl StockExchangeMarket {
     }
 }
 
+/// 證券類別，由國際證券識別碼所屬的分類與 CFI 代碼判斷
+#[derive(PartialEq, Debug, Copy, Clone, Display, EnumString)]
+#[repr(i32)]
+pub enum SecurityType {
+    /// 未分類 0
+    #[strum(serialize = "未分類")]
+    Unclassified = 0,
+    /// 普通股 1
+    #[strum(serialize = "普通股")]
+    Common = 1,
+    /// 特別股 2
+    #[strum(serialize = "特別股")]
+    Preferred = 2,
+    /// 臺灣存託憑證 3
+    #[strum(serialize = "臺灣存託憑證")]
+    Tdr = 3,
+    /// 指數股票型基金 4
+    #[strum(serialize = "ETF")]
+    Etf = 4,
+    /// 認購(售)權證 5
+    #[strum(serialize = "權證")]
+    Warrant = 5,
+}
+
+impl SecurityType {
+    /// 返回類別的序列號
+    pub fn serial(&self) -> i32 {
+        *self as i32
+    }
+
+    /// 根據序列號返回對應的類別
+    pub fn from(serial: i32) -> SecurityType {
+        match serial {
+            1 => SecurityType::Common,
+            2 => SecurityType::Preferred,
+            3 => SecurityType::Tdr,
+            4 => SecurityType::Etf,
+            5 => SecurityType::Warrant,
+            _ => SecurityType::Unclassified,
+        }
+    }
+
+    /// 依 CFI 代碼的前兩碼判斷類別，CFI 代碼缺漏時改用國際證券識別碼網頁上的分類名稱
+    ///
+    /// ex. ESVUFR 普通股、EPNRAR 特別股、EDSDDR 存託憑證、CEOGEU ETF、RWSCCA 權證
+    pub fn classify(category: &str, cfi_code: &str) -> SecurityType {
+        match cfi_code.get(..2) {
+            Some("ES") => return SecurityType::Common,
+            Some("EP") => return SecurityType::Preferred,
+            Some("ED") => return SecurityType::Tdr,
+            Some("CE") => return SecurityType::Etf,
+            Some("RW") => return SecurityType::Warrant,
+            _ => {}
+        }
+
+        if category.contains("權證") {
+            SecurityType::Warrant
+        } else if category.contains("存託憑證") {
+            SecurityType::Tdr
+        } else if category.contains("ETF") {
+            SecurityType::Etf
+        } else if category.contains("特別股") {
+            SecurityType::Preferred
+        } else if category == "股票" || category == "普通股" {
+            SecurityType::Common
+        } else {
+            SecurityType::Unclassified
+        }
+    }
+}
+
+/// 財報類別，母公司有子公司時以合併報表為準，沒有子公司的公司只會公告個別報表
+#[derive(PartialEq, Debug, Copy, Clone, Display, EnumString)]
+#[repr(i32)]
+pub enum StatementType {
+    /// 合併財報 1
+    #[strum(serialize = "合併")]
+    Consolidated = 1,
+    /// 個別財報 2
+    #[strum(serialize = "個別")]
+    Individual = 2,
+}
+
+impl StatementType {
+    /// 返回類別的序列號，數值越小越優先採用
+    pub fn serial(&self) -> i32 {
+        *self as i32
+    }
+
+    /// 根據序列號返回對應的類別，未知的序列號視為合併財報
+    pub fn from(serial: i32) -> StatementType {
+        match serial {
+            2 => StatementType::Individual,
+            _ => StatementType::Consolidated,
+        }
+    }
+
+    /// 依報表標題判斷類別 ex. 個別財務報告、合併財務報告，標題沒有註明時視為合併財報
+    pub fn detect(title: &str) -> StatementType {
+        if title.contains("個別") || title.contains("個體") {
+            StatementType::Individual
+        } else {
+            StatementType::Consolidated
+        }
+    }
+}
+
 /// 產業分類
 #[derive(PartialEq, Debug, Copy, Clone, Display, EnumString)]
 #[repr(i32)]
@@ -360,10 +476,145 @@ impl Industry {
     }
 }
 
+/// 股票代號，由 4~6 碼數字加上選擇性的一碼英文字尾組成 ex. 2330、00632R、2881A
+///
+/// 透過 `parse`、`TryFrom` 或反序列化建立時會檢查格式；從資料庫讀出的值視為已驗證，不再檢查
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(transparent)]
+pub struct StockSymbol(String);
+
+impl StockSymbol {
+    /// 檢查代號格式，英文字尾會轉成大寫
+    pub fn parse(symbol: &str) -> anyhow::Result<Self> {
+        let symbol = symbol.trim().to_uppercase();
+        if Self::is_valid(&symbol) {
+            Ok(StockSymbol(symbol))
+        } else {
+            Err(anyhow!("invalid stock symbol: {:?}", symbol))
+        }
+    }
+
+    /// 是否為合法的股票代號格式
+    pub fn is_valid(symbol: &str) -> bool {
+        let digits = symbol.bytes().take_while(u8::is_ascii_digit).count();
+        let suffix = &symbol.as_bytes()[digits..];
+
+        (4..=6).contains(&digits)
+            && (suffix.is_empty() || (suffix.len() == 1 && suffix[0].is_ascii_uppercase()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for StockSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Deref for StockSymbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for StockSymbol {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for StockSymbol {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for StockSymbol {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for StockSymbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl FromStr for StockSymbol {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<&str> for StockSymbol {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::parse(value)
+    }
+}
+
+impl TryFrom<String> for StockSymbol {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<StockSymbol> for String {
+    fn from(value: StockSymbol) -> Self {
+        value.0
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for StockSymbol {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let symbol = <String as serde::Deserialize>::deserialize(deserializer)?;
+        Self::parse(&symbol).map_err(serde::de::Error::custom)
+    }
+}
+
+impl sqlx::Type<Postgres> for StockSymbol {
+    fn type_info() -> PgTypeInfo {
+        <String as sqlx::Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <String as sqlx::Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl PgHasArrayType for StockSymbol {
+    fn array_type_info() -> PgTypeInfo {
+        <String as PgHasArrayType>::array_type_info()
+    }
+}
+
+impl sqlx::Encode<'_, Postgres> for StockSymbol {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        <&str as sqlx::Encode<Postgres>>::encode(self.0.as_str(), buf)
+    }
+}
+
+impl<'r> sqlx::Decode<'r, Postgres> for StockSymbol {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        Ok(StockSymbol(<String as sqlx::Decode<Postgres>>::decode(value)?))
+    }
+}
+
 /// 股票報價
 #[derive(Debug)]
 pub struct StockQuotes {
-    pub stock_symbol: String,
+    pub stock_symbol: StockSymbol,
     pub price: f64,
     /// 漲跌
     pub change: f64,
@@ -379,7 +630,73 @@ pub const ONE_DAYS_IN_SECONDS: usize = 60 * 60 * 24;
 #[cfg(test)]
 mod tests {
     use super::*;
-    
+
+    #[test]
+    fn test_stock_symbol() {
+        assert_eq!(StockSymbol::parse("2330").unwrap(), "2330");
+        assert_eq!(StockSymbol::parse(" 00632r ").unwrap().to_string(), "00632R");
+        assert_eq!("2881A".parse::<StockSymbol>().unwrap().as_str(), "2881A");
+        assert!(StockSymbol::parse("233").is_err());
+        assert!(StockSymbol::parse("1234567").is_err());
+        assert!(StockSymbol::parse("2330AB").is_err());
+        assert!(StockSymbol::parse("A2330").is_err());
+        assert!(StockSymbol::parse("").is_err());
+
+        let symbol: StockSymbol = serde_json::from_str(r#""006208""#).unwrap();
+        assert_eq!(serde_json::to_string(&symbol).unwrap(), r#""006208""#);
+        assert!(serde_json::from_str::<StockSymbol>(r#""TSMC""#).is_err());
+    }
+
+    #[test]
+    fn test_security_type_classify() {
+        assert_eq!(
+            SecurityType::classify("股票", "ESVUFR"),
+            SecurityType::Common
+        );
+        assert_eq!(
+            SecurityType::classify("特別股", "EPNRAR"),
+            SecurityType::Preferred
+        );
+        assert_eq!(
+            SecurityType::classify("臺灣存託憑證(TDR)", "EDSDDR"),
+            SecurityType::Tdr
+        );
+        assert_eq!(SecurityType::classify("ETF", "CEOGEU"), SecurityType::Etf);
+        assert_eq!(
+            SecurityType::classify("上市認購(售)權證", "RWSCCA"),
+            SecurityType::Warrant
+        );
+        assert_eq!(
+            SecurityType::classify("上櫃認購(售)權證", ""),
+            SecurityType::Warrant
+        );
+        assert_eq!(SecurityType::classify("普通股", ""), SecurityType::Common);
+        assert_eq!(
+            SecurityType::classify("受益證券-不動產投資信託", "CBCIXU"),
+            SecurityType::Unclassified
+        );
+        assert_eq!(SecurityType::from(5), SecurityType::Warrant);
+        assert_eq!(SecurityType::from(9), SecurityType::Unclassified);
+        assert_eq!(SecurityType::Etf.serial(), 4);
+    }
+
+    #[test]
+    fn test_statement_type() {
+        assert_eq!(
+            StatementType::detect("合併財務報告"),
+            StatementType::Consolidated
+        );
+        assert_eq!(
+            StatementType::detect("個別財務報告"),
+            StatementType::Individual
+        );
+        assert_eq!(StatementType::detect(""), StatementType::Consolidated);
+        assert_eq!(StatementType::from(2), StatementType::Individual);
+        assert_eq!(StatementType::from(0), StatementType::Consolidated);
+        assert_eq!(StatementType::Individual.to_string(), "個別");
+        assert!(StatementType::Consolidated.serial() < StatementType::Individual.serial());
+    }
+
     #[test]
     fn test_industry_serial() {
         assert_eq!(Industry::Cement.serial(), 1);
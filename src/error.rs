@@ -0,0 +1,176 @@
+use std::time::Duration;
+
+use chrono::NaiveDate;
+
+/// 爬蟲、回補等模組共用的錯誤類型
+///
+/// 各模組仍以 `anyhow::Result` 回傳，但底層會把可辨識的失敗包成此類型，
+/// 呼叫端(排程、重試、告警)可透過 [`classify`] 從錯誤鏈中取回並判斷是否值得重試。
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// 連線失敗、逾時等網路層的錯誤
+    #[error("http request to {url} failed: {source}")]
+    Http {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    /// 站點回應了非預期的狀態碼
+    #[error("unexpected http status {status} from {url}")]
+    Status { url: String, status: u16 },
+    /// 站點限制了請求頻率(HTTP 429)
+    #[error("rate limited by {url}, retry after {retry_after:?}")]
+    RateLimited {
+        url: String,
+        retry_after: Option<Duration>,
+    },
+    /// 被站點封鎖(HTTP 403 或驗證頁面)
+    #[error("blocked by {url}")]
+    Blocked { url: String },
+    /// 回應的內容無法解析
+    #[error("failed to parse {what}: {reason}")]
+    Parse { what: String, reason: String },
+    /// 資料庫操作失敗
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+    /// 指定的日期沒有開盤
+    #[error("{0} is not a trading day")]
+    NotTradingDay(NaiveDate),
+    /// 來源當天的請求數已達設定檔 crawler.<來源>.daily_cap 的上限
+    #[error("daily crawl budget of {crawler} is exhausted, resume on {resume_on}")]
+    BudgetExhausted {
+        crawler: String,
+        resume_on: NaiveDate,
+    },
+}
+
+impl Error {
+    /// 是否為暫時性的失敗，稍後重試可能成功
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::Http { source, .. } => {
+                source.is_timeout() || source.is_connect() || source.is_request()
+            }
+            Error::Status { status, .. } => *status >= 500,
+            Error::RateLimited { .. } => true,
+            Error::Database(why) => is_retryable_database_error(why),
+            Error::Blocked { .. }
+            | Error::Parse { .. }
+            | Error::NotTradingDay(_)
+            | Error::BudgetExhausted { .. } => false,
+        }
+    }
+
+    /// 建立解析失敗的錯誤
+    pub fn parse(what: impl Into<String>, reason: impl ToString) -> Self {
+        Error::Parse {
+            what: what.into(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+/// 從 anyhow 的錯誤鏈中取出第一個 [`Error`]
+pub fn classify(err: &anyhow::Error) -> Option<&Error> {
+    err.chain().find_map(|e| e.downcast_ref::<Error>())
+}
+
+/// 錯誤鏈中是否含有可重試的失敗，未經 [`Error`] 包裝的 sqlx、reqwest 錯誤也會一併判斷
+pub fn is_retryable(err: &anyhow::Error) -> bool {
+    err.chain().any(|e| {
+        if let Some(why) = e.downcast_ref::<Error>() {
+            return why.is_retryable();
+        }
+
+        if let Some(why) = e.downcast_ref::<sqlx::Error>() {
+            return is_retryable_database_error(why);
+        }
+
+        if let Some(why) = e.downcast_ref::<reqwest::Error>() {
+            return why.is_timeout() || why.is_connect();
+        }
+
+        false
+    })
+}
+
+/// 錯誤是否只是因為當天沒有開盤
+pub fn is_not_trading_day(err: &anyhow::Error) -> bool {
+    matches!(classify(err), Some(Error::NotTradingDay(_)))
+}
+
+/// 錯誤是因為來源當天的請求數已達上限時，回傳可以繼續抓取的日期
+pub fn budget_resume_on(err: &anyhow::Error) -> Option<NaiveDate> {
+    match classify(err) {
+        Some(Error::BudgetExhausted { resume_on, .. }) => Some(*resume_on),
+        _ => None,
+    }
+}
+
+/// 錯誤是否因為來源當天的請求數已達上限，逐筆抓取的迴圈遇到時應中斷並回傳給排程延後執行
+pub fn is_budget_exhausted(err: &anyhow::Error) -> bool {
+    budget_resume_on(err).is_some()
+}
+
+/// 資料庫錯誤是否為連線中斷、死結等重試後可能成功的失敗
+pub fn is_retryable_database_error(why: &sqlx::Error) -> bool {
+    match why {
+        sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed => true,
+        sqlx::Error::Database(db) => matches!(
+            db.code().as_deref(),
+            // serialization_failure、deadlock_detected、too_many_connections、admin_shutdown、cannot_connect_now
+            Some("40001") | Some("40P01") | Some("53300") | Some("57P01") | Some("57P03")
+        ),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::{anyhow, Context};
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let err = anyhow::Error::from(Error::NotTradingDay(date)).context("Failed to aggregate");
+
+        assert!(is_not_trading_day(&err));
+        assert!(!is_retryable(&err));
+
+        let err: anyhow::Error = Error::RateLimited {
+            url: "https://www.twse.com.tw".to_string(),
+            retry_after: None,
+        }
+        .into();
+        assert!(is_retryable(&err));
+
+        let status = |status| Error::Status {
+            url: "https://mops.twse.com.tw".to_string(),
+            status,
+        };
+        assert!(status(503).is_retryable());
+        assert!(!status(404).is_retryable());
+
+        let err = Err::<(), _>(sqlx::Error::PoolTimedOut)
+            .context("Failed to upsert")
+            .unwrap_err();
+        assert!(is_retryable(&err));
+        assert!(classify(&err).is_none());
+
+        let err = anyhow::Error::from(Error::BudgetExhausted {
+            crawler: "goodinfo".to_string(),
+            resume_on: date,
+        })
+        .context("Failed to fetch dividend");
+        assert!(!is_retryable(&err));
+        assert_eq!(budget_resume_on(&err), Some(date));
+        assert!(is_budget_exhausted(&err));
+        assert!(!is_budget_exhausted(&anyhow!("something wrong")));
+
+        assert!(!is_retryable(&anyhow!("something wrong")));
+        assert!(!Error::parse("revenue", "empty table").is_retryable());
+    }
+}
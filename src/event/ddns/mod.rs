@@ -0,0 +1,179 @@
+use futures::future::join_all;
+
+use crate::{
+    bot::alert,
+    config::{self, App, DdnsHost},
+    declare, logging, nosql,
+};
+
+/// 動態 DNS 的服務商
+pub mod provider;
+
+/// 將各主機更新為目前的 IP，只有 IP 與主機上一次更新成功的 IP 不同時才向服務商更新，
+/// 由 event::ip_monitor 偵測公網 IP 後呼叫
+pub async fn sync(ip: &str) {
+    let hosts = hosts(&config::SETTINGS.load());
+    join_all(hosts.iter().map(|host| update(host, ip))).await;
+}
+
+/// 設定檔 afraid、dyny、noip 區段的設定轉為主機後與 ddns.hosts 合併
+fn hosts(app: &App) -> Vec<DdnsHost> {
+    let mut hosts = Vec::with_capacity(app.ddns.hosts.len() + 2);
+
+    if !app.afraid.token.is_empty() {
+        hosts.push(DdnsHost {
+            provider: "afraid".to_string(),
+            token: app.afraid.token.clone(),
+            ..Default::default()
+        });
+    }
+
+    if !app.dyny.username.is_empty() {
+        hosts.push(DdnsHost {
+            provider: "dynu".to_string(),
+            username: app.dyny.username.clone(),
+            password: app.dyny.password.clone(),
+            ..Default::default()
+        });
+    }
+
+    if !app.noip.username.is_empty() {
+        hosts.extend(app.noip.hostnames.iter().map(|hostname| DdnsHost {
+            provider: "noip".to_string(),
+            hostname: hostname.clone(),
+            username: app.noip.username.clone(),
+            password: app.noip.password.clone(),
+            ..Default::default()
+        }));
+    }
+
+    hosts.extend(app.ddns.hosts.iter().cloned());
+    hosts
+}
+
+/// 更新單一主機，失敗時發送警示並且不記錄 IP，下一次排程會再重新更新
+async fn update(host: &DdnsHost, ip: &str) {
+    let key = cache_key(host);
+    if nosql::redis::CLIENT
+        .get_string(&key)
+        .await
+        .is_ok_and(|cached| cached == ip)
+    {
+        return;
+    }
+
+    let Some(provider) = provider::find(&host.provider) else {
+        logging::error_file_async(format!(
+            "The ddns provider {} is not supported",
+            host.provider
+        ));
+        return;
+    };
+
+    match provider.update(host, ip).await {
+        Ok(_) => {
+            if let Err(why) = nosql::redis::CLIENT
+                .set(key, ip, declare::ONE_DAYS_IN_SECONDS)
+                .await
+            {
+                logging::error_file_async(format!("{:?}", why));
+            }
+        }
+        Err(why) => {
+            logging::error_file_async(format!(
+                "Failed to {}::update({}) because {:#?}",
+                host.provider,
+                name(host),
+                why
+            ));
+            // 相同的警示在設定檔 alert.dedup_minutes 內只發送一次，避免每分鐘重試時重複通知
+            alert::send(
+                alert::DDNS_FAILURE,
+                &format!("DDNS {} {} 更新為 {} 失敗", host.provider, name(host), ip),
+            )
+            .await;
+        }
+    }
+}
+
+/// 主機在日誌與警示中的名稱，沒有主機名稱時使用帳號
+fn name(host: &DdnsHost) -> &str {
+    if !host.hostname.is_empty() {
+        return &host.hostname;
+    }
+
+    if !host.username.is_empty() {
+        return &host.username;
+    }
+
+    "default"
+}
+
+/// 主機上一次更新成功的 IP 在 redis 的鍵值
+fn cache_key(host: &DdnsHost) -> String {
+    format!("ddns:{}:{}", host.provider, name(host))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::{Afraid, Ddns, NoIp},
+        crawler::share,
+    };
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_hosts() {
+        let duckdns = DdnsHost {
+            provider: "duckdns".to_string(),
+            hostname: "myhome".to_string(),
+            token: "token".to_string(),
+            ..Default::default()
+        };
+        let app = App {
+            afraid: Afraid {
+                token: "afraid_token".to_string(),
+                ..Default::default()
+            },
+            noip: NoIp {
+                username: "user".to_string(),
+                password: "pw".to_string(),
+                hostnames: vec!["a.ddns.net".to_string(), "b.ddns.net".to_string()],
+            },
+            ddns: Ddns {
+                hosts: vec![duckdns.clone()],
+            },
+            ..Default::default()
+        };
+
+        let hosts = hosts(&app);
+        let keys = hosts.iter().map(cache_key).collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            vec![
+                "ddns:afraid:default",
+                "ddns:noip:a.ddns.net",
+                "ddns:noip:b.ddns.net",
+                "ddns:duckdns:myhome"
+            ]
+        );
+        assert_eq!(hosts.last(), Some(&duckdns));
+    }
+
+    #[tokio::test]
+    async fn test_execute() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 sync".to_string());
+
+        match share::get_public_ip().await {
+            Ok(ip) => sync(&ip).await,
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to get_public_ip because {:?}", why));
+            }
+        }
+
+        logging::debug_file_async("結束 sync".to_string());
+    }
+}
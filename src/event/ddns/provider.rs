@@ -0,0 +1,87 @@
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{config::DdnsHost, crawler};
+
+/// 動態 DNS 的服務商，依主機的設定將名稱指向新的 IP
+#[async_trait]
+pub trait Provider: Send + Sync {
+    async fn update(&self, host: &DdnsHost, ip: &str) -> Result<()>;
+}
+
+/// afraid.org 以更新 token 識別主機，以請求的來源 IP 作為新的 IP
+pub struct Afraid;
+
+#[async_trait]
+impl Provider for Afraid {
+    async fn update(&self, host: &DdnsHost, _ip: &str) -> Result<()> {
+        crawler::afraid::visit(&host.token).await
+    }
+}
+
+/// Cloudflare 以 API token 更新 zone 內主機的 A 記錄
+pub struct Cloudflare;
+
+#[async_trait]
+impl Provider for Cloudflare {
+    async fn update(&self, host: &DdnsHost, ip: &str) -> Result<()> {
+        crawler::cloudflare::visit(&host.token, &host.zone_id, &host.hostname, ip).await
+    }
+}
+
+/// DuckDNS 以 token 更新子網域
+pub struct DuckDns;
+
+#[async_trait]
+impl Provider for DuckDns {
+    async fn update(&self, host: &DdnsHost, ip: &str) -> Result<()> {
+        crawler::duckdns::visit(&host.hostname, &host.token, ip).await
+    }
+}
+
+/// dynu 以帳號密碼更新，沒有指定主機時更新帳號下全部的主機
+pub struct Dynu;
+
+#[async_trait]
+impl Provider for Dynu {
+    async fn update(&self, host: &DdnsHost, ip: &str) -> Result<()> {
+        crawler::dynu::visit(&host.username, &host.password, &host.hostname, ip).await
+    }
+}
+
+/// no-ip 以帳號密碼更新主機
+pub struct NoIp;
+
+#[async_trait]
+impl Provider for NoIp {
+    async fn update(&self, host: &DdnsHost, ip: &str) -> Result<()> {
+        crawler::noip::visit(&host.username, &host.password, &host.hostname, ip).await
+    }
+}
+
+/// 依設定檔 ddns.hosts 的 provider 取得服務商，不支援時回傳 None
+pub fn find(name: &str) -> Option<&'static dyn Provider> {
+    match name {
+        "afraid" => Some(&Afraid),
+        "cloudflare" => Some(&Cloudflare),
+        "duckdns" => Some(&DuckDns),
+        "dynu" => Some(&Dynu),
+        "noip" => Some(&NoIp),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_find() {
+        for name in ["afraid", "cloudflare", "duckdns", "dynu", "noip"] {
+            assert!(find(name).is_some(), "{}", name);
+        }
+
+        assert!(find("route53").is_none());
+    }
+}
@@ -0,0 +1,77 @@
+use anyhow::{anyhow, Result};
+
+use crate::{
+    bot, cache::SHARE, crawler::share, database::table::public_ip::PublicIp, event::ddns, logging,
+};
+
+/// 取得目前的公網 IP，與上一次記錄的 IP 不同時存入 public_ips 表並發送通知，
+/// 每次執行都會同步 ddns，IP 變動時可以立即更新，上一次更新失敗的主機也會重試
+pub async fn execute() -> Result<()> {
+    let ip_now = share::get_public_ip().await?;
+
+    if ip_now.is_empty() {
+        return Err(anyhow!("The IP addresses responses are empty."));
+    }
+
+    // 重啟後快取內沒有 IP，以資料庫最後一次的記錄比對，停機期間的變動也會通知，
+    // 讀到的記錄放入快取，之後的執行不必再查詢資料庫
+    let previous = match SHARE.get_current_ip().filter(|ip| !ip.is_empty()) {
+        Some(ip) => ip,
+        None => {
+            let latest = PublicIp::fetch_latest()
+                .await?
+                .map(|latest| latest.ip)
+                .unwrap_or_default();
+            if !latest.is_empty() {
+                SHARE.set_current_ip(latest.clone());
+            }
+            latest
+        }
+    };
+
+    if previous == ip_now {
+        ddns::sync(&ip_now).await;
+        return Ok(());
+    }
+
+    SHARE.set_current_ip(ip_now.clone());
+    ddns::sync(&ip_now).await;
+    PublicIp::new(ip_now.clone(), previous.clone())
+        .insert()
+        .await?;
+
+    logging::info_file_async(format!("公網 IP 由 {} 變更為 {}", previous, ip_now));
+
+    // 第一次記錄不是變動，不需要通知
+    if !previous.is_empty() {
+        bot::telegram::send(&format!(
+            "公網 IP 已變更\r\n舊 IP: {}\r\n新 IP: {}",
+            previous, ip_now
+        ))
+        .await;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_execute() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 execute".to_string());
+
+        match execute().await {
+            Ok(_) => {}
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to execute because {:?}", why));
+            }
+        }
+
+        logging::debug_file_async("結束 execute".to_string());
+    }
+}
@@ -3,4 +3,6 @@ pub mod taiwan_stock;
 /// 追踪 ex.即時股價是否達到高低標
 pub mod trace;
 
-pub mod ddns;
\ No newline at end of file
+pub mod ddns;
+/// 公網 IP 變動的偵測與通知
+pub mod ip_monitor;
@@ -0,0 +1,135 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+
+use crate::{
+    bot::{self, subscription::Event, telegram::fmt},
+    config::SETTINGS,
+    crawler::twse,
+    database::table::{
+        announcement::Announcement, bot_subscription::BotSubscription, stock_ownership_details,
+    },
+    declare::StockSymbol,
+    logging,
+};
+
+/// 未設定 announcement.keywords 時使用的關鍵字
+const DEFAULT_KEYWORDS: [&str; 8] = [
+    "減資", "合併", "處分", "增資", "解散", "下市", "重整", "退票",
+];
+
+/// 抓取重大訊息寫入資料庫，庫存、追踪中或以 /subscribe 訂閱的股票發布符合關鍵字的訊息時發送通知
+pub async fn execute() -> Result<()> {
+    let announcements = twse::announcement::visit().await?;
+    if announcements.is_empty() {
+        return Ok(());
+    }
+
+    let held = stock_ownership_details::fetch_held_or_traced_symbols().await?;
+    let subscribed = BotSubscription::fetch_symbols(Event::Announcement.as_str()).await?;
+    let keywords = keywords();
+    let mut items: Vec<(StockSymbol, String)> = Vec::new();
+
+    for item in announcements {
+        let announcement = match Announcement::try_from(item) {
+            Ok(announcement) => announcement,
+            Err(why) => {
+                logging::error_file_async(format!("{:?}", why));
+                continue;
+            }
+        };
+
+        match announcement.insert().await {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(why) => {
+                logging::error_file_async(format!("{:?}", why));
+                continue;
+            }
+        }
+
+        if !held.contains(&announcement.security_code)
+            && !subscribed.contains(&announcement.security_code)
+        {
+            continue;
+        }
+
+        let matched = match_keywords(&announcement.subject, &keywords);
+        if matched.is_empty() {
+            continue;
+        }
+
+        let line = format!(
+            "{} {} [{}]\n{}\n",
+            announcement.security_code,
+            fmt::escape_markdown(&announcement.name),
+            matched.join("、"),
+            fmt::escape_markdown(&announcement.subject)
+        );
+        items.push((announcement.security_code.clone(), line));
+    }
+
+    bot::alert::send_subscribed(
+        bot::alert::ANNOUNCEMENT,
+        Event::Announcement,
+        "重大訊息",
+        &items,
+        &held,
+    )
+    .await;
+
+    Ok(())
+}
+
+fn keywords() -> Vec<String> {
+    if SETTINGS.load().announcement.keywords.is_empty() {
+        DEFAULT_KEYWORDS.iter().map(|k| k.to_string()).collect()
+    } else {
+        SETTINGS.load().announcement.keywords.clone()
+    }
+}
+
+/// 回傳主旨中出現的關鍵字
+fn match_keywords<'a>(subject: &str, keywords: &'a [String]) -> Vec<&'a str> {
+    let mut seen = HashSet::new();
+    keywords
+        .iter()
+        .map(String::as_str)
+        .filter(|keyword| !keyword.is_empty() && subject.contains(keyword))
+        .filter(|keyword| seen.insert(*keyword))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cache::SHARE, logging};
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_match_keywords() {
+        let keywords: Vec<String> = DEFAULT_KEYWORDS.iter().map(|k| k.to_string()).collect();
+
+        assert_eq!(
+            match_keywords("公告本公司董事會決議辦理現金減資及處分資產", &keywords),
+            vec!["減資", "處分"]
+        );
+        assert!(match_keywords("公告本公司董事會決議發放股利", &keywords).is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_execute() {
+        dotenv::dotenv().ok();
+        SHARE.load().await;
+        logging::debug_file_async("開始 execute".to_string());
+
+        match execute().await {
+            Ok(_) => logging::debug_file_async("execute executed successfully.".to_string()),
+            Err(why) => logging::debug_file_async(format!("Failed to execute because {:?}", why)),
+        }
+
+        logging::debug_file_async("結束 execute".to_string());
+    }
+}
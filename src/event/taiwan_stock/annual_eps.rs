@@ -11,14 +11,17 @@ use crate::{
         yuanta::annual_profit::YuanTa,
     },
     database::table::{self, financial_statement::FinancialStatement},
-    logging, nosql,
+    declare::StockSymbol,
+    error, logging, nosql,
 };
 
+/// 從 fbs、元大、MoneyDJ 補上去年尚未有年度 EPS 的股票，
+/// 來源當天的請求數已達上限時回傳 Error::BudgetExhausted 讓排程延後執行
 pub async fn execute() -> Result<()> {
     let current_date: NaiveDate = Local::now().date_naive();
     let last_year = current_date.year() - 1;
     let without_annuals = table::financial_statement::fetch_without_annual(last_year).await?;
-    let mut stock_symbol: HashSet<String> = HashSet::new();
+    let mut stock_symbol: HashSet<StockSymbol> = HashSet::new();
     for ea in without_annuals {
         stock_symbol.insert(ea.security_code);
     }
@@ -40,8 +43,10 @@ pub async fn execute() -> Result<()> {
                     }
                 }
             }
+            Err(why) if error::is_budget_exhausted(&why) => return Err(why),
             Err(why) => {
                 logging::error_file_async(format!("{:?} ", why));
+                continue;
             }
         }
 
@@ -53,8 +58,10 @@ pub async fn execute() -> Result<()> {
     Ok(())
 }
 
+/// 依序向各站點取得年度獲利，全部失敗且其中有站點的請求數已達上限時回傳 Error::BudgetExhausted
 async fn fetch_annual_profit(ss: &str) -> Result<Vec<AnnualProfit>> {
     let sites = vec![Fbs::visit, YuanTa::visit, MoneyDJ::visit];
+    let mut exhausted = None;
 
     for fetch_func in sites {
         match fetch_func(ss).await {
@@ -65,12 +72,17 @@ async fn fetch_annual_profit(ss: &str) -> Result<Vec<AnnualProfit>> {
 
                 return Ok(ap);
             }
+            Err(why) if error::is_budget_exhausted(&why) => exhausted = Some(why),
             Err(why) => {
                 logging::error_file_async(format!("{:?} ", why));
             }
         }
     }
 
+    if let Some(why) = exhausted {
+        return Err(why);
+    }
+
     Err(anyhow!(
         "Failed to fetch annual profit({}) from all sites",
         ss
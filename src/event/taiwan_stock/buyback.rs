@@ -0,0 +1,182 @@
+use std::{collections::HashSet, fmt::Write};
+
+use anyhow::Result;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::{
+    bot::{self, telegram::fmt},
+    crawler::twse,
+    database::table::{buyback::Buyback, stock_ownership_details::StockOwnershipDetail},
+    declare::StockSymbol,
+    logging,
+};
+
+/// 執行完畢時已買回股數低於預定股數此百分比，視為明顯未達標
+const UNDER_TARGET_PERCENT: Decimal = dec!(50);
+
+/// 庫存股票的買回計畫需要提醒的事件
+#[derive(Debug, PartialEq)]
+enum Reminder {
+    /// 新公告的買回計畫
+    Announced,
+    /// 執行完畢但明顯未達預定股數
+    CompletedUnderTarget,
+}
+
+/// 更新庫藏股買回計畫，庫存股票公告買回或執行完畢卻明顯未達標時發送通知
+pub async fn execute() -> Result<()> {
+    let items = twse::buyback::visit().await?;
+    if items.is_empty() {
+        return Ok(());
+    }
+
+    let held: HashSet<StockSymbol> = StockOwnershipDetail::fetch(None)
+        .await?
+        .into_iter()
+        .map(|detail| detail.security_code)
+        .collect();
+    let mut msg = String::with_capacity(1024);
+
+    for item in items {
+        let buyback = match Buyback::try_from(item) {
+            Ok(buyback) => buyback,
+            Err(why) => {
+                logging::error_file_async(format!("{:?}", why));
+                continue;
+            }
+        };
+
+        let previous =
+            match Buyback::fetch_one(&buyback.security_code, buyback.resolution_date).await {
+                Ok(previous) => previous,
+                Err(why) => {
+                    logging::error_file_async(format!("{:?}", why));
+                    continue;
+                }
+            };
+
+        if let Err(why) = buyback.upsert().await {
+            logging::error_file_async(format!("{:?}", why));
+            continue;
+        }
+
+        if !held.contains(&buyback.security_code) {
+            continue;
+        }
+
+        match remind(previous.as_ref(), &buyback) {
+            Some(Reminder::Announced) => {
+                let _ = writeln!(
+                    msg,
+                    "{} {} 公告買回庫藏股\n預定買回 {} 張，價格區間 {}~{} 元，期間 {}~{}\n",
+                    buyback.security_code,
+                    fmt::escape_markdown(&buyback.name),
+                    fmt::thousands(buyback.target_shares / 1000),
+                    buyback.price_low.normalize(),
+                    buyback.price_high.normalize(),
+                    format_date(buyback.start_date),
+                    format_date(buyback.end_date),
+                );
+            }
+            Some(Reminder::CompletedUnderTarget) => {
+                let _ = writeln!(
+                    msg,
+                    "{} {} 庫藏股執行完畢但未達標\n預定 {} 張，實際買回 {} 張({}%)\n",
+                    buyback.security_code,
+                    fmt::escape_markdown(&buyback.name),
+                    fmt::thousands(buyback.target_shares / 1000),
+                    fmt::thousands(buyback.executed_shares / 1000),
+                    buyback.execution_rate(),
+                );
+            }
+            None => {}
+        }
+    }
+
+    if !msg.is_empty() {
+        bot::telegram::send(&format!("庫藏股\n{}", msg)).await;
+    }
+
+    Ok(())
+}
+
+/// 比對資料庫內原有的計畫，判斷是否需要提醒
+fn remind(previous: Option<&Buyback>, current: &Buyback) -> Option<Reminder> {
+    let Some(previous) = previous else {
+        return Some(Reminder::Announced);
+    };
+
+    if !previous.completed && current.completed && current.execution_rate() < UNDER_TARGET_PERCENT {
+        return Some(Reminder::CompletedUnderTarget);
+    }
+
+    None
+}
+
+fn format_date(date: Option<chrono::NaiveDate>) -> String {
+    date.map(|d| d.to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::{cache::SHARE, logging};
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn buyback(executed_shares: i64, completed: bool) -> Buyback {
+        Buyback {
+            security_code: "2330".parse().unwrap(),
+            name: "台積電".to_string(),
+            resolution_date: NaiveDate::from_ymd_opt(2024, 2, 13).unwrap(),
+            purpose: "轉讓股份予員工".to_string(),
+            target_shares: 2_000_000,
+            price_low: dec!(500),
+            price_high: dec!(800),
+            start_date: None,
+            end_date: None,
+            executed_shares,
+            completed,
+        }
+    }
+
+    #[test]
+    fn test_remind() {
+        assert_eq!(remind(None, &buyback(0, false)), Some(Reminder::Announced));
+        assert_eq!(
+            remind(Some(&buyback(100_000, false)), &buyback(500_000, true)),
+            Some(Reminder::CompletedUnderTarget)
+        );
+        assert_eq!(
+            remind(Some(&buyback(100_000, false)), &buyback(1_500_000, true)),
+            None
+        );
+        assert_eq!(
+            remind(Some(&buyback(500_000, true)), &buyback(500_000, true)),
+            None
+        );
+        assert_eq!(
+            remind(Some(&buyback(0, false)), &buyback(100_000, false)),
+            None
+        );
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_execute() {
+        dotenv::dotenv().ok();
+        SHARE.load().await;
+        logging::debug_file_async("開始 execute".to_string());
+
+        match execute().await {
+            Ok(_) => logging::debug_file_async("execute executed successfully.".to_string()),
+            Err(why) => logging::debug_file_async(format!("Failed to execute because {:?}", why)),
+        }
+
+        logging::debug_file_async("結束 execute".to_string());
+    }
+}
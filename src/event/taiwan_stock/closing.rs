@@ -4,13 +4,22 @@ use rust_decimal_macros::dec;
 
 use crate::{
     backfill, bot,
-    cache::{TtlCacheInner, TTL},
+    bot::telegram::fmt::{self, Align, Table},
+    cache::{TtlCacheInner, SHARE, TTL},
     calculation,
-    database::table::{
-        daily_money_history::extension::with_previous_trading_day_money_history::DailyMoneyHistoryWithPreviousTradingDayMoneyHistory,
-        daily_quote, last_daily_quotes, yield_rank::YieldRank,
+    config::{PipelineStep, SETTINGS},
+    database::{
+        table::{
+            daily_money_history::extension::with_previous_trading_day_money_history::DailyMoneyHistoryWithPreviousTradingDayMoneyHistory,
+            daily_quote, estimate_performance::EstimatePerformance, last_daily_quotes,
+            week52_stat::Week52Stat, yield_percentile::YieldPercentile, yield_rank::YieldRank,
+        },
+        timing,
     },
-    logging,
+    error,
+    event::taiwan_stock::{movers, protection, sector},
+    logging, quality, signals, telemetry,
+    util::http::crawl_budget,
 };
 
 /// 台股收盤事件發生時要進行的事情
@@ -27,57 +36,347 @@ pub async fn execute() -> Result<()> {
         ));
     }
 
-    if let Err(why) = res_aggregation {
-        logging::error_file_async(format!("Failed to closing::aggregate() because {:#?}", why));
+    match res_aggregation {
+        Err(why) if error::is_not_trading_day(&why) => {
+            logging::info_file_async(format!("closing::aggregate() skipped because {}", why));
+        }
+        Err(why) => {
+            logging::error_file_async(format!("Failed to closing::aggregate() because {:#?}", why));
+        }
+        Ok(_) => {}
     }
 
     Ok(())
 }
 
-/// 股票收盤數據匯總
-async fn aggregate(date: NaiveDate) -> Result<()> {
-    //抓取上市櫃公司每日收盤資訊
-    let daily_quote_count = backfill::quote::execute(date).await?;
-    //logging::info_file_async("抓取上市櫃收盤數據結束".to_string());
-    //let daily_quote_count = daily_quote::fetch_count_by_date(date).await?;
-    logging::info_file_async(format!("抓取上市櫃收盤數據結束:{}", daily_quote_count));
-
-    if daily_quote_count == 0 {
-        return Ok(());
+/// 收盤後依序執行的步驟，可由設定檔 pipeline.closing 調整順序或停用
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ClosingStep {
+    /// 抓取上市櫃公司每日收盤資訊
+    Quote,
+    /// 補上當日缺少的每日收盤數據
+    MakeupQuotes,
+    /// 計算均線
+    MovingAverage,
+    /// 計算依除權息還原的收盤價
+    AdjustedPrice,
+    /// 更新 last_daily_quotes 表內當日有成交的股票，每週全表重建一次
+    LastDailyQuotes,
+    /// 計算 52 週高低點與回檔幅度
+    Week52Stats,
+    /// 取得交易所公布的本益比、殖利率及股價淨值比
+    Valuation,
+    /// 計算當日殖利率在近 5 年殖利率分佈中的百分位
+    YieldPercentile,
+    /// 計算便宜、合理、昂貴價的估算
+    Estimate,
+    /// 驗證 3、6、12 個月前的估價與實際報酬
+    EstimatePerformance,
+    /// 重建指定日期的 yield_rank 表內的數據
+    YieldRank,
+    /// 發送指定產業的殖利率排行
+    YieldRankReport,
+    /// 發送全市場與庫存股票的漲跌幅排行與爆量股票
+    MoversReport,
+    /// 彙總各產業的平均漲跌幅並寫入 sector_daily_performance 表
+    SectorPerformance,
+    /// 發送各產業平均漲跌幅的熱度列表
+    SectorReport,
+    /// 以當日的開盤價或收盤價撮合模擬交易的委託
+    PaperTrading,
+    /// 計算帳戶內市值
+    MoneyHistory,
+    /// 計算庫存股票與整體庫存近一年的風險指標
+    RiskMetrics,
+    /// 依 signals.toml 的規則判斷策略訊號並通知
+    Signals,
+    /// 通知收盤價跌破停損價或突破停利價的庫存
+    Protection,
+    /// 檢查當日匯總後的數據品質
+    Quality,
+    /// 發送通知本日與前一個交易日的市值變化
+    MoneyChangeReport,
+}
+
+impl ClosingStep {
+    /// 未設定 pipeline.closing 時依此順序執行全部的步驟
+    const ALL: [ClosingStep; 22] = [
+        ClosingStep::Quote,
+        ClosingStep::MakeupQuotes,
+        ClosingStep::MovingAverage,
+        ClosingStep::AdjustedPrice,
+        ClosingStep::LastDailyQuotes,
+        ClosingStep::Week52Stats,
+        ClosingStep::Valuation,
+        ClosingStep::YieldPercentile,
+        ClosingStep::Estimate,
+        ClosingStep::EstimatePerformance,
+        ClosingStep::YieldRank,
+        ClosingStep::YieldRankReport,
+        ClosingStep::MoversReport,
+        ClosingStep::SectorPerformance,
+        ClosingStep::SectorReport,
+        ClosingStep::PaperTrading,
+        ClosingStep::MoneyHistory,
+        ClosingStep::RiskMetrics,
+        ClosingStep::Signals,
+        ClosingStep::Protection,
+        ClosingStep::Quality,
+        ClosingStep::MoneyChangeReport,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            ClosingStep::Quote => "quote",
+            ClosingStep::MakeupQuotes => "makeup_quotes",
+            ClosingStep::MovingAverage => "moving_average",
+            ClosingStep::AdjustedPrice => "adjusted_price",
+            ClosingStep::LastDailyQuotes => "last_daily_quotes",
+            ClosingStep::Week52Stats => "week52_stats",
+            ClosingStep::Valuation => "valuation",
+            ClosingStep::YieldPercentile => "yield_percentile",
+            ClosingStep::Estimate => "estimate",
+            ClosingStep::EstimatePerformance => "estimate_performance",
+            ClosingStep::YieldRank => "yield_rank",
+            ClosingStep::YieldRankReport => "yield_rank_report",
+            ClosingStep::MoversReport => "movers_report",
+            ClosingStep::SectorPerformance => "sector_performance",
+            ClosingStep::SectorReport => "sector_report",
+            ClosingStep::PaperTrading => "paper_trading",
+            ClosingStep::MoneyHistory => "money_history",
+            ClosingStep::RiskMetrics => "risk_metrics",
+            ClosingStep::Signals => "signals",
+            ClosingStep::Protection => "protection",
+            ClosingStep::Quality => "quality",
+            ClosingStep::MoneyChangeReport => "money_change_report",
+        }
     }
 
-    // 補上當日缺少的每日收盤數據
-    let lack_daily_quotes_count = daily_quote::makeup_for_the_lack_daily_quotes(date).await?;
-    logging::info_file_async(format!(
-        "補上當日缺少的每日收盤數據結束:{:#?}",
-        lack_daily_quotes_count
-    ));
+    fn from_name(name: &str) -> Option<Self> {
+        ClosingStep::ALL
+            .into_iter()
+            .find(|step| step.name() == name.trim())
+    }
+
+    /// 失敗時是否中止後續的步驟，報表、通知類的步驟失敗只記錄錯誤
+    fn is_fatal(&self) -> bool {
+        !matches!(
+            self,
+            ClosingStep::AdjustedPrice
+                | ClosingStep::Week52Stats
+                | ClosingStep::Valuation
+                | ClosingStep::YieldPercentile
+                | ClosingStep::EstimatePerformance
+                | ClosingStep::YieldRankReport
+                | ClosingStep::MoversReport
+                | ClosingStep::SectorPerformance
+                | ClosingStep::SectorReport
+                | ClosingStep::PaperTrading
+                | ClosingStep::RiskMetrics
+                | ClosingStep::Signals
+                | ClosingStep::Protection
+                | ClosingStep::Quality
+        )
+    }
+
+    async fn run(&self, date: NaiveDate) -> Result<()> {
+        match self {
+            ClosingStep::Quote => {
+                let daily_quote_count = backfill::quote::execute(date).await?;
+                logging::info_file_async(format!("抓取上市櫃收盤數據結束:{}", daily_quote_count));
+
+                if daily_quote_count == 0 {
+                    return Err(error::Error::NotTradingDay(date).into());
+                }
+            }
+            ClosingStep::MakeupQuotes => {
+                let lack_daily_quotes_count =
+                    daily_quote::makeup_for_the_lack_daily_quotes(date).await?;
+                logging::info_file_async(format!(
+                    "補上當日缺少的每日收盤數據結束:{:#?}",
+                    lack_daily_quotes_count
+                ));
+            }
+            ClosingStep::MovingAverage => {
+                calculation::daily_quotes::calculate_moving_average(date).await?;
+                logging::info_file_async("計算均線結束".to_string());
+            }
+            ClosingStep::AdjustedPrice => {
+                calculation::adjusted_price::calculate(date).await?;
+                logging::info_file_async("計算還原收盤價結束".to_string());
+            }
+            ClosingStep::LastDailyQuotes => {
+                let rebuilt = last_daily_quotes::LastDailyQuotes::refresh(date).await?;
+                logging::info_file_async(format!(
+                    "更新 last_daily_quotes 表內的數據結束(全表重建:{})",
+                    rebuilt
+                ));
+            }
+            ClosingStep::Week52Stats => {
+                Week52Stat::upsert(date).await?;
+                logging::info_file_async("計算 52 週高低點與回檔幅度結束".to_string());
+            }
+            ClosingStep::Valuation => {
+                let count = backfill::valuation::execute(date).await?;
+                logging::info_file_async(format!("抓取本益比、殖利率及股價淨值比結束:{}", count));
+            }
+            ClosingStep::YieldPercentile => {
+                let result = YieldPercentile::upsert(date).await?;
+                logging::info_file_async(format!(
+                    "計算殖利率近 5 年百分位結束:{}",
+                    result.rows_affected()
+                ));
+            }
+            ClosingStep::Estimate => {
+                calculation::estimated_price::calculate_estimated_price(date).await?;
+                logging::info_file_async("計算便宜、合理、昂貴價的估算結束".to_string());
+            }
+            ClosingStep::EstimatePerformance => {
+                EstimatePerformance::upsert(date).await?;
+                logging::info_file_async("驗證估價模型的實際報酬結束".to_string());
+            }
+            ClosingStep::YieldRank => {
+                YieldRank::upsert(date).await?;
+                logging::info_file_async("重建 yield_rank 表內的數據結束".to_string());
+            }
+            ClosingStep::YieldRankReport => notify_yield_rank(date).await?,
+            ClosingStep::MoversReport => movers::execute(date).await?,
+            ClosingStep::SectorPerformance => {
+                let count = sector::aggregate(date).await?;
+                logging::info_file_async(format!("彙總各產業的平均漲跌幅結束:{}", count));
+            }
+            ClosingStep::SectorReport => sector::execute(date).await?,
+            ClosingStep::PaperTrading => {
+                let count = calculation::paper_trading::fill(date).await?;
+                logging::info_file_async(format!("撮合模擬交易的委託結束:{}", count));
+            }
+            ClosingStep::MoneyHistory => {
+                calculation::money_history::calculate_money_history(date).await?;
+                logging::info_file_async("計算帳戶內市值結束".to_string());
+            }
+            ClosingStep::RiskMetrics => {
+                let count = calculation::risk::calculate(date).await?;
+                logging::info_file_async(format!("計算風險指標結束:{}", count));
+            }
+            ClosingStep::Signals => {
+                let count = signals::execute(date).await?;
+                logging::info_file_async(format!("判斷策略訊號結束:{}", count));
+            }
+            ClosingStep::Protection => {
+                let count = protection::execute(date).await?;
+                logging::info_file_async(format!("檢查庫存的停損與停利結束:{}", count));
+            }
+            ClosingStep::Quality => {
+                quality::execute(date).await?;
+            }
+            ClosingStep::MoneyChangeReport => notify_money_change(date).await?,
+        }
 
-    // 計算均線
-    calculation::daily_quotes::calculate_moving_average(date).await?;
-    logging::info_file_async("計算均線結束".to_string());
+        Ok(())
+    }
+}
 
-    // 重建 last_daily_quotes 表內的數據
-    last_daily_quotes::LastDailyQuotes::rebuild().await?;
-    logging::info_file_async("重建 last_daily_quotes 表內的數據結束".to_string());
+/// 依設定檔決定要執行的步驟與順序，未設定時執行全部的步驟，無法辨識的步驟名稱會被略過
+fn resolve_steps(config: &[PipelineStep]) -> Vec<ClosingStep> {
+    if config.is_empty() {
+        return ClosingStep::ALL.to_vec();
+    }
 
-    // 計算便宜、合理、昂貴價的估算
-    calculation::estimated_price::calculate_estimated_price(date).await?;
-    logging::info_file_async("計算便宜、合理、昂貴價的估算結束".to_string());
+    config
+        .iter()
+        .filter(|step| step.enabled)
+        .filter_map(|step| {
+            let resolved = ClosingStep::from_name(&step.name);
+            if resolved.is_none() {
+                logging::warn_file_async(format!("Unknown closing pipeline step: {}", step.name));
+            }
+            resolved
+        })
+        .collect()
+}
 
-    // 重建指定日期的 yield_rank 表內的數據
-    YieldRank::upsert(date).await?;
-    logging::info_file_async("重建 yield_rank 表內的數據結束".to_string());
+/// 股票收盤數據匯總
+async fn aggregate(date: NaiveDate) -> Result<()> {
+    for step in resolve_steps(&SETTINGS.load().pipeline.closing) {
+        let name = format!("closing::{}", step.name());
+        match telemetry::span(name, step.run(date)).await {
+            Ok(_) => {}
+            Err(why) if step.is_fatal() => {
+                return Err(why.context(format!("Failed to run closing step {}", step.name())));
+            }
+            Err(why) => {
+                logging::error_file_async(format!(
+                    "Failed to run closing step {} because {:#?}",
+                    step.name(),
+                    why
+                ));
+            }
+        }
+    }
 
-    // 計算帳戶內市值
-    calculation::money_history::calculate_money_history(date).await?;
-    logging::info_file_async("計算帳戶內市值結束".to_string());
+    // 記錄收盤流程中各資料表的查詢耗時
+    timing::log_summary("台股收盤");
+    // 記錄當天各來源的請求數，達上限的來源註明延到隔天的數量
+    crawl_budget::log_summary("台股收盤");
 
     // 清除記憶與Redis內所有的快取
     TTL.clear();
 
-    //發送通知本日與前一個交易日的市值變化
-    notify_money_change(date).await
+    Ok(())
+}
+
+/// 依設定檔列出的產業分類，發送各產業內殖利率最高的股票
+async fn notify_yield_rank(date: NaiveDate) -> Result<()> {
+    let report = &SETTINGS.load().report;
+    let limit = if report.yield_rank_limit > 0 {
+        report.yield_rank_limit
+    } else {
+        10
+    };
+
+    for industry_id in &report.yield_rank_industries {
+        let ranks = YieldRank::fetch_top(date, Some(*industry_id), limit).await?;
+        if ranks.is_empty() {
+            continue;
+        }
+
+        let industry_name = SHARE
+            .get_industry_name(*industry_id)
+            .unwrap_or_else(|| industry_id.to_string());
+        let mut table = Table::new(&["#", "代號", "名稱", "殖利率", "股利", "收盤價"]).align(&[
+            Align::Right,
+            Align::Left,
+            Align::Left,
+            Align::Right,
+            Align::Right,
+            Align::Right,
+        ]);
+        for (i, rank) in ranks.iter().enumerate() {
+            let name = SHARE
+                .get_stock(&rank.security_code)
+                .await
+                .map(|stock| stock.name)
+                .unwrap_or_default();
+            table.row(&[
+                (i + 1).to_string(),
+                rank.security_code.to_string(),
+                name,
+                format!("{:.2}%", rank.r#yield),
+                rank.dividend.to_string(),
+                rank.closing_price.to_string(),
+            ]);
+        }
+
+        let msg = format!(
+            "{} {} 殖利率排行\n{}",
+            date,
+            fmt::escape_markdown(&industry_name),
+            table.render()
+        );
+        bot::telegram::send(&msg).await;
+    }
+
+    Ok(())
 }
 
 async fn notify_money_change(date: NaiveDate) -> Result<()> {
@@ -92,17 +391,17 @@ async fn notify_money_change(date: NaiveDate) -> Result<()> {
     let unice_diff = mh.unice - mh.previous_unice;
     let unice_percentage = (unice_diff / mh.previous_unice) * hundred;
     let msg = format!(
-        "{} 市值變化\n合計:{} {} ({}%)\nEddie:{} {} ({}%)\nUnice:{} {} ({}%)",
+        "{} 市值變化\n合計:{} {} ({})\nEddie:{} {} ({})\nUnice:{} {} ({})",
         date,
-        mh.sum.round_dp(2),
-        sum_diff.round_dp(2),
-        sum_percentage.round_dp(2),
-        mh.eddie.round_dp(2),
-        eddie_diff.round_dp(2),
-        eddie_percentage.round_dp(2),
-        mh.unice.round_dp(2),
-        unice_diff.round_dp(2),
-        unice_percentage.round_dp(2),
+        fmt::number(mh.sum, 2),
+        fmt::number(sum_diff, 2),
+        fmt::percent(sum_percentage),
+        fmt::number(mh.eddie, 2),
+        fmt::number(eddie_diff, 2),
+        fmt::percent(eddie_percentage),
+        fmt::number(mh.unice, 2),
+        fmt::number(unice_diff, 2),
+        fmt::percent(unice_percentage),
     );
 
     bot::telegram::send(&msg).await;
@@ -117,6 +416,37 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_resolve_steps() {
+        assert_eq!(resolve_steps(&[]), ClosingStep::ALL.to_vec());
+
+        let step = |name: &str, enabled: bool| PipelineStep {
+            name: name.to_string(),
+            enabled,
+        };
+        let config = vec![
+            step("quote", true),
+            step("last_daily_quotes", true),
+            step("estimate", false),
+            step("unknown", true),
+            step("money_history", false),
+            step("quality", true),
+        ];
+
+        assert_eq!(
+            resolve_steps(&config),
+            vec![
+                ClosingStep::Quote,
+                ClosingStep::LastDailyQuotes,
+                ClosingStep::Quality
+            ]
+        );
+
+        for step in ClosingStep::ALL {
+            assert_eq!(ClosingStep::from_name(step.name()), Some(step));
+        }
+    }
+
     #[tokio::test]
     #[ignore]
     async fn test_aggregate() {
@@ -0,0 +1,75 @@
+use anyhow::Result;
+use chrono::{Local, NaiveDate, TimeDelta};
+use minijinja::context;
+
+use crate::{
+    bot::{self, telegram::fmt},
+    cache::SHARE,
+    database::table::{corporate_event::CorporateEvent, stock_ownership_details},
+    i18n,
+};
+
+/// 提前幾天提醒
+const REMIND_DAYS_AHEAD: i64 = 3;
+
+/// 提醒三天後召開股東會或法說會的庫存與追踪中股票
+pub async fn execute() -> Result<()> {
+    let date: NaiveDate = Local::now().date_naive() + TimeDelta::days(REMIND_DAYS_AHEAD);
+    let symbols: Vec<String> = stock_ownership_details::fetch_held_or_traced_symbols()
+        .await?
+        .into_iter()
+        .map(String::from)
+        .collect();
+    let events = CorporateEvent::fetch_between(&symbols, date, date).await?;
+    if events.is_empty() {
+        return Ok(());
+    }
+
+    let mut rows = Vec::with_capacity(events.len());
+    for event in events {
+        let name = SHARE
+            .get_stock(&event.security_code)
+            .await
+            .map(|stock| stock.name)
+            .unwrap_or_default();
+        rows.push(context! {
+            security_code => event.security_code,
+            name => fmt::escape_markdown(&name),
+            kind => event.kind,
+            description => fmt::escape_markdown(&event.description),
+        });
+    }
+
+    bot::telegram::send_localized(|language| {
+        i18n::text(
+            language,
+            "corporate_event",
+            context! { date => date, events => rows },
+        )
+    })
+    .await;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cache::SHARE, logging};
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_execute() {
+        dotenv::dotenv().ok();
+        SHARE.load().await;
+        logging::debug_file_async("開始 execute".to_string());
+
+        if let Err(why) = execute().await {
+            logging::debug_file_async(format!("Failed to execute because {:?}", why));
+        }
+
+        logging::debug_file_async("結束 execute".to_string());
+    }
+}
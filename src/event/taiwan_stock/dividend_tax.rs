@@ -0,0 +1,274 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use anyhow::{anyhow, Result};
+use chrono::{Datelike, Local};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::{
+    bot::{
+        telegram::fmt::{self, Align, Table},
+        Notifier, TelegramNotifier,
+    },
+    database::table::dividend::extension::taxable_dividend::{self, TaxableDividend},
+    logging, storage,
+};
+
+/// 二代健保補充保費費率
+const PREMIUM_RATE: Decimal = dec!(0.0211);
+/// 單次給付達此金額才扣取補充保費
+const PREMIUM_THRESHOLD: Decimal = dec!(20000);
+/// 單次給付計算補充保費的上限
+const PREMIUM_CAP: Decimal = dec!(10000000);
+/// 股利所得合併計稅時可抵減稅額的比率
+const CREDIT_RATE: Decimal = dec!(0.085);
+/// 每一申報戶可抵減稅額的上限
+const CREDIT_CAP: Decimal = dec!(80000);
+/// 股利所得分開計稅的稅率
+const SEPARATE_TAX_RATE: Decimal = dec!(0.28);
+
+const USAGE: &str = "usage: stock_crawler tax <year>";
+
+/// 成員全年度的股利所得與稅負試算
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MemberTax {
+    pub member_id: i64,
+    pub cash: Decimal,
+    /// 股票股利以面額計算的金額
+    pub stock_money: Decimal,
+    /// 被扣取的補充保費
+    pub premium: Decimal,
+}
+
+impl MemberTax {
+    /// 股利所得 = 現金股利 + 股票股利(面額)
+    pub fn income(&self) -> Decimal {
+        self.cash + self.stock_money
+    }
+
+    /// 合併計稅時的可抵減稅額
+    pub fn credit(&self) -> Decimal {
+        (self.income() * CREDIT_RATE).round_dp(0).min(CREDIT_CAP)
+    }
+
+    /// 分開計稅時的應納稅額
+    pub fn separate_tax(&self) -> Decimal {
+        (self.income() * SEPARATE_TAX_RATE).round_dp(0)
+    }
+}
+
+/// 每年一月匯出上一年度各成員的股利所得報表並發送摘要
+pub async fn execute() -> Result<()> {
+    let year = Local::now().year() - 1;
+    let (key, members) = export(year).await?;
+    if members.is_empty() {
+        return Ok(());
+    }
+
+    TelegramNotifier
+        .notify(&format!(
+            "{} 年度股利所得\n{}\n明細已匯出至 {}",
+            year,
+            summary_table(&members),
+            fmt::escape_markdown(&key)
+        ))
+        .await;
+
+    Ok(())
+}
+
+/// 依命令列參數匯出指定年度的股利所得報表 ex. `stock_crawler tax 2024`
+pub async fn command(args: &[String]) -> Result<()> {
+    let year: i32 = args
+        .first()
+        .and_then(|year| year.parse().ok())
+        .ok_or_else(|| anyhow!(USAGE))?;
+    let (key, members) = export(year).await?;
+    for member in &members {
+        println!(
+            "member {}\tincome {}\tpremium {}\tcredit {}\tseparate_tax {}",
+            member.member_id,
+            member.income(),
+            member.premium,
+            member.credit(),
+            member.separate_tax()
+        );
+    }
+    println!("exported to {}", key);
+
+    Ok(())
+}
+
+/// 將指定年度的股利所得 CSV 寫入儲存後端，回傳儲存的 key 與各成員的合計
+async fn export(year: i32) -> Result<(String, Vec<MemberTax>)> {
+    let dividends = taxable_dividend::fetch(year).await?;
+    let members = summarize(&dividends);
+    let key = format!("reports/dividend_tax_{}.csv", year);
+    storage::get_storage()
+        .put(&key, render_csv(&dividends, &members).into_bytes())
+        .await?;
+    logging::info_file_async(format!(
+        "{} 年度股利所得已匯出 {} 筆至 {}",
+        year,
+        dividends.len(),
+        key
+    ));
+
+    Ok((key, members))
+}
+
+/// 單次給付(現金股利 + 股票股利面額)達門檻時扣取的補充保費
+fn premium(dividend: &TaxableDividend) -> Decimal {
+    let income = dividend.cash + dividend.stock_money;
+    if income < PREMIUM_THRESHOLD {
+        return Decimal::ZERO;
+    }
+
+    (income.min(PREMIUM_CAP) * PREMIUM_RATE).round_dp(0)
+}
+
+/// 依成員加總全年度的股利與補充保費
+fn summarize(dividends: &[TaxableDividend]) -> Vec<MemberTax> {
+    let mut members: BTreeMap<i64, MemberTax> = BTreeMap::new();
+    for dividend in dividends {
+        let member = members
+            .entry(dividend.member_id)
+            .or_insert_with(|| MemberTax {
+                member_id: dividend.member_id,
+                ..Default::default()
+            });
+        member.cash += dividend.cash;
+        member.stock_money += dividend.stock_money;
+        member.premium += premium(dividend);
+    }
+
+    members.into_values().collect()
+}
+
+/// 含逗號或引號的欄位需以引號包住
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// 前段為每次發放的明細，後段為各成員的合計與稅負試算，開頭加上 BOM 讓 Excel 正確顯示中文
+fn render_csv(dividends: &[TaxableDividend], members: &[MemberTax]) -> String {
+    let mut csv = String::from("\u{feff}");
+    let _ = writeln!(
+        csv,
+        "成員,股票代號,股票名稱,發放日,現金股利,股票股利(股),股票股利(面額),股利所得,補充保費"
+    );
+    for d in dividends {
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{},{},{}",
+            d.member_id,
+            csv_field(&d.security_code),
+            csv_field(&d.name),
+            d.payable_date,
+            d.cash.round_dp(0),
+            d.stock.round_dp(0),
+            d.stock_money.round_dp(0),
+            (d.cash + d.stock_money).round_dp(0),
+            premium(d)
+        );
+    }
+
+    let _ = writeln!(
+        csv,
+        "\n成員,現金股利,股票股利(面額),股利所得,補充保費,合併計稅可抵減稅額,分開計稅應納稅額"
+    );
+    for m in members {
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{}",
+            m.member_id,
+            m.cash.round_dp(0),
+            m.stock_money.round_dp(0),
+            m.income().round_dp(0),
+            m.premium,
+            m.credit(),
+            m.separate_tax()
+        );
+    }
+
+    csv
+}
+
+fn summary_table(members: &[MemberTax]) -> String {
+    let mut table = Table::new(&["成員", "股利所得", "補充保費", "可抵減"]).align(&[
+        Align::Left,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+    ]);
+    for m in members {
+        table.row(&[
+            m.member_id.to_string(),
+            fmt::number(m.income(), 0),
+            fmt::number(m.premium, 0),
+            fmt::number(m.credit(), 0),
+        ]);
+    }
+
+    table.render()
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn dividend(member_id: i64, cash: Decimal, stock_money: Decimal) -> TaxableDividend {
+        TaxableDividend {
+            member_id,
+            security_code: "2884".parse().unwrap(),
+            name: "玉山金".to_string(),
+            payable_date: "2024-08-22".to_string(),
+            cash,
+            stock: stock_money / dec!(10),
+            stock_money,
+        }
+    }
+
+    #[test]
+    fn test_premium() {
+        assert_eq!(premium(&dividend(1, dec!(19999), dec!(0))), dec!(0));
+        assert_eq!(premium(&dividend(1, dec!(15000), dec!(5000))), dec!(422));
+        assert_eq!(premium(&dividend(1, dec!(20000000), dec!(0))), dec!(211000));
+    }
+
+    #[test]
+    fn test_summarize() {
+        let dividends = vec![
+            dividend(1, dec!(30000), dec!(0)),
+            dividend(1, dec!(10000), dec!(1000)),
+            dividend(2, dec!(2000000), dec!(0)),
+        ];
+
+        let members = summarize(&dividends);
+
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[0].income(), dec!(41000));
+        assert_eq!(members[0].premium, dec!(633));
+        assert_eq!(members[0].credit(), dec!(3485));
+        assert_eq!(members[0].separate_tax(), dec!(11480));
+        assert_eq!(members[1].credit(), CREDIT_CAP);
+    }
+
+    #[test]
+    fn test_render_csv() {
+        let mut with_comma = dividend(1, dec!(30000), dec!(0));
+        with_comma.name = "A,B".to_string();
+        let dividends = vec![with_comma];
+
+        let csv = render_csv(&dividends, &summarize(&dividends));
+
+        assert!(csv.starts_with('\u{feff}'));
+        assert!(csv.contains("1,2884,\"A,B\",2024-08-22,30000,0,0,30000,633\n"));
+        assert!(csv.contains("1,30000,0,30000,633,2550,8400\n"));
+    }
+}
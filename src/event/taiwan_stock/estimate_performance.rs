@@ -0,0 +1,148 @@
+use std::fmt::Write;
+
+use anyhow::Result;
+use chrono::{Datelike, Local, NaiveDate};
+use rust_decimal::Decimal;
+
+use crate::{
+    bot::{
+        telegram::fmt::{self, Align, Table},
+        Notifier, TelegramNotifier,
+    },
+    database::table::estimate_performance::{EstimatePerformance, ModelHitRate},
+};
+
+/// 每月一日發送上個月估價模型的命中率
+pub async fn execute() -> Result<()> {
+    report(Local::now().date_naive(), &TelegramNotifier).await
+}
+
+/// 統計驗證日在上個月的估價訊號，依模型與驗證期間列出命中率與平均報酬
+pub async fn report(today: NaiveDate, notifier: &dyn Notifier) -> Result<()> {
+    let (start, end) = last_month(today);
+    let rates = EstimatePerformance::fetch_hit_rates(start, end).await?;
+    if rates.is_empty() {
+        return Ok(());
+    }
+
+    notifier.notify(&compose(start, end, &rates)).await;
+
+    Ok(())
+}
+
+/// 上個月的第一天與最後一天
+fn last_month(today: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let first_day = today.with_day(1).unwrap_or(today);
+    let end = first_day.pred_opt().unwrap_or(first_day);
+
+    (end.with_day(1).unwrap_or(end), end)
+}
+
+fn model_name(model: &str) -> &str {
+    match model {
+        "overall" => "綜合",
+        "price" => "股價",
+        "dividend" => "股利",
+        "eps" => "EPS",
+        "pbr" => "淨值比",
+        "per" => "本益比",
+        "yield" => "殖利率",
+        "pbr_band" => "淨值比區間",
+        _ => model,
+    }
+}
+
+fn compose(start: NaiveDate, end: NaiveDate, rates: &[ModelHitRate]) -> String {
+    let mut msg = String::with_capacity(1024);
+    let _ = writeln!(&mut msg, "🎯 估價模型命中率 {} ~ {}", start, end);
+    let _ = writeln!(
+        &mut msg,
+        "便宜價訊號之後上漲或昂貴價訊號之後下跌視為命中，報酬率以還原股價計算"
+    );
+
+    let mut table =
+        Table::new(&["期間", "模型", "樣本", "命中率", "便宜報酬", "昂貴報酬"]).align(&[
+            Align::Right,
+            Align::Left,
+            Align::Right,
+            Align::Right,
+            Align::Right,
+            Align::Right,
+        ]);
+    let average = |value: Option<Decimal>| {
+        value.map_or_else(|| "-".to_string(), |v| format!("{}%", fmt::number(v, 2)))
+    };
+    for rate in rates {
+        let hit_rate = if rate.samples > 0 {
+            Decimal::from(rate.hits) / Decimal::from(rate.samples) * Decimal::ONE_HUNDRED
+        } else {
+            Decimal::ZERO
+        };
+        table.row(&[
+            format!("{}月", rate.months),
+            model_name(&rate.model).to_string(),
+            fmt::thousands(rate.samples),
+            format!("{}%", fmt::number(hit_rate, 1)),
+            average(rate.cheap_return),
+            average(rate.expensive_return),
+        ]);
+    }
+    let _ = write!(&mut msg, "{}", table.render());
+
+    msg
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_last_month() {
+        assert_eq!(
+            last_month(date(2024, 3, 1)),
+            (date(2024, 2, 1), date(2024, 2, 29))
+        );
+        assert_eq!(
+            last_month(date(2024, 1, 15)),
+            (date(2023, 12, 1), date(2023, 12, 31))
+        );
+    }
+
+    #[test]
+    fn test_compose() {
+        let rates = vec![
+            ModelHitRate {
+                model: "dividend".to_string(),
+                months: 3,
+                samples: 1200,
+                hits: 780,
+                cheap_return: Some(dec!(4.56)),
+                expensive_return: None,
+            },
+            ModelHitRate {
+                model: "per".to_string(),
+                months: 12,
+                samples: 0,
+                hits: 0,
+                cheap_return: None,
+                expensive_return: Some(dec!(-1.2)),
+            },
+        ];
+
+        let msg = compose(date(2024, 2, 1), date(2024, 2, 29), &rates);
+
+        assert!(msg.starts_with("🎯 估價模型命中率 2024-02-01 ~ 2024-02-29\n"));
+        assert!(msg.contains("股利"));
+        assert!(msg.contains("1,200"));
+        assert!(msg.contains("65.0%"));
+        assert!(msg.contains("4.56%"));
+        assert!(msg.contains("-1.20%"));
+    }
+}
@@ -1,9 +1,13 @@
-use std::fmt::Write;
-
 use anyhow::Result;
 use chrono::{Datelike, Local, NaiveDate};
+use minijinja::context;
 
-use crate::{bot, calculation, database::table::dividend};
+use crate::{
+    bot::{self, subscription::Event, telegram::fmt},
+    calculation,
+    database::table::dividend,
+    i18n,
+};
 
 /// 提醒本日為除權息的股票有那些
 pub async fn execute() -> Result<()> {
@@ -15,30 +19,44 @@ pub async fn execute() -> Result<()> {
         return Ok(());
     }
 
-    let mut stock_symbols: Vec<String> = Vec::with_capacity(stocks_dividend_info.len());
-    let mut msg = String::with_capacity(2048);
-    if writeln!(&mut msg, "{} 進行除權息的股票如下︰", today).is_ok() {
-        for stock in stocks_dividend_info {
-            stock_symbols.push(stock.stock_symbol.to_string());
-            let _ = writeln!(
-                &mut msg,
-                "    [{0}](https://tw.stock.yahoo.com/quote/{0}) {1} 現金︰{2}元({6}%) 股票 {3}元 合計︰{4}元({7}%) 昨收價:{5} 現金殖利率:{6}% 殖利率:{7}%",
-                stock.stock_symbol,
-                stock.name,
-                stock.cash_dividend.normalize(),
-                stock.stock_dividend.normalize(),
-                stock.sum.normalize(),
-                stock.closing_price.normalize(),
-                stock.cash_dividend_yield.normalize(),
-                stock.dividend_yield.normalize()
-            );
-        }
-    }
+    let stock_symbols: Vec<String> = stocks_dividend_info
+        .iter()
+        .map(|stock| stock.stock_symbol.to_string())
+        .collect();
 
     //計算股利
     calculation::dividend_record::execute(today.year(), Some(stock_symbols)).await;
-    //群內通知
-    bot::telegram::send(&msg).await;
+    //群內通知，以 /subscribe 訂閱股利的聊天室只收到訂閱的股票
+    bot::telegram::send_subscribed(
+        Event::Dividend,
+        &stocks_dividend_info,
+        |stock| &stock.stock_symbol,
+        None,
+        |language, list| {
+            let stocks: Vec<_> = list
+                .iter()
+                .map(|stock| {
+                    context! {
+                        symbol => stock.stock_symbol,
+                        name => fmt::escape_markdown(&stock.name),
+                        cash => stock.cash_dividend.normalize(),
+                        stock => stock.stock_dividend.normalize(),
+                        sum => stock.sum.normalize(),
+                        closing_price => stock.closing_price.normalize(),
+                        cash_yield => stock.cash_dividend_yield.normalize(),
+                        dividend_yield => stock.dividend_yield.normalize(),
+                    }
+                })
+                .collect();
+
+            i18n::text(
+                language,
+                "ex_dividend",
+                context! { date => today, stocks => stocks },
+            )
+        },
+    )
+    .await;
     Ok(())
 }
 
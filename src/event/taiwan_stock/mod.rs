@@ -1,12 +1,34 @@
+/// 重大訊息
+pub mod announcement;
 /// 財務年報
 pub mod annual_eps;
+/// 庫藏股買回計畫
+pub mod buyback;
 /// 收盤事件
 pub mod closing;
+/// 股東會與法說會的提醒
+pub mod corporate_event;
+/// 年度股利所得的報稅報表
+pub mod dividend_tax;
+/// 估價模型命中率的月報
+pub mod estimate_performance;
 /// 除息日的事件
 pub mod ex_dividend;
+/// 收盤後的漲跌幅排行與爆量股票
+pub mod movers;
+/// 收盤前庫存股票的最佳五檔快照
+pub mod order_book;
 /// 股利發放日的事件
 pub mod payable_date;
+/// 庫存的週報與月報
+pub mod portfolio_summary;
+/// 庫存的停損與停利通知
+pub mod protection;
 /// 公開申購公告
 pub mod public;
 /// 財務季報
 pub mod quarter_eps;
+/// 依目標配置的再平衡建議
+pub mod rebalance;
+/// 收盤後各產業的平均漲跌幅
+pub mod sector;
@@ -0,0 +1,199 @@
+use std::cmp::Reverse;
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use minijinja::context;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::{
+    bot::{
+        telegram::fmt::{self, Align, Table},
+        Notifier, TelegramNotifier,
+    },
+    database::table::daily_quote::{self, extension::DailyMover},
+    i18n,
+};
+
+/// 漲幅、跌幅、爆量各列出的股票數量
+const TOP_MOVERS: usize = 10;
+/// 成交量超過前 20 個交易日平均的幾倍視為爆量
+const VOLUME_SPIKE_RATIO: Decimal = dec!(3);
+
+/// 收盤後發送全市場與庫存股票的漲跌幅前十名及爆量股票
+pub async fn execute(date: NaiveDate) -> Result<()> {
+    report(date, &TelegramNotifier).await
+}
+
+async fn report(date: NaiveDate, notifier: &dyn Notifier) -> Result<()> {
+    let movers = daily_quote::fetch_daily_movers(date).await?;
+    if movers.is_empty() {
+        return Ok(());
+    }
+
+    notifier.notify(&render(date, false, &movers)).await;
+
+    let held: Vec<DailyMover> = movers.into_iter().filter(|m| m.is_held).collect();
+    if !held.is_empty() {
+        notifier.notify(&render(date, true, &held)).await;
+    }
+
+    Ok(())
+}
+
+/// 漲幅由大到小的前 TOP_MOVERS 檔，只列出上漲的股票
+fn gainers(movers: &[DailyMover]) -> Vec<&DailyMover> {
+    let mut list: Vec<&DailyMover> = movers
+        .iter()
+        .filter(|m| m.change_range > Decimal::ZERO)
+        .collect();
+    list.sort_by_key(|m| (Reverse(m.change_range), m.stock_symbol.clone()));
+    list.truncate(TOP_MOVERS);
+    list
+}
+
+/// 跌幅由大到小的前 TOP_MOVERS 檔，只列出下跌的股票
+fn losers(movers: &[DailyMover]) -> Vec<&DailyMover> {
+    let mut list: Vec<&DailyMover> = movers
+        .iter()
+        .filter(|m| m.change_range < Decimal::ZERO)
+        .collect();
+    list.sort_by_key(|m| (m.change_range, m.stock_symbol.clone()));
+    list.truncate(TOP_MOVERS);
+    list
+}
+
+/// 成交量為前 20 個交易日平均 VOLUME_SPIKE_RATIO 倍以上的股票，依倍數由大到小
+fn volume_spikes(movers: &[DailyMover]) -> Vec<(&DailyMover, Decimal)> {
+    let mut list: Vec<(&DailyMover, Decimal)> = movers
+        .iter()
+        .filter(|m| m.average_volume_20 > Decimal::ZERO)
+        .map(|m| (m, m.trading_volume / m.average_volume_20))
+        .filter(|(_, ratio)| *ratio > VOLUME_SPIKE_RATIO)
+        .collect();
+    list.sort_by_key(|(m, ratio)| (Reverse(*ratio), m.stock_symbol.clone()));
+    list.truncate(TOP_MOVERS);
+    list
+}
+
+/// 依範本 daily_movers 組成訊息，held 為 true 時是庫存股票的排行
+fn render(date: NaiveDate, held: bool, movers: &[DailyMover]) -> String {
+    let table = |list: Vec<&DailyMover>| (!list.is_empty()).then(|| change_table(&list));
+    let spikes = volume_spikes(movers);
+
+    i18n::text(
+        i18n::DEFAULT_LANGUAGE,
+        "daily_movers",
+        context! {
+            date => date,
+            held => held,
+            gainers => table(gainers(movers)),
+            losers => table(losers(movers)),
+            spikes => (!spikes.is_empty()).then(|| spike_table(&spikes)),
+            spike_ratio => VOLUME_SPIKE_RATIO,
+        },
+    )
+}
+
+fn change_table(list: &[&DailyMover]) -> String {
+    let mut table = Table::new(&["代號", "名稱", "收盤價", "漲跌幅"]).align(&[
+        Align::Left,
+        Align::Left,
+        Align::Right,
+        Align::Right,
+    ]);
+    for m in list {
+        table.row(&[
+            m.stock_symbol.to_string(),
+            m.name.clone(),
+            m.closing_price.normalize().to_string(),
+            fmt::percent(m.change_range),
+        ]);
+    }
+
+    table.render()
+}
+
+fn spike_table(spikes: &[(&DailyMover, Decimal)]) -> String {
+    let mut table = Table::new(&["代號", "名稱", "成交張數", "均量倍數"]).align(&[
+        Align::Left,
+        Align::Left,
+        Align::Right,
+        Align::Right,
+    ]);
+    for (m, ratio) in spikes {
+        table.row(&[
+            m.stock_symbol.to_string(),
+            m.name.clone(),
+            fmt::number(m.trading_volume / dec!(1000), 0),
+            format!("{}x", fmt::number(*ratio, 1)),
+        ]);
+    }
+
+    table.render()
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn mover(symbol: &str, change_range: Decimal, volume: Decimal, average: Decimal) -> DailyMover {
+        DailyMover {
+            stock_symbol: symbol.parse().unwrap(),
+            name: symbol.to_string(),
+            closing_price: dec!(100),
+            change: change_range,
+            change_range,
+            trading_volume: volume,
+            average_volume_20: average,
+            is_held: false,
+        }
+    }
+
+    fn symbols(list: &[&DailyMover]) -> Vec<String> {
+        list.iter().map(|m| m.stock_symbol.to_string()).collect()
+    }
+
+    #[test]
+    fn test_rank() {
+        let mut movers: Vec<DailyMover> = (0..15)
+            .map(|i| {
+                mover(
+                    &format!("{}", 1100 + i),
+                    Decimal::from(i),
+                    dec!(1000),
+                    dec!(1000),
+                )
+            })
+            .collect();
+        movers.push(mover("2330", dec!(-9.8), dec!(4000), dec!(1000)));
+        movers.push(mover("2303", dec!(-1.5), dec!(3000), dec!(1000)));
+        movers.push(mover("2884", dec!(0.5), dec!(9000), Decimal::ZERO));
+
+        let top = gainers(&movers);
+        assert_eq!(top.len(), TOP_MOVERS);
+        assert_eq!(top[0].stock_symbol, "1114");
+        assert_eq!(symbols(&losers(&movers)), vec!["2330", "2303"]);
+
+        let spikes = volume_spikes(&movers);
+        assert_eq!(spikes.len(), 1);
+        assert_eq!(spikes[0].0.stock_symbol, "2330");
+        assert_eq!(spikes[0].1, dec!(4));
+    }
+
+    #[test]
+    fn test_render() {
+        let movers = vec![
+            mover("2330", dec!(5), dec!(4000), dec!(1000)),
+            mover("2303", dec!(1), dec!(1000), dec!(1000)),
+        ];
+        let date = NaiveDate::from_ymd_opt(2024, 8, 23).unwrap();
+
+        let msg = render(date, true, &movers);
+
+        assert!(msg.starts_with("2024-08-23 庫存\n漲幅\n```\n"));
+        assert!(!msg.contains("\n跌幅\n"));
+        assert!(msg.contains("成交量超過 20 日均量 3 倍\n```\n"));
+    }
+}
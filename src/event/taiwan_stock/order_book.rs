@@ -0,0 +1,57 @@
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use chrono::Local;
+
+use crate::{
+    cache::SHARE,
+    crawler::twse,
+    database::table::stock_ownership_details::StockOwnershipDetail,
+    declare::{StockExchangeMarket, StockSymbol},
+    logging,
+    util::datetime::Weekend,
+};
+
+/// 收盤前每分鐘記錄庫存股票的最佳五檔，供分析收盤集合競價的委託變化
+///
+/// 相同揭示時間的快照只會寫入一次，休市日查到的是前一個交易日的最後一筆而被略過
+pub async fn execute() -> Result<()> {
+    if Local::now().is_weekend() {
+        return Ok(());
+    }
+
+    let codes: BTreeSet<StockSymbol> = StockOwnershipDetail::fetch(None)
+        .await?
+        .into_iter()
+        .map(|detail| detail.security_code)
+        .collect();
+
+    let mut symbols = Vec::with_capacity(codes.len());
+    for code in codes {
+        let Some(stock) = SHARE.get_stock(&code).await else {
+            continue;
+        };
+        if stock.stock_exchange_market_id == StockExchangeMarket::Listed.serial() {
+            symbols.push((code, false));
+        } else if stock.stock_exchange_market_id == StockExchangeMarket::OverTheCounter.serial() {
+            symbols.push((code, true));
+        }
+    }
+
+    if symbols.is_empty() {
+        return Ok(());
+    }
+
+    let mut inserted = 0;
+    for snapshot in twse::mis::visit(&symbols).await? {
+        match snapshot.insert().await {
+            Ok(true) => inserted += 1,
+            Ok(false) => {}
+            Err(why) => logging::error_file_async(format!("{:?}", why)),
+        }
+    }
+
+    logging::debug_file_async(format!("記錄最佳五檔快照:{}", inserted));
+
+    Ok(())
+}
@@ -1,11 +1,17 @@
-use std::fmt::Write;
+use std::collections::HashSet;
 
 use anyhow::Result;
 use chrono::{Local, NaiveDate};
+use minijinja::context;
 
-use crate::{bot, database::table::dividend};
+use crate::{
+    bot::{self, subscription::Event, telegram::fmt},
+    database::table::dividend,
+    declare::StockSymbol,
+    i18n,
+};
 
-/// 提提醒本日發放股利的股票(只通知自已有的股票)
+/// 提提醒本日發放股利的股票(只通知自已有的股票，以 /subscribe 訂閱股利的聊天室只收到訂閱的股票)
 pub async fn execute() -> Result<()> {
     let today: NaiveDate = Local::now().date_naive();
     let stocks_payable_date_info =
@@ -14,31 +20,40 @@ pub async fn execute() -> Result<()> {
         return Ok(());
     }
 
-    let mut stock_symbols: Vec<String> = Vec::with_capacity(stocks_payable_date_info.len());
-    let mut msg = String::with_capacity(2048);
-    if writeln!(&mut msg, "{} 進行股利發放的股票如下︰", today).is_ok() {
-        for stock in stocks_payable_date_info {
-            stock_symbols.push(stock.stock_symbol.to_string());
-            let _ = write!(&mut msg, "    {0} {1} ", stock.stock_symbol, stock.name, );
-
-            if stock.payable_date1 != "-" {
-                let _ = write!(&mut msg, "現金︰{0}元 ", stock.cash_dividend.normalize(), );
-            }
-
-            if stock.payable_date2 != "-" {
-                let _ = write!(&mut msg, "股票︰{0}元 ", stock.stock_dividend.normalize(), );
-            }
-
-
-            let _ = write!(&mut msg, "合計︰{0}元 ", stock.sum.normalize(), );
-
-
-            let _ = writeln!(&mut msg);
-        }
-    }
+    let held: HashSet<StockSymbol> = stocks_payable_date_info
+        .iter()
+        .filter(|stock| stock.is_held)
+        .map(|stock| stock.stock_symbol.clone())
+        .collect();
 
     //群內通知
-    bot::telegram::send(&msg).await;
+    bot::telegram::send_subscribed(
+        Event::Dividend,
+        &stocks_payable_date_info,
+        |stock| &stock.stock_symbol,
+        Some(&held),
+        |language, list| {
+            let stocks: Vec<_> = list
+                .iter()
+                .map(|stock| {
+                    context! {
+                        symbol => stock.stock_symbol,
+                        name => fmt::escape_markdown(&stock.name),
+                        cash => (stock.payable_date1 != "-").then(|| stock.cash_dividend.normalize()),
+                        stock => (stock.payable_date2 != "-").then(|| stock.stock_dividend.normalize()),
+                        sum => stock.sum.normalize(),
+                    }
+                })
+                .collect();
+
+            i18n::text(
+                language,
+                "payable_date",
+                context! { date => today, stocks => stocks },
+            )
+        },
+    )
+    .await;
     Ok(())
 }
 
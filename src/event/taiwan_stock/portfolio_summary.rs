@@ -0,0 +1,616 @@
+use std::cmp::Reverse;
+
+use anyhow::Result;
+use chrono::{Datelike, Local, Months, NaiveDate, TimeDelta};
+use minijinja::context;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::{
+    bot::{
+        command, lot as lot_command, note,
+        telegram::fmt::{self, Align, Table},
+        Notifier, TelegramNotifier,
+    },
+    calculation::{
+        allocation::{self, Allocation},
+        lot::{self, TagRealized},
+        money_history::{self, BenchmarkComparison},
+        risk,
+        xirr::{self, MemberXirr},
+    },
+    database::table::{
+        daily_money_history::DailyMoneyHistory,
+        daily_quote::{self, extension::PriceChange},
+        dividend::extension::held_dividend::{self, ReceivedDividend, UpcomingExDividend},
+        insider_shareholding::{InsiderShareholding, LargeDecrease},
+        risk_metric::RiskMetric,
+        trade_note::TradeNote,
+    },
+    i18n,
+};
+
+/// 漲幅、跌幅各列出的股票數量
+const TOP_MOVERS: usize = 3;
+/// 累計報酬比較的起始日，實際從第一筆庫存報酬開始
+const CUMULATIVE_SINCE: NaiveDate = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+
+/// 摘要的統計區間
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Period {
+    /// 週報，每週日晚上統計最近七天
+    Weekly,
+    /// 月報，每月一日統計上個月
+    Monthly,
+}
+
+impl Period {
+    /// 範本內判斷週報或月報的名稱
+    fn name(&self) -> &'static str {
+        match self {
+            Period::Weekly => "weekly",
+            Period::Monthly => "monthly",
+        }
+    }
+
+    /// 依執行日取得統計區間(期初, 期末)，期初為上一期的期末，比較兩天的收盤市值
+    fn range(&self, today: NaiveDate) -> (NaiveDate, NaiveDate) {
+        match self {
+            Period::Weekly => (today - TimeDelta::try_days(7).unwrap_or_default(), today),
+            Period::Monthly => {
+                let first_day = today.with_day(1).unwrap_or(today);
+                let end = first_day.pred_opt().unwrap_or(first_day);
+                let start = end.with_day(1).unwrap_or(end).pred_opt().unwrap_or(end);
+                (start, end)
+            }
+        }
+    }
+
+    /// 執行日之後要提醒的除權息區間
+    fn upcoming(&self, today: NaiveDate) -> (NaiveDate, NaiveDate) {
+        let tomorrow = today.succ_opt().unwrap_or(today);
+        match self {
+            Period::Weekly => (tomorrow, today + TimeDelta::try_days(7).unwrap_or_default()),
+            Period::Monthly => (
+                tomorrow,
+                today.checked_add_months(Months::new(1)).unwrap_or(today),
+            ),
+        }
+    }
+}
+
+/// 組成摘要所需的數據
+#[derive(Debug)]
+struct Summary {
+    start: NaiveDate,
+    end: NaiveDate,
+    /// 期初市值
+    start_value: Option<Decimal>,
+    /// 期末市值
+    end_value: Option<Decimal>,
+    changes: Vec<PriceChange>,
+    received: Vec<ReceivedDividend>,
+    upcoming: Vec<UpcomingExDividend>,
+    allocations: Vec<Allocation>,
+    /// 只有月報會列出期末的風險指標
+    risks: Vec<RiskMetric>,
+    /// 只有月報會列出當月與累計的報酬比較
+    benchmarks: Vec<(&'static str, BenchmarkComparison)>,
+    /// 只有月報會列出各成員截至期末的 XIRR
+    xirrs: Vec<MemberXirr>,
+    /// 只有月報會列出期間內新增的交易筆記
+    notes: Vec<TradeNote>,
+    /// 只有月報會列出期間內賣出的已實現損益依標籤的彙總
+    tags: Vec<TagRealized>,
+    /// 只有月報會列出期間內寫入的董監事持股大幅減少的庫存股票
+    decreases: Vec<LargeDecrease>,
+}
+
+/// 每週日晚上發送庫存的週報
+pub async fn weekly() -> Result<()> {
+    execute(Period::Weekly, Local::now().date_naive(), &TelegramNotifier).await
+}
+
+/// 每月一日發送庫存上個月的月報
+pub async fn monthly() -> Result<()> {
+    execute(
+        Period::Monthly,
+        Local::now().date_naive(),
+        &TelegramNotifier,
+    )
+    .await
+}
+
+/// 統計庫存在區間內的市值變化、漲跌幅、入帳股利與即將除權息的股票後送出摘要
+pub async fn execute(period: Period, today: NaiveDate, notifier: &dyn Notifier) -> Result<()> {
+    let (start, end) = period.range(today);
+    let (upcoming_start, upcoming_end) = period.upcoming(today);
+    let (risks, benchmarks, xirrs, notes, tags, decreases) = match period {
+        Period::Weekly => (
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+        ),
+        Period::Monthly => {
+            let first_day = start.succ_opt().unwrap_or(start);
+            let (sales, sale_notes) = lot::fetch_with_notes(None, first_day, end).await?;
+            (
+                RiskMetric::fetch_on_or_before(end).await?,
+                fetch_benchmarks(start, end).await?,
+                xirr::calculate(end).await?,
+                TradeNote::fetch_created_between(first_day, end).await?,
+                lot::realized_by_tag(&sales, &sale_notes),
+                InsiderShareholding::fetch_large_decreases(first_day, end).await?,
+            )
+        }
+    };
+
+    let summary = Summary {
+        start,
+        end,
+        start_value: DailyMoneyHistory::fetch_on_or_before(start)
+            .await?
+            .map(|mh| mh.sum),
+        end_value: DailyMoneyHistory::fetch_on_or_before(end)
+            .await?
+            .map(|mh| mh.sum),
+        changes: daily_quote::fetch_held_price_changes(start, end).await?,
+        received: held_dividend::fetch_received(start.succ_opt().unwrap_or(start), end).await?,
+        upcoming: held_dividend::fetch_upcoming(upcoming_start, upcoming_end).await?,
+        allocations: allocation::calculate().await?,
+        risks,
+        benchmarks,
+        xirrs,
+        notes,
+        tags,
+        decreases,
+    };
+
+    notifier.notify(&compose(period, &summary)).await;
+
+    Ok(())
+}
+
+/// 當期與自開始記錄以來的庫存報酬比較
+async fn fetch_benchmarks(
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<Vec<(&'static str, BenchmarkComparison)>> {
+    let mut benchmarks = Vec::with_capacity(2);
+    if let Some(comparison) = money_history::compare_with_benchmarks(start, end).await? {
+        benchmarks.push(("本期", comparison));
+    }
+    if let Some(comparison) = money_history::compare_with_benchmarks(CUMULATIVE_SINCE, end).await? {
+        benchmarks.push(("累計", comparison));
+    }
+
+    Ok(benchmarks)
+}
+
+/// 期間漲跌幅(%)
+fn change_percent(change: &PriceChange) -> Decimal {
+    if change.start_price.is_zero() {
+        return Decimal::ZERO;
+    }
+
+    (change.end_price - change.start_price) / change.start_price * dec!(100)
+}
+
+/// 依漲跌幅排序後取出漲幅最大與跌幅最大的各 n 檔，只列出確實上漲或下跌的股票
+fn top_movers(changes: &[PriceChange], n: usize) -> (Vec<&PriceChange>, Vec<&PriceChange>) {
+    let mut sorted: Vec<&PriceChange> = changes.iter().collect();
+    sorted.sort_by_key(|c| Reverse(change_percent(c)));
+
+    let gainers = sorted
+        .iter()
+        .filter(|c| change_percent(c) > Decimal::ZERO)
+        .take(n)
+        .copied()
+        .collect();
+    let losers = sorted
+        .iter()
+        .rev()
+        .filter(|c| change_percent(c) < Decimal::ZERO)
+        .take(n)
+        .copied()
+        .collect();
+
+    (gainers, losers)
+}
+
+fn movers_table(movers: &[&PriceChange]) -> String {
+    let mut table = Table::new(&["代號", "名稱", "收盤價", "漲跌幅"]).align(&[
+        Align::Left,
+        Align::Left,
+        Align::Right,
+        Align::Right,
+    ]);
+    for change in movers {
+        table.row(&[
+            change.stock_symbol.to_string(),
+            change.name.clone(),
+            change.end_price.normalize().to_string(),
+            format!("{}%", fmt::number(change_percent(change), 2)),
+        ]);
+    }
+
+    table.render()
+}
+
+fn benchmark_table(benchmarks: &[(&str, BenchmarkComparison)]) -> String {
+    let mut table = Table::new(&["期間", "庫存", "0050", "加權", "超額"]).align(&[
+        Align::Left,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+    ]);
+    let percent = |value: Decimal| format!("{}%", fmt::number(value, 2));
+    for (label, comparison) in benchmarks {
+        table.row(&[
+            label.to_string(),
+            percent(comparison.portfolio),
+            percent(comparison.etf),
+            percent(comparison.index),
+            percent(comparison.excess_over_etf()),
+        ]);
+    }
+
+    table.render()
+}
+
+/// 整體庫存排在第一列
+fn risk_table(risks: &[RiskMetric]) -> String {
+    let mut table = Table::new(&["代號", "名稱", "Beta", "波動度", "夏普"]).align(&[
+        Align::Left,
+        Align::Left,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+    ]);
+    let (portfolio, stocks): (Vec<&RiskMetric>, Vec<&RiskMetric>) = risks
+        .iter()
+        .partition(|r| r.security_code == risk::PORTFOLIO);
+    for r in portfolio.into_iter().chain(stocks) {
+        let (code, name) = if r.security_code == risk::PORTFOLIO {
+            ("合計".to_string(), String::new())
+        } else {
+            (r.security_code.clone(), r.name.clone())
+        };
+        table.row(&[
+            code,
+            name,
+            fmt::number(r.beta, 2),
+            format!("{}%", fmt::number(r.volatility, 2)),
+            fmt::number(r.sharpe_ratio, 2),
+        ]);
+    }
+
+    table.render()
+}
+
+/// 依範本 portfolio_summary 組成摘要
+fn compose(period: Period, summary: &Summary) -> String {
+    let (market_change, market_percent) = match (summary.start_value, summary.end_value) {
+        (Some(start), Some(end)) if !start.is_zero() => {
+            let diff = end - start;
+            (
+                Some(fmt::number(diff, 0)),
+                Some(fmt::percent(diff / start * dec!(100))),
+            )
+        }
+        _ => (None, None),
+    };
+    let (gainers, losers) = top_movers(&summary.changes, TOP_MOVERS);
+    // 只列出超過集中度門檻的成員，完整的比重可用 /allocation 查詢
+    let concentration: Vec<_> = summary
+        .allocations
+        .iter()
+        .flat_map(|a| {
+            a.warnings.iter().map(move |w| {
+                context! { member_id => a.member_id, warning => fmt::escape_markdown(w) }
+            })
+        })
+        .collect();
+    let received: Vec<_> = summary
+        .received
+        .iter()
+        .map(|dividend| {
+            context! {
+                payable_date => dividend.payable_date,
+                stock_symbol => dividend.stock_symbol,
+                name => fmt::escape_markdown(&dividend.name),
+                cash_dividend => dividend.cash_dividend.normalize(),
+                share_quantity => fmt::thousands(dividend.share_quantity),
+                amount => fmt::number(received_amount(dividend), 0),
+            }
+        })
+        .collect();
+    let upcoming: Vec<_> = summary
+        .upcoming
+        .iter()
+        .map(|stock| {
+            context! {
+                ex_dividend_date => stock.ex_dividend_date,
+                stock_symbol => stock.stock_symbol,
+                name => fmt::escape_markdown(&stock.name),
+                cash_dividend => (stock.cash_dividend > Decimal::ZERO)
+                    .then(|| stock.cash_dividend.normalize()),
+                stock_dividend => (stock.stock_dividend > Decimal::ZERO)
+                    .then(|| stock.stock_dividend.normalize()),
+            }
+        })
+        .collect();
+
+    let decreases: Vec<_> = summary
+        .decreases
+        .iter()
+        .map(|d| {
+            context! {
+                year => d.year,
+                month => format!("{:02}", d.month),
+                security_code => d.security_code,
+                name => fmt::escape_markdown(&d.name),
+                previous_lots => fmt::thousands(d.previous_shares_held / 1000),
+                current_lots => fmt::thousands(d.shares_held / 1000),
+                percent => fmt::percent(-d.decrease_percent),
+            }
+        })
+        .collect();
+
+    i18n::text(
+        i18n::DEFAULT_LANGUAGE,
+        "portfolio_summary",
+        context! {
+            period => period.name(),
+            start_date => summary.start,
+            end_date => summary.end,
+            market_value => summary.end_value.map(|end| fmt::number(end, 0)),
+            market_change => market_change,
+            market_percent => market_percent,
+            gainers => (!gainers.is_empty()).then(|| movers_table(&gainers)),
+            gainers_count => gainers.len(),
+            losers => (!losers.is_empty()).then(|| movers_table(&losers)),
+            losers_count => losers.len(),
+            concentration => concentration,
+            benchmarks => (!summary.benchmarks.is_empty())
+                .then(|| benchmark_table(&summary.benchmarks)),
+            xirrs => (!summary.xirrs.is_empty()).then(|| command::xirr_table(&summary.xirrs)),
+            risks => (!summary.risks.is_empty()).then(|| risk_table(&summary.risks)),
+            notes => summary.notes.iter().map(note::format_note).collect::<Vec<_>>(),
+            tags => (!summary.tags.is_empty()).then(|| lot_command::tag_table(&summary.tags)),
+            decreases => decreases,
+            received => received,
+            received_total => fmt::number(summary.received.iter().map(received_amount).sum(), 0),
+            upcoming => upcoming,
+        },
+    )
+}
+
+/// 入帳的現金股利金額
+fn received_amount(dividend: &ReceivedDividend) -> Decimal {
+    dividend.cash_dividend * Decimal::from(dividend.share_quantity)
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn change(stock_symbol: &str, start_price: Decimal, end_price: Decimal) -> PriceChange {
+        PriceChange {
+            stock_symbol: stock_symbol.parse().unwrap(),
+            name: format!("{}名稱", stock_symbol),
+            start_price,
+            end_price,
+        }
+    }
+
+    #[test]
+    fn test_range() {
+        assert_eq!(
+            Period::Weekly.range(date(2024, 8, 25)),
+            (date(2024, 8, 18), date(2024, 8, 25))
+        );
+        assert_eq!(
+            Period::Monthly.range(date(2024, 3, 1)),
+            (date(2024, 1, 31), date(2024, 2, 29))
+        );
+        assert_eq!(
+            Period::Monthly.upcoming(date(2024, 1, 31)),
+            (date(2024, 2, 1), date(2024, 2, 29))
+        );
+    }
+
+    #[test]
+    fn test_top_movers() {
+        let changes = vec![
+            change("1101", dec!(100), dec!(110)),
+            change("1102", dec!(100), dec!(90)),
+            change("1103", dec!(100), dec!(100)),
+            change("1104", dec!(50), dec!(60)),
+            change("1105", dec!(20), dec!(19)),
+        ];
+
+        let (gainers, losers) = top_movers(&changes, 3);
+        let symbols = |list: Vec<&PriceChange>| {
+            list.iter()
+                .map(|c| c.stock_symbol.as_str())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+
+        assert_eq!(symbols(gainers), "1104,1101");
+        assert_eq!(symbols(losers), "1102,1105");
+    }
+
+    #[test]
+    fn test_compose() {
+        let summary = Summary {
+            start: date(2024, 8, 18),
+            end: date(2024, 8, 25),
+            start_value: Some(dec!(1000000)),
+            end_value: Some(dec!(1010000)),
+            changes: vec![change("2330", dec!(900), dec!(950))],
+            received: vec![ReceivedDividend {
+                stock_symbol: "2884".parse().unwrap(),
+                name: "玉山金".to_string(),
+                payable_date: "2024-08-22".to_string(),
+                cash_dividend: dec!(0.5),
+                share_quantity: 2000,
+            }],
+            upcoming: vec![],
+            allocations: vec![],
+            risks: vec![],
+            benchmarks: vec![],
+            xirrs: vec![],
+            notes: vec![],
+            tags: vec![],
+            decreases: vec![],
+        };
+
+        let msg = compose(Period::Weekly, &summary);
+
+        assert!(msg.starts_with("📊 庫存週報 2024-08-18 ~ 2024-08-25\n"));
+        assert!(msg.contains("市值:1,010,000 10,000 (🔺1.00%)"));
+        assert!(msg.contains("漲幅前 1 名"));
+        assert!(!msg.contains("跌幅前"));
+        assert!(msg.contains("2024-08-22 2884 玉山金 每股 0.5 元 × 2,000 股 = 1,000 元"));
+        assert!(msg.contains("合計 1,000 元"));
+        assert!(msg.ends_with("即將除權息\n    無\n"));
+        assert!(!msg.contains("持股集中度"));
+    }
+
+    #[test]
+    fn test_compose_concentration() {
+        let holdings = vec![allocation::Holding {
+            member_id: 1,
+            stock_symbol: "2330".to_string(),
+            name: "台積電".to_string(),
+            industry: "半導體業".to_string(),
+            market_value: dec!(1000),
+            market_cap: dec!(1000),
+        }];
+        let summary = Summary {
+            start: date(2024, 8, 18),
+            end: date(2024, 8, 25),
+            start_value: None,
+            end_value: None,
+            changes: vec![],
+            received: vec![],
+            upcoming: vec![],
+            allocations: vec![allocation::analyze(1, &holdings, dec!(25), dec!(50))],
+            risks: vec![],
+            benchmarks: vec![],
+            xirrs: vec![],
+            notes: vec![],
+            tags: vec![],
+            decreases: vec![],
+        };
+
+        let msg = compose(Period::Weekly, &summary);
+
+        assert!(msg.contains("持股集中度\n    成員 1 單一股票 2330 台積電 佔 100%，超過 25%\n"));
+        assert!(msg.contains("    成員 1 產業 半導體業 佔 100%，超過 50%\n"));
+    }
+
+    #[test]
+    fn test_compose_notes() {
+        let summary = Summary {
+            start: date(2024, 6, 30),
+            end: date(2024, 7, 31),
+            start_value: None,
+            end_value: None,
+            changes: vec![],
+            received: vec![],
+            upcoming: vec![],
+            allocations: vec![],
+            risks: vec![],
+            benchmarks: vec![],
+            xirrs: vec![],
+            notes: vec![TradeNote {
+                serial: 5,
+                target: "sale".to_string(),
+                target_serial: 31,
+                security_code: "2330".parse().unwrap(),
+                tag: "earnings".to_string(),
+                note: "法說會後賣出".to_string(),
+                created_date: date(2024, 7, 19),
+                ..Default::default()
+            }],
+            tags: vec![TagRealized {
+                tag: "earnings".to_string(),
+                sales: 1,
+                cost: dec!(100000),
+                realized: dec!(8000),
+            }],
+            decreases: vec![LargeDecrease {
+                security_code: "1101".parse().unwrap(),
+                name: "台泥".to_string(),
+                year: 2024,
+                month: 6,
+                previous_shares_held: 10_000_000,
+                shares_held: 9_000_000,
+                decrease_percent: dec!(10),
+            }],
+        };
+
+        let msg = compose(Period::Monthly, &summary);
+
+        assert!(msg.contains("已實現損益(依標籤)\n```"));
+        assert!(msg.contains("8.00%"));
+        assert!(msg.contains("交易筆記\n    #5 2024-07-19 2330 賣出 #31 [earnings] 法說會後賣出\n"));
+        assert!(msg.contains(
+            "董監事持股大幅減少\n    2024/06 1101 台泥 10,000張 → 9,000張 (🔻-10.00%)\n"
+        ));
+    }
+
+    #[test]
+    fn test_risk_table() {
+        let metric = |code: &str, beta: Decimal| RiskMetric {
+            security_code: code.to_string(),
+            name: "台積電".to_string(),
+            beta,
+            volatility: dec!(25.1234),
+            sharpe_ratio: dec!(0.8),
+            sample_days: 240,
+            ..Default::default()
+        };
+
+        let table = risk_table(&[
+            metric("2330", dec!(1.2)),
+            metric(risk::PORTFOLIO, dec!(0.9)),
+        ]);
+        let rows: Vec<&str> = table.lines().filter(|l| l.contains("25.12%")).collect();
+
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].starts_with("合計"));
+        assert!(rows[0].contains("0.90"));
+        assert!(rows[1].starts_with("2330"));
+    }
+
+    #[test]
+    fn test_benchmark_table() {
+        let comparison = BenchmarkComparison {
+            start: date(2024, 1, 31),
+            end: date(2024, 2, 29),
+            portfolio: dec!(5.5),
+            etf: dec!(4),
+            index: dec!(3.25),
+        };
+
+        let table = benchmark_table(&[("本期", comparison)]);
+
+        assert!(table.contains("本期"));
+        assert!(table.contains("5.50%"));
+        assert!(table.contains("3.25%"));
+        assert!(table.contains("1.50%"));
+    }
+}
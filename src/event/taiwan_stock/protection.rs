@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::{Local, NaiveDate};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::{
+    bot::{self, telegram::fmt},
+    cache::SHARE,
+    crawler,
+    database::table::stock_ownership_details::{self, ProtectedPosition},
+    declare::StockSymbol,
+    logging, nosql,
+};
+
+/// 盤中觸及後在 Redis 記錄的秒數，期間內盤中與收盤後都不再重複通知
+const NOTIFIED_TTL_SECONDS: usize = 60 * 60 * 5;
+
+/// 價格觸及的停損或停利
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breach {
+    StopLoss,
+    TakeProfit,
+}
+
+impl Breach {
+    fn name(&self) -> &'static str {
+        match self {
+            Breach::StopLoss => "stop_loss",
+            Breach::TakeProfit => "take_profit",
+        }
+    }
+}
+
+/// 價格跌破(含)停損價或突破(含)停利價，沒有設定(0)的一方不判斷
+pub fn breach(stop_loss: Decimal, take_profit: Decimal, price: Decimal) -> Option<Breach> {
+    if price <= Decimal::ZERO {
+        return None;
+    }
+
+    if stop_loss > Decimal::ZERO && price <= stop_loss {
+        Some(Breach::StopLoss)
+    } else if take_profit > Decimal::ZERO && price >= take_profit {
+        Some(Breach::TakeProfit)
+    } else {
+        None
+    }
+}
+
+/// 收盤後檢查設定了停損或停利價的庫存，只通知當日才觸及(前一個交易日的收盤價尚未觸及)的庫存，回傳通知的筆數
+pub async fn execute(date: NaiveDate) -> Result<usize> {
+    let mut count = 0;
+    for position in stock_ownership_details::fetch_protected(date).await? {
+        let Some(breached) = breach(
+            position.stop_loss_price,
+            position.take_profit_price,
+            position.closing_price,
+        ) else {
+            continue;
+        };
+        let previous = breach(
+            position.stop_loss_price,
+            position.take_profit_price,
+            position.previous_closing_price,
+        );
+        if previous == Some(breached) || notified(&position, breached).await {
+            continue;
+        }
+
+        let msg = compose(&position, breached, "收盤價").await;
+        bot::alert::send(bot::alert::PROTECTION, &msg).await;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// 盤中以即時成交價檢查設定了停損或停利價的庫存，同一筆庫存觸及後 5 小時內不重複通知
+pub async fn check_intraday() -> Result<()> {
+    let positions = stock_ownership_details::fetch_protected(Local::now().date_naive()).await?;
+    let mut prices: HashMap<StockSymbol, Decimal> = HashMap::new();
+
+    for mut position in positions {
+        let price = match prices.get(&position.security_code) {
+            Some(price) => *price,
+            None => {
+                let price = crawler::fetch_stock_price_from_remote_site(&position.security_code)
+                    .await
+                    .unwrap_or_else(|why| {
+                        logging::error_file_async(format!("{:?}", why));
+                        Decimal::ZERO
+                    });
+                prices.insert(position.security_code.clone(), price);
+                price
+            }
+        };
+
+        let Some(breached) = breach(position.stop_loss_price, position.take_profit_price, price)
+        else {
+            continue;
+        };
+        if notified(&position, breached).await {
+            continue;
+        }
+
+        position.closing_price = price;
+        let msg = compose(&position, breached, "目前成交價").await;
+        if let Err(why) = nosql::redis::CLIENT
+            .set(
+                key(&position, breached),
+                price.to_string(),
+                NOTIFIED_TTL_SECONDS,
+            )
+            .await
+        {
+            logging::error_file_async(format!("{:?}", why));
+        }
+        bot::alert::send(bot::alert::PROTECTION, &msg).await;
+    }
+
+    Ok(())
+}
+
+fn key(position: &ProtectedPosition, breached: Breach) -> String {
+    format!("protection:{}:{}", position.serial, breached.name())
+}
+
+/// 盤中是否已通知過
+async fn notified(position: &ProtectedPosition, breached: Breach) -> bool {
+    nosql::redis::CLIENT
+        .contains_key(&key(position, breached))
+        .await
+        .unwrap_or(false)
+}
+
+async fn compose(position: &ProtectedPosition, breached: Breach, price_label: &str) -> String {
+    let name = SHARE
+        .get_stock(&position.security_code)
+        .await
+        .map(|stock| fmt::escape_markdown(&stock.name))
+        .unwrap_or_default();
+
+    format_message(position, breached, price_label, &name)
+}
+
+fn format_message(
+    position: &ProtectedPosition,
+    breached: Breach,
+    price_label: &str,
+    name: &str,
+) -> String {
+    let (icon, action, level) = match breached {
+        Breach::StopLoss => ("🛑", "跌破停損價", position.stop_loss_price),
+        Breach::TakeProfit => ("🎯", "突破停利價", position.take_profit_price),
+    };
+    let mut msg = format!(
+        "{} {} {} {} {}，{} {}\n成員 {} #{} {} 股",
+        icon,
+        position.security_code,
+        name,
+        action,
+        level.normalize(),
+        price_label,
+        position.closing_price.normalize(),
+        position.member_id,
+        position.serial,
+        fmt::thousands(position.share_quantity)
+    );
+    if position.share_price_average > Decimal::ZERO {
+        let rate =
+            (position.closing_price / position.share_price_average - Decimal::ONE) * dec!(100);
+        msg.push_str(&format!(
+            " 成本 {} 報酬 {}%",
+            position.share_price_average.normalize(),
+            fmt::number(rate, 2)
+        ));
+    }
+
+    msg
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_breach() {
+        assert_eq!(
+            breach(dec!(850), dec!(1200), dec!(850)),
+            Some(Breach::StopLoss)
+        );
+        assert_eq!(
+            breach(dec!(850), dec!(1200), dec!(1210)),
+            Some(Breach::TakeProfit)
+        );
+        assert_eq!(breach(dec!(850), dec!(1200), dec!(1000)), None);
+        assert_eq!(breach(Decimal::ZERO, dec!(1200), dec!(100)), None);
+        assert_eq!(breach(dec!(850), Decimal::ZERO, dec!(5000)), None);
+        assert_eq!(breach(dec!(850), dec!(1200), Decimal::ZERO), None);
+    }
+
+    #[test]
+    fn test_format_message() {
+        let position = ProtectedPosition {
+            serial: 123,
+            member_id: 1,
+            security_code: "2330".parse().unwrap(),
+            share_quantity: 2000,
+            share_price_average: dec!(900),
+            stop_loss_price: dec!(850.00),
+            take_profit_price: dec!(1200),
+            closing_price: dec!(840),
+            previous_closing_price: dec!(860),
+        };
+
+        assert_eq!(
+            format_message(&position, Breach::StopLoss, "收盤價", "台積電"),
+            "🛑 2330 台積電 跌破停損價 850，收盤價 840\n成員 1 #123 2,000 股 成本 900 報酬 -6.67%"
+        );
+    }
+}
@@ -9,7 +9,7 @@ use rust_decimal::{
 use rust_decimal_macros::dec;
 
 use crate::{
-    bot,
+    bot::{self, telegram::fmt},
     cache::SHARE,
     crawler,
     declare,
@@ -58,7 +58,7 @@ pub async fn execute() -> Result<()> {
                     &mut msg, "{stock_symbol} {stock_name} 起迄日︰{start}~{end} 承銷價︰{offering_price} 參考價︰{last_price} {price_change}發行市場:{market}",
                     market = stock.market,
                     stock_symbol = stock.stock_symbol,
-                    stock_name = stock.stock_name,
+                    stock_name = fmt::escape_markdown(&stock.stock_name),
                     start = start,
                     end = end,
                     offering_price = offering_price,
@@ -59,7 +59,7 @@ async fn process_eps(
     let eps = twse::eps::visit(market, year, quarter).await?;
 
     for mut e in eps {
-        if !without_financial_stocks.contains_key(&e.stock_symbol) {
+        if !without_financial_stocks.contains_key(e.stock_symbol.as_str()) {
             //不在清單內代表已收錄數據
             continue;
         }
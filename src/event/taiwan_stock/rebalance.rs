@@ -0,0 +1,142 @@
+use anyhow::Result;
+
+use crate::{
+    bot::{
+        telegram::fmt::{self, Align, Table},
+        Notifier, TelegramNotifier,
+    },
+    calculation::rebalance::{self, Plan, Suggestion},
+};
+
+/// 每月一日依設定檔的目標配置發送各成員的再平衡建議，未設定目標時不發送
+pub async fn execute() -> Result<()> {
+    report(&TelegramNotifier).await
+}
+
+pub async fn report(notifier: &dyn Notifier) -> Result<()> {
+    let plans = rebalance::calculate().await?;
+    if plans.is_empty() {
+        return Ok(());
+    }
+
+    notifier
+        .notify(&format!("⚖️ 再平衡建議\n{}", compose(&plans)))
+        .await;
+
+    Ok(())
+}
+
+/// 各成員的買賣建議表格，比重都在容許範圍內的成員只顯示一行說明
+pub fn compose(plans: &[Plan]) -> String {
+    plans
+        .iter()
+        .map(|plan| {
+            let mut section = format!(
+                "成員 {} 目標配置市值 {}",
+                plan.member_id,
+                fmt::number(plan.total, 0)
+            );
+            if plan.suggestions.is_empty() {
+                section.push_str("\n比重都在目標的容許範圍內，不需要調整");
+            } else {
+                let mut table = Table::new(&["代號", "名稱", "買賣", "數量", "比重", "費用"])
+                    .align(&[
+                        Align::Left,
+                        Align::Left,
+                        Align::Left,
+                        Align::Right,
+                        Align::Right,
+                        Align::Right,
+                    ]);
+                for s in &plan.suggestions {
+                    table.row(&[
+                        s.stock_symbol.clone(),
+                        s.name.clone(),
+                        s.side.label().to_string(),
+                        quantity(s),
+                        format!(
+                            "{}→{}%",
+                            fmt::number(s.current_percent, 1),
+                            fmt::number(s.target_percent, 1)
+                        ),
+                        fmt::number(s.cost, 0),
+                    ]);
+                }
+                section.push('\n');
+                section.push_str(&table.render());
+            }
+            if !plan.untargeted.is_empty() {
+                section.push_str(&format!("\n未設定目標: {}", plan.untargeted.join("、")));
+            }
+
+            section
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// 整張與零股的數量 ex. 2張300股、300股、2張
+fn quantity(suggestion: &Suggestion) -> String {
+    match (suggestion.lots(), suggestion.odd_shares()) {
+        (0, odd) => format!("{}股", odd),
+        (lots, 0) => format!("{}張", lots),
+        (lots, odd) => format!("{}張{}股", lots, odd),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use crate::calculation::rebalance::Side;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn suggestion(quantity: i64) -> Suggestion {
+        Suggestion {
+            stock_symbol: "0056".to_string(),
+            name: "元大高股息".to_string(),
+            side: Side::Buy,
+            quantity,
+            price: dec!(30),
+            current_percent: dec!(30),
+            target_percent: dec!(50),
+            cost: dec!(284),
+        }
+    }
+
+    #[test]
+    fn test_quantity() {
+        assert_eq!(quantity(&suggestion(6657)), "6張657股");
+        assert_eq!(quantity(&suggestion(2000)), "2張");
+        assert_eq!(quantity(&suggestion(300)), "300股");
+    }
+
+    #[test]
+    fn test_compose() {
+        let plans = vec![
+            Plan {
+                member_id: 1,
+                total: dec!(1000000),
+                suggestions: vec![suggestion(6657)],
+                untargeted: vec!["2884".to_string()],
+            },
+            Plan {
+                member_id: 2,
+                total: dec!(500000),
+                suggestions: Vec::new(),
+                untargeted: Vec::new(),
+            },
+        ];
+
+        let text = compose(&plans);
+
+        assert!(text.starts_with("成員 1 目標配置市值 1,000,000\n```"));
+        assert!(text.contains("買進"));
+        assert!(text.contains("6張657股"));
+        assert!(text.contains("30.0→50.0%"));
+        assert!(text.contains("未設定目標: 2884"));
+        assert!(text.ends_with("成員 2 目標配置市值 500,000\n比重都在目標的容許範圍內，不需要調整"));
+    }
+}
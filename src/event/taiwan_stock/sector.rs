@@ -0,0 +1,164 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use anyhow::Result;
+use chrono::{Days, NaiveDate};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::{
+    bot::{telegram::fmt, Notifier, TelegramNotifier},
+    cache::SHARE,
+    database::table::sector_daily_performance::SectorDailyPerformance,
+};
+
+/// 累計漲跌幅的交易日數
+const TREND_DAYS: usize = 5;
+/// 往前查詢的日曆天數，需涵蓋 TREND_DAYS 個交易日與連假
+const TREND_LOOKBACK_DAYS: u64 = 14;
+
+/// 彙總指定日期各產業的平均漲跌幅並寫入 sector_daily_performance 表
+pub async fn aggregate(date: NaiveDate) -> Result<u64> {
+    Ok(SectorDailyPerformance::upsert(date).await?.rows_affected())
+}
+
+/// 收盤後發送各產業當日平均漲跌幅的熱度列表
+pub async fn execute(date: NaiveDate) -> Result<()> {
+    report(date, &TelegramNotifier).await
+}
+
+async fn report(date: NaiveDate, notifier: &dyn Notifier) -> Result<()> {
+    let from = date
+        .checked_sub_days(Days::new(TREND_LOOKBACK_DAYS))
+        .unwrap_or(date);
+    let history = SectorDailyPerformance::fetch_between(from, date).await?;
+    let rows = summarize(date, &history);
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    notifier.notify(&render(date, &rows)).await;
+
+    Ok(())
+}
+
+/// 單一產業當日與近 TREND_DAYS 個交易日的表現
+#[derive(Debug, PartialEq)]
+struct SectorRow {
+    stock_industry_id: i32,
+    average_change_range: Decimal,
+    advancing: i32,
+    declining: i32,
+    /// 近 TREND_DAYS 個交易日的平均漲跌幅加總
+    trend_change_range: Decimal,
+}
+
+/// 取出指定日期各產業的表現並依平均漲跌幅由大到小排序，history 需依日期由舊到新排序
+fn summarize(date: NaiveDate, history: &[SectorDailyPerformance]) -> Vec<SectorRow> {
+    let mut trends: HashMap<i32, Vec<Decimal>> = HashMap::new();
+    for sector in history {
+        trends
+            .entry(sector.stock_industry_id)
+            .or_default()
+            .push(sector.average_change_range);
+    }
+
+    let mut rows: Vec<SectorRow> = history
+        .iter()
+        .filter(|sector| sector.date == date)
+        .map(|sector| {
+            let trend = &trends[&sector.stock_industry_id];
+            SectorRow {
+                stock_industry_id: sector.stock_industry_id,
+                average_change_range: sector.average_change_range,
+                advancing: sector.advancing,
+                declining: sector.declining,
+                trend_change_range: trend.iter().rev().take(TREND_DAYS).sum(),
+            }
+        })
+        .collect();
+    rows.sort_by_key(|row| (Reverse(row.average_change_range), row.stock_industry_id));
+    rows
+}
+
+/// 依平均漲跌幅決定熱度，台股習慣紅漲綠跌
+fn heat(change_range: Decimal) -> &'static str {
+    if change_range >= dec!(2) {
+        "🟥"
+    } else if change_range >= dec!(0.5) {
+        "🔴"
+    } else if change_range > dec!(-0.5) {
+        "⚪"
+    } else if change_range > dec!(-2) {
+        "🟢"
+    } else {
+        "🟩"
+    }
+}
+
+fn render(date: NaiveDate, rows: &[SectorRow]) -> String {
+    let mut lines = vec![format!(
+        "{} 產業熱度(平均漲跌幅 漲/跌家數 近{}日累計)",
+        date, TREND_DAYS
+    )];
+
+    for row in rows {
+        let name = SHARE
+            .get_industry_name(row.stock_industry_id)
+            .unwrap_or_else(|| row.stock_industry_id.to_string());
+        lines.push(format!(
+            "{} {} {} {}/{} {}%",
+            heat(row.average_change_range),
+            fmt::escape_markdown(&name),
+            fmt::percent(row.average_change_range),
+            row.advancing,
+            row.declining,
+            fmt::number(row.trend_change_range, 2)
+        ));
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn sector(day: u32, industry: i32, change_range: Decimal) -> SectorDailyPerformance {
+        SectorDailyPerformance {
+            date: NaiveDate::from_ymd_opt(2024, 12, day).unwrap(),
+            stock_industry_id: industry,
+            stock_count: 10,
+            advancing: 6,
+            declining: 3,
+            average_change_range: change_range,
+            trade_value: dec!(1000),
+        }
+    }
+
+    #[test]
+    fn test_heat() {
+        assert_eq!(heat(dec!(2.5)), "🟥");
+        assert_eq!(heat(dec!(0.5)), "🔴");
+        assert_eq!(heat(dec!(0)), "⚪");
+        assert_eq!(heat(dec!(-0.5)), "🟢");
+        assert_eq!(heat(dec!(-3)), "🟩");
+    }
+
+    #[test]
+    fn test_summarize() {
+        let mut history: Vec<SectorDailyPerformance> =
+            (16..=23).map(|day| sector(day, 24, dec!(1))).collect();
+        history.push(sector(20, 17, dec!(-1)));
+        history.push(sector(23, 17, dec!(2)));
+        history.sort_by_key(|s| s.date);
+
+        let rows = summarize(NaiveDate::from_ymd_opt(2024, 12, 23).unwrap(), &history);
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].stock_industry_id, 17);
+        assert_eq!(rows[0].trend_change_range, dec!(1));
+        assert_eq!(rows[1].stock_industry_id, 24);
+        assert_eq!(rows[1].trend_change_range, dec!(5));
+    }
+}
@@ -7,11 +7,14 @@ use rust_decimal::Decimal;
 use tokio::{task, time};
 
 use crate::{
-    bot,
+    bot::{self, telegram::fmt},
     cache::SHARE,
+    config::SETTINGS,
     crawler::{self, twse},
     database::table::trace::Trace,
-    declare, logging, nosql,
+    declare,
+    event::taiwan_stock::protection,
+    logging, nosql,
     util::{datetime::Weekend, map::Keyable},
 };
 
@@ -47,6 +50,13 @@ async fn trace_price_run() {
             logging::error_file_async(format!("Failed to trace target price: {:?}", why));
         }
 
+        // 設定檔 alert.intraday_protection 開啟時盤中也檢查庫存的停損與停利
+        if SETTINGS.load().alert.intraday_protection {
+            if let Err(why) = protection::check_intraday().await {
+                logging::error_file_async(format!("Failed to check protection: {:?}", why));
+            }
+        }
+
         ticker.tick().await;
     }
 }
@@ -121,7 +131,7 @@ async fn alert_on_price_boundary(target: Trace, current_price: Decimal) -> Resul
         .set(target_key, current_price.to_string(), 60 * 60 * 5)
         .await?;
 
-    bot::telegram::send(&to_bot_msg).await;
+    bot::alert::send(bot::alert::PRICE_ALERT, &to_bot_msg).await;
 
     Ok(true)
 }
@@ -130,7 +140,7 @@ async fn format_alert_message(target: &Trace, current_price: Decimal) -> String
     let stock_name = SHARE
         .get_stock(&target.stock_symbol)
         .await
-        .map_or_else(String::new, |stock| stock.name);
+        .map_or_else(String::new, |stock| fmt::escape_markdown(&stock.name));
     let boundary = if current_price < target.floor {
         "低於最低價"
     } else {
@@ -209,7 +219,7 @@ mod tests {
         logging::debug_file_async("開始 event::trace::stock_price::handle_price".to_string());
 
         let trace = Trace {
-            stock_symbol: "1303".to_string(),
+            stock_symbol: "1303".parse().unwrap(),
             floor: dec!(70),
             ceiling: dec!(60),
         };
@@ -266,7 +276,7 @@ mod tests {
         );
 
         let trace = Trace {
-            stock_symbol: "1558".to_string(),
+            stock_symbol: "1558".parse().unwrap(),
             floor: dec!(100),
             ceiling: dec!(0),
         };
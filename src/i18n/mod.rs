@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Context, Result};
+use config::{File, FileFormat};
+use minijinja::{AutoEscape, Environment, Value};
+use once_cell::sync::Lazy;
+
+use crate::{bot::settings, config::SETTINGS, logging};
+
+/// 聊天室未設定語系或範本缺少指定的語系時使用的語系
+pub const DEFAULT_LANGUAGE: &str = "zh-TW";
+/// 未設定 bot.telegram.templates_path 時讀取的範本檔
+const DEFAULT_TEMPLATES_PATH: &str = "templates.toml";
+/// 範本檔不存在時使用編譯時內嵌的範本
+const EMBEDDED_TEMPLATES: &str = include_str!("../../templates.toml");
+/// 各範本可以使用的佔位符，啟動時檢查範本沒有使用清單以外的佔位符
+const PLACEHOLDERS: &[(&str, &[&str])] = &[
+    ("ex_dividend", &["date", "stocks"]),
+    ("payable_date", &["date", "stocks"]),
+    ("corporate_event", &["date", "events"]),
+    (
+        "daily_movers",
+        &["date", "held", "gainers", "losers", "spikes", "spike_ratio"],
+    ),
+    (
+        "portfolio_summary",
+        &[
+            "period",
+            "start_date",
+            "end_date",
+            "market_value",
+            "market_change",
+            "market_percent",
+            "gainers",
+            "gainers_count",
+            "losers",
+            "losers_count",
+            "concentration",
+            "benchmarks",
+            "xirrs",
+            "risks",
+            "tags",
+            "notes",
+            "decreases",
+            "received",
+            "received_total",
+            "upcoming",
+        ],
+    ),
+];
+
+static TEMPLATES: Lazy<Templates> = Lazy::new(|| match load() {
+    Ok(templates) => templates,
+    Err(why) => {
+        logging::error_file_async(format!("{:?}", why));
+        Templates::parse(EMBEDDED_TEMPLATES).unwrap_or_default()
+    }
+});
+
+/// 各語系的訊息範本，範本名稱為 語系.範本 ex. zh-TW.ex_dividend
+#[derive(Debug, Default)]
+pub struct Templates {
+    env: Environment<'static>,
+}
+
+impl Templates {
+    /// 解析 TOML 格式的範本，每個表為一個語系，範本的語法錯誤會在這裡回報
+    pub fn parse(text: &str) -> Result<Templates> {
+        let sources = config::Config::builder()
+            .add_source(File::from_str(text, FileFormat::Toml))
+            .build()
+            .and_then(|cfg| cfg.try_deserialize::<HashMap<String, HashMap<String, String>>>())?;
+
+        let mut env = Environment::new();
+        env.set_trim_blocks(true);
+        env.set_lstrip_blocks(true);
+        env.set_auto_escape_callback(|_| AutoEscape::None);
+
+        for (language, templates) in sources {
+            for (key, source) in templates {
+                let name = format!("{}.{}", language, key);
+                env.add_template_owned(name.clone(), source)
+                    .with_context(|| format!("Failed to parse template {}", name))?;
+            }
+        }
+
+        Ok(Templates { env })
+    }
+
+    /// 檢查 DEFAULT_LANGUAGE 有全部的範本，且各範本只使用 PLACEHOLDERS 定義的佔位符
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+
+        for (key, _) in PLACEHOLDERS {
+            let name = format!("{}.{}", DEFAULT_LANGUAGE, key);
+            if self.env.get_template(&name).is_err() {
+                problems.push(format!("missing template {}", name));
+            }
+        }
+
+        for (name, template) in self.env.templates() {
+            let key = name.split_once('.').map_or(name, |(_, key)| key);
+            let Some((_, allowed)) = PLACEHOLDERS.iter().find(|(k, _)| *k == key) else {
+                problems.push(format!("unknown template {}", name));
+                continue;
+            };
+
+            let mut unknown: Vec<String> = template
+                .undeclared_variables(false)
+                .into_iter()
+                .filter(|variable| !allowed.contains(&variable.as_str()))
+                .collect();
+            if !unknown.is_empty() {
+                unknown.sort();
+                problems.push(format!(
+                    "template {} uses undefined placeholders {}",
+                    name,
+                    unknown.join(", ")
+                ));
+            }
+        }
+
+        if problems.is_empty() {
+            return Ok(());
+        }
+
+        problems.sort();
+        Err(anyhow!("Invalid templates: {}", problems.join("; ")))
+    }
+
+    /// 是否有指定語系的範本
+    pub fn has_language(&self, language: &str) -> bool {
+        self.env
+            .templates()
+            .any(|(name, _)| name.split_once('.').is_some_and(|(l, _)| l == language))
+    }
+
+    /// 以 ctx 產生指定語系的訊息，找不到語系時改用 DEFAULT_LANGUAGE，範本不存在或產生失敗時回傳 key
+    pub fn render(&self, language: &str, key: &str, ctx: Value) -> String {
+        let Some(template) = [language, DEFAULT_LANGUAGE]
+            .iter()
+            .find_map(|language| self.env.get_template(&format!("{}.{}", language, key)).ok())
+        else {
+            return key.to_string();
+        };
+
+        template.render(ctx).unwrap_or_else(|why| {
+            logging::error_file_async(format!(
+                "Failed to render template {} because {:?}",
+                template.name(),
+                why
+            ));
+            key.to_string()
+        })
+    }
+}
+
+/// 讀取設定檔 bot.telegram.templates_path 指定的範本，檔案不存在時使用內嵌的範本
+fn load() -> Result<Templates> {
+    let settings = SETTINGS.load();
+    let path = match settings.bot.telegram.templates_path.as_str() {
+        "" => DEFAULT_TEMPLATES_PATH,
+        path => path,
+    };
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(why) if why.kind() == std::io::ErrorKind::NotFound => EMBEDDED_TEMPLATES.to_string(),
+        Err(why) => return Err(why).context(format!("Failed to read templates {}", path)),
+    };
+
+    Templates::parse(&text).context(format!("Failed to parse templates {}", path))
+}
+
+/// 啟動時檢查範本檔，語法錯誤、缺少範本或使用未定義的佔位符時回傳錯誤
+pub fn validate() -> Result<()> {
+    load()?.validate()
+}
+
+/// 聊天室以 /settings 設定的語系，沒有時為設定檔 bot.telegram.languages 設定的語系，都未設定時為 DEFAULT_LANGUAGE
+pub fn language(chat_id: i64) -> String {
+    settings::language(chat_id).unwrap_or_else(|| {
+        SETTINGS
+            .load()
+            .bot
+            .telegram
+            .languages
+            .get(&chat_id)
+            .map_or(DEFAULT_LANGUAGE, String::as_str)
+            .to_string()
+    })
+}
+
+/// 範本是否有指定的語系
+pub fn is_supported(language: &str) -> bool {
+    TEMPLATES.has_language(language)
+}
+
+/// 以範本產生指定語系的訊息
+pub fn text(language: &str, key: &str, ctx: Value) -> String {
+    TEMPLATES.render(language, key, ctx)
+}
+
+#[cfg(test)]
+mod tests {
+    use minijinja::context;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let templates = Templates::parse(
+            r#"
+[zh-TW]
+corporate_event = '''
+{{ date }} 召開股東會的股票
+{% for event in events %}
+    {{ event.security_code }} {{ "股東會" if event.kind == "shareholder_meeting" else "法說會" }}
+{% endfor %}
+'''
+
+[en]
+corporate_event = "Meetings on {{ date }}"
+"#,
+        )
+        .unwrap();
+        let ctx = || {
+            context! {
+                date => "2024-06-04",
+                events => vec![context! { security_code => "2330", kind => "shareholder_meeting" }],
+            }
+        };
+
+        assert_eq!(
+            templates.render("en", "corporate_event", ctx()),
+            "Meetings on 2024-06-04"
+        );
+        assert_eq!(
+            templates.render("zh-TW", "corporate_event", ctx()),
+            "2024-06-04 召開股東會的股票\n    2330 股東會\n"
+        );
+        assert_eq!(
+            templates.render("ja", "corporate_event", ctx()),
+            templates.render("zh-TW", "corporate_event", ctx())
+        );
+        assert_eq!(templates.render("en", "missing", ctx()), "missing");
+    }
+
+    #[test]
+    fn test_validate() {
+        let templates = Templates::parse(EMBEDDED_TEMPLATES).unwrap();
+        assert!(templates.validate().is_ok());
+
+        let templates = Templates::parse(
+            r#"
+[zh-TW]
+ex_dividend = "{{ date }} {{ stock_list }}"
+unused = "x"
+"#,
+        )
+        .unwrap();
+        let why = templates.validate().unwrap_err().to_string();
+
+        assert!(why.contains("missing template zh-TW.payable_date"));
+        assert!(why.contains("template zh-TW.ex_dividend uses undefined placeholders stock_list"));
+        assert!(why.contains("unknown template zh-TW.unused"));
+
+        assert!(Templates::parse("[zh-TW]\nex_dividend = \"{% if %}\"").is_err());
+    }
+}
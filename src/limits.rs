@@ -0,0 +1,127 @@
+use std::{cmp::max, future::Future};
+
+use once_cell::sync::Lazy;
+use tokio::sync::Semaphore;
+
+use crate::config::SETTINGS;
+
+/// 未設定 limits.max_telegram_messages 時同時傳送中的訊息數
+const DEFAULT_TELEGRAM_MESSAGES: usize = 4;
+/// 未設定 limits.write_queue_capacity 時排隊等待寫入的筆數
+const DEFAULT_WRITE_QUEUE_CAPACITY: usize = 2048;
+/// 未設定 limits.write_batch_size 時每批寫入的筆數
+const DEFAULT_WRITE_BATCH_SIZE: usize = 500;
+
+static CRAWLS: Lazy<Semaphore> = Lazy::new(|| Semaphore::new(Stage::Crawl.max()));
+static DB_WRITES: Lazy<Semaphore> = Lazy::new(|| Semaphore::new(Stage::DbWrite.max()));
+static TELEGRAM_MESSAGES: Lazy<Semaphore> =
+    Lazy::new(|| Semaphore::new(Stage::TelegramMessage.max()));
+
+/// 受設定檔 limits 限制同時執行數量的階段
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stage {
+    /// 爬蟲的 HTTP 請求
+    Crawl,
+    /// 寫入資料庫
+    DbWrite,
+    /// 傳送 Telegram 訊息
+    TelegramMessage,
+}
+
+impl Stage {
+    /// 設定檔指定的上限，未設定(0)時依 CPU 數量決定
+    pub fn max(&self) -> usize {
+        let limits = &SETTINGS.load().limits;
+        let cpus = num_cpus::get();
+
+        match self {
+            Stage::Crawl => permits(limits.max_crawls, cpus * 8),
+            Stage::DbWrite => permits(limits.max_db_writes, max(16, cpus * 4)),
+            Stage::TelegramMessage => {
+                permits(limits.max_telegram_messages, DEFAULT_TELEGRAM_MESSAGES)
+            }
+        }
+    }
+
+    fn semaphore(&self) -> &'static Semaphore {
+        match self {
+            Stage::Crawl => &CRAWLS,
+            Stage::DbWrite => &DB_WRITES,
+            Stage::TelegramMessage => &TELEGRAM_MESSAGES,
+        }
+    }
+}
+
+/// 回補時排隊等待寫入的數據上限
+pub fn write_queue_capacity() -> usize {
+    permits(
+        SETTINGS.load().limits.write_queue_capacity,
+        DEFAULT_WRITE_QUEUE_CAPACITY,
+    )
+}
+
+/// 寫入端每批寫入的筆數
+pub fn write_batch_size() -> usize {
+    permits(
+        SETTINGS.load().limits.write_batch_size,
+        DEFAULT_WRITE_BATCH_SIZE,
+    )
+}
+
+/// 設定值為 0 時使用預設值
+fn permits(configured: usize, default: usize) -> usize {
+    match configured {
+        0 => max(default, 1),
+        configured => configured,
+    }
+}
+
+/// 取得階段的許可後執行 future，已達上限時等待其他執行中的工作結束
+pub async fn run<F: Future>(stage: Stage, future: F) -> F::Output {
+    // 信號量不會被關閉，acquire 不會失敗
+    let _permit = stage.semaphore().acquire().await;
+
+    future.await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use futures::{stream, StreamExt};
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_permits() {
+        assert_eq!(permits(0, 16), 16);
+        assert_eq!(permits(2, 16), 2);
+        assert_eq!(permits(0, 0), 1);
+    }
+
+    #[tokio::test]
+    async fn test_run() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let max = Stage::TelegramMessage.max();
+
+        stream::iter(0..max * 3)
+            .for_each_concurrent(None, |_| {
+                let running = running.clone();
+                let peak = peak.clone();
+                run(Stage::TelegramMessage, async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::task::yield_now().await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .await;
+
+        assert!(peak.load(Ordering::SeqCst) <= max);
+    }
+}
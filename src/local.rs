@@ -0,0 +1,58 @@
+use anyhow::{anyhow, Result};
+use chrono::{FixedOffset, NaiveDate, TimeZone};
+
+use crate::{
+    crawler::{goodinfo, tpex, twse},
+    database::{sqlite, table::dividend::Dividend},
+    logging,
+};
+
+/// 命令列本機模式的用法
+const USAGE: &str = "usage: stock_crawler local quote <date YYYY-MM-DD>
+       stock_crawler local revenue <year> <month>
+       stock_crawler local dividend <symbol>";
+
+/// 抓取數據後寫入 SQLite，不需要 Postgres ex. `stock_crawler local quote 2024-06-12`
+pub async fn command(args: &[String]) -> Result<()> {
+    let pool = sqlite::connect().await?;
+    let count = match args.first().map(String::as_str) {
+        Some("quote") => {
+            let date = match args.get(1) {
+                Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")?,
+                None => return Err(anyhow!(USAGE)),
+            };
+            let (twse, tpex) = tokio::join!(twse::quote::visit(date), tpex::quote::visit(date));
+            let mut quotes = twse?;
+            quotes.extend(tpex?);
+            sqlite::save_quotes(&pool, &quotes).await?
+        }
+        Some("revenue") => {
+            let (year, month) = match (args.get(1), args.get(2)) {
+                (Some(year), Some(month)) => (year.parse::<i32>()?, month.parse::<u32>()?),
+                _ => return Err(anyhow!(USAGE)),
+            };
+            let date_time = FixedOffset::east_opt(8 * 60 * 60)
+                .and_then(|timezone| timezone.with_ymd_and_hms(year, month, 1, 0, 0, 0).single())
+                .ok_or_else(|| anyhow!("Invalid year month {}-{}", year, month))?;
+            let revenues = twse::revenue::visit(date_time).await?;
+            sqlite::save_revenues(&pool, &revenues).await?
+        }
+        Some("dividend") => {
+            let symbol = args.get(1).ok_or_else(|| anyhow!(USAGE))?;
+            let dividends: Vec<Dividend> = goodinfo::dividend::visit(symbol)
+                .await?
+                .into_values()
+                .flatten()
+                .map(Dividend::from)
+                .collect();
+            sqlite::save_dividends(&pool, &dividends).await?
+        }
+        _ => return Err(anyhow!(USAGE)),
+    };
+
+    pool.close().await;
+    logging::info_file_async(format!("本機模式寫入 SQLite 完成，共 {} 筆", count));
+    println!("saved {} rows", count);
+
+    Ok(())
+}
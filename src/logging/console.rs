@@ -0,0 +1,140 @@
+use std::{
+    io::{self, IsTerminal},
+    sync::RwLock,
+    thread,
+};
+
+use chrono::{DateTime, Local};
+
+use crate::{config, logging::route::Level};
+
+/// 設定檔載入前不加顏色並使用精簡格式
+static STYLE: RwLock<Style> = RwLock::new(Style {
+    color: false,
+    verbose: false,
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Style {
+    /// 是否以 ANSI 顏色標示等級
+    color: bool,
+    /// 詳細格式會加上日期、執行緒與 tokio 任務的識別
+    verbose: bool,
+}
+
+/// 套用設定檔的 console 格式，stdout 不是終端機(ex. 導向檔案、systemd)時不加顏色
+pub fn configure(console: &config::ConsoleLog) {
+    let style = Style {
+        color: console.color && io::stdout().is_terminal(),
+        verbose: console.format.eq_ignore_ascii_case("verbose"),
+    };
+
+    match STYLE.write() {
+        Ok(mut current) => *current = style,
+        Err(poisoned) => *poisoned.into_inner() = style,
+    }
+}
+
+/// 將一行日誌輸出到 console
+pub fn write(level: Level, msg: &str) {
+    let style = match STYLE.read() {
+        Ok(style) => *style,
+        Err(poisoned) => *poisoned.into_inner(),
+    };
+    let context = if style.verbose {
+        context()
+    } else {
+        String::new()
+    };
+
+    println!("{}", format(level, msg, Local::now(), style, &context));
+}
+
+/// 目前的執行緒與 tokio 任務 ex. tokio-runtime-worker#ThreadId(3) task:27
+fn context() -> String {
+    let current = thread::current();
+    let task = tokio::task::try_id()
+        .map(|id| id.to_string())
+        .unwrap_or_else(|| "-".to_string());
+
+    format!(
+        "{}#{:?} task:{}",
+        current.name().unwrap_or("unnamed"),
+        current.id(),
+        task
+    )
+}
+
+/// 精簡格式 ex. 09:30:00.123 Info  msg，詳細格式 ex. 2024-07-01 09:30:00.123 Info  [main#ThreadId(1) task:-] msg
+fn format(level: Level, msg: &str, now: DateTime<Local>, style: Style, context: &str) -> String {
+    let time = if style.verbose {
+        now.format("%Y-%m-%d %H:%M:%S%.3f")
+    } else {
+        now.format("%H:%M:%S%.3f")
+    };
+    let name = format!("{:<5}", level.name());
+    let name = if style.color {
+        let color = match level {
+            Level::Error => "31",
+            Level::Warn => "33",
+            Level::Info => "32",
+            Level::Debug => "36",
+        };
+        format!("\x1b[{}m{}\x1b[0m", color, name)
+    } else {
+        name
+    };
+
+    if style.verbose {
+        format!("{} {} [{}] {}", time, name, context, msg)
+    } else {
+        format!("{} {} {}", time, name, msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        let now = Local.with_ymd_and_hms(2024, 7, 1, 9, 30, 0).unwrap();
+        let compact = Style {
+            color: false,
+            verbose: false,
+        };
+        let verbose = Style {
+            color: false,
+            verbose: true,
+        };
+        let colored = Style {
+            color: true,
+            verbose: false,
+        };
+
+        assert_eq!(
+            format(Level::Info, "started", now, compact, ""),
+            "09:30:00.000 Info  started"
+        );
+        assert_eq!(
+            format(Level::Warn, "slow", now, verbose, "main#ThreadId(1) task:-"),
+            "2024-07-01 09:30:00.000 Warn  [main#ThreadId(1) task:-] slow"
+        );
+        assert_eq!(
+            format(Level::Error, "failed", now, colored, ""),
+            "09:30:00.000 \x1b[31mError\x1b[0m failed"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_context() {
+        let in_task = tokio::spawn(async { context() }).await.unwrap();
+
+        assert!(!in_task.ends_with("task:-"));
+        // 不在 tokio 任務內時沒有任務識別
+        assert!(thread::spawn(context).join().unwrap().ends_with("task:-"));
+    }
+}
@@ -3,29 +3,54 @@ use std::{
     fs::{self},
     io::Write,
     path::{Path, PathBuf},
+    sync::Mutex,
 };
 
-use chrono::{format::DelayedFormat, Local};
+use chrono::Local;
 use once_cell::sync::Lazy;
 use tokio::{
     sync::{
         mpsc::UnboundedReceiver,
-        mpsc::{self, UnboundedSender}
+        mpsc::{self, UnboundedSender},
+        oneshot,
     },
     task
 };
 
-use crate::logging::rotate::Rotate;
+use crate::{
+    config,
+    logging::{rotate::Rotate, route::Level},
+};
 
+/// 依等級加上顏色的 console 輸出
+pub mod console;
 pub mod rotate;
+/// 依日誌等級決定輸出到檔案、console 或 syslog
+pub mod route;
+/// 排程任務每次執行的識別碼，用來串起同一次執行的日誌、資料異動與通知
+pub mod run_id;
+/// 輸出到 syslog，systemd 環境由 journald 接收
+pub mod syslog;
+
+pub use route::configure;
 
 static LOGGER: Lazy<Logger> = Lazy::new(|| Logger::new("default"));
+/// 所有日誌檔的寫入通道，關閉服務前用來將緩衝中的日誌寫入檔案
+static WRITERS: Mutex<Vec<UnboundedSender<LogMessage>>> = Mutex::new(Vec::new());
+
+/// 寫入通道內傳遞的訊息
+pub enum LogMessage {
+    /// 一行日誌
+    Line(String),
+    /// 寫入緩衝中的日誌後通知呼叫端
+    Flush(oneshot::Sender<()>),
+}
 
 pub struct Logger {
-    info_writer: UnboundedSender<String>,
-    warn_writer: UnboundedSender<String>,
-    error_writer: UnboundedSender<String>,
-    debug_writer: UnboundedSender<String>,
+    info_writer: UnboundedSender<LogMessage>,
+    warn_writer: UnboundedSender<LogMessage>,
+    error_writer: UnboundedSender<LogMessage>,
+    debug_writer: UnboundedSender<LogMessage>,
 }
 
 impl Logger {
@@ -39,45 +64,87 @@ impl Logger {
     }
 
     pub fn info(&self, log: String) {
-        self.send(log, &self.info_writer);
+        self.log(Level::Info, log, &self.info_writer);
     }
 
     pub fn warn(&self, log: String) {
-        self.send(log, &self.warn_writer);
+        self.log(Level::Warn, log, &self.warn_writer);
     }
 
     pub fn error(&self, log: String) {
-        self.send(log, &self.error_writer);
+        self.log(Level::Error, log, &self.error_writer);
     }
 
     pub fn debug(&self, log: String) {
-        self.send(log, &self.debug_writer);
+        self.log(Level::Debug, log, &self.debug_writer);
+    }
+
+    /// 依設定檔的輸出目的地將日誌寫入檔案、console 或 syslog
+    fn log(&self, level: Level, msg: String, writer: &UnboundedSender<LogMessage>) {
+        let outputs = route::outputs(level);
+        if !outputs.console && !outputs.syslog {
+            if outputs.file {
+                self.send(msg, writer);
+            }
+            return;
+        }
+
+        let msg = with_run_id(msg);
+        if outputs.console {
+            console::write(level, &msg);
+        }
+
+        if outputs.syslog {
+            syslog::send(level, &msg);
+        }
+
+        if outputs.file {
+            Self::write_line(msg, writer);
+        }
     }
 
-    pub fn send(&self, msg: String, writer: &UnboundedSender<String>) {
-        if let Err(why) = writer.send(msg) {
+    pub fn send(&self, msg: String, writer: &UnboundedSender<LogMessage>) {
+        Self::write_line(with_run_id(msg), writer);
+    }
+
+    fn write_line(msg: String, writer: &UnboundedSender<LogMessage>) {
+        if let Err(why) = writer.send(LogMessage::Line(msg)) {
             error_console(why.to_string());
         }
     }
 
-    fn create_writer(log_name: &str) -> UnboundedSender<String> {
+    fn create_writer(log_name: &str) -> UnboundedSender<LogMessage> {
         let log_path = Self::get_log_path(log_name).unwrap_or_else(|| {
             panic!("Failed to create log directory.");
         });
 
-        let (tx, rx) = mpsc::unbounded_channel::<String>();
+        let (tx, rx) = mpsc::unbounded_channel::<LogMessage>();
 
         task::spawn(Self::process_messages(rx, log_path.display().to_string()));
 
+        if let Ok(mut writers) = WRITERS.lock() {
+            writers.push(tx.clone());
+        }
+
         tx
     }
 
-    async fn process_messages(mut rx: UnboundedReceiver<String>, log_path: String) {
+    async fn process_messages(mut rx: UnboundedReceiver<LogMessage>, log_path: String) {
         let mut msg = String::with_capacity(2048);
         let mut rotate = Rotate::new(log_path);
 
         while let Some(message) = rx.recv().await {
             let now = Local::now();
+            let message = match message {
+                LogMessage::Line(message) => message,
+                LogMessage::Flush(done) => {
+                    if !msg.is_empty() {
+                        Self::write(&mut rotate, &mut msg, now);
+                    }
+                    let _ = done.send(());
+                    continue;
+                }
+            };
 
             if let Err(why) = writeln!(&mut msg, "{} {}", now.format("%F %X%.6f"), message) {
                 error_console(format!("Failed to writeln a message. because:{:#?}", why));
@@ -88,42 +155,76 @@ impl Logger {
                 continue;
             }
 
-            msg.push('\n');
+            Self::write(&mut rotate, &mut msg, now);
+        }
+    }
 
-            if let Some(writer) = rotate.get_writer(now) {
-                if let Ok(mut w) = writer.write() {
-                    let to_write = msg.as_bytes();
-                    if let Err(why) = w.write_all(to_write) {
-                        error_console(format!(
-                            "Failed to write msg:{}\r\nbecause:{:#?}",
-                            msg, why
-                        ));
-                    }
+    fn write(rotate: &mut Rotate, msg: &mut String, now: chrono::DateTime<Local>) {
+        msg.push('\n');
 
-                    if let Err(why) = w.flush() {
-                        error_console(format!("Failed to flush log file. because:{:#?}", why));
-                    }
+        if let Some(writer) = rotate.get_writer(now) {
+            if let Ok(mut w) = writer.write() {
+                let to_write = msg.as_bytes();
+                if let Err(why) = w.write_all(to_write) {
+                    error_console(format!("Failed to write msg:{}\r\nbecause:{:#?}", msg, why));
+                }
 
-                    msg.clear();
+                if let Err(why) = w.flush() {
+                    error_console(format!("Failed to flush log file. because:{:#?}", why));
                 }
+
+                msg.clear();
             }
         }
     }
 
     fn get_log_path(name: &str) -> Option<PathBuf> {
-        let path = Path::new("log");
+        let path = log_dir(&config::profile());
 
         if !path.exists() {
-            fs::create_dir_all(path).ok()?;
+            fs::create_dir_all(&path).ok()?;
         }
 
-        let mut log_path = PathBuf::from(path);
+        let mut log_path = path;
         log_path.push(format!("%Y-%m-%d_{}.log", name));
 
         Some(log_path)
     }
 }
 
+/// 在排程任務內時於日誌前加上這次執行的識別碼
+fn with_run_id(msg: String) -> String {
+    match run_id::current() {
+        Some(run_id) => format!("[{}] {}", run_id, msg),
+        None => msg,
+    }
+}
+
+/// 日誌檔的目錄，prod 寫入 log，其他環境寫入 log/{環境} 避免與正式環境的日誌混在一起
+fn log_dir(profile: &str) -> PathBuf {
+    let path = Path::new("log");
+    if profile == config::DEFAULT_PROFILE {
+        return path.to_path_buf();
+    }
+
+    path.join(profile)
+}
+
+/// 等待所有日誌檔將已送出的日誌寫入檔案，關閉服務前呼叫
+pub async fn flush() {
+    let writers = match WRITERS.lock() {
+        Ok(writers) => writers.clone(),
+        Err(_) => return,
+    };
+
+    for writer in writers {
+        let (tx, rx) = oneshot::channel();
+        if writer.send(LogMessage::Flush(tx)).is_ok() {
+            let _ = rx.await;
+        }
+    }
+}
+
 pub fn info_file_async(log: String) {
     LOGGER.info(log);
 }
@@ -141,17 +242,9 @@ pub fn debug_file_async(log: String) {
 }
 
 pub fn info_console(log: String) {
-    println!(
-        "{} Info {}",
-        Local::now().format("%Y-%m-%d %H:%M:%S.%3f"),
-        log
-    );
+    console::write(Level::Info, &log);
 }
 
 pub fn error_console(log: String) {
-    println!(
-        "{} Error {}",
-        DelayedFormat::to_string(&Local::now().format("%Y-%m-%d %H:%M:%S.%3f")),
-        log
-    );
+    console::write(Level::Error, &log);
 }
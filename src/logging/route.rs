@@ -0,0 +1,139 @@
+use std::sync::RwLock;
+
+use crate::config;
+
+/// 設定檔載入前的日誌只寫入檔案，與加入輸出設定前的行為相同
+static ROUTES: RwLock<Routes> = RwLock::new(Routes {
+    info: Outputs::FILE,
+    warn: Outputs::FILE,
+    error: Outputs::FILE,
+    debug: Outputs::FILE,
+});
+
+/// 日誌等級
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::Debug => "Debug",
+            Level::Info => "Info",
+            Level::Warn => "Warn",
+            Level::Error => "Error",
+        }
+    }
+}
+
+/// 一個等級的日誌要輸出到哪些目的地
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Outputs {
+    pub file: bool,
+    pub console: bool,
+    pub syslog: bool,
+}
+
+impl Outputs {
+    const FILE: Outputs = Outputs {
+        file: true,
+        console: false,
+        syslog: false,
+    };
+
+    /// 解析設定檔的目的地 ex. ["both", "syslog"]，無法辨識的目的地會被忽略
+    pub fn parse(values: &[String]) -> Self {
+        let mut outputs = Outputs::default();
+        for value in values {
+            match value.trim().to_lowercase().as_str() {
+                "file" => outputs.file = true,
+                "console" => outputs.console = true,
+                "both" => {
+                    outputs.file = true;
+                    outputs.console = true;
+                }
+                "syslog" | "journald" => outputs.syslog = true,
+                other => super::error_console(format!("Unknown log output: {}", other)),
+            }
+        }
+
+        outputs
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Routes {
+    info: Outputs,
+    warn: Outputs,
+    error: Outputs,
+    debug: Outputs,
+}
+
+/// 套用設定檔的輸出目的地與 console、syslog 的設定，設定檔載入與重新載入時呼叫
+pub fn configure(logging: &config::Logging) {
+    let routes = Routes {
+        info: Outputs::parse(&logging.info),
+        warn: Outputs::parse(&logging.warn),
+        error: Outputs::parse(&logging.error),
+        debug: Outputs::parse(&logging.debug),
+    };
+
+    match ROUTES.write() {
+        Ok(mut current) => *current = routes,
+        Err(poisoned) => *poisoned.into_inner() = routes,
+    }
+
+    super::syslog::configure(&logging.syslog);
+    super::console::configure(&logging.console);
+}
+
+/// 指定等級的日誌目前的輸出目的地
+pub fn outputs(level: Level) -> Outputs {
+    let routes = match ROUTES.read() {
+        Ok(routes) => *routes,
+        Err(poisoned) => *poisoned.into_inner(),
+    };
+
+    match level {
+        Level::Debug => routes.debug,
+        Level::Info => routes.info,
+        Level::Warn => routes.warn,
+        Level::Error => routes.error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let parse = |values: &[&str]| {
+            Outputs::parse(&values.iter().map(|v| v.to_string()).collect::<Vec<_>>())
+        };
+
+        assert_eq!(parse(&["file"]), Outputs::FILE);
+        assert_eq!(
+            parse(&["Both", "syslog"]),
+            Outputs {
+                file: true,
+                console: true,
+                syslog: true
+            }
+        );
+        assert_eq!(
+            parse(&["console", "unknown"]),
+            Outputs {
+                file: false,
+                console: true,
+                syslog: false
+            }
+        );
+        assert_eq!(parse(&[]), Outputs::default());
+    }
+}
@@ -0,0 +1,45 @@
+use std::future::Future;
+
+use uuid::Uuid;
+
+tokio::task_local! {
+    /// 目前排程任務這次執行的識別碼
+    static RUN_ID: String;
+}
+
+/// 產生新的執行識別碼
+pub fn generate() -> String {
+    Uuid::new_v4().to_string()
+}
+
+/// 在指定的執行識別碼下執行任務，任務內的日誌、audit_log 與 Telegram 通知都會帶上同一個識別碼
+/// 注意 tokio::spawn 出去的子任務不會繼承識別碼
+pub async fn scope<F>(run_id: String, future: F) -> F::Output
+where
+    F: Future,
+{
+    RUN_ID.scope(run_id, future).await
+}
+
+/// 取得目前的執行識別碼，不在排程任務內時回傳 None
+pub fn current() -> Option<String> {
+    RUN_ID.try_with(|run_id| run_id.clone()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scope() {
+        assert_eq!(current(), None);
+
+        let run_id = generate();
+        let inner = scope(run_id.clone(), async { current() }).await;
+
+        assert_eq!(inner, Some(run_id));
+        assert_eq!(current(), None);
+        assert_ne!(generate(), generate());
+    }
+}
@@ -0,0 +1,108 @@
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+use std::sync::Mutex;
+
+use crate::{config, logging::route::Level};
+
+/// 設定檔載入後才會設定，之前設定為 syslog 的日誌不會送出
+static SINK: Mutex<Option<Sink>> = Mutex::new(None);
+
+struct Sink {
+    path: String,
+    identifier: String,
+    /// 第一次送出時才連線，送出失敗時捨棄並在下一次重新連線
+    #[cfg(unix)]
+    socket: Option<UnixDatagram>,
+}
+
+/// 套用設定檔的 syslog socket 與識別名稱
+pub fn configure(syslog: &config::Syslog) {
+    let sink = Sink {
+        path: syslog.path.clone(),
+        identifier: syslog.identifier.clone(),
+        #[cfg(unix)]
+        socket: None,
+    };
+
+    match SINK.lock() {
+        Ok(mut current) => *current = Some(sink),
+        Err(poisoned) => *poisoned.into_inner() = Some(sink),
+    }
+}
+
+/// 將一行日誌送到 syslog，不支援 unix socket 的平台會忽略
+pub fn send(level: Level, msg: &str) {
+    let Ok(mut sink) = SINK.lock() else {
+        return;
+    };
+    let Some(sink) = sink.as_mut() else {
+        return;
+    };
+
+    #[cfg(unix)]
+    {
+        let line = format(level, &sink.identifier, std::process::id(), msg);
+        if sink.socket.is_none() {
+            sink.socket = match connect(&sink.path) {
+                Ok(socket) => Some(socket),
+                Err(why) => {
+                    super::error_console(format!(
+                        "Failed to connect syslog({}) because {:?}",
+                        sink.path, why
+                    ));
+                    return;
+                }
+            };
+        }
+
+        if let Some(socket) = &sink.socket {
+            if let Err(why) = socket.send(line.as_bytes()) {
+                sink.socket = None;
+                super::error_console(format!("Failed to send syslog because {:?}", why));
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = (level, msg);
+}
+
+/// 非阻塞的連線，syslog 忙碌時捨棄日誌而不是卡住呼叫端
+#[cfg(unix)]
+fn connect(path: &str) -> std::io::Result<UnixDatagram> {
+    let socket = UnixDatagram::unbound()?;
+    socket.connect(path)?;
+    socket.set_nonblocking(true)?;
+
+    Ok(socket)
+}
+
+/// RFC 3164 格式 ex. <11>stock_crawler[1234]: msg，facility 為 user(1)，時間由 syslog 補上
+fn format(level: Level, identifier: &str, pid: u32, msg: &str) -> String {
+    let severity = match level {
+        Level::Error => 3,
+        Level::Warn => 4,
+        Level::Info => 6,
+        Level::Debug => 7,
+    };
+
+    format!("<{}>{}[{}]: {}", 8 + severity, identifier, pid, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        assert_eq!(
+            format(Level::Error, "stock_crawler", 1234, "failed"),
+            "<11>stock_crawler[1234]: failed"
+        );
+        assert_eq!(
+            format(Level::Debug, "stock_crawler", 1, "debug"),
+            "<15>stock_crawler[1]: debug"
+        );
+    }
+}
@@ -7,6 +7,7 @@ use std::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
+    time::Duration,
 };
 
 use tokio::signal;
@@ -22,6 +23,10 @@ pub mod bot;
 pub mod cache;
 /// 計算類
 pub mod calculation;
+/// 行事曆匯出
+pub mod calendar;
+/// 圖表繪製
+pub mod charts;
 /// 設定檔
 pub mod config;
 /// 抓取數據類
@@ -30,16 +35,39 @@ pub mod crawler;
 pub mod database;
 /// 定義結構、enum等
 pub mod declare;
+/// 錯誤類型
+pub mod error;
 /// 事件
 pub mod event;
+/// 通知訊息的多語系範本
+pub mod i18n;
+/// 各階段的並行數量上限
+pub mod limits;
+/// 本機模式
+#[cfg(feature = "sqlite")]
+pub mod local;
 /// 日誌
 pub mod logging;
 /// nosql
 pub mod nosql;
+/// 將寫入的數據發布到 Kafka、NATS
+pub mod publisher;
+/// 數據品質檢查
+pub mod quality;
 ///
 pub mod rpc;
 /// 工作排程
 pub mod scheduler;
+/// 選股
+pub mod screener;
+/// 部署後的自我檢查
+pub mod self_test;
+/// 策略訊號
+pub mod signals;
+/// 檔案儲存
+pub mod storage;
+/// OpenTelemetry 追蹤
+pub mod telemetry;
 /// 工具類
 pub mod util;
 
@@ -108,12 +136,79 @@ async fn main() -> Result<(), Box<dyn Error>> {
     });
 
     dotenv::dotenv().ok();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    // stock_crawler local ... 不需要 Postgres，在載入快取前執行後結束
+    #[cfg(feature = "sqlite")]
+    if args.first().map(String::as_str) == Some("local") {
+        let result = local::command(&args[1..]).await;
+        logging::flush().await;
+        return Ok(result?);
+    }
+
+    // stock_crawler parse-fixture ... 只解析存檔的原始回應，不需要資料庫與快取
+    if args.first().map(String::as_str) == Some("parse-fixture") {
+        let result = crawler::fixture::command(&args[1..]).await;
+        logging::flush().await;
+        return Ok(result?);
+    }
+
+    // stock_crawler self-test 部署後檢查設定檔與各項外部服務，在載入快取前執行，資料庫無法連線時也能輸出結果
+    if args.first().map(String::as_str) == Some("self-test") {
+        let result = self_test::command().await;
+        database::close().await;
+        logging::flush().await;
+        return Ok(result?);
+    }
+
     cache::SHARE.load().await;
 
+    // stock_crawler backfill ...、stock_crawler crawl ...、stock_crawler screen ...、stock_crawler compare ...、stock_crawler tax ...、stock_crawler verify ... 只執行指令後結束，不啟動排程與服務
+    let command = match args.first().map(String::as_str) {
+        Some("backfill") => Some(backfill::command(&args[1..]).await),
+        Some("crawl") => Some(backfill::registry::command(&args[1..]).await),
+        Some("screen") => Some(screener::command(&args[1..]).await),
+        Some("compare") => Some(screener::compare::command(&args[1..]).await),
+        Some("tax") => Some(event::taiwan_stock::dividend_tax::command(&args[1..]).await),
+        Some("verify") => Some(quality::verify::command(&args[1..]).await),
+        _ => None,
+    };
+    if let Some(result) = command {
+        database::close().await;
+        logging::flush().await;
+        return Ok(result?);
+    }
+
+    // 範本有語法錯誤或使用未定義的佔位符時不啟動，避免排程執行後才發現通知無法產生
+    i18n::validate()?;
+
+    // 聊天室以 /settings 設定的偏好，載入失敗時先使用設定檔的設定
+    if let Err(why) = bot::settings::load().await {
+        logging::error_file_async(format!("{:?}", why));
+    }
+
+    // 以 /roles 授予的角色，載入失敗時只有設定檔內的聊天室與管理員可以使用指令
+    if let Err(why) = bot::role::load().await {
+        logging::error_file_async(format!("{:?}", why));
+    }
+
+    // 以 /subscribe 訂閱的個股通知，載入失敗時各聊天室先接收庫存與追踪股票的通知
+    if let Err(why) = bot::subscription::load().await {
+        logging::error_file_async(format!("{:?}", why));
+    }
+
     let sched = JobScheduler::new().await?;
     scheduler::start(&sched).await?;
     rpc::server::start().await?;
 
+    if config::SETTINGS.load().bot.telegram.poll_commands {
+        tokio::spawn(bot::command::listen());
+    }
+
+    if telemetry::is_enabled() {
+        tokio::spawn(telemetry::run());
+    }
+
     let pong = nosql::redis::CLIENT.ping().await;
     if let Ok(pong) = pong {
         println!("pong: {}", pong);
@@ -123,11 +218,36 @@ async fn main() -> Result<(), Box<dyn Error>> {
         tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
     }
 
+    shutdown(&sched).await;
     println!("Server stopped: {:?}", received_signal);
 
     Ok(())
 }
 
+/// 收到關閉訊號後等待執行中的任務結束的最長時間
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// 停止排程並等待執行中的任務，再關閉資料庫連線池與寫入緩衝中的日誌，避免重啟時批次寫入到一半被中斷
+async fn shutdown(sched: &JobScheduler) {
+    logging::info_file_async("收到關閉訊號，停止排程並等待執行中的任務".to_string());
+    bot::telegram::send("StockCrawler 正在關閉").await;
+
+    let running = scheduler::stop(sched, SHUTDOWN_TIMEOUT).await;
+    if running > 0 {
+        bot::telegram::send(&format!("StockCrawler 關閉時仍有 {} 個任務未完成", running)).await;
+    }
+
+    if let Err(why) = telemetry::export().await {
+        logging::error_file_async(format!("Failed to telemetry::export() because {:?}", why));
+    }
+
+    bot::alert::flush_all_digests().await;
+    bot::telegram::flush_all_deferred().await;
+    database::close().await;
+    logging::info_file_async("StockCrawler 已關閉".to_string());
+    logging::flush().await;
+}
+
 /*
 要計算價格下降的百分比，可以使用以下的公式：
 百分比變動=(新值−舊值) / 舊值 × 100%
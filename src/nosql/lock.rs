@@ -0,0 +1,136 @@
+use std::{env, time::Duration};
+
+use anyhow::{Context, Result};
+use deadpool_redis::redis::cmd;
+
+use crate::nosql::redis::CLIENT;
+
+/// 值與 token 相同時才延長存活時間，避免延長到其他持有者在鎖過期後取得的鎖
+const RENEW_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("PEXPIRE", KEYS[1], ARGV[2])
+end
+return 0
+"#;
+/// 值與 token 相同時才刪除
+const RELEASE_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+end
+return 0
+"#;
+
+/// 以 Redis 實作的分散式鎖，鎖的值為持有者的 token，只有持有者可以續約或釋放，
+/// 持有者停止續約超過存活時間後鎖自動過期，其他執行個體即可取得
+pub struct DistributedLock {
+    key: String,
+    token: String,
+    ttl: Duration,
+}
+
+impl DistributedLock {
+    /// token 由主機名稱、行程編號與隨機字串組成，同一台主機重啟後也不會與舊的 token 相同
+    pub fn new(key: &str, ttl: Duration) -> Self {
+        let host = env::var("HOSTNAME").unwrap_or_else(|_| "unknown".to_string());
+        let random = uuid::Uuid::new_v4().simple().to_string();
+        DistributedLock {
+            key: key.to_string(),
+            token: format!("{}:{}:{}", host, std::process::id(), &random[..8]),
+            ttl,
+        }
+    }
+
+    /// 持有者的識別
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// 鎖不存在時取得鎖，已被其他持有者取得時回傳 false
+    pub async fn acquire(&self) -> Result<bool> {
+        let mut conn = CLIENT.pool.get().await?;
+        let reply: Option<String> = cmd("SET")
+            .arg(&self.key)
+            .arg(&self.token)
+            .arg("NX")
+            .arg("PX")
+            .arg(self.ttl.as_millis() as u64)
+            .query_async(&mut conn)
+            .await
+            .context(format!("Failed to acquire lock({}) from Redis", self.key))?;
+
+        Ok(reply.is_some())
+    }
+
+    /// 延長鎖的存活時間，鎖已過期或被其他持有者取得時回傳 false
+    pub async fn renew(&self) -> Result<bool> {
+        let mut conn = CLIENT.pool.get().await?;
+        let renewed: i64 = cmd("EVAL")
+            .arg(RENEW_SCRIPT)
+            .arg(1)
+            .arg(&self.key)
+            .arg(&self.token)
+            .arg(self.ttl.as_millis() as u64)
+            .query_async(&mut conn)
+            .await
+            .context(format!("Failed to renew lock({}) from Redis", self.key))?;
+
+        Ok(renewed == 1)
+    }
+
+    /// 釋放自己持有的鎖，讓其他執行個體不必等到過期即可取得，鎖不是自己持有時回傳 false
+    pub async fn release(&self) -> Result<bool> {
+        let mut conn = CLIENT.pool.get().await?;
+        let released: i64 = cmd("EVAL")
+            .arg(RELEASE_SCRIPT)
+            .arg(1)
+            .arg(&self.key)
+            .arg(&self.token)
+            .query_async(&mut conn)
+            .await
+            .context(format!("Failed to release lock({}) from Redis", self.key))?;
+
+        Ok(released == 1)
+    }
+
+    /// 目前持有鎖的 token，沒有持有者時為 None
+    pub async fn holder(&self) -> Result<Option<String>> {
+        let mut conn = CLIENT.pool.get().await?;
+        let holder: Option<String> = cmd("GET")
+            .arg(&self.key)
+            .query_async(&mut conn)
+            .await
+            .context(format!(
+                "Failed to get lock({}) holder from Redis",
+                self.key
+            ))?;
+
+        Ok(holder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_lock() {
+        dotenv::dotenv().ok();
+        let first = DistributedLock::new("test:lock", Duration::from_secs(5));
+        let second = DistributedLock::new("test:lock", Duration::from_secs(5));
+
+        assert!(first.acquire().await.unwrap());
+        assert!(!second.acquire().await.unwrap());
+        assert!(first.renew().await.unwrap());
+        assert!(!second.renew().await.unwrap());
+        assert!(!second.release().await.unwrap());
+        assert_eq!(
+            first.holder().await.unwrap().as_deref(),
+            Some(first.token())
+        );
+        assert!(first.release().await.unwrap());
+        assert!(second.acquire().await.unwrap());
+        assert!(second.release().await.unwrap());
+    }
+}
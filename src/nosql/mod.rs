@@ -1 +1,3 @@
+/// 以 Redis 實作的分散式鎖
+pub mod lock;
 pub mod redis;
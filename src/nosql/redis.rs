@@ -20,12 +20,10 @@ pub struct Redis {
 impl Redis {
     pub fn new() -> Self {
         //redis://mypassword@127.0.0.1:6379
+        let redis = &SETTINGS.load().nosql.redis;
         let connection_url = format!(
             "redis://{}:{}@{}/{}",
-            SETTINGS.nosql.redis.account,
-            SETTINGS.nosql.redis.password,
-            SETTINGS.nosql.redis.addr,
-            SETTINGS.nosql.redis.db
+            redis.account, redis.password, redis.addr, redis.db
         );
 
         let cfg = Config::from_url(&connection_url);
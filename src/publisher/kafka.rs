@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use once_cell::sync::OnceCell;
+use rdkafka::{
+    config::ClientConfig,
+    producer::{FutureProducer, FutureRecord},
+    util::Timeout,
+};
+
+use crate::config::SETTINGS;
+
+/// 等待 broker 確認的最長時間
+const SEND_TIMEOUT: Duration = Duration::from_secs(5);
+
+static PRODUCER: OnceCell<FutureProducer> = OnceCell::new();
+
+fn producer() -> Result<&'static FutureProducer> {
+    PRODUCER.get_or_try_init(|| {
+        ClientConfig::new()
+            .set("bootstrap.servers", &SETTINGS.load().publisher.url)
+            .set("message.timeout.ms", SEND_TIMEOUT.as_millis().to_string())
+            .create()
+            .context(format!(
+                "Failed to create kafka producer for {}",
+                SETTINGS.load().publisher.url
+            ))
+    })
+}
+
+/// 依序發布到 topic，以 key 區分數據的種類
+pub async fn send(topic: &str, key: &str, payloads: &[String]) -> Result<()> {
+    let producer = producer()?;
+
+    for payload in payloads {
+        producer
+            .send(
+                FutureRecord::to(topic).key(key).payload(payload),
+                Timeout::After(SEND_TIMEOUT),
+            )
+            .await
+            .map_err(|(why, _)| {
+                anyhow!(
+                    "Failed to send {} to kafka topic {} because {:?}",
+                    key,
+                    topic,
+                    why
+                )
+            })?;
+    }
+
+    Ok(())
+}
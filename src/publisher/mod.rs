@@ -0,0 +1,193 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, NaiveDate};
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::{config::SETTINGS, database::table::daily_quote::DailyQuote, logging};
+
+/// 以 rdkafka 發布到 Kafka
+#[cfg(feature = "kafka")]
+mod kafka;
+/// 以 async-nats 發布到 NATS
+#[cfg(feature = "nats")]
+mod nats;
+
+/// 未設定 publisher.topic 時使用的 topic
+const DEFAULT_TOPIC: &str = "stock_crawler";
+
+/// 寫入資料庫的收盤報價
+pub const QUOTE: &str = "quote";
+/// 寫入資料庫的月營收
+pub const REVENUE: &str = "revenue";
+/// 發送的警示
+pub const ALERT: &str = "alert";
+
+/// 設定的 backend 未編譯進來時只記錄一次錯誤
+static UNSUPPORTED_LOGGED: AtomicBool = AtomicBool::new(false);
+
+/// 發布的訊息，Kafka 以 kind 為 key，NATS 以 kind 為 subject 的最後一段
+#[derive(Serialize, Debug)]
+struct Envelope<'a, T: Serialize> {
+    kind: &'a str,
+    published_time: DateTime<Local>,
+    data: T,
+}
+
+/// 發布的收盤報價
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Quote {
+    pub security_code: String,
+    pub date: NaiveDate,
+    pub opening_price: Decimal,
+    pub highest_price: Decimal,
+    pub lowest_price: Decimal,
+    pub closing_price: Decimal,
+    pub change: Decimal,
+    pub change_range: Decimal,
+    pub trading_volume: Decimal,
+    pub trade_value: Decimal,
+    pub transaction: Decimal,
+}
+
+impl From<&DailyQuote> for Quote {
+    fn from(dq: &DailyQuote) -> Self {
+        Quote {
+            security_code: dq.security_code.to_string(),
+            date: dq.date,
+            opening_price: dq.opening_price,
+            highest_price: dq.highest_price,
+            lowest_price: dq.lowest_price,
+            closing_price: dq.closing_price,
+            change: dq.change,
+            change_range: dq.change_range,
+            trading_volume: dq.trading_volume,
+            trade_value: dq.trade_value,
+            transaction: dq.transaction,
+        }
+    }
+}
+
+/// 發布的警示
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Alert<'a> {
+    /// 警示的規則 ex. price_alert
+    pub rule: &'a str,
+    pub message: &'a str,
+}
+
+/// 是否設定了 publisher.backend
+pub fn is_enabled() -> bool {
+    !SETTINGS.load().publisher.backend.is_empty()
+}
+
+/// 在背景將每一筆數據各自以 JSON 發布到設定檔 publisher 指定的 Kafka 或 NATS，未設定時不做任何事
+/// ex. `publisher::publish(publisher::REVENUE, [&revenue])`
+pub fn publish<T: Serialize>(kind: &'static str, items: impl IntoIterator<Item = T>) {
+    if !is_enabled() {
+        return;
+    }
+
+    let payloads: Vec<String> = items
+        .into_iter()
+        .filter_map(|data| match encode(kind, data, Local::now()) {
+            Ok(payload) => Some(payload),
+            Err(why) => {
+                logging::error_file_async(format!("{:?}", why));
+                None
+            }
+        })
+        .collect();
+    if payloads.is_empty() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        if let Err(why) = send(kind, payloads).await {
+            logging::error_file_async(format!("{:?}", why));
+        }
+    });
+}
+
+fn encode<T: Serialize>(kind: &str, data: T, published_time: DateTime<Local>) -> Result<String> {
+    serde_json::to_string(&Envelope {
+        kind,
+        published_time,
+        data,
+    })
+    .map_err(|why| {
+        anyhow!(
+            "Failed to serialize {} for publisher because {:?}",
+            kind,
+            why
+        )
+    })
+}
+
+fn topic() -> String {
+    match SETTINGS.load().publisher.topic.as_str() {
+        "" => DEFAULT_TOPIC.to_string(),
+        topic => topic.to_string(),
+    }
+}
+
+async fn send(kind: &str, payloads: Vec<String>) -> Result<()> {
+    let topic = topic();
+
+    match SETTINGS.load().publisher.backend.to_lowercase().as_str() {
+        #[cfg(feature = "kafka")]
+        "kafka" => kafka::send(&topic, kind, &payloads).await,
+        #[cfg(feature = "nats")]
+        "nats" => nats::send(&format!("{}.{}", topic, kind), &payloads).await,
+        backend => {
+            if UNSUPPORTED_LOGGED.swap(true, Ordering::Relaxed) {
+                return Ok(());
+            }
+
+            Err(anyhow!(
+                "Failed to publish {} {} messages to {} because backend {} is not supported, build with --features kafka or --features nats",
+                payloads.len(),
+                kind,
+                topic,
+                backend
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let mut dq = DailyQuote::new("2330".parse().unwrap());
+        dq.date = NaiveDate::from_ymd_opt(2024, 6, 12).unwrap();
+        dq.closing_price = dec!(900);
+        let published_time = Local.with_ymd_and_hms(2024, 6, 12, 15, 0, 0).unwrap();
+
+        let payload = encode(QUOTE, Quote::from(&dq), published_time).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&payload).unwrap();
+
+        assert_eq!(json["kind"], "quote");
+        assert_eq!(json["data"]["security_code"], "2330");
+        assert_eq!(json["data"]["date"], "2024-06-12");
+        assert_eq!(json["data"]["closing_price"], "900");
+
+        let payload = encode(
+            ALERT,
+            Alert {
+                rule: "price_alert",
+                message: "2330 突破 1000",
+            },
+            published_time,
+        )
+        .unwrap();
+        assert!(payload.contains(r#""rule":"price_alert""#));
+    }
+}
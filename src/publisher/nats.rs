@@ -0,0 +1,37 @@
+use anyhow::{Context, Result};
+use async_nats::Client;
+use tokio::sync::OnceCell;
+
+use crate::config::SETTINGS;
+
+static CLIENT: OnceCell<Client> = OnceCell::const_new();
+
+async fn client() -> Result<&'static Client> {
+    CLIENT
+        .get_or_try_init(|| async {
+            async_nats::connect(SETTINGS.load().publisher.url.as_str())
+                .await
+                .context(format!(
+                    "Failed to connect to nats {}",
+                    SETTINGS.load().publisher.url
+                ))
+        })
+        .await
+}
+
+/// 依序發布到 subject 後等待送出
+pub async fn send(subject: &str, payloads: &[String]) -> Result<()> {
+    let client = client().await?;
+
+    for payload in payloads {
+        client
+            .publish(subject.to_string(), payload.clone().into())
+            .await
+            .context(format!("Failed to publish to nats subject {}", subject))?;
+    }
+
+    client
+        .flush()
+        .await
+        .context(format!("Failed to flush nats subject {}", subject))
+}
@@ -0,0 +1,250 @@
+use std::{collections::HashSet, fmt::Write};
+
+use anyhow::Result;
+use chrono::{Datelike, Local, NaiveDate};
+
+use crate::{
+    bot,
+    cache::SHARE,
+    crawler::twse,
+    database::table::{financial_statement, stock_ownership_details::StockOwnershipDetail},
+    declare::{Quarter, SecurityType, StockExchangeMarket, StockSymbol},
+    logging,
+};
+
+/// 過了申報期限後持續檢查的天數，超過後不再通知
+const FOLLOW_UP_DAYS: i64 = 14;
+
+/// 上市櫃公司財報的法定申報期限
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Deadline {
+    /// 申報期限
+    pub date: NaiveDate,
+    /// 財報所屬的年度
+    pub year: i32,
+    /// 季報的季度，年報為 None
+    pub quarter: Option<Quarter>,
+}
+
+impl Deadline {
+    /// 財報的名稱 ex. 2023年度財報、2024年第一季財報
+    pub fn report_name(&self) -> String {
+        match self.quarter {
+            None => format!("{}年度財報", self.year),
+            Some(quarter) => {
+                let season = match quarter {
+                    Quarter::Q1 => "第一季",
+                    Quarter::Q2 => "第二季",
+                    Quarter::Q3 => "第三季",
+                    Quarter::Q4 => "第四季",
+                };
+                format!("{}年{}財報", self.year, season)
+            }
+        }
+    }
+
+    /// financial_statement 內代表已收錄的季度，年報以全年度或第四季任一筆為準
+    fn recorded_quarters(&self) -> Vec<String> {
+        match self.quarter {
+            None => vec![String::new(), Quarter::Q4.to_string()],
+            Some(quarter) => vec![quarter.to_string()],
+        }
+    }
+
+    /// 公開資訊觀測站查詢的季度，年報以第四季查詢全年度的數據
+    fn season(&self) -> Quarter {
+        self.quarter.unwrap_or(Quarter::Q4)
+    }
+}
+
+/// 指定年度內的申報期限：年報 3/31、第一季 5/15、第二季 8/14、第三季 11/14
+pub fn deadlines(year: i32) -> Vec<Deadline> {
+    [
+        (3, 31, year - 1, None),
+        (5, 15, year, Some(Quarter::Q1)),
+        (8, 14, year, Some(Quarter::Q2)),
+        (11, 14, year, Some(Quarter::Q3)),
+    ]
+    .into_iter()
+    .filter_map(|(month, day, report_year, quarter)| {
+        Some(Deadline {
+            date: NaiveDate::from_ymd_opt(year, month, day)?,
+            year: report_year,
+            quarter,
+        })
+    })
+    .collect()
+}
+
+/// 指定日期需要檢查的申報期限，只有在期限過後的 FOLLOW_UP_DAYS 天內才需要檢查
+fn due(date: NaiveDate) -> Option<Deadline> {
+    deadlines(date.year()).into_iter().find(|deadline| {
+        let days = (date - deadline.date).num_days();
+        days > 0 && days <= FOLLOW_UP_DAYS
+    })
+}
+
+/// 過了財報申報期限後，列出庫存股票中資料庫仍沒有財報的股票，
+/// 並以公開資訊觀測站的彙總表區分為尚未收錄或公司延遲申報後以 Telegram 通知
+pub async fn execute() -> Result<()> {
+    let Some(deadline) = due(Local::now().date_naive()) else {
+        return Ok(());
+    };
+
+    let mut held: Vec<StockSymbol> = Vec::new();
+    for detail in StockOwnershipDetail::fetch(None).await? {
+        if held.contains(&detail.security_code) || !files_statement(&detail.security_code).await {
+            continue;
+        }
+        held.push(detail.security_code);
+    }
+    if held.is_empty() {
+        return Ok(());
+    }
+
+    let recorded: HashSet<StockSymbol> = financial_statement::fetch_recorded_symbols(
+        deadline.year,
+        &deadline.recorded_quarters(),
+        &held,
+    )
+    .await?
+    .into_iter()
+    .collect();
+    if held.iter().all(|symbol| recorded.contains(symbol)) {
+        logging::info_file_async(format!(
+            "庫存 {} 檔股票的{}皆已收錄",
+            held.len(),
+            deadline.report_name()
+        ));
+        return Ok(());
+    }
+
+    let mut published = HashSet::new();
+    for market in [
+        StockExchangeMarket::Listed,
+        StockExchangeMarket::OverTheCounter,
+    ] {
+        published.extend(
+            twse::eps::visit(market, deadline.year, deadline.season())
+                .await?
+                .into_iter()
+                .map(|eps| eps.stock_symbol),
+        );
+    }
+
+    let (not_crawled, delayed) = classify(&held, &recorded, &published);
+    let msg = summary(&deadline, &not_crawled, &delayed).await;
+    logging::warn_file_async(msg.clone());
+    bot::telegram::send(&msg).await;
+
+    Ok(())
+}
+
+/// ETF、權證與存託憑證不需要申報財報
+async fn files_statement(symbol: &str) -> bool {
+    match SHARE.get_stock(symbol).await {
+        Some(stock) => {
+            !stock.is_tdr()
+                && !matches!(
+                    SecurityType::from(stock.security_type),
+                    SecurityType::Etf | SecurityType::Warrant
+                )
+        }
+        None => false,
+    }
+}
+
+/// 將資料庫沒有財報的股票分為公開資訊觀測站已公布但尚未收錄，以及公司尚未申報兩類
+fn classify(
+    held: &[StockSymbol],
+    recorded: &HashSet<StockSymbol>,
+    published: &HashSet<StockSymbol>,
+) -> (Vec<StockSymbol>, Vec<StockSymbol>) {
+    held.iter()
+        .filter(|symbol| !recorded.contains(*symbol))
+        .cloned()
+        .partition(|symbol| published.contains(symbol))
+}
+
+async fn summary(
+    deadline: &Deadline,
+    not_crawled: &[StockSymbol],
+    delayed: &[StockSymbol],
+) -> String {
+    let mut msg = format!(
+        "{}申報期限 {} 已過，庫存股票仍缺 {} 檔",
+        deadline.report_name(),
+        deadline.date,
+        not_crawled.len() + delayed.len()
+    );
+
+    for (title, symbols) in [("已公布但尚未收錄", not_crawled), ("公司延遲申報", delayed)]
+    {
+        if symbols.is_empty() {
+            continue;
+        }
+
+        let _ = write!(&mut msg, "\r\n{}:", title);
+        for symbol in symbols {
+            let name = SHARE
+                .get_stock(symbol)
+                .await
+                .map(|stock| stock.name)
+                .unwrap_or_default();
+            let _ = write!(&mut msg, "\r\n  {} {}", symbol, name);
+        }
+    }
+
+    msg
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_deadlines() {
+        let deadlines = deadlines(2024);
+
+        assert_eq!(deadlines.len(), 4);
+        assert_eq!(deadlines[0].report_name(), "2023年度財報");
+        assert_eq!(deadlines[0].season(), Quarter::Q4);
+        assert_eq!(deadlines[1].report_name(), "2024年第一季財報");
+        assert_eq!(
+            deadlines[3].date,
+            NaiveDate::from_ymd_opt(2024, 11, 14).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_due() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        assert_eq!(due(date(2024, 5, 15)), None);
+        assert_eq!(
+            due(date(2024, 5, 16)).map(|d| d.quarter),
+            Some(Some(Quarter::Q1))
+        );
+        assert_eq!(
+            due(date(2024, 5, 29)).map(|d| d.quarter),
+            Some(Some(Quarter::Q1))
+        );
+        assert_eq!(due(date(2024, 5, 30)), None);
+        assert_eq!(due(date(2024, 4, 1)).map(|d| d.year), Some(2023));
+        assert_eq!(due(date(2024, 1, 10)), None);
+    }
+
+    #[test]
+    fn test_classify() {
+        let symbol = |code: &str| code.parse::<StockSymbol>().unwrap();
+        let held = vec![symbol("2330"), symbol("2317"), symbol("1101")];
+        let recorded = HashSet::from([symbol("2330")]);
+        let published = HashSet::from([symbol("2330"), symbol("2317")]);
+
+        let (not_crawled, delayed) = classify(&held, &recorded, &published);
+
+        assert_eq!(not_crawled, vec![symbol("2317")]);
+        assert_eq!(delayed, vec![symbol("1101")]);
+    }
+}
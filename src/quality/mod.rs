@@ -0,0 +1,368 @@
+/// 財報申報期限過後檢查庫存股票是否缺少財報
+pub mod filing;
+/// 收盤價與雅虎報價的比對
+pub mod reconciliation;
+/// 重新計算歷史的衍生數據並與儲存的值比對
+pub mod verify;
+
+use std::fmt::Write;
+
+use anyhow::{Context, Result};
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::{
+    bot::{self, telegram::fmt},
+    database,
+    database::table::quality_report::QualityReport,
+    logging,
+};
+
+/// 有成交的報價數低於上市櫃股票數的此百分比時視為報價缺漏
+const MIN_QUOTE_COVERAGE_PERCENT: Decimal = dec!(90);
+/// 與前一個交易日相比收盤價漲跌超過此百分比，且當日沒有除權息時視為異常跳動
+const MAX_PRICE_JUMP_PERCENT: Decimal = dec!(30);
+/// 上市櫃公司須於每月 10 日前公告上月營收
+const REVENUE_DEADLINE_DAY: u32 = 10;
+/// 上月營收缺漏的股票數超過上市櫃股票數的此百分比時視為營收缺漏
+const MAX_REVENUE_MISSING_PERCENT: Decimal = dec!(5);
+/// 通知內最多列出的股票數
+const MAX_LISTED_SYMBOLS: usize = 20;
+
+/// 單一項檢查的結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub passed: bool,
+    pub detail: String,
+}
+
+impl CheckResult {
+    fn new(name: &'static str, passed: bool, detail: String) -> Self {
+        CheckResult {
+            name,
+            passed,
+            detail,
+        }
+    }
+}
+
+/// 收盤數據匯總後執行各項數據品質檢查，每項檢查寫入一筆 quality_report，有任何一項未通過時發送通知
+pub async fn execute(date: NaiveDate) -> Result<Vec<CheckResult>> {
+    let results = vec![
+        check_or_fail("quote_count", check_quote_count(date).await),
+        check_or_fail("price_jump", check_price_jump(date).await),
+        check_or_fail("revenue_missing", check_revenue_missing(date).await),
+        check_or_fail("critical_columns", check_critical_columns(date).await),
+    ];
+
+    for result in &results {
+        let report = QualityReport {
+            date,
+            check_name: result.name.to_string(),
+            passed: result.passed,
+            detail: result.detail.clone(),
+        };
+
+        if let Err(why) = report.upsert().await {
+            logging::error_file_async(format!("{:?}", why));
+        }
+    }
+
+    if let Some(msg) = format_failures(date, &results) {
+        bot::telegram::send(&msg).await;
+    }
+
+    Ok(results)
+}
+
+/// 檢查本身執行失敗時也視為未通過
+fn check_or_fail(name: &'static str, result: Result<CheckResult>) -> CheckResult {
+    result.unwrap_or_else(|why| {
+        logging::error_file_async(format!("Failed to check {} because {:?}", name, why));
+        CheckResult::new(name, false, format!("檢查失敗:{}", why))
+    })
+}
+
+fn format_failures(date: NaiveDate, results: &[CheckResult]) -> Option<String> {
+    let failed: Vec<&CheckResult> = results.iter().filter(|r| !r.passed).collect();
+    if failed.is_empty() {
+        return None;
+    }
+
+    let mut msg = format!("{} 數據品質檢查未通過\n", date);
+    for result in failed {
+        let _ = writeln!(
+            msg,
+            "[{}] {}",
+            fmt::escape_markdown(result.name),
+            fmt::escape_markdown(&result.detail)
+        );
+    }
+
+    Some(msg)
+}
+
+fn join_symbols(symbols: &[String]) -> String {
+    let mut joined = symbols
+        .iter()
+        .take(MAX_LISTED_SYMBOLS)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(",");
+    if symbols.len() > MAX_LISTED_SYMBOLS {
+        joined.push_str(&format!(" 等 {} 檔", symbols.len()));
+    }
+
+    joined
+}
+
+/// 有成交的報價數與上市櫃股票數比較
+async fn check_quote_count(date: NaiveDate) -> Result<CheckResult> {
+    let sql = r#"
+SELECT
+    (SELECT count(*) FROM "DailyQuotes" WHERE "Date" = $1 AND "TradingVolume" > 0) AS actual,
+    (SELECT count(*) FROM stocks WHERE stock_exchange_market_id IN (2, 4) AND "SuspendListing" = false) AS expected;
+"#;
+    let (actual, expected): (i64, i64) = sqlx::query_as(sql)
+        .bind(date)
+        .fetch_one(database::get_connection())
+        .await
+        .context(format!(
+            "Failed to check_quote_count({}) from database",
+            date
+        ))?;
+
+    Ok(evaluate_quote_count(actual, expected))
+}
+
+fn evaluate_quote_count(actual: i64, expected: i64) -> CheckResult {
+    let passed = expected == 0
+        || Decimal::from(actual) * dec!(100)
+            >= Decimal::from(expected) * MIN_QUOTE_COVERAGE_PERCENT;
+
+    CheckResult::new(
+        "quote_count",
+        passed,
+        format!("有成交的報價 {} 筆，上市櫃股票 {} 檔", actual, expected),
+    )
+}
+
+/// 收盤價與前一個交易日相比跳動過大且當日沒有除權息的股票
+async fn check_price_jump(date: NaiveDate) -> Result<CheckResult> {
+    let sql = r#"
+WITH previous AS (
+    SELECT DISTINCT ON ("SecurityCode") "SecurityCode", "ClosingPrice"
+    FROM "DailyQuotes"
+    WHERE "Date" < $1 AND "Date" >= $1 - 30
+    ORDER BY "SecurityCode", "Date" DESC
+)
+SELECT dq."SecurityCode"
+FROM "DailyQuotes" AS dq
+INNER JOIN previous AS p ON p."SecurityCode" = dq."SecurityCode"
+WHERE dq."Date" = $1
+    AND dq."ClosingPrice" > 0
+    AND p."ClosingPrice" > 0
+    AND abs(dq."ClosingPrice" - p."ClosingPrice") * 100 / p."ClosingPrice" > $2
+    AND NOT EXISTS (
+        SELECT 1 FROM dividend AS d
+        WHERE d.security_code = dq."SecurityCode"
+            AND d.year = $3
+            AND (d."ex-dividend_date1" = $4 OR d."ex-dividend_date2" = $4)
+    )
+ORDER BY dq."SecurityCode";
+"#;
+    let symbols: Vec<String> = sqlx::query_scalar(sql)
+        .bind(date)
+        .bind(MAX_PRICE_JUMP_PERCENT)
+        .bind(date.year())
+        .bind(date.format("%Y-%m-%d").to_string())
+        .fetch_all(database::get_connection())
+        .await
+        .context(format!(
+            "Failed to check_price_jump({}) from database",
+            date
+        ))?;
+
+    Ok(if symbols.is_empty() {
+        CheckResult::new("price_jump", true, "沒有異常跳動的收盤價".to_string())
+    } else {
+        CheckResult::new(
+            "price_jump",
+            false,
+            format!(
+                "收盤價漲跌超過 {}% 且當日無除權息:{}",
+                MAX_PRICE_JUMP_PERCENT,
+                join_symbols(&symbols)
+            ),
+        )
+    })
+}
+
+/// 過了申報期限仍沒有上月營收的股票
+async fn check_revenue_missing(date: NaiveDate) -> Result<CheckResult> {
+    let Some(revenue_month) = revenue_month(date) else {
+        return Ok(CheckResult::new(
+            "revenue_missing",
+            true,
+            "尚未到營收申報期限".to_string(),
+        ));
+    };
+
+    let sql = r#"
+SELECT s.stock_symbol
+FROM stocks AS s
+WHERE s.stock_exchange_market_id IN (2, 4)
+    AND s."SuspendListing" = false
+    AND s.stock_industry_id > 0
+    AND NOT EXISTS (
+        SELECT 1 FROM "Revenue" AS r WHERE r."SecurityCode" = s.stock_symbol AND r."Date" = $1
+    )
+ORDER BY s.stock_symbol;
+"#;
+    let missing: Vec<String> = sqlx::query_scalar(sql)
+        .bind(revenue_month)
+        .fetch_all(database::get_connection())
+        .await
+        .context(format!(
+            "Failed to check_revenue_missing({}) from database",
+            date
+        ))?;
+    let total: i64 = sqlx::query_scalar(
+        r#"SELECT count(*) FROM stocks WHERE stock_exchange_market_id IN (2, 4) AND "SuspendListing" = false AND stock_industry_id > 0"#,
+    )
+    .fetch_one(database::get_connection())
+    .await
+    .context("Failed to count listed stocks from database")?;
+
+    let passed = total == 0
+        || Decimal::from(missing.len() as i64) * dec!(100)
+            <= Decimal::from(total) * MAX_REVENUE_MISSING_PERCENT;
+    let detail = if missing.is_empty() {
+        format!("{} 營收沒有缺漏", revenue_month)
+    } else {
+        format!(
+            "{} 營收缺漏 {}/{} 檔:{}",
+            revenue_month,
+            missing.len(),
+            total,
+            join_symbols(&missing)
+        )
+    };
+
+    Ok(CheckResult::new("revenue_missing", passed, detail))
+}
+
+/// 過了申報期限後應已公告的營收月份 ex. 2024-02-15 => 202401，期限前回傳 None
+fn revenue_month(date: NaiveDate) -> Option<i64> {
+    if date.day() <= REVENUE_DEADLINE_DAY {
+        return None;
+    }
+
+    let (year, month) = if date.month() == 1 {
+        (date.year() - 1, 12)
+    } else {
+        (date.year(), date.month() - 1)
+    };
+
+    Some(year as i64 * 100 + month as i64)
+}
+
+/// 當日報價中關鍵欄位為空值或不合理的筆數
+async fn check_critical_columns(date: NaiveDate) -> Result<CheckResult> {
+    let sql = r#"
+SELECT "SecurityCode"
+FROM "DailyQuotes"
+WHERE "Date" = $1
+    AND ("SecurityCode" = ''
+        OR "ClosingPrice" IS NULL OR "ClosingPrice" <= 0
+        OR "OpeningPrice" IS NULL OR "OpeningPrice" <= 0
+        OR "HighestPrice" < "LowestPrice")
+ORDER BY "SecurityCode";
+"#;
+    let symbols: Vec<String> = sqlx::query_scalar(sql)
+        .bind(date)
+        .fetch_all(database::get_connection())
+        .await
+        .context(format!(
+            "Failed to check_critical_columns({}) from database",
+            date
+        ))?;
+
+    Ok(if symbols.is_empty() {
+        CheckResult::new("critical_columns", true, "關鍵欄位沒有異常".to_string())
+    } else {
+        CheckResult::new(
+            "critical_columns",
+            false,
+            format!(
+                "開盤價、收盤價為空或最高價低於最低價:{}",
+                join_symbols(&symbols)
+            ),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cache::SHARE, logging};
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_evaluate_quote_count() {
+        assert!(evaluate_quote_count(1800, 1900).passed);
+        assert!(!evaluate_quote_count(1000, 1900).passed);
+        assert!(evaluate_quote_count(0, 0).passed);
+    }
+
+    #[test]
+    fn test_revenue_month() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        assert_eq!(revenue_month(date(2024, 2, 15)), Some(202401));
+        assert_eq!(revenue_month(date(2024, 1, 11)), Some(202312));
+        assert_eq!(revenue_month(date(2024, 2, 10)), None);
+    }
+
+    #[test]
+    fn test_format_failures() {
+        let date = NaiveDate::from_ymd_opt(2024, 2, 15).unwrap();
+        let mut results = vec![CheckResult::new("quote_count", true, "ok".to_string())];
+        assert_eq!(format_failures(date, &results), None);
+
+        results.push(CheckResult::new("price_jump", false, "2330".to_string()));
+        assert_eq!(
+            format_failures(date, &results),
+            Some("2024-02-15 數據品質檢查未通過\n[price\\_jump] 2330\n".to_string())
+        );
+    }
+
+    #[test]
+    fn test_join_symbols() {
+        let symbols: Vec<String> = (0..25).map(|i| format!("{}", 1100 + i)).collect();
+
+        assert_eq!(join_symbols(&symbols[..2]), "1100,1101");
+        assert!(join_symbols(&symbols).ends_with("1119 等 25 檔"));
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_execute() {
+        dotenv::dotenv().ok();
+        SHARE.load().await;
+        logging::debug_file_async("開始 quality::execute".to_string());
+
+        let date = NaiveDate::from_ymd_opt(2024, 12, 23).unwrap();
+        match execute(date).await {
+            Ok(results) => logging::debug_file_async(format!("results:{:#?}", results)),
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to quality::execute because {:?}", why))
+            }
+        }
+
+        logging::debug_file_async("結束 quality::execute".to_string());
+    }
+}
@@ -0,0 +1,175 @@
+use std::{collections::HashSet, fmt::Write, time::Duration};
+
+use anyhow::Result;
+use chrono::{Local, NaiveDate};
+use rand::seq::IndexedRandom;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::{
+    bot,
+    crawler::{yahoo::Yahoo, StockInfo},
+    database::table::{
+        daily_quote::{self, DailyQuote},
+        price_discrepancy::PriceDiscrepancy,
+        stock_ownership_details::StockOwnershipDetail,
+    },
+    declare::StockSymbol,
+    logging,
+};
+
+/// 每日隨機抽樣比對的股票數，庫存股票另外全部比對
+const SAMPLE_SIZE: usize = 30;
+/// 收盤價與雅虎報價相差超過此百分比時記錄為不一致
+const MAX_DIFF_PERCENT: Decimal = dec!(0.5);
+/// 通知內最多列出的股票數
+const MAX_LISTED_SYMBOLS: usize = 20;
+
+/// 以雅虎的報價比對當日收盤價，抽樣的股票與所有庫存股票相差超過容許範圍時
+/// 寫入 price_discrepancies 待人工修正並以 Telegram 通知
+pub async fn execute() -> Result<()> {
+    let date = Local::now().date_naive();
+    let quotes = daily_quote::fetch_daily_quotes_by_date(date).await?;
+    if quotes.is_empty() {
+        return Ok(());
+    }
+
+    let held: HashSet<StockSymbol> = StockOwnershipDetail::fetch(None)
+        .await?
+        .into_iter()
+        .map(|detail| detail.security_code)
+        .collect();
+    let targets = select(&quotes, &held, &mut rand::rng());
+    let mut discrepancies = Vec::new();
+
+    for quote in targets {
+        match Yahoo::get_stock_price(&quote.security_code).await {
+            Ok(yahoo_price) => {
+                if let Some(discrepancy) = compare(date, quote, yahoo_price) {
+                    logging::warn_file_async(format!("收盤價與雅虎不一致 {:?}", discrepancy));
+                    if let Err(why) = discrepancy.upsert().await {
+                        logging::error_file_async(format!("{:?}", why));
+                    }
+                    discrepancies.push(discrepancy);
+                }
+            }
+            Err(why) => {
+                logging::error_file_async(format!(
+                    "Failed to Yahoo::get_stock_price({}) because {:?}",
+                    quote.security_code, why
+                ));
+            }
+        }
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+
+    if let Some(msg) = format_discrepancies(date, &discrepancies) {
+        bot::telegram::send(&msg).await;
+    }
+
+    Ok(())
+}
+
+/// 庫存股票全部比對，其餘股票隨機抽樣 SAMPLE_SIZE 檔
+fn select<'a, R: rand::Rng + ?Sized>(
+    quotes: &'a [DailyQuote],
+    held: &HashSet<StockSymbol>,
+    rng: &mut R,
+) -> Vec<&'a DailyQuote> {
+    let (mut targets, rest): (Vec<&DailyQuote>, Vec<&DailyQuote>) = quotes
+        .iter()
+        .filter(|quote| quote.closing_price > Decimal::ZERO)
+        .partition(|quote| held.contains(&quote.security_code));
+    targets.extend(rest.choose_multiple(rng, SAMPLE_SIZE).copied());
+
+    targets
+}
+
+/// 雅虎的報價無法解析(為零)時不比對
+fn compare(date: NaiveDate, quote: &DailyQuote, yahoo_price: Decimal) -> Option<PriceDiscrepancy> {
+    if yahoo_price <= Decimal::ZERO || quote.closing_price <= Decimal::ZERO {
+        return None;
+    }
+
+    let diff_percent =
+        ((yahoo_price - quote.closing_price) / quote.closing_price * dec!(100)).round_dp(4);
+    if diff_percent.abs() <= MAX_DIFF_PERCENT {
+        return None;
+    }
+
+    Some(PriceDiscrepancy {
+        date,
+        security_code: quote.security_code.clone(),
+        closing_price: quote.closing_price,
+        yahoo_price,
+        diff_percent,
+    })
+}
+
+fn format_discrepancies(date: NaiveDate, discrepancies: &[PriceDiscrepancy]) -> Option<String> {
+    if discrepancies.is_empty() {
+        return None;
+    }
+
+    let mut msg = format!(
+        "{} 收盤價與雅虎不一致 {} 檔，請確認後修正 DailyQuotes 並將 price_discrepancies.is_corrected 設為 true",
+        date,
+        discrepancies.len()
+    );
+    for item in discrepancies.iter().take(MAX_LISTED_SYMBOLS) {
+        let _ = write!(
+            &mut msg,
+            "\r\n{} 收盤價:{} 雅虎:{} ({}%)",
+            item.security_code,
+            item.closing_price.normalize(),
+            item.yahoo_price.normalize(),
+            item.diff_percent.normalize()
+        );
+    }
+
+    Some(msg)
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn quote(security_code: &str, closing_price: Decimal) -> DailyQuote {
+        let mut dq = DailyQuote::new(security_code.parse().unwrap());
+        dq.closing_price = closing_price;
+        dq
+    }
+
+    #[test]
+    fn test_compare() {
+        let date = NaiveDate::from_ymd_opt(2024, 6, 13).unwrap();
+
+        assert_eq!(compare(date, &quote("2330", dec!(900)), dec!(902)), None);
+        assert_eq!(
+            compare(date, &quote("2330", dec!(900)), Decimal::ZERO),
+            None
+        );
+
+        let discrepancy = compare(date, &quote("2330", dec!(900)), dec!(910)).unwrap();
+        assert_eq!(discrepancy.security_code, "2330");
+        assert_eq!(discrepancy.diff_percent, dec!(1.1111));
+    }
+
+    #[test]
+    fn test_select() {
+        let mut quotes: Vec<DailyQuote> = (0..100)
+            .map(|i| quote(&format!("{}", 1100 + i), dec!(10)))
+            .collect();
+        quotes.push(quote("2330", dec!(900)));
+        quotes.push(quote("9999", Decimal::ZERO));
+        let held = HashSet::from(["2330".parse().unwrap(), "9999".parse().unwrap()]);
+
+        let targets = select(&quotes, &held, &mut rand::rng());
+
+        assert_eq!(targets.len(), SAMPLE_SIZE + 1);
+        assert_eq!(targets[0].security_code, "2330");
+        assert!(targets.iter().all(|quote| quote.security_code != "9999"));
+    }
+}
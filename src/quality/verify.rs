@@ -0,0 +1,392 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::NaiveDate;
+use rand::seq::IndexedRandom;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use sqlx::FromRow;
+
+use crate::{
+    database::{self, timing::Timed},
+    logging,
+};
+
+/// 命令列驗證的用法
+const USAGE: &str = "usage: stock_crawler verify <from YYYY-MM-DD> <to YYYY-MM-DD> [sample_days]";
+/// 均線以四捨五入到小數第二位儲存，差距超過此值才視為不一致
+const MOVING_AVERAGE_TOLERANCE: Decimal = dec!(0.01);
+/// 殖利率差距超過此百分點才視為不一致
+const YIELD_TOLERANCE: Decimal = dec!(0.001);
+/// 庫存市值差距超過此金額(元)才視為不一致
+const MONEY_TOLERANCE: Decimal = dec!(1);
+
+/// 重新計算後與資料庫內儲存的值不一致的欄位
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct Drift {
+    pub table_name: String,
+    pub date: NaiveDate,
+    /// 整體數據(ex. 庫存總市值)時為空字串
+    pub security_code: String,
+    pub column_name: String,
+    pub stored: String,
+    pub expected: String,
+}
+
+/// 依命令列參數重新計算區間內(或區間內隨機抽樣 sample_days 個交易日)的衍生數據並輸出不一致的欄位
+/// ex. `stock_crawler verify 2024-01-01 2024-06-30 10`
+pub async fn command(args: &[String]) -> Result<()> {
+    let (from, to) = match (args.first(), args.get(1)) {
+        (Some(from), Some(to)) => (
+            NaiveDate::parse_from_str(from, "%Y-%m-%d")?,
+            NaiveDate::parse_from_str(to, "%Y-%m-%d")?,
+        ),
+        _ => return Err(anyhow!(USAGE)),
+    };
+    let sample = match args.get(2) {
+        Some(sample) => Some(sample.parse::<usize>().map_err(|_| anyhow!(USAGE))?),
+        None => None,
+    };
+
+    let trading_days = fetch_trading_days(from, to).await?;
+    let dates = sample_dates(&trading_days, sample, &mut rand::rng());
+    if dates.is_empty() {
+        println!("no trading days between {} and {}", from, to);
+        return Ok(());
+    }
+
+    let drifts = execute(&dates).await?;
+    for drift in &drifts {
+        println!(
+            "{}\t{}\t{}\t{}\tstored {}\texpected {}",
+            drift.table_name,
+            drift.date,
+            drift.security_code,
+            drift.column_name,
+            drift.stored,
+            drift.expected
+        );
+    }
+
+    let summary = summarize(&drifts);
+    logging::info_file_async(format!(
+        "驗證 {} 個交易日({} ~ {}) 的衍生數據，不一致:{:?}",
+        dates.len(),
+        from,
+        to,
+        summary
+    ));
+    println!(
+        "verified {} trading days between {} and {}",
+        dates.len(),
+        from,
+        to
+    );
+    for (table, count) in summary {
+        println!("{}\t{} drifts", table, count);
+    }
+
+    Ok(())
+}
+
+/// 重新計算指定交易日的均線、殖利率排行、庫存市值與目前的 last_daily_quotes，回傳不一致的欄位
+pub async fn execute(dates: &[NaiveDate]) -> Result<Vec<Drift>> {
+    let mut drifts = verify_moving_average(dates).await?;
+    drifts.extend(verify_last_daily_quotes().await?);
+    drifts.extend(verify_yield_rank(dates).await?);
+    drifts.extend(verify_money_history(dates).await?);
+
+    Ok(drifts)
+}
+
+/// 區間內有報價的交易日，由舊到新排序
+async fn fetch_trading_days(from: NaiveDate, to: NaiveDate) -> Result<Vec<NaiveDate>> {
+    let sql = r#"
+SELECT DISTINCT "Date"
+FROM "DailyQuotes"
+WHERE "Date" BETWEEN $1 AND $2
+ORDER BY "Date";
+"#;
+    sqlx::query_scalar(sql)
+        .bind(from)
+        .bind(to)
+        .fetch_all(database::get_connection())
+        .timed("DailyQuotes", "fetch_trading_days")
+        .await
+        .context(format!(
+            "Failed to fetch_trading_days({}, {}) from database",
+            from, to
+        ))
+}
+
+/// 未指定抽樣數時回傳全部的交易日，否則隨機抽出 sample 個交易日並依日期排序
+fn sample_dates<R: rand::Rng + ?Sized>(
+    trading_days: &[NaiveDate],
+    sample: Option<usize>,
+    rng: &mut R,
+) -> Vec<NaiveDate> {
+    let mut dates: Vec<NaiveDate> = match sample {
+        Some(sample) => trading_days.choose_multiple(rng, sample).copied().collect(),
+        None => trading_days.to_vec(),
+    };
+    dates.sort();
+
+    dates
+}
+
+/// 各表不一致的筆數，依表名排序
+fn summarize(drifts: &[Drift]) -> BTreeMap<&str, usize> {
+    let mut summary = BTreeMap::new();
+    for drift in drifts {
+        *summary.entry(drift.table_name.as_str()).or_insert(0) += 1;
+    }
+
+    summary
+}
+
+/// 以視窗函數重新計算均線，計算方式與 DailyQuote::fill_moving_average 相同：
+/// 取 400 天內最近 n 筆收盤價的平均，筆數不足 n 筆時為 0
+async fn verify_moving_average(dates: &[NaiveDate]) -> Result<Vec<Drift>> {
+    let (Some(first), Some(last)) = (dates.iter().min(), dates.iter().max()) else {
+        return Ok(Vec::new());
+    };
+    let sql = r#"
+WITH windowed AS (
+    SELECT
+        "Date",
+        "SecurityCode",
+        "MovingAverage5",
+        "MovingAverage10",
+        "MovingAverage20",
+        "MovingAverage60",
+        "MovingAverage120",
+        "MovingAverage240",
+        CASE WHEN count(*) OVER w5 = 5 AND min("Date") OVER w5 >= "Date" - 400
+            THEN round(avg("ClosingPrice") OVER w5, 2) ELSE 0 END AS expected5,
+        CASE WHEN count(*) OVER w10 = 10 AND min("Date") OVER w10 >= "Date" - 400
+            THEN round(avg("ClosingPrice") OVER w10, 2) ELSE 0 END AS expected10,
+        CASE WHEN count(*) OVER w20 = 20 AND min("Date") OVER w20 >= "Date" - 400
+            THEN round(avg("ClosingPrice") OVER w20, 2) ELSE 0 END AS expected20,
+        CASE WHEN count(*) OVER w60 = 60 AND min("Date") OVER w60 >= "Date" - 400
+            THEN round(avg("ClosingPrice") OVER w60, 2) ELSE 0 END AS expected60,
+        CASE WHEN count(*) OVER w120 = 120 AND min("Date") OVER w120 >= "Date" - 400
+            THEN round(avg("ClosingPrice") OVER w120, 2) ELSE 0 END AS expected120,
+        CASE WHEN count(*) OVER w240 = 240 AND min("Date") OVER w240 >= "Date" - 400
+            THEN round(avg("ClosingPrice") OVER w240, 2) ELSE 0 END AS expected240
+    FROM "DailyQuotes"
+    WHERE "Date" BETWEEN $2::date - 400 AND $3
+    WINDOW
+        w5 AS (PARTITION BY "SecurityCode" ORDER BY "Date" ROWS BETWEEN 4 PRECEDING AND CURRENT ROW),
+        w10 AS (PARTITION BY "SecurityCode" ORDER BY "Date" ROWS BETWEEN 9 PRECEDING AND CURRENT ROW),
+        w20 AS (PARTITION BY "SecurityCode" ORDER BY "Date" ROWS BETWEEN 19 PRECEDING AND CURRENT ROW),
+        w60 AS (PARTITION BY "SecurityCode" ORDER BY "Date" ROWS BETWEEN 59 PRECEDING AND CURRENT ROW),
+        w120 AS (PARTITION BY "SecurityCode" ORDER BY "Date" ROWS BETWEEN 119 PRECEDING AND CURRENT ROW),
+        w240 AS (PARTITION BY "SecurityCode" ORDER BY "Date" ROWS BETWEEN 239 PRECEDING AND CURRENT ROW)
+)
+SELECT
+    'DailyQuotes' AS table_name,
+    w."Date" AS date,
+    w."SecurityCode" AS security_code,
+    v.column_name,
+    v.stored::text AS stored,
+    v.expected::text AS expected
+FROM windowed AS w
+CROSS JOIN LATERAL (VALUES
+    ('MovingAverage5', w."MovingAverage5", w.expected5),
+    ('MovingAverage10', w."MovingAverage10", w.expected10),
+    ('MovingAverage20', w."MovingAverage20", w.expected20),
+    ('MovingAverage60', w."MovingAverage60", w.expected60),
+    ('MovingAverage120', w."MovingAverage120", w.expected120),
+    ('MovingAverage240', w."MovingAverage240", w.expected240)
+) AS v(column_name, stored, expected)
+WHERE w."Date" = ANY($1) AND abs(v.stored - v.expected) > $4
+ORDER BY w."Date", w."SecurityCode", v.column_name;
+"#;
+    sqlx::query_as::<_, Drift>(sql)
+        .bind(dates)
+        .bind(first)
+        .bind(last)
+        .bind(MOVING_AVERAGE_TOLERANCE)
+        .fetch_all(database::get_connection())
+        .timed("DailyQuotes", "verify_moving_average")
+        .await
+        .context(format!(
+            "Failed to verify_moving_average({}, {}) from database",
+            first, last
+        ))
+}
+
+/// last_daily_quotes 應為各股票在 DailyQuotes 內最新一筆的報價
+async fn verify_last_daily_quotes() -> Result<Vec<Drift>> {
+    let sql = r#"
+SELECT
+    'last_daily_quotes' AS table_name,
+    l.date,
+    l.security_code,
+    v.column_name,
+    v.stored,
+    v.expected
+FROM last_daily_quotes AS l
+CROSS JOIN LATERAL (
+    SELECT "Date", "ClosingPrice"
+    FROM "DailyQuotes"
+    WHERE "SecurityCode" = l.security_code
+    ORDER BY "Date" DESC
+    LIMIT 1
+) AS q
+CROSS JOIN LATERAL (VALUES
+    ('date', l.date::text, q."Date"::text),
+    ('closing_price', l.closing_price::text, q."ClosingPrice"::text)
+) AS v(column_name, stored, expected)
+WHERE (v.column_name = 'date' AND l.date <> q."Date")
+    OR (v.column_name = 'closing_price' AND l.closing_price <> q."ClosingPrice")
+ORDER BY l.security_code, v.column_name;
+"#;
+    sqlx::query_as::<_, Drift>(sql)
+        .fetch_all(database::get_connection())
+        .timed("last_daily_quotes", "verify")
+        .await
+        .context("Failed to verify_last_daily_quotes() from database")
+}
+
+/// 殖利率應為引用的股利合計 ÷ 引用的收盤價 × 100，引用的數據已不存在時也列出
+async fn verify_yield_rank(dates: &[NaiveDate]) -> Result<Vec<Drift>> {
+    let sql = r#"
+SELECT
+    'yield_rank' AS table_name,
+    y.date,
+    y.security_code,
+    'yield' AS column_name,
+    y.yield::text AS stored,
+    COALESCE(round(d."sum" / NULLIF(dq."ClosingPrice", 0) * 100, 4)::text, 'missing') AS expected
+FROM yield_rank AS y
+LEFT JOIN dividend AS d ON d.serial = y.dividend_serial
+LEFT JOIN "DailyQuotes" AS dq ON dq."Serial" = y.daily_quotes_serial
+WHERE y.date = ANY($1)
+    AND (d.serial IS NULL
+        OR dq."Serial" IS NULL
+        OR (dq."ClosingPrice" > 0 AND abs(y.yield - d."sum" / dq."ClosingPrice" * 100) > $2))
+ORDER BY y.date, y.security_code;
+"#;
+    sqlx::query_as::<_, Drift>(sql)
+        .bind(dates)
+        .bind(YIELD_TOLERANCE)
+        .fetch_all(database::get_connection())
+        .timed("yield_rank", "verify")
+        .await
+        .context("Failed to verify_yield_rank() from database")
+}
+
+/// 明細的收盤價應與當日報價相同，庫存總市值應為明細股數 × 當日收盤價的合計
+async fn verify_money_history(dates: &[NaiveDate]) -> Result<Vec<Drift>> {
+    let sql = r#"
+WITH detail AS (
+    SELECT d.date, d.security_code, d.total_shares, d.closing_price, dq."ClosingPrice" AS quote_price
+    FROM daily_money_history_detail AS d
+    INNER JOIN "DailyQuotes" AS dq ON dq."Date" = d.date AND dq."SecurityCode" = d.security_code
+    WHERE d.date = ANY($1)
+),
+expected AS (
+    SELECT date, SUM(total_shares * quote_price) AS sum
+    FROM detail
+    GROUP BY date
+)
+SELECT
+    'daily_money_history' AS table_name,
+    h.date,
+    '' AS security_code,
+    'sum' AS column_name,
+    h.sum::text AS stored,
+    round(e.sum, 4)::text AS expected
+FROM daily_money_history AS h
+INNER JOIN expected AS e ON e.date = h.date
+WHERE abs(h.sum - e.sum) > $2
+UNION ALL
+SELECT DISTINCT
+    'daily_money_history_detail' AS table_name,
+    date,
+    security_code,
+    'closing_price' AS column_name,
+    closing_price::text AS stored,
+    quote_price::text AS expected
+FROM detail
+WHERE closing_price <> quote_price
+ORDER BY table_name, date, security_code;
+"#;
+    sqlx::query_as::<_, Drift>(sql)
+        .bind(dates)
+        .bind(MONEY_TOLERANCE)
+        .fetch_all(database::get_connection())
+        .timed("daily_money_history", "verify")
+        .await
+        .context("Failed to verify_money_history() from database")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cache::SHARE, logging};
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn date(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, d).unwrap()
+    }
+
+    #[test]
+    fn test_sample_dates() {
+        let trading_days: Vec<NaiveDate> = (3..=28).map(date).collect();
+        let mut rng = rand::rng();
+
+        assert_eq!(sample_dates(&trading_days, None, &mut rng), trading_days);
+
+        let sampled = sample_dates(&trading_days, Some(5), &mut rng);
+        assert_eq!(sampled.len(), 5);
+        assert!(sampled.windows(2).all(|w| w[0] < w[1]));
+        assert!(sampled.iter().all(|d| trading_days.contains(d)));
+
+        assert_eq!(sample_dates(&trading_days[..2], Some(5), &mut rng).len(), 2);
+    }
+
+    #[test]
+    fn test_summarize() {
+        let drift = |table_name: &str| Drift {
+            table_name: table_name.to_string(),
+            date: date(3),
+            security_code: "2330".to_string(),
+            column_name: "yield".to_string(),
+            stored: "1".to_string(),
+            expected: "2".to_string(),
+        };
+
+        let drifts = vec![
+            drift("yield_rank"),
+            drift("DailyQuotes"),
+            drift("yield_rank"),
+        ];
+
+        let summary = summarize(&drifts);
+
+        assert_eq!(
+            summary.into_iter().collect::<Vec<_>>(),
+            vec![("DailyQuotes", 1), ("yield_rank", 2)]
+        );
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_execute() {
+        dotenv::dotenv().ok();
+        SHARE.load().await;
+        logging::debug_file_async("開始 verify::execute".to_string());
+
+        match execute(&[date(28)]).await {
+            Ok(drifts) => logging::debug_file_async(format!("drifts:{:#?}", drifts)),
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to verify::execute because {:?}", why))
+            }
+        }
+
+        logging::debug_file_async("結束 verify::execute".to_string());
+    }
+}
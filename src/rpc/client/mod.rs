@@ -17,12 +17,13 @@ struct Grpc {
 
 impl Grpc {
     pub async fn new() -> Result<Self> {
-        let pem = fs::read_to_string(&SETTINGS.rpc.go_service.tls_cert_file).await?;
+        let settings = SETTINGS.load();
+        let pem = fs::read_to_string(&settings.rpc.go_service.tls_cert_file).await?;
         let ca = Certificate::from_pem(pem);
         let tls = ClientTlsConfig::new()
             .ca_certificate(ca)
-            .domain_name(&SETTINGS.rpc.go_service.domain_name);
-        let channel = Channel::from_static(&SETTINGS.rpc.go_service.target)
+            .domain_name(&settings.rpc.go_service.domain_name);
+        let channel = Channel::from_shared(settings.rpc.go_service.target.clone())?
             .tls_config(tls)?
             .connect()
             .await?;
@@ -74,7 +74,7 @@ mod tests {
     #[ignore]
     async fn test_control_request_to_server() {
         dotenv::dotenv().ok();
-        let pem = std::fs::read_to_string(&SETTINGS.system.ssl_cert_file).unwrap();
+        let pem = std::fs::read_to_string(&SETTINGS.load().system.ssl_cert_file).unwrap();
         let ca = Certificate::from_pem(pem);
 
         let tls = ClientTlsConfig::new()
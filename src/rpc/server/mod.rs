@@ -17,11 +17,11 @@ pub mod stock_service;
 
 /// 啟動 GRPC Server
 pub async fn start() -> Result<()> {
-    if SETTINGS.system.grpc_use_port == 0 {
+    if SETTINGS.load().system.grpc_use_port == 0 {
         return Ok(());
     }
 
-    let addr = format!("0.0.0.0:{}", SETTINGS.system.grpc_use_port).parse()?;
+    let addr = format!("0.0.0.0:{}", SETTINGS.load().system.grpc_use_port).parse()?;
 
     // 使用 tokio::spawn 啟動一個新的異步任務
     tokio::spawn(async move {
@@ -50,10 +50,12 @@ async fn run_grpc_server(addr: SocketAddr) -> Result<()> {
 }
 
 fn get_tls_config() -> Option<(String, String)> {
-    if !SETTINGS.system.ssl_cert_file.is_empty() && !SETTINGS.system.ssl_key_file.is_empty() {
+    if !SETTINGS.load().system.ssl_cert_file.is_empty()
+        && !SETTINGS.load().system.ssl_key_file.is_empty()
+    {
         Some((
-            SETTINGS.system.ssl_cert_file.clone(),
-            SETTINGS.system.ssl_key_file.clone(),
+            SETTINGS.load().system.ssl_cert_file.clone(),
+            SETTINGS.load().system.ssl_key_file.clone(),
         ))
     } else {
         None
@@ -3,6 +3,7 @@ use tonic::{Request, Response, Status};
 
 use crate::{
     crawler,
+    declare::StockSymbol,
     logging,
     rpc::{
         stock::{
@@ -14,12 +15,19 @@ use crate::{
             StockQuotesReply,
             HolidayScheduleReply,
             HolidayScheduleRequest,
-            HolidaySchedule
+            HolidaySchedule,
+            SearchStocksReply,
+            SearchStocksRequest,
+            StockMatch
         }
     },
     crawler::twse,
+    database::table::stock,
 };
 
+/// SearchStocks 未指定筆數時預設回傳的筆數
+const DEFAULT_SEARCH_LIMIT: usize = 10;
+
 #[derive(Default)]
 pub struct StockService {}
 
@@ -74,10 +82,38 @@ impl Stock for StockService {
             holiday: holiday_schedules,
         }))
     }
+
+    async fn search_stocks(
+        &self,
+        req: Request<SearchStocksRequest>,
+    ) -> Result<Response<SearchStocksReply>, Status> {
+        let request = req.into_inner();
+        let limit = match usize::try_from(request.limit) {
+            Ok(limit) if limit > 0 => limit,
+            _ => DEFAULT_SEARCH_LIMIT,
+        };
+        let stocks = stock::search(&request.query, limit)
+            .into_iter()
+            .map(|m| StockMatch {
+                stock_symbol: m.stock_symbol.to_string(),
+                name: m.name,
+            })
+            .collect();
+
+        Ok(Response::new(SearchStocksReply { stocks }))
+    }
 }
 
 async fn fetch_current_quotes_for_symbol(stock_symbol: &str) -> Option<StockQuotes> {
-    if let Ok(sq) = crawler::fetch_stock_quotes_from_remote_site(stock_symbol).await {
+    let symbol = match StockSymbol::parse(stock_symbol) {
+        Ok(symbol) => symbol,
+        Err(why) => {
+            logging::error_file_async(format!("{:?}", why));
+            return None;
+        }
+    };
+
+    if let Ok(sq) = crawler::fetch_stock_quotes_from_remote_site(&symbol).await {
         return Some(StockQuotes {
             stock_symbol: stock_symbol.to_string(),
             price: sq.price,
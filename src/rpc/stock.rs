@@ -58,6 +58,26 @@ pub struct HolidayScheduleReply {
     #[prost(message, repeated, tag = "1")]
     pub holiday: ::prost::alloc::vec::Vec<HolidaySchedule>,
 }
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SearchStocksRequest {
+    #[prost(string, tag = "1")]
+    pub query: ::prost::alloc::string::String,
+    /// 最多回傳幾筆，0 代表預設的 10 筆
+    #[prost(int32, tag = "2")]
+    pub limit: i32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StockMatch {
+    #[prost(string, tag = "1")]
+    pub stock_symbol: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SearchStocksReply {
+    #[prost(message, repeated, tag = "1")]
+    pub stocks: ::prost::alloc::vec::Vec<StockMatch>,
+}
 /// Generated client implementations.
 pub mod stock_client {
     #![allow(
@@ -220,6 +240,28 @@ pub mod stock_client {
                 .insert(GrpcMethod::new("stock.Stock", "FetchHolidaySchedule"));
             self.inner.unary(req, path, codec).await
         }
+        /// 以代號或名稱模糊搜尋股票
+        pub async fn search_stocks(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SearchStocksRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SearchStocksReply>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/stock.Stock/SearchStocks");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("stock.Stock", "SearchStocks"));
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -255,6 +297,14 @@ pub mod stock_server {
             tonic::Response<super::HolidayScheduleReply>,
             tonic::Status,
         >;
+        /// 以代號或名稱模糊搜尋股票
+        async fn search_stocks(
+            &self,
+            request: tonic::Request<super::SearchStocksRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SearchStocksReply>,
+            tonic::Status,
+        >;
     }
     #[derive(Debug)]
     pub struct StockServer<T> {
@@ -464,6 +514,51 @@ pub mod stock_server {
                     };
                     Box::pin(fut)
                 }
+                "/stock.Stock/SearchStocks" => {
+                    #[allow(non_camel_case_types)]
+                    struct SearchStocksSvc<T: Stock>(pub Arc<T>);
+                    impl<
+                        T: Stock,
+                    > tonic::server::UnaryService<super::SearchStocksRequest>
+                    for SearchStocksSvc<T> {
+                        type Response = super::SearchStocksReply;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SearchStocksRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Stock>::search_stocks(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = SearchStocksSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         let mut response = http::Response::new(empty_body());
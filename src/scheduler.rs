@@ -1,34 +1,71 @@
-use std::{env, future::Future};
+use std::{
+    collections::HashSet,
+    env,
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::Duration,
+};
 
-use anyhow::{Context, Error, Result};
+use anyhow::{anyhow, Context, Error, Result};
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use croner::Cron;
+use futures::future::BoxFuture;
+use once_cell::sync::{Lazy, OnceCell};
 use tokio_cron_scheduler::{Job, JobScheduler};
 
 use crate::{
     backfill::{
-        delisted_company, dividend, financial_statement, isin, net_asset_value_per_share,
-        qualified_foreign_institutional_investor, revenue, stock_weight,
+        dividend, financial_statement, net_asset_value_per_share,
+        registry::{self, Crawler},
     },
-    bot, declare, event,
-    event::ddns,
-    logging,
+    bot, calendar,
+    config::SETTINGS,
+    database,
+    database::table::{job_control::JobControl, job_run::JobRun},
+    declare, error, event,
+    event::ip_monitor,
+    logging::{self, run_id},
+    quality, storage, telemetry,
 };
 
+/// 多個執行個體同時運行時選出唯一執行排程任務的 leader
+pub mod leader;
+
 /// 啟動排程
 pub async fn start(sched: &JobScheduler) -> Result<()> {
+    load_controls().await;
+    if leader::is_enabled() {
+        leader::campaign().await;
+        tokio::spawn(leader::keep_alive(on_elected));
+    }
     run_cron(sched).await.context("Failed to run cron jobs")?;
 
     //若在開盤埘間重啟服務定時任務會無法觸發，所以在啟動時要先執行股價追踪的任務，執行完後再設定一次定時任務
-    if declare::StockExchange::TWSE.is_open() {
+    if leader::is_leader() && declare::StockExchange::TWSE.is_open() {
         if let Err(why) = event::trace::stock_price::execute().await {
             logging::error_file_async(format!("{:?}", why));
         }
     }
 
-    let msg = format!(
+    let mut msg = format!(
         "StockCrawler 已啟動\r\nRust OS/Arch: {}/{}\r\n",
         env::consts::OS,
         env::consts::ARCH
     );
+    if leader::is_enabled() {
+        msg.push_str(&format!(
+            "{} 目前為排程的 {}\r\n",
+            leader::token(),
+            if leader::is_leader() {
+                "leader"
+            } else {
+                "follower"
+            }
+        ));
+    }
 
     bot::telegram::send(&msg).await;
 
@@ -41,15 +78,21 @@ async fn run_cron(sched: &JobScheduler) -> Result<()> {
     //let expression = "0   30   9,12,15     1,15       May-Aug  Mon,Wed,Fri  2018/2";
     // UTC 時間
 
-    let jobs = vec![
+    let mut jobs = vec![
         // 01:00 更新興櫃股票的每股淨值
         create_job("0 0 17 * * *", net_asset_value_per_share::emerging::execute),
+        // 02:00 備份資料庫
+        create_job("0 0 18 * * *", database::backup::execute),
+        // 02:15 預先建立 DailyQuotes 之後年度的分區並封存超過保留年限的分區
+        create_job("0 15 18 * * *", database::partition::execute),
         // 02:30 更新盈餘分配率
         create_job("0 30 18 * * *", dividend::payout_ratio::execute),
         // 03:00 更新台股季度財報
         create_job("0 0 19 * * *", event::taiwan_stock::quarter_eps::execute),
         // 04:00 更新台股季度財報(ROE、ROA為零的數據)
         create_job("0 0 20 * * *", financial_statement::quarter::execute),
+        // 04:30 更新台股季度的現金流量
+        create_job("0 30 20 * * *", financial_statement::cash_flow::execute),
         // 05:00 更新台股年度財報(僅有eps 等少數欄位的資料)
         create_job("0 0 21 * * *", event::taiwan_stock::annual_eps::execute),
         // 05:00 更新台股年度財報
@@ -59,75 +102,673 @@ async fn run_cron(sched: &JobScheduler) -> Result<()> {
             "0 0 21 * * *",
             net_asset_value_per_share::zero_value::execute,
         ),
-        // 05:00 取得台股的營收
-        create_job("0 0 21 * * *", revenue::execute),
-        // 05:00 更新台股國際證券識別碼
-        create_job("0 0 21 * * *", isin::execute),
-        // 05:00 更新下市的股票
-        create_job("0 0 21 * * *", delisted_company::execute),
         // 08:00 提醒本日除權息的股票
         create_job("0 0 0 * * *", event::taiwan_stock::ex_dividend::execute),
         // 08:00 提醒本日發放股利的股票(只通知自已有的股票)
         create_job("0 0 0 * * *", event::taiwan_stock::payable_date::execute),
         // 08:00 提醒本日開始公開申購的股票
-        create_job("0 0 0 * * *", || async {
-            event::taiwan_stock::public::execute().await
-            //Ok(())
-        }),
-        // 09:00 更新股票權值佔比
-        create_job("0 0 1 * * *", stock_weight::execute),
+        create_job("0 0 0 * * *", event::taiwan_stock::public::execute),
+        // 08:00 提醒三天後召開股東會或法說會的庫存與追踪中股票
+        create_job("0 0 0 * * *", event::taiwan_stock::corporate_event::execute),
+        // 08:30 將前一日的日誌搬移至儲存後端
+        create_job("0 30 0 * * *", storage::ship_logs),
         // 09:00 提醒本日已達高低標的股票有那些
         create_job("0 0 1 * * *", event::trace::stock_price::execute),
+        // 週一至週五 13:20~13:31 每分鐘記錄庫存股票的最佳五檔
+        create_job(
+            "0 20-31 5 * * Mon-Fri",
+            event::taiwan_stock::order_book::execute,
+        ),
         // 15:00 取得收盤報價數據
         create_job("0 0 7 * * *", event::taiwan_stock::closing::execute),
+        // 16:30 以雅虎的報價比對抽樣與庫存股票的收盤價
+        create_job("0 30 8 * * *", quality::reconciliation::execute),
+        // 17:00 財報申報期限過後的兩週內，檢查庫存股票是否仍缺少財報
+        create_job("0 0 9 * * *", quality::filing::execute),
+        // 18:00 更新庫藏股買回計畫，提醒庫存股票公告買回或執行完畢未達標
+        create_job("0 0 10 * * *", event::taiwan_stock::buyback::execute),
+        // 每週六 10:00 以證交所除權除息結果比對庫存股票近 10 年的股利
+        create_job("0 0 2 * * Sat", dividend::completeness::execute),
+        // 每週日 20:00 發送庫存週報
+        create_job(
+            "0 0 12 * * Sun",
+            event::taiwan_stock::portfolio_summary::weekly,
+        ),
+        // 每月一日 20:00 發送庫存上個月的月報
+        create_job(
+            "0 0 12 1 * *",
+            event::taiwan_stock::portfolio_summary::monthly,
+        ),
+        // 每月一日 20:15 依目標配置發送各成員的再平衡建議
+        create_job("0 15 12 1 * *", event::taiwan_stock::rebalance::execute),
+        // 每月一日 20:30 發送上個月估價模型的命中率
+        create_job(
+            "0 30 12 1 * *",
+            event::taiwan_stock::estimate_performance::execute,
+        ),
+        // 每年一月十五日 20:00 匯出上一年度各成員的股利所得報表
+        create_job("0 0 12 15 1 *", event::taiwan_stock::dividend_tax::execute),
         // 21:00 資料庫內尚未有年度配息數據的股票取出後向第三方查詢後更新回資料庫
         create_job("0 0 13 * * *", dividend::execute),
-        // 22:00 外資持股狀態
+        // 21:30 匯出庫存與追踪中股票的除權息、股利發放與財報公布期限行事曆
+        create_job("0 30 13 * * *", calendar::execute),
+        // 08:00~22:30 每 30 分鐘抓取重大訊息
         create_job(
-            "0 0 14 * * *",
-            qualified_foreign_institutional_investor::execute,
+            "0 0,30 0-14 * * *",
+            event::taiwan_stock::announcement::execute,
         ),
-        // 每分鐘更新一次ddns的ip
-        create_job("0 * * * * *", ddns::refresh),
+        // 每 10 分鐘送出已離開勿擾時段的聊天室延後的通知
+        create_job("0 */10 * * * *", bot::telegram::flush_deferred),
+        // 每 10 分鐘送出已到達摘要間隔的警示
+        create_job("0 */10 * * * *", bot::alert::flush_digests),
+        // 每小時 15 分重新寫入 failed_writes 內寫入失敗的數據
+        create_job("0 15 * * * *", database::retry_queue::execute),
+        // 每 30 秒檢查公網 IP，變動時立即更新 ddns 並發送通知
+        create_job("*/30 * * * * *", ip_monitor::execute),
     ];
+    // 實作 Crawler 並註冊於 backfill::registry 的數據來源
+    jobs.extend(registry::crawlers().iter().cloned().map(crawler_job));
 
-    for job in jobs.into_iter().flatten() {
+    for job in &jobs {
         sched
-            .add(job)
+            .add(job.to_job()?)
             .await
             .context("Failed to add job to scheduler")?;
     }
 
-    sched.start().await.context("Failed to start scheduler")
+    sched.start().await.context("Failed to start scheduler")?;
+    let _ = JOBS.set(jobs.clone());
+    tokio::spawn(catch_up(jobs));
+    tokio::spawn(database::health::monitor());
+
+    Ok(())
 }
 
 pub trait Scheduler {
     fn is_weekend(&self) -> bool;
 }
 
-fn create_job<F, Fut>(cron_expr: &'static str, task: F) -> Result<Job>
+/// 收到關閉訊號後不再執行新觸發的任務
+static ACCEPTING_JOBS: AtomicBool = AtomicBool::new(true);
+/// 執行中的任務數量
+static RUNNING_JOBS: AtomicUsize = AtomicUsize::new(0);
+/// 已加入排程的任務，供管理指令查詢與手動執行
+static JOBS: OnceCell<Vec<TrackedJob>> = OnceCell::new();
+/// 停用中的任務名稱，排程觸發與啟動補跑時略過
+static DISABLED: Lazy<RwLock<HashSet<String>>> = Lazy::new(Default::default);
+/// 整個排程是否暫停 ex. 資料庫維護期間
+static PAUSED: AtomicBool = AtomicBool::new(false);
+/// job_controls 內代表整個排程的名稱
+const ALL_JOBS: &str = "*";
+/// 因來源當天的請求數已達上限而延到隔天執行的任務
+static DEFERRED: Lazy<Mutex<HashSet<String>>> = Lazy::new(Default::default);
+
+/// 任務的排程與執行狀態
+#[derive(Debug)]
+pub struct JobStatus {
+    pub name: String,
+    pub cron_expr: &'static str,
+    pub disabled: bool,
+    /// 最後一次成功執行的時間
+    pub last_success: Option<DateTime<Local>>,
+}
+
+/// 取得所有任務的狀態
+pub async fn status() -> Result<Vec<JobStatus>> {
+    let last_runs = JobRun::fetch_all().await?;
+
+    Ok(JOBS
+        .get()
+        .into_iter()
+        .flatten()
+        .map(|job| JobStatus {
+            name: job.name.clone(),
+            cron_expr: job.cron_expr,
+            disabled: is_disabled(&job.name),
+            last_success: last_runs.get(&job.name).copied(),
+        })
+        .collect())
+}
+
+/// 目前執行中的任務數量
+pub fn running_jobs() -> usize {
+    RUNNING_JOBS.load(Ordering::SeqCst)
+}
+
+/// 立即在背景執行名稱符合 keyword 的任務，暫停中的任務也會執行，回傳執行的任務名稱
+pub fn run_now(keyword: &str) -> Result<Vec<String>> {
+    let jobs = find_jobs(keyword)?;
+    for job in &jobs {
+        tokio::spawn((job.run)());
+    }
+
+    Ok(jobs.into_iter().map(|job| job.name.clone()).collect())
+}
+
+/// 暫停整個排程，執行中的任務不受影響，記錄於 job_controls 重啟後仍維持暫停
+pub async fn pause() -> Result<()> {
+    JobControl::save(ALL_JOBS, true).await?;
+    PAUSED.store(true, Ordering::SeqCst);
+
+    Ok(())
+}
+
+/// 恢復整個排程，暫停期間錯過的任務不會補跑
+pub async fn resume() -> Result<()> {
+    JobControl::save(ALL_JOBS, false).await?;
+    PAUSED.store(false, Ordering::SeqCst);
+
+    Ok(())
+}
+
+/// 整個排程是否暫停
+pub fn is_paused() -> bool {
+    PAUSED.load(Ordering::SeqCst)
+}
+
+/// 停用名稱符合 keyword 的任務，記錄於 job_controls 重啟後仍維持停用，回傳停用的任務名稱
+pub async fn disable(keyword: &str) -> Result<Vec<String>> {
+    set_disabled(keyword, true).await
+}
+
+/// 啟用名稱符合 keyword 的任務，回傳啟用的任務名稱
+pub async fn enable(keyword: &str) -> Result<Vec<String>> {
+    set_disabled(keyword, false).await
+}
+
+async fn set_disabled(keyword: &str, disabled: bool) -> Result<Vec<String>> {
+    let names: Vec<String> = find_jobs(keyword)?
+        .into_iter()
+        .map(|job| job.name.clone())
+        .collect();
+
+    for name in &names {
+        JobControl::save(name, disabled).await?;
+        if let Ok(mut set) = DISABLED.write() {
+            if disabled {
+                set.insert(name.clone());
+            } else {
+                set.remove(name);
+            }
+        }
+    }
+
+    Ok(names)
+}
+
+fn is_disabled(name: &str) -> bool {
+    DISABLED
+        .read()
+        .map(|disabled| disabled.contains(name))
+        .unwrap_or(false)
+}
+
+/// 排程觸發或啟動補跑時是否略過任務，啟用 leader 選舉時 follower 略過全部的任務
+fn is_skipped(name: &str) -> bool {
+    is_paused() || is_disabled(name) || !leader::is_leader()
+}
+
+/// 由 follower 成為 leader 時補跑前一個 leader 停止後錯過的任務
+fn on_elected() {
+    if let Some(jobs) = JOBS.get() {
+        tokio::spawn(catch_up(jobs.clone()));
+    }
+}
+
+/// 讀取 job_controls 內暫停與停用的狀態，讀取失敗時所有任務照常執行
+async fn load_controls() {
+    match JobControl::fetch_disabled().await {
+        Ok(mut names) => {
+            PAUSED.store(names.remove(ALL_JOBS), Ordering::SeqCst);
+            if let Ok(mut disabled) = DISABLED.write() {
+                *disabled = names;
+            }
+        }
+        Err(why) => {
+            logging::error_file_async(format!("Failed to load job controls because {:?}", why))
+        }
+    }
+}
+
+fn find_jobs(keyword: &str) -> Result<Vec<&'static TrackedJob>> {
+    let jobs: Vec<&TrackedJob> = JOBS
+        .get()
+        .into_iter()
+        .flatten()
+        .filter(|job| matches_job(&job.name, keyword))
+        .collect();
+
+    if jobs.is_empty() {
+        return Err(anyhow!("No job matches {}", keyword));
+    }
+
+    Ok(jobs)
+}
+
+/// keyword 為完整的任務名稱或名稱中的一段 ex. revenue 符合 backfill::revenue::execute
+fn matches_job(name: &str, keyword: &str) -> bool {
+    name == keyword || name.split("::").any(|segment| segment == keyword)
+}
+
+/// 執行中的任務計數，離開作用域時自動減一
+struct RunningJob;
+
+impl RunningJob {
+    fn start() -> Self {
+        RUNNING_JOBS.fetch_add(1, Ordering::SeqCst);
+        RunningJob
+    }
+}
+
+impl Drop for RunningJob {
+    fn drop(&mut self) {
+        RUNNING_JOBS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 停止排程並等待執行中的任務結束後釋放 leader 鎖，超過 timeout 仍未結束時回傳尚在執行的任務數量
+pub async fn stop(sched: &JobScheduler, timeout: Duration) -> usize {
+    ACCEPTING_JOBS.store(false, Ordering::SeqCst);
+    if let Err(why) = sched.clone().shutdown().await {
+        logging::error_file_async(format!("Failed to shutdown scheduler because {:?}", why));
+    }
+
+    let wait = async {
+        while RUNNING_JOBS.load(Ordering::SeqCst) > 0 {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    };
+
+    let timed_out = tokio::time::timeout(timeout, wait).await.is_err();
+    leader::resign().await;
+    if timed_out {
+        let running = RUNNING_JOBS.load(Ordering::SeqCst);
+        logging::warn_file_async(format!(
+            "Scheduler stopped with {} job(s) still running after {:?}",
+            running, timeout
+        ));
+        return running;
+    }
+
+    0
+}
+
+/// RETRYABLE_JOBS 內的任務因暫時性的錯誤失敗時最多執行的次數
+const JOB_MAX_ATTEMPTS: u32 = 3;
+/// 重試前等待的時間，每次重試再乘上已執行的次數
+const JOB_RETRY_DELAY: Duration = Duration::from_secs(60);
+/// 資料庫斷線時任務延後執行的最長時間，超過後放棄本次執行
+const DATABASE_WAIT_LIMIT: Duration = Duration::from_secs(60 * 30);
+/// 延到隔天的任務在隔天 00:00 之後多久執行
+const DEFERRED_DELAY: Duration = Duration::from_secs(60 * 5);
+
+/// 啟動時預設不補跑的任務，執行頻率高或只在特定時段有意義
+const NO_CATCH_UP: [&str; 4] = [
+    "event::ip_monitor::execute",
+    "event::taiwan_stock::announcement::execute",
+    "event::taiwan_stock::order_book::execute",
+    "event::trace::stock_price::execute",
+];
+
+/// 失敗時可以整個重新執行的任務，只寫入可重複 upsert 的數據且不發送通知，
+/// 其餘任務重跑會重複發送 Telegram 訊息或重複寫入部分數據，暫時性的錯誤交由 HTTP、資料庫呼叫端重試
+const RETRYABLE_JOBS: [&str; 13] = [
+    "backfill::corporate_event::execute",
+    "backfill::dividend::execute",
+    "backfill::dividend::payout_ratio::execute",
+    "backfill::financial_statement::annual::execute",
+    "backfill::financial_statement::cash_flow::execute",
+    "backfill::financial_statement::quarter::execute",
+    "backfill::market_cap::execute",
+    "backfill::net_asset_value_per_share::emerging::execute",
+    "backfill::net_asset_value_per_share::zero_value::execute",
+    "backfill::odd_lot_quote::execute",
+    "backfill::stock_weight::execute",
+    "backfill::suspend_listing::execute",
+    "event::taiwan_stock::annual_eps::execute",
+];
+
+type JobRunner = Arc<dyn Fn() -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// 排程任務，除了交給排程器的 cron 外，也保留執行方式供啟動時補跑
+#[derive(Clone)]
+struct TrackedJob {
+    /// 任務函式的路徑 ex. backfill::revenue::execute
+    name: String,
+    cron_expr: &'static str,
+    run: JobRunner,
+}
+
+impl TrackedJob {
+    fn to_job(&self) -> Result<Job> {
+        let run = self.run.clone();
+        let name = self.name.clone();
+        Ok(Job::new_async(self.cron_expr, move |_uuid, _l| {
+            if is_skipped(&name) {
+                logging::info_file_async(format!(
+                    "Skip task({}) because it is paused, disabled or not the leader",
+                    name
+                ));
+                return Box::pin(async {});
+            }
+
+            run()
+        })?)
+    }
+}
+
+/// 依任務函式的型別取得名稱，去掉 crate 名稱的前綴
+fn job_name<F>() -> String {
+    let name = std::any::type_name::<F>();
+    name.split_once("::")
+        .map(|(_, path)| path)
+        .unwrap_or(name)
+        .to_string()
+}
+
+fn create_job<F, Fut>(cron_expr: &'static str, task: F) -> TrackedJob
 where
     F: Fn() -> Fut + Clone + Send + Sync + 'static,
-    Fut: Future<Output = Result<(), Error>> + Send,
+    Fut: Future<Output = Result<(), Error>> + Send + 'static,
 {
-    Ok(Job::new_async(cron_expr, move |_uuid, _l| {
+    named_job(job_name::<F>(), cron_expr, task)
+}
+
+fn crawler_job(crawler: Arc<dyn Crawler>) -> TrackedJob {
+    named_job(
+        registry::job_name(crawler.as_ref()),
+        crawler.schedule(),
+        move || {
+            let crawler = crawler.clone();
+            async move { crawler.execute().await }
+        },
+    )
+}
+
+fn named_job<F, Fut>(name: String, cron_expr: &'static str, task: F) -> TrackedJob
+where
+    F: Fn() -> Fut + Clone + Send + Sync + 'static,
+    Fut: Future<Output = Result<(), Error>> + Send + 'static,
+{
+    let job_name = name.clone();
+    let run: JobRunner = Arc::new(move || {
         let task = task.clone();
-        Box::pin(async move {
-            if let Err(why) = task().await {
+        let name = job_name.clone();
+        Box::pin(run_id::scope(run_id::generate(), async move {
+            telemetry::trace(name.clone(), run_job(task, name)).await
+        }))
+    });
+
+    TrackedJob {
+        name,
+        cron_expr,
+        run,
+    }
+}
+
+/// 等待資料庫可用後執行任務，RETRYABLE_JOBS 內的任務遇到可重試的錯誤時最多執行 JOB_MAX_ATTEMPTS 次
+async fn run_job<F, Fut>(task: F, name: String)
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<(), Error>>,
+{
+    if !database::health::wait_until_available(DATABASE_WAIT_LIMIT).await {
+        logging::error_file_async(format!(
+            "Skip task({}) because database is unavailable",
+            name
+        ));
+        return;
+    }
+
+    if !ACCEPTING_JOBS.load(Ordering::SeqCst) {
+        logging::info_file_async(format!("Skip task({}) because of shutdown", name));
+        return;
+    }
+
+    let _running = RunningJob::start();
+    let max_attempts = max_attempts(&name);
+    for attempt in 1..=max_attempts {
+        match telemetry::span(format!("{} attempt {}", name, attempt), task()).await {
+            Ok(_) => {
+                record_success(&name).await;
+                return;
+            }
+            Err(why) if error::is_not_trading_day(&why) => {
+                logging::info_file_async(format!("Skip task({}) because {}", name, why));
+                record_success(&name).await;
+                return;
+            }
+            Err(why) if attempt < max_attempts && error::is_retryable(&why) => {
+                logging::warn_file_async(format!(
+                    "Task({}) failed on attempt {} and will be retried because {:?}",
+                    name, attempt, why
+                ));
+                tokio::time::sleep(JOB_RETRY_DELAY * attempt).await;
+            }
+            Err(why) => {
+                if let Some(resume_on) = error::budget_resume_on(&why) {
+                    defer(name, resume_on, &why);
+                    return;
+                }
+
                 logging::error_file_async(format!(
                     "Failed to execute task({}) because {:?}",
-                    cron_expr, why
+                    name, why
                 ));
+                return;
             }
-        })
-    })?)
+        }
+    }
+}
+
+/// 只有 RETRYABLE_JOBS 內的任務失敗時會整個重新執行
+fn max_attempts(name: &str) -> u32 {
+    if RETRYABLE_JOBS.contains(&name) {
+        JOB_MAX_ATTEMPTS
+    } else {
+        1
+    }
+}
+
+/// 來源當天的請求數已達上限時，剩餘的工作延到 resume_on 再執行一次，同一任務在執行前只會延後一次
+fn defer(name: String, resume_on: NaiveDate, why: &Error) {
+    logging::warn_file_async(format!(
+        "Defer task({}) to {} because {}",
+        name, resume_on, why
+    ));
+
+    let first = DEFERRED
+        .lock()
+        .map(|mut deferred| deferred.insert(name.clone()))
+        .unwrap_or(false);
+    if !first {
+        return;
+    }
+
+    let delay = delay_until(resume_on, Local::now());
+    tokio::spawn(async move {
+        tokio::time::sleep(delay).await;
+        if let Ok(mut deferred) = DEFERRED.lock() {
+            deferred.remove(&name);
+        }
+
+        if is_skipped(&name) {
+            return;
+        }
+
+        if let Err(why) = run_now(&name) {
+            logging::error_file_async(format!("{:?}", why));
+        }
+    });
+}
+
+/// 距離 resume_on 00:00 加上 DEFERRED_DELAY 的時間
+fn delay_until(resume_on: NaiveDate, now: DateTime<Local>) -> Duration {
+    let Some(midnight) = resume_on
+        .and_hms_opt(0, 0, 0)
+        .and_then(|midnight| Local.from_local_datetime(&midnight).earliest())
+    else {
+        return DEFERRED_DELAY;
+    };
+
+    (midnight - now).to_std().unwrap_or_default() + DEFERRED_DELAY
+}
+
+async fn record_success(name: &str) {
+    if let Err(why) = JobRun::record_success(name).await {
+        logging::error_file_async(format!("{:?}", why));
+    }
+}
+
+/// 比對各任務最後一次成功執行的時間與排程，將停機期間錯過的任務依序補跑一次
+async fn catch_up(jobs: Vec<TrackedJob>) {
+    let catch_up = &SETTINGS.load().catch_up;
+    if !catch_up.enabled {
+        return;
+    }
+
+    let last_runs = match JobRun::fetch_all().await {
+        Ok(last_runs) => last_runs,
+        Err(why) => {
+            logging::error_file_async(format!("Failed to catch up jobs because {:?}", why));
+            return;
+        }
+    };
+    let now = Utc::now();
+
+    for job in jobs {
+        if NO_CATCH_UP.contains(&job.name.as_str())
+            || catch_up.excluded.contains(&job.name)
+            || is_skipped(&job.name)
+        {
+            continue;
+        }
+
+        // 沒有執行紀錄的任務無從判斷是否錯過，等排程觸發後再開始記錄
+        let Some(last_success) = last_runs.get(&job.name) else {
+            continue;
+        };
+
+        match is_missed(job.cron_expr, last_success.with_timezone(&Utc), now) {
+            Ok(true) => {
+                logging::info_file_async(format!(
+                    "Catch up task({}) last succeeded at {}",
+                    job.name, last_success
+                ));
+                (job.run)().await;
+            }
+            Ok(false) => {}
+            Err(why) => logging::error_file_async(format!(
+                "Failed to check task({}) schedule because {:?}",
+                job.name, why
+            )),
+        }
+    }
+}
+
+/// 最後一次成功執行之後的下一個排程時間已經過了，代表錯過了至少一次
+fn is_missed(cron_expr: &str, last_success: DateTime<Utc>, now: DateTime<Utc>) -> Result<bool> {
+    let cron = Cron::new(cron_expr)
+        .with_seconds_optional()
+        .parse()
+        .context(format!("Failed to parse cron expression {}", cron_expr))?;
+    let next = cron
+        .find_next_occurrence(&last_success, false)
+        .context(format!("Failed to find next occurrence of {}", cron_expr))?;
+
+    Ok(next <= now)
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::backfill::{dividend::payout_ratio, financial_statement::cash_flow, revenue};
+
     // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
     use super::*;
 
+    fn name_of<F>(_: F) -> String {
+        job_name::<F>()
+    }
+
+    #[test]
+    fn test_job_name() {
+        assert_eq!(name_of(revenue::execute), "backfill::revenue::execute");
+        assert_eq!(name_of(ip_monitor::execute), NO_CATCH_UP[0]);
+    }
+
+    #[test]
+    fn test_max_attempts() {
+        assert_eq!(
+            max_attempts(&name_of(payout_ratio::execute)),
+            JOB_MAX_ATTEMPTS
+        );
+        assert_eq!(max_attempts(&name_of(cash_flow::execute)), JOB_MAX_ATTEMPTS);
+        assert_eq!(max_attempts(&name_of(revenue::execute)), 1);
+        assert_eq!(
+            max_attempts(&name_of(event::taiwan_stock::ex_dividend::execute)),
+            1
+        );
+    }
+
+    #[test]
+    fn test_matches_job() {
+        assert!(matches_job("backfill::revenue::execute", "revenue"));
+        assert!(matches_job(
+            "backfill::revenue::execute",
+            "backfill::revenue::execute"
+        ));
+        assert!(matches_job(
+            "backfill::dividend::payout_ratio::execute",
+            "dividend"
+        ));
+        assert!(!matches_job(
+            "event::taiwan_stock::ex_dividend::execute",
+            "dividend"
+        ));
+        assert!(!matches_job("backfill::revenue::execute", "rev"));
+    }
+
+    #[test]
+    fn test_is_missed() {
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        let last_success = at("2024-01-01T21:00:05Z");
+
+        assert!(!is_missed("0 0 21 * * *", last_success, at("2024-01-02T20:59:59Z")).unwrap());
+        assert!(is_missed("0 0 21 * * *", last_success, at("2024-01-02T21:00:00Z")).unwrap());
+        assert!(is_missed("0 0 21 * * *", last_success, at("2024-01-05T03:00:00Z")).unwrap());
+        assert!(!is_missed("0 0 12 * * Sun", last_success, at("2024-01-07T11:59:59Z")).unwrap());
+        assert!(is_missed("0 0 12 * * Sun", last_success, at("2024-01-07T12:00:00Z")).unwrap());
+        assert!(is_missed("bad cron", last_success, at("2024-01-05T03:00:00Z")).is_err());
+    }
+
+    #[test]
+    fn test_delay_until() {
+        let now = Local.with_ymd_and_hms(2024, 6, 12, 23, 0, 0).unwrap();
+
+        assert_eq!(
+            delay_until(NaiveDate::from_ymd_opt(2024, 6, 13).unwrap(), now),
+            Duration::from_secs(60 * 60) + DEFERRED_DELAY
+        );
+        assert_eq!(
+            delay_until(NaiveDate::from_ymd_opt(2024, 6, 12).unwrap(), now),
+            DEFERRED_DELAY
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stop_waits_for_running_job() {
+        let sched = JobScheduler::new().await.unwrap();
+        let job = RunningJob::start();
+        let release = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            drop(job);
+        });
+
+        assert_eq!(stop(&sched, Duration::from_secs(5)).await, 0);
+        assert!(!ACCEPTING_JOBS.load(Ordering::SeqCst));
+        release.await.unwrap();
+    }
+
     async fn run() -> Result<()> {
         let sched = JobScheduler::new().await?;
         let every_minute = Job::new_async("* * * * * *", |_uuid, _l| {
@@ -0,0 +1,179 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+
+use crate::{bot, config::SETTINGS, logging, nosql::lock::DistributedLock};
+
+/// Redis 內 leader 鎖的 key
+const LEADER_KEY: &str = "scheduler:leader";
+
+/// 本執行個體在 leader 選舉使用的鎖
+static LOCK: Lazy<DistributedLock> = Lazy::new(|| DistributedLock::new(LEADER_KEY, ttl()));
+/// 本執行個體目前是否為 leader
+static IS_LEADER: AtomicBool = AtomicBool::new(false);
+/// 最後一次成功取得或續約的時間，Redis 無法連線時用來判斷鎖是否可能已經過期
+static LAST_RENEWED: Lazy<Mutex<Option<Instant>>> = Lazy::new(|| Mutex::new(None));
+
+/// 一次選舉後身分的變化
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    /// 由 follower 成為 leader
+    Elected,
+    /// 由 leader 退為 follower
+    Demoted,
+    Unchanged,
+}
+
+fn ttl() -> Duration {
+    Duration::from_secs(SETTINGS.load().leader_election.ttl_seconds.max(3))
+}
+
+/// 是否啟用 leader 選舉
+pub fn is_enabled() -> bool {
+    SETTINGS.load().leader_election.enabled
+}
+
+/// 本執行個體是否應該執行排程任務，未啟用 leader 選舉時永遠為 true
+pub fn is_leader() -> bool {
+    !is_enabled() || IS_LEADER.load(Ordering::SeqCst)
+}
+
+/// 本執行個體的識別，管理指令用來顯示目前的 leader
+pub fn token() -> &'static str {
+    LOCK.token()
+}
+
+/// 目前持有 leader 鎖的執行個體
+pub async fn holder() -> Result<Option<String>> {
+    LOCK.holder().await
+}
+
+/// leader 續約、follower 嘗試取得鎖，回傳身分的變化
+pub async fn campaign() -> Transition {
+    let was_leader = IS_LEADER.load(Ordering::SeqCst);
+    let result = if was_leader {
+        LOCK.renew().await
+    } else {
+        LOCK.acquire().await
+    };
+    if let Err(why) = &result {
+        logging::error_file_async(format!("{:?}", why));
+    }
+
+    let since_renewed = LAST_RENEWED
+        .lock()
+        .ok()
+        .and_then(|last| *last)
+        .map(|last| last.elapsed())
+        .unwrap_or(Duration::MAX);
+    let is_leader = next_state(
+        was_leader,
+        result.as_ref().ok().copied(),
+        since_renewed,
+        ttl(),
+    );
+    if is_leader && result.as_ref().is_ok_and(|acquired| *acquired) {
+        if let Ok(mut last) = LAST_RENEWED.lock() {
+            *last = Some(Instant::now());
+        }
+    }
+    IS_LEADER.store(is_leader, Ordering::SeqCst);
+
+    transition(was_leader, is_leader)
+}
+
+/// 依取得或續約的結果決定是否為 leader，Redis 無法連線(None)時 leader 在鎖過期前維持身分，
+/// 過期後其他執行個體可能已經取得鎖，因此退為 follower 避免同時有兩個 leader
+fn next_state(
+    was_leader: bool,
+    acquired: Option<bool>,
+    since_renewed: Duration,
+    ttl: Duration,
+) -> bool {
+    match acquired {
+        Some(acquired) => acquired,
+        None => was_leader && since_renewed < ttl,
+    }
+}
+
+fn transition(was_leader: bool, is_leader: bool) -> Transition {
+    match (was_leader, is_leader) {
+        (false, true) => Transition::Elected,
+        (true, false) => Transition::Demoted,
+        _ => Transition::Unchanged,
+    }
+}
+
+/// 每隔三分之一的存活時間參與一次選舉，leader 停止續約(行程結束或當機)後由其他執行個體接手，
+/// 成為 leader 時呼叫 on_elected 補跑接手前錯過的任務
+pub async fn keep_alive(on_elected: fn()) {
+    let interval = ttl() / 3;
+    loop {
+        tokio::time::sleep(interval).await;
+        match campaign().await {
+            Transition::Elected => {
+                notify(&format!("StockCrawler {} 成為排程的 leader", token())).await;
+                on_elected();
+            }
+            Transition::Demoted => {
+                notify(&format!(
+                    "StockCrawler {} 無法續約，不再執行排程任務",
+                    token()
+                ))
+                .await;
+            }
+            Transition::Unchanged => {}
+        }
+    }
+}
+
+/// 關閉前釋放 leader 鎖，讓其他執行個體不必等到鎖過期即可接手
+pub async fn resign() {
+    if !is_enabled() || !IS_LEADER.swap(false, Ordering::SeqCst) {
+        return;
+    }
+
+    if let Err(why) = LOCK.release().await {
+        logging::error_file_async(format!("{:?}", why));
+    }
+}
+
+async fn notify(msg: &str) {
+    logging::info_file_async(msg.to_string());
+    bot::telegram::send(msg).await;
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_next_state() {
+        let ttl = Duration::from_secs(30);
+        let recent = Duration::from_secs(10);
+        let expired = Duration::from_secs(31);
+
+        assert!(next_state(false, Some(true), Duration::MAX, ttl));
+        assert!(!next_state(true, Some(false), recent, ttl));
+        // Redis 無法連線時，leader 在鎖過期前維持身分，follower 不會成為 leader
+        assert!(next_state(true, None, recent, ttl));
+        assert!(!next_state(true, None, expired, ttl));
+        assert!(!next_state(false, None, recent, ttl));
+    }
+
+    #[test]
+    fn test_transition() {
+        assert_eq!(transition(false, true), Transition::Elected);
+        assert_eq!(transition(true, false), Transition::Demoted);
+        assert_eq!(transition(true, true), Transition::Unchanged);
+        assert_eq!(transition(false, false), Transition::Unchanged);
+    }
+}
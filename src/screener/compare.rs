@@ -0,0 +1,175 @@
+use std::{fmt::Write, path::Path};
+
+use anyhow::{anyhow, Context, Result};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::{
+    bot::telegram::fmt,
+    database::table::stock::extension::metrics::{self, StockMetrics},
+};
+
+/// 一次最多比較的股票數量，太多時 Telegram 的表格會換行而無法對齊
+pub const MAX_SYMBOLS: usize = 6;
+/// 比較的指標，依序為 /compare 表格的列與 CSV 的欄位
+pub const LABELS: [&str; 6] = [
+    "殖利率(%)",
+    "本益比",
+    "淨值比",
+    "營收年增(%)",
+    "近四季EPS",
+    "距季線(%)",
+];
+
+/// 命令列比較的用法
+const USAGE: &str = "usage: stock_crawler compare 2330 2303 2454 [--output compare.csv]";
+
+/// 依序取得各股票的最新衍生指標，回傳找到的指標與找不到的股票代號
+pub async fn fetch(symbols: &[String]) -> Result<(Vec<StockMetrics>, Vec<String>)> {
+    let mut found = Vec::with_capacity(symbols.len());
+    let mut missing = Vec::new();
+    for symbol in symbols {
+        let symbol = symbol.to_uppercase();
+        match metrics::fetch_one(&symbol).await? {
+            Some(m) => found.push(m),
+            None => missing.push(symbol),
+        }
+    }
+
+    Ok((found, missing))
+}
+
+/// 各指標的數值，沒有數據時為 -
+pub fn values(m: &StockMetrics) -> [String; 6] {
+    let optional = |value: Option<Decimal>| {
+        value
+            .map(|value| fmt::number(value, 2))
+            .unwrap_or_else(|| "-".to_string())
+    };
+
+    [
+        optional(m.dividend_yield),
+        optional(m.price_earning_ratio),
+        optional(m.price_to_book_ratio),
+        optional(m.revenue_yoy),
+        m.last_four_eps.normalize().to_string(),
+        optional(distance_from_ma60(m)),
+    ]
+}
+
+/// 收盤價高於(正)或低於(負)季線的幅度(%)，沒有季線時為 None
+pub fn distance_from_ma60(m: &StockMetrics) -> Option<Decimal> {
+    (m.moving_average_60 > Decimal::ZERO)
+        .then(|| (m.closing_price / m.moving_average_60 - Decimal::ONE) * dec!(100))
+}
+
+/// 每檔股票一列，開頭加上 BOM 讓 Excel 正確顯示中文，數值不加千分位以便試算表計算
+pub fn render_csv(list: &[StockMetrics]) -> String {
+    let mut csv = String::from("\u{feff}");
+    let _ = writeln!(csv, "股票代號,名稱,{}", LABELS.join(","));
+    for m in list {
+        let values: Vec<String> = values(m).iter().map(|v| v.replace(',', "")).collect();
+        let _ = writeln!(
+            csv,
+            "{},{},{}",
+            csv_field(&m.stock_symbol),
+            csv_field(&m.name),
+            values.join(",")
+        );
+    }
+
+    csv
+}
+
+/// 含逗號或引號的欄位需以引號包住
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// 依命令列參數比較多檔股票並輸出 CSV，指定 --output 時寫入檔案，否則輸出到標準輸出
+/// ex. `stock_crawler compare 2330 2303 2454 --output compare.csv`
+pub async fn command(args: &[String]) -> Result<()> {
+    let (symbols, output) = match args.iter().position(|arg| arg == "--output") {
+        Some(i) => (
+            &args[..i],
+            Some(args.get(i + 1).ok_or_else(|| anyhow!(USAGE))?),
+        ),
+        None => (args, None),
+    };
+    if symbols.is_empty() {
+        return Err(anyhow!(USAGE));
+    }
+
+    let (list, missing) = fetch(symbols).await?;
+    if !missing.is_empty() {
+        eprintln!("查無 {} 的數據", missing.join("、"));
+    }
+
+    let csv = render_csv(&list);
+    match output {
+        Some(path) => {
+            std::fs::write(Path::new(path), csv)
+                .with_context(|| format!("Failed to write {}", path))?;
+            println!("已輸出 {} 檔股票至 {}", list.len(), path);
+        }
+        None => print!("{}", csv),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn metrics() -> StockMetrics {
+        StockMetrics {
+            stock_symbol: "2330".parse().unwrap(),
+            name: "台積電".to_string(),
+            closing_price: dec!(1100),
+            moving_average_60: dec!(1000),
+            last_four_eps: dec!(45.25),
+            price_earning_ratio: Some(dec!(24.31)),
+            price_to_book_ratio: Some(dec!(7.12)),
+            dividend_yield: Some(dec!(1.64)),
+            revenue_yoy: Some(dec!(39.6)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_values() {
+        assert_eq!(
+            values(&metrics()),
+            ["1.64", "24.31", "7.12", "39.60", "45.25", "10.00"].map(String::from)
+        );
+        assert_eq!(
+            values(&StockMetrics::default()),
+            ["-", "-", "-", "-", "0", "-"].map(String::from)
+        );
+    }
+
+    #[test]
+    fn test_render_csv() {
+        let mut other = metrics();
+        other.stock_symbol = "2303".parse().unwrap();
+        other.name = "聯電".to_string();
+        other.closing_price = dec!(1234.5);
+        other.moving_average_60 = dec!(1000);
+
+        let csv = render_csv(&[metrics(), other]);
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(
+            lines[0],
+            "\u{feff}股票代號,名稱,殖利率(%),本益比,淨值比,營收年增(%),近四季EPS,距季線(%)"
+        );
+        assert_eq!(lines[1], "2330,台積電,1.64,24.31,7.12,39.60,45.25,10.00");
+        assert_eq!(lines[2], "2303,聯電,1.64,24.31,7.12,39.60,45.25,23.45");
+    }
+}
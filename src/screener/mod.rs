@@ -0,0 +1,44 @@
+use anyhow::{anyhow, Result};
+
+use crate::database::table::stock::extension::metrics::{self, StockMetrics};
+
+/// 多檔股票的指標並列比較
+pub mod compare;
+/// 選股條件的解析與判斷
+pub mod parser;
+
+/// 命令列選股的用法
+const USAGE: &str = r#"usage: stock_crawler screen "yield > 5 && pe < 12 && revenue_yoy > 0""#;
+
+/// 可使用的指標名稱，以逗號分隔
+pub fn field_names() -> String {
+    parser::Field::ALL
+        .iter()
+        .map(|field| field.name())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// 以最新的衍生指標篩選出符合條件的股票，依股票代號排序
+pub async fn screen(expr: &parser::Expr) -> Result<Vec<StockMetrics>> {
+    Ok(metrics::fetch_latest()
+        .await?
+        .into_iter()
+        .filter(|m| expr.evaluate(m))
+        .collect())
+}
+
+/// 依命令列參數選股後逐行輸出股票代號與名稱 ex. `stock_crawler screen "pe < 12"`
+pub async fn command(args: &[String]) -> Result<()> {
+    if args.is_empty() {
+        return Err(anyhow!(USAGE));
+    }
+
+    let expr = parser::parse(&args.join(" "))
+        .map_err(|why| anyhow!("{}\nfields: {}\n{}", why, field_names(), USAGE))?;
+    for stock in screen(&expr).await? {
+        println!("{}\t{}", stock.stock_symbol, stock.name);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,464 @@
+use std::{fmt, iter::Peekable, str::Chars};
+
+use anyhow::{anyhow, bail, Result};
+use rust_decimal::prelude::ToPrimitive;
+
+use crate::database::table::stock::extension::metrics::StockMetrics;
+
+/// 條件內可使用的指標
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    /// 收盤價
+    Close,
+    /// 漲跌幅(%)
+    Change,
+    /// 成交股數
+    Volume,
+    Ma20,
+    Ma60,
+    /// 近四季 EPS
+    Eps,
+    /// 股東權益報酬率(%)
+    Roe,
+    /// 市值
+    MarketCap,
+    /// 本益比
+    Pe,
+    /// 股價淨值比
+    Pb,
+    /// 殖利率(%)
+    Yield,
+    /// 殖利率在近 5 年殖利率分佈中的百分位(%)
+    YieldPercentile,
+    /// 營收年增率(%)
+    RevenueYoy,
+    /// 營收月增率(%)
+    RevenueMom,
+    /// 距 52 週最高價(%)
+    DistanceFromHigh,
+    /// 自歷史最高收盤價的回檔幅度(%)
+    Drawdown,
+    /// 近四季的自由現金流量(仟元)
+    Fcf,
+    /// 自由現金流量殖利率(%)
+    FcfYield,
+}
+
+impl Field {
+    pub const ALL: [Field; 18] = [
+        Field::Close,
+        Field::Change,
+        Field::Volume,
+        Field::Ma20,
+        Field::Ma60,
+        Field::Eps,
+        Field::Roe,
+        Field::MarketCap,
+        Field::Pe,
+        Field::Pb,
+        Field::Yield,
+        Field::YieldPercentile,
+        Field::RevenueYoy,
+        Field::RevenueMom,
+        Field::DistanceFromHigh,
+        Field::Drawdown,
+        Field::Fcf,
+        Field::FcfYield,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Field::Close => "close",
+            Field::Change => "change",
+            Field::Volume => "volume",
+            Field::Ma20 => "ma20",
+            Field::Ma60 => "ma60",
+            Field::Eps => "eps",
+            Field::Roe => "roe",
+            Field::MarketCap => "market_cap",
+            Field::Pe => "pe",
+            Field::Pb => "pb",
+            Field::Yield => "yield",
+            Field::YieldPercentile => "yield_percentile",
+            Field::RevenueYoy => "revenue_yoy",
+            Field::RevenueMom => "revenue_mom",
+            Field::DistanceFromHigh => "distance_from_high",
+            Field::Drawdown => "drawdown",
+            Field::Fcf => "fcf",
+            Field::FcfYield => "fcf_yield",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        Field::ALL.into_iter().find(|field| field.name() == name)
+    }
+
+    /// 取出股票該指標的值，沒有數據時為 None
+    pub fn value(&self, metrics: &StockMetrics) -> Option<f64> {
+        let value = match self {
+            Field::Close => Some(metrics.closing_price),
+            Field::Change => Some(metrics.change_range),
+            Field::Volume => Some(metrics.trading_volume),
+            Field::Ma20 => Some(metrics.moving_average_20),
+            Field::Ma60 => Some(metrics.moving_average_60),
+            Field::Eps => Some(metrics.last_four_eps),
+            Field::Roe => Some(metrics.return_on_equity),
+            Field::MarketCap => Some(metrics.market_cap),
+            Field::Pe => metrics.price_earning_ratio,
+            Field::Pb => metrics.price_to_book_ratio,
+            Field::Yield => metrics.dividend_yield,
+            Field::YieldPercentile => metrics.yield_percentile,
+            Field::RevenueYoy => metrics.revenue_yoy,
+            Field::RevenueMom => metrics.revenue_mom,
+            Field::DistanceFromHigh => metrics.distance_from_high,
+            Field::Drawdown => metrics.drawdown,
+            Field::Fcf => metrics.free_cash_flow,
+            Field::FcfYield => metrics.fcf_yield,
+        };
+
+        value.and_then(|v| v.to_f64())
+    }
+}
+
+/// 比較的兩側，可以是指標或數字 ex. `close > ma20`、`pe < 12`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operand {
+    Field(Field),
+    Number(f64),
+}
+
+impl Operand {
+    fn value(&self, metrics: &StockMetrics) -> Option<f64> {
+        match self {
+            Operand::Field(field) => field.value(metrics),
+            Operand::Number(number) => Some(*number),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+}
+
+impl CompareOp {
+    fn apply(&self, left: f64, right: f64) -> bool {
+        match self {
+            CompareOp::Gt => left > right,
+            CompareOp::Ge => left >= right,
+            CompareOp::Lt => left < right,
+            CompareOp::Le => left <= right,
+            CompareOp::Eq => (left - right).abs() < f64::EPSILON,
+            CompareOp::Ne => (left - right).abs() >= f64::EPSILON,
+        }
+    }
+}
+
+/// 解析後的選股條件
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Compare(Operand, CompareOp, Operand),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+impl Expr {
+    /// 股票是否符合條件，比較時任一側沒有數據視為不符合
+    pub fn evaluate(&self, metrics: &StockMetrics) -> bool {
+        match self {
+            Expr::Compare(left, op, right) => match (left.value(metrics), right.value(metrics)) {
+                (Some(left), Some(right)) => op.apply(left, right),
+                _ => false,
+            },
+            Expr::And(left, right) => left.evaluate(metrics) && right.evaluate(metrics),
+            Expr::Or(left, right) => left.evaluate(metrics) || right.evaluate(metrics),
+            Expr::Not(expr) => !expr.evaluate(metrics),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    Compare(CompareOp),
+    And,
+    Or,
+    Not,
+    Minus,
+    LeftParen,
+    RightParen,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(ident) => write!(f, "{}", ident),
+            Token::Number(number) => write!(f, "{}", number),
+            Token::Compare(op) => write!(f, "{:?}", op),
+            Token::And => write!(f, "&&"),
+            Token::Or => write!(f, "||"),
+            Token::Not => write!(f, "!"),
+            Token::Minus => write!(f, "-"),
+            Token::LeftParen => write!(f, "("),
+            Token::RightParen => write!(f, ")"),
+        }
+    }
+}
+
+/// 解析選股條件 ex. `yield > 5 && pe < 12 && revenue_yoy > 0`
+///
+/// 支援 `&&`(and)、`||`(or)、`!`(not)、括號與 `> >= < <= == !=` 的比較，數字後可加上 %
+pub fn parse(input: &str) -> Result<Expr> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        bail!("empty expression");
+    }
+
+    let mut parser = Parser { tokens, pos: 0 };
+    let expr = parser.or()?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some(token) => Err(anyhow!("unexpected '{}'", token)),
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LeftParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RightParen);
+            }
+            '-' => {
+                chars.next();
+                tokens.push(Token::Minus);
+            }
+            '&' | '|' => {
+                chars.next();
+                if chars.next() != Some(c) {
+                    bail!("expected '{0}{0}'", c);
+                }
+                tokens.push(if c == '&' { Token::And } else { Token::Or });
+            }
+            '>' | '<' | '=' | '!' => {
+                chars.next();
+                let eq = chars.next_if_eq(&'=').is_some();
+                tokens.push(match (c, eq) {
+                    ('>', false) => Token::Compare(CompareOp::Gt),
+                    ('>', true) => Token::Compare(CompareOp::Ge),
+                    ('<', false) => Token::Compare(CompareOp::Lt),
+                    ('<', true) => Token::Compare(CompareOp::Le),
+                    ('=', _) => Token::Compare(CompareOp::Eq),
+                    ('!', true) => Token::Compare(CompareOp::Ne),
+                    _ => Token::Not,
+                });
+            }
+            c if c.is_ascii_digit() || c == '.' => tokens.push(number(&mut chars)?),
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut ident = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                    ident.push(c);
+                }
+                tokens.push(match ident.to_lowercase().as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    _ => Token::Ident(ident),
+                });
+            }
+            c => bail!("unexpected character '{}'", c),
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn number(chars: &mut Peekable<Chars>) -> Result<Token> {
+    let mut text = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+        text.push(c);
+    }
+    // 百分比的 % 只是標示，數值與指標的單位相同
+    chars.next_if_eq(&'%');
+
+    text.parse::<f64>()
+        .map(Token::Number)
+        .map_err(|_| anyhow!("invalid number '{}'", text))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut expr = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut expr = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.next();
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        match self.peek() {
+            Some(Token::Not) => {
+                self.next();
+                Ok(Expr::Not(Box::new(self.unary()?)))
+            }
+            Some(Token::LeftParen) => {
+                self.next();
+                let expr = self.or()?;
+                match self.next() {
+                    Some(Token::RightParen) => Ok(expr),
+                    _ => Err(anyhow!("expected ')'")),
+                }
+            }
+            _ => self.comparison(),
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Expr> {
+        let left = self.operand()?;
+        let op = match self.next() {
+            Some(Token::Compare(op)) => op,
+            Some(token) => bail!("expected comparison operator but found '{}'", token),
+            None => bail!("expected comparison operator"),
+        };
+        let right = self.operand()?;
+
+        Ok(Expr::Compare(left, op, right))
+    }
+
+    fn operand(&mut self) -> Result<Operand> {
+        match self.next() {
+            Some(Token::Ident(name)) => Field::from_name(&name)
+                .map(Operand::Field)
+                .ok_or_else(|| anyhow!("unknown field '{}'", name)),
+            Some(Token::Number(number)) => Ok(Operand::Number(number)),
+            Some(Token::Minus) => match self.next() {
+                Some(Token::Number(number)) => Ok(Operand::Number(-number)),
+                _ => Err(anyhow!("expected number after '-'")),
+            },
+            Some(token) => Err(anyhow!("expected field or number but found '{}'", token)),
+            None => Err(anyhow!("unexpected end of expression")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn metrics() -> StockMetrics {
+        StockMetrics {
+            stock_symbol: "2884".parse().unwrap(),
+            name: "玉山金".to_string(),
+            closing_price: dec!(27.5),
+            moving_average_20: dec!(27),
+            price_earning_ratio: Some(dec!(15.2)),
+            dividend_yield: Some(dec!(5.3)),
+            yield_percentile: Some(dec!(85)),
+            revenue_yoy: Some(dec!(-3.1)),
+            fcf_yield: Some(dec!(4.2)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("pe < 12").unwrap(),
+            Expr::Compare(
+                Operand::Field(Field::Pe),
+                CompareOp::Lt,
+                Operand::Number(12.0)
+            )
+        );
+        assert_eq!(
+            parse("yield > 5 && pe < 12 || close >= ma20").unwrap(),
+            Expr::Or(
+                Box::new(Expr::And(
+                    Box::new(parse("yield > 5").unwrap()),
+                    Box::new(parse("pe < 12").unwrap())
+                )),
+                Box::new(parse("close >= ma20").unwrap())
+            )
+        );
+        assert_eq!(
+            parse("NOT (revenue_yoy <= -5%)").unwrap(),
+            Expr::Not(Box::new(Expr::Compare(
+                Operand::Field(Field::RevenueYoy),
+                CompareOp::Le,
+                Operand::Number(-5.0)
+            )))
+        );
+
+        assert!(parse("").is_err());
+        assert!(parse("foo > 1").is_err());
+        assert!(parse("pe 12").is_err());
+        assert!(parse("(pe < 12").is_err());
+        assert!(parse("pe < 12 &").is_err());
+        assert!(parse("pe < 12 pb").is_err());
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let m = metrics();
+        let matches = |input: &str| parse(input).unwrap().evaluate(&m);
+
+        assert!(matches("yield > 5 && close > ma20"));
+        assert!(!matches("yield > 5 && pe < 12"));
+        assert!(matches("pe < 12 or revenue_yoy < 0"));
+        assert!(matches("!(pe < 12)"));
+        assert!(matches("close == 27.5 && close != 27"));
+        assert!(matches("yield_percentile >= 80 && yield > 5"));
+        assert!(matches("fcf_yield > 4"));
+        // 沒有數據的指標不符合任何比較
+        assert!(!matches("pb < 100"));
+        assert!(!matches("pb >= 100"));
+    }
+}
@@ -0,0 +1,174 @@
+use std::{
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+
+use crate::{
+    bot,
+    config::{self, App},
+    crawler, database, i18n, nosql,
+};
+
+/// 單項檢查等待回應的最長時間
+const CHECK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 單項檢查的結果
+pub struct Check {
+    pub name: &'static str,
+    pub result: Result<()>,
+    pub elapsed: Duration,
+}
+
+/// 部署後檢查設定檔、資料庫、Redis、Telegram 與證交所是否正常，輸出每一項的結果，有任一項失敗時回傳錯誤
+/// ex. `stock_crawler self-test`
+pub async fn command() -> Result<()> {
+    let checks = execute().await;
+    println!("{}", render(&checks));
+
+    let failed = checks.iter().filter(|check| check.result.is_err()).count();
+    if failed > 0 {
+        return Err(anyhow!("{} of {} checks failed", failed, checks.len()));
+    }
+
+    Ok(())
+}
+
+/// 依序執行各項檢查，設定檔有問題時其他項目都無法執行，只回傳設定檔的結果
+pub async fn execute() -> Vec<Check> {
+    let config = run("config", async { check_config() }).await;
+    if config.result.is_err() {
+        return vec![config];
+    }
+
+    vec![
+        config,
+        run("templates", async { i18n::validate() }).await,
+        run("postgres", database::health::ping()).await,
+        run("redis", async {
+            nosql::redis::CLIENT.ping().await.map(|_| ())
+        })
+        .await,
+        run(
+            "telegram",
+            bot::telegram::try_send("StockCrawler 自我檢查的測試訊息"),
+        )
+        .await,
+        run("twse", crawler::twse::ping()).await,
+    ]
+}
+
+async fn run<F>(name: &'static str, check: F) -> Check
+where
+    F: Future<Output = Result<()>>,
+{
+    let start = Instant::now();
+    let result = match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(result) => result,
+        Err(_) => Err(anyhow!("Timed out after {:?}", CHECK_TIMEOUT)),
+    };
+
+    Check {
+        name,
+        result,
+        elapsed: start.elapsed(),
+    }
+}
+
+/// 載入設定檔並檢查連線必要的欄位，設定檔格式錯誤或缺少 env 時載入會 panic
+fn check_config() -> Result<()> {
+    let app = panic::catch_unwind(AssertUnwindSafe(|| (*config::SETTINGS.load()).clone()))
+        .map_err(|_| anyhow!("Failed to load app.json or env"))?;
+
+    let problems = config_problems(&app);
+    if !problems.is_empty() {
+        return Err(anyhow!(problems.join(", ")));
+    }
+
+    Ok(())
+}
+
+/// 連線資料庫、Redis 與發送通知必要但未設定的欄位
+fn config_problems(app: &App) -> Vec<&'static str> {
+    let required = [
+        (app.postgresql.host.is_empty(), "postgresql.host is empty"),
+        (app.postgresql.user.is_empty(), "postgresql.user is empty"),
+        (app.postgresql.db.is_empty(), "postgresql.db is empty"),
+        (app.nosql.redis.addr.is_empty(), "nosql.redis.addr is empty"),
+        (
+            app.bot.telegram.token.is_empty(),
+            "bot.telegram.token is empty",
+        ),
+        (
+            app.bot.telegram.allowed.is_empty() && !app.bot.sandbox.enabled,
+            "bot.telegram.allowed is empty",
+        ),
+    ];
+
+    required
+        .into_iter()
+        .filter(|(missing, _)| *missing)
+        .map(|(_, problem)| problem)
+        .collect()
+}
+
+/// 每一項一行，欄位以 tab 分隔 ex. PASS postgres 12ms
+fn render(checks: &[Check]) -> String {
+    checks
+        .iter()
+        .map(|check| {
+            let elapsed = check.elapsed.as_millis();
+            match &check.result {
+                Ok(_) => format!("PASS\t{}\t{}ms", check.name, elapsed),
+                Err(why) => format!("FAIL\t{}\t{}ms\t{:#}", check.name, elapsed, why),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_config_problems() {
+        let mut app = App::default();
+        assert_eq!(config_problems(&app).len(), 6);
+
+        app.postgresql.host = "localhost".to_string();
+        app.postgresql.user = "user".to_string();
+        app.postgresql.db = "db".to_string();
+        app.nosql.redis.addr = "localhost:6379".to_string();
+        app.bot.telegram.token = "token".to_string();
+        assert_eq!(config_problems(&app), vec!["bot.telegram.allowed is empty"]);
+
+        app.bot.sandbox.enabled = true;
+        assert!(config_problems(&app).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_run_and_render() {
+        let checks = vec![
+            run("config", async { Ok(()) }).await,
+            run("redis", async { Err(anyhow!("connection refused")) }).await,
+        ];
+        let lines: Vec<String> = render(&checks)
+            .lines()
+            .map(|line| {
+                let columns: Vec<&str> = line.split('\t').collect();
+                format!(
+                    "{} {} {}",
+                    columns[0],
+                    columns[1],
+                    columns.get(3).unwrap_or(&"")
+                )
+            })
+            .collect();
+
+        assert_eq!(lines, vec!["PASS config ", "FAIL redis connection refused"]);
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+};
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+use crate::{
+    bot::{alert, telegram::fmt},
+    cache::SHARE,
+    config::SETTINGS,
+    database::table::{
+        daily_quote::{self, extension::SignalBar},
+        daily_valuation::DailyValuation,
+        signal::Signal,
+        stock_ownership_details,
+    },
+    declare::StockSymbol,
+    logging,
+};
+
+/// 策略規則的定義與判斷
+pub mod rule;
+
+/// 未設定 signals.rules_path 時讀取的規則檔
+const DEFAULT_RULES_PATH: &str = "signals.toml";
+
+/// 讀取設定檔 signals.rules_path 指定的規則，檔案不存在時視為沒有規則
+pub fn load_rules() -> Result<Vec<rule::Rule>> {
+    let settings = SETTINGS.load();
+    let path = match settings.signals.rules_path.as_str() {
+        "" => DEFAULT_RULES_PATH,
+        path => path,
+    };
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(why) if why.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(why) => return Err(why).context(format!("Failed to read signal rules {}", path)),
+    };
+
+    rule::parse(&text).context(format!("Failed to parse signal rules {}", path))
+}
+
+/// 依規則判斷指定日期各股票的訊號並寫入 signals 表，回傳寫入的筆數
+///
+/// 庫存或追踪中的股票出現前一個交易日沒有的訊號時發送通知
+pub async fn execute(date: NaiveDate) -> Result<usize> {
+    let rules = load_rules()?;
+    if rules.is_empty() {
+        return Ok(0);
+    }
+
+    let lookback = rules.iter().map(rule::Rule::lookback).max().unwrap_or(1);
+    let bars = daily_quote::fetch_signal_bars(date, lookback.max(2)).await?;
+
+    let mut yields: HashMap<i32, HashMap<StockSymbol, (Decimal, Decimal)>> = HashMap::new();
+    for rule in &rules {
+        if let rule::Condition::YieldAboveAverage { years } = rule.condition {
+            if yields.contains_key(&years) {
+                continue;
+            }
+            let averages = DailyValuation::fetch_yield_averages(date, years).await?;
+            yields.insert(
+                years,
+                averages
+                    .into_iter()
+                    .map(|(code, current, average)| (code, (current, average)))
+                    .collect(),
+            );
+        }
+    }
+
+    let signals = evaluate(&rules, &bars, &yields);
+    let mut count = 0;
+    for signal in &signals {
+        if let Err(why) = signal.upsert().await {
+            logging::error_file_async(format!("{:?}", why));
+            continue;
+        }
+        count += 1;
+    }
+
+    let previous_date = bars
+        .iter()
+        .map(|bar| bar.date)
+        .filter(|day| *day < date)
+        .max();
+    let previous = match previous_date {
+        Some(previous_date) => Signal::fetch_keys(previous_date).await?,
+        None => HashSet::new(),
+    };
+    let watched = stock_ownership_details::fetch_held_or_traced_symbols().await?;
+    let notify: HashSet<&str> = rules
+        .iter()
+        .filter(|rule| rule.notify)
+        .map(|rule| rule.name.as_str())
+        .collect();
+    let fresh: Vec<&Signal> = signals
+        .iter()
+        .filter(|signal| {
+            notify.contains(signal.rule.as_str())
+                && watched.contains(&signal.security_code)
+                && !previous.contains(&(signal.security_code.clone(), signal.rule.clone()))
+        })
+        .collect();
+
+    if !fresh.is_empty() {
+        let mut msg = format!("{} 策略訊號\n", date);
+        for signal in fresh {
+            let name = SHARE
+                .get_stock(&signal.security_code)
+                .await
+                .map(|stock| stock.name)
+                .unwrap_or_default();
+            let label = rules
+                .iter()
+                .find(|rule| rule.name == signal.rule)
+                .map_or(signal.rule.as_str(), |rule| rule.label());
+            let _ = writeln!(
+                msg,
+                "{} {} {} ({})",
+                signal.security_code,
+                fmt::escape_markdown(&name),
+                fmt::escape_markdown(label),
+                signal.value.normalize()
+            );
+        }
+        alert::send(alert::SIGNAL, &msg).await;
+    }
+
+    Ok(count)
+}
+
+/// 依股票代號分組後逐一判斷規則，bars 需依股票代號、日期由舊到新排序
+fn evaluate(
+    rules: &[rule::Rule],
+    bars: &[SignalBar],
+    yields: &HashMap<i32, HashMap<StockSymbol, (Decimal, Decimal)>>,
+) -> Vec<Signal> {
+    let mut signals = Vec::new();
+    for stock_bars in bars.chunk_by(|a, b| a.security_code == b.security_code) {
+        let Some(today) = stock_bars.last() else {
+            continue;
+        };
+
+        for rule in rules {
+            let value = rule.evaluate(stock_bars, |years| {
+                yields.get(&years)?.get(&today.security_code).copied()
+            });
+            if let Some(value) = value {
+                signals.push(Signal {
+                    date: today.date,
+                    security_code: today.security_code.clone(),
+                    rule: rule.name.clone(),
+                    value,
+                });
+            }
+        }
+    }
+
+    signals
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn bar(security_code: &str, day: u32, ma5: Decimal, ma20: Decimal) -> SignalBar {
+        SignalBar {
+            security_code: security_code.parse().unwrap(),
+            date: NaiveDate::from_ymd_opt(2024, 5, day).unwrap(),
+            closing_price: dec!(100),
+            moving_average_5: ma5,
+            moving_average_20: ma20,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let rules = rule::parse(
+            r#"
+[[rules]]
+name = "golden_cross"
+kind = "golden_cross"
+fast = 5
+slow = 20
+
+[[rules]]
+name = "high_yield"
+kind = "yield_above_average"
+years = 5
+"#,
+        )
+        .unwrap();
+        let bars = vec![
+            bar("2317", 2, dec!(99), dec!(100)),
+            bar("2317", 3, dec!(101), dec!(100)),
+            bar("2330", 2, dec!(101), dec!(100)),
+            bar("2330", 3, dec!(102), dec!(100)),
+        ];
+        let yields = HashMap::from([(
+            5,
+            HashMap::from([("2330".parse().unwrap(), (dec!(5), dec!(4)))]),
+        )]);
+
+        let signals = evaluate(&rules, &bars, &yields);
+
+        let keys: Vec<(&str, &str)> = signals
+            .iter()
+            .map(|s| (s.security_code.as_str(), s.rule.as_str()))
+            .collect();
+        assert_eq!(keys, vec![("2317", "golden_cross"), ("2330", "high_yield")]);
+        assert_eq!(
+            signals[0].date,
+            NaiveDate::from_ymd_opt(2024, 5, 3).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_default_rules() {
+        let text = std::fs::read_to_string(DEFAULT_RULES_PATH).unwrap();
+
+        assert!(!rule::parse(&text).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,318 @@
+use std::collections::HashSet;
+
+use anyhow::{anyhow, Result};
+use config::{File, FileFormat};
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use serde::Deserialize;
+
+use crate::database::table::daily_quote::extension::SignalBar;
+
+/// signals.toml 的內容
+#[derive(Debug, Default, Deserialize)]
+struct RuleSet {
+    #[serde(default)]
+    rules: Vec<Rule>,
+}
+
+/// 一條策略規則 ex.
+/// ```toml
+/// [[rules]]
+/// name = "golden_cross"
+/// description = "5 日線上穿 20 日線"
+/// kind = "golden_cross"
+/// fast = 5
+/// slow = 20
+/// ```
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct Rule {
+    /// 寫入 signals 表的規則名稱，不可重複
+    pub name: String,
+    /// 通知時顯示的說明，未設定時顯示名稱
+    #[serde(default)]
+    pub description: String,
+    #[serde(flatten)]
+    pub condition: Condition,
+    /// 庫存或追踪中的股票出現新的訊號時是否通知，未設定時為 true
+    #[serde(default = "default_notify")]
+    pub notify: bool,
+}
+
+fn default_notify() -> bool {
+    true
+}
+
+/// 規則的判斷條件，以 kind 區分
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Condition {
+    /// 快線由下往上穿越慢線，均線天數可用 5、10、20、60、120、240
+    GoldenCross { fast: u32, slow: u32 },
+    /// 快線在慢線之上
+    MaAbove { fast: u32, slow: u32 },
+    /// 近 period 日的 RSI 低於 threshold
+    RsiBelow { period: usize, threshold: f64 },
+    /// 殖利率高於近 years 年的平均殖利率
+    YieldAboveAverage { years: i32 },
+}
+
+impl Rule {
+    /// 通知時顯示的文字
+    pub fn label(&self) -> &str {
+        if self.description.is_empty() {
+            &self.name
+        } else {
+            &self.description
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            return Err(anyhow!("Signal rule name is empty"));
+        }
+
+        let valid = match &self.condition {
+            Condition::GoldenCross { fast, slow } | Condition::MaAbove { fast, slow } => {
+                fast < slow
+                    && SignalBar::MOVING_AVERAGE_DAYS.contains(fast)
+                    && SignalBar::MOVING_AVERAGE_DAYS.contains(slow)
+            }
+            Condition::RsiBelow { period, threshold } => {
+                *period >= 2 && (0.0..=100.0).contains(threshold)
+            }
+            Condition::YieldAboveAverage { years } => *years >= 1,
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Invalid signal rule {}: {:?}",
+                self.name,
+                self.condition
+            ))
+        }
+    }
+
+    /// 判斷所需的交易日數(含當日)
+    pub fn lookback(&self) -> i64 {
+        match &self.condition {
+            Condition::GoldenCross { .. } => 2,
+            Condition::RsiBelow { period, .. } => *period as i64 + 1,
+            Condition::MaAbove { .. } | Condition::YieldAboveAverage { .. } => 1,
+        }
+    }
+
+    /// 以股票由舊到新的每日數據判斷最後一日是否觸發，觸發時回傳當時的指標值
+    ///
+    /// yield_average 依年數回傳該股票當日的 (殖利率, 平均殖利率)
+    pub fn evaluate(
+        &self,
+        bars: &[SignalBar],
+        yield_average: impl Fn(i32) -> Option<(Decimal, Decimal)>,
+    ) -> Option<Decimal> {
+        match &self.condition {
+            Condition::GoldenCross { fast, slow } => {
+                let [previous, today] = bars.get(bars.len().checked_sub(2)?..)? else {
+                    return None;
+                };
+                let crossed = previous.moving_average(*fast)? <= previous.moving_average(*slow)?
+                    && today.moving_average(*fast)? > today.moving_average(*slow)?;
+                crossed.then(|| today.moving_average(*fast))?
+            }
+            Condition::MaAbove { fast, slow } => {
+                let today = bars.last()?;
+                let value = today.moving_average(*fast)?;
+                (value > today.moving_average(*slow)?).then_some(value)
+            }
+            Condition::RsiBelow { period, threshold } => {
+                let closes: Vec<Decimal> = bars.iter().map(|bar| bar.closing_price).collect();
+                let value = rsi(&closes, *period)?;
+                (value.to_f64()? < *threshold).then_some(value)
+            }
+            Condition::YieldAboveAverage { years } => {
+                let (current, average) = yield_average(*years)?;
+                (current > average).then_some(current)
+            }
+        }
+    }
+}
+
+/// 解析 TOML 格式的規則並檢查均線天數、名稱是否重複等設定
+pub fn parse(text: &str) -> Result<Vec<Rule>> {
+    let rule_set: RuleSet = config::Config::builder()
+        .add_source(File::from_str(text, FileFormat::Toml))
+        .build()
+        .and_then(|cfg| cfg.try_deserialize())?;
+
+    let mut names = HashSet::new();
+    for rule in &rule_set.rules {
+        rule.validate()?;
+        if !names.insert(rule.name.as_str()) {
+            return Err(anyhow!("Duplicate signal rule {}", rule.name));
+        }
+    }
+
+    Ok(rule_set.rules)
+}
+
+/// 以最近 period 日的平均漲幅與平均跌幅計算 RSI，收盤價不足 period + 1 筆時回傳 None
+pub fn rsi(closes: &[Decimal], period: usize) -> Option<Decimal> {
+    let window = closes.get(closes.len().checked_sub(period + 1)?..)?;
+    let (gain, loss) =
+        window
+            .windows(2)
+            .fold((Decimal::ZERO, Decimal::ZERO), |(gain, loss), pair| {
+                let change = pair[1] - pair[0];
+                if change > Decimal::ZERO {
+                    (gain + change, loss)
+                } else {
+                    (gain, loss - change)
+                }
+            });
+
+    if gain + loss == Decimal::ZERO {
+        return Some(Decimal::from(50));
+    }
+
+    Some((Decimal::from(100) * gain / (gain + loss)).round_dp(4))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use rust_decimal_macros::dec;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    fn bar(closing_price: Decimal, ma5: Decimal, ma20: Decimal) -> SignalBar {
+        SignalBar {
+            security_code: "2330".parse().unwrap(),
+            date: NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(),
+            closing_price,
+            moving_average_5: ma5,
+            moving_average_20: ma20,
+            ..Default::default()
+        }
+    }
+
+    fn rule(condition: Condition) -> Rule {
+        Rule {
+            name: "test".to_string(),
+            description: String::new(),
+            condition,
+            notify: true,
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let rules = parse(
+            r#"
+[[rules]]
+name = "golden_cross"
+description = "5 日線上穿 20 日線"
+kind = "golden_cross"
+fast = 5
+slow = 20
+
+[[rules]]
+name = "rsi_oversold"
+kind = "rsi_below"
+period = 14
+threshold = 30
+notify = false
+
+[[rules]]
+name = "high_yield"
+kind = "yield_above_average"
+years = 5
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(rules.len(), 3);
+        assert_eq!(
+            rules[0].condition,
+            Condition::GoldenCross { fast: 5, slow: 20 }
+        );
+        assert_eq!(rules[0].label(), "5 日線上穿 20 日線");
+        assert_eq!(
+            rules[1].condition,
+            Condition::RsiBelow {
+                period: 14,
+                threshold: 30.0
+            }
+        );
+        assert!(!rules[1].notify);
+        assert_eq!(rules[2].label(), "high_yield");
+        assert_eq!(rules[2].lookback(), 1);
+
+        assert!(
+            parse("[[rules]]\nname = \"x\"\nkind = \"ma_above\"\nfast = 7\nslow = 20").is_err()
+        );
+        assert!(parse("[[rules]]\nname = \"x\"\nkind = \"unknown\"").is_err());
+        assert!(parse(
+            "[[rules]]\nname = \"x\"\nkind = \"ma_above\"\nfast = 5\nslow = 20\n[[rules]]\nname = \"x\"\nkind = \"ma_above\"\nfast = 20\nslow = 60"
+        )
+        .is_err());
+        assert!(parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_moving_average_rules() {
+        let cross = rule(Condition::GoldenCross { fast: 5, slow: 20 });
+        let above = rule(Condition::MaAbove { fast: 5, slow: 20 });
+        let crossed = vec![
+            bar(dec!(100), dec!(99), dec!(100)),
+            bar(dec!(103), dec!(101), dec!(100)),
+        ];
+        let stayed = vec![
+            bar(dec!(100), dec!(101), dec!(100)),
+            bar(dec!(103), dec!(102), dec!(100)),
+        ];
+
+        assert_eq!(cross.evaluate(&crossed, |_| None), Some(dec!(101)));
+        assert_eq!(cross.evaluate(&stayed, |_| None), None);
+        assert_eq!(cross.evaluate(&crossed[1..], |_| None), None);
+        assert_eq!(above.evaluate(&stayed, |_| None), Some(dec!(102)));
+        assert_eq!(
+            above.evaluate(&[bar(dec!(100), dec!(0), dec!(100))], |_| None),
+            None
+        );
+    }
+
+    #[test]
+    fn test_rsi() {
+        let closes = [dec!(10), dec!(11), dec!(10), dec!(12)];
+
+        assert_eq!(rsi(&closes, 3), Some(dec!(75)));
+        assert_eq!(rsi(&closes, 4), None);
+        assert_eq!(rsi(&[dec!(10), dec!(10)], 1), Some(dec!(50)));
+
+        let oversold = rule(Condition::RsiBelow {
+            period: 3,
+            threshold: 30.0,
+        });
+        let falling: Vec<SignalBar> = [dec!(12), dec!(11), dec!(10), dec!(9)]
+            .into_iter()
+            .map(|price| bar(price, dec!(0), dec!(0)))
+            .collect();
+        assert_eq!(oversold.evaluate(&falling, |_| None), Some(dec!(0)));
+    }
+
+    #[test]
+    fn test_yield_above_average() {
+        let high_yield = rule(Condition::YieldAboveAverage { years: 5 });
+
+        assert_eq!(
+            high_yield.evaluate(&[], |years| (years == 5).then_some((dec!(6), dec!(4.5)))),
+            Some(dec!(6))
+        );
+        assert_eq!(
+            high_yield.evaluate(&[], |_| Some((dec!(4), dec!(4.5)))),
+            None
+        );
+        assert_eq!(high_yield.evaluate(&[], |_| None), None);
+    }
+}
@@ -0,0 +1,163 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use tokio::{io::AsyncWriteExt, sync::mpsc};
+
+use crate::storage::Storage;
+
+const DEFAULT_ROOT: &str = "storage";
+
+/// 將物件存放在本機目錄下，key 即為相對路徑
+pub struct Local {
+    root: PathBuf,
+}
+
+impl Local {
+    pub fn new(root: &str) -> Self {
+        let root = if root.is_empty() { DEFAULT_ROOT } else { root };
+
+        Local {
+            root: PathBuf::from(root),
+        }
+    }
+
+    /// 將 key 轉為實際路徑，不允許跳脫根目錄
+    fn path_of(&self, key: &str) -> Result<PathBuf> {
+        let key = key.trim_start_matches('/');
+        if key.is_empty() || key.split('/').any(|segment| segment == "..") {
+            return Err(anyhow!("Invalid storage key: {}", key));
+        }
+
+        Ok(self.root.join(key))
+    }
+}
+
+#[async_trait]
+impl Storage for Local {
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<()> {
+        let path = self.path_of(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        tokio::fs::write(&path, data)
+            .await
+            .context(format!("Failed to write {}", path.display()))
+    }
+
+    async fn put_stream(&self, key: &str, mut chunks: mpsc::Receiver<Vec<u8>>) -> Result<()> {
+        let path = self.path_of(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let mut file = tokio::fs::File::create(&path)
+            .await
+            .context(format!("Failed to create {}", path.display()))?;
+        while let Some(chunk) = chunks.recv().await {
+            file.write_all(&chunk)
+                .await
+                .context(format!("Failed to write {}", path.display()))?;
+        }
+
+        file.flush()
+            .await
+            .context(format!("Failed to write {}", path.display()))
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        let path = self.path_of(key)?;
+        tokio::fs::read(&path)
+            .await
+            .context(format!("Failed to read {}", path.display()))
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        let path = self.path_of(key)?;
+        tokio::fs::remove_file(&path)
+            .await
+            .context(format!("Failed to remove {}", path.display()))
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut keys = Vec::new();
+        if !self.root.exists() {
+            return Ok(keys);
+        }
+
+        let mut dirs = vec![self.root.clone()];
+        while let Some(dir) = dirs.pop() {
+            let mut entries = tokio::fs::read_dir(&dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                if path.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+
+                if let Some(key) = to_key(&self.root, &path) {
+                    if key.starts_with(prefix) {
+                        keys.push(key);
+                    }
+                }
+            }
+        }
+
+        keys.sort();
+
+        Ok(keys)
+    }
+}
+
+fn to_key(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let segments: Vec<&str> = relative
+        .components()
+        .filter_map(|c| c.as_os_str().to_str())
+        .collect();
+
+    Some(segments.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[tokio::test]
+    async fn test_put_list_get_delete() {
+        let root = std::env::temp_dir().join(format!("storage_test_{}", std::process::id()));
+        let storage = Local::new(root.to_str().unwrap());
+
+        storage.put("exports/a.csv", b"a".to_vec()).await.unwrap();
+        storage.put("exports/b/c.csv", b"c".to_vec()).await.unwrap();
+        storage.put("logs/d.log", b"d".to_vec()).await.unwrap();
+
+        let keys = storage.list("exports/").await.unwrap();
+        assert_eq!(keys, vec!["exports/a.csv", "exports/b/c.csv"]);
+        assert_eq!(storage.get("exports/b/c.csv").await.unwrap(), b"c".to_vec());
+        assert!(storage.put("../escape.txt", vec![]).await.is_err());
+
+        let (tx, rx) = mpsc::channel(2);
+        let writer = storage.put_stream("backups/e.sql.gz", rx);
+        let sender = async move {
+            tx.send(b"e1".to_vec()).await.unwrap();
+            tx.send(b"e2".to_vec()).await.unwrap();
+        };
+        let (written, _) = tokio::join!(writer, sender);
+        written.unwrap();
+        assert_eq!(
+            storage.get("backups/e.sql.gz").await.unwrap(),
+            b"e1e2".to_vec()
+        );
+
+        storage.delete("exports/a.csv").await.unwrap();
+        assert_eq!(
+            storage.list("exports/").await.unwrap(),
+            vec!["exports/b/c.csv"]
+        );
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
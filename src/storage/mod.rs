@@ -0,0 +1,165 @@
+use std::{path::Path, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{Local, NaiveDate};
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc;
+
+use crate::{config::SETTINGS, logging};
+
+/// 本機檔案系統
+pub mod local;
+/// S3 相容的物件儲存
+pub mod s3;
+
+static STORAGE: Lazy<Arc<dyn Storage>> = Lazy::new(|| {
+    let storage = &SETTINGS.load().storage;
+    match storage.backend.as_str() {
+        "s3" => Arc::new(s3::S3::new(&storage.s3)),
+        _ => Arc::new(local::Local::new(&storage.local.root)),
+    }
+});
+
+/// 檔案儲存的後端，key 一律以 `/` 分隔路徑，ex. `logs/2024-01-01_default_info.log`
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// 寫入一個物件，已存在時覆蓋
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<()>;
+    /// 依序寫入 chunks 收到的內容直到通道關閉，用於備份檔等不適合整個放進記憶體的物件
+    async fn put_stream(&self, key: &str, chunks: mpsc::Receiver<Vec<u8>>) -> Result<()>;
+    /// 讀取一個物件
+    async fn get(&self, key: &str) -> Result<Vec<u8>>;
+    /// 刪除一個物件
+    async fn delete(&self, key: &str) -> Result<()>;
+    /// 列出指定前綴下所有物件的 key
+    async fn list(&self, prefix: &str) -> Result<Vec<String>>;
+}
+
+/// 取得設定檔指定的儲存後端
+pub fn get_storage() -> Arc<dyn Storage> {
+    STORAGE.clone()
+}
+
+/// 將爬蟲取得的原始回應封存，供日後解析器回歸測試或追查數據來源
+pub async fn archive_raw_response(url: &str, body: &str) -> Result<()> {
+    let now = Local::now();
+    let host = reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_string()))
+        .unwrap_or_else(|| "unknown".to_string());
+    let digest = hex::encode(Sha256::digest(url.as_bytes()));
+    let key = format!(
+        "raw/{}/{}/{}_{}.txt",
+        now.format("%Y-%m-%d"),
+        host,
+        now.format("%H%M%S%.3f"),
+        &digest[..16]
+    );
+
+    get_storage().put(&key, body.as_bytes().to_vec()).await
+}
+
+/// 在不阻塞呼叫端的情況下封存原始回應(需於設定檔啟用 archive_raw_response)
+pub fn archive_raw_response_async(url: &str, body: &str) {
+    if !SETTINGS.load().storage.archive_raw_response {
+        return;
+    }
+
+    let url = url.to_string();
+    let body = body.to_string();
+    tokio::spawn(async move {
+        if let Err(why) = archive_raw_response(&url, &body).await {
+            logging::error_file_async(format!(
+                "Failed to archive_raw_response({}) because {:?}",
+                url, why
+            ));
+        }
+    });
+}
+
+/// 將本機 log 目錄內今日以前的日誌上傳至儲存後端後刪除，避免 VPS 的硬碟被塞滿
+pub async fn ship_logs() -> Result<()> {
+    if !SETTINGS.load().storage.ship_logs {
+        return Ok(());
+    }
+
+    let today = Local::now().date_naive();
+    let storage = get_storage();
+    let mut entries = tokio::fs::read_dir("log").await?;
+
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if !path.is_file() || !is_before(&path, today) {
+            continue;
+        }
+
+        let file_name = match path.file_name().and_then(|f| f.to_str()) {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+
+        let data = tokio::fs::read(&path).await?;
+        let key = format!("logs/{}", file_name);
+        if let Err(why) = storage.put(&key, data).await {
+            logging::error_file_async(format!("Failed to ship log({}) because {:?}", key, why));
+            continue;
+        }
+
+        tokio::fs::remove_file(&path).await?;
+        logging::info_file_async(format!("the log has been shipped:{}", key));
+    }
+
+    Ok(())
+}
+
+/// 日誌的檔名格式為 %Y-%m-%d_name.log，由檔名判斷是否為指定日期以前的檔案
+fn is_before(path: &Path, date: NaiveDate) -> bool {
+    path.file_name()
+        .and_then(|f| f.to_str())
+        .and_then(|name| name.get(..10))
+        .and_then(|prefix| NaiveDate::parse_from_str(prefix, "%Y-%m-%d").ok())
+        .is_some_and(|d| d < date)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_is_before() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+
+        assert!(is_before(
+            &PathBuf::from("log/2024-01-01_default_info.log"),
+            date
+        ));
+        assert!(!is_before(
+            &PathBuf::from("log/2024-01-02_default_info.log"),
+            date
+        ));
+        assert!(!is_before(&PathBuf::from("log/default_info.log"), date));
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_ship_logs() {
+        dotenv::dotenv().ok();
+        logging::debug_file_async("開始 ship_logs".to_string());
+
+        match ship_logs().await {
+            Ok(_) => {
+                logging::debug_file_async("ship_logs executed successfully.".to_string());
+            }
+            Err(why) => {
+                logging::debug_file_async(format!("Failed to ship_logs because {:?}", why));
+            }
+        }
+
+        logging::debug_file_async("結束 ship_logs".to_string());
+    }
+}
@@ -0,0 +1,264 @@
+use std::{
+    future::Future,
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    config::SETTINGS,
+    logging::{self, run_id},
+    scheduler,
+    util::http,
+};
+
+/// 匯出 span 的間隔
+const EXPORT_INTERVAL: Duration = Duration::from_secs(10);
+/// 等待匯出的 span 上限，收集器無法連線時丟棄較舊的 span 避免佔用記憶體
+const MAX_PENDING_SPANS: usize = 10_000;
+/// OTLP 的 SPAN_KIND_INTERNAL
+const SPAN_KIND_INTERNAL: i32 = 1;
+
+/// 已結束等待匯出的 span
+static PENDING: Mutex<Vec<Span>> = Mutex::new(Vec::new());
+
+tokio::task_local! {
+    /// 目前所在的 trace 與 span
+    static CONTEXT: SpanContext;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct SpanContext {
+    trace_id: String,
+    span_id: String,
+}
+
+/// OTLP/HTTP JSON 格式的 span
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct Span {
+    trace_id: String,
+    span_id: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    parent_span_id: String,
+    name: String,
+    kind: i32,
+    start_time_unix_nano: String,
+    end_time_unix_nano: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportRequest {
+    resource_spans: Vec<ResourceSpans>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResourceSpans {
+    resource: Resource,
+    scope_spans: Vec<ScopeSpans>,
+}
+
+#[derive(Serialize)]
+struct Resource {
+    attributes: Vec<KeyValue>,
+}
+
+#[derive(Serialize)]
+struct ScopeSpans {
+    scope: Scope,
+    spans: Vec<Span>,
+}
+
+#[derive(Serialize)]
+struct Scope {
+    name: String,
+}
+
+#[derive(Serialize)]
+struct KeyValue {
+    key: String,
+    value: AnyValue,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AnyValue {
+    string_value: String,
+}
+
+/// 是否啟用 OpenTelemetry 追蹤
+pub fn is_enabled() -> bool {
+    SETTINGS.load().telemetry.enabled && !SETTINGS.load().telemetry.endpoint.is_empty()
+}
+
+/// 以新的 trace 執行任務，排程任務內的 trace id 與執行識別碼(run_id)相同
+pub async fn trace<F>(name: impl Into<String>, future: F) -> F::Output
+where
+    F: Future,
+{
+    if !is_enabled() {
+        return future.await;
+    }
+
+    let trace_id = run_id::current()
+        .and_then(|id| Uuid::parse_str(&id).ok())
+        .unwrap_or_else(Uuid::new_v4)
+        .simple()
+        .to_string();
+
+    record(name.into(), trace_id, String::new(), future).await
+}
+
+/// 在目前的 trace 底下以子 span 執行任務並記錄耗時，不在 trace 內時直接執行
+/// ex. `telemetry::span("closing::quote", step.run(date)).await`
+pub async fn span<F>(name: impl Into<String>, future: F) -> F::Output
+where
+    F: Future,
+{
+    let parent = match CONTEXT.try_with(Clone::clone) {
+        Ok(parent) if is_enabled() => parent,
+        _ => return future.await,
+    };
+
+    record(name.into(), parent.trace_id, parent.span_id, future).await
+}
+
+async fn record<F>(name: String, trace_id: String, parent_span_id: String, future: F) -> F::Output
+where
+    F: Future,
+{
+    let context = SpanContext {
+        trace_id,
+        span_id: format!("{:016x}", rand::random::<u64>()),
+    };
+    let start = unix_nano();
+    let output = CONTEXT.scope(context.clone(), future).await;
+
+    push(Span {
+        trace_id: context.trace_id,
+        span_id: context.span_id,
+        parent_span_id,
+        name,
+        kind: SPAN_KIND_INTERNAL,
+        start_time_unix_nano: start.to_string(),
+        end_time_unix_nano: unix_nano().to_string(),
+    });
+
+    output
+}
+
+fn push(span: Span) {
+    if let Ok(mut pending) = PENDING.lock() {
+        if pending.len() >= MAX_PENDING_SPANS {
+            pending.remove(0);
+        }
+        pending.push(span);
+    }
+}
+
+fn unix_nano() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default()
+}
+
+/// 將等待中的 span 以 OTLP/HTTP JSON 送到設定檔 telemetry.endpoint
+pub async fn export() -> Result<()> {
+    let spans = match PENDING.lock() {
+        Ok(mut pending) => std::mem::take(&mut *pending),
+        Err(_) => return Ok(()),
+    };
+
+    if spans.is_empty() || !is_enabled() {
+        return Ok(());
+    }
+
+    let req = request(&SETTINGS.load().telemetry.service_name, spans);
+    http::post_use_json::<ExportRequest, serde_json::Value>(
+        &SETTINGS.load().telemetry.endpoint,
+        None,
+        Some(&req),
+    )
+    .await?;
+
+    Ok(())
+}
+
+fn request(service_name: &str, spans: Vec<Span>) -> ExportRequest {
+    let service_name = if service_name.is_empty() {
+        "stock_crawler"
+    } else {
+        service_name
+    };
+
+    ExportRequest {
+        resource_spans: vec![ResourceSpans {
+            resource: Resource {
+                attributes: vec![KeyValue {
+                    key: "service.name".to_string(),
+                    value: AnyValue {
+                        string_value: service_name.to_string(),
+                    },
+                }],
+            },
+            scope_spans: vec![ScopeSpans {
+                scope: Scope {
+                    name: module_path!().to_string(),
+                },
+                spans,
+            }],
+        }],
+    }
+}
+
+/// 定時匯出 span，啟動時以 tokio::spawn 執行
+pub async fn run() {
+    let mut interval = tokio::time::interval(EXPORT_INTERVAL);
+    loop {
+        interval.tick().await;
+        // 只由 leader 匯出，follower 累積的 span 受 MAX_PENDING_SPANS 限制
+        if !scheduler::leader::is_leader() {
+            continue;
+        }
+
+        if let Err(why) = export().await {
+            logging::error_file_async(format!("Failed to telemetry::export() because {:?}", why));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_request() {
+        let span = Span {
+            trace_id: "5b8efff798038103d269b633813fc60c".to_string(),
+            span_id: "eee19b7ec3c1b174".to_string(),
+            parent_span_id: String::new(),
+            name: "closing::quote".to_string(),
+            kind: SPAN_KIND_INTERNAL,
+            start_time_unix_nano: "1718150400000000000".to_string(),
+            end_time_unix_nano: "1718150401000000000".to_string(),
+        };
+        let json = serde_json::to_value(request("", vec![span])).unwrap();
+
+        assert_eq!(
+            json["resourceSpans"][0]["resource"]["attributes"][0]["value"]["stringValue"],
+            "stock_crawler"
+        );
+        let span = &json["resourceSpans"][0]["scopeSpans"][0]["spans"][0];
+        assert_eq!(span["traceId"], "5b8efff798038103d269b633813fc60c");
+        assert_eq!(span["name"], "closing::quote");
+        assert_eq!(span["startTimeUnixNano"], "1718150400000000000");
+        assert!(span.get("parentSpanId").is_none());
+    }
+}
@@ -97,7 +97,20 @@ pub fn gregorian_year_to_roc_year(gregorian_year: i32) -> i32 {
 
 /// Parse a date string in the format of ROC calendar
 /// and return it as a NaiveDate in the Gregorian calendar.
+///
+/// Both separated (113/01/02, 113-01-02) and compact (1130102) formats are accepted.
 pub fn parse_taiwan_date(date_str: &str) -> Option<NaiveDate> {
+    let date_str = date_str.trim();
+    if date_str.len() >= 6 && date_str.chars().all(|c| c.is_ascii_digit()) {
+        let (year, month_day) = date_str.split_at(date_str.len() - 4);
+        let (month, day) = month_day.split_at(2);
+        return NaiveDate::from_ymd_opt(
+            roc_year_to_gregorian_year(parse_date_part::<i32>(year)?),
+            parse_date_part::<u32>(month)?,
+            parse_date_part::<u32>(day)?,
+        );
+    }
+
     let split_date: Vec<&str> = date_str.split(['/', '-']).collect();
     if split_date.len() != 3 {
         return None;
@@ -0,0 +1,173 @@
+use std::{collections::HashMap, fmt::Write, sync::Mutex};
+
+use anyhow::Result;
+use chrono::{Days, Local, NaiveDate};
+
+use crate::{error, logging};
+
+/// 當天各來源的請求數，跨日後重新計算
+static BUDGET: Mutex<Option<Budget>> = Mutex::new(None);
+
+/// 來源當天的請求數與超過上限後被延到隔天的請求數
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Usage {
+    pub requests: u64,
+    pub deferred: u64,
+}
+
+/// 某一天各來源的用量，key 為 crawler::SOURCES 的來源名稱
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Budget {
+    date: NaiveDate,
+    sources: HashMap<String, Usage>,
+}
+
+impl Budget {
+    fn new(date: NaiveDate) -> Self {
+        Budget {
+            date,
+            sources: HashMap::new(),
+        }
+    }
+
+    /// 未達上限時計入一次請求並回傳 true，已達上限時記錄延後並回傳 false，上限為 0 時不限制
+    fn consume(&mut self, source: &str, daily_cap: u64) -> bool {
+        let usage = self.sources.entry(source.to_string()).or_default();
+        if daily_cap > 0 && usage.requests >= daily_cap {
+            usage.deferred += 1;
+            return false;
+        }
+
+        usage.requests += 1;
+        true
+    }
+}
+
+/// 送出請求前計入來源當天的請求數，超過設定檔 crawler.<來源>.daily_cap 時回傳 Error::BudgetExhausted，
+/// 排程會將任務延到隔天再執行
+pub fn consume(source: &str, daily_cap: u64) -> Result<()> {
+    let today = Local::now().date_naive();
+    let Ok(mut budget) = BUDGET.lock() else {
+        return Ok(());
+    };
+
+    let current = budget.get_or_insert_with(|| Budget::new(today));
+    if current.date != today {
+        // 跨日時記錄前一天的用量
+        let previous = std::mem::replace(current, Budget::new(today));
+        logging::info_file_async(summary(
+            &format!("{} 爬蟲請求量", previous.date),
+            previous.date,
+            &sorted(previous.sources),
+        ));
+    }
+
+    if current.consume(source, daily_cap) {
+        return Ok(());
+    }
+
+    Err(error::Error::BudgetExhausted {
+        crawler: source.to_string(),
+        resume_on: next_day(today),
+    }
+    .into())
+}
+
+/// 取得當天各來源的用量，依來源名稱排序
+pub fn usage() -> Vec<(String, Usage)> {
+    BUDGET
+        .lock()
+        .ok()
+        .and_then(|budget| budget.clone())
+        .map(|budget| sorted(budget.sources))
+        .unwrap_or_default()
+}
+
+/// 將當天各來源的請求數與延到隔天的請求數寫入日誌
+pub fn log_summary(title: &str) {
+    let usage = usage();
+    if usage.is_empty() {
+        return;
+    }
+
+    logging::info_file_async(summary(title, Local::now().date_naive(), &usage));
+}
+
+fn sorted(sources: HashMap<String, Usage>) -> Vec<(String, Usage)> {
+    let mut list: Vec<(String, Usage)> = sources.into_iter().collect();
+    list.sort_by(|a, b| a.0.cmp(&b.0));
+
+    list
+}
+
+fn next_day(date: NaiveDate) -> NaiveDate {
+    date.checked_add_days(Days::new(1)).unwrap_or(date)
+}
+
+fn summary(title: &str, date: NaiveDate, usage: &[(String, Usage)]) -> String {
+    let mut msg = format!("{} 爬蟲請求量:", title);
+    for (source, usage) in usage {
+        let _ = write!(&mut msg, "\n    {} requests:{}", source, usage.requests);
+        if usage.deferred > 0 {
+            let _ = write!(
+                &mut msg,
+                " deferred:{} carry over to {}",
+                usage.deferred,
+                next_day(date)
+            );
+        }
+    }
+
+    msg
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_consume() {
+        let mut budget = Budget::new(NaiveDate::from_ymd_opt(2024, 6, 12).unwrap());
+
+        assert!(budget.consume("goodinfo", 2));
+        assert!(budget.consume("goodinfo", 2));
+        assert!(!budget.consume("goodinfo", 2));
+        assert!(budget.consume("twse", 0));
+
+        assert_eq!(
+            budget.sources["goodinfo"],
+            Usage {
+                requests: 2,
+                deferred: 1
+            }
+        );
+        assert_eq!(budget.sources["twse"].requests, 1);
+    }
+
+    #[test]
+    fn test_summary() {
+        let date = NaiveDate::from_ymd_opt(2024, 6, 30).unwrap();
+        let usage = vec![
+            (
+                "goodinfo".to_string(),
+                Usage {
+                    requests: 500,
+                    deferred: 37,
+                },
+            ),
+            (
+                "twse".to_string(),
+                Usage {
+                    requests: 12,
+                    deferred: 0,
+                },
+            ),
+        ];
+
+        assert_eq!(
+            summary("台股收盤", date, &usage),
+            "台股收盤 爬蟲請求量:\n    goodinfo requests:500 deferred:37 carry over to 2024-07-01\n    twse requests:12"
+        );
+    }
+}
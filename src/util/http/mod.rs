@@ -6,23 +6,23 @@ use std::{
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use once_cell::sync::{Lazy, OnceCell};
-use reqwest::{header, header::SET_COOKIE, Client, Method, RequestBuilder, Response};
+use reqwest::{header, header::SET_COOKIE, Client, Method, RequestBuilder, Response, StatusCode};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use tokio::sync::Semaphore;
 
-use crate::{logging::Logger, util};
+use crate::{
+    crawler, error,
+    limits::{self, Stage},
+    logging::Logger,
+    storage, telemetry, util,
+};
 
+/// 各爬蟲來源每天的請求量與上限
+pub mod crawl_budget;
 pub mod element;
+/// 各爬蟲來源的請求逾時與重試策略
+pub mod policy;
 pub mod user_agent;
 
-/// A semaphore for limiting concurrent requests.
-///
-/// The initial number of permits is set to eight times the number of available CPU cores.
-static SEMAPHORE: Lazy<Semaphore> = Lazy::new(|| {
-    let cpus = num_cpus::get();
-    Semaphore::new(cpus * 8)
-});
-
 /// A singleton instance of the reqwest client.
 static CLIENT: OnceCell<Client> = OnceCell::new();
 
@@ -100,7 +100,7 @@ pub async fn get_json<RES: DeserializeOwned>(url: &str) -> Result<RES> {
         .await?
         .json::<RES>()
         .await
-        .map_err(|e| anyhow!("Error parsing response JSON: {:?}", e))
+        .map_err(|e| error::Error::parse(url, e).into())
 }
 
 pub async fn get_response(url: &str, headers: Option<header::HeaderMap>) -> Result<Response> {
@@ -117,11 +117,15 @@ pub async fn get_response(url: &str, headers: Option<header::HeaderMap>) -> Resu
 ///
 /// * `Result<String>`: The response text, or an error if the request fails or the response cannot be parsed.
 pub async fn get(url: &str, headers: Option<header::HeaderMap>) -> Result<String> {
-    get_response(url, headers)
+    let text = get_response(url, headers)
         .await?
         .text()
         .await
-        .map_err(|e| anyhow!("Error parsing response text: {:?}", e))
+        .map_err(|e| anyhow!("Error parsing response text: {:?}", e))?;
+
+    storage::archive_raw_response_async(url, &text);
+
+    Ok(text)
 }
 
 pub fn extract_cookies(response: &Response) -> Option<String> {
@@ -145,11 +149,15 @@ pub fn extract_cookies(response: &Response) -> Option<String> {
 ///
 /// * `Result<String>`: The Big5 encoded response text, or an error if the request fails or the response cannot be parsed.
 pub async fn get_use_big5(url: &str) -> Result<String> {
-    send(Method::GET, url, None, None::<fn(_) -> _>)
+    let text = send(Method::GET, url, None, None::<fn(_) -> _>)
         .await?
         .text_force_big5()
         .await
-        .map_err(|e| anyhow!("Error parsing response text use BIG5: {:?}", e))
+        .map_err(|e| anyhow!("Error parsing response text use BIG5: {:?}", e))?;
+
+    storage::archive_raw_response_async(url, &text);
+
+    Ok(text)
 }
 
 /// Performs an HTTP POST request with JSON request and response, and specified headers.
@@ -201,7 +209,8 @@ where
     // Print the response body
     //println!("Response body: {}", res_body);
 
-    serde_json::from_str(&res_body).map_err(|e| anyhow!("Error parsing response JSON({}): {:?}", &res_body, e))
+    serde_json::from_str(&res_body)
+        .map_err(|e| error::Error::parse(url, format!("{:?} body: {}", e, &res_body)).into())
 }
 
 /// Performs an HTTP POST request with form data and specified headers, and returns the response as text.
@@ -239,7 +248,40 @@ pub async fn post(
     .map_err(|why| anyhow!("Error parsing response text: {:?}", why))
 }
 
-const MAX_RETRIES: usize = 2;
+/// Performs an HTTP request with a raw byte body and a per-request timeout.
+///
+/// # Arguments
+///
+/// * `method`: The HTTP method to use for the request.
+/// * `url`: The URL to send the request to.
+/// * `headers`: An optional set of headers to include with the request.
+/// * `body`: An optional raw body.
+/// * `timeout`: Overrides the client's default timeout, ex. for uploading large files.
+///
+/// # Returns
+///
+/// * `Result<Response>`: The HTTP response, or an error if all attempts to send the request fail.
+pub async fn request_bytes(
+    method: Method,
+    url: &str,
+    headers: Option<header::HeaderMap>,
+    body: Option<Vec<u8>>,
+    timeout: Duration,
+) -> Result<Response> {
+    send(
+        method,
+        url,
+        headers,
+        Some(move |rb: RequestBuilder| {
+            let rb = rb.timeout(timeout);
+            match body {
+                Some(b) => rb.body(b),
+                None => rb,
+            }
+        }),
+    )
+    .await
+}
 
 /// Sends an HTTP request using the specified method, URL, headers, and body with retries on failure.
 ///
@@ -250,15 +292,17 @@ const MAX_RETRIES: usize = 2;
 /// * `headers`: An optional set of headers to include with the request.
 /// * `body`: An optional function that takes a `reqwest::RequestBuilder` and returns a new `RequestBuilder` with the request body added (JSON, form data, etc.).
 ///
-/// This function will attempt to send the request up to MAX_RETRIES times. If a request attempt fails, it logs the error and retries the request after a delay. The delay increases with each attempt.
+/// The timeout, retry count and backoff come from the `RequestPolicy` of the crawler source the URL belongs to
+/// (config `crawler.<source>`). If a request attempt fails, it logs the error and retries the request after a delay.
+/// The delay doubles with each attempt.
 ///
 /// # Returns
 ///
-/// * `Result<Response>`: The HTTP response, or an error if all attempts to send the request fail. If all attempts fail, it returns an error indicating that the request failed after MAX_RETRIES attempts.
+/// * `Result<Response>`: The HTTP response, or an error if all attempts to send the request fail.
 ///
 /// # Errors
 ///
-/// This function will return an `Err` if the request fails to send after MAX_RETRIES attempts.
+/// This function will return an `Err` if the request fails to send after all attempts of the policy.
 ///
 /// # Example
 ///
@@ -277,8 +321,10 @@ async fn send(
     body: Option<impl FnOnce(RequestBuilder) -> RequestBuilder>,
 ) -> Result<Response> {
     let visit_log = format!("{method}:{url}");
+    let policy = policy::RequestPolicy::for_url(url);
+    let source = crawler::source_of(url);
     let client = get_client()?;
-    let mut rb = client.request(method, url);
+    let mut rb = client.request(method, url).timeout(policy.timeout());
 
     if let Some(h) = headers {
         rb = rb.headers(h);
@@ -288,29 +334,45 @@ async fn send(
         rb = body_fn(rb);
     }
 
-    for attempt in 1..=MAX_RETRIES {
+    let mut last_error = None;
+
+    for attempt in 1..=policy.attempts() {
         let msg = format!("Attempt {} to send {}", attempt, visit_log);
         let rb_clone = rb
             .try_clone()
             .ok_or_else(|| anyhow!("Failed to clone RequestBuilder"))?;
-        let permit = SEMAPHORE.acquire().await;
-        let start = Instant::now();
-        let res = rb_clone.send().await;
-        let elapsed = start.elapsed().as_millis();
-
-        drop(permit);
+        // 來源當天的請求數已達 crawler.<來源>.daily_cap 時不再送出
+        if let Some(source) = source {
+            crawl_budget::consume(source, policy.daily_cap)?;
+        }
+        // 同時送出的請求數受設定檔 limits.max_crawls 限制
+        let (res, elapsed) = limits::run(Stage::Crawl, async {
+            let start = Instant::now();
+            let res = telemetry::span(visit_log.as_str(), rb_clone.send()).await;
+            (res, start.elapsed().as_millis())
+        })
+        .await;
 
         match res {
             Ok(response) => {
                 LOGGER.info(format!("{} {} ms", msg, elapsed));
+                // 5xx 多半是站點暫時的問題，還有剩餘次數時稍後重送
+                if response.status().is_server_error() && attempt < policy.attempts() {
+                    LOGGER.error(format!("{} responded {}", msg, response.status()));
+                    tokio::time::sleep(policy.backoff(attempt)).await;
+
+                    continue;
+                }
+
                 //let text = response.text().await?; // Here we take ownership of response
                 //LOGGER.info(format!("Response text: {}", text));
-                return Ok(response);
+                return check_status(url, response);
             }
             Err(why) => {
                 LOGGER.error(format!("{} failed because {:?}. {} ms", msg, why, elapsed));
-                if attempt < MAX_RETRIES {
-                    tokio::time::sleep(Duration::from_secs(2u64.pow(attempt as u32))).await;
+                last_error = Some(why);
+                if attempt < policy.attempts() {
+                    tokio::time::sleep(policy.backoff(attempt)).await;
 
                     continue;
                 }
@@ -318,11 +380,49 @@ async fn send(
         }
     }
 
-    Err(anyhow!(
-        "Failed to send request to {} after {} attempts",
-        url,
-        MAX_RETRIES
-    ))
+    match last_error {
+        Some(source) => Err(error::Error::Http {
+            url: url.to_string(),
+            source,
+        }
+        .into()),
+        None => Err(anyhow!(
+            "Failed to send request to {} after {} attempts",
+            url,
+            policy.attempts()
+        )),
+    }
+}
+
+/// Converts responses that mean "slow down", "go away" or "try again later" (5xx) into typed
+/// errors so callers can tell them apart from ordinary failures.
+fn check_status(url: &str, response: Response) -> Result<Response> {
+    match response.status() {
+        StatusCode::TOO_MANY_REQUESTS => {
+            let retry_after = response
+                .headers()
+                .get(header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<u64>().ok())
+                .map(Duration::from_secs);
+
+            Err(error::Error::RateLimited {
+                url: url.to_string(),
+                retry_after,
+            }
+            .into())
+        }
+        StatusCode::FORBIDDEN => Err(error::Error::Blocked {
+            url: url.to_string(),
+        }
+        .into()),
+        status if status.is_server_error() => Err(error::Error::Status {
+            url: url.to_string(),
+            status: status.as_u16(),
+        }
+        .into()),
+        _ => Ok(response),
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{config::SETTINGS, crawler};
+
+/// 請求的逾時與重試策略，設定檔 crawler.<來源> 未設定的欄位使用預設值
+/// ex. `"crawler": { "goodinfo": { "timeout_secs": 30, "retries": 2 } }`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct RequestPolicy {
+    /// 單次請求的逾時秒數
+    pub timeout_secs: u64,
+    /// 失敗後重試的次數
+    pub retries: usize,
+    /// 第一次重試前等待的毫秒數，之後每次加倍
+    pub backoff_ms: u64,
+    /// 每天最多送出的請求數(含重試)，達上限後當天剩餘的工作延到隔天，0 為不限制
+    pub daily_cap: u64,
+}
+
+impl Default for RequestPolicy {
+    fn default() -> Self {
+        RequestPolicy {
+            timeout_secs: 3,
+            retries: 1,
+            backoff_ms: 2000,
+            daily_cap: 0,
+        }
+    }
+}
+
+impl RequestPolicy {
+    /// 取得設定檔內指定來源的策略 ex. `RequestPolicy::for_source("goodinfo")`
+    pub fn for_source(source: &str) -> Self {
+        SETTINGS
+            .load()
+            .crawler
+            .get(source)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// 依網址的網域取得所屬爬蟲來源的策略，不屬於任何來源時使用預設值
+    pub fn for_url(url: &str) -> Self {
+        crawler::source_of(url)
+            .map(Self::for_source)
+            .unwrap_or_default()
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+
+    /// 包含第一次在內最多送出幾次請求
+    pub fn attempts(&self) -> usize {
+        self.retries + 1
+    }
+
+    /// 第 attempt 次失敗後重試前等待的時間
+    pub fn backoff(&self, attempt: usize) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16) as u32;
+        Duration::from_millis(self.backoff_ms.saturating_mul(2u64.pow(exponent)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let policy = RequestPolicy::default();
+
+        assert_eq!(policy.attempts(), 2);
+        assert_eq!(policy.timeout(), Duration::from_secs(3));
+        assert_eq!(policy.backoff(1), Duration::from_secs(2));
+        assert_eq!(policy.backoff(2), Duration::from_secs(4));
+        assert_eq!(policy.backoff(3), Duration::from_secs(8));
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let policy: RequestPolicy =
+            serde_json::from_str(r#"{ "timeout_secs": 30, "retries": 2 }"#).unwrap();
+
+        assert_eq!(
+            policy,
+            RequestPolicy {
+                timeout_secs: 30,
+                retries: 2,
+                backoff_ms: 2000,
+                daily_cap: 0,
+            }
+        );
+    }
+}
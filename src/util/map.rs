@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// 股息記錄的鍵名
 pub trait Keyable {
@@ -16,3 +16,29 @@ pub fn vec_to_hashmap<T: Keyable>(entities: Vec<T>) -> HashMap<String, T> {
     }
     map
 }
+
+/// 依 key 去除重複的數據，相同 key 以最後一筆為準，結果依 key 排序
+/// 批次 INSERT ... ON CONFLICT 同一個 key 出現兩次時會失敗，寫入前需先去除重複
+pub fn dedup_by_key<T, K: Ord>(entities: &[T], key: impl Fn(&T) -> K) -> Vec<&T> {
+    let map: BTreeMap<K, &T> = entities.iter().map(|e| (key(e), e)).collect();
+    map.into_values().collect()
+}
+
+/// 取出每一筆數據的同一個欄位，批次寫入時綁定為 UNNEST 的陣列參數
+pub fn column<T, U>(entities: &[&T], field: impl Fn(&T) -> U) -> Vec<U> {
+    entities.iter().map(|e| field(e)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    // 注意這個慣用法：在 tests 模組中，從外部範疇匯入所有名字。
+    use super::*;
+
+    #[test]
+    fn test_dedup_by_key() {
+        let entities = [("2330", 1), ("2317", 2), ("2330", 3)];
+        let deduped = dedup_by_key(&entities, |e| e.0);
+
+        assert_eq!(deduped, vec![&("2317", 2), &("2330", 3)]);
+    }
+}